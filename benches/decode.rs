@@ -0,0 +1,174 @@
+// Decoding and dumping benchmarks, on inputs built here so that they need no
+// fixture files: a certificate with a large SAN list, a CT log entry's
+// precertificate and issuing chain, and a CBOR map of about a megabyte.
+// Parsing is measured apart from dumping, and the CBOR dump with and without
+// --stream, to show where the time goes.
+//
+//   cargo bench --bench decode [-- <filter>]
+
+use std::borrow::Cow;
+
+use asn1_cbor_tools::cbor::{self, CborItem, CborValue};
+use asn1_cbor_tools::der::DerBuilder;
+use asn1_cbor_tools::{dump_asn1_to_string, dump_cbor_to_string, try_parse_asn1};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+// RFC 6962 extensions: the SCT list, and the poison that marks a precertificate
+const SCT_LIST_OID: &str = "1.3.6.1.4.1.11129.2.4.2";
+const POISON_OID: &str = "1.3.6.1.4.1.11129.2.4.3";
+
+/// A name of one organization and common name
+fn name(b: DerBuilder, organization: &str, common_name: &str) -> DerBuilder {
+    b.sequence(|b| {
+        b.set(|b| b.sequence(|b| b.oid("2.5.4.10").utf8_string(organization)))
+            .set(|b| b.sequence(|b| b.oid("2.5.4.3").utf8_string(common_name)))
+    })
+}
+
+/// An RSA certificate for `subject` issued by `issuer`, with `dns_names` in
+/// its subjectAltName and `extensions` added after the usual ones
+fn certificate(
+    subject: &str,
+    issuer: &str,
+    dns_names: usize,
+    extensions: impl FnOnce(DerBuilder) -> DerBuilder,
+) -> Vec<u8> {
+    let modulus: Vec<u8> = (0..513u32).map(|i| (i * 7 + 1) as u8 | 0x80).collect();
+    let key = DerBuilder::new()
+        .sequence(|b| b.unsigned_integer(&modulus).integer(65537))
+        .finish();
+    let san = DerBuilder::new()
+        .sequence(|mut b| {
+            for i in 0..dns_names {
+                b = b.primitive(0x82, format!("host{}.{}", i, subject).as_bytes());
+            }
+            b
+        })
+        .finish();
+    let sha256_rsa = |b: DerBuilder| b.sequence(|b| b.oid("1.2.840.113549.1.1.11").null());
+
+    DerBuilder::new()
+        .sequence(|b| {
+            let b = b.sequence(|b| {
+                let b = b.explicit(0, |b| b.integer(2)).integer(0x1234_5678_9ABC);
+                let b = name(sha256_rsa(b), "Example CA", issuer);
+                let b = b.sequence(|b| b.utc_time("250101000000Z").utc_time("260101000000Z"));
+                let b = name(b, "Example", subject);
+                b.sequence(|b| {
+                    b.sequence(|b| b.oid("1.2.840.113549.1.1.1").null())
+                        .bit_string(&key, 0)
+                })
+                .explicit(3, |b| {
+                    b.sequence(|b| {
+                        let b = b
+                            .sequence(|b| {
+                                b.oid("2.5.29.15")
+                                    .boolean(true)
+                                    .octet_string(&[3, 2, 5, 160])
+                            })
+                            .sequence(|b| b.oid("2.5.29.17").octet_string(&san));
+                        extensions(b)
+                    })
+                })
+            });
+            sha256_rsa(b).bit_string(&modulus, 0)
+        })
+        .finish()
+}
+
+/// A certificate with a thousand names, as some CDN certificates have
+fn large_certificate() -> Vec<u8> {
+    certificate("www.example.com", "Example Issuing CA", 1000, |b| b)
+}
+
+/// The DER of a CT log entry for a precertificate, as in the extra_data of a
+/// get-entries response: the precertificate, then the chain that issued it
+fn ct_log_entry() -> Vec<u8> {
+    let mut entry = certificate("leaf.example.com", "Example Issuing CA", 50, |b| {
+        b.sequence(|b| b.oid(POISON_OID).boolean(true).octet_string(&[0x05, 0x00]))
+            .sequence(|b| b.oid(SCT_LIST_OID).octet_string(&[0x04, 0x02, 0x00, 0x00]))
+    });
+    entry.extend(certificate(
+        "Example Issuing CA",
+        "Example Root CA",
+        0,
+        |b| b,
+    ));
+    entry.extend(certificate("Example Root CA", "Example Root CA", 0, |b| b));
+    entry
+}
+
+/// A map of records keyed by text, about a megabyte encoded
+fn megabyte_map() -> Vec<u8> {
+    let item = CborItem::from_value;
+    let text = |s: String| item(CborValue::Text(Cow::Owned(s)));
+    let pairs = (0..12_000u64)
+        .map(|i| {
+            let record = CborValue::Map(vec![
+                (text("id".into()), item(CborValue::Unsigned(i))),
+                (text("name".into()), text(format!("record number {}", i))),
+                (
+                    text("digest".into()),
+                    item(CborValue::Bytes(Cow::Owned(vec![i as u8; 32]))),
+                ),
+                (
+                    text("values".into()),
+                    item(CborValue::Array(vec![
+                        item(CborValue::Negative(i)),
+                        item(CborValue::Float64(i as f64 / 3.0)),
+                        item(CborValue::Boolean(i % 2 == 0)),
+                    ])),
+                ),
+            ]);
+            (text(format!("key-{:06}", i)), item(record))
+        })
+        .collect();
+    CborValue::Map(pairs).encode()
+}
+
+fn asn1_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("asn1");
+    for (input, der) in [
+        ("large_certificate", large_certificate()),
+        ("ct_log_entry", ct_log_entry()),
+    ] {
+        // Timing a dump that stops early would mislead
+        let text = dump_asn1_to_string(&der, &[]).unwrap();
+        assert!(!text.contains("Error"), "{} doesn't dump cleanly", input);
+
+        group.throughput(Throughput::Bytes(der.len() as u64));
+        group.bench_with_input(BenchmarkId::new("parse", input), &der, |b, der| {
+            b.iter(|| try_parse_asn1(black_box(der)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("dump", input), &der, |b, der| {
+            b.iter(|| dump_asn1_to_string(black_box(der), &[]).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("dump_json", input), &der, |b, der| {
+            b.iter(|| dump_asn1_to_string(black_box(der), &["--format", "json"]).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn cbor_benchmarks(c: &mut Criterion) {
+    let data = megabyte_map();
+    let text = dump_cbor_to_string(&data, &[]).unwrap();
+    assert!(!text.contains("Error"), "megabyte_map doesn't dump cleanly");
+
+    let mut group = c.benchmark_group("cbor");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("parse/megabyte_map", |b| {
+        b.iter(|| cbor::parse_slice(black_box(&data)).unwrap())
+    });
+    group.bench_function("dump/megabyte_map", |b| {
+        b.iter(|| dump_cbor_to_string(black_box(&data), &[]).unwrap())
+    });
+    group.bench_function("dump_stream/megabyte_map", |b| {
+        b.iter(|| dump_cbor_to_string(black_box(&data), &["--stream"]).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, asn1_benchmarks, cbor_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+// The whole of dumpasn1's output code, through dump_asn1_to_string(). The
+// first byte picks the options, so that each display mode gets exercised.
+#![no_main]
+
+use asn1_cbor_tools::dump_asn1_to_string;
+use libfuzzer_sys::fuzz_target;
+
+const OPTIONS: &[&[&str]] = &[
+    &[],
+    &["-a", "-dd", "-t", "-v"],
+    &["-p", "-x", "-w", "40", "--dots", "--oid-info"],
+    &["-r", "--strict", "--int-both", "--digest", "sha256"],
+    &["--recover", "--show-secrets", "-i"],
+    &["--format", "diag"],
+    &["--format", "json"],
+    &["--format", "html"],
+];
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((&choice, input)) = data.split_first() {
+        let options = OPTIONS[choice as usize % OPTIONS.len()];
+        let _ = dump_asn1_to_string(input, options);
+    }
+});
@@ -0,0 +1,23 @@
+// The whole of dumpcbor's output code, through dump_cbor_to_string(). The
+// first byte picks the options, so that each display mode gets exercised.
+#![no_main]
+
+use asn1_cbor_tools::dump_cbor_to_string;
+use libfuzzer_sys::fuzz_target;
+
+const OPTIONS: &[&[&str]] = &[
+    &[],
+    &["-a", "-o", "-x", "-v"],
+    &["-c", "-t", "--recover"],
+    &["--stream", "-o", "--hex-offsets"],
+    &["--format", "diag"],
+    &["--format", "json"],
+    &["--format", "html"],
+];
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((&choice, input)) = data.split_first() {
+        let options = OPTIONS[choice as usize % OPTIONS.len()];
+        let _ = dump_cbor_to_string(input, options);
+    }
+});
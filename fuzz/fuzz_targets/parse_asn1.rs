@@ -0,0 +1,9 @@
+// try_parse_asn1() on arbitrary input
+#![no_main]
+
+use asn1_cbor_tools::try_parse_asn1;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = try_parse_asn1(data);
+});
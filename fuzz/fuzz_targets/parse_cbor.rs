@@ -0,0 +1,15 @@
+// try_parse_cbor() on arbitrary input, re-encoding whatever parses: neither
+// may panic, and a parse must allocate no more than the input justifies
+#![no_main]
+
+use asn1_cbor_tools::try_parse_cbor;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(items) = try_parse_cbor(data) {
+        for item in items {
+            let encoding = item.value.encode();
+            assert!(try_parse_cbor(&encoding).is_ok());
+        }
+    }
+});
@@ -0,0 +1,352 @@
+// Digests, PBKDF2 and AES-CBC decryption, for --digest, --digest-each and
+// PKCS #12 decryption. The digests and PBKDF2 are ring's, so builds without
+// the crypto feature have none. AES-CBC decryption is here because ring
+// doesn't offer it; it's for inspecting data only and makes no attempt at
+// constant-time operation.
+
+#[cfg(feature = "crypto")]
+use ring::{digest, pbkdf2};
+
+/// Hash functions for digests and PBKDF2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlg {
+    Sha1,
+    Sha256,
+}
+
+impl HashAlg {
+    /// The names from_name takes, for messages
+    pub const NAMES: &'static str = "sha1 or sha256";
+
+    /// Look up an algorithm by a command-line name such as `sha256`, for
+    /// --digest and --digest-each
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        let alg = match name.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha1" => HashAlg::Sha1,
+            "sha256" => HashAlg::Sha256,
+            _ => {
+                return Err(format!(
+                    "Unsupported digest algorithm: {} (use {})",
+                    name,
+                    Self::NAMES
+                ))
+            }
+        };
+        if cfg!(feature = "crypto") {
+            Ok(alg)
+        } else {
+            Err("Digests need a build with the crypto feature \
+                 (cargo install --features crypto)"
+                .to_string())
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlg::Sha1 => "SHA-1",
+            HashAlg::Sha256 => "SHA-256",
+        }
+    }
+
+    #[cfg(feature = "crypto")]
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        let algorithm = match self {
+            HashAlg::Sha1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+            HashAlg::Sha256 => &digest::SHA256,
+        };
+        digest::digest(algorithm, data).as_ref().to_vec()
+    }
+
+    /// Without the crypto feature from_name gives no algorithm to digest with
+    #[cfg(not(feature = "crypto"))]
+    pub fn digest(self, _data: &[u8]) -> Vec<u8> {
+        unreachable!("digests need the crypto feature")
+    }
+}
+
+/// PBKDF2 (RFC 8018, section 5.2) with HMAC of `alg`
+#[cfg(feature = "crypto")]
+pub fn pbkdf2(
+    alg: HashAlg,
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    key_len: usize,
+) -> Result<Vec<u8>, String> {
+    let algorithm = match alg {
+        HashAlg::Sha1 => pbkdf2::PBKDF2_HMAC_SHA1,
+        HashAlg::Sha256 => pbkdf2::PBKDF2_HMAC_SHA256,
+    };
+    let iterations = std::num::NonZeroU32::new(iterations).ok_or("no PBKDF2 iterations")?;
+    let mut key = vec![0; key_len];
+    pbkdf2::derive(algorithm, iterations, salt, password, &mut key);
+    Ok(key)
+}
+
+#[cfg(not(feature = "crypto"))]
+pub fn pbkdf2(
+    _alg: HashAlg,
+    _password: &[u8],
+    _salt: &[u8],
+    _iterations: u32,
+    _key_len: usize,
+) -> Result<Vec<u8>, String> {
+    Err("decryption needs a build with the crypto feature \
+         (cargo install --features crypto)"
+        .to_string())
+}
+
+/// Multiply in GF(2^8) with the AES reduction polynomial
+const fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let high = a & 0x80;
+        a <<= 1;
+        if high != 0 {
+            a ^= 0x1B;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Generate the AES S-box by walking the multiplicative group of GF(2^8)
+const fn aes_sbox() -> [u8; 256] {
+    let mut sbox = [0u8; 256];
+    let mut p: u8 = 1;
+    let mut q: u8 = 1;
+    loop {
+        // Multiply p by 3 and divide q by 3, so q is always p's inverse
+        p = p ^ (p << 1) ^ if p & 0x80 != 0 { 0x1B } else { 0 };
+        q ^= q << 1;
+        q ^= q << 2;
+        q ^= q << 4;
+        if q & 0x80 != 0 {
+            q ^= 0x09;
+        }
+        let x = q ^ q.rotate_left(1) ^ q.rotate_left(2) ^ q.rotate_left(3) ^ q.rotate_left(4);
+        sbox[p as usize] = x ^ 0x63;
+        if p == 1 {
+            break;
+        }
+    }
+    sbox[0] = 0x63;
+    sbox
+}
+
+const fn aes_inv_sbox() -> [u8; 256] {
+    let sbox = aes_sbox();
+    let mut inverse = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        inverse[sbox[i] as usize] = i as u8;
+        i += 1;
+    }
+    inverse
+}
+
+const SBOX: [u8; 256] = aes_sbox();
+const INV_SBOX: [u8; 256] = aes_inv_sbox();
+
+/// Expand an AES-128/192/256 key into round keys
+fn aes_expand_key(key: &[u8]) -> Vec<[u8; 16]> {
+    let nk = key.len() / 4;
+    let rounds = nk + 6;
+    let mut words: Vec<[u8; 4]> = key
+        .chunks_exact(4)
+        .map(|w| [w[0], w[1], w[2], w[3]])
+        .collect();
+
+    let mut rcon: u8 = 1;
+    for i in nk..4 * (rounds + 1) {
+        let mut temp = words[i - 1];
+        if i % nk == 0 {
+            temp = [
+                SBOX[temp[1] as usize] ^ rcon,
+                SBOX[temp[2] as usize],
+                SBOX[temp[3] as usize],
+                SBOX[temp[0] as usize],
+            ];
+            rcon = gf_mul(rcon, 2);
+        } else if nk > 6 && i % nk == 4 {
+            temp = temp.map(|b| SBOX[b as usize]);
+        }
+        let previous = words[i - nk];
+        words.push([
+            previous[0] ^ temp[0],
+            previous[1] ^ temp[1],
+            previous[2] ^ temp[2],
+            previous[3] ^ temp[3],
+        ]);
+    }
+
+    words
+        .chunks_exact(4)
+        .map(|round| {
+            let mut round_key = [0u8; 16];
+            for (i, word) in round.iter().enumerate() {
+                round_key[4 * i..4 * i + 4].copy_from_slice(word);
+            }
+            round_key
+        })
+        .collect()
+}
+
+/// Decrypt a single AES block in place
+fn aes_decrypt_block(round_keys: &[[u8; 16]], block: &mut [u8; 16]) {
+    let rounds = round_keys.len() - 1;
+    let add_round_key = |block: &mut [u8; 16], round: usize| {
+        for (b, k) in block.iter_mut().zip(&round_keys[round]) {
+            *b ^= k;
+        }
+    };
+
+    add_round_key(block, rounds);
+    for round in (0..rounds).rev() {
+        // InvShiftRows: row r moves right by r columns
+        let state = *block;
+        for row in 1..4 {
+            for col in 0..4 {
+                block[row + 4 * ((col + row) % 4)] = state[row + 4 * col];
+            }
+        }
+        // InvSubBytes
+        for b in block.iter_mut() {
+            *b = INV_SBOX[*b as usize];
+        }
+        add_round_key(block, round);
+        // InvMixColumns, skipped after the final round key
+        if round > 0 {
+            for col in 0..4 {
+                let c = [
+                    block[4 * col],
+                    block[4 * col + 1],
+                    block[4 * col + 2],
+                    block[4 * col + 3],
+                ];
+                for row in 0..4 {
+                    block[4 * col + row] = gf_mul(c[row], 14)
+                        ^ gf_mul(c[(row + 1) % 4], 11)
+                        ^ gf_mul(c[(row + 2) % 4], 13)
+                        ^ gf_mul(c[(row + 3) % 4], 9);
+                }
+            }
+        }
+    }
+}
+
+/// Decrypt AES-CBC data and strip PKCS #7 padding
+pub fn aes_cbc_decrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    if !matches!(key.len(), 16 | 24 | 32) {
+        return Err(format!("invalid AES key length {}", key.len()));
+    }
+    if iv.len() != 16 {
+        return Err(format!("invalid AES-CBC IV length {}", iv.len()));
+    }
+    if data.is_empty() || !data.len().is_multiple_of(16) {
+        return Err(format!(
+            "ciphertext length {} is not a multiple of the block size",
+            data.len()
+        ));
+    }
+
+    let round_keys = aes_expand_key(key);
+    let mut previous = [0u8; 16];
+    previous.copy_from_slice(iv);
+    let mut plaintext = Vec::with_capacity(data.len());
+
+    for chunk in data.chunks_exact(16) {
+        let mut block = [0u8; 16];
+        block.copy_from_slice(chunk);
+        aes_decrypt_block(&round_keys, &mut block);
+        for (b, p) in block.iter_mut().zip(&previous) {
+            *b ^= p;
+        }
+        plaintext.extend_from_slice(&block);
+        previous.copy_from_slice(chunk);
+    }
+
+    let pad = *plaintext.last().unwrap_or(&0) as usize;
+    if pad == 0
+        || pad > 16
+        || !plaintext[plaintext.len() - pad..]
+            .iter()
+            .all(|&b| b as usize == pad)
+    {
+        return Err("bad padding (wrong password?)".to_string());
+    }
+    plaintext.truncate(plaintext.len() - pad);
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "crypto")]
+    use crate::common::hex::hex_lower;
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn test_digest() {
+        assert_eq!(
+            hex_lower(&HashAlg::Sha1.digest(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex_lower(&HashAlg::Sha256.digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn test_pbkdf2_hmac_sha256() {
+        // RFC 7914, section 11
+        let key = pbkdf2(HashAlg::Sha256, b"passwd", b"salt", 1, 64).unwrap();
+        assert_eq!(
+            hex_lower(&key),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+    }
+
+    #[cfg(not(feature = "crypto"))]
+    #[test]
+    fn test_needs_crypto_feature() {
+        let err = HashAlg::from_name("sha256").unwrap_err();
+        assert!(err.contains("--features crypto"), "{}", err);
+        assert!(pbkdf2(HashAlg::Sha1, b"pw", b"salt", 1, 16).is_err());
+    }
+
+    #[test]
+    fn test_aes_decrypt_block_fips197() {
+        // FIPS 197, appendix C
+        let plaintext = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
+            0xEE, 0xFF,
+        ];
+        let cases: [(usize, &str); 3] = [
+            (16, "69c4e0d86a7b0430d8cdb78070b4c55a"),
+            (24, "dda97ca4864cdfe06eaf70a0ec0d7191"),
+            (32, "8ea2b7ca516745bfeafc49904b496089"),
+        ];
+        for (key_len, ciphertext) in cases {
+            let key: Vec<u8> = (0..key_len as u8).collect();
+            let mut block = [0u8; 16];
+            for (i, b) in block.iter_mut().enumerate() {
+                *b = u8::from_str_radix(&ciphertext[2 * i..2 * i + 2], 16).unwrap();
+            }
+            aes_decrypt_block(&aes_expand_key(&key), &mut block);
+            assert_eq!(block, plaintext, "AES-{}", key_len * 8);
+        }
+    }
+
+    #[test]
+    fn test_aes_cbc_rejects_bad_padding() {
+        let result = aes_cbc_decrypt(&[0u8; 16], &[0u8; 16], &[0u8; 16]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,330 @@
+// In-memory DER tag-length-value helpers
+// Used for lookahead checks (encapsulation, structure recognition) where the
+// streaming reader in the main dumper would be awkward to use
+
+use super::tags::{CONSTRUCTED, FORM_MASK, LEN_MASK, LEN_XTND, TAG_MASK};
+
+/// Maximum nesting depth followed when validating in-memory encodings
+const MAX_CHECK_DEPTH: usize = 64;
+
+/// Header information for a definite-length TLV held in a byte slice
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tlv {
+    pub id: u8,            // First identifier octet (class, form and low tag bits)
+    pub tag: u32,          // Tag number
+    pub header_len: usize, // Size of tag+length
+    pub length: usize,     // Content length
+}
+
+impl Tlv {
+    pub fn is_constructed(&self) -> bool {
+        (self.id & FORM_MASK) == CONSTRUCTED
+    }
+
+    pub fn total_len(&self) -> usize {
+        self.header_len + self.length
+    }
+
+    /// Content octets of this TLV within the slice it was parsed from
+    pub fn content<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        &data[self.header_len..self.total_len()]
+    }
+}
+
+/// Parse a definite-length TLV header at the start of `data`, checking that
+/// the declared content fits within the slice
+pub fn parse_tlv(data: &[u8]) -> Option<Tlv> {
+    let id = *data.first()?;
+    let mut pos = 1;
+    let mut tag = (id & TAG_MASK) as u32;
+
+    if tag == TAG_MASK as u32 {
+        tag = 0;
+        loop {
+            let byte = *data.get(pos)?;
+            pos += 1;
+            if tag > (u32::MAX >> 7) {
+                return None;
+            }
+            tag = (tag << 7) | (byte & 0x7F) as u32;
+            if (byte & LEN_XTND) == 0 {
+                break;
+            }
+        }
+    }
+
+    let len_byte = *data.get(pos)?;
+    pos += 1;
+    let length = if (len_byte & LEN_XTND) != 0 {
+        let num_octets = (len_byte & LEN_MASK) as usize;
+        if num_octets == 0 || num_octets > 4 {
+            return None;
+        }
+        let mut length = 0usize;
+        for _ in 0..num_octets {
+            length = (length << 8) | *data.get(pos)? as usize;
+            pos += 1;
+        }
+        length
+    } else {
+        len_byte as usize
+    };
+
+    if data.len() - pos < length {
+        return None;
+    }
+
+    Some(Tlv {
+        id,
+        tag,
+        header_len: pos,
+        length,
+    })
+}
+
+/// Iterator over the consecutive TLVs making up a constructed item's content
+pub struct Children<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+/// Iterate over the TLVs in `data`, yielding each one's offset within `data`
+pub fn children(data: &[u8]) -> Children<'_> {
+    Children { data, pos: 0 }
+}
+
+impl Iterator for Children<'_> {
+    type Item = (usize, Tlv);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tlv = parse_tlv(&self.data[self.pos..])?;
+        let offset = self.pos;
+        self.pos += tlv.total_len();
+        Some((offset, tlv))
+    }
+}
+
+/// Check that `data` consists entirely of well-formed definite-length TLVs,
+/// descending into constructed items
+pub fn is_valid_der(data: &[u8]) -> bool {
+    is_valid_at_depth(data, 0)
+}
+
+fn is_valid_at_depth(data: &[u8], depth: usize) -> bool {
+    if depth > MAX_CHECK_DEPTH {
+        return false;
+    }
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let tlv = match parse_tlv(&data[pos..]) {
+            Some(tlv) => tlv,
+            None => return false,
+        };
+        if tlv.is_constructed() && !is_valid_at_depth(tlv.content(&data[pos..]), depth + 1) {
+            return false;
+        }
+        pos += tlv.total_len();
+    }
+    true
+}
+
+/// Check whether the content of an OCTET STRING or BIT STRING looks like a
+/// single encapsulated DER object rather than opaque binary data
+pub fn looks_encapsulated(data: &[u8]) -> bool {
+    let tlv = match parse_tlv(data) {
+        Some(tlv) => tlv,
+        None => return false,
+    };
+
+    // Only accept the object types that are routinely wrapped (ENUMERATED for
+    // CRL reason codes), so that random binary data which happens to parse
+    // isn't misreported
+    let plausible = match tlv.id {
+        0x30 | 0x31 => tlv.length > 0,
+        0x02 | 0x03 | 0x04 | 0x06 | 0x0A => tlv.length > 0 && data.len() > 2,
+        _ => false,
+    };
+
+    plausible && tlv.total_len() == data.len() && is_valid_der(data)
+}
+
+/// Find the first offset in damaged data where a plausible object starts:
+/// a constructed item whose contents are all well-formed, which random bytes
+/// rarely manage, or well-formed primitive items leading straight up to one.
+/// Primitive items alone match by chance too easily to resync on.
+pub fn find_plausible_item(data: &[u8]) -> Option<usize> {
+    (0..data.len()).find(|&pos| {
+        let mut next = pos;
+        while let Some(tlv) = parse_tlv(&data[next..]) {
+            let end = next + tlv.total_len();
+            if !is_valid_der(&data[next..end]) {
+                return false;
+            }
+            if tlv.is_constructed() {
+                return tlv.length > 0;
+            }
+            next = end;
+        }
+        false
+    })
+}
+
+/// Find the item starting at `target` within `data`, descending into
+/// constructed items and encapsulated objects the way the dump does
+pub fn find_item(data: &[u8], target: usize) -> Option<Tlv> {
+    find_at_depth(data, target, 0)
+}
+
+fn find_at_depth(data: &[u8], target: usize, depth: usize) -> Option<Tlv> {
+    if depth > MAX_CHECK_DEPTH {
+        return None;
+    }
+    let (pos, tlv) = children(data).find(|(pos, tlv)| target < pos + tlv.total_len())?;
+    if pos == target {
+        return Some(tlv);
+    }
+    if target < pos + tlv.header_len {
+        return None;
+    }
+
+    let content = tlv.content(&data[pos..]);
+    let inner = target - pos - tlv.header_len;
+    if tlv.is_constructed() {
+        return find_at_depth(content, inner, depth + 1);
+    }
+    // BIT STRINGs carry an unused-bits octet before the wrapped data
+    let skip = if tlv.id == 0x03 { 1 } else { 0 };
+    if (tlv.id == 0x03 || tlv.id == 0x04)
+        && inner >= skip
+        && content.len() > skip
+        && looks_encapsulated(&content[skip..])
+    {
+        return find_at_depth(&content[skip..], inner - skip, depth + 1);
+    }
+    None
+}
+
+/// Decode OBJECT IDENTIFIER content octets to dotted-decimal form
+pub fn decode_oid(data: &[u8]) -> Option<String> {
+    let arcs = decode_arcs(data)?;
+    let mut arcs = arcs.into_iter();
+    let first = arcs.next()?;
+
+    // The first subidentifier encodes the first two arcs
+    let (arc1, arc2) = match first {
+        0..=39 => (0, first),
+        40..=79 => (1, first - 40),
+        _ => (2, first - 80),
+    };
+
+    let mut oid = format!("{}.{}", arc1, arc2);
+    for arc in arcs {
+        oid.push_str(&format!(".{}", arc));
+    }
+    Some(oid)
+}
+
+/// Decode RELATIVE-OID content octets, where every subidentifier is a
+/// single arc
+pub fn decode_relative_oid(data: &[u8]) -> Option<String> {
+    let arcs: Vec<String> = decode_arcs(data)?.iter().map(u64::to_string).collect();
+    Some(arcs.join("."))
+}
+
+/// Decode base-128 subidentifiers, rejecting truncated or overlong values
+pub fn decode_arcs(data: &[u8]) -> Option<Vec<u64>> {
+    if data.is_empty() || (data[data.len() - 1] & 0x80) != 0 {
+        return None;
+    }
+
+    let mut arcs = Vec::new();
+    let mut value: u64 = 0;
+    for &byte in data {
+        if value > (u64::MAX >> 7) {
+            return None;
+        }
+        value = (value << 7) | (byte & 0x7F) as u64;
+        if (byte & 0x80) == 0 {
+            arcs.push(value);
+            value = 0;
+        }
+    }
+    Some(arcs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tlv_short_and_long_form() {
+        let short = parse_tlv(&[0x02, 0x01, 0x2A]).expect("short form");
+        assert_eq!((short.tag, short.header_len, short.length), (2, 2, 1));
+
+        let mut long = vec![0x04, 0x81, 0x80];
+        long.extend(vec![0u8; 0x80]);
+        let long = parse_tlv(&long).expect("long form");
+        assert_eq!((long.header_len, long.length), (3, 0x80));
+
+        // Declared length runs past the end of the buffer
+        assert!(parse_tlv(&[0x04, 0x05, 0x00]).is_none());
+    }
+
+    #[test]
+    fn test_children_iterates_sequence_content() {
+        let content = [0x02, 0x01, 0x01, 0x05, 0x00, 0x06, 0x01, 0x2A];
+        let tags: Vec<u32> = children(&content).map(|(_, tlv)| tlv.tag).collect();
+        assert_eq!(tags, vec![2, 5, 6]);
+    }
+
+    #[test]
+    fn test_looks_encapsulated() {
+        assert!(looks_encapsulated(&[0x30, 0x03, 0x02, 0x01, 0x05]));
+        assert!(!looks_encapsulated(&[0x30, 0x03, 0x02, 0x01]));
+        assert!(!looks_encapsulated(b"hello world"));
+    }
+
+    #[test]
+    fn test_find_plausible_item() {
+        // Garbage, a SEQUENCE whose content isn't well-formed, then a good one
+        let data = [
+            0xFF, 0x02, 0x30, 0x02, 0x02, 0x05, 0x30, 0x03, 0x02, 0x01, 0x05,
+        ];
+        assert_eq!(find_plausible_item(&data), Some(6));
+        assert_eq!(find_plausible_item(&[0x05, 0x00, 0x02, 0x01, 0x00]), None);
+        // An INTEGER before a good SEQUENCE is kept with it
+        let data = [0xFF, 0x02, 0x01, 0x05, 0x30, 0x03, 0x02, 0x01, 0x07];
+        assert_eq!(find_plausible_item(&data), Some(1));
+    }
+
+    #[test]
+    fn test_find_item() {
+        // SEQUENCE { INTEGER 5, OCTET STRING { INTEGER 7 } }
+        let data = [0x30, 0x08, 0x02, 0x01, 0x05, 0x04, 0x03, 0x02, 0x01, 0x07];
+        assert_eq!(find_item(&data, 0).map(|tlv| tlv.length), Some(8));
+        assert_eq!(find_item(&data, 2).map(|tlv| tlv.id), Some(0x02));
+        assert_eq!(find_item(&data, 7).map(|tlv| tlv.length), Some(1));
+        // Offsets inside a header or content don't start an item
+        assert!(find_item(&data, 3).is_none());
+        assert!(find_item(&data, 10).is_none());
+    }
+
+    #[test]
+    fn test_decode_oid() {
+        let oid = decode_oid(&[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02]);
+        assert_eq!(oid.as_deref(), Some("1.2.840.113549.1.7.2"));
+        // Joint-ISO arcs above 39 need the full first subidentifier
+        assert_eq!(decode_oid(&[0x88, 0x37, 0x03]).as_deref(), Some("2.999.3"));
+        assert!(decode_oid(&[0x2A, 0x86]).is_none());
+    }
+
+    #[test]
+    fn test_decode_relative_oid() {
+        assert_eq!(
+            decode_relative_oid(&[0x08, 0x86, 0x48, 0x01]).as_deref(),
+            Some("8.840.1")
+        );
+        assert!(decode_relative_oid(&[0x86]).is_none());
+    }
+}
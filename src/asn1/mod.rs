@@ -0,0 +1,20 @@
+// Support modules for the ASN.1 dumper: tag values, in-memory DER helpers,
+// the built-in OID name table, structure templates for well-known formats,
+// the local time zone for certificate validity times and checking
+// certificate signatures. Also the ASN.1 module codegen writes for DER
+// samples, and the RFC 5280 profile checks of lint-cert
+
+pub mod crypto;
+pub mod der;
+pub mod oids;
+pub mod pem;
+pub mod pkcs12;
+pub mod profile;
+pub mod signature;
+pub mod skeleton;
+pub mod strings;
+pub mod tags;
+pub mod templates;
+pub mod time;
+pub mod x509;
+pub mod zone;
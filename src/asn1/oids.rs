@@ -0,0 +1,281 @@
+// Built-in table of well-known OBJECT IDENTIFIER names
+// A small subset of what dumpasn1.cfg provides for the original tool,
+// covering the OIDs used by the structure templates. A dumpasn1.cfg can be
+// loaded as well (--oid-config), and its names are used in place of these.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::sync::{Mutex, RwLock};
+
+use super::der::decode_oid;
+use crate::error::DumpError;
+
+// Names read from OID config files, kept for as long as the process runs
+// since the built-in names they stand in for are static
+static LOADED_NAMES: RwLock<BTreeMap<String, &'static str>> = RwLock::new(BTreeMap::new());
+// The files already read, which a batch of dumps with the same options
+// would otherwise read again for each file
+static LOADED_FILES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Dotted-decimal OID and its descriptive name
+static OID_NAMES: &[(&str, &str)] = &[
+    // PKCS #1
+    ("1.2.840.113549.1.1.1", "rsaEncryption"),
+    ("1.2.840.113549.1.1.2", "md2WithRSAEncryption"),
+    ("1.2.840.113549.1.1.4", "md5WithRSAEncryption"),
+    ("1.2.840.113549.1.1.5", "sha1WithRSAEncryption"),
+    ("1.2.840.113549.1.1.10", "rsassa-pss"),
+    ("1.2.840.113549.1.1.11", "sha256WithRSAEncryption"),
+    ("1.2.840.113549.1.1.12", "sha384WithRSAEncryption"),
+    ("1.2.840.113549.1.1.13", "sha512WithRSAEncryption"),
+    ("1.3.14.3.2.29", "sha1WithRSASignature"),
+    // DSA signatures
+    ("1.2.840.10040.4.3", "dsa-with-sha1"),
+    // PKCS #5 and PKCS #12 password-based encryption
+    ("1.2.840.113549.1.5.12", "pbkdf2"),
+    ("1.2.840.113549.1.5.13", "pbes2"),
+    ("1.2.840.113549.1.12.1.3", "pbeWithSHAAnd3-KeyTripleDES-CBC"),
+    ("1.2.840.113549.1.12.1.6", "pbeWithSHAAnd40BitRC2-CBC"),
+    ("1.2.840.113549.2.7", "hmacWithSHA1"),
+    ("1.2.840.113549.2.9", "hmacWithSHA256"),
+    ("1.2.840.113549.2.10", "hmacWithSHA384"),
+    ("1.2.840.113549.2.11", "hmacWithSHA512"),
+    // PKCS #7 / CMS content types
+    ("1.2.840.113549.1.7.1", "data"),
+    ("1.2.840.113549.1.7.2", "signedData"),
+    ("1.2.840.113549.1.7.3", "envelopedData"),
+    ("1.2.840.113549.1.7.4", "signedAndEnvelopedData"),
+    ("1.2.840.113549.1.7.5", "digestedData"),
+    ("1.2.840.113549.1.7.6", "encryptedData"),
+    ("1.2.840.113549.1.9.16.1.2", "authData"),
+    ("1.2.840.113549.1.9.16.1.4", "tstInfo"),
+    ("1.2.840.113549.1.9.16.1.9", "compressedData"),
+    ("1.2.840.113549.1.9.16.1.23", "authEnvelopedData"),
+    // PKCS #9 attributes
+    ("1.2.840.113549.1.9.1", "emailAddress"),
+    ("1.2.840.113549.1.9.3", "contentType"),
+    ("1.2.840.113549.1.9.4", "messageDigest"),
+    ("1.2.840.113549.1.9.5", "signingTime"),
+    ("1.2.840.113549.1.9.6", "countersignature"),
+    ("1.2.840.113549.1.9.15", "sMIMECapabilities"),
+    ("1.2.840.113549.1.9.16.2.12", "signingCertificate"),
+    ("1.2.840.113549.1.9.16.2.47", "signingCertificateV2"),
+    ("1.2.840.113549.1.9.52", "cmsAlgorithmProtection"),
+    ("1.2.840.113549.1.9.20", "friendlyName"),
+    ("1.2.840.113549.1.9.21", "localKeyID"),
+    ("1.2.840.113549.1.9.22.1", "x509Certificate"),
+    ("1.2.840.113549.1.9.23.1", "x509CRL"),
+    // PKCS #12 bag types
+    ("1.2.840.113549.1.12.10.1.1", "keyBag"),
+    ("1.2.840.113549.1.12.10.1.2", "pkcs8ShroudedKeyBag"),
+    ("1.2.840.113549.1.12.10.1.3", "certBag"),
+    ("1.2.840.113549.1.12.10.1.4", "crlBag"),
+    ("1.2.840.113549.1.12.10.1.5", "secretBag"),
+    ("1.2.840.113549.1.12.10.1.6", "safeContentsBag"),
+    // Digest algorithms
+    ("1.2.840.113549.2.5", "md5"),
+    ("1.3.14.3.2.26", "sha1"),
+    ("2.16.840.1.101.3.4.2.1", "sha256"),
+    ("2.16.840.1.101.3.4.2.2", "sha384"),
+    ("2.16.840.1.101.3.4.2.3", "sha512"),
+    // Content encryption algorithms
+    ("1.2.840.113549.3.7", "des-ede3-cbc"),
+    ("2.16.840.1.101.3.4.1.2", "aes128-CBC"),
+    ("2.16.840.1.101.3.4.1.22", "aes192-CBC"),
+    ("2.16.840.1.101.3.4.1.42", "aes256-CBC"),
+    // Elliptic curve keys and signatures
+    ("1.2.840.10045.2.1", "ecPublicKey"),
+    ("1.2.840.10045.3.1.7", "prime256v1"),
+    ("1.3.132.0.34", "secp384r1"),
+    ("1.3.132.0.35", "secp521r1"),
+    ("1.2.840.10045.4.1", "ecdsa-with-SHA1"),
+    ("1.2.840.10045.4.3.2", "ecdsa-with-SHA256"),
+    ("1.2.840.10045.4.3.3", "ecdsa-with-SHA384"),
+    ("1.2.840.10045.4.3.4", "ecdsa-with-SHA512"),
+    ("1.3.101.110", "X25519"),
+    ("1.3.101.111", "X448"),
+    ("1.3.101.112", "Ed25519"),
+    ("1.3.101.113", "Ed448"),
+    // X.500 attribute types
+    ("2.5.4.3", "commonName"),
+    ("2.5.4.4", "surname"),
+    ("2.5.4.5", "serialNumber"),
+    ("2.5.4.6", "countryName"),
+    ("2.5.4.7", "localityName"),
+    ("2.5.4.8", "stateOrProvinceName"),
+    ("2.5.4.9", "streetAddress"),
+    ("2.5.4.10", "organizationName"),
+    ("2.5.4.11", "organizationalUnitName"),
+    ("2.5.4.12", "title"),
+    ("2.5.4.42", "givenName"),
+    ("0.9.2342.19200300.100.1.25", "domainComponent"),
+    // X.509 certificate extensions
+    ("2.5.29.14", "subjectKeyIdentifier"),
+    ("2.5.29.15", "keyUsage"),
+    ("2.5.29.17", "subjectAltName"),
+    ("2.5.29.18", "issuerAltName"),
+    ("2.5.29.19", "basicConstraints"),
+    ("2.5.29.20", "cRLNumber"),
+    ("2.5.29.21", "cRLReason"),
+    ("2.5.29.30", "nameConstraints"),
+    ("2.5.29.31", "cRLDistributionPoints"),
+    ("2.5.29.32", "certificatePolicies"),
+    ("2.5.29.35", "authorityKeyIdentifier"),
+    ("2.5.29.36", "policyConstraints"),
+    ("2.5.29.37", "extKeyUsage"),
+    ("2.5.29.54", "inhibitAnyPolicy"),
+    ("1.3.6.1.5.5.7.1.1", "authorityInfoAccess"),
+    ("1.3.6.1.4.1.11129.2.4.2", "signedCertificateTimestampList"),
+    // Extended key usages and access methods
+    ("1.3.6.1.5.5.7.3.1", "serverAuth"),
+    ("1.3.6.1.5.5.7.3.2", "clientAuth"),
+    ("1.3.6.1.5.5.7.3.3", "codeSigning"),
+    ("1.3.6.1.5.5.7.3.4", "emailProtection"),
+    ("1.3.6.1.5.5.7.3.8", "timeStamping"),
+    ("1.3.6.1.5.5.7.3.9", "OCSPSigning"),
+    ("1.3.6.1.5.5.7.48.1", "ocsp"),
+    ("1.3.6.1.5.5.7.48.2", "caIssuers"),
+    // OCSP (RFC 6960)
+    ("1.3.6.1.5.5.7.48.1.1", "ocspBasic"),
+    ("1.3.6.1.5.5.7.48.1.2", "ocspNonce"),
+    ("1.3.6.1.5.5.7.48.1.3", "ocspCRL"),
+    ("1.3.6.1.5.5.7.48.1.4", "ocspResponse"),
+    ("1.3.6.1.5.5.7.48.1.5", "ocspNoCheck"),
+    ("1.3.6.1.5.5.7.48.1.6", "ocspArchiveCutoff"),
+    ("1.3.6.1.5.5.7.48.1.7", "ocspServiceLocator"),
+    ("1.3.6.1.5.5.7.48.1.8", "ocspPrefSigAlgs"),
+    ("1.3.6.1.5.5.7.48.1.9", "ocspExtendedRevoke"),
+];
+
+/// Look up the descriptive name for a dotted-decimal OID
+pub fn oid_name(oid: &str) -> Option<&'static str> {
+    if let Some(name) = LOADED_NAMES.read().ok()?.get(oid) {
+        return Some(name);
+    }
+    OID_NAMES
+        .iter()
+        .find(|(dotted, _)| *dotted == oid)
+        .map(|(_, name)| *name)
+}
+
+/// Read an OID config file in the format of dumpasn1.cfg, so that the names
+/// it gives are used from then on
+pub fn load_config(path: &str) -> io::Result<()> {
+    let mut loaded_files = LOADED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    if loaded_files.iter().any(|loaded| loaded == path) {
+        return Ok(());
+    }
+    let data = fs::read(path)
+        .map_err(|e| DumpError::io(format!("Can't read OID config '{}'", path), e))?;
+    let names = parse_config(&String::from_utf8_lossy(&data)).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("OID config '{}': {}", path, e),
+        )
+    })?;
+    let mut loaded_names = LOADED_NAMES.write().unwrap_or_else(|e| e.into_inner());
+    for (oid, name) in names {
+        loaded_names.insert(oid, Box::leak(name.into_boxed_str()));
+    }
+    loaded_files.push(path.to_string());
+    Ok(())
+}
+
+/// The OIDs and names in the text of a dumpasn1.cfg: each entry starts with
+/// an `OID = 06 03 55 04 03` line giving the encoded OID in hex, and its
+/// `Description = ` line gives the name. Comment and Warning lines, and
+/// lines starting with '#', are skipped.
+fn parse_config(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut names = Vec::new();
+    let mut oid = None;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (line, ""),
+        };
+        match key {
+            "OID" => {
+                oid =
+                    Some(config_oid(value).ok_or_else(|| {
+                        format!("line {}: can't decode OID '{}'", index + 1, value)
+                    })?);
+            }
+            "Description" => match &oid {
+                Some(oid) => names.push((oid.clone(), description_name(value).to_string())),
+                None => return Err(format!("line {}: Description before any OID", index + 1)),
+            },
+            _ => {}
+        }
+    }
+    Ok(names)
+}
+
+/// The dotted form of an OID given as the hex bytes of its DER encoding
+fn config_oid(hex: &str) -> Option<String> {
+    let bytes = hex
+        .split_whitespace()
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    match bytes[..] {
+        [0x06, length, ref content @ ..] if length as usize == content.len() => decode_oid(content),
+        _ => None,
+    }
+}
+
+/// A description without the arcs dumpasn1.cfg often ends it with, as in
+/// "commonName (2 5 4 3)", since the dump shows the OID beside its name
+fn description_name(description: &str) -> &str {
+    match description.rsplit_once(" (") {
+        Some((name, arcs))
+            if arcs.ends_with(')')
+                && arcs[..arcs.len() - 1]
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == ' ') =>
+        {
+            name
+        }
+        _ => description,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oid_name_lookup() {
+        assert_eq!(oid_name("1.2.840.113549.1.7.2"), Some("signedData"));
+        assert_eq!(oid_name("2.5.4.3"), Some("commonName"));
+        assert_eq!(oid_name("1.2.3.4.5"), None);
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let text = "# Extra OIDs\n\
+                    OID = 06 03 55 04 03\n\
+                    Comment = X.520 id-at-commonName (2 5 4 3)\n\
+                    Description = commonName (2 5 4 3)\n\
+                    \n\
+                    OID = 06 04 2A 03 04 05\n\
+                    Description = privateArc (uses 1 2 3)\n\
+                    Warning\n";
+        assert_eq!(
+            parse_config(text).unwrap(),
+            [
+                ("2.5.4.3".to_string(), "commonName".to_string()),
+                (
+                    "1.2.3.4.5".to_string(),
+                    "privateArc (uses 1 2 3)".to_string()
+                ),
+            ]
+        );
+        let err = parse_config("OID = 06 05 2A\n").unwrap_err();
+        assert_eq!(err, "line 1: can't decode OID '06 05 2A'");
+        assert!(parse_config("Description = orphan\n").is_err());
+    }
+}
@@ -0,0 +1,193 @@
+// Decryption of PKCS #12 EncryptedData layers
+// Given a password, the EncryptedData ContentInfos in a PFX's AuthenticatedSafe
+// (normally holding the certificate bags) are decrypted so that the SafeContents
+// inside can be dumped. Only PBES2 with PBKDF2 and AES-CBC is supported, which
+// is what current OpenSSL and Windows versions produce by default.
+
+use std::collections::HashMap;
+
+use super::crypto::{self, HashAlg};
+use super::der::{children, decode_oid, parse_tlv};
+use super::oids::oid_name;
+use super::tags::OID;
+
+/// Upper bound on PBKDF2 iterations, so a hostile file can't stall the dump
+const MAX_ITERATIONS: u32 = 10_000_000;
+
+/// Result of decrypting one encryptedContent item
+pub type Decrypted = Result<Vec<u8>, String>;
+
+/// Content octets of a complete encoding
+fn content_of(encoding: &[u8]) -> Option<&[u8]> {
+    parse_tlv(encoding).map(|tlv| tlv.content(encoding))
+}
+
+/// Complete encodings of the components of a constructed item
+fn components(encoding: &[u8]) -> Vec<&[u8]> {
+    match content_of(encoding) {
+        Some(content) => children(content)
+            .map(|(pos, tlv)| &content[pos..pos + tlv.total_len()])
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn oid_of(encoding: &[u8]) -> Option<String> {
+    if encoding.first() != Some(&OID) {
+        return None;
+    }
+    content_of(encoding).and_then(decode_oid)
+}
+
+fn describe(oid: &str) -> String {
+    match oid_name(oid) {
+        Some(name) => format!("{} ({})", name, oid),
+        None => oid.to_string(),
+    }
+}
+
+/// Decrypt every EncryptedData layer in a PFX, keyed by the absolute offset of
+/// each encryptedContent item. Returns nothing if `data` isn't a PFX.
+pub fn decrypt_layers(data: &[u8], offset: usize, password: &str) -> HashMap<usize, Decrypted> {
+    let mut results = HashMap::new();
+
+    let pfx = components(data);
+    let auth_safe = match pfx.get(1) {
+        Some(auth_safe) => components(auth_safe),
+        None => return results,
+    };
+    // authSafe is a data ContentInfo whose OCTET STRING holds the AuthenticatedSafe
+    let authenticated_safe = match auth_safe.get(1).map(|content| components(content)) {
+        Some(wrapped) => match wrapped.first().and_then(|octets| content_of(octets)) {
+            Some(encoding) => components(encoding),
+            None => return results,
+        },
+        None => return results,
+    };
+
+    for content_info in authenticated_safe {
+        let parts = components(content_info);
+        if parts.first().and_then(|oid| oid_of(oid)).as_deref() != Some("1.2.840.113549.1.7.6") {
+            continue;
+        }
+        let encrypted_data = match parts.get(1).map(|content| components(content)) {
+            Some(wrapped) if !wrapped.is_empty() => components(wrapped[0]),
+            _ => continue,
+        };
+        let info = match encrypted_data.get(1) {
+            Some(info) => components(info),
+            None => continue,
+        };
+        if let (Some(algorithm), Some(encrypted)) = (info.get(1), info.get(2)) {
+            if encrypted[0] != 0x80 {
+                continue;
+            }
+            let position = offset + (encrypted.as_ptr() as usize - data.as_ptr() as usize);
+            let ciphertext = content_of(encrypted).unwrap_or_default();
+            results.insert(position, decrypt(algorithm, ciphertext, password));
+        }
+    }
+
+    results
+}
+
+/// Decrypt content encrypted under a PBES2 AlgorithmIdentifier
+pub fn decrypt(algorithm: &[u8], ciphertext: &[u8], password: &str) -> Decrypted {
+    let parts = components(algorithm);
+    let scheme = parts
+        .first()
+        .and_then(|oid| oid_of(oid))
+        .unwrap_or_default();
+    if scheme != "1.2.840.113549.1.5.13" {
+        return Err(format!(
+            "unsupported encryption algorithm {}",
+            describe(&scheme)
+        ));
+    }
+
+    let params = parts.get(1).map(|p| components(p)).unwrap_or_default();
+    let (kdf, cipher) = match params.as_slice() {
+        [kdf, cipher] => (components(kdf), components(cipher)),
+        _ => return Err("malformed PBES2 parameters".to_string()),
+    };
+
+    let kdf_oid = kdf.first().and_then(|oid| oid_of(oid)).unwrap_or_default();
+    if kdf_oid != "1.2.840.113549.1.5.12" {
+        return Err(format!(
+            "unsupported key derivation function {}",
+            describe(&kdf_oid)
+        ));
+    }
+    let kdf_params = kdf.get(1).map(|p| components(p)).unwrap_or_default();
+    let salt = match kdf_params.first() {
+        Some(salt) if salt[0] == 0x04 => content_of(salt).unwrap_or_default(),
+        _ => return Err("PBKDF2 salt missing".to_string()),
+    };
+    let iterations = kdf_params
+        .get(1)
+        .and_then(|count| content_of(count))
+        .filter(|count| !count.is_empty() && count.len() <= 4)
+        .map(|count| count.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32))
+        .ok_or_else(|| "PBKDF2 iteration count missing".to_string())?;
+    if iterations == 0 || iterations > MAX_ITERATIONS {
+        return Err(format!(
+            "unreasonable PBKDF2 iteration count {}",
+            iterations
+        ));
+    }
+
+    // The PRF is the last component when present, defaulting to HMAC-SHA1
+    let prf = match kdf_params.iter().skip(2).find(|p| p[0] == 0x30) {
+        Some(prf) => {
+            let prf_oid = components(prf)
+                .first()
+                .and_then(|oid| oid_of(oid))
+                .unwrap_or_default();
+            match prf_oid.as_str() {
+                "1.2.840.113549.2.7" => HashAlg::Sha1,
+                "1.2.840.113549.2.9" => HashAlg::Sha256,
+                _ => return Err(format!("unsupported PBKDF2 PRF {}", describe(&prf_oid))),
+            }
+        }
+        None => HashAlg::Sha1,
+    };
+
+    let cipher_oid = cipher
+        .first()
+        .and_then(|oid| oid_of(oid))
+        .unwrap_or_default();
+    let key_len = match cipher_oid.as_str() {
+        "2.16.840.1.101.3.4.1.2" => 16,
+        "2.16.840.1.101.3.4.1.22" => 24,
+        "2.16.840.1.101.3.4.1.42" => 32,
+        _ => return Err(format!("unsupported cipher {}", describe(&cipher_oid))),
+    };
+    let iv = match cipher.get(1) {
+        Some(iv) if iv[0] == 0x04 => content_of(iv).unwrap_or_default(),
+        _ => return Err("cipher IV missing".to_string()),
+    };
+
+    let key = crypto::pbkdf2(prf, password.as_bytes(), salt, iterations, key_len)?;
+    crypto::aes_cbc_decrypt(&key, iv, ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_a_pfx() {
+        assert!(decrypt_layers(&[0x30, 0x03, 0x02, 0x01, 0x03], 0, "pw").is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_algorithm() {
+        // pbeWithSHAAnd3-KeyTripleDES-CBC with empty parameters
+        let algorithm = [
+            0x30, 0x0E, 0x06, 0x0A, 0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x0C, 0x01, 0x03,
+            0x30, 0x00,
+        ];
+        let err = decrypt(&algorithm, &[0u8; 16], "pw").expect_err("should be unsupported");
+        assert!(err.starts_with("unsupported encryption algorithm"), "{err}");
+    }
+}
@@ -0,0 +1,596 @@
+// RFC 5280 profile checks on certificates, for asn1cbor lint-cert. The
+// dumper checks how a certificate is encoded; these check what it says:
+// the serial number, the string types in its names, the extensions it must
+// have and how each must be marked. What RFC 5280 says a certificate MUST
+// or MUST NOT do is an error, and what it SHOULD do a warning.
+
+use super::der::{decode_oid, parse_tlv};
+use super::oids::oid_name;
+use super::tags::{
+    tag_name, BMPSTRING, BOOLEAN, INTEGER, OCTETSTRING, OID, T61STRING, UNIVERSALSTRING,
+    VIDEOTEXSTRING,
+};
+use super::x509::{self, components_at};
+use crate::diagnostics::{Diagnostic, Severity};
+
+// Longest serial number allowed, in octets (section 4.1.2.2)
+const MAX_SERIAL_LENGTH: usize = 20;
+
+const SUBJECT_KEY_IDENTIFIER: &str = "2.5.29.14";
+const KEY_USAGE: &str = "2.5.29.15";
+const SUBJECT_ALT_NAME: &str = "2.5.29.17";
+const BASIC_CONSTRAINTS: &str = "2.5.29.19";
+const AUTHORITY_KEY_IDENTIFIER: &str = "2.5.29.35";
+
+// The keyCertSign bit of keyUsage, in its first octet
+const KEY_CERT_SIGN: u8 = 0x04;
+
+/// How RFC 5280 says an extension is to be marked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marking {
+    Critical,
+    ShouldBeCritical,
+    NonCritical,
+}
+
+// The extensions marked the same way in every certificate. basicConstraints
+// and subjectAltName are critical only in some, and checked apart
+const MARKINGS: &[(&str, Marking)] = &[
+    (AUTHORITY_KEY_IDENTIFIER, Marking::NonCritical), // 4.2.1.1
+    (SUBJECT_KEY_IDENTIFIER, Marking::NonCritical),   // 4.2.1.2
+    (KEY_USAGE, Marking::ShouldBeCritical),           // 4.2.1.3
+    ("2.5.29.30", Marking::Critical),                 // nameConstraints, 4.2.1.10
+    ("2.5.29.36", Marking::Critical),                 // policyConstraints, 4.2.1.11
+    ("2.5.29.54", Marking::Critical),                 // inhibitAnyPolicy, 4.2.1.14
+];
+
+/// An extension of the certificate being checked
+struct Extension<'a> {
+    oid: String,
+    critical: bool,
+    value: Option<&'a [u8]>, // The extnValue content
+    offset: usize,
+}
+
+impl Extension<'_> {
+    fn name(&self) -> &str {
+        oid_name(&self.oid).unwrap_or(&self.oid)
+    }
+}
+
+/// Check a certificate encoding against the RFC 5280 profile, returning
+/// what it breaks with offsets into `data`
+pub fn lint_certificate(data: &[u8]) -> Vec<Diagnostic> {
+    let (Some(certificate), Some(&(tbs_offset, _))) =
+        (x509::parse_certificate(data), components_at(data).first())
+    else {
+        return vec![Diagnostic {
+            code: "not-a-certificate",
+            severity: Severity::Error,
+            offset: Some(0),
+            path: Vec::new(),
+            message: "not an X.509 certificate".to_string(),
+        }];
+    };
+    let mut linter = Linter::default();
+    let tbs: Vec<(usize, &[u8])> = components_at(certificate.tbs_certificate)
+        .into_iter()
+        .map(|(offset, field)| (tbs_offset + offset, field))
+        .collect();
+    // The version is v1 (0) unless [0] gives another
+    let (version, fields) = match tbs.first() {
+        Some((_, field)) if field[0] == 0xA0 => (version_number(field), &tbs[1..]),
+        _ => (Some(0), &tbs[..]),
+    };
+
+    let (serial_offset, serial) = fields[0];
+    linter.check_serial(serial_offset, serial);
+    let (issuer_offset, issuer) = fields[2];
+    let (subject_offset, subject) = fields[4];
+    if components_at(issuer).is_empty() {
+        let msg = "the issuer is an empty name, which RFC 5280 forbids".to_string();
+        linter.report(
+            Severity::Error,
+            "empty-issuer",
+            issuer_offset,
+            &["issuer"],
+            msg,
+        );
+    }
+    linter.check_name(issuer_offset, issuer, "issuer");
+    linter.check_name(subject_offset, subject, "subject");
+
+    let extensions_field = fields.iter().find(|(_, field)| field[0] == 0xA3);
+    if let (Some(&(offset, _)), false) = (extensions_field, version == Some(2)) {
+        let msg = "the certificate has extensions, which need version 3 (v3)".to_string();
+        linter.report(
+            Severity::Error,
+            "extensions-without-v3",
+            offset,
+            &["extensions"],
+            msg,
+        );
+    }
+    let (offset, extensions) = match extensions_field {
+        Some(&(offset, field)) => (offset, parse_extensions(offset, field)),
+        None => (tbs_offset, Vec::new()),
+    };
+    linter.check_extensions(&extensions, offset, &certificate);
+    linter.findings
+}
+
+/// The number in a [0] version field, 0 for v1 to 2 for v3
+fn version_number(field: &[u8]) -> Option<u8> {
+    let (_, integer) = *components_at(field).first()?;
+    match parse_tlv(integer)?.content(integer) {
+        [version] => Some(*version),
+        _ => None,
+    }
+}
+
+/// The extensions in the [3] extensions field at `offset`
+fn parse_extensions(offset: usize, field: &[u8]) -> Vec<Extension<'_>> {
+    let Some(&(list_offset, list)) = components_at(field).first() else {
+        return Vec::new();
+    };
+    fn content(encoding: &[u8]) -> Option<&[u8]> {
+        parse_tlv(encoding).map(|tlv| tlv.content(encoding))
+    }
+    let mut extensions = Vec::new();
+    for (extension_offset, extension) in components_at(list) {
+        let parts = components_at(extension);
+        let Some(oid) = parts
+            .first()
+            .filter(|(_, oid)| oid[0] == OID)
+            .and_then(|(_, oid)| decode_oid(content(oid)?))
+        else {
+            continue;
+        };
+        let critical = parts
+            .get(1)
+            .filter(|(_, flag)| flag[0] == BOOLEAN)
+            .and_then(|(_, flag)| content(flag))
+            .is_some_and(|flag| flag.iter().any(|&octet| octet != 0));
+        let value = parts
+            .last()
+            .filter(|(_, value)| value[0] == OCTETSTRING)
+            .and_then(|(_, value)| content(value));
+        extensions.push(Extension {
+            oid,
+            critical,
+            value,
+            offset: offset + list_offset + extension_offset,
+        });
+    }
+    extensions
+}
+
+#[derive(Default)]
+struct Linter {
+    findings: Vec<Diagnostic>,
+}
+
+impl Linter {
+    fn report(
+        &mut self,
+        severity: Severity,
+        code: &'static str,
+        offset: usize,
+        path: &[&str],
+        message: String,
+    ) {
+        let path = ["tbsCertificate"].iter().chain(path);
+        self.findings.push(Diagnostic {
+            code,
+            severity,
+            offset: Some(offset),
+            path: path.map(|name| name.to_string()).collect(),
+            message,
+        });
+    }
+
+    /// Check that the serial number is positive and at most 20 octets long
+    /// (section 4.1.2.2)
+    fn check_serial(&mut self, offset: usize, serial: &[u8]) {
+        let Some(tlv) = parse_tlv(serial).filter(|tlv| tlv.id == INTEGER) else {
+            return;
+        };
+        let content = tlv.content(serial);
+        let sign = if content.first().is_some_and(|&octet| octet & 0x80 != 0) {
+            Some("negative")
+        } else if content.iter().all(|&octet| octet == 0) {
+            Some("zero")
+        } else {
+            None
+        };
+        if let Some(sign) = sign {
+            let msg = format!(
+                "serialNumber is {}, where RFC 5280 requires a positive integer",
+                sign
+            );
+            self.report(
+                Severity::Error,
+                "serial-not-positive",
+                offset,
+                &["serialNumber"],
+                msg,
+            );
+        }
+        // A leading zero octet only keeps the number positive
+        let length = match content {
+            [0, rest @ ..] if !rest.is_empty() => rest.len(),
+            _ => content.len(),
+        };
+        if length > MAX_SERIAL_LENGTH {
+            let msg = format!(
+                "serialNumber is {} octets long, more than the {} RFC 5280 allows",
+                length, MAX_SERIAL_LENGTH
+            );
+            self.report(
+                Severity::Error,
+                "serial-too-long",
+                offset,
+                &["serialNumber"],
+                msg,
+            );
+        }
+    }
+
+    /// Warn of attribute values in a name that use the string types RFC 5280
+    /// keeps only for old certificates (section 4.1.2.4)
+    fn check_name(&mut self, offset: usize, name: &[u8], field: &'static str) {
+        for (rdn_offset, rdn) in components_at(name) {
+            for (attribute_offset, attribute) in components_at(rdn) {
+                let [(_, attribute_type), (value_offset, value)] = components_at(attribute)[..]
+                else {
+                    continue;
+                };
+                if attribute_type[0] != OID
+                    || !matches!(
+                        value[0],
+                        T61STRING | VIDEOTEXSTRING | UNIVERSALSTRING | BMPSTRING
+                    )
+                {
+                    continue;
+                }
+                let oid = parse_tlv(attribute_type)
+                    .and_then(|tlv| decode_oid(tlv.content(attribute_type)))
+                    .unwrap_or_default();
+                let label = x509::attribute_label(&oid)
+                    .or_else(|| oid_name(&oid))
+                    .unwrap_or(&oid);
+                let msg = format!(
+                    "{} in the {} is a {}, which RFC 5280 says new certificates shouldn't use \
+                     (PrintableString or UTF8String)",
+                    label,
+                    field,
+                    tag_name(value[0] as u32)
+                );
+                let offset = offset + rdn_offset + attribute_offset + value_offset;
+                self.report(
+                    Severity::Warning,
+                    "deprecated-string-type",
+                    offset,
+                    &[field],
+                    msg,
+                );
+            }
+        }
+    }
+
+    /// Check the extensions a certificate must have and how each is marked.
+    /// `offset` is that of the extensions field, or of tbsCertificate if
+    /// there isn't one
+    fn check_extensions(
+        &mut self,
+        extensions: &[Extension],
+        offset: usize,
+        certificate: &x509::Certificate,
+    ) {
+        let path = &["extensions"][..];
+        for (index, extension) in extensions.iter().enumerate() {
+            if extensions[..index]
+                .iter()
+                .any(|other| other.oid == extension.oid)
+            {
+                let msg = format!(
+                    "{} appears more than once, where RFC 5280 allows one of each extension",
+                    extension.name()
+                );
+                self.report(
+                    Severity::Error,
+                    "duplicate-extension",
+                    extension.offset,
+                    path,
+                    msg,
+                );
+            }
+            let marking = MARKINGS
+                .iter()
+                .find(|(oid, _)| *oid == extension.oid)
+                .map(|&(_, marking)| marking);
+            let (severity, code, rule) = match (marking, extension.critical) {
+                (Some(Marking::NonCritical), true) => {
+                    (Severity::Error, "critical-extension", "forbids")
+                }
+                (Some(Marking::Critical), false) => {
+                    (Severity::Error, "non-critical-extension", "requires")
+                }
+                (Some(Marking::ShouldBeCritical), false) => {
+                    (Severity::Warning, "non-critical-extension", "recommends")
+                }
+                _ => continue,
+            };
+            let msg = match extension.critical {
+                true => format!(
+                    "{} is marked critical, which RFC 5280 {}",
+                    extension.name(),
+                    rule
+                ),
+                false => format!(
+                    "{} isn't marked critical, as RFC 5280 {}",
+                    extension.name(),
+                    rule
+                ),
+            };
+            self.report(severity, code, extension.offset, path, msg);
+        }
+
+        let find = |oid: &str| extensions.iter().find(|extension| extension.oid == oid);
+        let basic_constraints = find(BASIC_CONSTRAINTS);
+        let is_ca = basic_constraints
+            .and_then(|extension| extension.value)
+            .is_some_and(asserts_ca);
+        let key_usage = find(KEY_USAGE);
+        let key_cert_sign = key_usage
+            .and_then(|extension| extension.value)
+            .and_then(|value| parse_tlv(value).map(|tlv| tlv.content(value)))
+            .is_some_and(|bits| bits.get(1).is_some_and(|&octet| octet & KEY_CERT_SIGN != 0));
+
+        // Section 4.2.1.9
+        if let Some(extension) = basic_constraints.filter(|extension| is_ca && !extension.critical)
+        {
+            let msg =
+                "basicConstraints isn't marked critical, as RFC 5280 requires in CA certificates"
+                    .to_string();
+            self.report(
+                Severity::Error,
+                "non-critical-extension",
+                extension.offset,
+                path,
+                msg,
+            );
+        }
+        // Section 4.2.1.3
+        if let (Some(extension), false) = (key_usage.filter(|_| key_cert_sign), is_ca) {
+            let msg = "keyUsage asserts keyCertSign, but basicConstraints doesn't make this \
+                       a CA certificate"
+                .to_string();
+            self.report(
+                Severity::Error,
+                "key-cert-sign-without-ca",
+                extension.offset,
+                path,
+                msg,
+            );
+        }
+        if is_ca && key_usage.is_none() {
+            let msg = "no keyUsage extension, which RFC 5280 requires in CA certificates";
+            self.report(
+                Severity::Error,
+                "missing-key-usage",
+                offset,
+                path,
+                msg.to_string(),
+            );
+        }
+        // Section 4.2.1.2
+        if find(SUBJECT_KEY_IDENTIFIER).is_none() {
+            let (severity, msg) = match is_ca {
+                true => (Severity::Error, "requires in CA certificates"),
+                false => (Severity::Warning, "recommends in end-entity certificates"),
+            };
+            let msg = format!("no subjectKeyIdentifier extension, which RFC 5280 {}", msg);
+            self.report(severity, "missing-ski", offset, path, msg);
+        }
+        // Section 4.2.1.1
+        if find(AUTHORITY_KEY_IDENTIFIER).is_none() && certificate.issuer != certificate.subject {
+            let msg = "no authorityKeyIdentifier extension, which RFC 5280 requires in all but \
+                       self-signed certificates";
+            self.report(
+                Severity::Error,
+                "missing-aki",
+                offset,
+                path,
+                msg.to_string(),
+            );
+        }
+        // Sections 4.1.2.6 and 4.2.1.6
+        if components_at(certificate.subject).is_empty() {
+            match find(SUBJECT_ALT_NAME) {
+                None => {
+                    let msg = "the subject is empty, so RFC 5280 requires a subjectAltName \
+                               extension";
+                    let code = "missing-subject-alt-name";
+                    self.report(Severity::Error, code, offset, path, msg.to_string());
+                }
+                Some(extension) if !extension.critical => {
+                    let msg = "subjectAltName isn't marked critical, as RFC 5280 requires when \
+                               the subject is empty";
+                    let code = "non-critical-extension";
+                    self.report(
+                        Severity::Error,
+                        code,
+                        extension.offset,
+                        path,
+                        msg.to_string(),
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+/// Whether a basicConstraints value has cA set
+fn asserts_ca(value: &[u8]) -> bool {
+    components_at(value).first().is_some_and(|&(_, flag)| {
+        flag[0] == BOOLEAN
+            && parse_tlv(flag).is_some_and(|tlv| tlv.content(flag).iter().any(|&octet| octet != 0))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testutil::Certificate;
+    use crate::der::DerBuilder;
+
+    fn extension(b: DerBuilder, oid: &str, critical: bool, value: &[u8]) -> DerBuilder {
+        b.sequence(|b| {
+            let b = b.oid(oid);
+            let b = if critical { b.boolean(true) } else { b };
+            b.octet_string(value)
+        })
+    }
+
+    fn codes(data: &[u8]) -> Vec<&'static str> {
+        lint_certificate(data)
+            .iter()
+            .map(|finding| finding.code)
+            .collect()
+    }
+
+    #[test]
+    fn test_serial_number() {
+        let ski = |b| extension(b, SUBJECT_KEY_IDENTIFIER, false, &[0x04, 0x01, 0x01]);
+        let cert = |serial| {
+            Certificate {
+                serial,
+                extensions: Some(&ski),
+                ..Certificate::default()
+            }
+            .build()
+        };
+        assert!(codes(&cert(&[0x01])).is_empty());
+        assert!(codes(&cert(&[0x00, 0x80])).is_empty());
+        assert_eq!(codes(&cert(&[0xFF])), ["serial-not-positive"]);
+        assert_eq!(codes(&cert(&[0x00])), ["serial-not-positive"]);
+        let mut long = vec![0x00];
+        long.extend([0x80; 20]);
+        assert!(codes(&cert(&long)).is_empty());
+        assert_eq!(codes(&cert(&[0x01; 21])), ["serial-too-long"]);
+
+        let findings = lint_certificate(&cert(&[0xFF]));
+        // After the certificate and tbsCertificate headers and the version
+        assert_eq!(findings[0].offset, Some(3 + 3 + 5));
+        assert_eq!(findings[0].path, ["tbsCertificate", "serialNumber"]);
+        assert_eq!(
+            findings[0].message,
+            "serialNumber is negative, where RFC 5280 requires a positive integer"
+        );
+    }
+
+    #[test]
+    fn test_extensions() {
+        // A CA certificate with a non-critical basicConstraints, a critical
+        // authorityKeyIdentifier and no keyUsage or subjectKeyIdentifier
+        let ca = |b| {
+            let b = extension(b, BASIC_CONSTRAINTS, false, &[0x30, 0x03, 0x01, 0x01, 0xFF]);
+            extension(b, AUTHORITY_KEY_IDENTIFIER, true, &[0x30, 0x00])
+        };
+        let cert = Certificate {
+            issuer: Some("Root"),
+            subject: Some("Sub CA"),
+            extensions: Some(&ca),
+            ..Certificate::default()
+        }
+        .build();
+        assert_eq!(
+            codes(&cert),
+            [
+                "critical-extension",
+                "non-critical-extension",
+                "missing-key-usage",
+                "missing-ski",
+            ]
+        );
+        let findings = lint_certificate(&cert);
+        assert_eq!(
+            findings[0].message,
+            "authorityKeyIdentifier is marked critical, which RFC 5280 forbids"
+        );
+        assert_eq!(findings[3].severity, Severity::Error);
+
+        // An end-entity certificate with keyCertSign, a repeated keyUsage
+        // that isn't critical, and an empty subject without subjectAltName
+        let end_entity = |b| {
+            let b = extension(b, KEY_USAGE, true, &[0x03, 0x02, 0x02, 0x04]);
+            extension(b, KEY_USAGE, false, &[0x03, 0x02, 0x07, 0x80])
+        };
+        let cert = Certificate {
+            issuer: Some("CA"),
+            subject: None,
+            extensions: Some(&end_entity),
+            ..Certificate::default()
+        }
+        .build();
+        assert_eq!(
+            codes(&cert),
+            [
+                "duplicate-extension",
+                "non-critical-extension",
+                "key-cert-sign-without-ca",
+                "missing-ski",
+                "missing-aki",
+                "missing-subject-alt-name",
+            ]
+        );
+        let findings = lint_certificate(&cert);
+        assert_eq!(findings[1].severity, Severity::Warning);
+        assert_eq!(findings[3].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_names_and_version() {
+        // No extensions, so no subjectKeyIdentifier either, in a v3
+        // certificate with an empty issuer
+        let cert = Certificate {
+            issuer: None,
+            ..Certificate::default()
+        }
+        .build();
+        assert_eq!(codes(&cert), ["empty-issuer", "missing-ski", "missing-aki"]);
+
+        let bmp_name = DerBuilder::new()
+            .sequence(|b| {
+                b.set(|b| b.sequence(|b| b.oid("2.5.4.3").primitive(BMPSTRING, &[0x00, 0x41])))
+            })
+            .finish();
+        let mut linter = Linter::default();
+        linter.check_name(100, &bmp_name, "subject");
+        assert_eq!(linter.findings[0].code, "deprecated-string-type");
+        assert_eq!(linter.findings[0].offset, Some(100 + 11));
+        assert_eq!(
+            linter.findings[0].message,
+            "CN in the subject is a BMPString, which RFC 5280 says new certificates shouldn't \
+             use (PrintableString or UTF8String)"
+        );
+
+        // Extensions in a v1 certificate
+        let ski = |b| extension(b, SUBJECT_KEY_IDENTIFIER, false, &[0x04, 0x01, 0x01]);
+        let mut cert = Certificate {
+            extensions: Some(&ski),
+            ..Certificate::default()
+        }
+        .build();
+        let version = cert
+            .windows(5)
+            .position(|window| window == [0xA0, 0x03, 0x02, 0x01, 0x02])
+            .unwrap();
+        cert[version + 4] = 0;
+        assert_eq!(codes(&cert), ["extensions-without-v3"]);
+
+        assert_eq!(codes(&[0x30, 0x00]), ["not-a-certificate"]);
+    }
+}
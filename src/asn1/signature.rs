@@ -0,0 +1,173 @@
+// Checking a certificate's signature with the public key of its issuer's
+// certificate. Unlike the primitives in crypto.rs this uses ring, for the
+// RSA, ECDSA and Ed25519 signatures found in certificates, so it's only
+// built with the crypto feature; without it every signature is left
+// unchecked, with a note of the feature that would check it.
+
+use super::x509::{self, Certificate};
+
+/// How checking a certificate's signature came out
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "crypto"), allow(dead_code))]
+pub enum Verification {
+    Valid,
+    Invalid,
+    Unchecked(String), // Why it couldn't be checked
+}
+
+/// Check the signature of `certificate` over its tbsCertificate with the
+/// subject public key of `issuer`
+pub fn verify_certificate(certificate: &Certificate, issuer: &Certificate) -> Verification {
+    let Some(signature) = x509::bit_string_bytes(certificate.signature_value) else {
+        return Verification::Unchecked("the signature isn't a whole number of bytes".to_string());
+    };
+    let Some((key_algorithm, key)) = x509::public_key(issuer.subject_public_key_info) else {
+        return Verification::Unchecked(
+            "the issuer's subjectPublicKeyInfo can't be read".to_string(),
+        );
+    };
+    let Some(algorithm) = x509::algorithm_oid(certificate.signature_algorithm) else {
+        return Verification::Unchecked("the signature algorithm can't be read".to_string());
+    };
+    let key_type = x509::algorithm_oid(key_algorithm).unwrap_or_default();
+    let curve = x509::parameters_oid(key_algorithm);
+    verify(
+        &algorithm,
+        &key_type,
+        curve.as_deref(),
+        key,
+        certificate.tbs_certificate,
+        signature,
+    )
+}
+
+#[cfg(feature = "crypto")]
+fn verify(
+    algorithm: &str,
+    key_type: &str,
+    curve: Option<&str>,
+    key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Verification {
+    use ring::signature::{self as ring_signature, UnparsedPublicKey, VerificationAlgorithm};
+
+    const RSA: &str = "1.2.840.113549.1.1.1";
+    const EC: &str = "1.2.840.10045.2.1";
+    const P256: &str = "1.2.840.10045.3.1.7";
+    const P384: &str = "1.3.132.0.34";
+    const ED25519: &str = "1.3.101.112";
+
+    let verifier: &dyn VerificationAlgorithm = match (algorithm, key_type, curve) {
+        ("1.2.840.113549.1.1.5", RSA, _) => {
+            &ring_signature::RSA_PKCS1_2048_8192_SHA1_FOR_LEGACY_USE_ONLY
+        }
+        ("1.2.840.113549.1.1.11", RSA, _) => &ring_signature::RSA_PKCS1_2048_8192_SHA256,
+        ("1.2.840.113549.1.1.12", RSA, _) => &ring_signature::RSA_PKCS1_2048_8192_SHA384,
+        ("1.2.840.113549.1.1.13", RSA, _) => &ring_signature::RSA_PKCS1_2048_8192_SHA512,
+        ("1.2.840.10045.4.3.2", EC, Some(P256)) => &ring_signature::ECDSA_P256_SHA256_ASN1,
+        ("1.2.840.10045.4.3.3", EC, Some(P256)) => &ring_signature::ECDSA_P256_SHA384_ASN1,
+        ("1.2.840.10045.4.3.2", EC, Some(P384)) => &ring_signature::ECDSA_P384_SHA256_ASN1,
+        ("1.2.840.10045.4.3.3", EC, Some(P384)) => &ring_signature::ECDSA_P384_SHA384_ASN1,
+        (ED25519, ED25519, _) => &ring_signature::ED25519,
+        _ => {
+            return Verification::Unchecked(format!(
+                "{} signatures with {} keys aren't supported",
+                name(algorithm),
+                key_name(key_type, curve)
+            ))
+        }
+    };
+    match UnparsedPublicKey::new(verifier, key).verify(message, signature) {
+        Ok(()) => Verification::Valid,
+        Err(_) => Verification::Invalid,
+    }
+}
+
+#[cfg(not(feature = "crypto"))]
+fn verify(
+    _algorithm: &str,
+    _key_type: &str,
+    _curve: Option<&str>,
+    _key: &[u8],
+    _message: &[u8],
+    _signature: &[u8],
+) -> Verification {
+    Verification::Unchecked(
+        "checking signatures needs a build with the crypto feature \
+         (cargo install --features crypto)"
+            .to_string(),
+    )
+}
+
+/// The name of an algorithm OID, or the OID
+#[cfg(feature = "crypto")]
+fn name(oid: &str) -> String {
+    super::oids::oid_name(oid).map_or_else(|| oid.to_string(), str::to_string)
+}
+
+/// The name of a key type, with its curve if it has one
+#[cfg(feature = "crypto")]
+fn key_name(key_type: &str, curve: Option<&str>) -> String {
+    match curve {
+        Some(curve) => format!("{} {}", name(key_type), name(curve)),
+        None => name(key_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testutil::Certificate;
+
+    /// A certificate for the Ed25519 public key `key`, with the signature
+    /// `sign` gives for its tbsCertificate
+    fn certificate(key: &[u8], sign: impl Fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+        Certificate {
+            key,
+            ..Certificate::default()
+        }
+        .signed(sign)
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn test_verify_certificate() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let key = key_pair.public_key().as_ref().to_vec();
+        let cert = certificate(&key, |tbs| key_pair.sign(tbs).as_ref().to_vec());
+        let parsed = x509::parse_certificate(&cert).unwrap();
+        assert_eq!(verify_certificate(&parsed, &parsed), Verification::Valid);
+
+        let forged = certificate(&key, |_| vec![0; 64]);
+        let forged = x509::parse_certificate(&forged).unwrap();
+        assert_eq!(verify_certificate(&forged, &parsed), Verification::Invalid);
+
+        assert_eq!(
+            verify(
+                "1.2.840.113549.1.1.10",
+                "1.2.840.113549.1.1.1",
+                None,
+                &[],
+                &[],
+                &[]
+            ),
+            Verification::Unchecked(
+                "rsassa-pss signatures with rsaEncryption keys aren't supported".to_string()
+            )
+        );
+    }
+
+    #[cfg(not(feature = "crypto"))]
+    #[test]
+    fn test_verify_certificate() {
+        let cert = certificate(&[0; 32], |_| vec![0; 64]);
+        let parsed = x509::parse_certificate(&cert).unwrap();
+        assert!(matches!(
+            verify_certificate(&parsed, &parsed),
+            Verification::Unchecked(reason) if reason.contains("--features crypto")
+        ));
+    }
+}
@@ -0,0 +1,648 @@
+// A plausible ASN.1 module for DER samples, as codegen writes it: SEQUENCE,
+// SET and CHOICE types guessed from what the samples hold, as a start on
+// documenting a format that has no module of its own. Components keep the
+// field names of a recognized structure, such as a certificate, and are
+// numbered otherwise. A SET or SEQUENCE whose elements all have the same
+// tag is taken to be a SET OF or SEQUENCE OF, and a constructed tag holding
+// a single item to be an EXPLICIT one. Across samples, components are lined
+// up by their tags, those missing from some samples becoming OPTIONAL, and
+// items of different types at the same place become a CHOICE.
+
+use std::collections::{HashMap, HashSet};
+
+use super::der::{children, looks_encapsulated, parse_tlv, Tlv};
+use super::tags::*;
+use super::templates::{self, Annotation};
+
+/// Maximum nesting depth followed when inferring types
+const MAX_DEPTH: usize = 64;
+
+/// What the samples of an item had in common
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Unknown,        // No samples, as for the elements of an empty SEQUENCE OF
+    Primitive(u32), // A universal type, by tag number
+    Sequence(Vec<Component>),
+    Set(Vec<Component>),
+    SequenceOf(Box<Type>),
+    SetOf(Box<Type>),
+    Tagged {
+        class: u8, // APPLICATION, CONTEXT or PRIVATE
+        number: u32,
+        explicit: bool,
+        inner: Box<Type>,
+    },
+    Containing(u32, Box<Type>), // OCTET STRING or BIT STRING holding DER
+    Choice(Vec<Type>),
+}
+
+/// A component of a SEQUENCE or SET
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    pub name: Option<String>, // From a recognized structure
+    pub ty: Type,
+    pub optional: bool, // Missing from some samples
+}
+
+impl Type {
+    /// The type of the DER items in `data` taken as samples of the same
+    /// type, their components named where the structure is recognized
+    pub fn infer_all(data: &[u8]) -> Type {
+        let mut annotations = HashMap::new();
+        for (pos, tlv) in children(data) {
+            let item = &data[pos..pos + tlv.total_len()];
+            if let Some(template) = templates::recognize(item) {
+                templates::annotate(item, pos, template, &mut annotations);
+            }
+        }
+        let inference = Inference { annotations };
+        children(data)
+            .map(|(pos, tlv)| inference.infer(data, pos, tlv, 0))
+            .fold(Type::Unknown, Type::merge)
+    }
+
+    /// The class and number of the tag this type is encoded with, None for
+    /// a CHOICE, whose tag is that of its alternative
+    fn tag(&self) -> Option<(u8, u32)> {
+        match self {
+            Type::Unknown | Type::Choice(_) => None,
+            Type::Primitive(tag) | Type::Containing(tag, _) => Some((UNIVERSAL, *tag)),
+            Type::Sequence(_) | Type::SequenceOf(_) => Some((UNIVERSAL, SEQUENCE as u32)),
+            Type::Set(_) | Type::SetOf(_) => Some((UNIVERSAL, SET as u32)),
+            Type::Tagged { class, number, .. } => Some((*class, *number)),
+        }
+    }
+
+    /// What this type and `other` have in common
+    pub fn merge(self, other: Type) -> Type {
+        use Type::*;
+        match (self, other) {
+            (Unknown, ty) | (ty, Unknown) => ty,
+            (Choice(alternatives), ty) | (ty, Choice(alternatives)) => {
+                Choice(add_alternative(alternatives, ty))
+            }
+            (Sequence(a), Sequence(b)) => Sequence(merge_components(a, b)),
+            (Set(a), Set(b)) => Set(merge_components(a, b)),
+            (SequenceOf(a), SequenceOf(b)) => SequenceOf(Box::new(a.merge(*b))),
+            (SetOf(a), SetOf(b)) => SetOf(Box::new(a.merge(*b))),
+            (SequenceOf(element), Sequence(components))
+            | (Sequence(components), SequenceOf(element)) => SequenceOf(Box::new(
+                components
+                    .into_iter()
+                    .map(|c| c.ty)
+                    .fold(*element, Type::merge),
+            )),
+            (SetOf(element), Set(components)) | (Set(components), SetOf(element)) => {
+                SetOf(Box::new(
+                    components
+                        .into_iter()
+                        .map(|c| c.ty)
+                        .fold(*element, Type::merge),
+                ))
+            }
+            (Containing(a, x), Containing(b, y)) if a == b => Containing(a, Box::new(x.merge(*y))),
+            (Containing(a, _), Primitive(b)) | (Primitive(b), Containing(a, _)) if a == b => {
+                Primitive(a)
+            }
+            (
+                Tagged {
+                    class,
+                    number,
+                    explicit: x_explicit,
+                    inner: x,
+                },
+                Tagged {
+                    class: y_class,
+                    number: y_number,
+                    explicit: y_explicit,
+                    inner: y,
+                },
+            ) if (class, number) == (y_class, y_number) => {
+                // A tag holding one item in some samples and several in
+                // others is an IMPLICIT SEQUENCE
+                let implicit = |explicit: bool, inner: Box<Type>| match explicit {
+                    true => Sequence(vec![Component {
+                        name: None,
+                        ty: *inner,
+                        optional: false,
+                    }]),
+                    false => *inner,
+                };
+                let explicit = x_explicit && y_explicit;
+                let inner = match explicit {
+                    true => x.merge(*y),
+                    false => implicit(x_explicit, x).merge(implicit(y_explicit, y)),
+                };
+                Tagged {
+                    class,
+                    number,
+                    explicit,
+                    inner: Box::new(inner),
+                }
+            }
+            (a, b) if a == b => a,
+            (a, b) if a.tag() == b.tag() => a, // Only for an unknown universal tag
+            (a, b) => Choice(vec![a, b]),
+        }
+    }
+}
+
+/// The alternatives of a CHOICE with those of `ty` added, each merged with
+/// the one of the same tag if there's one
+fn add_alternative(mut alternatives: Vec<Type>, ty: Type) -> Vec<Type> {
+    if let Type::Choice(more) = ty {
+        return more.into_iter().fold(alternatives, add_alternative);
+    }
+    match alternatives.iter().position(|a| a.tag() == ty.tag()) {
+        Some(index) => {
+            let existing = alternatives.remove(index);
+            alternatives.insert(index, existing.merge(ty));
+        }
+        None => alternatives.push(ty),
+    }
+    alternatives
+}
+
+/// The field names of recognized structures, by offset
+struct Inference {
+    annotations: HashMap<usize, Annotation>,
+}
+
+impl Inference {
+    /// The type of the item `tlv` at `pos` in `data`
+    fn infer(&self, data: &[u8], pos: usize, tlv: Tlv, depth: usize) -> Type {
+        let content_offset = pos + tlv.header_len;
+        let content = &data[content_offset..pos + tlv.total_len()];
+        if depth > MAX_DEPTH {
+            return Type::Unknown;
+        }
+        let components = || -> Vec<Component> {
+            children(content)
+                .map(|(child_pos, child)| Component {
+                    name: self
+                        .annotations
+                        .get(&(content_offset + child_pos))
+                        .map(|a| a.name.to_string()),
+                    ty: self.infer(data, content_offset + child_pos, child, depth + 1),
+                    optional: false,
+                })
+                .collect()
+        };
+
+        let class = tlv.id & CLASS_MASK;
+        if class != UNIVERSAL {
+            let inner = match tlv.is_constructed() {
+                false => Type::Primitive(OCTETSTRING as u32),
+                true => {
+                    let mut components = components();
+                    if components.len() == 1 {
+                        return Type::Tagged {
+                            class,
+                            number: tlv.tag,
+                            explicit: true,
+                            inner: Box::new(components.remove(0).ty),
+                        };
+                    }
+                    Type::Sequence(components)
+                }
+            };
+            return Type::Tagged {
+                class,
+                number: tlv.tag,
+                explicit: false,
+                inner: Box::new(inner),
+            };
+        }
+
+        match u8::try_from(tlv.tag) {
+            Ok(SEQUENCE | SET) if tlv.is_constructed() => {
+                let set = tlv.tag == SET as u32;
+                let components = components();
+                // Elements alike, which different field names rule out
+                let tags: HashSet<_> = components.iter().map(|c| c.ty.tag()).collect();
+                let names: HashSet<_> = components.iter().map(|c| &c.name).collect();
+                let alike = tags.len() == 1 && names.len() == 1 && (components.len() > 1 || set);
+                match (set, alike) {
+                    (false, false) => Type::Sequence(components),
+                    (true, false) => Type::Set(components),
+                    (false, true) => Type::SequenceOf(Box::new(element_type(components))),
+                    (true, true) => Type::SetOf(Box::new(element_type(components))),
+                }
+            }
+            Ok(OCTETSTRING | BITSTRING) if !tlv.is_constructed() => {
+                // BIT STRINGs carry an unused-bits octet before the wrapped data
+                let skip = usize::from(tlv.tag == BITSTRING as u32);
+                let wrapped = content.get(skip..).unwrap_or_default();
+                match parse_tlv(wrapped) {
+                    Some(inner) if content.len() > skip && looks_encapsulated(wrapped) => {
+                        Type::Containing(
+                            tlv.tag,
+                            Box::new(self.infer(data, content_offset + skip, inner, depth + 1)),
+                        )
+                    }
+                    _ => Type::Primitive(tlv.tag),
+                }
+            }
+            _ => Type::Primitive(tlv.tag),
+        }
+    }
+}
+
+/// What the elements of a SEQUENCE OF or SET OF have in common
+fn element_type(components: Vec<Component>) -> Type {
+    components
+        .into_iter()
+        .map(|c| c.ty)
+        .fold(Type::Unknown, Type::merge)
+}
+
+/// Whether two components are samples of the same one: they have the same
+/// field name, or else the same tag
+fn same_component(a: &Component, b: &Component) -> bool {
+    match (&a.name, &b.name) {
+        (Some(x), Some(y)) => x == y,
+        _ => a.ty.tag().is_some() && a.ty.tag() == b.ty.tag(),
+    }
+}
+
+/// The components of two samples of a SEQUENCE or SET, lined up by the
+/// longest run they have in common, the rest being OPTIONAL
+fn merge_components(a: Vec<Component>, b: Vec<Component>) -> Vec<Component> {
+    // Longest common subsequence, counted from the ends
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if same_component(&a[i], &b[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let optional = |c: Component| Component {
+        optional: true,
+        ..c
+    };
+    let mut merged = Vec::new();
+    let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+    let (mut i, mut j) = (0, 0);
+    while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+        if same_component(x, y) && lengths[i][j] == lengths[i + 1][j + 1] + 1 {
+            let (x, y) = (a.next().unwrap(), b.next().unwrap());
+            merged.push(Component {
+                name: x.name.or(y.name),
+                ty: x.ty.merge(y.ty),
+                optional: x.optional || y.optional,
+            });
+            (i, j) = (i + 1, j + 1);
+        } else if lengths[i][j] == lengths[i + 1][j + 1] {
+            // Neither is part of the common run, so they're taken to be
+            // samples of a component whose type varies
+            let (x, y) = (a.next().unwrap(), b.next().unwrap());
+            merged.push(Component {
+                name: x.name.or(y.name),
+                ty: x.ty.merge(y.ty),
+                optional: x.optional || y.optional,
+            });
+            (i, j) = (i + 1, j + 1);
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            merged.extend(a.next().map(optional));
+            i += 1;
+        } else {
+            merged.extend(b.next().map(optional));
+            j += 1;
+        }
+    }
+    merged.extend(a.map(optional));
+    merged.extend(b.map(optional));
+    merged
+}
+
+/// ASN.1 source for `ty` as a type called `name`, in a module of that name,
+/// with the types it needs defined after it
+pub fn asn1_module(ty: &Type, name: &str) -> String {
+    let mut writer = ModuleWriter::default();
+    match ty {
+        Type::Sequence(_) | Type::Set(_) | Type::Choice(_) => {
+            writer.define(ty, name);
+        }
+        _ => {
+            writer.definitions.push(None);
+            let reference = writer.reference(ty, name);
+            writer.definitions[0] = Some(format!("{} ::= {}\n", name, reference));
+        }
+    }
+
+    let mut module = format!("{}Module DEFINITIONS ::= BEGIN\n", name);
+    for definition in writer.definitions.iter().flatten() {
+        module.push('\n');
+        module.push_str(definition);
+    }
+    module.push_str("\nEND\n");
+    module
+}
+
+#[derive(Default)]
+struct ModuleWriter {
+    definitions: Vec<Option<String>>, // None where a definition was the same as another
+    type_names: HashSet<String>,
+    bodies: HashMap<String, String>, // Name of the type defined as each
+}
+
+impl ModuleWriter {
+    /// How to refer to `ty`, defining any type it needs named after `hint`
+    fn reference(&mut self, ty: &Type, hint: &str) -> String {
+        match ty {
+            Type::Unknown => "ANY".to_string(),
+            Type::Primitive(tag) => match tag_name(*tag) {
+                "Unknown" => format!("[UNIVERSAL {}] IMPLICIT OCTET STRING", tag),
+                name => name.to_string(),
+            },
+            Type::Sequence(_) | Type::Set(_) | Type::Choice(_) => self.define(ty, hint),
+            Type::SequenceOf(element) => {
+                format!("SEQUENCE OF {}", self.reference(element, &item_hint(hint)))
+            }
+            Type::SetOf(element) => format!("SET OF {}", self.reference(element, &item_hint(hint))),
+            Type::Tagged {
+                class,
+                number,
+                explicit,
+                inner,
+            } => {
+                let class = match *class {
+                    APPLICATION => "APPLICATION ",
+                    PRIVATE => "PRIVATE ",
+                    _ => "",
+                };
+                // A CHOICE can't be tagged implicitly
+                let explicit = *explicit || matches!(**inner, Type::Choice(_));
+                format!(
+                    "[{}{}] {} {}",
+                    class,
+                    number,
+                    if explicit { "EXPLICIT" } else { "IMPLICIT" },
+                    self.reference(inner, hint)
+                )
+            }
+            Type::Containing(tag, inner) => format!(
+                "{} (CONTAINING {})",
+                tag_name(*tag),
+                self.reference(inner, hint)
+            ),
+        }
+    }
+
+    /// Define a SEQUENCE, SET or CHOICE named after `hint`, or find the one
+    /// defined the same way already, returning its name
+    fn define(&mut self, ty: &Type, hint: &str) -> String {
+        let name = unique(hint.to_string(), &mut self.type_names);
+        // The type goes before those of its components
+        let index = self.definitions.len();
+        self.definitions.push(None);
+
+        let (keyword, lines) = match ty {
+            Type::Sequence(components) => ("SEQUENCE", self.component_lines(components, &name)),
+            Type::Set(components) => ("SET", self.component_lines(components, &name)),
+            Type::Choice(alternatives) => ("CHOICE", self.alternative_lines(alternatives, &name)),
+            _ => unreachable!("only structured types are defined"),
+        };
+        let width = lines.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        let lines: Vec<String> = lines
+            .iter()
+            .map(|(name, ty)| format!("    {:width$}  {}", name, ty, width = width))
+            .collect();
+        let body = match lines.is_empty() {
+            true => format!("{} {{ }}", keyword),
+            false => format!("{} {{\n{}\n}}", keyword, lines.join(",\n")),
+        };
+
+        if let Some(existing) = self.bodies.get(&body) {
+            self.type_names.remove(&name);
+            return existing.clone();
+        }
+        self.definitions[index] = Some(format!("{} ::= {}\n", name, body));
+        self.bodies.insert(body, name.clone());
+        name
+    }
+
+    /// The identifier and type of each component of a SEQUENCE or SET
+    fn component_lines(&mut self, components: &[Component], parent: &str) -> Vec<(String, String)> {
+        let mut names = HashSet::new();
+        let mut lines = Vec::new();
+        for (i, component) in components.iter().enumerate() {
+            let name = match &component.name {
+                Some(name) => identifier(name),
+                None => format!("field{}", i + 1),
+            };
+            let name = unique(name, &mut names);
+            let hint = match &component.name {
+                Some(_) => type_reference(&name),
+                None => format!("{}{}", parent, type_reference(&name)),
+            };
+            let mut ty = self.reference(&component.ty, &hint);
+            if component.optional {
+                ty.push_str(" OPTIONAL");
+            }
+            lines.push((name, ty));
+        }
+        lines
+    }
+
+    /// The identifier and type of each alternative of a CHOICE, named after
+    /// their tags
+    fn alternative_lines(&mut self, alternatives: &[Type], parent: &str) -> Vec<(String, String)> {
+        let mut names = HashSet::new();
+        let mut lines = Vec::new();
+        for alternative in alternatives {
+            let name = match alternative.tag() {
+                Some((UNIVERSAL, tag)) => identifier(tag_name(tag)),
+                Some((APPLICATION, number)) => format!("application{}", number),
+                Some((PRIVATE, number)) => format!("private{}", number),
+                Some((_, number)) => format!("context{}", number),
+                None => "alternative".to_string(),
+            };
+            let name = unique(name, &mut names);
+            let hint = format!("{}{}", parent, type_reference(&name));
+            lines.push((name.clone(), self.reference(alternative, &hint)));
+        }
+        lines
+    }
+}
+
+/// `name`, or it with a number after it if that's taken, adding it to
+/// `taken`
+fn unique(name: String, taken: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut number = 2;
+    while taken.contains(&candidate) {
+        candidate = format!("{}{}", name, number);
+        number += 1;
+    }
+    taken.insert(candidate.clone());
+    candidate
+}
+
+/// The name for the elements of a SEQUENCE OF or SET OF
+fn item_hint(hint: &str) -> String {
+    match hint.ends_with("Item") {
+        true => hint.to_string(),
+        false => format!("{}Item", hint),
+    }
+}
+
+/// An ASN.1 identifier, which starts with a lowercase letter and has only
+/// letters and digits here, in lower camel case: tbsCertificate, or
+/// objectIdentifier for OBJECT IDENTIFIER
+fn identifier(name: &str) -> String {
+    let mut identifier = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let Some(first) = word.chars().next() else {
+            continue;
+        };
+        if identifier.is_empty() {
+            if first.is_ascii_digit() {
+                identifier.push_str("field");
+            }
+            // The capitals it starts with, bar one that starts a word: utf8String
+            let capitals = word
+                .find(|c: char| c.is_ascii_lowercase())
+                .map_or(word.len(), |end| end.saturating_sub(1).max(1));
+            identifier.push_str(&word[..capitals].to_ascii_lowercase());
+            identifier.push_str(&word[capitals..]);
+        } else if word.chars().any(|c| c.is_ascii_lowercase()) {
+            identifier.push(first.to_ascii_uppercase());
+            identifier.push_str(&word[1..]);
+        } else {
+            identifier.push(first.to_ascii_uppercase());
+            identifier.push_str(&word[1..].to_ascii_lowercase());
+        }
+    }
+    match identifier.is_empty() {
+        true => "field".to_string(),
+        false => identifier,
+    }
+}
+
+/// A type reference made from an identifier: the same with a capital first
+fn type_reference(identifier: &str) -> String {
+    let mut chars = identifier.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::DerBuilder;
+
+    #[test]
+    fn test_identifiers() {
+        assert_eq!(identifier("tbsCertificate"), "tbsCertificate");
+        assert_eq!(identifier("OBJECT IDENTIFIER"), "objectIdentifier");
+        assert_eq!(identifier("UTF8String"), "utf8String");
+        assert_eq!(identifier("INTEGER"), "integer");
+        assert_eq!(identifier("2nd"), "field2nd");
+        assert_eq!(identifier("--"), "field");
+        assert_eq!(type_reference("field3"), "Field3");
+    }
+
+    #[test]
+    fn test_module() {
+        // SEQUENCE { INTEGER, [0] { UTF8String }, OCTET STRING { SEQUENCE {
+        // BOOLEAN } }, SET { OID, OID } }, then one without the [0]
+        let encapsulated = DerBuilder::new().sequence(|b| b.boolean(true)).finish();
+        let data = DerBuilder::new()
+            .sequence(|b| {
+                b.integer(1)
+                    .explicit(0, |b| b.utf8_string("a"))
+                    .octet_string(&encapsulated)
+                    .set(|b| b.oid("1.2.3").oid("1.2.4"))
+            })
+            .sequence(|b| {
+                b.integer(2)
+                    .octet_string(&encapsulated)
+                    .set(|b| b.oid("1.2.5"))
+            })
+            .finish();
+        assert_eq!(
+            asn1_module(&Type::infer_all(&data), "Message"),
+            "MessageModule DEFINITIONS ::= BEGIN\n\
+             \n\
+             Message ::= SEQUENCE {\n\
+             \x20   field1  INTEGER,\n\
+             \x20   field2  [0] EXPLICIT UTF8String OPTIONAL,\n\
+             \x20   field3  OCTET STRING (CONTAINING MessageField3),\n\
+             \x20   field4  SET OF OBJECT IDENTIFIER\n\
+             }\n\
+             \n\
+             MessageField3 ::= SEQUENCE {\n\
+             \x20   field1  BOOLEAN\n\
+             }\n\
+             \n\
+             END\n"
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        // An INTEGER in one sample and text in another is a CHOICE
+        let data = DerBuilder::new()
+            .sequence(|b| b.integer(1))
+            .sequence(|b| b.utf8_string("a"))
+            .sequence(|b| b.implicit(1, |b| b.null()))
+            .finish();
+        assert_eq!(
+            asn1_module(&Type::infer_all(&data), "Value"),
+            "ValueModule DEFINITIONS ::= BEGIN\n\
+             \n\
+             Value ::= SEQUENCE {\n\
+             \x20   field1  ValueField1\n\
+             }\n\
+             \n\
+             ValueField1 ::= CHOICE {\n\
+             \x20   integer     INTEGER,\n\
+             \x20   utf8String  UTF8String,\n\
+             \x20   context1    [1] IMPLICIT OCTET STRING\n\
+             }\n\
+             \n\
+             END\n"
+        );
+    }
+
+    #[test]
+    fn test_certificate_names() {
+        let algorithm = |b: DerBuilder| b.sequence(|b| b.oid("1.2.840.10045.4.3.2"));
+        let name = |b: DerBuilder| {
+            b.sequence(|b| b.set(|b| b.sequence(|b| b.oid("2.5.4.3").utf8_string("t"))))
+        };
+        let cert = DerBuilder::new()
+            .sequence(|b| {
+                let b = b.sequence(|b| {
+                    let b = b.explicit(0, |b| b.integer(2)).integer(1);
+                    let b = name(algorithm(b));
+                    let b = b.sequence(|b| b.utc_time("240101000000Z").utc_time("250101000000Z"));
+                    name(b).sequence(|b| {
+                        b.sequence(|b| b.oid("1.2.840.10045.2.1").oid("1.2.840.10045.3.1.7"))
+                            .bit_string(&[4, 0x41, 0], 0)
+                    })
+                });
+                algorithm(b).bit_string(&[0], 0)
+            })
+            .finish();
+        let module = asn1_module(&Type::infer_all(&cert), "Certificate");
+        assert!(module.contains(
+            "Certificate ::= SEQUENCE {\n\
+             \x20   tbsCertificate      TbsCertificate,\n\
+             \x20   signatureAlgorithm  Signature,\n\
+             \x20   signatureValue      BIT STRING\n\
+             }\n"
+        ));
+        assert!(module.contains("    version               [0] EXPLICIT INTEGER,\n"));
+        // The subject is defined the same way as the issuer
+        assert!(module.contains("    subject               Issuer,\n"));
+        assert!(module.contains("    field1  SET OF IssuerField1Item\n"));
+    }
+}
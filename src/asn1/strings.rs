@@ -0,0 +1,137 @@
+// Character string decoding
+// Converts the content octets of the ASN.1 string types that aren't ASCII
+// compatible into text
+
+/// Decode a BMPString (UTF-16BE). Unpaired surrogates and a trailing odd
+/// byte become replacement characters.
+pub fn decode_bmp_string(content: &[u8]) -> String {
+    let units = content
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+    let mut text: String = char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    if !content.len().is_multiple_of(2) {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    text
+}
+
+/// Decode a UniversalString (UTF-32BE). Values that aren't Unicode scalar
+/// values and trailing partial characters become replacement characters.
+pub fn decode_universal_string(content: &[u8]) -> String {
+    let mut text: String = content
+        .chunks_exact(4)
+        .map(|quad| {
+            char::from_u32(u32::from_be_bytes([quad[0], quad[1], quad[2], quad[3]]))
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+        })
+        .collect();
+    if !content.len().is_multiple_of(4) {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    text
+}
+
+/// Upper half of the T.61 character set, 0xA0 to 0xFF. Positions 0xC1 to
+/// 0xCF are non-spacing diacritics, handled separately; zero marks codes with
+/// no assigned character.
+#[rustfmt::skip]
+const T61_UPPER: [char; 96] = [
+    '\u{A0}', '¡', '¢', '£', '$', '¥', '#', '§', '¤', '\0', '\0', '«', '\0', '\0', '\0', '\0',
+    '°', '±', '²', '³', '×', 'µ', '¶', '·', '÷', '\0', '\0', '»', '¼', '½', '¾', '¿',
+    '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0',
+    '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0', '\0',
+    'Ω', 'Æ', 'Đ', 'ª', 'Ħ', '\0', 'Ĳ', 'Ŀ', 'Ł', 'Ø', 'Œ', 'º', 'Þ', 'Ŧ', 'Ŋ', 'ŉ',
+    'ĸ', 'æ', 'đ', 'ð', 'ħ', 'ı', 'ĳ', 'ŀ', 'ł', 'ø', 'œ', 'ß', 'þ', 'ŧ', 'ŋ', '\u{AD}',
+];
+
+/// Unicode combining mark for a T.61 non-spacing diacritic
+fn t61_diacritic(byte: u8) -> Option<char> {
+    let mark = match byte {
+        0xC1 => '\u{300}', // Grave
+        0xC2 => '\u{301}', // Acute
+        0xC3 => '\u{302}', // Circumflex
+        0xC4 => '\u{303}', // Tilde
+        0xC5 => '\u{304}', // Macron
+        0xC6 => '\u{306}', // Breve
+        0xC7 => '\u{307}', // Dot above
+        0xC8 => '\u{308}', // Diaeresis
+        0xCA => '\u{30A}', // Ring
+        0xCB => '\u{327}', // Cedilla
+        0xCD => '\u{30B}', // Double acute
+        0xCE => '\u{328}', // Ogonek
+        0xCF => '\u{30C}', // Caron
+        _ => return None,
+    };
+    Some(mark)
+}
+
+/// Decode a TeletexString or VideotexString using the T.61 character set.
+/// Diacritics precede the letter they apply to in T.61 but follow it in
+/// Unicode, so they're moved after the next character. The primary set is
+/// read as ASCII, since legacy encoders routinely used it that way.
+pub fn decode_t61_string(content: &[u8]) -> String {
+    let mut text = String::with_capacity(content.len());
+    let mut pending_mark = None;
+    for &byte in content {
+        if let Some(mark) = t61_diacritic(byte) {
+            if let Some(previous) = pending_mark.replace(mark) {
+                // Diacritic with nothing to apply to
+                text.push(previous);
+            }
+            continue;
+        }
+        let ch = match byte {
+            0x00..=0x7F => byte as char,
+            0xA0..=0xFF => match T61_UPPER[(byte - 0xA0) as usize] {
+                '\0' => char::REPLACEMENT_CHARACTER,
+                ch => ch,
+            },
+            _ => char::REPLACEMENT_CHARACTER,
+        };
+        text.push(ch);
+        if let Some(mark) = pending_mark.take() {
+            text.push(mark);
+        }
+    }
+    text.extend(pending_mark);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_bmp_string() {
+        assert_eq!(decode_bmp_string(&[0x00, 0x41, 0x00, 0xE9]), "Aé");
+        // Surrogate pair for U+1F600
+        assert_eq!(decode_bmp_string(&[0xD8, 0x3D, 0xDE, 0x00]), "\u{1F600}");
+        assert_eq!(decode_bmp_string(&[0xD8, 0x3D, 0x00, 0x41]), "\u{FFFD}A");
+        assert_eq!(decode_bmp_string(&[0x00, 0x41, 0x00]), "A\u{FFFD}");
+    }
+
+    #[test]
+    fn test_decode_t61_string() {
+        assert_eq!(decode_t61_string(b"Test CA"), "Test CA");
+        // Acute accent followed by 'e', then the pound sign and eszett
+        assert_eq!(
+            decode_t61_string(&[b'C', b'a', b'f', 0xC2, b'e', b' ', 0xA3, 0xFB]),
+            "Cafe\u{301} £ß"
+        );
+        assert_eq!(decode_t61_string(&[0xA9, 0xC8]), "\u{FFFD}\u{308}");
+    }
+
+    #[test]
+    fn test_decode_universal_string() {
+        assert_eq!(
+            decode_universal_string(&[0x00, 0x00, 0x00, 0x41, 0x00, 0x01, 0xF6, 0x00]),
+            "A\u{1F600}"
+        );
+        assert_eq!(
+            decode_universal_string(&[0x00, 0x11, 0x00, 0x00, 0x00]),
+            "\u{FFFD}\u{FFFD}"
+        );
+    }
+}
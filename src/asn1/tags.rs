@@ -0,0 +1,90 @@
+// Identifier and length octet values from X.690
+
+// Constants for ASN.1 tag classes
+pub const CLASS_MASK: u8 = 0xC0;
+pub const UNIVERSAL: u8 = 0x00;
+pub const APPLICATION: u8 = 0x40;
+pub const CONTEXT: u8 = 0x80;
+pub const PRIVATE: u8 = 0xC0;
+
+// Constants for encoding type
+pub const FORM_MASK: u8 = 0x20;
+pub const CONSTRUCTED: u8 = 0x20;
+
+// Universal tag values
+pub const TAG_MASK: u8 = 0x1F;
+pub const EOC: u8 = 0x00;
+pub const BOOLEAN: u8 = 0x01;
+pub const INTEGER: u8 = 0x02;
+pub const BITSTRING: u8 = 0x03;
+pub const OCTETSTRING: u8 = 0x04;
+pub const NULLTAG: u8 = 0x05;
+pub const OID: u8 = 0x06;
+pub const OBJDESCRIPTOR: u8 = 0x07;
+pub const EXTERNAL: u8 = 0x08;
+pub const REAL: u8 = 0x09;
+pub const ENUMERATED: u8 = 0x0A;
+pub const EMBEDDED_PDV: u8 = 0x0B;
+pub const UTF8STRING: u8 = 0x0C;
+pub const RELATIVE_OID: u8 = 0x0D;
+pub const SEQUENCE: u8 = 0x10;
+pub const SET: u8 = 0x11;
+pub const NUMERICSTRING: u8 = 0x12;
+pub const PRINTABLESTRING: u8 = 0x13;
+pub const T61STRING: u8 = 0x14;
+pub const VIDEOTEXSTRING: u8 = 0x15;
+pub const IA5STRING: u8 = 0x16;
+pub const UTCTIME: u8 = 0x17;
+pub const GENERALIZEDTIME: u8 = 0x18;
+pub const GRAPHICSTRING: u8 = 0x19;
+pub const VISIBLESTRING: u8 = 0x1A;
+pub const GENERALSTRING: u8 = 0x1B;
+pub const UNIVERSALSTRING: u8 = 0x1C;
+pub const BMPSTRING: u8 = 0x1E;
+pub const OID_IRI: u8 = 0x23;
+pub const RELATIVE_OID_IRI: u8 = 0x24;
+
+// Length encoding
+pub const LEN_XTND: u8 = 0x80;
+pub const LEN_MASK: u8 = 0x7F;
+
+/// Get descriptive string for universal tags
+pub fn tag_name(tag: u32) -> &'static str {
+    let tag = match u8::try_from(tag) {
+        Ok(tag) => tag,
+        Err(_) => return "Unknown",
+    };
+    match tag {
+        EOC => "End-of-contents octets",
+        BOOLEAN => "BOOLEAN",
+        INTEGER => "INTEGER",
+        BITSTRING => "BIT STRING",
+        OCTETSTRING => "OCTET STRING",
+        NULLTAG => "NULL",
+        OID => "OBJECT IDENTIFIER",
+        OBJDESCRIPTOR => "ObjectDescriptor",
+        EXTERNAL => "EXTERNAL",
+        REAL => "REAL",
+        ENUMERATED => "ENUMERATED",
+        EMBEDDED_PDV => "EMBEDDED PDV",
+        UTF8STRING => "UTF8String",
+        RELATIVE_OID => "RELATIVE-OID",
+        SEQUENCE => "SEQUENCE",
+        SET => "SET",
+        NUMERICSTRING => "NumericString",
+        PRINTABLESTRING => "PrintableString",
+        T61STRING => "TeletexString",
+        VIDEOTEXSTRING => "VideotexString",
+        IA5STRING => "IA5String",
+        UTCTIME => "UTCTime",
+        GENERALIZEDTIME => "GeneralizedTime",
+        GRAPHICSTRING => "GraphicString",
+        VISIBLESTRING => "VisibleString",
+        GENERALSTRING => "GeneralString",
+        UNIVERSALSTRING => "UniversalString",
+        BMPSTRING => "BMPString",
+        OID_IRI => "OID-IRI",
+        RELATIVE_OID_IRI => "RELATIVE-OID-IRI",
+        _ => "Unknown",
+    }
+}
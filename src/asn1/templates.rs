@@ -0,0 +1,1259 @@
+// Structure templates for well-known ASN.1 formats
+// Recognizes common top-level objects and labels their components so that
+// the dump shows field names (signerInfos, tbsCertificate, ...) alongside the
+// generic tag information
+
+use std::collections::HashMap;
+
+use super::der::{children, decode_oid, looks_encapsulated, parse_tlv};
+use super::tags::{BITSTRING, OID};
+
+/// Maximum depth followed when labeling or searching nested structures
+const MAX_TEMPLATE_DEPTH: usize = 64;
+
+/// How the content of a templated item is interpreted
+pub enum Kind {
+    /// Content isn't labeled any further
+    Any,
+    /// SEQUENCE or SET whose components are matched positionally
+    Fields(&'static [Field]),
+    /// SEQUENCE OF, each element described by the same field
+    Repeated(&'static Field),
+    /// SET OF, whose elements DER requires to be sorted by their encodings.
+    /// Kept apart from Repeated because implicit tagging hides the SET tag.
+    SetOf(&'static Field),
+    /// [n] EXPLICIT wrapper around a single inner item
+    Explicit(&'static Field),
+    /// CHOICE whose alternative is selected by the identifier octet
+    Choice(&'static [Field]),
+    /// ANY DEFINED BY the OID carried in the preceding component
+    DefinedBy(fn(&str) -> Option<&'static Field>),
+    /// OCTET STRING or BIT STRING wrapping another DER object
+    Encapsulates(&'static Field),
+    /// INTEGER or ENUMERATED whose values have names, indexed by value, or a
+    /// BIT STRING whose bits have names, indexed by bit number
+    Named(&'static [&'static str]),
+}
+
+/// A named component of a structure template
+pub struct Field {
+    pub name: &'static str,
+    pub tag: Option<u8>, // Expected identifier octet, if fixed
+    pub optional: bool,
+    pub secret: bool, // Holds private key material
+    pub kind: Kind,
+}
+
+/// Label attached to an item, keyed by the item's offset in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Annotation {
+    pub name: &'static str,
+    pub secret: bool,
+    pub values: Option<&'static [&'static str]>, // Names of INTEGER values or bits
+    pub set_of: bool,                            // Elements must be in DER order
+}
+
+const fn field(name: &'static str, kind: Kind) -> Field {
+    Field {
+        name,
+        tag: None,
+        optional: false,
+        secret: false,
+        kind,
+    }
+}
+
+const fn tagged(name: &'static str, tag: u8, kind: Kind) -> Field {
+    Field {
+        name,
+        tag: Some(tag),
+        optional: false,
+        secret: false,
+        kind,
+    }
+}
+
+const fn optional(name: &'static str, tag: u8, kind: Kind) -> Field {
+    Field {
+        name,
+        tag: Some(tag),
+        optional: true,
+        secret: false,
+        kind,
+    }
+}
+
+const fn secret(name: &'static str, tag: u8, kind: Kind) -> Field {
+    Field {
+        name,
+        tag: Some(tag),
+        optional: false,
+        secret: true,
+        kind,
+    }
+}
+
+// Common building blocks
+
+const ALGORITHM_IDENTIFIER: &[Field] = &[
+    tagged("algorithm", 0x06, Kind::Any),
+    Field {
+        name: "parameters",
+        tag: None,
+        optional: true,
+        secret: false,
+        kind: Kind::DefinedBy(algorithm_parameters),
+    },
+];
+
+const PBKDF2_PARAMS: &[Field] = &[
+    tagged("salt", 0x04, Kind::Any),
+    tagged("iterationCount", 0x02, Kind::Any),
+    optional("keyLength", 0x02, Kind::Any),
+    optional("prf", 0x30, Kind::Fields(ALGORITHM_IDENTIFIER)),
+];
+
+const PBES2_PARAMS: &[Field] = &[
+    tagged(
+        "keyDerivationFunc",
+        0x30,
+        Kind::Fields(ALGORITHM_IDENTIFIER),
+    ),
+    tagged("encryptionScheme", 0x30, Kind::Fields(ALGORITHM_IDENTIFIER)),
+];
+
+const PKCS12_PBE_PARAMS: &[Field] = &[
+    tagged("salt", 0x04, Kind::Any),
+    tagged("iterations", 0x02, Kind::Any),
+];
+
+const ATTRIBUTE: &[Field] = &[
+    tagged("attrType", 0x06, Kind::Any),
+    tagged(
+        "attrValues",
+        0x31,
+        Kind::SetOf(&field("", Kind::DefinedBy(attribute_value))),
+    ),
+];
+
+// X.509 certificates and CRLs (RFC 5280)
+
+const VALIDITY: &[Field] = &[field("notBefore", Kind::Any), field("notAfter", Kind::Any)];
+
+const SUBJECT_PUBLIC_KEY_INFO: &[Field] = &[
+    tagged("algorithm", 0x30, Kind::Fields(ALGORITHM_IDENTIFIER)),
+    tagged("subjectPublicKey", 0x03, Kind::Any),
+];
+
+const EXTENSION: &[Field] = &[
+    tagged("extnID", 0x06, Kind::Any),
+    optional("critical", 0x01, Kind::Any),
+    tagged("extnValue", 0x04, Kind::DefinedBy(extension_value)),
+];
+
+const EXTENSIONS: Kind = Kind::Repeated(&field("extension", Kind::Fields(EXTENSION)));
+
+const CRL_REASONS: &[&str] = &[
+    "unspecified",
+    "keyCompromise",
+    "cACompromise",
+    "affiliationChanged",
+    "superseded",
+    "cessationOfOperation",
+    "certificateHold",
+    "",
+    "removeFromCRL",
+    "privilegeWithdrawn",
+    "aACompromise",
+];
+
+const KEY_USAGE_BITS: &[&str] = &[
+    "digitalSignature",
+    "nonRepudiation",
+    "keyEncipherment",
+    "dataEncipherment",
+    "keyAgreement",
+    "keyCertSign",
+    "cRLSign",
+    "encipherOnly",
+    "decipherOnly",
+];
+
+const TBS_CERTIFICATE: &[Field] = &[
+    optional("version", 0xA0, Kind::Explicit(&field("", Kind::Any))),
+    tagged("serialNumber", 0x02, Kind::Any),
+    tagged("signature", 0x30, Kind::Fields(ALGORITHM_IDENTIFIER)),
+    tagged("issuer", 0x30, Kind::Any),
+    tagged("validity", 0x30, Kind::Fields(VALIDITY)),
+    tagged("subject", 0x30, Kind::Any),
+    tagged(
+        "subjectPublicKeyInfo",
+        0x30,
+        Kind::Fields(SUBJECT_PUBLIC_KEY_INFO),
+    ),
+    optional("issuerUniqueID", 0x81, Kind::Any),
+    optional("subjectUniqueID", 0x82, Kind::Any),
+    optional("extensions", 0xA3, Kind::Explicit(&field("", EXTENSIONS))),
+];
+
+const CERTIFICATE: &[Field] = &[
+    tagged("tbsCertificate", 0x30, Kind::Fields(TBS_CERTIFICATE)),
+    tagged(
+        "signatureAlgorithm",
+        0x30,
+        Kind::Fields(ALGORITHM_IDENTIFIER),
+    ),
+    tagged("signatureValue", 0x03, Kind::Any),
+];
+
+const CERTIFICATE_LIST: &[Field] = &[
+    tagged("tbsCertList", 0x30, Kind::Any),
+    tagged(
+        "signatureAlgorithm",
+        0x30,
+        Kind::Fields(ALGORITHM_IDENTIFIER),
+    ),
+    tagged("signatureValue", 0x03, Kind::Any),
+];
+
+// OCSP (RFC 6960)
+
+const CERT_ID: &[Field] = &[
+    tagged("hashAlgorithm", 0x30, Kind::Fields(ALGORITHM_IDENTIFIER)),
+    tagged("issuerNameHash", 0x04, Kind::Any),
+    tagged("issuerKeyHash", 0x04, Kind::Any),
+    tagged("serialNumber", 0x02, Kind::Any),
+];
+
+const REQUEST: &[Field] = &[
+    tagged("reqCert", 0x30, Kind::Fields(CERT_ID)),
+    optional(
+        "singleRequestExtensions",
+        0xA0,
+        Kind::Explicit(&field("", EXTENSIONS)),
+    ),
+];
+
+const TBS_REQUEST: &[Field] = &[
+    optional("version", 0xA0, Kind::Explicit(&field("", Kind::Any))),
+    optional("requestorName", 0xA1, Kind::Explicit(&field("", Kind::Any))),
+    tagged(
+        "requestList",
+        0x30,
+        Kind::Repeated(&field("request", Kind::Fields(REQUEST))),
+    ),
+    optional(
+        "requestExtensions",
+        0xA2,
+        Kind::Explicit(&field("", EXTENSIONS)),
+    ),
+];
+
+const OCSP_SIGNATURE: &[Field] = &[
+    tagged(
+        "signatureAlgorithm",
+        0x30,
+        Kind::Fields(ALGORITHM_IDENTIFIER),
+    ),
+    tagged("signature", 0x03, Kind::Any),
+    optional(
+        "certs",
+        0xA0,
+        Kind::Explicit(&field(
+            "",
+            Kind::Repeated(&field("certificate", Kind::Fields(CERTIFICATE))),
+        )),
+    ),
+];
+
+const OCSP_REQUEST: &[Field] = &[
+    tagged("tbsRequest", 0x30, Kind::Fields(TBS_REQUEST)),
+    optional(
+        "optionalSignature",
+        0xA0,
+        Kind::Explicit(&field("", Kind::Fields(OCSP_SIGNATURE))),
+    ),
+];
+
+const RESPONSE_BYTES: &[Field] = &[
+    tagged("responseType", 0x06, Kind::Any),
+    tagged("response", 0x04, Kind::DefinedBy(ocsp_response_type)),
+];
+
+const OCSP_RESPONSE: &[Field] = &[
+    tagged(
+        "responseStatus",
+        0x0A,
+        Kind::Named(&[
+            "successful",
+            "malformedRequest",
+            "internalError",
+            "tryLater",
+            "",
+            "sigRequired",
+            "unauthorized",
+        ]),
+    ),
+    optional(
+        "responseBytes",
+        0xA0,
+        Kind::Explicit(&field("", Kind::Fields(RESPONSE_BYTES))),
+    ),
+];
+
+const REVOKED_INFO: &[Field] = &[
+    tagged("revocationTime", 0x18, Kind::Any),
+    optional(
+        "revocationReason",
+        0xA0,
+        Kind::Explicit(&field("reasonCode", Kind::Named(CRL_REASONS))),
+    ),
+];
+
+const SINGLE_RESPONSE: &[Field] = &[
+    tagged("certID", 0x30, Kind::Fields(CERT_ID)),
+    field(
+        "certStatus",
+        Kind::Choice(&[
+            tagged("good", 0x80, Kind::Any),
+            tagged("revoked", 0xA1, Kind::Fields(REVOKED_INFO)),
+            tagged("unknown", 0x82, Kind::Any),
+        ]),
+    ),
+    tagged("thisUpdate", 0x18, Kind::Any),
+    optional("nextUpdate", 0xA0, Kind::Explicit(&field("", Kind::Any))),
+    optional(
+        "singleExtensions",
+        0xA1,
+        Kind::Explicit(&field("", EXTENSIONS)),
+    ),
+];
+
+const RESPONSE_DATA: &[Field] = &[
+    optional("version", 0xA0, Kind::Explicit(&field("", Kind::Any))),
+    field(
+        "responderID",
+        Kind::Choice(&[
+            tagged("byName", 0xA1, Kind::Explicit(&field("", Kind::Any))),
+            tagged("byKey", 0xA2, Kind::Explicit(&field("", Kind::Any))),
+        ]),
+    ),
+    tagged("producedAt", 0x18, Kind::Any),
+    tagged(
+        "responses",
+        0x30,
+        Kind::Repeated(&field("singleResponse", Kind::Fields(SINGLE_RESPONSE))),
+    ),
+    optional(
+        "responseExtensions",
+        0xA1,
+        Kind::Explicit(&field("", EXTENSIONS)),
+    ),
+];
+
+const BASIC_OCSP_RESPONSE: &[Field] = &[
+    tagged("tbsResponseData", 0x30, Kind::Fields(RESPONSE_DATA)),
+    tagged(
+        "signatureAlgorithm",
+        0x30,
+        Kind::Fields(ALGORITHM_IDENTIFIER),
+    ),
+    tagged("signature", 0x03, Kind::Any),
+    optional(
+        "certs",
+        0xA0,
+        Kind::Explicit(&field(
+            "",
+            Kind::Repeated(&field("certificate", Kind::Fields(CERTIFICATE))),
+        )),
+    ),
+];
+
+static BASIC_OCSP_RESPONSE_VALUE: Field = tagged(
+    "response",
+    0x04,
+    Kind::Encapsulates(&field(
+        "basicOCSPResponse",
+        Kind::Fields(BASIC_OCSP_RESPONSE),
+    )),
+);
+
+// Nonces are an OCTET STRING inside extnValue (RFC 8954), although older
+// responders put the raw value there
+static OCSP_NONCE_VALUE: Field = tagged(
+    "extnValue",
+    0x04,
+    Kind::Encapsulates(&field("nonce", Kind::Any)),
+);
+static KEY_USAGE_VALUE: Field = tagged(
+    "extnValue",
+    0x04,
+    Kind::Encapsulates(&field("keyUsage", Kind::Named(KEY_USAGE_BITS))),
+);
+static CRL_REASON_VALUE: Field = tagged(
+    "extnValue",
+    0x04,
+    Kind::Encapsulates(&field("reasonCode", Kind::Named(CRL_REASONS))),
+);
+
+// PKCS #7 / CMS (RFC 5652)
+
+const CONTENT_INFO: &[Field] = &[
+    tagged("contentType", 0x06, Kind::Any),
+    optional(
+        "content",
+        0xA0,
+        Kind::Explicit(&field("", Kind::DefinedBy(content_type))),
+    ),
+];
+
+const ENCAPSULATED_CONTENT_INFO: &[Field] = &[
+    tagged("eContentType", 0x06, Kind::Any),
+    optional(
+        "eContent",
+        0xA0,
+        Kind::Explicit(&field("", Kind::Encapsulates(&field("", Kind::Any)))),
+    ),
+];
+
+const ISSUER_AND_SERIAL_NUMBER: &[Field] = &[
+    tagged("issuer", 0x30, Kind::Any),
+    tagged("serialNumber", 0x02, Kind::Any),
+];
+
+const SIGNER_INFO: &[Field] = &[
+    tagged("version", 0x02, Kind::Any),
+    field(
+        "sid",
+        Kind::Choice(&[
+            tagged(
+                "issuerAndSerialNumber",
+                0x30,
+                Kind::Fields(ISSUER_AND_SERIAL_NUMBER),
+            ),
+            tagged("subjectKeyIdentifier", 0x80, Kind::Any),
+        ]),
+    ),
+    tagged("digestAlgorithm", 0x30, Kind::Fields(ALGORITHM_IDENTIFIER)),
+    optional(
+        "signedAttrs",
+        0xA0,
+        Kind::SetOf(&field("attribute", Kind::Fields(ATTRIBUTE))),
+    ),
+    tagged(
+        "signatureAlgorithm",
+        0x30,
+        Kind::Fields(ALGORITHM_IDENTIFIER),
+    ),
+    tagged("signature", 0x04, Kind::Any),
+    optional(
+        "unsignedAttrs",
+        0xA1,
+        Kind::SetOf(&field("attribute", Kind::Fields(ATTRIBUTE))),
+    ),
+];
+
+const SIGNED_DATA_FIELDS: &[Field] = &[
+    tagged("version", 0x02, Kind::Any),
+    tagged(
+        "digestAlgorithms",
+        0x31,
+        Kind::SetOf(&field(
+            "digestAlgorithm",
+            Kind::Fields(ALGORITHM_IDENTIFIER),
+        )),
+    ),
+    tagged(
+        "encapContentInfo",
+        0x30,
+        Kind::Fields(ENCAPSULATED_CONTENT_INFO),
+    ),
+    optional(
+        "certificates",
+        0xA0,
+        Kind::SetOf(&field("certificate", Kind::Fields(CERTIFICATE))),
+    ),
+    optional(
+        "crls",
+        0xA1,
+        Kind::SetOf(&field("crl", Kind::Fields(CERTIFICATE_LIST))),
+    ),
+    tagged(
+        "signerInfos",
+        0x31,
+        Kind::SetOf(&field("signerInfo", Kind::Fields(SIGNER_INFO))),
+    ),
+];
+
+// Private keys (RFC 5208, RFC 5958, RFC 8017, RFC 5915, RFC 8410)
+
+const ONE_ASYMMETRIC_KEY: &[Field] = &[
+    tagged("version", 0x02, Kind::Any),
+    tagged(
+        "privateKeyAlgorithm",
+        0x30,
+        Kind::Fields(ALGORITHM_IDENTIFIER),
+    ),
+    secret(
+        "privateKey",
+        0x04,
+        Kind::Encapsulates(&field("", Kind::DefinedBy(private_key_type))),
+    ),
+    optional("attributes", 0xA0, Kind::Any),
+    optional("publicKey", 0x81, Kind::Any),
+];
+
+const ENCRYPTED_PRIVATE_KEY_INFO: &[Field] = &[
+    tagged(
+        "encryptionAlgorithm",
+        0x30,
+        Kind::Fields(ALGORITHM_IDENTIFIER),
+    ),
+    tagged("encryptedData", 0x04, Kind::Any),
+];
+
+const RSA_PRIVATE_KEY: &[Field] = &[
+    tagged("version", 0x02, Kind::Any),
+    tagged("modulus", 0x02, Kind::Any),
+    tagged("publicExponent", 0x02, Kind::Any),
+    secret("privateExponent", 0x02, Kind::Any),
+    secret("prime1", 0x02, Kind::Any),
+    secret("prime2", 0x02, Kind::Any),
+    secret("exponent1", 0x02, Kind::Any),
+    secret("exponent2", 0x02, Kind::Any),
+    secret("coefficient", 0x02, Kind::Any),
+    optional("otherPrimeInfos", 0x30, Kind::Any),
+];
+
+const EC_PRIVATE_KEY: &[Field] = &[
+    tagged("version", 0x02, Kind::Any),
+    secret("privateKey", 0x04, Kind::Any),
+    optional(
+        "parameters",
+        0xA0,
+        Kind::Explicit(&field("namedCurve", Kind::Any)),
+    ),
+    optional("publicKey", 0xA1, Kind::Explicit(&field("", Kind::Any))),
+];
+
+static RSA_PRIVATE_KEY_VALUE: Field = tagged("rsaPrivateKey", 0x30, Kind::Fields(RSA_PRIVATE_KEY));
+static EC_PRIVATE_KEY_VALUE: Field = tagged("ecPrivateKey", 0x30, Kind::Fields(EC_PRIVATE_KEY));
+static CURVE_PRIVATE_KEY_VALUE: Field = secret("curvePrivateKey", 0x04, Kind::Any);
+
+static PBES2_PARAMETERS: Field = tagged("pbes2Params", 0x30, Kind::Fields(PBES2_PARAMS));
+static PBKDF2_PARAMETERS: Field = tagged("pbkdf2Params", 0x30, Kind::Fields(PBKDF2_PARAMS));
+static PKCS12_PBE_PARAMETERS: Field = tagged("pbeParams", 0x30, Kind::Fields(PKCS12_PBE_PARAMS));
+static NAMED_CURVE_PARAMETER: Field = tagged("namedCurve", 0x06, Kind::Any);
+
+// PKCS #12 (RFC 7292)
+
+const DIGEST_INFO: &[Field] = &[
+    tagged("digestAlgorithm", 0x30, Kind::Fields(ALGORITHM_IDENTIFIER)),
+    tagged("digest", 0x04, Kind::Any),
+];
+
+const MAC_DATA: &[Field] = &[
+    tagged("mac", 0x30, Kind::Fields(DIGEST_INFO)),
+    tagged("macSalt", 0x04, Kind::Any),
+    optional("iterations", 0x02, Kind::Any),
+];
+
+const PFX: &[Field] = &[
+    tagged("version", 0x02, Kind::Any),
+    tagged("authSafe", 0x30, Kind::Fields(AUTH_SAFE_CONTENT_INFO)),
+    optional("macData", 0x30, Kind::Fields(MAC_DATA)),
+];
+
+const AUTH_SAFE_CONTENT_INFO: &[Field] = &[
+    tagged("contentType", 0x06, Kind::Any),
+    optional(
+        "content",
+        0xA0,
+        Kind::Explicit(&field(
+            "data",
+            Kind::Encapsulates(&field(
+                "authenticatedSafe",
+                Kind::Repeated(&field("contentInfo", Kind::Fields(SAFE_CONTENT_INFO))),
+            )),
+        )),
+    ),
+];
+
+const SAFE_CONTENT_INFO: &[Field] = &[
+    tagged("contentType", 0x06, Kind::Any),
+    optional(
+        "content",
+        0xA0,
+        Kind::Explicit(&field("", Kind::DefinedBy(safe_contents_container))),
+    ),
+];
+
+const ENCRYPTED_CONTENT_INFO: &[Field] = &[
+    tagged("contentType", 0x06, Kind::Any),
+    tagged(
+        "contentEncryptionAlgorithm",
+        0x30,
+        Kind::Fields(ALGORITHM_IDENTIFIER),
+    ),
+    optional("encryptedContent", 0x80, Kind::Any),
+];
+
+const ENCRYPTED_DATA: &[Field] = &[
+    tagged("version", 0x02, Kind::Any),
+    tagged(
+        "encryptedContentInfo",
+        0x30,
+        Kind::Fields(ENCRYPTED_CONTENT_INFO),
+    ),
+    optional("unprotectedAttrs", 0xA1, Kind::Any),
+];
+
+const SAFE_BAG: &[Field] = &[
+    tagged("bagId", 0x06, Kind::Any),
+    tagged(
+        "bagValue",
+        0xA0,
+        Kind::Explicit(&field("", Kind::DefinedBy(bag_type))),
+    ),
+    optional(
+        "bagAttributes",
+        0x31,
+        Kind::SetOf(&field("attribute", Kind::Fields(ATTRIBUTE))),
+    ),
+];
+
+const CERT_BAG: &[Field] = &[
+    tagged("certId", 0x06, Kind::Any),
+    tagged(
+        "certValue",
+        0xA0,
+        Kind::Explicit(&field("", Kind::DefinedBy(cert_bag_type))),
+    ),
+];
+
+const CRL_BAG: &[Field] = &[
+    tagged("crlId", 0x06, Kind::Any),
+    tagged(
+        "crlValue",
+        0xA0,
+        Kind::Explicit(&field("", Kind::DefinedBy(cert_bag_type))),
+    ),
+];
+
+const SECRET_BAG: &[Field] = &[
+    tagged("secretTypeId", 0x06, Kind::Any),
+    secret("secretValue", 0xA0, Kind::Explicit(&field("", Kind::Any))),
+];
+
+/// SafeContents, also used to label the plaintext of decrypted EncryptedData
+pub static SAFE_CONTENTS: Field = tagged(
+    "safeContents",
+    0x30,
+    Kind::Repeated(&field("safeBag", Kind::Fields(SAFE_BAG))),
+);
+static SAFE_CONTENTS_DATA: Field = tagged("data", 0x04, Kind::Encapsulates(&SAFE_CONTENTS));
+static ENCRYPTED_DATA_VALUE: Field = tagged("encryptedData", 0x30, Kind::Fields(ENCRYPTED_DATA));
+
+static KEY_BAG: Field = tagged("keyBag", 0x30, Kind::Fields(ONE_ASYMMETRIC_KEY));
+static SHROUDED_KEY_BAG: Field = tagged(
+    "pkcs8ShroudedKeyBag",
+    0x30,
+    Kind::Fields(ENCRYPTED_PRIVATE_KEY_INFO),
+);
+static CERT_BAG_VALUE: Field = tagged("certBag", 0x30, Kind::Fields(CERT_BAG));
+static CRL_BAG_VALUE: Field = tagged("crlBag", 0x30, Kind::Fields(CRL_BAG));
+static SECRET_BAG_VALUE: Field = tagged("secretBag", 0x30, Kind::Fields(SECRET_BAG));
+static SAFE_CONTENTS_BAG: Field = tagged(
+    "safeContentsBag",
+    0x30,
+    Kind::Repeated(&field("safeBag", Kind::Fields(SAFE_BAG))),
+);
+static X509_CERTIFICATE_VALUE: Field = tagged(
+    "x509Certificate",
+    0x04,
+    Kind::Encapsulates(&field("certificate", Kind::Fields(CERTIFICATE))),
+);
+static X509_CRL_VALUE: Field = tagged(
+    "x509CRL",
+    0x04,
+    Kind::Encapsulates(&field("crl", Kind::Fields(CERTIFICATE_LIST))),
+);
+
+static FRIENDLY_NAME_ATTR: Field = field("friendlyName", Kind::Any);
+static LOCAL_KEY_ID_ATTR: Field = field("localKeyId", Kind::Any);
+
+static SIGNED_DATA: Field = tagged("signedData", 0x30, Kind::Fields(SIGNED_DATA_FIELDS));
+static DATA: Field = tagged("data", 0x04, Kind::Any);
+
+static CONTENT_TYPE_ATTR: Field = field("contentType", Kind::Any);
+static MESSAGE_DIGEST_ATTR: Field = field("messageDigest", Kind::Any);
+static SIGNING_TIME_ATTR: Field = field("signingTime", Kind::Any);
+static COUNTERSIGNATURE_ATTR: Field = field("countersignature", Kind::Fields(SIGNER_INFO));
+
+/// Top-level templates
+static OCSP_REQUEST_TEMPLATE: Field = tagged("ocspRequest", 0x30, Kind::Fields(OCSP_REQUEST));
+static OCSP_RESPONSE_TEMPLATE: Field = tagged("ocspResponse", 0x30, Kind::Fields(OCSP_RESPONSE));
+static BASIC_OCSP_RESPONSE_TEMPLATE: Field =
+    tagged("basicOCSPResponse", 0x30, Kind::Fields(BASIC_OCSP_RESPONSE));
+static CONTENT_INFO_TEMPLATE: Field = tagged("contentInfo", 0x30, Kind::Fields(CONTENT_INFO));
+pub static CERTIFICATE_TEMPLATE: Field = tagged("certificate", 0x30, Kind::Fields(CERTIFICATE));
+pub static PFX_TEMPLATE: Field = tagged("pfx", 0x30, Kind::Fields(PFX));
+static PRIVATE_KEY_INFO_TEMPLATE: Field =
+    tagged("privateKeyInfo", 0x30, Kind::Fields(ONE_ASYMMETRIC_KEY));
+static ENCRYPTED_PRIVATE_KEY_INFO_TEMPLATE: Field = tagged(
+    "encryptedPrivateKeyInfo",
+    0x30,
+    Kind::Fields(ENCRYPTED_PRIVATE_KEY_INFO),
+);
+
+/// Content of a CMS ContentInfo, selected by contentType
+fn content_type(oid: &str) -> Option<&'static Field> {
+    match oid {
+        "1.2.840.113549.1.7.1" => Some(&DATA),
+        "1.2.840.113549.1.7.2" => Some(&SIGNED_DATA),
+        _ => None,
+    }
+}
+
+/// Content of a ContentInfo inside a PKCS #12 AuthenticatedSafe
+fn safe_contents_container(oid: &str) -> Option<&'static Field> {
+    match oid {
+        "1.2.840.113549.1.7.1" => Some(&SAFE_CONTENTS_DATA),
+        "1.2.840.113549.1.7.6" => Some(&ENCRYPTED_DATA_VALUE),
+        _ => None,
+    }
+}
+
+/// Value of a PKCS #12 SafeBag, selected by bagId
+fn bag_type(oid: &str) -> Option<&'static Field> {
+    match oid {
+        "1.2.840.113549.1.12.10.1.1" => Some(&KEY_BAG),
+        "1.2.840.113549.1.12.10.1.2" => Some(&SHROUDED_KEY_BAG),
+        "1.2.840.113549.1.12.10.1.3" => Some(&CERT_BAG_VALUE),
+        "1.2.840.113549.1.12.10.1.4" => Some(&CRL_BAG_VALUE),
+        "1.2.840.113549.1.12.10.1.5" => Some(&SECRET_BAG_VALUE),
+        "1.2.840.113549.1.12.10.1.6" => Some(&SAFE_CONTENTS_BAG),
+        _ => None,
+    }
+}
+
+/// Value of a PKCS #12 CertBag or CRLBag, selected by certId/crlId
+fn cert_bag_type(oid: &str) -> Option<&'static Field> {
+    match oid {
+        "1.2.840.113549.1.9.22.1" => Some(&X509_CERTIFICATE_VALUE),
+        "1.2.840.113549.1.9.23.1" => Some(&X509_CRL_VALUE),
+        _ => None,
+    }
+}
+
+/// Response inside OCSP ResponseBytes, selected by responseType
+fn ocsp_response_type(oid: &str) -> Option<&'static Field> {
+    match oid {
+        "1.3.6.1.5.5.7.48.1.1" => Some(&BASIC_OCSP_RESPONSE_VALUE),
+        _ => None,
+    }
+}
+
+/// Value of an X.509 or OCSP extension, selected by extnID
+fn extension_value(oid: &str) -> Option<&'static Field> {
+    match oid {
+        "1.3.6.1.5.5.7.48.1.2" => Some(&OCSP_NONCE_VALUE),
+        "2.5.29.15" => Some(&KEY_USAGE_VALUE),
+        "2.5.29.21" => Some(&CRL_REASON_VALUE),
+        _ => None,
+    }
+}
+
+/// Parameters of an AlgorithmIdentifier, selected by the algorithm
+fn algorithm_parameters(oid: &str) -> Option<&'static Field> {
+    match oid {
+        "1.2.840.113549.1.5.12" => Some(&PBKDF2_PARAMETERS),
+        "1.2.840.113549.1.5.13" => Some(&PBES2_PARAMETERS),
+        "1.2.840.10045.2.1" => Some(&NAMED_CURVE_PARAMETER),
+        _ if oid.starts_with("1.2.840.113549.1.12.1.") => Some(&PKCS12_PBE_PARAMETERS),
+        _ => None,
+    }
+}
+
+/// Encapsulated key inside a PrivateKeyInfo, selected by privateKeyAlgorithm
+fn private_key_type(oid: &str) -> Option<&'static Field> {
+    match oid {
+        "1.2.840.113549.1.1.1" | "1.2.840.113549.1.1.10" => Some(&RSA_PRIVATE_KEY_VALUE),
+        "1.2.840.10045.2.1" => Some(&EC_PRIVATE_KEY_VALUE),
+        "1.3.101.110" | "1.3.101.111" | "1.3.101.112" | "1.3.101.113" => {
+            Some(&CURVE_PRIVATE_KEY_VALUE)
+        }
+        _ => None,
+    }
+}
+
+/// Value of a CMS attribute, selected by attrType
+fn attribute_value(oid: &str) -> Option<&'static Field> {
+    match oid {
+        "1.2.840.113549.1.9.3" => Some(&CONTENT_TYPE_ATTR),
+        "1.2.840.113549.1.9.4" => Some(&MESSAGE_DIGEST_ATTR),
+        "1.2.840.113549.1.9.5" => Some(&SIGNING_TIME_ATTR),
+        "1.2.840.113549.1.9.6" => Some(&COUNTERSIGNATURE_ATTR),
+        "1.2.840.113549.1.9.20" => Some(&FRIENDLY_NAME_ATTR),
+        "1.2.840.113549.1.9.21" => Some(&LOCAL_KEY_ID_ATTR),
+        _ => None,
+    }
+}
+
+/// Split a top-level SEQUENCE into the complete encodings of its components
+fn sequence_parts(data: &[u8]) -> Option<Vec<&[u8]>> {
+    let outer = parse_tlv(data).filter(|tlv| tlv.id == 0x30)?;
+    let content = outer.content(data);
+    Some(
+        children(content)
+            .map(|(pos, tlv)| &content[pos..pos + tlv.total_len()])
+            .collect(),
+    )
+}
+
+/// Decode an encoding if it is an OBJECT IDENTIFIER
+fn oid_value(encoding: &[u8]) -> Option<String> {
+    let tlv = parse_tlv(encoding).filter(|tlv| tlv.id == OID)?;
+    decode_oid(tlv.content(encoding))
+}
+
+/// Decode an encoding if it is a small INTEGER
+fn small_integer(encoding: &[u8]) -> Option<u8> {
+    match encoding {
+        [0x02, 0x01, value] => Some(*value),
+        _ => None,
+    }
+}
+
+/// Check for a ContentInfo: a SEQUENCE starting with a CMS content type OID
+fn is_content_info(data: &[u8]) -> bool {
+    let parts = match sequence_parts(data) {
+        Some(parts) => parts,
+        None => return false,
+    };
+    parts
+        .first()
+        .and_then(|part| oid_value(part))
+        .is_some_and(|oid| oid.starts_with("1.2.840.113549.1.7.") || content_type(&oid).is_some())
+}
+
+/// Check for an X.509 certificate: SEQUENCE { SEQUENCE, SEQUENCE, BIT STRING }
+/// where the first component starts with a version tag or serial number
+fn is_certificate(data: &[u8]) -> bool {
+    let parts = match sequence_parts(data) {
+        Some(parts) => parts,
+        None => return false,
+    };
+    if parts.len() != 3 || parts[0][0] != 0x30 || parts[1][0] != 0x30 || parts[2][0] != 0x03 {
+        return false;
+    }
+    matches!(
+        sequence_parts(parts[0]).as_deref(),
+        Some([first, ..]) if first[0] == 0xA0 || first[0] == 0x02
+    )
+}
+
+/// Check for an OCSPRequest: SEQUENCE { TBSRequest, [0] ... } where the
+/// request list holds CertIDs
+fn is_ocsp_request(data: &[u8]) -> bool {
+    let tbs_request = match sequence_parts(data).as_deref() {
+        Some([tbs_request]) | Some([tbs_request, [0xA0, ..]]) => sequence_parts(tbs_request),
+        _ => None,
+    };
+    let request_list = match tbs_request {
+        Some(parts) => parts.into_iter().find(|part| part[0] == 0x30),
+        None => return false,
+    };
+    let first_request = request_list
+        .and_then(sequence_parts)
+        .and_then(|requests| requests.first().and_then(|r| sequence_parts(r)));
+    matches!(
+        first_request.as_deref(),
+        Some([cert_id, ..]) if sequence_parts(cert_id).is_some_and(|id| {
+            id.len() == 4 && id[0][0] == 0x30 && id[1][0] == 0x04 && id[2][0] == 0x04 && id[3][0] == 0x02
+        })
+    )
+}
+
+/// Check for an OCSPResponse: SEQUENCE { ENUMERATED, [0] ... }
+fn is_ocsp_response(data: &[u8]) -> bool {
+    matches!(
+        sequence_parts(data).as_deref(),
+        Some([[0x0A, 0x01, _]]) | Some([[0x0A, 0x01, _], [0xA0, ..]])
+    )
+}
+
+/// Check for a bare BasicOCSPResponse: like a certificate, but the signed
+/// data has a responderID and a GeneralizedTime producedAt
+fn is_basic_ocsp_response(data: &[u8]) -> bool {
+    let parts = match sequence_parts(data) {
+        Some(parts) => parts,
+        None => return false,
+    };
+    if !(3..=4).contains(&parts.len()) || parts[0][0] != 0x30 || parts[2][0] != 0x03 {
+        return false;
+    }
+    let response_data = sequence_parts(parts[0]).unwrap_or_default();
+    let rest = match response_data.first() {
+        Some(version) if version[0] == 0xA0 => &response_data[1..],
+        _ => &response_data[..],
+    };
+    matches!(rest, [responder, produced_at, ..] if (responder[0] == 0xA1 || responder[0] == 0xA2) && produced_at[0] == 0x18)
+}
+
+/// Check for a PKCS #12 PFX: SEQUENCE { INTEGER 3, ContentInfo, ... }
+fn is_pfx(data: &[u8]) -> bool {
+    match sequence_parts(data).as_deref() {
+        Some([version, auth_safe, ..]) => {
+            small_integer(version) == Some(3) && is_content_info(auth_safe)
+        }
+        _ => false,
+    }
+}
+
+/// Check for a PKCS #8 PrivateKeyInfo / OneAsymmetricKey:
+/// SEQUENCE { INTEGER 0|1, AlgorithmIdentifier, OCTET STRING, ... }
+fn is_private_key_info(data: &[u8]) -> bool {
+    match sequence_parts(data).as_deref() {
+        Some([version, algorithm, key, ..]) => {
+            matches!(small_integer(version), Some(0 | 1))
+                && algorithm[0] == 0x30
+                && key[0] == 0x04
+                && sequence_parts(algorithm)
+                    .is_some_and(|alg| alg.first().is_some_and(|oid| oid[0] == OID))
+        }
+        _ => false,
+    }
+}
+
+/// Check for a PKCS #8 EncryptedPrivateKeyInfo: SEQUENCE { AlgorithmIdentifier,
+/// OCTET STRING } with a password-based encryption algorithm
+fn is_encrypted_private_key_info(data: &[u8]) -> bool {
+    match sequence_parts(data).as_deref() {
+        Some([algorithm, encrypted]) if encrypted[0] == 0x04 => sequence_parts(algorithm)
+            .and_then(|alg| alg.first().and_then(|oid| oid_value(oid)))
+            .is_some_and(|oid| {
+                oid.starts_with("1.2.840.113549.1.5.") || oid.starts_with("1.2.840.113549.1.12.1.")
+            }),
+        _ => false,
+    }
+}
+
+/// Check for a PKCS #1 RSAPrivateKey: a version followed by eight INTEGERs
+fn is_rsa_private_key(data: &[u8]) -> bool {
+    match sequence_parts(data) {
+        Some(parts) => {
+            parts.len() >= 9
+                && matches!(small_integer(parts[0]), Some(0 | 1))
+                && parts[..9].iter().all(|part| part[0] == 0x02)
+        }
+        None => false,
+    }
+}
+
+/// Check for an RFC 5915 ECPrivateKey: SEQUENCE { INTEGER 1, OCTET STRING, ... }
+fn is_ec_private_key(data: &[u8]) -> bool {
+    match sequence_parts(data).as_deref() {
+        Some([version, key, rest @ ..]) => {
+            small_integer(version) == Some(1)
+                && key[0] == 0x04
+                && rest.iter().all(|part| part[0] == 0xA0 || part[0] == 0xA1)
+        }
+        _ => false,
+    }
+}
+
+/// Check whether a complete encoding has the shape of a given format
+type Recognizer = fn(&[u8]) -> bool;
+
+/// Known top-level formats, tried in order
+static RECOGNIZERS: &[(Recognizer, &Field)] = &[
+    (is_content_info, &CONTENT_INFO_TEMPLATE),
+    (is_basic_ocsp_response, &BASIC_OCSP_RESPONSE_TEMPLATE),
+    (is_certificate, &CERTIFICATE_TEMPLATE),
+    (is_ocsp_request, &OCSP_REQUEST_TEMPLATE),
+    (is_ocsp_response, &OCSP_RESPONSE_TEMPLATE),
+    (is_pfx, &PFX_TEMPLATE),
+    (is_private_key_info, &PRIVATE_KEY_INFO_TEMPLATE),
+    (
+        is_encrypted_private_key_info,
+        &ENCRYPTED_PRIVATE_KEY_INFO_TEMPLATE,
+    ),
+    (is_rsa_private_key, &RSA_PRIVATE_KEY_VALUE),
+    (is_ec_private_key, &EC_PRIVATE_KEY_VALUE),
+];
+
+/// Identify the template matching a complete top-level encoding, if any
+pub fn recognize(data: &[u8]) -> Option<&'static Field> {
+    RECOGNIZERS
+        .iter()
+        .find(|(matches, _)| matches(data))
+        .map(|(_, template)| *template)
+}
+
+/// Label the components of `data` according to `template`, recording
+/// annotations against absolute offsets starting at `offset`
+pub fn annotate(
+    data: &[u8],
+    offset: usize,
+    template: &'static Field,
+    annotations: &mut HashMap<usize, Annotation>,
+) {
+    annotate_item(data, offset, template, None, annotations, 0);
+}
+
+fn annotate_item(
+    data: &[u8],
+    offset: usize,
+    field: &'static Field,
+    defining_oid: Option<&str>,
+    annotations: &mut HashMap<usize, Annotation>,
+    depth: usize,
+) {
+    if depth > MAX_TEMPLATE_DEPTH {
+        return;
+    }
+    let tlv = match parse_tlv(data) {
+        Some(tlv) => tlv,
+        None => return,
+    };
+
+    // Resolve open types and alternatives to the field actually present
+    let field = match field.kind {
+        Kind::DefinedBy(lookup) => match defining_oid.and_then(lookup) {
+            Some(resolved) => resolved,
+            None => field,
+        },
+        Kind::Choice(alternatives) => match alternatives
+            .iter()
+            .find(|alt| alt.tag.is_none_or(|tag| tag == tlv.id))
+        {
+            Some(alternative) => alternative,
+            None => field,
+        },
+        _ => field,
+    };
+
+    if !field.name.is_empty() {
+        annotations.insert(
+            offset,
+            Annotation {
+                name: field.name,
+                secret: field.secret,
+                values: match field.kind {
+                    Kind::Named(names) => Some(names),
+                    _ => None,
+                },
+                set_of: matches!(field.kind, Kind::SetOf(_)),
+            },
+        );
+    }
+
+    let content = tlv.content(data);
+    let content_offset = offset + tlv.header_len;
+
+    match field.kind {
+        Kind::Fields(fields) if tlv.is_constructed() => {
+            let mut next = 0;
+            let mut previous_oid: Option<String> = None;
+            for (pos, child) in children(content) {
+                // Skip optional components that aren't present
+                while fields
+                    .get(next)
+                    .is_some_and(|f| f.optional && f.tag.is_some_and(|tag| tag != child.id))
+                {
+                    next += 1;
+                }
+                let component = match fields.get(next) {
+                    Some(f) if f.tag.is_none_or(|tag| tag == child.id) => f,
+                    _ => break, // Encoding doesn't follow the template
+                };
+                let child_data = &content[pos..pos + child.total_len()];
+                annotate_item(
+                    child_data,
+                    content_offset + pos,
+                    component,
+                    previous_oid.as_deref(),
+                    annotations,
+                    depth + 1,
+                );
+                // Components without an OID (a BOOLEAN critical flag, say)
+                // leave the defining OID in place for the ones after them
+                if let Some(oid) = first_oid(child_data, depth + 1) {
+                    previous_oid = Some(oid);
+                }
+                next += 1;
+            }
+        }
+        Kind::Repeated(element) | Kind::SetOf(element) if tlv.is_constructed() => {
+            for (pos, child) in children(content) {
+                annotate_item(
+                    &content[pos..pos + child.total_len()],
+                    content_offset + pos,
+                    element,
+                    defining_oid,
+                    annotations,
+                    depth + 1,
+                );
+            }
+        }
+        Kind::Explicit(inner) if tlv.is_constructed() => {
+            if let Some((pos, child)) = children(content).next() {
+                annotate_item(
+                    &content[pos..pos + child.total_len()],
+                    content_offset + pos,
+                    inner,
+                    defining_oid,
+                    annotations,
+                    depth + 1,
+                );
+            }
+        }
+        Kind::Encapsulates(inner) if !tlv.is_constructed() => {
+            // BIT STRINGs carry an unused-bits octet before the wrapped data
+            let skip = if tlv.id == BITSTRING { 1 } else { 0 };
+            if content.len() > skip && looks_encapsulated(&content[skip..]) {
+                annotate_item(
+                    &content[skip..],
+                    content_offset + skip,
+                    inner,
+                    defining_oid,
+                    annotations,
+                    depth + 1,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Find the first OBJECT IDENTIFIER within an encoding, depth-first, which is
+/// what an ANY DEFINED BY component that follows it is keyed on
+fn first_oid(data: &[u8], depth: usize) -> Option<String> {
+    if depth > MAX_TEMPLATE_DEPTH {
+        return None;
+    }
+    let tlv = parse_tlv(data)?;
+    if tlv.id == OID {
+        return decode_oid(tlv.content(data));
+    }
+    if !tlv.is_constructed() {
+        return None;
+    }
+    let content = tlv.content(data);
+    children(content)
+        .find_map(|(pos, child)| first_oid(&content[pos..pos + child.total_len()], depth + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::DerBuilder;
+
+    // ContentInfo { signedData, [0] { SignedData { version 1, digestAlgorithms
+    // { sha256 }, encapContentInfo { 1.2.3.4 }, signerInfos {} } } }
+    const SIGNED_DATA_SAMPLE: &[u8] = &[
+        0x30, 0x2A, 0x06, 0x09, 0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02, 0xA0, 0x1D,
+        0x30, 0x1B, 0x02, 0x01, 0x01, 0x31, 0x0D, 0x30, 0x0B, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01,
+        0x65, 0x03, 0x04, 0x02, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2A, 0x03, 0x04, 0x31, 0x00,
+    ];
+
+    #[test]
+    fn test_recognize_content_info() {
+        let template = recognize(SIGNED_DATA_SAMPLE).expect("should be recognized");
+        assert_eq!(template.name, "contentInfo");
+        assert!(recognize(&[0x30, 0x03, 0x02, 0x01, 0x00]).is_none());
+    }
+
+    #[test]
+    fn test_annotate_signed_data() {
+        let mut annotations = HashMap::new();
+        annotate(
+            SIGNED_DATA_SAMPLE,
+            100,
+            &CONTENT_INFO_TEMPLATE,
+            &mut annotations,
+        );
+
+        let name_at = |offset: usize| annotations.get(&(100 + offset)).map(|a| a.name);
+        assert_eq!(name_at(0), Some("contentInfo"));
+        assert_eq!(name_at(2), Some("contentType"));
+        assert_eq!(name_at(13), Some("content"));
+        assert_eq!(name_at(15), Some("signedData"));
+        assert_eq!(name_at(17), Some("version"));
+        assert_eq!(name_at(20), Some("digestAlgorithms"));
+        assert_eq!(name_at(22), Some("digestAlgorithm"));
+        assert_eq!(name_at(35), Some("encapContentInfo"));
+        assert_eq!(name_at(42), Some("signerInfos"));
+        let set_of_at = |offset: usize| annotations.get(&(100 + offset)).map(|a| a.set_of);
+        assert_eq!(set_of_at(20), Some(true));
+        assert_eq!(set_of_at(22), Some(false));
+        assert_eq!(set_of_at(42), Some(true));
+    }
+
+    #[test]
+    fn test_ocsp_response_status_names() {
+        // OCSPResponse { responseStatus unauthorized }
+        let response = [0x30, 0x03, 0x0A, 0x01, 0x06];
+        let template = recognize(&response).expect("should be recognized");
+        assert_eq!(template.name, "ocspResponse");
+
+        let mut annotations = HashMap::new();
+        annotate(&response, 0, template, &mut annotations);
+        assert_eq!(annotations[&2].name, "responseStatus");
+        let names = annotations[&2].values.expect("should have value names");
+        assert_eq!(names[6], "unauthorized");
+    }
+
+    #[test]
+    fn test_extension_value_after_critical_flag() {
+        // Extension { cRLReason, critical TRUE, OCTET STRING { ENUMERATED 1 } }
+        let extension = [
+            0x30, 0x0D, 0x06, 0x03, 0x55, 0x1D, 0x15, 0x01, 0x01, 0xFF, 0x04, 0x03, 0x0A, 0x01,
+            0x01,
+        ];
+        static EXTENSION_FIELD: Field = tagged("extension", 0x30, Kind::Fields(EXTENSION));
+        let mut annotations = HashMap::new();
+        annotate(&extension, 0, &EXTENSION_FIELD, &mut annotations);
+        assert_eq!(annotations[&7].name, "critical");
+        assert_eq!(annotations[&12].name, "reasonCode");
+        assert_eq!(annotations[&12].values.map(|v| v[1]), Some("keyCompromise"));
+    }
+
+    #[test]
+    fn test_ec_private_key_is_secret() {
+        // ECPrivateKey { version 1, privateKey 'AABB'H }
+        let key = [0x30, 0x07, 0x02, 0x01, 0x01, 0x04, 0x02, 0xAA, 0xBB];
+        let template = recognize(&key).expect("should be recognized");
+        assert_eq!(template.name, "ecPrivateKey");
+
+        let mut annotations = HashMap::new();
+        annotate(&key, 0, template, &mut annotations);
+        assert!(!annotations[&2].secret);
+        assert_eq!(annotations[&5].name, "privateKey");
+        assert!(annotations[&5].secret);
+    }
+
+    #[test]
+    fn test_private_key_info_labels_encapsulated_key() {
+        // PrivateKeyInfo { 0, { ecPublicKey }, OCTET STRING { ECPrivateKey } }
+        let ec_private_key = DerBuilder::new()
+            .sequence(|b| b.integer(1).octet_string(&[0xAA]))
+            .finish();
+        let key = DerBuilder::new()
+            .sequence(|b| {
+                b.integer(0)
+                    .sequence(|b| b.oid("1.2.840.10045.2.1"))
+                    .octet_string(&ec_private_key)
+            })
+            .finish();
+        let template = recognize(&key).expect("should be recognized");
+        assert_eq!(template.name, "privateKeyInfo");
+
+        let mut annotations = HashMap::new();
+        annotate(&key, 0, template, &mut annotations);
+        assert_eq!(annotations[&5].name, "privateKeyAlgorithm");
+        assert_eq!(annotations[&18].name, "ecPrivateKey");
+        assert!(annotations[&23].secret);
+    }
+}
@@ -0,0 +1,531 @@
+// UTCTime and GeneralizedTime parsing
+// Turns the string forms into a normalized ISO 8601 timestamp and notes where
+// the encoding departs from the usual YYMMDDHHMMSSZ profile
+
+/// Fraction digits considered when carrying a fraction of an hour or minute
+const MAX_FRACTION_DIGITS: usize = 20;
+
+/// Time zone designator of a time value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Utc,         // Trailing Z
+    Offset(i16), // Differential from UTC in minutes
+    Local,       // No designator (GeneralizedTime only)
+}
+
+/// The components of a parsed time string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Time {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: Option<u8>,
+    pub second: Option<u8>,
+    pub fraction: Option<String>, // Digits after the decimal mark, of the last unit given
+    pub decimal_comma: bool,      // Fraction introduced by ',' rather than '.'
+    pub zone: Zone,
+    pub two_digit_year: bool, // UTCTime, century inferred from the pivot
+}
+
+impl Time {
+    /// Render as ISO 8601 in the time zone it was given in, filling in
+    /// omitted minutes and seconds
+    pub fn to_iso8601(&self) -> String {
+        let (minute, second, fraction) = self.clock();
+        let mut text = format_iso8601(
+            self.year as i64,
+            self.month,
+            self.day,
+            self.hour,
+            minute,
+            second,
+            fraction.as_deref(),
+        );
+        match self.zone {
+            Zone::Utc => text.push('Z'),
+            Zone::Offset(minutes) => {
+                let sign = if minutes < 0 { '-' } else { '+' };
+                let minutes = minutes.unsigned_abs();
+                text.push_str(&format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60));
+            }
+            Zone::Local => {}
+        }
+        text
+    }
+
+    /// Render a time given with a UTC offset as ISO 8601 in UTC, moving to
+    /// the previous or next day where needed. Times already in UTC or in
+    /// local time have nothing to normalize.
+    pub fn to_utc_iso8601(&self) -> Option<String> {
+        let offset = match self.zone {
+            Zone::Offset(minutes) => minutes as i64,
+            _ => return None,
+        };
+        let (minute, second, fraction) = self.clock();
+        let minutes = self.hour as i64 * 60 + minute as i64 - offset;
+        let days =
+            days_from_civil(self.year as i64, self.month, self.day) + minutes.div_euclid(1440);
+        let (year, month, day) = civil_from_days(days);
+        let minutes = minutes.rem_euclid(1440);
+        let mut text = format_iso8601(
+            year,
+            month,
+            day,
+            (minutes / 60) as u8,
+            (minutes % 60) as u8,
+            second,
+            fraction.as_deref(),
+        );
+        text.push('Z');
+        Some(text)
+    }
+
+    /// Minutes, seconds and fraction of a second, carrying a fraction of an
+    /// hour or minute (2024011512.5 is 12:30) down into the smaller units
+    fn clock(&self) -> (u8, u8, Option<String>) {
+        let (minute, second) = (self.minute.unwrap_or(0), self.second.unwrap_or(0));
+        let digits = match self.fraction.as_deref() {
+            Some(digits) if !digits.is_empty() => digits,
+            _ => return (minute, second, None),
+        };
+        if self.second.is_some() {
+            return (minute, second, Some(digits.to_string()));
+        }
+        let unit: u128 = if self.minute.is_some() { 60 } else { 3600 };
+        // Digits beyond these can't change the result at nanosecond precision
+        let digits = &digits[..digits.len().min(MAX_FRACTION_DIGITS)];
+        let scale = 10u128.pow(digits.len() as u32);
+        let total = digits.parse::<u128>().unwrap_or(0) * unit;
+        let (whole, rest) = (total / scale, total % scale); // Whole seconds below one unit
+        let fraction = format!("{:0width$}", rest, width = digits.len());
+        let fraction = fraction.trim_end_matches('0');
+        (
+            minute + (whole / 60) as u8,
+            (whole % 60) as u8,
+            (!fraction.is_empty()).then(|| fraction.to_string()),
+        )
+    }
+
+    /// Seconds since 1970-01-01T00:00:00Z, leaving out any fraction. A time
+    /// without a zone is taken to be in UTC.
+    pub fn epoch_seconds(&self) -> i64 {
+        let (minute, second, _) = self.clock();
+        let offset = match self.zone {
+            Zone::Offset(minutes) => minutes as i64 * 60,
+            _ => 0,
+        };
+        days_from_civil(self.year as i64, self.month, self.day) * 86400
+            + self.hour as i64 * 3600
+            + minute as i64 * 60
+            + second as i64
+            - offset
+    }
+
+    /// Describe the parts of the value that DER and certificate profiles
+    /// don't allow, which require YYMMDDhhmmssZ or YYYYMMDDhhmmss[.f]Z
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        match (self.minute, self.second) {
+            (None, _) => warnings.push("time has no minutes or seconds".to_string()),
+            (Some(_), None) => warnings.push("time has no seconds".to_string()),
+            _ => {}
+        }
+        if self.two_digit_year && self.fraction.is_some() {
+            warnings.push("UTCTime can't have fractional seconds".to_string());
+        }
+        if let Some(fraction) = &self.fraction {
+            if fraction.is_empty() {
+                warnings.push("decimal mark isn't followed by any digits".to_string());
+            } else if fraction.ends_with('0') {
+                warnings.push("fractional seconds have trailing zeros".to_string());
+            }
+            if self.decimal_comma {
+                warnings.push("decimal mark is a comma rather than a full stop".to_string());
+            }
+        }
+        match self.zone {
+            Zone::Utc => {}
+            Zone::Offset(_) => warnings.push("time zone isn't Z (UTC)".to_string()),
+            Zone::Local => warnings.push("time has no time zone, local time assumed".to_string()),
+        }
+        warnings
+    }
+}
+
+/// Parse a UTCTime: YYMMDDhhmm[ss] followed by Z or a +/-hhmm offset. Two-digit
+/// years below 50 are taken to be in the 21st century, as in RFC 5280.
+/// Fractional seconds and a missing zone aren't valid UTCTime, but they're
+/// accepted here so that the value can be shown along with a warning.
+pub fn parse_utc_time(text: &str) -> Option<Time> {
+    let mut input = Input(text.as_bytes());
+    let year = input.number(2)?;
+    let year = if year < 50 { 2000 + year } else { 1900 + year };
+    let (month, day, hour) = (input.number(2)?, input.number(2)?, input.number(2)?);
+    let minute = input.number(2)?;
+    let second = input.optional_number(2);
+    let (fraction, decimal_comma) = match second {
+        Some(_) => input.fraction(),
+        None => (None, false),
+    };
+    let zone = input.zone()?;
+    input.finish()?;
+    check_ranges(Time {
+        year,
+        month: month as u8,
+        day: day as u8,
+        hour: hour as u8,
+        minute: Some(minute as u8),
+        second: second.map(|s| s as u8),
+        fraction,
+        decimal_comma,
+        zone,
+        two_digit_year: true,
+    })
+}
+
+/// Parse a GeneralizedTime: YYYYMMDDhh[mm[ss]][.fff] followed by an optional Z
+/// or +/-hhmm offset
+pub fn parse_generalized_time(text: &str) -> Option<Time> {
+    let mut input = Input(text.as_bytes());
+    let year = input.number(4)?;
+    let (month, day, hour) = (input.number(2)?, input.number(2)?, input.number(2)?);
+    let minute = input.optional_number(2);
+    let second = minute.and_then(|_| input.optional_number(2));
+    let (fraction, decimal_comma) = input.fraction();
+    let zone = input.zone()?;
+    input.finish()?;
+    check_ranges(Time {
+        year,
+        month: month as u8,
+        day: day as u8,
+        hour: hour as u8,
+        minute: minute.map(|m| m as u8),
+        second: second.map(|s| s as u8),
+        fraction,
+        decimal_comma,
+        zone,
+        two_digit_year: false,
+    })
+}
+
+/// Parse an instant given on the command line: ISO 8601 such as
+/// 2024-06-01T12:00:00Z, 2024-06-01T14:00+02:00 or 2024-06-01 (midnight UTC),
+/// or a count of seconds since the epoch such as @1717243200
+pub fn parse_instant(text: &str) -> Option<i64> {
+    if let Some(seconds) = text.strip_prefix('@') {
+        return seconds.parse().ok();
+    }
+    // Drop the separators of the extended format, leaving GeneralizedTime
+    let (date, rest) = text.split_at(text.len().min(10));
+    let date = date.replace('-', "");
+    let rest = rest.strip_prefix('T').unwrap_or(rest).replace(':', "");
+    let mut compact = date + &rest;
+    if rest.is_empty() {
+        compact.push_str("00");
+    }
+    if !compact.ends_with(['Z', 'z']) && !compact.contains(['+', '-']) {
+        compact.push('Z');
+    }
+    parse_generalized_time(&compact).map(|time| time.epoch_seconds())
+}
+
+/// Render seconds since the epoch as ISO 8601, in UTC or, given an offset
+/// east of UTC in seconds, in that zone
+pub fn format_instant(seconds: i64, offset: Option<i32>) -> String {
+    let local = seconds + offset.unwrap_or(0) as i64;
+    let (year, month, day) = civil_from_days(local.div_euclid(86400));
+    let clock = local.rem_euclid(86400);
+    let mut text = format_iso8601(
+        year,
+        month,
+        day,
+        (clock / 3600) as u8,
+        (clock / 60 % 60) as u8,
+        (clock % 60) as u8,
+        None,
+    );
+    match offset {
+        None => text.push('Z'),
+        Some(offset) => {
+            let sign = if offset < 0 { '-' } else { '+' };
+            let minutes = offset.unsigned_abs() / 60;
+            text.push_str(&format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60));
+        }
+    }
+    text
+}
+
+/// Describe a span of seconds in days, or in hours and minutes when it's
+/// under two days
+pub fn format_span(seconds: u64) -> String {
+    let plural = |n: u64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    match seconds {
+        0..=59 => plural(seconds, "second"),
+        60..=3599 => plural(seconds / 60, "minute"),
+        3600..=172_799 => format!(
+            "{}, {}",
+            plural(seconds / 3600, "hour"),
+            plural(seconds / 60 % 60, "minute")
+        ),
+        _ => plural(seconds / 86400, "day"),
+    }
+}
+
+/// Reject field values outside their basic ranges
+fn check_ranges(time: Time) -> Option<Time> {
+    let valid = (1..=12).contains(&time.month)
+        && (1..=days_in_month(time.year as i64, time.month)).contains(&time.day)
+        && time.hour < 24
+        && time.minute.is_none_or(|m| m < 60)
+        && time.second.is_none_or(|s| s < 61); // Allow for a leap second
+    valid.then_some(time)
+}
+
+fn format_iso8601(
+    year: i64,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    fraction: Option<&str>,
+) -> String {
+    let mut text = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    );
+    if let Some(fraction) = fraction {
+        text.push('.');
+        text.push_str(fraction);
+    }
+    text
+}
+
+fn days_in_month(year: i64, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar
+pub(crate) fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Inverse of days_from_civil
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u8;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u8;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Cursor over the characters of a time string
+struct Input<'a>(&'a [u8]);
+
+impl Input<'_> {
+    fn number(&mut self, digits: usize) -> Option<u16> {
+        let field = self.0.get(..digits)?;
+        if !field.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.0 = &self.0[digits..];
+        Some(field.iter().fold(0, |n, d| n * 10 + (d - b'0') as u16))
+    }
+
+    fn optional_number(&mut self, digits: usize) -> Option<u16> {
+        match self.0.first() {
+            Some(c) if c.is_ascii_digit() => self.number(digits),
+            _ => None,
+        }
+    }
+
+    /// Digits after a decimal mark, and whether the mark was a comma
+    fn fraction(&mut self) -> (Option<String>, bool) {
+        match self.0.first() {
+            Some(&mark @ (b'.' | b',')) => {
+                let digits = self.0[1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .count();
+                let fraction = String::from_utf8_lossy(&self.0[1..1 + digits]).into_owned();
+                self.0 = &self.0[1 + digits..];
+                (Some(fraction), mark == b',')
+            }
+            _ => (None, false),
+        }
+    }
+
+    fn zone(&mut self) -> Option<Zone> {
+        match self.0.first() {
+            None => Some(Zone::Local),
+            Some(b'Z') => {
+                self.0 = &self.0[1..];
+                Some(Zone::Utc)
+            }
+            Some(&sign @ (b'+' | b'-')) => {
+                self.0 = &self.0[1..];
+                let (hours, minutes) = (self.number(2)?, self.number(2)?);
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                let offset = (hours * 60 + minutes) as i16;
+                Some(Zone::Offset(if sign == b'-' { -offset } else { offset }))
+            }
+            Some(_) => None,
+        }
+    }
+
+    fn finish(&self) -> Option<()> {
+        self.0.is_empty().then_some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utc_time() {
+        let time = parse_utc_time("240115123045Z").expect("valid");
+        assert_eq!(time.to_iso8601(), "2024-01-15T12:30:45Z");
+        assert!(time.warnings().is_empty());
+
+        let time = parse_utc_time("9912312359+0100").expect("valid");
+        assert_eq!(time.to_iso8601(), "1999-12-31T23:59:00+01:00");
+        assert_eq!(time.warnings().len(), 2);
+
+        let time = parse_utc_time("240115123045.5").expect("parsed despite errors");
+        assert_eq!(
+            time.warnings(),
+            vec![
+                "UTCTime can't have fractional seconds",
+                "time has no time zone, local time assumed"
+            ]
+        );
+
+        // Two-digit years pivot at 50
+        assert_eq!(parse_utc_time("491231235959Z").map(|t| t.year), Some(2049));
+        assert_eq!(parse_utc_time("500101000000Z").map(|t| t.year), Some(1950));
+
+        assert!(parse_utc_time("241315123045Z").is_none());
+        assert!(parse_utc_time("2401151230Zjunk").is_none());
+    }
+
+    #[test]
+    fn test_generalized_time() {
+        let time = parse_generalized_time("20240115123045.25Z").expect("valid");
+        assert_eq!(time.to_iso8601(), "2024-01-15T12:30:45.25Z");
+        assert!(time.warnings().is_empty());
+
+        let time = parse_generalized_time("2024011512").expect("valid");
+        assert_eq!(time.to_iso8601(), "2024-01-15T12:00:00");
+        assert_eq!(
+            time.warnings(),
+            vec![
+                "time has no minutes or seconds",
+                "time has no time zone, local time assumed"
+            ]
+        );
+
+        let time = parse_generalized_time("20240115123045-0530").expect("valid");
+        assert_eq!(time.to_iso8601(), "2024-01-15T12:30:45-05:30");
+        assert!(parse_generalized_time("2024-01-15").is_none());
+        assert!(parse_generalized_time("20230229000000Z").is_none());
+    }
+
+    #[test]
+    fn test_fractions() {
+        // Fractions of an hour and a minute carry into the smaller units
+        let time = parse_generalized_time("2024011512.5Z").expect("valid");
+        assert_eq!(time.to_iso8601(), "2024-01-15T12:30:00Z");
+        let time = parse_generalized_time("202401151230.25Z").expect("valid");
+        assert_eq!(time.to_iso8601(), "2024-01-15T12:30:15Z");
+        let time = parse_generalized_time("202401151230.0001Z").expect("valid");
+        assert_eq!(time.to_iso8601(), "2024-01-15T12:30:00.006Z");
+
+        let time = parse_generalized_time("20240115123045,50Z").expect("valid");
+        assert_eq!(
+            time.warnings(),
+            vec![
+                "fractional seconds have trailing zeros",
+                "decimal mark is a comma rather than a full stop"
+            ]
+        );
+        let time = parse_generalized_time("20240115123045.Z").expect("valid");
+        assert_eq!(time.to_iso8601(), "2024-01-15T12:30:45Z");
+        assert_eq!(
+            time.warnings(),
+            vec!["decimal mark isn't followed by any digits"]
+        );
+    }
+
+    #[test]
+    fn test_utc_normalization() {
+        let time = parse_generalized_time("20240115123045.5-0530").expect("valid");
+        assert_eq!(
+            time.to_utc_iso8601().as_deref(),
+            Some("2024-01-15T18:00:45.5Z")
+        );
+        // Offsets that cross midnight, month, year and leap day boundaries
+        let utc = |text| parse_generalized_time(text).and_then(|t| t.to_utc_iso8601());
+        assert_eq!(
+            utc("20240101003000+0100").as_deref(),
+            Some("2023-12-31T23:30:00Z")
+        );
+        assert_eq!(
+            utc("20240228233000-0100").as_deref(),
+            Some("2024-02-29T00:30:00Z")
+        );
+        assert_eq!(
+            utc("20231231230000-0200").as_deref(),
+            Some("2024-01-01T01:00:00Z")
+        );
+        assert_eq!(utc("20240115123045Z"), None);
+    }
+    #[test]
+    fn test_instants() {
+        let time = parse_utc_time("240115123045+0100").unwrap();
+        assert_eq!(time.epoch_seconds(), 1_705_318_245);
+        assert_eq!(parse_instant("2024-01-15T11:30:45Z"), Some(1_705_318_245));
+        assert_eq!(
+            parse_instant("2024-01-15T12:30:45+01:00"),
+            Some(1_705_318_245)
+        );
+        assert_eq!(parse_instant("20240115113045Z"), Some(1_705_318_245));
+        assert_eq!(parse_instant("2024-01-15"), Some(1_705_276_800));
+        assert_eq!(parse_instant("@-1"), Some(-1));
+        assert_eq!(parse_instant("yesterday"), None);
+
+        assert_eq!(format_instant(1_705_318_245, None), "2024-01-15T11:30:45Z");
+        assert_eq!(
+            format_instant(1_705_318_245, Some(-18000)),
+            "2024-01-15T06:30:45-05:00"
+        );
+        assert_eq!(format_span(1), "1 second");
+        assert_eq!(format_span(3600), "1 hour, 0 minutes");
+        assert_eq!(format_span(172_800), "2 days");
+    }
+}
@@ -0,0 +1,270 @@
+// X.509 certificate field extraction
+// Pulls out the parts of a certificate needed for cross-certificate checks,
+// such as working out the issuing order of a chain, for checking that the
+// certificate names the same signature algorithm inside and outside the
+// signed part, and not a weak one, and for checking the signature itself
+
+use super::der::{children, decode_oid, parse_tlv};
+use super::oids::oid_name;
+use super::strings::{decode_bmp_string, decode_t61_string, decode_universal_string};
+use super::tags::{BITSTRING, BMPSTRING, OID, T61STRING, UNIVERSALSTRING, VIDEOTEXSTRING};
+
+/// Selected fields of a parsed certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate<'a> {
+    pub issuer: &'a [u8], // Complete Name encodings, for comparison
+    pub subject: &'a [u8],
+    pub signature: &'a [u8], // The AlgorithmIdentifier in tbsCertificate
+    pub signature_algorithm: &'a [u8], // The one after it
+    pub signature_algorithm_offset: usize, // Of the latter, in the certificate
+    pub tbs_certificate: &'a [u8], // The signed part, complete
+    pub subject_public_key_info: &'a [u8],
+    pub signature_value: &'a [u8], // The BIT STRING, complete
+    pub signature_value_offset: usize,
+}
+
+/// Complete encodings of the components of a constructed item
+fn components(encoding: &[u8]) -> Vec<&[u8]> {
+    components_at(encoding)
+        .into_iter()
+        .map(|(_, component)| component)
+        .collect()
+}
+
+/// The components of a constructed item with their offsets in `encoding`
+pub fn components_at(encoding: &[u8]) -> Vec<(usize, &[u8])> {
+    match parse_tlv(encoding) {
+        Some(tlv) if tlv.is_constructed() => {
+            let content = tlv.content(encoding);
+            children(content)
+                .map(|(pos, child)| (tlv.header_len + pos, &content[pos..pos + child.total_len()]))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Extract the issuer, subject, key and signature from a certificate
+/// encoding
+pub fn parse_certificate(data: &[u8]) -> Option<Certificate<'_>> {
+    let certificate = components_at(data);
+    let &(_, tbs_certificate) = certificate.first()?;
+    let &(signature_algorithm_offset, signature_algorithm) = certificate.get(1)?;
+    let &(signature_value_offset, signature_value) = certificate.get(2)?;
+    let tbs = components(tbs_certificate);
+    // Skip the optional [0] version
+    let fields = match tbs.first() {
+        Some(version) if version[0] == 0xA0 => &tbs[1..],
+        _ => &tbs[..],
+    };
+    match fields {
+        [_serial, signature, issuer, _validity, subject, subject_public_key_info, ..]
+            if issuer[0] == 0x30 && subject[0] == 0x30 =>
+        {
+            Some(Certificate {
+                issuer,
+                subject,
+                signature,
+                signature_algorithm,
+                signature_algorithm_offset,
+                tbs_certificate,
+                subject_public_key_info,
+                signature_value,
+                signature_value_offset,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The algorithm OID of an AlgorithmIdentifier encoding, dotted
+pub fn algorithm_oid(encoding: &[u8]) -> Option<String> {
+    let fields = components(encoding);
+    let oid = fields.first().filter(|oid| oid[0] == OID)?;
+    decode_oid(parse_tlv(oid)?.content(oid))
+}
+
+/// The parameters of an AlgorithmIdentifier encoding when they're an OID,
+/// as for the named curve of an EC key, dotted
+pub fn parameters_oid(encoding: &[u8]) -> Option<String> {
+    let fields = components(encoding);
+    let oid = fields.get(1).filter(|oid| oid[0] == OID)?;
+    decode_oid(parse_tlv(oid)?.content(oid))
+}
+
+/// The AlgorithmIdentifier and the key of a SubjectPublicKeyInfo encoding
+pub fn public_key(encoding: &[u8]) -> Option<(&[u8], &[u8])> {
+    match components(encoding).as_slice() {
+        [algorithm, key] => Some((algorithm, bit_string_bytes(key)?)),
+        _ => None,
+    }
+}
+
+/// The content of a BIT STRING encoding as bytes, if it has no unused bits,
+/// as keys and signatures have
+pub fn bit_string_bytes(encoding: &[u8]) -> Option<&[u8]> {
+    let tlv = parse_tlv(encoding).filter(|tlv| tlv.id == BITSTRING)?;
+    match tlv.content(encoding) {
+        [0, bytes @ ..] => Some(bytes),
+        _ => None,
+    }
+}
+
+/// The name of an AlgorithmIdentifier's algorithm, or its dotted OID
+pub fn algorithm_name(encoding: &[u8]) -> String {
+    match algorithm_oid(encoding) {
+        Some(oid) => oid_name(&oid).map_or(oid, str::to_string),
+        None => "(unrecognized AlgorithmIdentifier)".to_string(),
+    }
+}
+
+/// The broken hash function a signature algorithm relies on, for the
+/// RSA, DSA and ECDSA signatures made with MD2, MD5 or SHA-1
+pub fn weak_signature_hash(oid: &str) -> Option<&'static str> {
+    match oid {
+        "1.2.840.113549.1.1.2" => Some("MD2"),
+        "1.2.840.113549.1.1.4" => Some("MD5"),
+        "1.2.840.113549.1.1.5" | "1.3.14.3.2.29" | "1.2.840.10040.4.3" | "1.2.840.10045.4.1" => {
+            Some("SHA-1")
+        }
+        _ => None,
+    }
+}
+
+/// Short names for the attribute types commonly found in DNs
+pub fn attribute_label(oid: &str) -> Option<&'static str> {
+    match oid {
+        "2.5.4.3" => Some("CN"),
+        "2.5.4.5" => Some("serialNumber"),
+        "2.5.4.6" => Some("C"),
+        "2.5.4.7" => Some("L"),
+        "2.5.4.8" => Some("ST"),
+        "2.5.4.9" => Some("street"),
+        "2.5.4.10" => Some("O"),
+        "2.5.4.11" => Some("OU"),
+        "0.9.2342.19200300.100.1.25" => Some("DC"),
+        "0.9.2342.19200300.100.1.1" => Some("UID"),
+        "1.2.840.113549.1.9.1" => Some("emailAddress"),
+        _ => None,
+    }
+}
+
+/// Decode a directory string value for display
+fn string_value(encoding: &[u8]) -> String {
+    let tlv = match parse_tlv(encoding) {
+        Some(tlv) => tlv,
+        None => return "?".to_string(),
+    };
+    let content = tlv.content(encoding);
+    match tlv.id {
+        BMPSTRING => decode_bmp_string(content),
+        UNIVERSALSTRING => decode_universal_string(content),
+        T61STRING | VIDEOTEXSTRING => decode_t61_string(content),
+        _ => String::from_utf8_lossy(content).into_owned(),
+    }
+}
+
+/// Render a Name as a one-line string such as `CN=Test CA, O=Example`
+pub fn name_to_string(name: &[u8]) -> String {
+    let mut parts = Vec::new();
+    for rdn in components(name) {
+        for attribute in components(rdn) {
+            let fields = components(attribute);
+            let (attr_type, value) = match fields.as_slice() {
+                [attr_type, value] if attr_type[0] == OID => (attr_type, value),
+                _ => continue,
+            };
+            let oid = parse_tlv(attr_type)
+                .and_then(|tlv| decode_oid(tlv.content(attr_type)))
+                .unwrap_or_default();
+            let label = attribute_label(&oid).map(str::to_string).unwrap_or(oid);
+            parts.push(format!("{}={}", label, string_value(value)));
+        }
+    }
+    if parts.is_empty() {
+        "(empty name)".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::DerBuilder;
+
+    // Name { CN=Test, O=Ex }
+    fn name() -> Vec<u8> {
+        DerBuilder::new()
+            .sequence(|b| {
+                b.set(|b| b.sequence(|b| b.oid("2.5.4.3").utf8_string("Test")))
+                    .set(|b| b.sequence(|b| b.oid("2.5.4.10").utf8_string("Ex")))
+            })
+            .finish()
+    }
+
+    #[test]
+    fn test_name_to_string() {
+        assert_eq!(name_to_string(&name()), "CN=Test, O=Ex");
+        assert_eq!(name_to_string(&[0x30, 0x00]), "(empty name)");
+    }
+
+    #[test]
+    fn test_parse_certificate() {
+        let name = name();
+        let cert = DerBuilder::new()
+            .sequence(|b| {
+                b.sequence(|b| {
+                    b.explicit(0, |b| b.integer(2))
+                        .integer(1)
+                        .sequence(|b| b)
+                        .raw(&name)
+                        .sequence(|b| b)
+                        .raw(&name)
+                        .sequence(|b| b)
+                })
+                .sequence(|b| b)
+                .bit_string(&[], 0)
+            })
+            .finish();
+
+        let parsed = parse_certificate(&cert).expect("should parse");
+        assert_eq!(parsed.issuer, name);
+        assert_eq!(parsed.subject, name);
+        assert_eq!(parsed.signature, [0x30, 0x00]);
+        assert_eq!(
+            &cert[parsed.signature_algorithm_offset..][..2],
+            parsed.signature_algorithm
+        );
+        assert_eq!(parsed.tbs_certificate[0], 0x30);
+        assert_eq!(
+            parsed.tbs_certificate.len(),
+            parsed.signature_algorithm_offset - 2
+        );
+        assert_eq!(parsed.subject_public_key_info, [0x30, 0x00]);
+        assert_eq!(
+            &cert[parsed.signature_value_offset..],
+            parsed.signature_value
+        );
+        assert_eq!(bit_string_bytes(parsed.signature_value), Some(&[][..]));
+        assert!(parse_certificate(&[0x30, 0x00]).is_none());
+    }
+    #[test]
+    fn test_signature_algorithms() {
+        let sha1 = DerBuilder::new()
+            .sequence(|b| b.oid("1.2.840.113549.1.1.5").null())
+            .finish();
+        assert_eq!(
+            algorithm_oid(&sha1).as_deref(),
+            Some("1.2.840.113549.1.1.5")
+        );
+        assert_eq!(algorithm_name(&sha1), "sha1WithRSAEncryption");
+        assert_eq!(weak_signature_hash("1.2.840.113549.1.1.5"), Some("SHA-1"));
+        assert_eq!(weak_signature_hash("1.2.840.113549.1.1.4"), Some("MD5"));
+        assert_eq!(weak_signature_hash("1.2.840.113549.1.1.11"), None);
+        assert_eq!(
+            algorithm_name(&[0x30, 0x00]),
+            "(unrecognized AlgorithmIdentifier)"
+        );
+    }
+}
@@ -0,0 +1,375 @@
+// The local time zone, for showing certificate validity times in local time
+// as well as UTC. The zone comes from TZ when it's set, either as the name
+// of a zoneinfo file or as a POSIX rule such as CET-1CEST,M3.5.0,M10.5.0/3,
+// and otherwise from /etc/localtime. Zoneinfo files are read in the TZif
+// format of RFC 8536, using the rule in their footer past the last
+// transition they list.
+
+use std::env;
+use std::fs;
+
+use super::time::days_from_civil;
+
+const ZONEINFO: &str = "/usr/share/zoneinfo";
+const LOCALTIME: &str = "/etc/localtime";
+
+/// The offset of a time zone from UTC at some instant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offset {
+    pub seconds: i32, // East of UTC
+    pub abbreviation: String,
+}
+
+/// The local zone's offset at `at`, in seconds since the epoch, if the zone
+/// can be worked out
+pub fn local_offset(at: i64) -> Option<Offset> {
+    match env::var("TZ") {
+        Ok(tz) => zone_offset(&tz, at),
+        Err(_) => parse_tzif(&fs::read(LOCALTIME).ok()?, at),
+    }
+}
+
+/// The offset at `at` of the zone a TZ value names
+pub fn zone_offset(tz: &str, at: i64) -> Option<Offset> {
+    let name = tz.strip_prefix(':').unwrap_or(tz);
+    if name.is_empty() {
+        return Some(Offset {
+            seconds: 0,
+            abbreviation: "UTC".to_string(),
+        });
+    }
+    let path = if name.starts_with('/') {
+        name.to_string()
+    } else {
+        format!("{}/{}", ZONEINFO, name)
+    };
+    match fs::read(path) {
+        Ok(data) if !name.contains("..") => parse_tzif(&data, at),
+        _ => posix_offset(name, at),
+    }
+}
+
+/// The offset at `at` that a TZif file gives
+fn parse_tzif(data: &[u8], at: i64) -> Option<Offset> {
+    let mut reader = Reader(data);
+    let header = reader.header()?;
+    let (header, time_size) = if header.version >= b'2' {
+        // Skip the 32-bit block for the 64-bit one after it
+        reader.take(header.block_len(4))?;
+        (reader.header()?, 8)
+    } else {
+        (header, 4)
+    };
+    let times: Vec<i64> = (0..header.time_count)
+        .map(|_| reader.int(time_size))
+        .collect::<Option<_>>()?;
+    let indexes = reader.take(header.time_count)?;
+    let types: Vec<(i32, usize)> = (0..header.type_count)
+        .map(|_| {
+            let offset = reader.int(4)? as i32;
+            let _is_dst = reader.take(1)?;
+            Some((offset, *reader.take(1)?.first()? as usize))
+        })
+        .collect::<Option<_>>()?;
+    let abbreviations = reader.take(header.char_count)?;
+    reader.take(header.leap_count * (time_size + 4) + header.std_count + header.ut_count)?;
+
+    // Past the last transition, the footer's rule applies
+    let footer = std::str::from_utf8(reader.0).ok().map(str::trim);
+    if times.last().is_none_or(|&last| at >= last) {
+        if let Some(offset) = footer.and_then(|rule| posix_offset(rule, at)) {
+            return Some(offset);
+        }
+    }
+    let index = match times.iter().rposition(|&time| time <= at) {
+        Some(transition) => *indexes.get(transition)? as usize,
+        None => 0,
+    };
+    let &(seconds, start) = types.get(index)?;
+    let abbreviation = abbreviations.get(start..)?;
+    let end = abbreviation.iter().position(|&c| c == 0)?;
+    Some(Offset {
+        seconds,
+        abbreviation: String::from_utf8_lossy(&abbreviation[..end]).into_owned(),
+    })
+}
+
+/// The counts in a TZif header
+struct Header {
+    version: u8,
+    ut_count: usize,
+    std_count: usize,
+    leap_count: usize,
+    time_count: usize,
+    type_count: usize,
+    char_count: usize,
+}
+
+impl Header {
+    /// The length of the data block the header describes, with times of
+    /// `time_size` bytes
+    fn block_len(&self, time_size: usize) -> usize {
+        self.time_count * (time_size + 1)
+            + self.type_count * 6
+            + self.char_count
+            + self.leap_count * (time_size + 4)
+            + self.std_count
+            + self.ut_count
+    }
+}
+
+/// Cursor over the bytes of a TZif file
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.0.get(..len)?;
+        self.0 = &self.0[len..];
+        Some(bytes)
+    }
+
+    /// A big-endian signed integer of 4 or 8 bytes
+    fn int(&mut self, size: usize) -> Option<i64> {
+        let bytes = self.take(size)?;
+        let value = bytes.iter().fold(0u64, |n, &b| (n << 8) | b as u64);
+        Some(if size == 4 {
+            value as u32 as i32 as i64
+        } else {
+            value as i64
+        })
+    }
+
+    fn header(&mut self) -> Option<Header> {
+        if self.take(4)? != b"TZif" {
+            return None;
+        }
+        let version = *self.take(1)?.first()?;
+        self.take(15)?;
+        let mut count = || self.int(4).map(|n| n as u32 as usize);
+        Some(Header {
+            version,
+            ut_count: count()?,
+            std_count: count()?,
+            leap_count: count()?,
+            time_count: count()?,
+            type_count: count()?,
+            char_count: count()?,
+        })
+    }
+}
+
+/// The offset at `at` that a POSIX TZ rule gives, such as EST5EDT or
+/// CET-1CEST,M3.5.0,M10.5.0/3. Daylight saving time is applied for rules
+/// whose dates are given as Mm.w.d, the form zoneinfo files use.
+fn posix_offset(rule: &str, at: i64) -> Option<Offset> {
+    let mut rule = Rule(rule);
+    let std_name = rule.name()?;
+    // POSIX offsets are west of UTC
+    let std_offset = -rule.seconds()?;
+    if rule.0.is_empty() {
+        return Some(Offset {
+            seconds: std_offset as i32,
+            abbreviation: std_name,
+        });
+    }
+    let dst_name = rule.name()?;
+    let dst_offset = match rule.0.starts_with(',') {
+        true => std_offset + 3600,
+        false => -rule.seconds()?,
+    };
+    let in_dst = match rule.0.strip_prefix(',') {
+        Some(dates) => {
+            rule.0 = dates;
+            let (start, start_time) = rule.date()?;
+            rule.0 = rule.0.strip_prefix(',')?;
+            let (end, end_time) = rule.date()?;
+            let year = civil_year(at + std_offset);
+            let start = start.day(year)? * 86400 + start_time - std_offset;
+            let end = end.day(year)? * 86400 + end_time - dst_offset;
+            if start < end {
+                (start..end).contains(&at)
+            } else {
+                !(end..start).contains(&at)
+            }
+        }
+        None => false,
+    };
+    let (seconds, abbreviation) = match in_dst {
+        true => (dst_offset, dst_name),
+        false => (std_offset, std_name),
+    };
+    Some(Offset {
+        seconds: seconds as i32,
+        abbreviation,
+    })
+}
+
+/// The year in which the day holding `seconds` since the epoch falls
+fn civil_year(seconds: i64) -> i64 {
+    let mut year = 1970 + seconds.div_euclid(86400 * 365 + 86400 / 4);
+    while days_from_civil(year, 1, 1) * 86400 > seconds {
+        year -= 1;
+    }
+    while days_from_civil(year + 1, 1, 1) * 86400 <= seconds {
+        year += 1;
+    }
+    year
+}
+
+/// A transition date of the Mm.w.d form: weekday `day` (0 for Sunday) of
+/// week `week` (5 for the last) of `month`
+struct RuleDate {
+    month: u8,
+    week: u8,
+    day: u8,
+}
+
+impl RuleDate {
+    /// The date in `year`, in days since the epoch
+    fn day(&self, year: i64) -> Option<i64> {
+        if !(1..=12).contains(&self.month) || !(1..=5).contains(&self.week) || self.day > 6 {
+            return None;
+        }
+        let first = days_from_civil(year, self.month, 1);
+        let next_month = match self.month {
+            12 => days_from_civil(year + 1, 1, 1),
+            month => days_from_civil(year, month + 1, 1),
+        };
+        // 1970-01-01 was a Thursday
+        let weekday = (first + 4).rem_euclid(7);
+        let mut day =
+            first + (self.day as i64 - weekday).rem_euclid(7) + (self.week as i64 - 1) * 7;
+        while day >= next_month {
+            day -= 7;
+        }
+        Some(day)
+    }
+}
+
+/// Cursor over the text of a POSIX TZ rule
+struct Rule<'a>(&'a str);
+
+impl Rule<'_> {
+    /// A zone abbreviation: letters, or anything between < and >
+    fn name(&mut self) -> Option<String> {
+        let (name, rest) = match self.0.strip_prefix('<') {
+            Some(quoted) => {
+                let end = quoted.find('>')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => {
+                let end = self
+                    .0
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .unwrap_or(self.0.len());
+                self.0.split_at(end)
+            }
+        };
+        self.0 = rest;
+        (!name.is_empty()).then(|| name.to_string())
+    }
+
+    /// A time of [+-]hh[:mm[:ss]], in seconds
+    fn seconds(&mut self) -> Option<i64> {
+        let (sign, rest) = match self.0.as_bytes().first() {
+            Some(b'-') => (-1, &self.0[1..]),
+            Some(b'+') => (1, &self.0[1..]),
+            _ => (1, self.0),
+        };
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != ':')
+            .unwrap_or(rest.len());
+        let mut seconds = 0;
+        let mut parts = rest[..end].split(':');
+        for scale in [3600, 60, 1] {
+            match parts.next() {
+                Some(part) => seconds += part.parse::<i64>().ok()? * scale,
+                None if scale == 3600 => return None,
+                None => break,
+            }
+        }
+        self.0 = &rest[end..];
+        Some(sign * seconds)
+    }
+
+    /// A date of Mm.w.d with an optional /time, 02:00 by default
+    fn date(&mut self) -> Option<(RuleDate, i64)> {
+        let rest = self.0.strip_prefix('M')?;
+        let end = rest.find([',', '/']).unwrap_or(rest.len());
+        let mut fields = rest[..end].split('.').map(|field| field.parse::<u8>().ok());
+        let date = RuleDate {
+            month: fields.next()??,
+            week: fields.next()??,
+            day: fields.next()??,
+        };
+        self.0 = &rest[end..];
+        let time = match self.0.strip_prefix('/') {
+            Some(time) => {
+                self.0 = time;
+                self.seconds()?
+            }
+            None => 7200,
+        };
+        Some((date, time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posix_offset() {
+        // 2024-07-01T00:00:00Z and 2024-01-01T00:00:00Z
+        let (summer, winter) = (1_719_792_000, 1_704_067_200);
+        let rule = "CET-1CEST,M3.5.0,M10.5.0/3";
+        assert_eq!(
+            posix_offset(rule, summer),
+            Some(Offset {
+                seconds: 7200,
+                abbreviation: "CEST".to_string()
+            })
+        );
+        assert_eq!(posix_offset(rule, winter).unwrap().seconds, 3600);
+        // The switch on 2024-03-31 at 01:00 UTC
+        assert_eq!(posix_offset(rule, 1_711_846_799).unwrap().seconds, 3600);
+        assert_eq!(posix_offset(rule, 1_711_846_800).unwrap().seconds, 7200);
+        // Southern hemisphere summer spans the new year
+        let rule = "AEST-10AEDT,M10.1.0,M4.1.0/3";
+        assert_eq!(posix_offset(rule, winter).unwrap().abbreviation, "AEDT");
+        assert_eq!(posix_offset(rule, summer).unwrap().seconds, 36000);
+        assert_eq!(posix_offset("<+0530>-5:30", 0).unwrap().seconds, 19800);
+        assert_eq!(posix_offset("EST5", 0).unwrap().seconds, -18000);
+        assert_eq!(posix_offset("5", 0), None);
+    }
+
+    #[test]
+    fn test_parse_tzif() {
+        // A version 1 file with one transition, at 1000, from XST (+1:00) to
+        // YST (-2:00)
+        let mut data = b"TZif\0".to_vec();
+        data.extend([0; 15]);
+        for count in [0u32, 0, 0, 1, 2, 8] {
+            data.extend(count.to_be_bytes());
+        }
+        data.extend(1000u32.to_be_bytes());
+        data.push(1);
+        data.extend(3600i32.to_be_bytes());
+        data.extend([0, 0]);
+        data.extend((-7200i32).to_be_bytes());
+        data.extend([0, 4]);
+        data.extend(b"XST\0YST\0");
+        assert_eq!(parse_tzif(&data, 999).unwrap().abbreviation, "XST");
+        assert_eq!(parse_tzif(&data, 1000).unwrap().seconds, -7200);
+        assert_eq!(parse_tzif(&data[..40], 0), None);
+        assert_eq!(parse_tzif(b"not a zone file", 0), None);
+    }
+
+    #[test]
+    fn test_civil_year() {
+        assert_eq!(civil_year(0), 1970);
+        assert_eq!(civil_year(-1), 1969);
+        assert_eq!(civil_year(1_704_067_199), 2023);
+        assert_eq!(civil_year(1_704_067_200), 2024);
+    }
+}
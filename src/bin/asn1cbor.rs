@@ -0,0 +1,8 @@
+// ASN.1 and CBOR tools under one command: dump-asn1, dump-cbor, diff,
+// convert and lint
+
+use asn1_cbor_tools::cli;
+
+fn main() {
+    cli::main(None);
+}
@@ -0,0 +1,7 @@
+// ASN.1 DER dumper, the dump-asn1 command of asn1cbor on its own
+
+use asn1_cbor_tools::cli::{self, Command};
+
+fn main() {
+    cli::main(Some(Command::DumpAsn1));
+}
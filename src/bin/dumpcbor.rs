@@ -0,0 +1,7 @@
+// CBOR dumper, the dump-cbor command of asn1cbor on its own
+
+use asn1_cbor_tools::cli::{self, Command};
+
+fn main() {
+    cli::main(Some(Command::DumpCbor));
+}
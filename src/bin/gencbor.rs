@@ -0,0 +1,7 @@
+// Random CBOR generator, the gen-cbor command of asn1cbor on its own
+
+use asn1_cbor_tools::cli::{self, Command};
+
+fn main() {
+    cli::main(Some(Command::GenCbor));
+}
@@ -0,0 +1,515 @@
+// C509 certificates (draft-ietf-cose-cbor-encoded-cert)
+// Recognizes the CBOR array form of a C509 certificate, names its elements,
+// and decodes the compressed representations of names, times, algorithms and
+// extensions into something readable
+
+use super::{CborItem, CborValue};
+use crate::common::hex::hex_upper;
+
+/// Name and interpretation attached to an element of a labeled array, or
+/// the value of a pair in a labeled map
+#[derive(Debug)]
+pub struct Label {
+    pub name: &'static str, // Empty to leave the element unnamed
+    pub note: Option<String>,
+    // Labels for the elements of an array value or the values of a map's
+    // pairs, or if `encoded` is set, for those of the CBOR item that a byte
+    // string value holds, which is dumped below it
+    pub children: Vec<Label>,
+    pub encoded: bool,
+}
+
+/// Element names for the current draft layout, where the signature algorithm
+/// follows the serial number
+const FIELDS: &[&str] = &[
+    "c509CertificateType",
+    "certificateSerialNumber",
+    "issuerSignatureAlgorithm",
+    "issuer",
+    "validityNotBefore",
+    "validityNotAfter",
+    "subject",
+    "subjectPublicKeyAlgorithm",
+    "subjectPublicKey",
+    "extensions",
+    "issuerSignatureValue",
+];
+
+/// Element names for earlier drafts, where the signature algorithm comes
+/// just before the signature
+const LEGACY_FIELDS: &[&str] = &[
+    "c509CertificateType",
+    "certificateSerialNumber",
+    "issuer",
+    "validityNotBefore",
+    "validityNotAfter",
+    "subject",
+    "subjectPublicKeyAlgorithm",
+    "subjectPublicKey",
+    "extensions",
+    "issuerSignatureAlgorithm",
+    "issuerSignatureValue",
+];
+
+/// Attribute types from the C509 Attributes Registry
+const ATTRIBUTES: &[&str] = &[
+    "emailAddress",
+    "CN",
+    "SN",
+    "serialNumber",
+    "C",
+    "L",
+    "ST",
+    "street",
+    "O",
+    "OU",
+    "title",
+    "businessCategory",
+    "postalCode",
+    "GN",
+    "initials",
+    "generationQualifier",
+    "dnQualifier",
+    "pseudonym",
+    "organizationIdentifier",
+    "jurisdictionL",
+    "jurisdictionST",
+    "jurisdictionC",
+    "DC",
+];
+
+/// Extensions from the C509 Extensions Registry
+fn extension_name(id: u64) -> Option<&'static str> {
+    match id {
+        1 => Some("subjectKeyIdentifier"),
+        2 => Some("keyUsage"),
+        3 => Some("subjectAltName"),
+        4 => Some("basicConstraints"),
+        5 => Some("cRLDistributionPoints"),
+        6 => Some("certificatePolicies"),
+        7 => Some("authorityKeyIdentifier"),
+        8 => Some("extKeyUsage"),
+        9 => Some("authorityInfoAccess"),
+        10 => Some("signedCertificateTimestampList"),
+        24 => Some("subjectDirectoryAttributes"),
+        25 => Some("issuerAltName"),
+        26 => Some("nameConstraints"),
+        27 => Some("policyMappings"),
+        28 => Some("policyConstraints"),
+        29 => Some("freshestCRL"),
+        30 => Some("inhibitAnyPolicy"),
+        31 => Some("subjectInfoAccess"),
+        _ => None,
+    }
+}
+
+/// Signature algorithms from the C509 Signature Algorithms Registry
+fn signature_algorithm(id: i64) -> Option<&'static str> {
+    match id {
+        -256 => Some("sha1WithRSAEncryption"),
+        -255 => Some("ecdsa-with-SHA1"),
+        0 => Some("ecdsa-with-SHA256"),
+        1 => Some("ecdsa-with-SHA384"),
+        2 => Some("ecdsa-with-SHA512"),
+        3 => Some("ecdsa-with-SHAKE128"),
+        4 => Some("ecdsa-with-SHAKE256"),
+        12 => Some("Ed25519"),
+        13 => Some("Ed448"),
+        23 => Some("sha256WithRSAEncryption"),
+        24 => Some("sha384WithRSAEncryption"),
+        25 => Some("sha512WithRSAEncryption"),
+        26 => Some("RSASSA-PSS with SHA-256"),
+        27 => Some("RSASSA-PSS with SHA-384"),
+        28 => Some("RSASSA-PSS with SHA-512"),
+        _ => None,
+    }
+}
+
+/// Public key algorithms from the C509 Public Key Algorithms Registry
+fn public_key_algorithm(id: i64) -> Option<&'static str> {
+    match id {
+        0 => Some("rsaEncryption"),
+        1 => Some("EC public key on secp256r1"),
+        2 => Some("EC public key on secp384r1"),
+        3 => Some("EC public key on secp521r1"),
+        8 => Some("X25519"),
+        9 => Some("X448"),
+        10 => Some("Ed25519"),
+        11 => Some("Ed448"),
+        _ => None,
+    }
+}
+
+const KEY_USAGE_BITS: &[&str] = &[
+    "digitalSignature",
+    "nonRepudiation",
+    "keyEncipherment",
+    "dataEncipherment",
+    "keyAgreement",
+    "keyCertSign",
+    "cRLSign",
+    "encipherOnly",
+    "decipherOnly",
+];
+
+fn key_purpose(id: u64) -> Option<&'static str> {
+    match id {
+        1 => Some("serverAuth"),
+        2 => Some("clientAuth"),
+        3 => Some("codeSigning"),
+        4 => Some("emailProtection"),
+        8 => Some("timeStamping"),
+        9 => Some("OCSPSigning"),
+        _ => None,
+    }
+}
+
+fn as_int(item: &CborItem) -> Option<i64> {
+    match item.value {
+        CborValue::Unsigned(_) | CborValue::Negative(_) => {
+            i64::try_from(item.value.integer()?).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Labels for the elements of an array that has the shape of a C509
+/// certificate
+pub fn certificate_labels(items: &[CborItem]) -> Option<Vec<Label>> {
+    let fields = field_names(items)?;
+    let labels = fields
+        .iter()
+        .zip(items)
+        .map(|(&name, item)| match (name, &item.value) {
+            ("extensions", CborValue::Array(extensions)) => Label {
+                name,
+                note: None,
+                children: extension_labels(extensions),
+                encoded: false,
+            },
+            // A lone integer is a keyUsage extension, negative if critical
+            ("extensions", _) => Label {
+                name,
+                note: as_int(item)
+                    .and_then(describe_key_usage)
+                    .map(|usage| format!("keyUsage: {}", usage)),
+                children: Vec::new(),
+                encoded: false,
+            },
+            _ => Label {
+                name,
+                note: describe(name, item),
+                children: Vec::new(),
+                encoded: false,
+            },
+        })
+        .collect();
+    Some(labels)
+}
+
+/// Labels for the (extensionID, extensionValue) pairs of an Extensions array
+fn extension_labels(items: &[CborItem]) -> Vec<Label> {
+    items
+        .chunks(2)
+        .flat_map(|pair| {
+            let id = &pair[0];
+            let value = pair.get(1);
+            [
+                Label {
+                    name: "extensionID",
+                    note: describe_extension_id(id),
+                    children: Vec::new(),
+                    encoded: false,
+                },
+                Label {
+                    name: "extensionValue",
+                    note: value.and_then(|value| describe_extension_value(id, value)),
+                    children: Vec::new(),
+                    encoded: false,
+                },
+            ]
+        })
+        .collect()
+}
+
+/// Element names for an array that has the shape of a C509 certificate
+fn field_names(items: &[CborItem]) -> Option<&'static [&'static str]> {
+    if items.len() != 11
+        || !matches!(items[0].value, CborValue::Unsigned(0..=3))
+        || !matches!(items[1].value, CborValue::Bytes(_))
+    {
+        return None;
+    }
+    // notBefore is always an integer, which tells the two layouts apart
+    let is_time = |item: &CborItem| matches!(item.value, CborValue::Unsigned(_));
+    if is_time(&items[4]) && !is_time(&items[3]) {
+        Some(FIELDS)
+    } else if is_time(&items[3]) {
+        Some(LEGACY_FIELDS)
+    } else {
+        None
+    }
+}
+
+/// Readable interpretation of a C509 certificate element, if it needs one
+fn describe(field: &str, item: &CborItem) -> Option<String> {
+    match field {
+        "c509CertificateType" => match as_int(item)? {
+            0 | 2 => Some("natively signed C509 certificate".to_string()),
+            1 | 3 => Some("CBOR re-encoded X.509 certificate".to_string()),
+            _ => None,
+        },
+        "issuer" | "subject" => describe_name(item),
+        "validityNotBefore" => describe_time(item),
+        "validityNotAfter" => match item.value {
+            CborValue::Null => Some("no well-defined expiration date".to_string()),
+            _ => describe_time(item),
+        },
+        "issuerSignatureAlgorithm" => describe_algorithm(item, signature_algorithm),
+        "subjectPublicKeyAlgorithm" => describe_algorithm(item, public_key_algorithm),
+        _ => None,
+    }
+}
+
+fn describe_algorithm(
+    item: &CborItem,
+    registry: fn(i64) -> Option<&'static str>,
+) -> Option<String> {
+    match &item.value {
+        CborValue::Array(parts) => match parts.first().map(|part| &part.value) {
+            Some(CborValue::Bytes(oid)) => decode_oid(oid),
+            _ => None,
+        },
+        CborValue::Bytes(oid) => decode_oid(oid),
+        _ => registry(as_int(item)?).map(str::to_string),
+    }
+}
+
+/// Name: a text string for a lone common name, bytes for an EUI-64 common
+/// name, or an array of (attribute type, value) pairs
+fn describe_name(item: &CborItem) -> Option<String> {
+    match &item.value {
+        CborValue::Text(cn) => Some(format!("CN={}", cn)),
+        CborValue::Bytes(eui) => Some(format!("CN={}", hex_upper(eui, "-"))),
+        CborValue::Null => Some("same as subject".to_string()),
+        CborValue::Array(parts) => {
+            let mut attributes = Vec::new();
+            for pair in parts.chunks(2) {
+                // Negative attribute types mark PrintableString values
+                let label = match as_int(&pair[0]) {
+                    Some(id) => ATTRIBUTES
+                        .get(id.unsigned_abs() as usize)
+                        .map(|name| name.to_string())
+                        .unwrap_or_else(|| format!("attribute {}", id)),
+                    None => match &pair[0].value {
+                        CborValue::Bytes(oid) => decode_oid(oid)?,
+                        _ => return None,
+                    },
+                };
+                let value = match pair.get(1).map(|value| &value.value) {
+                    Some(CborValue::Text(text)) => text.to_string(),
+                    Some(CborValue::Bytes(bytes)) => hex_upper(bytes, ""),
+                    _ => "?".to_string(),
+                };
+                attributes.push(format!("{}={}", label, value));
+            }
+            Some(attributes.join(", "))
+        }
+        _ => None,
+    }
+}
+
+/// ~time: seconds since the epoch, shown as a UTC date and time
+fn describe_time(item: &CborItem) -> Option<String> {
+    match item.value {
+        CborValue::Unsigned(seconds) => Some(format_epoch(seconds)),
+        _ => None,
+    }
+}
+
+/// Format seconds since 1970 as an ISO 8601 UTC timestamp
+fn format_epoch(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let secs = seconds % 86400;
+
+    // Civil-from-days conversion for the proleptic Gregorian calendar
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+/// Name and criticality of an extension identifier. Registered extensions
+/// are small integers, negated when the extension is critical.
+fn describe_extension_id(item: &CborItem) -> Option<String> {
+    let id = as_int(item)?;
+    let name = extension_name(id.unsigned_abs())?;
+    Some(if id < 0 {
+        format!("{}, critical", name)
+    } else {
+        name.to_string()
+    })
+}
+
+/// Decoded value of a registered extension, for the common compact forms
+fn describe_extension_value(id: &CborItem, value: &CborItem) -> Option<String> {
+    match as_int(id)?.unsigned_abs() {
+        2 => describe_key_usage(as_int(value)?),
+        4 => match as_int(value)? {
+            -2 => Some("cA FALSE".to_string()),
+            -1 => Some("cA TRUE".to_string()),
+            n => Some(format!("cA TRUE, pathLenConstraint {}", n)),
+        },
+        8 => {
+            let purposes: Vec<String> = match &value.value {
+                CborValue::Array(ids) => ids.iter().filter_map(describe_key_purpose).collect(),
+                _ => vec![describe_key_purpose(value)?],
+            };
+            Some(purposes.join(", "))
+        }
+        _ => None,
+    }
+}
+
+fn describe_key_purpose(item: &CborItem) -> Option<String> {
+    match &item.value {
+        CborValue::Unsigned(id) => Some(
+            key_purpose(*id)
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("key purpose {}", id)),
+        ),
+        CborValue::Bytes(oid) => decode_oid(oid),
+        _ => None,
+    }
+}
+
+/// KeyUsage bits, bit 0 being digitalSignature
+fn describe_key_usage(bits: i64) -> Option<String> {
+    let bits = bits.unsigned_abs();
+    let names: Vec<&str> = KEY_USAGE_BITS
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| bits & (1 << bit) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if names.is_empty() {
+        None
+    } else {
+        Some(names.join(", "))
+    }
+}
+
+/// Decode DER OBJECT IDENTIFIER content octets (unwrapped ~oid)
+fn decode_oid(data: &[u8]) -> Option<String> {
+    if data.is_empty() || data[data.len() - 1] & 0x80 != 0 {
+        return None;
+    }
+    let mut arcs = Vec::new();
+    let mut value: u64 = 0;
+    for &byte in data {
+        if value > (u64::MAX >> 7) {
+            return None;
+        }
+        value = (value << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            arcs.push(value);
+            value = 0;
+        }
+    }
+    let first = arcs[0];
+    let (arc1, arc2) = match first {
+        0..=39 => (0, first),
+        40..=79 => (1, first - 40),
+        _ => (2, first - 80),
+    };
+    let mut oid = format!("{}.{}", arc1, arc2);
+    for arc in &arcs[1..] {
+        oid.push_str(&format!(".{}", arc));
+    }
+    Some(oid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testutil::item;
+
+    #[test]
+    fn test_format_epoch() {
+        assert_eq!(format_epoch(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_epoch(1_800_000_000), "2027-01-15T08:00:00Z");
+        assert_eq!(format_epoch(951_782_400), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_describe_name() {
+        let name = item(CborValue::Array(vec![
+            item(CborValue::Unsigned(1)),
+            item(CborValue::Text("RFC test CA".into())),
+            item(CborValue::Negative(3)),
+            item(CborValue::Text("SE".into())),
+        ]));
+        assert_eq!(
+            describe("issuer", &name).as_deref(),
+            Some("CN=RFC test CA, C=SE")
+        );
+        let eui = item(CborValue::Bytes(vec![0x01, 0x23, 0x45, 0xFF, 0xFE].into()));
+        assert_eq!(
+            describe("subject", &eui).as_deref(),
+            Some("CN=01-23-45-FF-FE")
+        );
+    }
+
+    #[test]
+    fn test_describe_extensions() {
+        let key_usage = item(CborValue::Negative(1));
+        assert_eq!(
+            describe_extension_id(&key_usage).as_deref(),
+            Some("keyUsage, critical")
+        );
+        let bits = item(CborValue::Unsigned(0x21));
+        assert_eq!(
+            describe_extension_value(&key_usage, &bits).as_deref(),
+            Some("digitalSignature, keyCertSign")
+        );
+        let basic = item(CborValue::Unsigned(4));
+        assert_eq!(
+            describe_extension_value(&basic, &item(CborValue::Negative(1))).as_deref(),
+            Some("cA FALSE")
+        );
+    }
+
+    #[test]
+    fn test_field_names_layouts() {
+        let mut items: Vec<CborItem> = (0..11).map(|_| item(CborValue::Null)).collect();
+        items[0] = item(CborValue::Unsigned(2));
+        items[1] = item(CborValue::Bytes(vec![1].into()));
+        items[4] = item(CborValue::Unsigned(1_700_000_000));
+        assert_eq!(
+            field_names(&items).map(|f| f[2]),
+            Some("issuerSignatureAlgorithm")
+        );
+
+        items[3] = item(CborValue::Unsigned(1_700_000_000));
+        items[4] = item(CborValue::Null);
+        assert_eq!(field_names(&items).map(|f| f[2]), Some("issuer"));
+
+        items.pop();
+        assert!(field_names(&items).is_none());
+    }
+}
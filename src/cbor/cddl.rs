@@ -0,0 +1,201 @@
+// A CDDL (RFC 8610) skeleton for the items a schema describes, as codegen
+// writes it with --cddl: a rule for each map with text keys, named after
+// where it was found, with the fields missing from some samples optional
+// and null as a choice where some samples had it.
+
+use std::collections::HashSet;
+
+use super::codegen::unique;
+use super::schema::{Field, Schema};
+use crate::common::emit::snake_case;
+
+/// CDDL rules for `schema` as a type called `name`, with the rules it needs
+/// defined after it
+pub fn cddl(schema: &Schema, name: &str) -> String {
+    let mut writer = CddlWriter::default();
+    match schema {
+        Schema::Struct(fields) => {
+            writer.define_map(&rule_name(name), fields);
+        }
+        _ => {
+            let name = writer.rule_name(&rule_name(name));
+            writer.rules.push(String::new());
+            let rule = format!("{} = {}\n", name, writer.type_of(schema, &name));
+            writer.rules[0] = rule;
+        }
+    }
+    writer.rules.join("\n")
+}
+
+#[derive(Default)]
+struct CddlWriter {
+    rules: Vec<String>,
+    rule_names: HashSet<String>,
+}
+
+impl CddlWriter {
+    /// The CDDL type for `schema`, defining any rule it needs named after
+    /// `hint`
+    fn type_of(&mut self, schema: &Schema, hint: &str) -> String {
+        match schema {
+            Schema::Unknown | Schema::Any => "any".to_string(),
+            Schema::Bool => "bool".to_string(),
+            Schema::Unsigned => "uint".to_string(),
+            Schema::Integer => "int".to_string(),
+            Schema::Float => "float".to_string(),
+            Schema::Text => "tstr".to_string(),
+            Schema::Bytes => "bstr".to_string(),
+            Schema::Array(element) => {
+                format!("[* {}]", self.type_of(element, &format!("{}-item", hint)))
+            }
+            Schema::Tuple(elements) => {
+                let types: Vec<String> = elements
+                    .iter()
+                    .enumerate()
+                    .map(|(i, element)| self.type_of(element, &format!("{}-{}", hint, i + 1)))
+                    .collect();
+                format!("[{}]", types.join(", "))
+            }
+            Schema::Struct(fields) => self.define_map(hint, fields),
+            Schema::IntMap(value) => {
+                format!(
+                    "{{ * int => {} }}",
+                    self.type_of(value, &format!("{}-value", hint))
+                )
+            }
+            Schema::Tagged(tag, content) => format!("#6.{}({})", tag, self.type_of(content, hint)),
+            Schema::Nullable(content) => match **content {
+                Schema::Unknown => "null".to_string(),
+                _ => format!("{} / null", self.type_of(content, hint)),
+            },
+        }
+    }
+
+    /// Define a rule for a map with `fields`, named after `hint`, returning
+    /// its name
+    fn define_map(&mut self, hint: &str, fields: &[Field]) -> String {
+        let name = self.rule_name(hint);
+        // The rule goes before those of its fields
+        let index = self.rules.len();
+        self.rules.push(String::new());
+
+        let mut entries = Vec::new();
+        for field in fields {
+            let hint = format!("{}-{}", name, rule_name(&field.key));
+            let value = self.type_of(&field.schema, &hint);
+            let optional = if field.optional { "? " } else { "" };
+            entries.push(format!(
+                "  {}{}: {}",
+                optional,
+                member_key(&field.key),
+                value
+            ));
+        }
+        self.rules[index] = match entries.is_empty() {
+            true => format!("{} = {{ }}\n", name),
+            false => format!("{} = {{\n{}\n}}\n", name, entries.join(",\n")),
+        };
+        name
+    }
+
+    /// A rule name from `hint` that no other rule has
+    fn rule_name(&mut self, hint: &str) -> String {
+        unique(hint.to_string(), "-", &mut self.rule_names)
+    }
+}
+
+/// A name or map key in the lowercase, hyphenated style of CDDL rule names
+fn rule_name(name: &str) -> String {
+    let name = snake_case(name).replace('_', "-");
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("field-{}", name).trim_end_matches('-').to_string()
+    }
+}
+
+/// A map key as CDDL writes it: bare if it's an identifier, else quoted
+fn member_key(key: &str) -> String {
+    let start = |c: char| c.is_ascii_alphabetic() || matches!(c, '@' | '_' | '$');
+    let bare = key.starts_with(start)
+        && key
+            .chars()
+            .all(|c| start(c) || c.is_ascii_digit() || matches!(c, '-' | '.'))
+        && !key.ends_with(['-', '.']);
+    if bare {
+        return key.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in key.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cbor::parse_slice;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(rule_name("Message"), "message");
+        assert_eq!(rule_name("createdAt"), "created-at");
+        assert_eq!(rule_name("2fa"), "field-2fa");
+        assert_eq!(rule_name("-"), "field");
+        assert_eq!(member_key("created-at"), "created-at");
+        assert_eq!(member_key("$id"), "$id");
+        assert_eq!(member_key("a b"), "\"a b\"");
+        assert_eq!(member_key("1"), "\"1\"");
+        assert_eq!(member_key("x-"), "\"x-\"");
+        assert_eq!(member_key("say \"hi\""), "\"say \\\"hi\\\"\"");
+    }
+
+    #[test]
+    fn test_cddl() {
+        // {"id": h'01', "type": "a", "tags": [{"k": 1}]}, then {"id": h'02', "tags": []}
+        let data = [
+            0xA3, 0x62, 0x69, 0x64, 0x41, 0x01, 0x64, 0x74, 0x79, 0x70, 0x65, 0x61, 0x61, 0x64,
+            0x74, 0x61, 0x67, 0x73, 0x81, 0xA1, 0x61, 0x6B, 0x01, 0xA2, 0x62, 0x69, 0x64, 0x41,
+            0x02, 0x64, 0x74, 0x61, 0x67, 0x73, 0x80,
+        ];
+        let schema = Schema::infer_all(&parse_slice(&data).unwrap());
+        assert_eq!(
+            cddl(&schema, "Message"),
+            "message = {\n\
+             \x20 id: bstr,\n\
+             \x20 ? type: tstr,\n\
+             \x20 tags: [* message-tags-item]\n\
+             }\n\
+             \n\
+             message-tags-item = {\n\
+             \x20 k: uint\n\
+             }\n"
+        );
+
+        // [1, {-1: 1(2)}], then [-2, {2: 1(3)}]
+        let data = [
+            0x82, 0x01, 0xA1, 0x20, 0xC1, 0x02, 0x82, 0x21, 0xA1, 0x02, 0xC1, 0x03,
+        ];
+        let schema = Schema::infer_all(&parse_slice(&data).unwrap());
+        assert_eq!(
+            cddl(&schema, "Root"),
+            "root = [int, { * int => #6.1(uint) }]\n"
+        );
+
+        // {"a": null, "b": {}, "c": null}, then {"a": "x", "b": {}}
+        let data = [
+            0xA3, 0x61, 0x61, 0xF6, 0x61, 0x62, 0xA0, 0x61, 0x63, 0xF6, 0xA2, 0x61, 0x61, 0x61,
+            0x78, 0x61, 0x62, 0xA0,
+        ];
+        let schema = Schema::infer_all(&parse_slice(&data).unwrap());
+        assert_eq!(
+            cddl(&schema, "Root"),
+            "root = {\n  a: tstr / null,\n  b: root-b,\n  ? c: null\n}\n\nroot-b = { }\n"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+// CoAP content formats (RFC 7252 section 12.3), the numbers a CoAP message
+// gives its payload's media type by, for dumpcbor --content-format. Each
+// format dumpcbor can decode says how its payload is read: as plain CBOR,
+// as a COSE message of one kind, as a COSE key or key set, or as a SenML
+// pack (RFC 8428), whose map keys are the integer labels of its fields. Formats that aren't CBOR are
+// listed so that they can be refused by name.
+
+use super::CborValue;
+
+/// How the payload of a content format is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Payload {
+    Cbor,
+    Cose(u64), // The tag of the message it holds, tagged or not
+    Senml,
+    Key,    // A COSE_Key
+    KeySet, // A COSE_KeySet
+    NotCbor,
+}
+
+/// A content format from the CoAP registry
+#[derive(Debug, PartialEq, Eq)]
+pub struct ContentFormat {
+    pub id: u16,
+    pub media_type: &'static str,
+    pub payload: Payload,
+}
+
+const fn format(id: u16, media_type: &'static str, payload: Payload) -> ContentFormat {
+    ContentFormat {
+        id,
+        media_type,
+        payload,
+    }
+}
+
+/// The registered content formats, by number
+pub const CONTENT_FORMATS: &[ContentFormat] = &[
+    format(0, "text/plain; charset=utf-8", Payload::NotCbor),
+    format(
+        16,
+        r#"application/cose; cose-type="cose-encrypt0""#,
+        Payload::Cose(16),
+    ),
+    format(
+        17,
+        r#"application/cose; cose-type="cose-mac0""#,
+        Payload::Cose(17),
+    ),
+    format(
+        18,
+        r#"application/cose; cose-type="cose-sign1""#,
+        Payload::Cose(18),
+    ),
+    format(19, "application/ace+cbor", Payload::Cbor),
+    format(40, "application/link-format", Payload::NotCbor),
+    format(41, "application/xml", Payload::NotCbor),
+    format(42, "application/octet-stream", Payload::NotCbor),
+    format(47, "application/exi", Payload::NotCbor),
+    format(50, "application/json", Payload::NotCbor),
+    format(51, "application/json-patch+json", Payload::NotCbor),
+    format(52, "application/merge-patch+json", Payload::NotCbor),
+    format(60, "application/cbor", Payload::Cbor),
+    format(61, "application/cwt", Payload::Cbor),
+    format(62, "application/multipart-core", Payload::Cbor),
+    format(63, "application/cbor-seq", Payload::Cbor),
+    format(
+        96,
+        r#"application/cose; cose-type="cose-encrypt""#,
+        Payload::Cose(96),
+    ),
+    format(
+        97,
+        r#"application/cose; cose-type="cose-mac""#,
+        Payload::Cose(97),
+    ),
+    format(
+        98,
+        r#"application/cose; cose-type="cose-sign""#,
+        Payload::Cose(98),
+    ),
+    format(101, "application/cose-key", Payload::Key),
+    format(102, "application/cose-key-set", Payload::KeySet),
+    format(110, "application/senml+json", Payload::NotCbor),
+    format(111, "application/sensml+json", Payload::NotCbor),
+    format(112, "application/senml+cbor", Payload::Senml),
+    format(113, "application/sensml+cbor", Payload::Senml),
+    format(140, "application/yang-data+cbor; id=sid", Payload::Cbor),
+    format(256, "application/coap-group+json", Payload::NotCbor),
+];
+
+impl ContentFormat {
+    /// The numbers of the formats dumpcbor decodes, for completions
+    pub const CBOR_IDS: &'static str =
+        "16, 17, 18, 19, 60, 61, 62, 63, 96, 97, 98, 101, 102, 112, 113 or 140";
+}
+
+/// The content format numbered `id`, if it's registered
+pub fn content_format(id: u16) -> Option<&'static ContentFormat> {
+    CONTENT_FORMATS.iter().find(|format| format.id == id)
+}
+
+/// The name of a SenML field by its CBOR label (RFC 8428 section 6)
+pub fn senml_label(key: &CborValue) -> Option<&'static str> {
+    let name = match key.integer() {
+        Some(-1) => "bver",
+        Some(-2) => "bn",
+        Some(-3) => "bt",
+        Some(-4) => "bu",
+        Some(-5) => "bv",
+        Some(-16) => "bs",
+        Some(0) => "n",
+        Some(1) => "u",
+        Some(2) => "v",
+        Some(3) => "vs",
+        Some(4) => "vb",
+        Some(5) => "s",
+        Some(6) => "t",
+        Some(7) => "ut",
+        Some(8) => "vd",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_format() {
+        assert_eq!(content_format(18).unwrap().payload, Payload::Cose(18));
+        assert_eq!(
+            content_format(112).unwrap().media_type,
+            "application/senml+cbor"
+        );
+        assert_eq!(content_format(50).unwrap().payload, Payload::NotCbor);
+        assert_eq!(content_format(1), None);
+        assert!(CONTENT_FORMATS.windows(2).all(|w| w[0].id < w[1].id));
+        let cbor_ids: Vec<String> = CONTENT_FORMATS
+            .iter()
+            .filter(|format| format.payload != Payload::NotCbor)
+            .map(|format| format.id.to_string())
+            .collect();
+        let (first, last) = cbor_ids.split_at(cbor_ids.len() - 1);
+        assert_eq!(
+            ContentFormat::CBOR_IDS,
+            format!("{} or {}", first.join(", "), last[0])
+        );
+
+        assert_eq!(senml_label(&CborValue::Negative(1)), Some("bn"));
+        assert_eq!(senml_label(&CborValue::Unsigned(2)), Some("v"));
+        assert_eq!(senml_label(&CborValue::Unsigned(9)), None);
+    }
+}
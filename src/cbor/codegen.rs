@@ -0,0 +1,242 @@
+// Rust types for the items a schema describes, as codegen writes them: a
+// struct for each map with text keys, deriving serde's Serialize and
+// Deserialize so that ciborium can read and write the items. Keys that
+// aren't Rust identifiers are renamed, and optional fields are left out of
+// what's written when they're None.
+
+use std::collections::HashSet;
+
+use super::schema::{Field, Schema};
+use crate::common::emit::snake_case;
+
+/// Keywords that can't be field names as they are, but can as raw identifiers
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+/// Rust source for `schema` as a type called `name`, with the types it
+/// needs defined after it
+pub fn rust_types(schema: &Schema, name: &str) -> String {
+    let mut writer = RustWriter::default();
+    match schema {
+        Schema::Struct(fields) => {
+            writer.define_struct(name, fields);
+        }
+        _ => {
+            writer.definitions.push(String::new());
+            let alias = format!("pub type {} = {};\n", name, writer.type_of(schema, name));
+            writer.definitions[0] = alias;
+        }
+    }
+
+    let mut code = String::new();
+    if writer.uses_map {
+        code.push_str("use std::collections::BTreeMap;\n\n");
+    }
+    code.push_str("use serde::{Deserialize, Serialize};\n");
+    if writer.uses_bytes {
+        code.push_str("use serde_bytes::ByteBuf;\n");
+    }
+    for definition in &writer.definitions {
+        code.push('\n');
+        code.push_str(definition);
+    }
+    code
+}
+
+#[derive(Default)]
+struct RustWriter {
+    definitions: Vec<String>,
+    type_names: HashSet<String>,
+    uses_bytes: bool,
+    uses_map: bool,
+}
+
+impl RustWriter {
+    /// The Rust type for `schema`, defining any struct it needs named after
+    /// `hint`
+    fn type_of(&mut self, schema: &Schema, hint: &str) -> String {
+        match schema {
+            Schema::Unknown | Schema::Any => "ciborium::Value".to_string(),
+            Schema::Bool => "bool".to_string(),
+            Schema::Unsigned => "u64".to_string(),
+            Schema::Integer => "i64".to_string(),
+            Schema::Float => "f64".to_string(),
+            Schema::Text => "String".to_string(),
+            Schema::Bytes => {
+                // Vec<u8> would be written as an array of integers
+                self.uses_bytes = true;
+                "ByteBuf".to_string()
+            }
+            Schema::Array(element) => {
+                format!("Vec<{}>", self.type_of(element, &format!("{}Item", hint)))
+            }
+            Schema::Tuple(elements) => {
+                let types: Vec<String> = elements
+                    .iter()
+                    .enumerate()
+                    .map(|(i, element)| self.type_of(element, &format!("{}{}", hint, i + 1)))
+                    .collect();
+                format!("({})", types.join(", "))
+            }
+            Schema::Struct(fields) => self.define_struct(hint, fields),
+            Schema::IntMap(value) => {
+                self.uses_map = true;
+                format!(
+                    "BTreeMap<i64, {}>",
+                    self.type_of(value, &format!("{}Value", hint))
+                )
+            }
+            Schema::Tagged(tag, content) => format!(
+                "ciborium::tag::Required<{}, {}>",
+                self.type_of(content, hint),
+                tag
+            ),
+            Schema::Nullable(content) => format!("Option<{}>", self.type_of(content, hint)),
+        }
+    }
+
+    /// Define a struct with `fields`, named after `hint`, returning its name
+    fn define_struct(&mut self, hint: &str, fields: &[Field]) -> String {
+        let name = self.type_name(hint);
+        // The struct goes before those of its fields
+        let index = self.definitions.len();
+        self.definitions.push(String::new());
+
+        let mut code = String::new();
+        code.push_str("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n");
+        code.push_str(&format!("pub struct {} {{\n", name));
+        let mut field_names = HashSet::new();
+        for field in fields {
+            let identifier = unique(field_identifier(&field.key), "_", &mut field_names);
+            let mut attributes = Vec::new();
+            if identifier.trim_start_matches("r#") != field.key {
+                attributes.push(format!("rename = {:?}", field.key));
+            }
+            let hint = format!("{}{}", name, pascal_case(&field.key));
+            let mut field_type = self.type_of(&field.schema, &hint);
+            if field.optional {
+                attributes.push("default".to_string());
+                attributes.push("skip_serializing_if = \"Option::is_none\"".to_string());
+                if !matches!(field.schema, Schema::Nullable(_)) {
+                    field_type = format!("Option<{}>", field_type);
+                }
+            }
+            if !attributes.is_empty() {
+                code.push_str(&format!("    #[serde({})]\n", attributes.join(", ")));
+            }
+            code.push_str(&format!("    pub {}: {},\n", identifier, field_type));
+        }
+        code.push_str("}\n");
+        self.definitions[index] = code;
+        name
+    }
+
+    /// A type name from `hint` that no other type has
+    fn type_name(&mut self, hint: &str) -> String {
+        unique(hint.to_string(), "", &mut self.type_names)
+    }
+}
+
+/// `name`, or it with a number after `separator` if that's taken, adding
+/// it to `taken`
+pub(super) fn unique(name: String, separator: &str, taken: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut number = 2;
+    while taken.contains(&candidate) {
+        candidate = format!("{}{}{}", name, separator, number);
+        number += 1;
+    }
+    taken.insert(candidate.clone());
+    candidate
+}
+
+/// A field name for a map key: snake case, raw if it's a keyword
+fn field_identifier(key: &str) -> String {
+    let snake = snake_case(key);
+    if snake.is_empty() || snake.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("field_{}", snake)
+    } else if matches!(snake.as_str(), "self" | "super" | "crate") {
+        format!("{}_", snake)
+    } else if KEYWORDS.contains(&snake.as_str()) {
+        format!("r#{}", snake)
+    } else {
+        snake
+    }
+}
+
+/// A map key in PascalCase, to name the type of its values
+fn pascal_case(key: &str) -> String {
+    snake_case(key)
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cbor::parse_slice;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(field_identifier("createdAt"), "created_at");
+        assert_eq!(field_identifier("type"), "r#type");
+        assert_eq!(field_identifier("self"), "self_");
+        assert_eq!(field_identifier("2fa"), "field_2fa");
+        assert_eq!(field_identifier("-"), "field_");
+        assert_eq!(pascal_case("created-at"), "CreatedAt");
+        assert_eq!(pascal_case("x509"), "X509");
+    }
+
+    #[test]
+    fn test_rust_types() {
+        // {"id": h'01', "type": "a", "tags": [{"k": 1}]}, then {"id": h'02', "tags": []}
+        let data = [
+            0xA3, 0x62, 0x69, 0x64, 0x41, 0x01, 0x64, 0x74, 0x79, 0x70, 0x65, 0x61, 0x61, 0x64,
+            0x74, 0x61, 0x67, 0x73, 0x81, 0xA1, 0x61, 0x6B, 0x01, 0xA2, 0x62, 0x69, 0x64, 0x41,
+            0x02, 0x64, 0x74, 0x61, 0x67, 0x73, 0x80,
+        ];
+        let schema = Schema::infer_all(&parse_slice(&data).unwrap());
+        assert_eq!(
+            rust_types(&schema, "Message"),
+            "use serde::{Deserialize, Serialize};\n\
+             use serde_bytes::ByteBuf;\n\
+             \n\
+             #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n\
+             pub struct Message {\n\
+             \x20   pub id: ByteBuf,\n\
+             \x20   #[serde(default, skip_serializing_if = \"Option::is_none\")]\n\
+             \x20   pub r#type: Option<String>,\n\
+             \x20   pub tags: Vec<MessageTagsItem>,\n\
+             }\n\
+             \n\
+             #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n\
+             pub struct MessageTagsItem {\n\
+             \x20   pub k: u64,\n\
+             }\n"
+        );
+
+        // [1, {-1: 1(2)}]
+        let data = [0x82, 0x01, 0xA1, 0x20, 0xC1, 0x02];
+        let schema = Schema::infer_all(&parse_slice(&data).unwrap());
+        assert_eq!(
+            rust_types(&schema, "Root"),
+            "use std::collections::BTreeMap;\n\
+             \n\
+             use serde::{Deserialize, Serialize};\n\
+             \n\
+             pub type Root = (u64, BTreeMap<i64, ciborium::tag::Required<u64, 1>>);\n"
+        );
+    }
+}
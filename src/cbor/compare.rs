@@ -0,0 +1,279 @@
+// Comparing CBOR values. Values are ordered as their deterministic encodings
+// would be (RFC 8949 section 4.2.1): bytewise, so that shorter heads, and
+// so smaller arguments and shorter strings, come first. Equality and hashing
+// agree with that order, so map pair order and how item heads happened to
+// be encoded don't count; equivalent() also disregards the width of floats
+// and whether integers are written as bignums.
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use super::value::argument_info;
+use super::*;
+
+impl CborValue<'_> {
+    /// Sort key for the head of the value's deterministic encoding: major
+    /// type, additional info, then the argument. Floats are keyed by their
+    /// bits; a float16 held as an f32 orders the same way as its own bits.
+    fn head(&self) -> (u8, u8, u64) {
+        let (major_type, argument) = match self {
+            CborValue::Unsigned(n) => (MAJOR_UNSIGNED, *n),
+            CborValue::Negative(n) => (MAJOR_NEGATIVE, *n),
+            CborValue::Bytes(bytes) => (MAJOR_BYTES, bytes.len() as u64),
+            CborValue::Text(text) => (MAJOR_TEXT, text.len() as u64),
+            CborValue::Array(items) => (MAJOR_ARRAY, items.len() as u64),
+            CborValue::Map(pairs) => (MAJOR_MAP, pairs.len() as u64),
+            CborValue::Tag(tag, _) => (MAJOR_TAG, *tag),
+            CborValue::Simple(n) => (MAJOR_SIMPLE, *n as u64),
+            CborValue::Boolean(false) => (MAJOR_SIMPLE, SIMPLE_FALSE as u64),
+            CborValue::Boolean(true) => (MAJOR_SIMPLE, SIMPLE_TRUE as u64),
+            CborValue::Null => (MAJOR_SIMPLE, SIMPLE_NULL as u64),
+            CborValue::Undefined => (MAJOR_SIMPLE, SIMPLE_UNDEFINED as u64),
+            CborValue::Float16(f) => return (MAJOR_SIMPLE, AI_2BYTES, f.to_bits() as u64),
+            CborValue::Float32(f) => return (MAJOR_SIMPLE, AI_4BYTES, f.to_bits() as u64),
+            CborValue::Float64(f) => return (MAJOR_SIMPLE, AI_8BYTES, f.to_bits()),
+            CborValue::Break => return (MAJOR_SIMPLE, AI_INDEFINITE, 0),
+        };
+        (major_type, argument_info(argument), argument)
+    }
+
+    /// Check whether two values are the same in the CBOR data model, however
+    /// they're encoded. Besides what `==` ignores, floats match by value
+    /// whatever their width (with all NaNs alike), and bignums (tags 2 and
+    /// 3) match the integers they hold.
+    pub fn equivalent(&self, other: &CborValue) -> bool {
+        if let (Some(a), Some(b)) = (self.as_float(), other.as_float()) {
+            return a == b || (a.is_nan() && b.is_nan());
+        }
+        if let (Some(a), Some(b)) = (self.as_integer(), other.as_integer()) {
+            return a == b;
+        }
+        match (self, other) {
+            (CborValue::Array(a), CborValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.value.equivalent(&y.value))
+            }
+            (CborValue::Map(a), CborValue::Map(b)) => {
+                // Pair each entry with a distinct matching one
+                let mut matched = vec![false; b.len()];
+                a.len() == b.len()
+                    && a.iter().all(|(key, value)| {
+                        let found = b.iter().enumerate().position(|(i, (k, v))| {
+                            !matched[i]
+                                && key.value.equivalent(&k.value)
+                                && value.value.equivalent(&v.value)
+                        });
+                        found.map(|i| matched[i] = true).is_some()
+                    })
+            }
+            (CborValue::Tag(a, x), CborValue::Tag(b, y)) => a == b && x.value.equivalent(&y.value),
+            _ => self == other,
+        }
+    }
+
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            CborValue::Float16(f) | CborValue::Float32(f) => Some(*f as f64),
+            CborValue::Float64(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Value of an integer or of a bignum small enough to hold
+    fn as_integer(&self) -> Option<i128> {
+        match self {
+            CborValue::Unsigned(_) | CborValue::Negative(_) => self.integer(),
+            CborValue::Tag(tag @ (TAG_BIGNUM_POS | TAG_BIGNUM_NEG), item) => {
+                let CborValue::Bytes(bytes) = &item.value else {
+                    return None;
+                };
+                let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+                let magnitude = &bytes[start..];
+                if magnitude.len() > 15 {
+                    return None;
+                }
+                let n = magnitude.iter().fold(0i128, |n, &b| n << 8 | b as i128);
+                Some(if *tag == TAG_BIGNUM_NEG { -1 - n } else { n })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Map pairs in the order of their deterministic encodings
+fn sorted_pairs<'p, 'a>(
+    pairs: &'p [(CborItem<'a>, CborItem<'a>)],
+) -> Vec<&'p (CborItem<'a>, CborItem<'a>)> {
+    let mut sorted: Vec<_> = pairs.iter().collect();
+    sorted.sort();
+    sorted
+}
+
+impl Ord for CborValue<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.head()
+            .cmp(&other.head())
+            .then_with(|| match (self, other) {
+                (CborValue::Bytes(a), CborValue::Bytes(b)) => a.cmp(b),
+                (CborValue::Text(a), CborValue::Text(b)) => a.as_bytes().cmp(b.as_bytes()),
+                (CborValue::Array(a), CborValue::Array(b)) => a.cmp(b),
+                (CborValue::Map(a), CborValue::Map(b)) => sorted_pairs(a).cmp(&sorted_pairs(b)),
+                (CborValue::Tag(_, a), CborValue::Tag(_, b)) => a.cmp(b),
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+impl PartialOrd for CborValue<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for CborValue<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for CborValue<'_> {}
+
+impl Hash for CborValue<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.head().hash(state);
+        match self {
+            CborValue::Bytes(bytes) => bytes.hash(state),
+            CborValue::Text(text) => text.hash(state),
+            CborValue::Array(items) => items.hash(state),
+            CborValue::Map(pairs) => sorted_pairs(pairs).hash(state),
+            CborValue::Tag(_, item) => item.hash(state),
+            _ => {}
+        }
+    }
+}
+
+// Items compare by value alone: their heads' additional info and raw bytes
+// are encoding details
+
+impl Ord for CborItem<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl PartialOrd for CborItem<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for CborItem<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for CborItem<'_> {}
+
+impl Hash for CborItem<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testutil::item;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash(value: &CborValue) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_deterministic_order() {
+        // The key order example of RFC 8949 section 4.2.1
+        let mut keys = vec![
+            CborValue::Boolean(false),
+            CborValue::Array(vec![item(CborValue::Negative(0))]),
+            CborValue::Text("aa".into()),
+            CborValue::Negative(0),
+            CborValue::Array(vec![item(CborValue::Unsigned(100))]),
+            CborValue::Text("z".into()),
+            CborValue::Unsigned(100),
+            CborValue::Unsigned(10),
+        ];
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                CborValue::Unsigned(10),
+                CborValue::Unsigned(100),
+                CborValue::Negative(0),
+                CborValue::Text("z".into()),
+                CborValue::Text("aa".into()),
+                CborValue::Array(vec![item(CborValue::Unsigned(100))]),
+                CborValue::Array(vec![item(CborValue::Negative(0))]),
+                CborValue::Boolean(false),
+            ]
+        );
+        assert!(CborValue::Negative(0) > CborValue::Unsigned(u64::MAX));
+        assert!(CborValue::Negative(0) < CborValue::Negative(1));
+        assert!(CborValue::Float16(1.0) < CborValue::Float64(0.5));
+    }
+
+    #[test]
+    fn test_equality_and_hash() {
+        let a = item(CborValue::Text("a".into()));
+        let b = item(CborValue::Text("b".into()));
+        let one = item(CborValue::Unsigned(1));
+        let two = item(CborValue::Unsigned(2));
+        let map = CborValue::Map(vec![(a.clone(), one.clone()), (b.clone(), two.clone())]);
+        let reordered = CborValue::Map(vec![(b, two), (a, one)]);
+        assert_eq!(map, reordered);
+        assert_eq!(hash(&map), hash(&reordered));
+
+        // The same value with a longer head is still equal
+        let mut long_head = item(CborValue::Unsigned(1));
+        long_head.additional_info = AI_8BYTES;
+        assert_eq!(long_head, item(CborValue::Unsigned(1)));
+
+        assert_eq!(CborValue::Float64(f64::NAN), CborValue::Float64(f64::NAN));
+        assert_ne!(CborValue::Float64(0.0), CborValue::Float64(-0.0));
+        assert_ne!(CborValue::Float16(1.0), CborValue::Float64(1.0));
+        assert_ne!(
+            CborValue::Text("a".into()),
+            CborValue::Bytes(b"a".to_vec().into())
+        );
+    }
+
+    #[test]
+    fn test_equivalent() {
+        assert!(CborValue::Float16(1.5).equivalent(&CborValue::Float64(1.5)));
+        assert!(CborValue::Float32(f32::NAN).equivalent(&CborValue::Float64(f64::NAN)));
+        assert!(!CborValue::Float64(1.5).equivalent(&CborValue::Unsigned(1)));
+
+        let bignum = |tag, bytes: &[u8]| {
+            CborValue::Tag(tag, Box::new(item(CborValue::Bytes(bytes.to_vec().into()))))
+        };
+        assert!(bignum(TAG_BIGNUM_POS, &[0, 1, 0]).equivalent(&CborValue::Unsigned(256)));
+        assert!(bignum(TAG_BIGNUM_NEG, &[]).equivalent(&CborValue::Negative(0)));
+        assert!(!bignum(TAG_BIGNUM_NEG, &[1]).equivalent(&CborValue::Negative(0)));
+
+        let array = |values: Vec<CborValue<'static>>| {
+            CborValue::Array(values.into_iter().map(CborItem::from_value).collect())
+        };
+        let half = array(vec![CborValue::Float16(0.5), CborValue::Unsigned(1)]);
+        let double = array(vec![CborValue::Float64(0.5), CborValue::Unsigned(1)]);
+        assert!(half.equivalent(&double));
+        assert!(!half.equivalent(&array(vec![CborValue::Float64(0.5)])));
+
+        let map = |key, value| CborValue::Map(vec![(item(key), item(value))]);
+        assert!(map(CborValue::Unsigned(1), CborValue::Float32(2.0))
+            .equivalent(&map(CborValue::Unsigned(1), CborValue::Float64(2.0))));
+        assert!(!map(CborValue::Unsigned(1), CborValue::Null)
+            .equivalent(&map(CborValue::Unsigned(2), CborValue::Null)));
+    }
+}
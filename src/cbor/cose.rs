@@ -0,0 +1,734 @@
+// COSE messages (RFC 9052): the arrays of signed, MACed and encrypted
+// messages, told apart by their tags, and a check that an item has the
+// layout of the message it's meant to be. Only the structure is checked:
+// headers' contents, signatures and ciphertexts are taken as they are. The
+// labels for the dump name each element, down through the signatures and
+// recipients, decode protected headers and name the common parameters in
+// every header, with countersignatures (RFC 9338) laid out as signatures.
+// Recipients are labeled layer by layer, their headers naming the key
+// agreement parameters as well, with ephemeral and static keys laid out as
+// the COSE_Key maps they are. Algorithms, key types and curves are given
+// their registered names (IANA "COSE Algorithms", "COSE Key Types" and
+// "COSE Elliptic Curves") wherever a header, key or CWT confirmation holds
+// them, and the claims of a CWT Claims header are named.
+
+use super::c509::Label;
+use super::{parse_slice, CborItem, CborValue};
+
+/// What an element of a COSE array holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Protected,   // A byte string holding a serialized header map, or empty
+    Unprotected, // A header map
+    Content,     // A byte string, or null when detached
+    Bytes,
+    Signatures, // One or more COSE_Signature arrays
+    Recipients, // One or more COSE_recipient arrays
+}
+
+/// A kind of COSE message
+#[derive(Debug, PartialEq, Eq)]
+pub struct Message {
+    pub name: &'static str,
+    pub tag: u64,
+    pub fields: &'static [(&'static str, Field)],
+}
+
+/// The messages of RFC 9052, by their CBOR tags
+pub const MESSAGES: &[Message] = &[
+    Message {
+        name: "COSE_Encrypt0",
+        tag: 16,
+        fields: &[
+            ("protected", Field::Protected),
+            ("unprotected", Field::Unprotected),
+            ("ciphertext", Field::Content),
+        ],
+    },
+    Message {
+        name: "COSE_Mac0",
+        tag: 17,
+        fields: &[
+            ("protected", Field::Protected),
+            ("unprotected", Field::Unprotected),
+            ("payload", Field::Content),
+            ("tag", Field::Bytes),
+        ],
+    },
+    Message {
+        name: "COSE_Sign1",
+        tag: 18,
+        fields: &[
+            ("protected", Field::Protected),
+            ("unprotected", Field::Unprotected),
+            ("payload", Field::Content),
+            ("signature", Field::Bytes),
+        ],
+    },
+    Message {
+        name: "COSE_Encrypt",
+        tag: 96,
+        fields: &[
+            ("protected", Field::Protected),
+            ("unprotected", Field::Unprotected),
+            ("ciphertext", Field::Content),
+            ("recipients", Field::Recipients),
+        ],
+    },
+    Message {
+        name: "COSE_Mac",
+        tag: 97,
+        fields: &[
+            ("protected", Field::Protected),
+            ("unprotected", Field::Unprotected),
+            ("payload", Field::Content),
+            ("tag", Field::Bytes),
+            ("recipients", Field::Recipients),
+        ],
+    },
+    Message {
+        name: "COSE_Sign",
+        tag: 98,
+        fields: &[
+            ("protected", Field::Protected),
+            ("unprotected", Field::Unprotected),
+            ("payload", Field::Content),
+            ("signatures", Field::Signatures),
+        ],
+    },
+];
+
+const SIGNATURE: &[(&str, Field)] = &[
+    ("protected", Field::Protected),
+    ("unprotected", Field::Unprotected),
+    ("signature", Field::Bytes),
+];
+
+// A recipient may have recipients of its own, as a fourth element
+const RECIPIENT: &[(&str, Field)] = &[
+    ("protected", Field::Protected),
+    ("unprotected", Field::Unprotected),
+    ("ciphertext", Field::Content),
+    ("recipients", Field::Recipients),
+];
+
+/// The message a CBOR tag marks, if it's one of COSE's
+pub fn message(tag: u64) -> Option<&'static Message> {
+    MESSAGES.iter().find(|message| message.tag == tag)
+}
+
+impl Message {
+    /// Check that `item`, tagged or not, has this message's layout, saying
+    /// what's wrong if it doesn't
+    pub fn check(&self, item: &CborItem) -> Result<(), String> {
+        let item = match &item.value {
+            CborValue::Tag(tag, _) if *tag != self.tag => {
+                return Err(format!(
+                    "tag {} isn't {}'s tag {}",
+                    tag, self.name, self.tag
+                ));
+            }
+            CborValue::Tag(_, tagged) => tagged,
+            _ => item,
+        };
+        check_array(self.name, self.fields, self.fields.len(), item)
+    }
+
+    /// Labels for the elements of the message's array, `items`, naming
+    /// those of the signatures and recipients in it as well
+    pub fn labels(&self, items: &[CborItem]) -> Vec<Label> {
+        field_labels(self.fields, items, header_name)
+    }
+}
+
+/// How the parameters of a header are named by their labels
+type HeaderNames = fn(&CborValue) -> Option<&'static str>;
+
+fn field_labels(
+    fields: &[(&'static str, Field)],
+    items: &[CborItem],
+    names: HeaderNames,
+) -> Vec<Label> {
+    fields
+        .iter()
+        .zip(items)
+        .map(|(&(name, field), item)| match (field, &item.value) {
+            (Field::Protected, CborValue::Bytes(bytes)) => protected_label(name, bytes, names),
+            (Field::Unprotected, CborValue::Map(pairs)) => {
+                label(name, None, header_labels_by(pairs, names))
+            }
+            (Field::Signatures, CborValue::Array(signatures)) => label(
+                name,
+                None,
+                numbered(
+                    "COSE_Signature",
+                    "signature",
+                    SIGNATURE,
+                    signatures,
+                    header_name,
+                ),
+            ),
+            (Field::Recipients, CborValue::Array(recipients)) => label(
+                name,
+                None,
+                numbered(
+                    "COSE_recipient",
+                    "recipient",
+                    RECIPIENT,
+                    recipients,
+                    recipient_header_name,
+                ),
+            ),
+            _ => label(name, None, Vec::new()),
+        })
+        .collect()
+}
+
+fn label(name: &'static str, note: Option<String>, children: Vec<Label>) -> Label {
+    Label {
+        name,
+        note,
+        children,
+        encoded: false,
+    }
+}
+
+/// Labels for each of a list of signatures, recipients or countersignatures,
+/// numbered where there's more than one
+fn numbered(
+    name: &'static str,
+    what: &str,
+    fields: &[(&'static str, Field)],
+    items: &[CborItem],
+    names: HeaderNames,
+) -> Vec<Label> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let note = (items.len() > 1).then(|| format!("{} {} of {}", what, i + 1, items.len()));
+            let children = match &item.value {
+                CborValue::Array(elements) => field_labels(fields, elements, names),
+                _ => Vec::new(),
+            };
+            label(name, note, children)
+        })
+        .collect()
+}
+
+/// A protected header, decoded below its byte string if it holds a map
+fn protected_label(name: &'static str, bytes: &[u8], names: HeaderNames) -> Label {
+    match parse_slice(bytes).as_deref() {
+        Ok([header]) => match &header.value {
+            CborValue::Map(pairs) => Label {
+                encoded: true,
+                ..label(name, None, header_labels_by(pairs, names))
+            },
+            _ => label(name, None, Vec::new()),
+        },
+        _ => label(name, None, Vec::new()),
+    }
+}
+
+/// Labels for the values of a header map, naming the common parameters and
+/// laying out the countersignatures of RFC 9338 as signatures are
+pub fn header_labels(pairs: &[(CborItem, CborItem)]) -> Vec<Label> {
+    header_labels_by(pairs, header_name)
+}
+
+fn header_labels_by(pairs: &[(CborItem, CborItem)], names: HeaderNames) -> Vec<Label> {
+    pairs
+        .iter()
+        .map(|(key, value)| {
+            let name = names(&key.value).unwrap_or("");
+            match (&key.value, &value.value) {
+                // A countersignature, or a list of them
+                (
+                    CborValue::Unsigned(HEADER_COUNTERSIGNATURE | HEADER_COUNTERSIGNATURE_V2),
+                    CborValue::Array(elements),
+                ) => match elements.first().map(|first| &first.value) {
+                    Some(CborValue::Array(_)) => label(
+                        name,
+                        None,
+                        numbered(
+                            "COSE_Countersignature",
+                            "countersignature",
+                            SIGNATURE,
+                            elements,
+                            header_name,
+                        ),
+                    ),
+                    _ => label(name, None, field_labels(SIGNATURE, elements, header_name)),
+                },
+                // The ephemeral or static key of a key agreement, named so
+                // only in a recipient's header
+                (_, CborValue::Map(key_pairs))
+                    if matches!(name, "ephemeral key" | "static key") =>
+                {
+                    label(name, None, key_labels(key_pairs))
+                }
+                (CborValue::Unsigned(HEADER_CWT_CLAIMS), CborValue::Map(claims)) => {
+                    label(name, None, claims_labels(claims))
+                }
+                (CborValue::Unsigned(HEADER_ALG), alg) => {
+                    label(name, algorithm_name(alg).map(String::from), Vec::new())
+                }
+                _ => label(name, None, Vec::new()),
+            }
+        })
+        .collect()
+}
+
+const HEADER_ALG: u64 = 1;
+const HEADER_COUNTERSIGNATURE: u64 = 7;
+const HEADER_COUNTERSIGNATURE_V2: u64 = 11;
+const HEADER_CWT_CLAIMS: u64 = 15;
+
+/// The name of a common header parameter (RFC 9052 section 3.1, RFC 9338,
+/// RFC 9360)
+pub fn header_name(key: &CborValue) -> Option<&'static str> {
+    let CborValue::Unsigned(label) = key else {
+        return None;
+    };
+    let name = match label {
+        1 => "alg",
+        2 => "crit",
+        3 => "content type",
+        4 => "kid",
+        5 => "IV",
+        6 => "Partial IV",
+        7 => "counter signature",
+        9 => "CounterSignature0",
+        10 => "kid context",
+        11 => "Countersignature version 2",
+        12 => "Countersignature0 version 2",
+        13 => "kcwt",
+        14 => "kccs",
+        15 => "CWT Claims",
+        16 => "typ",
+        32 => "x5bag",
+        33 => "x5chain",
+        34 => "x5t",
+        35 => "x5u",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// The name of a recipient's header parameter: a common one, or one of the
+/// key agreement parameters of the ECDH and direct key algorithms (RFC 9053
+/// sections 5 and 6)
+pub fn recipient_header_name(key: &CborValue) -> Option<&'static str> {
+    let name = match key.integer() {
+        Some(-1) => "ephemeral key",
+        Some(-2) => "static key",
+        Some(-3) => "static key id",
+        Some(-20) => "salt",
+        Some(-21) => "PartyU identity",
+        Some(-22) => "PartyU nonce",
+        Some(-23) => "PartyU other",
+        Some(-24) => "PartyV identity",
+        Some(-25) => "PartyV nonce",
+        Some(-26) => "PartyV other",
+        _ => return header_name(key),
+    };
+    Some(name)
+}
+
+/// Labels for the values of a COSE_Key map (RFC 9052 section 7.1), whose
+/// negative labels depend on its key type: the curve and coordinates of
+/// OKP (1) and EC2 (2) keys, the modulus and exponents of RSA (3) ones and
+/// the key value of symmetric (4) ones
+pub fn key_labels(pairs: &[(CborItem, CborItem)]) -> Vec<Label> {
+    let key_type = pairs
+        .iter()
+        .find_map(|(key, value)| match (&key.value, &value.value) {
+            (CborValue::Unsigned(1), CborValue::Unsigned(kty)) => Some(*kty),
+            _ => None,
+        });
+    pairs
+        .iter()
+        .map(|(key, value)| {
+            let name = match (key.value.integer(), key_type) {
+                (Some(1), _) => "kty",
+                (Some(2), _) => "kid",
+                (Some(3), _) => "alg",
+                (Some(4), _) => "key_ops",
+                (Some(5), _) => "Base IV",
+                (Some(-1), Some(1 | 2)) => "crv",
+                (Some(-2), Some(1 | 2)) => "x",
+                (Some(-3), Some(2)) => "y",
+                (Some(-4), Some(1 | 2)) => "d",
+                (Some(-1), Some(3)) => "n",
+                (Some(-2), Some(3)) => "e",
+                (Some(-3), Some(3)) => "d",
+                (Some(-1), Some(4)) => "k",
+                _ => "",
+            };
+            let note = match name {
+                "kty" => key_type_name(&value.value),
+                "alg" => algorithm_name(&value.value),
+                "crv" => curve_name(&value.value),
+                _ => None,
+            };
+            label(name, note.map(String::from), Vec::new())
+        })
+        .collect()
+}
+
+/// Labels for the keys of a COSE_KeySet, an array of COSE_Key maps
+pub fn key_set_labels(keys: &[CborItem]) -> Vec<Label> {
+    keys.iter()
+        .map(|key| match &key.value {
+            CborValue::Map(pairs) => label("COSE_Key", None, key_labels(pairs)),
+            _ => label("", None, Vec::new()),
+        })
+        .collect()
+}
+
+/// Labels for the values of a CWT claims map (RFC 8392 section 3.1), with
+/// the key of a proof-of-possession confirmation (RFC 8747) laid out
+pub fn claims_labels(pairs: &[(CborItem, CborItem)]) -> Vec<Label> {
+    pairs
+        .iter()
+        .map(|(key, value)| {
+            let CborValue::Unsigned(claim) = key.value else {
+                return label("", None, Vec::new());
+            };
+            match (claim, &value.value) {
+                (1, _) => label("iss", None, Vec::new()),
+                (2, _) => label("sub", None, Vec::new()),
+                (3, _) => label("aud", None, Vec::new()),
+                (4, _) => label("exp", None, Vec::new()),
+                (5, _) => label("nbf", None, Vec::new()),
+                (6, _) => label("iat", None, Vec::new()),
+                (7, _) => label("cti", None, Vec::new()),
+                (8, CborValue::Map(methods)) => label("cnf", None, confirmation_labels(methods)),
+                (8, _) => label("cnf", None, Vec::new()),
+                _ => label("", None, Vec::new()),
+            }
+        })
+        .collect()
+}
+
+fn confirmation_labels(pairs: &[(CborItem, CborItem)]) -> Vec<Label> {
+    pairs
+        .iter()
+        .map(|(key, value)| match (&key.value, &value.value) {
+            (CborValue::Unsigned(1), CborValue::Map(key_pairs)) => {
+                label("COSE_Key", None, key_labels(key_pairs))
+            }
+            (CborValue::Unsigned(2), _) => label("Encrypted_COSE_Key", None, Vec::new()),
+            (CborValue::Unsigned(3), _) => label("kid", None, Vec::new()),
+            _ => label("", None, Vec::new()),
+        })
+        .collect()
+}
+
+/// The registered name of a COSE algorithm by its number
+pub fn algorithm_name(alg: &CborValue) -> Option<&'static str> {
+    let name = match alg.integer() {
+        Some(-259) => "RS512",
+        Some(-258) => "RS384",
+        Some(-257) => "RS256",
+        Some(-47) => "ES256K",
+        Some(-44) => "SHA-512",
+        Some(-43) => "SHA-384",
+        Some(-39) => "PS512",
+        Some(-38) => "PS384",
+        Some(-37) => "PS256",
+        Some(-36) => "ES512",
+        Some(-35) => "ES384",
+        Some(-34) => "ECDH-SS + A256KW",
+        Some(-33) => "ECDH-SS + A192KW",
+        Some(-32) => "ECDH-SS + A128KW",
+        Some(-31) => "ECDH-ES + A256KW",
+        Some(-30) => "ECDH-ES + A192KW",
+        Some(-29) => "ECDH-ES + A128KW",
+        Some(-28) => "ECDH-SS + HKDF-512",
+        Some(-27) => "ECDH-SS + HKDF-256",
+        Some(-26) => "ECDH-ES + HKDF-512",
+        Some(-25) => "ECDH-ES + HKDF-256",
+        Some(-18) => "SHAKE128",
+        Some(-17) => "SHA-512/256",
+        Some(-16) => "SHA-256",
+        Some(-15) => "SHA-256/64",
+        Some(-14) => "SHA-1",
+        Some(-13) => "direct+HKDF-AES-256",
+        Some(-12) => "direct+HKDF-AES-128",
+        Some(-11) => "direct+HKDF-SHA-512",
+        Some(-10) => "direct+HKDF-SHA-256",
+        Some(-8) => "EdDSA",
+        Some(-7) => "ES256",
+        Some(-6) => "direct",
+        Some(-5) => "A256KW",
+        Some(-4) => "A192KW",
+        Some(-3) => "A128KW",
+        Some(1) => "A128GCM",
+        Some(2) => "A192GCM",
+        Some(3) => "A256GCM",
+        Some(4) => "HMAC 256/64",
+        Some(5) => "HMAC 256/256",
+        Some(6) => "HMAC 384/384",
+        Some(7) => "HMAC 512/512",
+        Some(10) => "AES-CCM-16-64-128",
+        Some(11) => "AES-CCM-16-64-256",
+        Some(12) => "AES-CCM-64-64-128",
+        Some(13) => "AES-CCM-64-64-256",
+        Some(14) => "AES-MAC 128/64",
+        Some(15) => "AES-MAC 256/64",
+        Some(24) => "ChaCha20/Poly1305",
+        Some(25) => "AES-MAC 128/128",
+        Some(26) => "AES-MAC 256/128",
+        Some(30) => "AES-CCM-16-128-128",
+        Some(31) => "AES-CCM-16-128-256",
+        Some(32) => "AES-CCM-64-128-128",
+        Some(33) => "AES-CCM-64-128-256",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// The registered name of a COSE key type by its number
+pub fn key_type_name(kty: &CborValue) -> Option<&'static str> {
+    let name = match kty {
+        CborValue::Unsigned(1) => "OKP",
+        CborValue::Unsigned(2) => "EC2",
+        CborValue::Unsigned(3) => "RSA",
+        CborValue::Unsigned(4) => "Symmetric",
+        CborValue::Unsigned(5) => "HSS-LMS",
+        CborValue::Unsigned(6) => "WalnutDSA",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// The registered name of a COSE elliptic curve by its number
+pub fn curve_name(crv: &CborValue) -> Option<&'static str> {
+    let name = match crv {
+        CborValue::Unsigned(1) => "P-256",
+        CborValue::Unsigned(2) => "P-384",
+        CborValue::Unsigned(3) => "P-521",
+        CborValue::Unsigned(4) => "X25519",
+        CborValue::Unsigned(5) => "X448",
+        CborValue::Unsigned(6) => "Ed25519",
+        CborValue::Unsigned(7) => "Ed448",
+        CborValue::Unsigned(8) => "secp256k1",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Check an array of `fields`, of which the first `required` must be there
+fn check_array(
+    name: &str,
+    fields: &[(&str, Field)],
+    required: usize,
+    item: &CborItem,
+) -> Result<(), String> {
+    let CborValue::Array(elements) = &item.value else {
+        return Err(format!("{} isn't an array", name));
+    };
+    if elements.len() < required || elements.len() > fields.len() {
+        return Err(format!(
+            "{} has {} elements, not {}",
+            name,
+            elements.len(),
+            fields.len()
+        ));
+    }
+    for ((field_name, field), element) in fields.iter().zip(elements) {
+        check_field(*field, &element.value)
+            .map_err(|e| format!("{} {} {}", name, field_name, e))?;
+    }
+    Ok(())
+}
+
+fn check_field(field: Field, value: &CborValue) -> Result<(), String> {
+    match (field, value) {
+        (Field::Protected, CborValue::Bytes(bytes)) => {
+            if bytes.is_empty() {
+                return Ok(());
+            }
+            match parse_slice(bytes).as_deref() {
+                Ok([header]) if matches!(header.value, CborValue::Map(_)) => Ok(()),
+                _ => Err("isn't a serialized header map".to_string()),
+            }
+        }
+        (Field::Unprotected, CborValue::Map(_))
+        | (Field::Content, CborValue::Bytes(_) | CborValue::Null)
+        | (Field::Bytes, CborValue::Bytes(_)) => Ok(()),
+        (Field::Signatures | Field::Recipients, CborValue::Array(elements))
+            if !elements.is_empty() =>
+        {
+            for element in elements {
+                match field {
+                    Field::Signatures => check_array("COSE_Signature", SIGNATURE, 3, element)?,
+                    _ => check_array("COSE_recipient", RECIPIENT, 3, element)?,
+                }
+            }
+            Ok(())
+        }
+        (Field::Signatures | Field::Recipients, CborValue::Array(_)) => Err("is empty".to_string()),
+        (Field::Content, _) => Err("isn't a byte string or null".to_string()),
+        (Field::Unprotected, _) => Err("isn't a map".to_string()),
+        (Field::Signatures | Field::Recipients, _) => Err("isn't an array".to_string()),
+        _ => Err("isn't a byte string".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        // 18([h'A10126', {4: h'3131'}, h'00', h'01'])
+        let data = [
+            0xD2, 0x84, 0x43, 0xA1, 0x01, 0x26, 0xA1, 0x04, 0x42, 0x31, 0x31, 0x41, 0x00, 0x41,
+            0x01,
+        ];
+        let items = parse_slice(&data).unwrap();
+        let sign1 = message(18).unwrap();
+        assert_eq!(sign1.name, "COSE_Sign1");
+        assert_eq!(sign1.check(&items[0]), Ok(()));
+        assert_eq!(
+            message(98).unwrap().check(&items[0]),
+            Err("tag 18 isn't COSE_Sign's tag 98".to_string())
+        );
+        // The array alone is taken as an untagged COSE_Sign1
+        let CborValue::Tag(_, array) = &items[0].value else {
+            panic!("expected a tag");
+        };
+        assert_eq!(sign1.check(array), Ok(()));
+        let CborValue::Array(elements) = &array.value else {
+            panic!("expected an array");
+        };
+        let labels = sign1.labels(elements);
+        let names: Vec<&str> = labels.iter().map(|label| label.name).collect();
+        assert_eq!(names, ["protected", "unprotected", "payload", "signature"]);
+
+        // 17([h'01', {}, null, h'00']), whose protected header isn't a map
+        let data = [0xD1, 0x84, 0x41, 0x01, 0xA0, 0xF6, 0x41, 0x00];
+        let items = parse_slice(&data).unwrap();
+        assert_eq!(
+            message(17).unwrap().check(&items[0]),
+            Err("COSE_Mac0 protected isn't a serialized header map".to_string())
+        );
+
+        // 98([h'', {}, h'00', [[h'', {}]]]), with a signature left out
+        let data = [
+            0xD8, 0x62, 0x84, 0x40, 0xA0, 0x41, 0x00, 0x81, 0x82, 0x40, 0xA0,
+        ];
+        let items = parse_slice(&data).unwrap();
+        assert_eq!(
+            message(98).unwrap().check(&items[0]),
+            Err("COSE_Sign signatures COSE_Signature has 2 elements, not 3".to_string())
+        );
+        let CborValue::Tag(_, array) = &items[0].value else {
+            panic!("expected a tag");
+        };
+        let CborValue::Array(elements) = &array.value else {
+            panic!("expected an array");
+        };
+        let labels = message(98).unwrap().labels(elements);
+        assert_eq!(labels[3].children[0].name, "COSE_Signature");
+        assert_eq!(labels[3].children[0].children[1].name, "unprotected");
+    }
+    #[test]
+    fn test_header_labels() {
+        // {1: -7, 7: [h'', {}, h'00'], 11: [[h'', {}, h'01'], [h'', {}, h'02']]}
+        let data = [
+            0xA3, 0x01, 0x26, 0x07, 0x83, 0x40, 0xA0, 0x41, 0x00, 0x0B, 0x82, 0x83, 0x40, 0xA0,
+            0x41, 0x01, 0x83, 0x40, 0xA0, 0x41, 0x02,
+        ];
+        let items = parse_slice(&data).unwrap();
+        let CborValue::Map(pairs) = &items[0].value else {
+            panic!("expected a map");
+        };
+        let labels = header_labels(pairs);
+        assert_eq!(labels[0].name, "alg");
+        assert!(labels[0].children.is_empty());
+        // A single countersignature is laid out as a signature
+        assert_eq!(labels[1].name, "counter signature");
+        assert_eq!(labels[1].children[2].name, "signature");
+        // A list of them is numbered
+        let countersignatures = &labels[2].children;
+        assert_eq!(countersignatures.len(), 2);
+        assert_eq!(countersignatures[1].name, "COSE_Countersignature");
+        assert_eq!(
+            countersignatures[1].note.as_deref(),
+            Some("countersignature 2 of 2")
+        );
+        assert_eq!(header_name(&CborValue::Unsigned(8)), None);
+
+        // A protected header is decoded below its byte string
+        let protected = protected_label("protected", &[0xA1, 0x04, 0x41, 0x31], header_name);
+        assert!(protected.encoded);
+        assert_eq!(protected.children[0].name, "kid");
+        assert!(!protected_label("protected", &[], header_name).encoded);
+    }
+    #[test]
+    fn test_recipient_labels() {
+        // 96([h'', {}, h'AA', [[h'', {-1: {1: 1, -1: 6, -2: h'01'}, -22: h'00'},
+        //     h'BB', [[h'', {1: -5}, h'CC']]]]]), ECDH-ES+AESKW with a layer below
+        let data = [
+            0xD8, 0x60, 0x84, 0x40, 0xA0, 0x41, 0xAA, 0x81, 0x84, 0x40, 0xA2, 0x20, 0xA3, 0x01,
+            0x01, 0x20, 0x06, 0x21, 0x41, 0x01, 0x35, 0x41, 0x00, 0x41, 0xBB, 0x81, 0x83, 0x40,
+            0xA1, 0x01, 0x24, 0x41, 0xCC,
+        ];
+        let items = parse_slice(&data).unwrap();
+        let encrypt = message(96).unwrap();
+        assert_eq!(encrypt.check(&items[0]), Ok(()));
+        let CborValue::Tag(_, array) = &items[0].value else {
+            panic!("expected a tag");
+        };
+        let CborValue::Array(elements) = &array.value else {
+            panic!("expected an array");
+        };
+        let labels = encrypt.labels(elements);
+        let recipient = &labels[3].children[0];
+        assert_eq!(recipient.name, "COSE_recipient");
+        let header = &recipient.children[1].children;
+        assert_eq!(header[0].name, "ephemeral key");
+        let key: Vec<&str> = header[0].children.iter().map(|label| label.name).collect();
+        assert_eq!(key, ["kty", "crv", "x"]);
+        assert_eq!(header[1].name, "PartyU nonce");
+        let inner = &recipient.children[3].children[0];
+        assert_eq!(inner.children[1].children[0].name, "alg");
+
+        // Key agreement parameters are only named in a recipient's header
+        assert_eq!(header_name(&CborValue::Negative(0)), None);
+        assert_eq!(recipient_header_name(&CborValue::Unsigned(4)), Some("kid"));
+    }
+    #[test]
+    fn test_registered_names() {
+        assert_eq!(algorithm_name(&CborValue::Negative(6)), Some("ES256"));
+        assert_eq!(algorithm_name(&CborValue::Negative(7)), Some("EdDSA"));
+        assert_eq!(algorithm_name(&CborValue::Unsigned(1)), Some("A128GCM"));
+        assert_eq!(algorithm_name(&CborValue::Unsigned(8)), None);
+        assert_eq!(key_type_name(&CborValue::Unsigned(1)), Some("OKP"));
+        assert_eq!(curve_name(&CborValue::Unsigned(6)), Some("Ed25519"));
+
+        // {1: 1, 3: -8, -1: 6, -2: h'01'}, an Ed25519 key
+        let data = [0xA4, 0x01, 0x01, 0x03, 0x27, 0x20, 0x06, 0x21, 0x41, 0x01];
+        let items = parse_slice(&data).unwrap();
+        let CborValue::Map(pairs) = &items[0].value else {
+            panic!("expected a map");
+        };
+        let labels = key_labels(pairs);
+        let notes: Vec<Option<&str>> = labels.iter().map(|label| label.note.as_deref()).collect();
+        assert_eq!(notes, [Some("OKP"), Some("EdDSA"), Some("Ed25519"), None]);
+
+        // {1: "a", 8: {3: h'6B'}}, claims with a confirmation by key ID
+        let data = [0xA2, 0x01, 0x61, b'a', 0x08, 0xA1, 0x03, 0x41, 0x6B];
+        let items = parse_slice(&data).unwrap();
+        let CborValue::Map(pairs) = &items[0].value else {
+            panic!("expected a map");
+        };
+        let labels = claims_labels(pairs);
+        assert_eq!(labels[0].name, "iss");
+        assert_eq!(labels[1].name, "cnf");
+        assert_eq!(labels[1].children[0].name, "kid");
+    }
+}
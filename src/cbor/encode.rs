@@ -0,0 +1,287 @@
+// Encoding CBOR values with preferred serialization (RFC 8949 section 4.1):
+// arguments as short as possible, definite lengths, and floats in the
+// shortest width that keeps their value. Canonical mode also sorts map keys
+// by their encodings, giving the deterministic encoding of section 4.2.1.
+
+use super::*;
+
+/// Serializes CBOR values to bytes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborEncoder {
+    canonical: bool,
+}
+
+impl CborEncoder {
+    pub fn new() -> Self {
+        CborEncoder::default()
+    }
+
+    /// Sort map keys bytewise by their encodings, so that equal values
+    /// always encode to the same bytes
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Encode a value to a new buffer
+    pub fn encode(&self, value: &CborValue) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_to(value, &mut out);
+        out
+    }
+
+    /// Append the encoding of a value to `out`
+    pub fn encode_to(&self, value: &CborValue, out: &mut Vec<u8>) {
+        match value {
+            CborValue::Unsigned(n) => write_head(out, MAJOR_UNSIGNED, *n),
+            CborValue::Negative(n) => write_head(out, MAJOR_NEGATIVE, *n),
+            CborValue::Bytes(bytes) => {
+                write_head(out, MAJOR_BYTES, bytes.len() as u64);
+                out.extend_from_slice(bytes);
+            }
+            CborValue::Text(text) => {
+                write_head(out, MAJOR_TEXT, text.len() as u64);
+                out.extend_from_slice(text.as_bytes());
+            }
+            CborValue::Array(items) => {
+                write_head(out, MAJOR_ARRAY, items.len() as u64);
+                for item in items {
+                    self.encode_to(&item.value, out);
+                }
+            }
+            CborValue::Map(pairs) => {
+                write_head(out, MAJOR_MAP, pairs.len() as u64);
+                if self.canonical {
+                    let mut encoded: Vec<(Vec<u8>, &CborValue)> = pairs
+                        .iter()
+                        .map(|(key, value)| (self.encode(&key.value), &value.value))
+                        .collect();
+                    encoded.sort_by(|a, b| a.0.cmp(&b.0));
+                    for (key, value) in encoded {
+                        out.extend_from_slice(&key);
+                        self.encode_to(value, out);
+                    }
+                } else {
+                    for (key, value) in pairs {
+                        self.encode_to(&key.value, out);
+                        self.encode_to(&value.value, out);
+                    }
+                }
+            }
+            CborValue::Tag(tag, item) => {
+                write_head(out, MAJOR_TAG, *tag);
+                self.encode_to(&item.value, out);
+            }
+            CborValue::Simple(n) => write_head(out, MAJOR_SIMPLE, *n as u64),
+            CborValue::Boolean(false) => out.push(MAJOR_SIMPLE << 5 | SIMPLE_FALSE),
+            CborValue::Boolean(true) => out.push(MAJOR_SIMPLE << 5 | SIMPLE_TRUE),
+            CborValue::Null => out.push(MAJOR_SIMPLE << 5 | SIMPLE_NULL),
+            CborValue::Undefined => out.push(MAJOR_SIMPLE << 5 | SIMPLE_UNDEFINED),
+            CborValue::Float16(f) | CborValue::Float32(f) => write_float(out, *f as f64),
+            CborValue::Float64(f) => write_float(out, *f),
+            CborValue::Break => out.push(BREAK),
+        }
+    }
+}
+
+impl CborValue<'_> {
+    /// Encode with preferred serialization
+    pub fn encode(&self) -> Vec<u8> {
+        CborEncoder::new().encode(self)
+    }
+}
+
+/// Write an initial byte and the shortest encoding of its argument
+pub(super) fn write_head(out: &mut Vec<u8>, major_type: u8, argument: u64) {
+    let ai = value::argument_info(argument);
+    out.push(major_type << 5 | ai);
+    let size = match ai {
+        AI_1BYTE => 1,
+        AI_2BYTES => 2,
+        AI_4BYTES => 4,
+        AI_8BYTES => 8,
+        _ => return,
+    };
+    out.extend_from_slice(&argument.to_be_bytes()[8 - size..]);
+}
+
+/// Write a float in the shortest width that holds its value exactly
+pub(super) fn write_float(out: &mut Vec<u8>, f: f64) {
+    match f64_to_f32(f) {
+        Some(single) => match f32_to_f16(single) {
+            Some(half) => {
+                out.push(MAJOR_SIMPLE << 5 | AI_2BYTES);
+                out.extend_from_slice(&half.to_be_bytes());
+            }
+            None => {
+                out.push(MAJOR_SIMPLE << 5 | AI_4BYTES);
+                out.extend_from_slice(&single.to_bits().to_be_bytes());
+            }
+        },
+        None => {
+            out.push(MAJOR_SIMPLE << 5 | AI_8BYTES);
+            out.extend_from_slice(&f.to_bits().to_be_bytes());
+        }
+    }
+}
+
+/// Bits of the half-precision float equal to `f`, if there is one: the
+/// width preferred serialization would have written it in
+pub fn half_bits(f: f64) -> Option<u16> {
+    f64_to_f32(f).and_then(f32_to_f16)
+}
+
+/// Narrow a float to single precision if that loses nothing, NaN payloads
+/// included
+fn f64_to_f32(f: f64) -> Option<f32> {
+    if f.is_nan() {
+        let bits = f.to_bits();
+        if bits & 0x1FFF_FFFF != 0 {
+            return None;
+        }
+        let sign = (bits >> 63) as u32;
+        let payload = ((bits >> 29) & 0x7F_FFFF) as u32;
+        return Some(f32::from_bits(sign << 31 | 0xFF << 23 | payload));
+    }
+    let single = f as f32;
+    (single as f64 == f).then_some(single)
+}
+
+/// Bits of the half-precision float equal to `f`, if there is one
+fn f32_to_f16(f: f32) -> Option<u16> {
+    let bits = f.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xFF) as i32;
+    let mant = bits & 0x7F_FFFF;
+
+    if exp == 0xFF {
+        // Infinity or NaN
+        if mant & 0x1FFF != 0 {
+            return None;
+        }
+        return Some(sign | 0x7C00 | (mant >> 13) as u16);
+    }
+    if exp == 0 {
+        // Zero; single-precision subnormals are too small for half precision
+        return (mant == 0).then_some(sign);
+    }
+    let exp = exp - 127;
+    match exp {
+        -14..=15 if mant & 0x1FFF == 0 => {
+            Some(sign | ((exp + 15) as u16) << 10 | (mant >> 13) as u16)
+        }
+        -24..=-15 => {
+            // Subnormal: the significand, implicit bit included, in units
+            // of 2^-24
+            let significand = mant | 0x80_0000;
+            let shift = (-exp - 1) as u32;
+            if significand & ((1 << shift) - 1) != 0 {
+                return None;
+            }
+            Some(sign | (significand >> shift) as u16)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::hex::{hex_lower, parse_hex};
+    use crate::common::testutil::item;
+
+    #[test]
+    fn test_encode_values() {
+        // Examples from RFC 8949 Appendix A
+        let cases = [
+            (CborValue::Unsigned(0), "00"),
+            (CborValue::Unsigned(24), "1818"),
+            (CborValue::Unsigned(1000000), "1a000f4240"),
+            (CborValue::Unsigned(u64::MAX), "1bffffffffffffffff"),
+            (CborValue::Negative(0), "20"),
+            (CborValue::Negative(999), "3903e7"),
+            (CborValue::Float64(0.0), "f90000"),
+            (CborValue::Float64(-0.0), "f98000"),
+            (CborValue::Float64(1.5), "f93e00"),
+            (CborValue::Float32(100000.0), "fa47c35000"),
+            (CborValue::Float64(1.1), "fb3ff199999999999a"),
+            (CborValue::Float64(5.960464477539063e-8), "f90001"),
+            (CborValue::Float64(0.00006103515625), "f90400"),
+            (CborValue::Float64(-4.0), "f9c400"),
+            (CborValue::Float64(1.0e300), "fb7e37e43c8800759c"),
+            (CborValue::Float64(f64::INFINITY), "f97c00"),
+            (CborValue::Float64(f64::NAN), "f97e00"),
+            (CborValue::Boolean(false), "f4"),
+            (CborValue::Null, "f6"),
+            (CborValue::Undefined, "f7"),
+            (CborValue::Simple(16), "f0"),
+            (CborValue::Simple(255), "f8ff"),
+            (CborValue::Bytes(vec![1, 2, 3, 4].into()), "4401020304"),
+            (CborValue::Text("IETF".into()), "6449455446"),
+            (CborValue::Text("\u{00fc}".into()), "62c3bc"),
+            (
+                CborValue::Tag(1, Box::new(item(CborValue::Unsigned(1363896240)))),
+                "c11a514b67b0",
+            ),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(hex_lower(&value.encode()), expected, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Preferred serializations from RFC 8949 Appendix A re-encode to the
+        // same bytes, including the indefinite-length forms' definite twins
+        for encoded in [
+            "8301820203820405",
+            "a26161016162820203",
+            "a56161614161626142616361436164614461656145",
+            "826161a161626163",
+            "d82076687474703a2f2f7777772e6578616d706c652e636f6d",
+            "f90001",
+            "f97bff",
+            "fa7f7fffff",
+            "c249010000000000000000",
+            "3b7fffffffffffffff",
+            "3bfffffffffffffffe",
+            "3bffffffffffffffff",
+        ] {
+            let data = parse_hex(encoded).unwrap();
+            let items = parse_slice(&data).unwrap();
+            assert_eq!(hex_lower(&items[0].value.encode()), encoded);
+        }
+        // Indefinite lengths become definite
+        let data = parse_hex("9f018202039f0405ffff").unwrap();
+        let items = parse_slice(&data).unwrap();
+        assert_eq!(hex_lower(&items[0].value.encode()), "8301820203820405");
+    }
+
+    #[test]
+    fn test_canonical_map() {
+        // The key order example of RFC 8949 section 4.2.1
+        let keys = [
+            CborValue::Boolean(false),
+            CborValue::Array(vec![item(CborValue::Negative(0))]),
+            CborValue::Text("aa".into()),
+            CborValue::Negative(0),
+            CborValue::Text("z".into()),
+            CborValue::Unsigned(100),
+            CborValue::Unsigned(10),
+        ];
+        let map = CborValue::Map(
+            keys.into_iter()
+                .map(|key| (item(key), item(CborValue::Null)))
+                .collect(),
+        );
+        // Without canonical mode, pairs keep their order
+        assert_eq!(
+            hex_lower(&map.encode()),
+            "a7f4f68120f6626161f620f6617af61864f60af6"
+        );
+        assert_eq!(
+            hex_lower(&CborEncoder::new().canonical(true).encode(&map)),
+            "a70af61864f620f6617af6626161f68120f6f4f6"
+        );
+    }
+}
@@ -0,0 +1,418 @@
+// Random CBOR for building fuzz corpora: well-formed data items of bounded
+// depth and size, all drawn from a seed so that a corpus can be made again.
+// Unless held to preferred serialization, arguments are sometimes written
+// wider than they need to be and lengths left indefinite. A malformed item
+// is a well-formed one with a single fault put in it: cut short, a reserved
+// additional info value or an indefinite-length integer in place of one of
+// its items, or a byte of a text string that isn't UTF-8.
+
+use super::encode::{write_float, write_head};
+use super::*;
+
+// Tags given to generated items, besides random ones
+const TAGS: &[u64] = &[
+    TAG_DATETIME,
+    TAG_EPOCH,
+    TAG_BIGNUM_POS,
+    TAG_BIGNUM_NEG,
+    TAG_BASE64URL,
+    TAG_CBOR,
+    TAG_URI,
+    TAG_SELF_DESCRIBE,
+];
+
+// Characters of text strings, of every UTF-8 length
+const CHARS: &[char] = &['a', 'k', 'z', '0', ' ', '"', 'é', 'ß', '€', '漢', '😀'];
+
+// Floats with encodings of each width, and the special values
+const FLOATS: &[f64] = &[
+    0.0,
+    -0.0,
+    1.5,
+    -4.0,
+    65504.0,
+    100000.0,
+    1.1,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::NAN,
+];
+
+/// Generates random CBOR data items from a seed
+#[derive(Debug, Clone)]
+pub struct CborGenerator {
+    random: Random,
+    max_depth: usize,
+    max_length: usize,
+    preferred: bool,
+    malformed: bool,
+    items: Vec<ItemSpan>, // Where each item of the one being made is
+}
+
+/// Where an item is in the encoding, to put a fault in
+#[derive(Debug, Clone, Copy)]
+struct ItemSpan {
+    start: usize,
+    end: usize,
+    text: bool, // A text string of definite length, ending with its content
+}
+
+impl CborGenerator {
+    /// A generator whose items are all decided by `seed`
+    pub fn new(seed: u64) -> Self {
+        CborGenerator {
+            random: Random::new(seed),
+            max_depth: 4,
+            max_length: 8,
+            preferred: false,
+            malformed: false,
+            items: Vec::new(),
+        }
+    }
+
+    /// Nest arrays, maps and tags at most this deep (default 4)
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Give arrays and maps at most this many entries, and strings at most
+    /// this many bytes or characters (default 8)
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Write only preferred serialization: the shortest arguments and floats,
+    /// and definite lengths
+    pub fn preferred(mut self, preferred: bool) -> Self {
+        self.preferred = preferred;
+        self
+    }
+
+    /// Put a fault in each item, so that a parser has to reject it
+    pub fn malformed(mut self, malformed: bool) -> Self {
+        self.malformed = malformed;
+        self
+    }
+
+    /// Generate the encoding of the next item
+    pub fn generate(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.items.clear();
+        self.item(&mut out, 0, true);
+        if self.malformed {
+            self.break_item(&mut out);
+        }
+        out
+    }
+
+    fn next(&mut self) -> u64 {
+        self.random.next()
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.random.below(n)
+    }
+
+    fn one_in(&mut self, n: usize) -> bool {
+        self.random.one_in(n)
+    }
+
+    fn pick<T: Copy>(&mut self, choices: &[T]) -> T {
+        self.random.pick(choices)
+    }
+
+    /// An argument of a random width, so that every width is written
+    fn argument(&mut self) -> u64 {
+        let bits = self.pick(&[5, 8, 16, 32, 64]);
+        match bits {
+            5 => self.next() % 24,
+            64 => self.next(),
+            _ => self.next() % (1 << bits),
+        }
+    }
+
+    fn length(&mut self) -> usize {
+        self.below(self.max_length + 1)
+    }
+
+    /// Write an item's initial byte and argument, sometimes wider than it
+    /// needs to be
+    fn head(&mut self, out: &mut Vec<u8>, major_type: u8, argument: u64) {
+        if self.preferred || !self.one_in(4) {
+            return write_head(out, major_type, argument);
+        }
+        let needed = match value::argument_info(argument) {
+            ai if ai < AI_1BYTE => AI_1BYTE,
+            ai => ai,
+        };
+        let ai = needed + self.below((AI_8BYTES - needed + 1) as usize) as u8;
+        out.push(major_type << 5 | ai);
+        let size = 1 << (ai - AI_1BYTE);
+        out.extend_from_slice(&argument.to_be_bytes()[8 - size..]);
+    }
+
+    /// Whether to leave the length of a string, array or map indefinite
+    fn indefinite(&mut self) -> bool {
+        !self.preferred && self.one_in(5)
+    }
+
+    /// Write a random item, no deeper than max_depth, noting where it is if
+    /// `record` is set
+    fn item(&mut self, out: &mut Vec<u8>, depth: usize, record: bool) {
+        let start = out.len();
+        let index = self.items.len();
+        if record {
+            self.items.push(ItemSpan {
+                start,
+                end: start,
+                text: false,
+            });
+        }
+        // Arrays, maps and tags are left out at the deepest level
+        let kind = if depth < self.max_depth {
+            self.below(8)
+        } else {
+            self.pick(&[0, 1, 2, 3, 7])
+        };
+        let mut text = false;
+        match kind {
+            0 => {
+                let n = self.argument();
+                self.head(out, MAJOR_UNSIGNED, n);
+            }
+            1 => {
+                let n = self.argument();
+                self.head(out, MAJOR_NEGATIVE, n);
+            }
+            2 => {
+                let bytes: Vec<u8> = (0..self.length()).map(|_| self.next() as u8).collect();
+                self.string(out, MAJOR_BYTES, &bytes, &vec![1; bytes.len()]);
+            }
+            3 => {
+                let text_value: String = (0..self.length()).map(|_| self.pick(CHARS)).collect();
+                let chars: Vec<usize> = text_value.chars().map(char::len_utf8).collect();
+                let definite = self.string(out, MAJOR_TEXT, text_value.as_bytes(), &chars);
+                text = definite && !text_value.is_empty();
+            }
+            4 => {
+                let count = self.length();
+                self.container(out, MAJOR_ARRAY, count, |generator, out| {
+                    generator.item(out, depth + 1, record);
+                });
+            }
+            5 => {
+                let count = self.length();
+                self.container(out, MAJOR_MAP, count, |generator, out| {
+                    generator.item(out, generator.max_depth, record);
+                    generator.item(out, depth + 1, record);
+                });
+            }
+            6 => {
+                let tag = if self.one_in(3) {
+                    self.argument()
+                } else {
+                    self.pick(TAGS)
+                };
+                self.head(out, MAJOR_TAG, tag);
+                if tag == TAG_CBOR {
+                    // The content's items are inside a byte string, where a
+                    // fault would only make the string's content malformed
+                    let mut embedded = Vec::new();
+                    self.item(&mut embedded, depth + 1, false);
+                    self.head(out, MAJOR_BYTES, embedded.len() as u64);
+                    out.extend_from_slice(&embedded);
+                } else {
+                    self.item(out, depth + 1, record);
+                }
+            }
+            _ if self.one_in(2) => self.float(out),
+            _ => self.simple(out),
+        }
+        if record {
+            self.items[index].end = out.len();
+            self.items[index].text = text;
+        }
+    }
+
+    /// Write a string, in chunks if its length is left indefinite, split
+    /// only between the pieces `units` gives the lengths of. Returns whether
+    /// it was written with a definite length
+    fn string(&mut self, out: &mut Vec<u8>, major_type: u8, bytes: &[u8], units: &[usize]) -> bool {
+        if !self.indefinite() {
+            self.head(out, major_type, bytes.len() as u64);
+            out.extend_from_slice(bytes);
+            return true;
+        }
+        out.push(major_type << 5 | AI_INDEFINITE);
+        let mut start = 0;
+        let mut units = units.iter();
+        while start < bytes.len() {
+            let mut end = start;
+            for _ in 0..=self.below(3) {
+                match units.next() {
+                    Some(unit) => end += unit,
+                    None => break,
+                }
+            }
+            self.head(out, major_type, (end - start) as u64);
+            out.extend_from_slice(&bytes[start..end]);
+            start = end;
+        }
+        out.push(BREAK);
+        false
+    }
+
+    /// Write an array or map of `count` entries, each written by `entry`
+    fn container(
+        &mut self,
+        out: &mut Vec<u8>,
+        major_type: u8,
+        count: usize,
+        mut entry: impl FnMut(&mut Self, &mut Vec<u8>),
+    ) {
+        let indefinite = self.indefinite();
+        if indefinite {
+            out.push(major_type << 5 | AI_INDEFINITE);
+        } else {
+            self.head(out, major_type, count as u64);
+        }
+        for _ in 0..count {
+            entry(self, out);
+        }
+        if indefinite {
+            out.push(BREAK);
+        }
+    }
+
+    fn float(&mut self, out: &mut Vec<u8>) {
+        let f = if self.one_in(3) {
+            f64::from_bits(self.next())
+        } else {
+            self.pick(FLOATS)
+        };
+        if self.preferred || !self.one_in(3) {
+            write_float(out, f);
+        } else {
+            out.push(MAJOR_SIMPLE << 5 | AI_8BYTES);
+            out.extend_from_slice(&f.to_bits().to_be_bytes());
+        }
+    }
+
+    fn simple(&mut self, out: &mut Vec<u8>) {
+        let value = match self.below(3) {
+            0 => SIMPLE_FALSE + self.below(4) as u8, // false, true, null, undefined
+            1 => self.below(SIMPLE_FALSE as usize) as u8,
+            // Values below 32 in a byte of their own are malformed
+            _ => 32 + self.below(224) as u8,
+        };
+        write_head(out, MAJOR_SIMPLE, value as u64);
+    }
+
+    /// Put a fault in the item just generated
+    fn break_item(&mut self, out: &mut Vec<u8>) {
+        let index = self.below(self.items.len());
+        let span = self.items[index];
+        match self.below(4) {
+            // Each item is complete only at its end
+            0 if out.len() > 1 => out.truncate(1 + self.below(out.len() - 1)),
+            1 if span.text => out[span.end - 1] = 0xFF, // Never in UTF-8
+            2 => {
+                let major_type = self.pick(&[MAJOR_UNSIGNED, MAJOR_NEGATIVE, MAJOR_TAG]);
+                out.splice(span.start..span.end, [major_type << 5 | AI_INDEFINITE]);
+            }
+            _ => {
+                let major_type = self.below(8) as u8;
+                let reserved = 28 + self.below(3) as u8;
+                out.splice(span.start..span.end, [major_type << 5 | reserved]);
+            }
+        }
+    }
+}
+
+/// Random numbers from a seed, by SplitMix64
+#[derive(Debug, Clone)]
+pub(super) struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub(super) fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    pub(super) fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A random number below `n`, which must be above 0
+    pub(super) fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// True one time in `n`
+    pub(super) fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    pub(super) fn pick<T: Copy>(&mut self, choices: &[T]) -> T {
+        choices[self.below(choices.len())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_items_parse() {
+        for preferred in [false, true] {
+            let mut generator = CborGenerator::new(7).preferred(preferred);
+            for _ in 0..500 {
+                let data = generator.generate();
+                let items = parse_slice(&data).expect("well-formed");
+                assert_eq!(items.len(), 1);
+                if preferred {
+                    assert_eq!(CborEncoder::new().encode(&items[0].value), data);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_items() {
+        let generate = |seed| {
+            let mut generator = CborGenerator::new(seed).max_depth(2).max_length(3);
+            (0..20).map(|_| generator.generate()).collect::<Vec<_>>()
+        };
+        assert_eq!(generate(1), generate(1));
+        assert_ne!(generate(1), generate(2));
+    }
+
+    #[test]
+    fn test_malformed_items_are_rejected() {
+        let mut generator = CborGenerator::new(11).malformed(true);
+        for _ in 0..500 {
+            let data = generator.generate();
+            assert!(parse_slice(&data).is_err(), "{:02X?}", data);
+        }
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let mut generator = CborGenerator::new(3).max_depth(0);
+        for _ in 0..100 {
+            let data = generator.generate();
+            let items = parse_slice(&data).unwrap();
+            assert!(!matches!(
+                items[0].value,
+                CborValue::Array(_) | CborValue::Map(_) | CborValue::Tag(..)
+            ));
+        }
+    }
+}
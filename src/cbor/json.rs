@@ -0,0 +1,245 @@
+// Conversion between CBOR values and serde_json values (the "json" feature).
+// Where JSON has no equivalent, conversion follows RFC 8949 section 6.1:
+//
+// - Byte strings become base64url text without padding, or base64 or base16
+//   text inside tags 21, 22 and 23
+// - Bignums (tags 2 and 3), and negative integers below -2^63, become text
+//   holding their decimal value
+// - Other tags are dropped, leaving the tagged item converted
+// - Map keys other than text strings are stringified: integers as decimal,
+//   anything else as the JSON text of its conversion. If stringified keys
+//   collide, the last value wins.
+// - NaN, infinities, undefined and simple values other than true, false and
+//   null become null
+//
+// Going the other way, JSON strings always become text strings, and numbers
+// become integers if they're written without a fraction or exponent and fit
+// in 64 bits, otherwise float64. Byte strings, tags and bignums are never
+// produced, so a CBOR -> JSON -> CBOR round trip isn't exact in general.
+
+use std::borrow::Cow;
+
+use serde_json::{Map, Number, Value};
+
+use super::*;
+use crate::asn1::pem::{encode_base64, encode_base64_with, Alphabet};
+use crate::common::hex::hex_lower;
+
+/// How byte strings are written as JSON text
+#[derive(Clone, Copy)]
+enum ByteEncoding {
+    Base64Url,
+    Base64,
+    Base16,
+}
+
+impl CborValue<'_> {
+    /// Convert to a serde_json value, losing what JSON can't express as
+    /// described in the module notes
+    pub fn to_json(&self) -> Value {
+        self.to_json_with(ByteEncoding::Base64Url)
+    }
+
+    fn to_json_with(&self, encoding: ByteEncoding) -> Value {
+        match self {
+            CborValue::Unsigned(n) => Value::from(*n),
+            CborValue::Negative(n) => {
+                let n = -1 - i128::from(*n);
+                i64::try_from(n).map_or_else(|_| Value::String(n.to_string()), Value::from)
+            }
+            CborValue::Bytes(bytes) => Value::String(encode_bytes(bytes, encoding)),
+            CborValue::Text(text) => Value::String(text.to_string()),
+            CborValue::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| item.value.to_json_with(encoding))
+                    .collect(),
+            ),
+            CborValue::Map(pairs) => {
+                let mut object = Map::new();
+                for (key, value) in pairs {
+                    object.insert(
+                        key_string(&key.value, encoding),
+                        value.value.to_json_with(encoding),
+                    );
+                }
+                Value::Object(object)
+            }
+            CborValue::Tag(tag @ (TAG_BIGNUM_POS | TAG_BIGNUM_NEG), item) => match &item.value {
+                CborValue::Bytes(bytes) => Value::String(bignum(bytes, *tag == TAG_BIGNUM_NEG)),
+                other => other.to_json_with(encoding),
+            },
+            CborValue::Tag(TAG_BASE64URL, item) => item.value.to_json_with(ByteEncoding::Base64Url),
+            CborValue::Tag(TAG_BASE64, item) => item.value.to_json_with(ByteEncoding::Base64),
+            CborValue::Tag(TAG_BASE16, item) => item.value.to_json_with(ByteEncoding::Base16),
+            CborValue::Tag(_, item) => item.value.to_json_with(encoding),
+            CborValue::Boolean(b) => Value::Bool(*b),
+            CborValue::Float16(f) | CborValue::Float32(f) => float(*f as f64),
+            CborValue::Float64(f) => float(*f),
+            CborValue::Simple(_) | CborValue::Null | CborValue::Undefined | CborValue::Break => {
+                Value::Null
+            }
+        }
+    }
+}
+
+impl CborValue<'static> {
+    /// Convert a serde_json value, as described in the module notes
+    pub fn from_json(json: &Value) -> Self {
+        match json {
+            Value::Null => CborValue::Null,
+            Value::Bool(b) => CborValue::Boolean(*b),
+            Value::Number(n) => {
+                if let Some(n) = n.as_u64() {
+                    CborValue::Unsigned(n)
+                } else if let Some(n) = n.as_i64() {
+                    CborValue::Negative((-1 - n) as u64)
+                } else {
+                    CborValue::Float64(n.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            Value::String(s) => CborValue::Text(Cow::Owned(s.clone())),
+            Value::Array(values) => CborValue::Array(
+                values
+                    .iter()
+                    .map(|value| CborItem::from_value(CborValue::from_json(value)))
+                    .collect(),
+            ),
+            Value::Object(object) => CborValue::Map(
+                object
+                    .iter()
+                    .map(|(key, value)| {
+                        (
+                            CborItem::from_value(CborValue::Text(Cow::Owned(key.clone()))),
+                            CborItem::from_value(CborValue::from_json(value)),
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Stringify a map key for a JSON object
+fn key_string(key: &CborValue, encoding: ByteEncoding) -> String {
+    match key {
+        CborValue::Text(text) => text.to_string(),
+        CborValue::Unsigned(_) | CborValue::Negative(_) => {
+            key.integer().unwrap_or_default().to_string()
+        }
+        other => other.to_json_with(encoding).to_string(),
+    }
+}
+
+/// JSON number for a float, or null for NaN and infinities
+fn float(f: f64) -> Value {
+    Number::from_f64(f).map_or(Value::Null, Value::Number)
+}
+
+fn encode_bytes(bytes: &[u8], encoding: ByteEncoding) -> String {
+    match encoding {
+        ByteEncoding::Base64Url => encode_base64_with(bytes, Alphabet::UrlSafe),
+        ByteEncoding::Base64 => encode_base64(bytes),
+        ByteEncoding::Base16 => hex_lower(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testutil::item;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_json() {
+        let value = CborValue::Map(vec![
+            (
+                item(CborValue::Text("bytes".into())),
+                item(CborValue::Bytes(vec![0xFB, 0xFF].into())),
+            ),
+            (
+                item(CborValue::Unsigned(1)),
+                item(CborValue::Tag(
+                    TAG_BASE16,
+                    Box::new(item(CborValue::Array(vec![item(CborValue::Bytes(
+                        vec![0xAB].into(),
+                    ))]))),
+                )),
+            ),
+            (
+                item(CborValue::Bytes(vec![0x01].into())),
+                item(CborValue::Tag(
+                    TAG_BIGNUM_NEG,
+                    Box::new(item(CborValue::Bytes(vec![0x01, 0x00].into()))),
+                )),
+            ),
+            (
+                item(CborValue::Negative(1)),
+                item(CborValue::Array(vec![
+                    item(CborValue::Float64(f64::NAN)),
+                    item(CborValue::Float16(1.5)),
+                    item(CborValue::Undefined),
+                    item(CborValue::Tag(
+                        0,
+                        Box::new(item(CborValue::Text("t".into()))),
+                    )),
+                ])),
+            ),
+        ]);
+        assert_eq!(
+            value.to_json(),
+            json!({
+                "bytes": "-_8",
+                "1": ["ab"],
+                "\"AQ\"": "-257",
+                "-2": [null, 1.5, null, "t"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_json() {
+        let json = json!({"a": [1, -2, 2.5, "x", null, true, 18446744073709551615u64]});
+        let value = CborValue::from_json(&json);
+        let CborValue::Map(pairs) = &value else {
+            panic!("expected a map");
+        };
+        assert!(matches!(&pairs[0].0.value, CborValue::Text(key) if key == "a"));
+        let CborValue::Array(items) = &pairs[0].1.value else {
+            panic!("expected an array");
+        };
+        let values: Vec<String> = items.iter().map(|i| format!("{:?}", i.value)).collect();
+        assert_eq!(
+            values,
+            [
+                "Unsigned(1)",
+                "Negative(1)",
+                "Float64(2.5)",
+                "Text(\"x\")",
+                "Null",
+                "Boolean(true)",
+                "Unsigned(18446744073709551615)"
+            ]
+        );
+        assert_eq!(items[6].additional_info, AI_8BYTES);
+        assert_eq!(value.to_json(), json);
+    }
+
+    #[test]
+    fn test_negative_range() {
+        // -2^63 is the last one serde_json holds as a number
+        let value = CborValue::Array(vec![
+            item(CborValue::Negative(i64::MAX as u64)),
+            item(CborValue::Negative(1 << 63)),
+            item(CborValue::Negative(u64::MAX)),
+        ]);
+        assert_eq!(
+            value.to_json(),
+            json!([i64::MIN, "-9223372036854775809", "-18446744073709551616"])
+        );
+        let CborValue::Array(items) = CborValue::from_json(&json!([i64::MIN])) else {
+            panic!("expected an array");
+        };
+        assert!(matches!(items[0].value, CborValue::Negative(n) if n == i64::MAX as u64));
+    }
+}
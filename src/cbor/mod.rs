@@ -0,0 +1,107 @@
+// CBOR data items and the parsers that produce them, plus recognizers for
+// CBOR-native formats whose elements are labeled in the dump, the layouts
+// of COSE messages, the CoAP content formats, a generator of random items
+// for fuzz corpora, a mutator of well-formed ones, and the schemas inferred
+// from samples of items with the Rust types and CDDL codegen writes for them
+
+pub mod c509;
+mod cddl;
+pub mod coap;
+mod codegen;
+mod compare;
+pub mod cose;
+mod encode;
+mod generate;
+#[cfg(feature = "json")]
+mod json;
+mod mutate;
+mod node;
+mod parse;
+mod schema;
+mod value;
+
+pub use cddl::cddl;
+pub use codegen::rust_types;
+pub use encode::{half_bits, CborEncoder};
+pub use generate::CborGenerator;
+pub use mutate::CborMutator;
+pub use node::to_nodes;
+pub use parse::{f16_to_f32, parse_slice, END_BEFORE_BREAK};
+pub use schema::{Field, Schema};
+pub(crate) use value::bignum;
+pub use value::{CborItem, CborValue};
+
+// CBOR major types
+pub const MAJOR_UNSIGNED: u8 = 0;
+pub const MAJOR_NEGATIVE: u8 = 1;
+pub const MAJOR_BYTES: u8 = 2;
+pub const MAJOR_TEXT: u8 = 3;
+pub const MAJOR_ARRAY: u8 = 4;
+pub const MAJOR_MAP: u8 = 5;
+pub const MAJOR_TAG: u8 = 6;
+pub const MAJOR_SIMPLE: u8 = 7;
+
+// Additional info values
+pub const AI_1BYTE: u8 = 24;
+pub const AI_2BYTES: u8 = 25;
+pub const AI_4BYTES: u8 = 26;
+pub const AI_8BYTES: u8 = 27;
+pub const AI_INDEFINITE: u8 = 31;
+
+// The break code ending indefinite-length items
+pub const BREAK: u8 = 0xFF;
+
+// Simple values
+pub const SIMPLE_FALSE: u8 = 20;
+pub const SIMPLE_TRUE: u8 = 21;
+pub const SIMPLE_NULL: u8 = 22;
+pub const SIMPLE_UNDEFINED: u8 = 23;
+
+// Well-known CBOR tags
+pub const TAG_DATETIME: u64 = 0;
+pub const TAG_EPOCH: u64 = 1;
+pub const TAG_BIGNUM_POS: u64 = 2;
+pub const TAG_BIGNUM_NEG: u64 = 3;
+pub const TAG_DECIMAL: u64 = 4;
+pub const TAG_BIGFLOAT: u64 = 5;
+pub const TAG_BASE64URL: u64 = 21;
+pub const TAG_BASE64: u64 = 22;
+pub const TAG_BASE16: u64 = 23;
+pub const TAG_CBOR: u64 = 24;
+pub const TAG_RATIONAL: u64 = 30;
+pub const TAG_URI: u64 = 32;
+pub const TAG_BASE64URL_ENC: u64 = 33;
+pub const TAG_BASE64_ENC: u64 = 34;
+pub const TAG_REGEX: u64 = 35;
+pub const TAG_MIME: u64 = 36;
+pub const TAG_CBOR_SEQUENCE: u64 = 63;
+pub const TAG_SELF_DESCRIBE: u64 = 55799;
+
+/// Get the name of a well-known tag
+pub fn tag_name(tag: u64) -> Option<&'static str> {
+    match tag {
+        TAG_DATETIME => Some("date/time string"),
+        TAG_EPOCH => Some("epoch-based date/time"),
+        TAG_BIGNUM_POS => Some("positive bignum"),
+        TAG_BIGNUM_NEG => Some("negative bignum"),
+        TAG_DECIMAL => Some("decimal fraction"),
+        TAG_BIGFLOAT => Some("bigfloat"),
+        TAG_BASE64URL => Some("base64url encoding"),
+        TAG_BASE64 => Some("base64 encoding"),
+        TAG_BASE16 => Some("base16 encoding"),
+        TAG_CBOR => Some("encoded CBOR data item"),
+        TAG_RATIONAL => Some("rational number"),
+        TAG_URI => Some("URI"),
+        TAG_BASE64URL_ENC => Some("base64url"),
+        TAG_BASE64_ENC => Some("base64"),
+        TAG_REGEX => Some("regular expression"),
+        TAG_MIME => Some("MIME message"),
+        TAG_CBOR_SEQUENCE => Some("encoded CBOR sequence"),
+        TAG_SELF_DESCRIBE => Some("self-describe CBOR"),
+        _ => cose::message(tag).map(|message| message.name),
+    }
+}
+
+// Deepest nesting parsed before giving up, to keep recursion off the end of
+// the stack
+pub const MAX_PARSE_DEPTH: usize = 512;
@@ -0,0 +1,296 @@
+// Structure-aware mutation of CBOR for robustness testing: variants of a
+// well-formed seed, each with one change made to one of its items rather
+// than to random bytes. A string, array or map gets a different length, an
+// item a different major type, a map one of its pairs twice over, or a
+// string a shorter content with its length to match, which can cut a UTF-8
+// character in two. All of it is drawn from a seed, as for CborGenerator.
+
+use super::encode::write_head;
+use super::generate::Random;
+use super::*;
+use crate::error::DumpError;
+
+/// The ways an item can be changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mutation {
+    Length,
+    MajorType,
+    DuplicateKey,
+    Truncate,
+}
+
+const MUTATIONS: &[Mutation] = &[
+    Mutation::Length,
+    Mutation::MajorType,
+    Mutation::DuplicateKey,
+    Mutation::Truncate,
+];
+
+/// Where an item of the seed is, and what's needed to change it
+#[derive(Debug, Clone)]
+struct Span {
+    start: usize,
+    content: usize, // Just past the item's head
+    major_type: u8,
+    length: Option<u64>, // Of a definite-length string, array or map
+    entries: Vec<usize>, // Where a map's keys and values start, then where the last ends
+}
+
+impl Span {
+    fn applies(&self, mutation: Mutation) -> bool {
+        match mutation {
+            Mutation::Length => (MAJOR_BYTES..=MAJOR_MAP).contains(&self.major_type),
+            Mutation::MajorType => true,
+            Mutation::DuplicateKey => self.entries.len() > 1,
+            Mutation::Truncate => {
+                matches!(self.major_type, MAJOR_BYTES | MAJOR_TEXT)
+                    && self.length.is_some_and(|length| length > 0)
+            }
+        }
+    }
+}
+
+/// Makes variants of a CBOR seed, each with one structural change
+#[derive(Debug, Clone)]
+pub struct CborMutator {
+    random: Random,
+    data: Vec<u8>,
+    spans: Vec<Span>,
+}
+
+impl CborMutator {
+    /// A mutator of `data`, a well-formed CBOR sequence of at least one
+    /// item, whose variants are all decided by `seed`
+    pub fn new(data: &[u8], seed: u64) -> Result<Self, DumpError> {
+        if parse_slice(data)?.is_empty() {
+            return Err(DumpError::malformed(
+                None,
+                &[],
+                "The seed has no CBOR items",
+            ));
+        }
+        let mut spans = Vec::new();
+        let mut pos = 0;
+        while pos < data.len() {
+            pos = scan(data, pos, &mut spans);
+        }
+        Ok(CborMutator {
+            random: Random::new(seed),
+            data: data.to_vec(),
+            spans,
+        })
+    }
+
+    /// Make the next variant of the seed
+    pub fn mutate(&mut self) -> Vec<u8> {
+        let mutations: Vec<Mutation> = MUTATIONS
+            .iter()
+            .copied()
+            .filter(|&mutation| self.spans.iter().any(|span| span.applies(mutation)))
+            .collect();
+        let mutation = self.random.pick(&mutations);
+        let spans: Vec<&Span> = self
+            .spans
+            .iter()
+            .filter(|span| span.applies(mutation))
+            .collect();
+        let span = spans[self.random.below(spans.len())].clone();
+        match mutation {
+            Mutation::Length => {
+                let length = self.other_length(span.length);
+                self.with_head(&span, length, &[], span.content)
+            }
+            Mutation::MajorType => {
+                let mut out = self.data.clone();
+                let major_type = (span.major_type + 1 + self.random.below(7) as u8) % 8;
+                out[span.start] = major_type << 5 | (out[span.start] & 0x1F);
+                out
+            }
+            Mutation::DuplicateKey => {
+                let pair = self.random.below(span.entries.len() / 2);
+                let (start, end) = (span.entries[2 * pair], span.entries[2 * pair + 2]);
+                let mut out = self.data[..end].to_vec();
+                out.extend_from_slice(&self.data[start..end]);
+                out.extend_from_slice(&self.data[end..]);
+                match span.length {
+                    Some(length) => {
+                        let content = span.content;
+                        let mut fixed = Vec::new();
+                        fixed.extend_from_slice(&out[..span.start]);
+                        write_head(&mut fixed, span.major_type, length + 1);
+                        fixed.extend_from_slice(&out[content..]);
+                        fixed
+                    }
+                    None => out,
+                }
+            }
+            Mutation::Truncate => {
+                let length = span.length.unwrap_or(0) as usize;
+                let kept = self.random.below(length);
+                let content = &self.data[span.content..span.content + kept];
+                self.with_head(&span, kept as u64, content, span.content + length)
+            }
+        }
+    }
+
+    /// The seed with `span`'s head written for `argument`, and the bytes
+    /// from its content up to `rest` replaced with `content`
+    fn with_head(&self, span: &Span, argument: u64, content: &[u8], rest: usize) -> Vec<u8> {
+        let mut out = self.data[..span.start].to_vec();
+        write_head(&mut out, span.major_type, argument);
+        out.extend_from_slice(content);
+        out.extend_from_slice(&self.data[rest..]);
+        out
+    }
+
+    /// A length other than `length`: none, one out either way, far too many,
+    /// or for an indefinite length a small definite one
+    fn other_length(&mut self, length: Option<u64>) -> u64 {
+        let choices = match length {
+            Some(n) => vec![
+                0,
+                n.wrapping_sub(1),
+                n + 1,
+                n * 2 + 1,
+                u32::MAX as u64,
+                u64::MAX,
+            ],
+            None => vec![0, 1, 2, 3],
+        };
+        let choices: Vec<u64> = choices
+            .into_iter()
+            .filter(|&choice| Some(choice) != length)
+            .collect();
+        self.random.pick(&choices)
+    }
+}
+
+/// Note where the item at `pos` and the items inside it are, returning
+/// where it ends. `data` is known to be well-formed
+fn scan(data: &[u8], pos: usize, spans: &mut Vec<Span>) -> usize {
+    let major_type = data[pos] >> 5;
+    let (argument, content) = head(data, pos);
+    let index = spans.len();
+    spans.push(Span {
+        start: pos,
+        content,
+        major_type,
+        length: argument.filter(|_| (MAJOR_BYTES..=MAJOR_MAP).contains(&major_type)),
+        entries: Vec::new(),
+    });
+    let mut next = content;
+    match major_type {
+        MAJOR_BYTES | MAJOR_TEXT => match argument {
+            Some(length) => next += length as usize,
+            None => {
+                // The chunks are strings of their own
+                while data[next] != BREAK {
+                    next = scan(data, next, spans);
+                }
+                next += 1;
+            }
+        },
+        MAJOR_ARRAY | MAJOR_MAP => {
+            let per_entry = if major_type == MAJOR_MAP { 2 } else { 1 };
+            let count = argument.map(|n| n * per_entry);
+            let mut entries = vec![next];
+            loop {
+                let done = match count {
+                    Some(count) => entries.len() as u64 > count,
+                    None => data[next] == BREAK,
+                };
+                if done {
+                    break;
+                }
+                next = scan(data, next, spans);
+                entries.push(next);
+            }
+            if major_type == MAJOR_MAP {
+                spans[index].entries = entries;
+            }
+            if argument.is_none() {
+                next += 1;
+            }
+        }
+        MAJOR_TAG => next = scan(data, next, spans),
+        _ => {}
+    }
+    next
+}
+
+/// The argument of the item at `pos`, None for an indefinite length, and
+/// where its head ends
+fn head(data: &[u8], pos: usize) -> (Option<u64>, usize) {
+    let size = match data[pos] & 0x1F {
+        AI_INDEFINITE => return (None, pos + 1),
+        ai @ 0..=23 => return (Some(ai as u64), pos + 1),
+        ai => 1 << (ai - AI_1BYTE),
+    };
+    let mut buf = [0u8; 8];
+    buf[8 - size..].copy_from_slice(&data[pos + 1..pos + 1 + size]);
+    (Some(u64::from_be_bytes(buf)), pos + 1 + size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // {"a": [1, "xyz"], "b": (_ h'01' h'0203')}
+    const SEED: &[u8] = &[
+        0xA2, 0x61, b'a', 0x82, 0x01, 0x63, b'x', b'y', b'z', 0x61, b'b', 0x5F, 0x41, 0x01, 0x42,
+        0x02, 0x03, 0xFF,
+    ];
+
+    #[test]
+    fn test_scan() {
+        let mut spans = Vec::new();
+        assert_eq!(scan(SEED, 0, &mut spans), SEED.len());
+        let starts: Vec<usize> = spans.iter().map(|span| span.start).collect();
+        assert_eq!(starts, [0, 1, 3, 4, 5, 9, 11, 12, 14]);
+        assert_eq!(spans[0].entries, [1, 3, 9, 11, 18]);
+        assert_eq!((spans[0].length, spans[6].length), (Some(2), None));
+    }
+
+    #[test]
+    fn test_mutations() {
+        let mut mutator = CborMutator::new(SEED, 1).unwrap();
+        let variants: Vec<Vec<u8>> = (0..200).map(|_| mutator.mutate()).collect();
+        assert!(variants.iter().all(|variant| variant != SEED));
+        // Some are well-formed and some aren't
+        let parsed = variants.iter().filter(|v| parse_slice(v).is_ok()).count();
+        assert!(parsed > 0 && parsed < variants.len(), "{}", parsed);
+
+        // Each pair shows up twice over, and "xyz" is cut to "xy"
+        let pair_a = &SEED[1..9];
+        let pair_b = &SEED[9..];
+        for expected in [
+            [&[0xA3], pair_a, pair_a, pair_b].concat(),
+            [&[0xA3], pair_a, pair_b, pair_b].concat(),
+            [&SEED[..5], &[0x62, b'x', b'y'], &SEED[9..]].concat(),
+        ] {
+            assert!(variants.contains(&expected), "{:02X?}", expected);
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_variants() {
+        let variants = |seed| {
+            let mut mutator = CborMutator::new(SEED, seed).unwrap();
+            (0..20).map(|_| mutator.mutate()).collect::<Vec<_>>()
+        };
+        assert_eq!(variants(3), variants(3));
+        assert_ne!(variants(3), variants(4));
+    }
+
+    #[test]
+    fn test_bad_seed() {
+        assert!(CborMutator::new(&[], 1).is_err());
+        assert!(CborMutator::new(&[0x82, 0x01], 1).is_err());
+        // A single integer can only have its major type changed
+        let mut mutator = CborMutator::new(&[0x05], 1).unwrap();
+        for _ in 0..10 {
+            let variant = mutator.mutate();
+            assert_eq!((variant.len(), variant[0] & 0x1F), (1, 5));
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// Describing CBOR items as render nodes. Offsets are worked out from the
+// items' heads, so they're exact for everything but what follows an
+// indefinite-length string: its chunks aren't kept, so from there on
+// offsets are unknown.
+
+use super::c509::{self, Label};
+use super::*;
+use crate::render::{Content, Node};
+
+impl CborItem<'_> {
+    /// Describe the item for a renderer, taking it to start at `offset`
+    pub fn to_node(&self, offset: usize) -> Node {
+        node(self, Some(offset)).0
+    }
+}
+
+/// Describe a CBOR sequence for a renderer, with offsets counted from the
+/// start of the first item
+pub fn to_nodes(items: &[CborItem]) -> Vec<Node> {
+    let mut offset = Some(0);
+    items
+        .iter()
+        .map(|item| {
+            let (node, end) = node(item, offset);
+            offset = end;
+            node
+        })
+        .collect()
+}
+
+/// Node for an item starting at `offset`, and the offset just past it
+fn node(item: &CborItem, offset: Option<usize>) -> (Node, Option<usize>) {
+    let header_length = item.header_length();
+    let indefinite = item.additional_info == AI_INDEFINITE;
+    let start = offset.map(|o| o + header_length);
+
+    // Nested items follow one another from the end of the head
+    let mut next = start;
+    let mut label = None;
+    let mut child = |item: &CborItem| {
+        let (node, end) = node(item, next);
+        next = end;
+        node
+    };
+    let (kind, content) = match &item.value {
+        CborValue::Unsigned(n) => ("unsigned", Content::Integer(n.to_string())),
+        CborValue::Negative(n) => (
+            "negative",
+            Content::Integer((-1 - i128::from(*n)).to_string()),
+        ),
+        CborValue::Bytes(bytes) => {
+            next = start.filter(|_| !indefinite).map(|s| s + bytes.len());
+            ("bytes", Content::Bytes(bytes.to_vec()))
+        }
+        CborValue::Text(text) => {
+            next = start.filter(|_| !indefinite).map(|s| s + text.len());
+            ("text", Content::Text(text.to_string()))
+        }
+        CborValue::Array(items) => {
+            let mut nodes: Vec<Node> = items.iter().map(&mut child).collect();
+            if let Some(labels) = c509::certificate_labels(items) {
+                apply_labels(&mut nodes, labels);
+                label = Some("c509Certificate".to_string());
+            }
+            ("array", Content::List(nodes))
+        }
+        CborValue::Map(pairs) => {
+            let pairs = pairs
+                .iter()
+                .map(|(key, value)| (child(key), child(value)))
+                .collect();
+            ("map", Content::Map(pairs))
+        }
+        CborValue::Tag(tag, item) => ("tag", Content::Tagged(*tag, Box::new(child(item)))),
+        CborValue::Simple(n) => ("simple", Content::Simple(*n)),
+        CborValue::Boolean(b) => ("bool", Content::Bool(*b)),
+        CborValue::Null => ("null", Content::Null),
+        CborValue::Undefined => ("undefined", Content::Undefined),
+        CborValue::Float16(f) => ("float16", Content::Float(*f as f64)),
+        CborValue::Float32(f) => ("float32", Content::Float(*f as f64)),
+        CborValue::Float64(f) => ("float64", Content::Float(*f)),
+        CborValue::Break => ("break", Content::Other("0xFF".to_string())),
+    };
+
+    // The break code ends an indefinite-length array or map
+    let is_container = matches!(item.value, CborValue::Array(_) | CborValue::Map(_));
+    let end = match next {
+        Some(end) if indefinite && is_container => Some(end + 1),
+        end => end,
+    };
+    let length = match (start, next) {
+        (Some(start), Some(next)) if !indefinite => Some(next - start),
+        _ => None,
+    };
+
+    let mut node = Node::new(kind, content);
+    node.offset = offset;
+    node.header_length = header_length;
+    node.length = length;
+    node.label = label;
+    if let CborValue::Tag(tag, _) = &item.value {
+        node.notes.extend(tag_name(*tag).map(str::to_string));
+    }
+    (node, end)
+}
+
+/// Name the elements of a recognized array, and theirs in turn
+fn apply_labels(nodes: &mut [Node], labels: Vec<Label>) {
+    for (node, label) in nodes.iter_mut().zip(labels) {
+        node.label = Some(label.name.to_string());
+        node.notes.extend(label.note);
+        if let Content::List(children) = &mut node.content {
+            apply_labels(children, label.children);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::hex::parse_hex;
+
+    #[test]
+    fn test_offsets() {
+        // [1, h'0102', {_ "a": 1(1000)}], 24, [_ "b"]
+        let data = parse_hex("8301420102bf6161c11903e8ff18189f6162ff").unwrap();
+        let items = parse_slice(&data).unwrap();
+        let nodes = to_nodes(&items);
+        let positions = |node: &Node| (node.offset, node.header_length, node.length);
+
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(positions(&nodes[0]), (Some(0), 1, Some(12)));
+        let children = nodes[0].children();
+        assert_eq!(positions(children[1]), (Some(2), 1, Some(2)));
+        let map = children[2];
+        assert_eq!(positions(map), (Some(5), 1, None));
+        let tag = map.children()[1];
+        assert_eq!(positions(tag), (Some(8), 1, Some(3)));
+        assert_eq!(tag.notes, ["epoch-based date/time"]);
+        assert_eq!(positions(tag.children()[0]), (Some(9), 3, Some(0)));
+        assert_eq!(positions(&nodes[1]), (Some(13), 2, Some(0)));
+        assert_eq!(positions(&nodes[2]), (Some(15), 1, None));
+    }
+
+    #[test]
+    fn test_unknown_offsets() {
+        // (_ h'01', h'02'), 1: nothing after the chunked string has an offset
+        let data = parse_hex("5f41014102ff01").unwrap();
+        let items = parse_slice(&data).unwrap();
+        let nodes = to_nodes(&items);
+        assert_eq!(nodes[0].offset, Some(0));
+        assert_eq!(nodes[0].content, Content::Bytes(vec![1, 2]));
+        assert_eq!(nodes[1].offset, None);
+        assert_eq!(items[1].to_node(6).offset, Some(6));
+    }
+}
@@ -0,0 +1,494 @@
+// Parsing CBOR held in memory. Strings of definite length borrow from the
+// input, so a parse allocates only for arrays, maps and tags, and for the
+// joined chunks of indefinite-length strings.
+
+use std::borrow::Cow;
+use std::io;
+
+use super::*;
+use crate::common::read::missing;
+use crate::error::DumpError;
+
+/// Truncation detail for an indefinite-length item missing its break code
+pub const END_BEFORE_BREAK: &str = "input ends before the break code";
+
+/// Parse a CBOR sequence held in memory into its top-level items. Unlike
+/// dumpcbor, which shows what it can of damaged input, any malformation is
+/// an error.
+pub fn parse_slice(data: &[u8]) -> Result<Vec<CborItem<'_>>, DumpError> {
+    parse_items(data).map_err(DumpError::from)
+}
+
+// The parser works in io::Result, whose error is a single pointer, to keep
+// its recursive frames small
+fn parse_items(data: &[u8]) -> io::Result<Vec<CborItem<'_>>> {
+    let mut parser = SliceParser {
+        data,
+        pos: 0,
+        depth: 0,
+    };
+    let mut items = Vec::new();
+    while parser.pos < data.len() {
+        let start = parser.pos;
+        let item = parser.item()?;
+        if matches!(item.value, CborValue::Break) {
+            return Err(unexpected_break(start));
+        }
+        items.push(item);
+    }
+    Ok(items)
+}
+
+struct SliceParser<'a> {
+    data: &'a [u8],
+    pos: usize,
+    depth: usize, // Nesting of the item being parsed
+}
+
+impl<'a> SliceParser<'a> {
+    /// Parse the item at the current position, which is known to hold at
+    /// least its initial byte. A break code is returned as CborValue::Break
+    /// for the enclosing item to deal with.
+    fn item(&mut self) -> io::Result<CborItem<'a>> {
+        if self.depth >= MAX_PARSE_DEPTH {
+            return Err(too_deep(self.pos));
+        }
+        self.depth += 1;
+        let result = self.value();
+        self.depth -= 1;
+        result
+    }
+
+    fn value(&mut self) -> io::Result<CborItem<'a>> {
+        let start = self.pos;
+        let byte = self.data[start];
+        self.pos += 1;
+        let major_type = byte >> 5;
+        let additional_info = byte & 0x1F;
+        check_indefinite(major_type, additional_info, start)?;
+
+        let value = match major_type {
+            MAJOR_UNSIGNED => CborValue::Unsigned(self.argument(additional_info, start)?),
+            MAJOR_NEGATIVE => CborValue::Negative(self.argument(additional_info, start)?),
+            MAJOR_BYTES if additional_info == AI_INDEFINITE => {
+                let mut bytes = Vec::new();
+                while let Some(chunk) = self.chunk(major_type, start)? {
+                    bytes.extend_from_slice(chunk);
+                }
+                CborValue::Bytes(Cow::Owned(bytes))
+            }
+            MAJOR_BYTES => {
+                let length = self.argument(additional_info, start)?;
+                CborValue::Bytes(Cow::Borrowed(self.take(
+                    length,
+                    start,
+                    "byte string content",
+                )?))
+            }
+            MAJOR_TEXT if additional_info == AI_INDEFINITE => {
+                let mut text = String::new();
+                while let Some(chunk) = self.chunk(major_type, start)? {
+                    text.push_str(utf8(chunk, start)?);
+                }
+                CborValue::Text(Cow::Owned(text))
+            }
+            MAJOR_TEXT => {
+                let length = self.argument(additional_info, start)?;
+                let bytes = self.take(length, start, "text string content")?;
+                CborValue::Text(Cow::Borrowed(utf8(bytes, start)?))
+            }
+            MAJOR_ARRAY => {
+                let length = self.argument(additional_info, start)?;
+                let mut items = Vec::new();
+                // An indefinite-length array ends at a break code instead
+                while (items.len() as u64) < length {
+                    let item = match self.child(start, "array", length, items.len() as u64)? {
+                        Some(item) => item,
+                        None => break,
+                    };
+                    items.push(item);
+                }
+                CborValue::Array(items)
+            }
+            MAJOR_MAP => {
+                let length = self.argument(additional_info, start)?;
+                let mut pairs = Vec::new();
+                while (pairs.len() as u64) < length {
+                    let key = match self.child(start, "map", length, pairs.len() as u64)? {
+                        Some(key) => key,
+                        None => break,
+                    };
+                    if self.pos == self.data.len() {
+                        let detail = "input ends before the value of the last key";
+                        return Err(truncated(start, "map", detail));
+                    }
+                    let value = self.item()?;
+                    if matches!(value.value, CborValue::Break) {
+                        return Err(unexpected_break(self.pos - 1));
+                    }
+                    pairs.push((key, value));
+                }
+                CborValue::Map(pairs)
+            }
+            MAJOR_TAG => {
+                let tag = self.argument(additional_info, start)?;
+                if self.pos == self.data.len() {
+                    let detail = "input ends before the tagged item";
+                    return Err(truncated(start, "tag", detail));
+                }
+                let item = self.item()?;
+                if matches!(item.value, CborValue::Break) {
+                    return Err(unexpected_break(self.pos - 1));
+                }
+                CborValue::Tag(tag, Box::new(item))
+            }
+            _ => match additional_info {
+                SIMPLE_FALSE => CborValue::Boolean(false),
+                SIMPLE_TRUE => CborValue::Boolean(true),
+                SIMPLE_NULL => CborValue::Null,
+                SIMPLE_UNDEFINED => CborValue::Undefined,
+                AI_2BYTES => {
+                    let bytes = self.take(2, start, "float")?;
+                    CborValue::Float16(f16_to_f32(u16::from_be_bytes([bytes[0], bytes[1]])))
+                }
+                AI_4BYTES => {
+                    let bits = self.argument(additional_info, start)?;
+                    CborValue::Float32(f32::from_bits(bits as u32))
+                }
+                AI_8BYTES => {
+                    let bits = self.argument(additional_info, start)?;
+                    CborValue::Float64(f64::from_bits(bits))
+                }
+                AI_INDEFINITE => CborValue::Break,
+                0..=19 => CborValue::Simple(additional_info),
+                _ => CborValue::Simple(self.two_byte_simple(additional_info, start)?),
+            },
+        };
+
+        let mut item = CborItem::new(major_type, additional_info, value);
+        self.keep_head(&mut item, start);
+        Ok(item)
+    }
+
+    /// Keep the head of the item at `start` as it was encoded. Out of line,
+    /// to keep value()'s frame small for deep nesting
+    #[inline(never)]
+    fn keep_head(&self, item: &mut CborItem, start: usize) {
+        item.raw_bytes = self.data[start..start + item.header_length()].to_vec();
+    }
+
+    /// Read a simple value in the two-byte form, which RFC 8949 only allows
+    /// for values from 32 up; the others have a one-byte form. The reserved
+    /// additional info values end up here too, to be rejected. Out of line,
+    /// like keep_head()
+    #[inline(never)]
+    fn two_byte_simple(&mut self, ai: u8, start: usize) -> io::Result<u8> {
+        let value = self.argument(ai, start)?;
+        if value < 32 {
+            return Err(malformed(
+                start,
+                format!(
+                    "Simple value {} at offset {} is in the two-byte form, \
+                     which is only for values from 32 up",
+                    value, start
+                ),
+            ));
+        }
+        Ok(value as u8)
+    }
+
+    /// Parse the next element of the array or map at `start`, of which
+    /// `present` have been parsed. None means an indefinite length's break
+    /// code was reached.
+    fn child(
+        &mut self,
+        start: usize,
+        part: &str,
+        length: u64,
+        present: u64,
+    ) -> io::Result<Option<CborItem<'a>>> {
+        if self.pos == self.data.len() {
+            let detail = if length == u64::MAX {
+                END_BEFORE_BREAK.to_string()
+            } else {
+                let unit = if part == "map" { "pair" } else { "item" };
+                missing(length, present, unit)
+            };
+            return Err(truncated(start, part, &detail));
+        }
+        let item = self.item()?;
+        match item.value {
+            CborValue::Break if length == u64::MAX => Ok(None),
+            CborValue::Break => Err(unexpected_break(self.pos - 1)),
+            _ => Ok(Some(item)),
+        }
+    }
+
+    /// Read the next chunk of the indefinite-length string at `start`, or
+    /// None at the break code
+    fn chunk(&mut self, major_type: u8, start: usize) -> io::Result<Option<&'a [u8]>> {
+        let part = if major_type == MAJOR_BYTES {
+            "byte string"
+        } else {
+            "text string"
+        };
+        let byte = match self.data.get(self.pos) {
+            Some(&byte) => byte,
+            None => return Err(truncated(start, part, END_BEFORE_BREAK)),
+        };
+        if byte == BREAK {
+            self.pos += 1;
+            return Ok(None);
+        }
+        if byte >> 5 != major_type || byte & 0x1F == AI_INDEFINITE {
+            return Err(malformed(
+                self.pos,
+                format!(
+                    "Chunk at offset {} in indefinite {} at offset {} isn't a definite-length {}",
+                    self.pos, part, start, part
+                ),
+            ));
+        }
+        let chunk_start = self.pos;
+        self.pos += 1;
+        let length = self.argument(byte & 0x1F, chunk_start)?;
+        self.take(length, chunk_start, &format!("{} content", part))
+            .map(Some)
+    }
+
+    /// Read the argument (length or value) that follows an initial byte
+    fn argument(&mut self, ai: u8, start: usize) -> io::Result<u64> {
+        let size = match ai {
+            0..=23 => return Ok(ai as u64),
+            AI_1BYTE => 1,
+            AI_2BYTES => 2,
+            AI_4BYTES => 4,
+            AI_8BYTES => 8,
+            AI_INDEFINITE => return Ok(u64::MAX), // Marker for indefinite length
+            _ => {
+                return Err(malformed(
+                    start,
+                    format!("Invalid additional info at offset {}", start),
+                ))
+            }
+        };
+        let mut buf = [0u8; 8];
+        buf[8 - size..].copy_from_slice(self.take(size as u64, start, "argument")?);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    /// Take the next `length` bytes of the item at `start`. The length comes
+    /// from the input, so it's checked against what's there before use.
+    fn take(&mut self, length: u64, start: usize, part: &str) -> io::Result<&'a [u8]> {
+        let available = self.data.len() - self.pos;
+        if length > available as u64 {
+            let detail = missing(length, available as u64, "byte");
+            return Err(truncated(start, part, &detail));
+        }
+        let bytes = &self.data[self.pos..self.pos + length as usize];
+        self.pos += length as usize;
+        Ok(bytes)
+    }
+}
+
+/// Check that the text string at `start` is valid UTF-8
+fn utf8(bytes: &[u8], start: usize) -> io::Result<&str> {
+    std::str::from_utf8(bytes).map_err(|e| {
+        malformed(
+            start,
+            format!("Text string at offset {} isn't valid UTF-8: {}", start, e),
+        )
+    })
+}
+
+fn unexpected_break(offset: usize) -> io::Error {
+    malformed(
+        offset,
+        format!(
+            "Break code at offset {} outside an indefinite-length item",
+            offset
+        ),
+    )
+}
+
+// Errors built outside the recursive functions, to keep their frames small
+
+/// Reject an indefinite length on an integer or tag, which always have an
+/// argument. Kept out of the recursive frames
+#[inline(never)]
+fn check_indefinite(major_type: u8, additional_info: u8, start: usize) -> io::Result<()> {
+    if additional_info == AI_INDEFINITE
+        && matches!(major_type, MAJOR_UNSIGNED | MAJOR_NEGATIVE | MAJOR_TAG)
+    {
+        return Err(malformed(
+            start,
+            format!(
+                "Indefinite length at offset {} isn't allowed for major type {}",
+                start, major_type
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn malformed(offset: usize, message: String) -> io::Error {
+    DumpError::malformed(Some(offset), &[], message).into()
+}
+
+fn truncated(offset: usize, part: &str, detail: &str) -> io::Error {
+    DumpError::truncated(offset, part, detail, &[]).into()
+}
+
+fn too_deep(offset: usize) -> io::Error {
+    DumpError::too_deep(Some(offset), &[], MAX_PARSE_DEPTH).into()
+}
+
+/// Convert IEEE 754 half-precision float to single-precision
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits >> 15) & 1) as u32;
+    let exp = ((bits >> 10) & 0x1F) as u32;
+    let mant = (bits & 0x3FF) as u32;
+
+    if exp == 0 {
+        if mant == 0 {
+            // Zero
+            f32::from_bits(sign << 31)
+        } else {
+            // Subnormal, in units of 2^-24, which is normal in single
+            // precision
+            let magnitude = mant as f32 / (1 << 24) as f32;
+            if sign == 1 {
+                -magnitude
+            } else {
+                magnitude
+            }
+        }
+    } else if exp == 0x1F {
+        if mant == 0 {
+            // Infinity
+            f32::from_bits((sign << 31) | (0xFF << 23))
+        } else {
+            // NaN
+            f32::from_bits((sign << 31) | (0xFF << 23) | (mant << 13))
+        }
+    } else {
+        // Normalized
+        let new_exp = exp + 127 - 15;
+        let new_mant = mant << 13;
+        f32::from_bits((sign << 31) | (new_exp << 23) | new_mant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_slice_borrows_strings() {
+        // {"a": h'0102'}, then (_ "b", "c")
+        let data = [
+            0xA1, 0x61, b'a', 0x42, 0x01, 0x02, 0x7F, 0x61, b'b', 0x61, b'c', 0xFF,
+        ];
+        let items = parse_slice(&data).unwrap();
+        assert_eq!(items.len(), 2);
+        match &items[0].value {
+            CborValue::Map(pairs) => {
+                assert!(matches!(
+                    &pairs[0].0.value,
+                    CborValue::Text(Cow::Borrowed("a"))
+                ));
+                match &pairs[0].1.value {
+                    CborValue::Bytes(Cow::Borrowed(bytes)) => {
+                        assert_eq!(bytes.as_ptr(), data[4..].as_ptr())
+                    }
+                    other => panic!("expected borrowed bytes, got {:?}", other),
+                }
+            }
+            other => panic!("expected a map, got {:?}", other),
+        }
+        // Chunks have to be joined
+        assert!(matches!(&items[1].value, CborValue::Text(Cow::Owned(s)) if s == "bc"));
+    }
+
+    #[test]
+    fn test_parse_slice_values() {
+        // [_ 0, -1, 1000, 1.5, false, null, simple(16), 0("x")]
+        let data = [
+            0x9F, 0x00, 0x20, 0x19, 0x03, 0xE8, 0xF9, 0x3E, 0x00, 0xF4, 0xF6, 0xF0, 0xC0, 0x61,
+            b'x', 0xFF,
+        ];
+        let items = parse_slice(&data).unwrap();
+        let CborValue::Array(elements) = &items[0].value else {
+            panic!("expected an array");
+        };
+        let values: Vec<String> = elements.iter().map(|e| format!("{:?}", e.value)).collect();
+        assert_eq!(
+            values,
+            [
+                "Unsigned(0)",
+                "Negative(0)",
+                "Unsigned(1000)",
+                "Float16(1.5)",
+                "Boolean(false)",
+                "Null",
+                "Simple(16)",
+                "Tag(0, CborItem { major_type: 3, additional_info: 1, value: Text(\"x\"), \
+                 raw_bytes: [97] })",
+            ]
+        );
+        // Heads are kept as they were encoded
+        assert_eq!(items[0].raw_bytes, [0x9F]);
+        assert_eq!(elements[2].raw_bytes, [0x19, 0x03, 0xE8]);
+        assert_eq!(elements[3].raw_bytes, [0xF9, 0x3E, 0x00]);
+    }
+
+    #[test]
+    fn test_parse_slice_errors() {
+        let truncated = |data: &[u8]| parse_slice(data).unwrap_err().is_truncated();
+        // Lengths are checked against the input, not allocated
+        assert!(truncated(&[
+            0x5B, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF
+        ]));
+        assert!(truncated(&[0x83, 0x01]));
+        assert!(truncated(&[0x9F, 0x01]));
+        assert!(truncated(&[0xA1, 0x01]));
+        for data in [
+            &[0x62, 0xC3, 0x28][..],
+            &[0x5F, 0x61, b'a', 0xFF],
+            &[0x82, 0x01, 0xFF],
+            &[0xFF],
+            &[0x1C],
+            // Simple values below 32 have a one-byte form only
+            &[0xF8, 0x18],
+            &[0xF8, 0x01],
+            &[0xFC],
+            // Integers and tags can't have an indefinite length
+            &[0x1F],
+            &[0x3F],
+            &[0xDF, 0x01],
+        ] {
+            let e = parse_slice(data).unwrap_err();
+            assert!(matches!(e, DumpError::Malformed { .. }), "{:?}", e);
+        }
+        assert_eq!(
+            parse_slice(&[0x82, 0x01, 0xFF]).unwrap_err().offset(),
+            Some(2)
+        );
+
+        let e = parse_slice(&[0x00, 0x83, 0x01]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Truncated array at offset 1: 2 more items expected (1 of 3 present)"
+        );
+        assert_eq!(e.offset(), Some(1));
+
+        let deep = vec![0x81; MAX_PARSE_DEPTH + 1];
+        assert!(matches!(
+            parse_slice(&deep),
+            Err(DumpError::TooDeep {
+                limit: MAX_PARSE_DEPTH,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+// The shape of CBOR items, inferred from samples of them for codegen. Each
+// sample gives a schema, and the schemas of several are merged: unsigned
+// integers widen to signed ones, null makes a value optional, a map key
+// missing from some samples makes that field optional, and values that have
+// nothing in common become "any". Integers and floats are kept apart, as
+// ciborium won't read one as the other.
+
+use super::*;
+
+/// What the samples of an item had in common
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schema {
+    Unknown, // No samples, as for the elements of empty arrays
+    Bool,
+    Unsigned,
+    Integer,
+    Float,
+    Text,
+    Bytes,
+    Array(Box<Schema>),  // Elements of one shape, any number of them
+    Tuple(Vec<Schema>),  // Elements of different shapes, position by position
+    Struct(Vec<Field>),  // A map with text keys
+    IntMap(Box<Schema>), // A map with integer keys
+    Tagged(u64, Box<Schema>),
+    Nullable(Box<Schema>), // Null in some samples
+    Any,
+}
+
+/// A text key of a map and what its values had in common
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub key: String,
+    pub schema: Schema,
+    pub optional: bool, // Missing from some samples
+}
+
+impl Schema {
+    /// The schema of a single item
+    pub fn infer(item: &CborItem) -> Schema {
+        match &item.value {
+            CborValue::Boolean(_) => Schema::Bool,
+            CborValue::Unsigned(_) => Schema::Unsigned,
+            CborValue::Negative(_) => Schema::Integer,
+            CborValue::Float16(_) | CborValue::Float32(_) | CborValue::Float64(_) => Schema::Float,
+            CborValue::Text(_) => Schema::Text,
+            CborValue::Bytes(_) => Schema::Bytes,
+            CborValue::Null => Schema::Nullable(Box::new(Schema::Unknown)),
+            CborValue::Array(elements) => {
+                let schemas: Vec<Schema> = elements.iter().map(Schema::infer).collect();
+                let merged = schemas.iter().cloned().fold(Schema::Unknown, Schema::merge);
+                if merged == Schema::Any && schemas.len() > 1 {
+                    Schema::Tuple(schemas)
+                } else {
+                    Schema::Array(Box::new(merged))
+                }
+            }
+            CborValue::Map(pairs) => {
+                if pairs
+                    .iter()
+                    .all(|(key, _)| matches!(key.value, CborValue::Text(_)))
+                {
+                    let mut fields: Vec<Field> = Vec::new();
+                    for (key, value) in pairs {
+                        if let CborValue::Text(key) = &key.value {
+                            // A repeated key is malformed, but merges like a sample
+                            let schema = Schema::infer(value);
+                            match fields.iter_mut().find(|field| field.key == *key) {
+                                Some(field) => field.schema = field.schema.clone().merge(schema),
+                                None => fields.push(Field {
+                                    key: key.to_string(),
+                                    schema,
+                                    optional: false,
+                                }),
+                            }
+                        }
+                    }
+                    Schema::Struct(fields)
+                } else if pairs.iter().all(|(key, _)| {
+                    matches!(key.value, CborValue::Unsigned(_) | CborValue::Negative(_))
+                }) {
+                    let values = pairs.iter().map(|(_, value)| Schema::infer(value));
+                    Schema::IntMap(Box::new(values.fold(Schema::Unknown, Schema::merge)))
+                } else {
+                    Schema::Any
+                }
+            }
+            CborValue::Tag(tag, content) => Schema::Tagged(*tag, Box::new(Schema::infer(content))),
+            CborValue::Simple(_) | CborValue::Undefined | CborValue::Break => Schema::Any,
+        }
+    }
+
+    /// The schema of several samples, given the schemas of each
+    pub fn infer_all<'a>(items: impl IntoIterator<Item = &'a CborItem<'a>>) -> Schema {
+        items
+            .into_iter()
+            .map(Schema::infer)
+            .fold(Schema::Unknown, Schema::merge)
+    }
+
+    /// What this schema and `other` have in common
+    pub fn merge(self, other: Schema) -> Schema {
+        use Schema::*;
+        match (self, other) {
+            (Unknown, schema) | (schema, Unknown) => schema,
+            (Nullable(a), Nullable(b)) => Nullable(Box::new(a.merge(*b))),
+            (Nullable(a), b) | (b, Nullable(a)) => Nullable(Box::new(a.merge(b))),
+            (Any, _) | (_, Any) => Any,
+            (a, b) if a == b && !matches!(a, Struct(_) | Array(_) | Tuple(_)) => a,
+            (Unsigned | Integer, Unsigned | Integer) => Integer,
+            (Array(a), Array(b)) => Array(Box::new(a.merge(*b))),
+            (Tuple(a), Tuple(b)) if a.len() == b.len() => {
+                Tuple(a.into_iter().zip(b).map(|(a, b)| a.merge(b)).collect())
+            }
+            (Tuple(elements), Array(element)) | (Array(element), Tuple(elements)) => {
+                Array(Box::new(elements.into_iter().fold(*element, Schema::merge)))
+            }
+            (Tuple(a), Tuple(b)) => Array(Box::new(
+                a.into_iter().chain(b).fold(Unknown, Schema::merge),
+            )),
+            (Struct(a), Struct(b)) => Struct(merge_fields(a, b)),
+            (IntMap(a), IntMap(b)) => IntMap(Box::new(a.merge(*b))),
+            (Tagged(a, x), Tagged(b, y)) if a == b => Tagged(a, Box::new(x.merge(*y))),
+            _ => Any,
+        }
+    }
+}
+
+/// The fields of two samples of a map, in the order they were first seen,
+/// those missing from either being optional
+fn merge_fields(a: Vec<Field>, mut b: Vec<Field>) -> Vec<Field> {
+    let mut fields = Vec::new();
+    for mut field in a {
+        match b.iter().position(|other| other.key == field.key) {
+            Some(index) => {
+                let other = b.remove(index);
+                field.schema = field.schema.merge(other.schema);
+                field.optional |= other.optional;
+            }
+            None => field.optional = true,
+        }
+        fields.push(field);
+    }
+    fields.extend(b.into_iter().map(|field| Field {
+        optional: true,
+        ..field
+    }));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(data: &[u8]) -> Schema {
+        Schema::infer_all(&parse_slice(data).unwrap())
+    }
+
+    #[test]
+    fn test_infer() {
+        // {"a": 1, "b": [h'00', h'01']}
+        let data = [
+            0xA2, 0x61, 0x61, 0x01, 0x61, 0x62, 0x82, 0x41, 0x00, 0x41, 0x01,
+        ];
+        let field = |key: &str, schema| Field {
+            key: key.to_string(),
+            schema,
+            optional: false,
+        };
+        assert_eq!(
+            schema(&data),
+            Schema::Struct(vec![
+                field("a", Schema::Unsigned),
+                field("b", Schema::Array(Box::new(Schema::Bytes))),
+            ])
+        );
+        // [1, "x"], then {1: -1}
+        assert_eq!(
+            schema(&[0x82, 0x01, 0x61, 0x78]),
+            Schema::Tuple(vec![Schema::Unsigned, Schema::Text])
+        );
+        assert_eq!(
+            schema(&[0xA1, 0x01, 0x20]),
+            Schema::IntMap(Box::new(Schema::Integer))
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        // {"a": 1, "b": true}, {"a": -1.5, "c": null}, {"a": 2, "b": false, "c": "x"}
+        let data = [
+            0xA2, 0x61, 0x61, 0x01, 0x61, 0x62, 0xF5, 0xA2, 0x61, 0x61, 0xF9, 0xBE, 0x00, 0x61,
+            0x63, 0xF6, 0xA3, 0x61, 0x61, 0x02, 0x61, 0x62, 0xF4, 0x61, 0x63, 0x61, 0x78,
+        ];
+        let Schema::Struct(fields) = schema(&data) else {
+            panic!("not a struct");
+        };
+        let summary: Vec<(&str, &Schema, bool)> = fields
+            .iter()
+            .map(|field| (field.key.as_str(), &field.schema, field.optional))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("a", &Schema::Any, false),
+                ("b", &Schema::Bool, true),
+                ("c", &Schema::Nullable(Box::new(Schema::Text)), true),
+            ]
+        );
+
+        assert_eq!(Schema::Text.merge(Schema::Bytes), Schema::Any);
+        assert_eq!(
+            Schema::Tuple(vec![Schema::Text, Schema::Bool])
+                .merge(Schema::Tuple(vec![Schema::Text])),
+            Schema::Array(Box::new(Schema::Any))
+        );
+        assert_eq!(
+            Schema::Array(Box::new(Schema::Unsigned))
+                .merge(Schema::Array(Box::new(Schema::Unknown))),
+            Schema::Array(Box::new(Schema::Unsigned))
+        );
+    }
+}
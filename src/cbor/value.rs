@@ -0,0 +1,305 @@
+// Decoded CBOR data items
+
+use std::borrow::Cow;
+
+use super::*;
+
+/// A CBOR data item, with the initial byte fields it was encoded with
+#[derive(Debug, Clone)]
+pub struct CborItem<'a> {
+    pub major_type: u8,
+    pub additional_info: u8,
+    pub value: CborValue<'a>,
+    pub raw_bytes: Vec<u8>, // The head as encoded, when parsed
+}
+
+/// CBOR value types. Strings borrow from the input when it's parsed from a
+/// slice, and are owned when it's read from a stream.
+#[derive(Debug, Clone)]
+pub enum CborValue<'a> {
+    Unsigned(u64),
+    Negative(u64), // The argument: the value is -1 - it, down to -2^64
+    Bytes(Cow<'a, [u8]>),
+    Text(Cow<'a, str>),
+    Array(Vec<CborItem<'a>>),
+    Map(Vec<(CborItem<'a>, CborItem<'a>)>),
+    Tag(u64, Box<CborItem<'a>>),
+    Simple(u8),
+    Boolean(bool),
+    Null,
+    Undefined,
+    Float16(f32),
+    Float32(f32),
+    Float64(f64),
+    Break,
+}
+
+impl<'a> CborItem<'a> {
+    pub fn new(major_type: u8, additional_info: u8, value: CborValue<'a>) -> Self {
+        CborItem {
+            major_type,
+            additional_info,
+            value,
+            raw_bytes: Vec::new(),
+        }
+    }
+
+    /// Wrap a value in an item with the initial byte of its preferred
+    /// serialization: the shortest argument, and definite lengths
+    pub fn from_value(value: CborValue<'a>) -> Self {
+        let (major_type, argument) = match &value {
+            CborValue::Unsigned(n) => (MAJOR_UNSIGNED, *n),
+            CborValue::Negative(n) => (MAJOR_NEGATIVE, *n),
+            CborValue::Bytes(bytes) => (MAJOR_BYTES, bytes.len() as u64),
+            CborValue::Text(text) => (MAJOR_TEXT, text.len() as u64),
+            CborValue::Array(items) => (MAJOR_ARRAY, items.len() as u64),
+            CborValue::Map(pairs) => (MAJOR_MAP, pairs.len() as u64),
+            CborValue::Tag(tag, _) => (MAJOR_TAG, *tag),
+            CborValue::Simple(n) => (MAJOR_SIMPLE, *n as u64),
+            CborValue::Boolean(false) => (MAJOR_SIMPLE, SIMPLE_FALSE as u64),
+            CborValue::Boolean(true) => (MAJOR_SIMPLE, SIMPLE_TRUE as u64),
+            CborValue::Null => (MAJOR_SIMPLE, SIMPLE_NULL as u64),
+            CborValue::Undefined => (MAJOR_SIMPLE, SIMPLE_UNDEFINED as u64),
+            CborValue::Float16(_) => return CborItem::new(MAJOR_SIMPLE, AI_2BYTES, value),
+            CborValue::Float32(_) => return CborItem::new(MAJOR_SIMPLE, AI_4BYTES, value),
+            CborValue::Float64(_) => return CborItem::new(MAJOR_SIMPLE, AI_8BYTES, value),
+            CborValue::Break => return CborItem::new(MAJOR_SIMPLE, AI_INDEFINITE, value),
+        };
+        CborItem::new(major_type, argument_info(argument), value)
+    }
+
+    /// The item the self-described CBOR tag (55799) wraps, if this is one
+    pub fn self_described(&self) -> Option<&CborItem<'a>> {
+        match &self.value {
+            CborValue::Tag(TAG_SELF_DESCRIBE, item) => Some(item),
+            _ => None,
+        }
+    }
+
+    /// Length of the item's head: the initial byte and the argument after it
+    pub fn header_length(&self) -> usize {
+        1 + match self.additional_info {
+            AI_1BYTE => 1,
+            AI_2BYTES => 2,
+            AI_4BYTES => 4,
+            AI_8BYTES => 8,
+            _ => 0,
+        }
+    }
+
+    /// Keep the head the item was parsed from: its initial byte and the
+    /// argument, in as many bytes as the additional info gave it
+    pub fn with_head(mut self, argument: u64) -> Self {
+        let width = self.header_length() - 1;
+        self.raw_bytes = vec![(self.major_type << 5) | self.additional_info];
+        self.raw_bytes
+            .extend_from_slice(&argument.to_be_bytes()[8 - width..]);
+        self
+    }
+
+    /// Length of what follows the head: a string's bytes, the nested items
+    /// and any break code. It's unknown for an indefinite-length string,
+    /// and what encloses one, as its chunks aren't kept
+    pub fn content_length(&self) -> Option<usize> {
+        let indefinite = self.additional_info == AI_INDEFINITE;
+        let encoded = |item: &CborItem| Some(item.header_length() + item.content_length()?);
+        match &self.value {
+            CborValue::Bytes(_) | CborValue::Text(_) if indefinite => None,
+            CborValue::Bytes(bytes) => Some(bytes.len()),
+            CborValue::Text(text) => Some(text.len()),
+            CborValue::Array(items) => {
+                let nested: Option<usize> = items.iter().map(encoded).sum();
+                Some(nested? + indefinite as usize)
+            }
+            CborValue::Map(pairs) => {
+                let nested: Option<usize> = pairs
+                    .iter()
+                    .map(|(key, value)| Some(encoded(key)? + encoded(value)?))
+                    .sum();
+                Some(nested? + indefinite as usize)
+            }
+            CborValue::Tag(_, item) => encoded(item),
+            _ => Some(0),
+        }
+    }
+
+    /// Copy any strings borrowed from the input, so the item can outlive it
+    pub fn into_owned(self) -> CborItem<'static> {
+        CborItem {
+            major_type: self.major_type,
+            additional_info: self.additional_info,
+            value: self.value.into_owned(),
+            raw_bytes: self.raw_bytes,
+        }
+    }
+}
+
+impl CborValue<'_> {
+    /// The value of an unsigned or negative integer
+    pub fn integer(&self) -> Option<i128> {
+        match *self {
+            CborValue::Unsigned(n) => Some(n.into()),
+            CborValue::Negative(n) => Some(-1 - i128::from(n)),
+            _ => None,
+        }
+    }
+
+    /// Copy any strings borrowed from the input, so the value can outlive it
+    pub fn into_owned(self) -> CborValue<'static> {
+        match self {
+            CborValue::Unsigned(n) => CborValue::Unsigned(n),
+            CborValue::Negative(n) => CborValue::Negative(n),
+            CborValue::Bytes(bytes) => CborValue::Bytes(Cow::Owned(bytes.into_owned())),
+            CborValue::Text(text) => CborValue::Text(Cow::Owned(text.into_owned())),
+            CborValue::Array(items) => {
+                CborValue::Array(items.into_iter().map(CborItem::into_owned).collect())
+            }
+            CborValue::Map(pairs) => CborValue::Map(
+                pairs
+                    .into_iter()
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect(),
+            ),
+            CborValue::Tag(tag, item) => CborValue::Tag(tag, Box::new(item.into_owned())),
+            CborValue::Simple(n) => CborValue::Simple(n),
+            CborValue::Boolean(b) => CborValue::Boolean(b),
+            CborValue::Null => CborValue::Null,
+            CborValue::Undefined => CborValue::Undefined,
+            CborValue::Float16(f) => CborValue::Float16(f),
+            CborValue::Float32(f) => CborValue::Float32(f),
+            CborValue::Float64(f) => CborValue::Float64(f),
+            CborValue::Break => CborValue::Break,
+        }
+    }
+}
+
+/// Additional info for the shortest encoding of an argument
+pub(super) fn argument_info(argument: u64) -> u8 {
+    match argument {
+        0..=23 => argument as u8,
+        24..=0xFF => AI_1BYTE,
+        0x100..=0xFFFF => AI_2BYTES,
+        0x1_0000..=0xFFFF_FFFF => AI_4BYTES,
+        _ => AI_8BYTES,
+    }
+}
+
+/// Decimal value of a bignum's big-endian magnitude, or of -1 minus it for
+/// a negative bignum
+pub(crate) fn bignum(magnitude: &[u8], negative: bool) -> String {
+    const LIMB: u64 = 1_000_000_000;
+    // Nine decimal digits per limb, least significant first
+    let mut limbs: Vec<u64> = vec![0];
+    let add = |limbs: &mut Vec<u64>, multiplier: u64, mut carry: u64| {
+        for limb in limbs.iter_mut() {
+            let value = *limb * multiplier + carry;
+            *limb = value % LIMB;
+            carry = value / LIMB;
+        }
+        if carry > 0 {
+            limbs.push(carry);
+        }
+    };
+    for &byte in magnitude {
+        add(&mut limbs, 256, byte as u64);
+    }
+    if negative {
+        add(&mut limbs, 1, 1);
+    }
+
+    let mut text = String::new();
+    if negative {
+        text.push('-');
+    }
+    let mut limbs = limbs.iter().rev();
+    if let Some(first) = limbs.next() {
+        text.push_str(&first.to_string());
+    }
+    for limb in limbs {
+        text.push_str(&format!("{:09}", limb));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_owned() {
+        let data = b"abc".to_vec();
+        let item = CborItem::new(
+            4,
+            1,
+            CborValue::Array(vec![CborItem::new(
+                3,
+                3,
+                CborValue::Text(Cow::Borrowed(std::str::from_utf8(&data).unwrap())),
+            )]),
+        );
+        let owned = item.into_owned();
+        drop(data);
+        match owned.value {
+            CborValue::Array(items) => {
+                assert!(matches!(&items[0].value, CborValue::Text(Cow::Owned(s)) if s == "abc"))
+            }
+            _ => panic!("expected an array"),
+        }
+    }
+
+    #[test]
+    fn test_from_value() {
+        let info = |value: CborValue| {
+            let item = CborItem::from_value(value);
+            (item.major_type, item.additional_info)
+        };
+        assert_eq!(info(CborValue::Unsigned(23)), (MAJOR_UNSIGNED, 23));
+        assert_eq!(info(CborValue::Unsigned(24)), (MAJOR_UNSIGNED, AI_1BYTE));
+        assert_eq!(info(CborValue::Negative(24)), (MAJOR_NEGATIVE, AI_1BYTE));
+        assert_eq!(info(CborValue::Negative(0)), (MAJOR_NEGATIVE, 0));
+        assert_eq!(
+            info(CborValue::Text("a".repeat(256).into())),
+            (MAJOR_TEXT, AI_2BYTES)
+        );
+        assert_eq!(
+            info(CborValue::Tag(
+                1 << 32,
+                Box::new(CborItem::from_value(CborValue::Null))
+            )),
+            (MAJOR_TAG, AI_8BYTES)
+        );
+        assert_eq!(info(CborValue::Simple(32)), (MAJOR_SIMPLE, AI_1BYTE));
+        assert_eq!(info(CborValue::Boolean(true)), (MAJOR_SIMPLE, SIMPLE_TRUE));
+        assert_eq!(info(CborValue::Float64(0.5)), (MAJOR_SIMPLE, AI_8BYTES));
+    }
+
+    #[test]
+    fn test_lengths() {
+        // {"a": [_ 1, 1000]}, 1(1.5 as a float32), (_ h'01')
+        let data = [
+            0xA1, 0x61, b'a', 0x9F, 0x01, 0x19, 0x03, 0xE8, 0xFF, 0xC1, 0xFA, 0x3F, 0xC0, 0x00,
+            0x00, 0x5F, 0x41, 0x01, 0xFF,
+        ];
+        let items = parse_slice(&data).unwrap();
+        let lengths: Vec<_> = items
+            .iter()
+            .map(|item| (item.header_length(), item.content_length()))
+            .collect();
+        assert_eq!(lengths, [(1, Some(8)), (1, Some(5)), (1, None)]);
+        let CborValue::Map(pairs) = &items[0].value else {
+            panic!("expected a map");
+        };
+        assert_eq!(pairs[0].1.content_length(), Some(5));
+    }
+
+    #[test]
+    fn test_bignum() {
+        assert_eq!(bignum(&[], false), "0");
+        assert_eq!(bignum(&[], true), "-1");
+        // 2^64 and -1 - 2^64, the examples of RFC 8949 Appendix A
+        let two_to_64 = [1, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(bignum(&two_to_64, false), "18446744073709551616");
+        assert_eq!(bignum(&two_to_64, true), "-18446744073709551617");
+        assert_eq!(bignum(&[0x3B, 0x9A, 0xCA, 0x00], false), "1000000000");
+    }
+}
@@ -0,0 +1,215 @@
+// asn1cbor dump on several inputs, such as a directory of certificates or a
+// pattern matching them: each file is dumped under a header naming it, as
+// dump would dump it alone, and a table at the end gives what each held and
+// the errors and warnings found in it. Files are dumped --jobs at a time and
+// printed in the order they were named.
+
+use std::io::{self, BufWriter, Write};
+
+use super::detect::{self, Checked, Decoder};
+use super::inputs::{expand_inputs, file_args, is_single_file};
+use super::usage_error;
+use crate::common::dumper::Dumper;
+use crate::common::jobs;
+use crate::diagnostics::{self, Severity, EXIT_CLEAN, EXIT_ERRORS, EXIT_WARNINGS};
+use crate::output::Captured;
+
+// Options for a single output, error or expected file, which several dumps
+// can't share, and --follow, which never finishes one file to start the next
+const SINGLE_FILE_OPTIONS: &[&str] = &["-O", "--errors-json", "--expect", "--follow"];
+
+/// What dumping one file came to
+struct Dumped {
+    filename: String,
+    text: String,                     // The dump, ending with any error that stopped it
+    checked: Result<Checked, String>, // Or why the file couldn't be dumped at all
+}
+
+/// For dump-asn1 or dump-cbor with `args`, dump the files the directory or
+/// pattern they name stands for, each with `decoder` or under --auto with
+/// the one it calls for. None if `args` name a single file, or don't parse,
+/// for the dumper to take as usual
+pub(crate) fn dump_expanded<D: Dumper>(
+    args: &[String],
+    decoder: Decoder,
+) -> Option<io::Result<i32>> {
+    let positions = D::input_positions(args).ok()?;
+    let [position] = positions[..] else {
+        return None;
+    };
+    if is_single_file(&args[position]) {
+        return None;
+    }
+    let mut options = args.to_vec();
+    let name = options.remove(position);
+    if options[position - 1] == "-f" {
+        options.remove(position - 1);
+    }
+    let decoder = match options.iter().any(|option| option == "--auto") {
+        true => None,
+        false => Some(decoder),
+    };
+    Some(dump(
+        &[name],
+        &detect::without_auto(&options),
+        decoder,
+        jobs::default_jobs(),
+    ))
+}
+
+/// Dump the files `names` stands for with the dumper `options`, and with
+/// `decoder` if given or else the decoder each file calls for, returning
+/// the worst exit status among them
+pub fn dump(
+    names: &[String],
+    options: &[String],
+    decoder: Option<Decoder>,
+    jobs: usize,
+) -> io::Result<i32> {
+    if let Some(option) = options
+        .iter()
+        .find(|option| SINGLE_FILE_OPTIONS.contains(&option.as_str()))
+    {
+        usage_error(&format!(
+            "{} can't be used with more than one input file",
+            option
+        ));
+    }
+    let inputs = expand_inputs(names)?;
+
+    let mut out = BufWriter::new(io::stdout());
+    let mut summary = Vec::new();
+    jobs::run_ordered(
+        &inputs,
+        jobs,
+        |input| dump_file(options, decoder, input),
+        |dumped| {
+            if !summary.is_empty() {
+                writeln!(out)?;
+            }
+            writeln!(out, "==> {} <==", dumped.filename)?;
+            out.write_all(dumped.text.as_bytes())?;
+            if let Err(message) = &dumped.checked {
+                writeln!(out, "Error: {}", message)?;
+            }
+            summary.push(SummaryRow::new(&dumped));
+            Ok(())
+        },
+    )?;
+    write_summary(&mut out, &summary)?;
+    out.flush()?;
+    Ok(summary
+        .iter()
+        .map(|row| row.status)
+        .max()
+        .unwrap_or(EXIT_CLEAN))
+}
+
+fn dump_file(options: &[String], decoder: Option<Decoder>, input: &str) -> Dumped {
+    let captured = Captured::default();
+    let checked = detect::check_to(&file_args(options, input), decoder, captured.output());
+    Dumped {
+        filename: input.to_string(),
+        text: captured.text(),
+        checked: checked.map_err(|e| e.to_string()),
+    }
+}
+
+/// A line of the table closing the dumps
+struct SummaryRow {
+    filename: String,
+    input: String,
+    counts: Option<(usize, usize)>, // Errors and warnings, unless dumping failed
+    status: i32,
+}
+
+impl SummaryRow {
+    fn new(dumped: &Dumped) -> Self {
+        match &dumped.checked {
+            Ok(checked) => {
+                let count = |severity| {
+                    checked
+                        .diagnostics
+                        .iter()
+                        .filter(|diagnostic| diagnostic.severity == severity)
+                        .count()
+                };
+                SummaryRow {
+                    filename: dumped.filename.clone(),
+                    input: checked
+                        .detected
+                        .map_or_else(|| "-".to_string(), |detected| detected.to_string()),
+                    counts: Some((count(Severity::Error), count(Severity::Warning))),
+                    status: checked.status,
+                }
+            }
+            Err(_) => SummaryRow {
+                filename: dumped.filename.clone(),
+                input: "-".to_string(),
+                counts: None,
+                status: diagnostics::EXIT_FAILURE,
+            },
+        }
+    }
+}
+
+/// The table of files, and the totals
+fn write_summary(out: &mut impl Write, rows: &[SummaryRow]) -> io::Result<()> {
+    writeln!(out, "\nSummary of {} file(s):", rows.len())?;
+    writeln!(
+        out,
+        "  {:>6}  {:>8}  {:<20} File",
+        "Errors", "Warnings", "Input"
+    )?;
+    for row in rows {
+        let (errors, warnings) = match row.counts {
+            Some((errors, warnings)) => (errors.to_string(), warnings.to_string()),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        writeln!(
+            out,
+            "  {:>6}  {:>8}  {:<20} {}",
+            errors, warnings, row.input, row.filename
+        )?;
+    }
+    let with_status = |status| rows.iter().filter(|row| row.status == status).count();
+    writeln!(
+        out,
+        "Clean: {}, with warnings: {}, with errors: {}, failed: {}",
+        with_status(EXIT_CLEAN),
+        with_status(EXIT_WARNINGS),
+        with_status(EXIT_ERRORS),
+        rows.iter().filter(|row| row.counts.is_none()).count()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_summary() {
+        let row = |filename: &str, input: &str, counts, status| SummaryRow {
+            filename: filename.to_string(),
+            input: input.to_string(),
+            counts,
+            status,
+        };
+        let rows = [
+            row("certs/a.der", "DER", Some((0, 0)), EXIT_CLEAN),
+            row("certs/b.pem", "PEM", Some((2, 1)), EXIT_ERRORS),
+            row("certs/c.bin", "-", None, diagnostics::EXIT_FAILURE),
+        ];
+        let mut out = Vec::new();
+        write_summary(&mut out, &rows).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\nSummary of 3 file(s):\n\
+             \x20 Errors  Warnings  Input                File\n\
+             \x20      0         0  DER                  certs/a.der\n\
+             \x20      2         1  PEM                  certs/b.pem\n\
+             \x20      -         -  -                    certs/c.bin\n\
+             Clean: 1, with warnings: 0, with errors: 1, failed: 1\n"
+        );
+    }
+}
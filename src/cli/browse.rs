@@ -0,0 +1,589 @@
+// asn1cbor browse: the decoded tree of a file in a terminal UI, built with
+// the tui feature. Elements expand and collapse, a search finds them by their
+// text, and a hex pane shows the bytes of the one selected with its header
+// picked out. Copying puts an element's bytes on the clipboard as hex through
+// the terminal (OSC 52), which works over SSH as well.
+
+use std::io::{self, Write};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use super::{decoder_option, file_nodes, pem_content, read_input, usage_error, Decoder};
+use crate::asn1::pem;
+use crate::common::hex::{hex_upper, printable};
+use crate::common::wrap::hex_bytes_per_line;
+use crate::diagnostics::EXIT_CLEAN;
+use crate::render::{json_string, Content, Node};
+
+// Byte strings longer than this are cut short in the outline
+const INLINE_BYTES: usize = 16;
+
+// Width of the offsets in front of each line of the hex pane
+const OFFSET_WIDTH: usize = 6;
+
+/// Options for browse
+#[derive(Debug, Default)]
+struct Options {
+    decoder: Option<Decoder>, // None to detect it
+    show_secrets: bool,
+    input_file: String,
+}
+
+fn print_help(program_name: &str) {
+    println!("Usage: {} [OPTIONS] <file>", program_name);
+    println!("\nShows the decoded items of a file as a tree to explore in the terminal, with");
+    println!("the bytes of the selected element alongside. Whether the file is ASN.1 or CBOR");
+    println!("is detected unless --asn1 or --cbor says; PEM and base64 are decoded first.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  --asn1                  Decode the input as ASN.1 DER, BER or PEM");
+    println!("  --cbor                  Decode the input as CBOR");
+    println!("  --auto                  Detect what the input is (the default)");
+    println!("  --show-secrets          Show private key material, which is otherwise masked");
+    println!("\nKEYS:");
+    println!("  Up, Down, k, j          Select the element above or below");
+    println!("  PgUp, PgDn, Home, End   Move a page, or to the first or last element");
+    println!("  Right, l                Expand the element, or go to its first child");
+    println!("  Left, h                 Collapse the element, or go to its parent");
+    println!("  Enter, Space            Expand or collapse the element");
+    println!("  e, c                    Expand or collapse everything");
+    println!("  /, n, N                 Search the element text, and find the next or previous");
+    println!("                          match");
+    println!("  K, J                    Scroll the hex pane");
+    println!("  y, Y                    Copy the element's bytes, or only its content, as hex");
+    println!("  q, Esc                  Quit");
+}
+
+fn parse_args_from(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut input_file = None;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                std::process::exit(0);
+            }
+            "--show-secrets" => options.show_secrets = true,
+            arg @ ("--asn1" | "--cbor" | "--auto") => options.decoder = decoder_option(arg),
+            arg if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            arg if input_file.is_none() => input_file = Some(arg.to_string()),
+            arg => return Err(format!("Unexpected argument: {}", arg)),
+        }
+        i += 1;
+    }
+    options.input_file = input_file.ok_or("No input file specified")?;
+    Ok(options)
+}
+
+/// Run browse with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let options = parse_args_from(args).unwrap_or_else(|e| usage_error(&e));
+    let (decoder, data) = read_input(&options.input_file, options.decoder)?;
+    // Offsets are into the DER, so that's what the hex pane shows
+    let data = match decoder {
+        Decoder::Asn1 if pem::looks_like_pem(&data) => pem_content(&data)?,
+        _ => data,
+    };
+    let nodes = file_nodes(&data, decoder, options.show_secrets)?;
+    let mut browser = Browser::new(data, &nodes);
+
+    let mut terminal = ratatui::init();
+    let result = browser.run(&mut terminal);
+    ratatui::restore();
+    result.map(|()| EXIT_CLEAN)
+}
+
+/// An element of the tree, as a line of the outline
+#[derive(Debug, Clone)]
+struct Row {
+    depth: usize,
+    parent: Option<usize>,
+    has_children: bool,
+    text: String,
+    notes: Vec<String>,
+    offset: Option<usize>,
+    header_length: usize,
+    length: Option<usize>, // None for indefinite length
+}
+
+/// The tree and the state of the browser
+#[derive(Debug)]
+struct Browser {
+    data: Vec<u8>,
+    rows: Vec<Row>,
+    expanded: Vec<bool>,
+    selected: usize, // Index into rows
+    hex_scroll: usize,
+    query: String,          // The last search
+    prompt: Option<String>, // A search being typed
+    message: String,
+    page: usize, // Rows in the outline pane when last drawn
+}
+
+impl Browser {
+    /// A browser of `nodes`, decoded from `data`, with the top-level
+    /// elements expanded
+    fn new(data: Vec<u8>, nodes: &[Node]) -> Self {
+        let mut rows = Vec::new();
+        for node in nodes {
+            add_rows(&mut rows, node, None, "");
+        }
+        let expanded = rows.iter().map(|row| row.depth == 0).collect();
+        Browser {
+            data,
+            rows,
+            expanded,
+            selected: 0,
+            hex_scroll: 0,
+            query: String::new(),
+            prompt: None,
+            message: String::new(),
+            page: 1,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                    return Ok(());
+                }
+                if !self.key(key.code)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Act on a key, returning false to quit
+    fn key(&mut self, code: KeyCode) -> io::Result<bool> {
+        if let Some(prompt) = &mut self.prompt {
+            match code {
+                KeyCode::Enter => {
+                    self.query = self.prompt.take().unwrap_or_default();
+                    self.search(true);
+                }
+                KeyCode::Esc => self.prompt = None,
+                KeyCode::Backspace => {
+                    prompt.pop();
+                }
+                KeyCode::Char(c) => prompt.push(c),
+                _ => {}
+            }
+            return Ok(true);
+        }
+        self.message.clear();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-(self.page as isize)),
+            KeyCode::PageDown => self.move_by(self.page as isize),
+            KeyCode::Home => self.move_by(isize::MIN),
+            KeyCode::End => self.move_by(isize::MAX),
+            KeyCode::Right | KeyCode::Char('l') => self.expand(),
+            KeyCode::Left | KeyCode::Char('h') => self.collapse(),
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let selected = self.selected;
+                self.expanded[selected] = !self.expanded[selected];
+            }
+            KeyCode::Char('e') => self.expanded.fill(true),
+            KeyCode::Char('c') => {
+                self.expanded.fill(false);
+                while let Some(parent) = self.rows[self.selected].parent {
+                    self.select(parent);
+                }
+            }
+            KeyCode::Char('/') => self.prompt = Some(String::new()),
+            KeyCode::Char('n') => self.search(true),
+            KeyCode::Char('N') => self.search(false),
+            KeyCode::Char('K') => self.hex_scroll = self.hex_scroll.saturating_sub(1),
+            KeyCode::Char('J') => self.hex_scroll += 1,
+            KeyCode::Char('y') => self.copy(false)?,
+            KeyCode::Char('Y') => self.copy(true)?,
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// The rows not inside a collapsed element, in order
+    fn visible(&self) -> Vec<usize> {
+        let mut shown = vec![false; self.rows.len()];
+        let mut visible = Vec::new();
+        for (index, row) in self.rows.iter().enumerate() {
+            shown[index] = match row.parent {
+                Some(parent) => shown[parent] && self.expanded[parent],
+                None => true,
+            };
+            if shown[index] {
+                visible.push(index);
+            }
+        }
+        visible
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index;
+        self.hex_scroll = 0;
+    }
+
+    /// Move the selection `delta` visible rows down, or up if it's negative
+    fn move_by(&mut self, delta: isize) {
+        let visible = self.visible();
+        let position = visible
+            .iter()
+            .position(|&index| index == self.selected)
+            .unwrap_or(0);
+        let target = position
+            .saturating_add_signed(delta)
+            .min(visible.len().saturating_sub(1));
+        if let Some(&index) = visible.get(target) {
+            self.select(index);
+        }
+    }
+
+    fn expand(&mut self) {
+        let selected = self.selected;
+        if !self.rows[selected].has_children {
+            return;
+        }
+        if self.expanded[selected] {
+            self.select(selected + 1);
+        } else {
+            self.expanded[selected] = true;
+        }
+    }
+
+    fn collapse(&mut self) {
+        let selected = self.selected;
+        if self.rows[selected].has_children && self.expanded[selected] {
+            self.expanded[selected] = false;
+        } else if let Some(parent) = self.rows[selected].parent {
+            self.select(parent);
+        }
+    }
+
+    /// Select the next element whose text has the query in it, ignoring
+    /// case, or the previous one if `forward` isn't set. Collapsed elements
+    /// are searched too, and opened to show the match
+    fn search(&mut self, forward: bool) {
+        if self.query.is_empty() {
+            return;
+        }
+        let query = self.query.to_lowercase();
+        let count = self.rows.len();
+        let found = (1..=count)
+            .map(|step| {
+                if forward {
+                    (self.selected + step) % count
+                } else {
+                    (self.selected + count - step) % count
+                }
+            })
+            .find(|&index| self.rows[index].text.to_lowercase().contains(&query));
+        match found {
+            Some(index) => {
+                let mut parent = self.rows[index].parent;
+                while let Some(ancestor) = parent {
+                    self.expanded[ancestor] = true;
+                    parent = self.rows[ancestor].parent;
+                }
+                self.select(index);
+            }
+            None => self.message = format!("No match for '{}'", self.query),
+        }
+    }
+
+    /// Where an element starts, where its content starts and where it ends.
+    /// An indefinite length ends where the next element not inside it
+    /// starts
+    fn range(&self, index: usize) -> Option<(usize, usize, usize)> {
+        let row = &self.rows[index];
+        let start = row.offset?;
+        let content = start + row.header_length;
+        let end = match row.length {
+            Some(length) => content + length,
+            None => self.rows[index + 1..]
+                .iter()
+                .find(|next| next.depth <= row.depth)
+                .and_then(|next| next.offset)
+                .unwrap_or(self.data.len()),
+        };
+        let end = end.min(self.data.len());
+        Some((start.min(end), content.min(end), end))
+    }
+
+    /// Put the selected element's bytes, or only its content, on the
+    /// clipboard as hex
+    fn copy(&mut self, content_only: bool) -> io::Result<()> {
+        let Some((start, content, end)) = self.range(self.selected) else {
+            self.message = "The element's offset isn't known".to_string();
+            return Ok(());
+        };
+        let start = if content_only { content } else { start };
+        let text = hex_upper(&self.data[start..end], "");
+        let mut out = io::stdout();
+        write!(out, "{}", osc52(&text))?;
+        out.flush()?;
+        self.message = format!("Copied {} bytes at offset {}", end - start, start);
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [outline, hex] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(main);
+        self.draw_outline(frame, outline);
+        self.draw_hex(frame, hex);
+
+        let status_line = match &self.prompt {
+            Some(prompt) => format!("/{}", prompt),
+            None if !self.message.is_empty() => self.message.clone(),
+            None => "arrows move and expand  / search  y copy  q quit".to_string(),
+        };
+        frame.render_widget(
+            Paragraph::new(status_line).style(Style::new().add_modifier(Modifier::REVERSED)),
+            status,
+        );
+    }
+
+    fn draw_outline(&mut self, frame: &mut Frame, area: Rect) {
+        self.page = area.height.saturating_sub(2).max(1) as usize;
+        let visible = self.visible();
+        let items: Vec<ListItem> = visible
+            .iter()
+            .map(|&index| {
+                let row = &self.rows[index];
+                let marker = match (row.has_children, self.expanded[index]) {
+                    (false, _) => "  ",
+                    (true, true) => "▾ ",
+                    (true, false) => "▸ ",
+                };
+                let offset = row.offset.map_or(String::new(), |o| o.to_string());
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:>w$} ", offset, w = OFFSET_WIDTH),
+                        Style::new().fg(Color::DarkGray),
+                    ),
+                    Span::raw(format!("{}{}{}", "  ".repeat(row.depth), marker, row.text)),
+                ]))
+            })
+            .collect();
+        let mut state =
+            ListState::default().with_selected(visible.iter().position(|&i| i == self.selected));
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(" Elements "))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn draw_hex(&mut self, frame: &mut Frame, area: Rect) {
+        let row = &self.rows[self.selected];
+        let range = self.range(self.selected);
+        let mut lines = Vec::new();
+        if let Some(offset) = row.offset {
+            let length = row
+                .length
+                .map_or("indefinite".to_string(), |l| l.to_string());
+            lines.push(Line::from(format!(
+                "Offset {}, header {} bytes, length {}",
+                offset, row.header_length, length
+            )));
+        }
+        for note in &row.notes {
+            lines.push(Line::styled(note.clone(), Style::new().fg(Color::Yellow)));
+        }
+        lines.push(Line::default());
+        if let Some((start, content, end)) = range {
+            let width = (area.width as usize).saturating_sub(2 + OFFSET_WIDTH + 1);
+            let per_line = hex_bytes_per_line(width, true);
+            let header = Style::new().fg(Color::Cyan);
+            let rows = (start..end).step_by(per_line);
+            self.hex_scroll = self.hex_scroll.min(rows.len().saturating_sub(1));
+            for line_start in rows.skip(self.hex_scroll) {
+                let line_end = (line_start + per_line).min(end);
+                let mut spans = vec![Span::styled(
+                    format!("{:>w$} ", line_start, w = OFFSET_WIDTH),
+                    Style::new().fg(Color::DarkGray),
+                )];
+                for offset in line_start..line_end {
+                    let style = if offset < content {
+                        header
+                    } else {
+                        Style::new()
+                    };
+                    spans.push(Span::styled(format!("{:02X} ", self.data[offset]), style));
+                }
+                let padding = (per_line - (line_end - line_start)) * 3;
+                let text: String = self.data[line_start..line_end]
+                    .iter()
+                    .map(|&b| printable(b))
+                    .collect();
+                spans.push(Span::raw(format!("{:padding$} {}", "", text)));
+                lines.push(Line::from(spans));
+            }
+        } else {
+            lines.push(Line::from("The element's offset isn't known"));
+        }
+        let pane =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Bytes "));
+        frame.render_widget(pane, area);
+    }
+}
+
+/// Add a row for `node` and each element inside it
+fn add_rows(rows: &mut Vec<Row>, node: &Node, parent: Option<usize>, prefix: &str) {
+    let index = rows.len();
+    rows.push(Row {
+        depth: parent.map_or(0, |parent| rows[parent].depth + 1),
+        parent,
+        has_children: !node.children().is_empty(),
+        text: format!("{}{}", prefix, summary(node)),
+        notes: node.notes.clone(),
+        offset: node.offset,
+        header_length: node.header_length,
+        length: node.length,
+    });
+    match &node.content {
+        Content::List(nodes) => {
+            for child in nodes {
+                add_rows(rows, child, Some(index), "");
+            }
+        }
+        Content::Map(pairs) => {
+            for (key, value) in pairs {
+                add_rows(rows, key, Some(index), "");
+                add_rows(rows, value, Some(index), "=> ");
+            }
+        }
+        Content::Tagged(_, child) => add_rows(rows, child, Some(index), ""),
+        _ => {}
+    }
+}
+
+/// An element's label, type and value, on one line
+fn summary(node: &Node) -> String {
+    let mut text = match &node.label {
+        Some(label) => format!("{}: {}", label, node.kind),
+        None => node.kind.clone(),
+    };
+    let value = match &node.content {
+        Content::Null | Content::Undefined => String::new(),
+        Content::Integer(n) => n.clone(),
+        Content::Float(f) => f.to_string(),
+        Content::Bool(b) => b.to_string(),
+        Content::Simple(n) => n.to_string(),
+        Content::Text(text) => json_string(text),
+        Content::Bytes(bytes) if bytes.len() <= INLINE_BYTES => hex_upper(bytes, " "),
+        Content::Bytes(bytes) => format!(
+            "{} ... ({} bytes)",
+            hex_upper(&bytes[..INLINE_BYTES], " "),
+            bytes.len()
+        ),
+        Content::Oid { dotted, name } => match name {
+            Some(name) => format!("{} ({})", dotted, name),
+            None => dotted.clone(),
+        },
+        Content::Other(text) => text.clone(),
+        Content::List(nodes) => format!("({} elements)", nodes.len()),
+        Content::Map(pairs) => format!("({} pairs)", pairs.len()),
+        Content::Tagged(tag, _) => tag.to_string(),
+    };
+    if !value.is_empty() {
+        text.push(' ');
+        text.push_str(&value);
+    }
+    text
+}
+
+/// The escape sequence asking the terminal to put `text` on the clipboard
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", pem::encode_base64(text.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::sample;
+
+    fn browser() -> Browser {
+        Browser::new(vec![0; 29], &sample())
+    }
+
+    #[test]
+    fn test_rows() {
+        let browser = browser();
+        let texts: Vec<&str> = browser.rows.iter().map(|row| row.text.as_str()).collect();
+        assert_eq!(texts[0], "SEQUENCE (3 elements)");
+        assert_eq!(
+            texts[2],
+            "algorithm: OBJECT IDENTIFIER 2.16.840.1.101.3.4.2.1 (sha256)"
+        );
+        assert_eq!(texts[3], "UTF8String \"a\\\"b\"");
+        assert_eq!(texts[6], "=> bytes 01 02");
+        assert_eq!(texts[8], "=> tag 1");
+        assert_eq!(browser.rows[9].depth, 2);
+        assert_eq!(browser.visible(), [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut browser = browser();
+        browser.move_by(isize::MAX);
+        assert_eq!(browser.selected, 8);
+        browser.expand();
+        assert_eq!(browser.visible().len(), 10);
+        browser.expand();
+        assert_eq!(browser.selected, 9);
+        browser.collapse();
+        browser.collapse();
+        assert_eq!((browser.selected, browser.visible().len()), (8, 9));
+        browser.key(KeyCode::Char('c')).unwrap();
+        assert_eq!((browser.selected, browser.visible()), (4, vec![0, 4]));
+        browser.move_by(-1);
+        assert_eq!(browser.selected, 0);
+    }
+
+    #[test]
+    fn test_search() {
+        let mut browser = browser();
+        browser.key(KeyCode::Char('c')).unwrap();
+        for c in "/NULL".chars() {
+            browser.key(KeyCode::Char(c)).unwrap();
+        }
+        browser.key(KeyCode::Enter).unwrap();
+        assert_eq!(browser.selected, 9);
+        assert!(browser.visible().contains(&9));
+        browser.search(true);
+        assert_eq!(browser.selected, 9);
+        browser.query = "missing".to_string();
+        browser.search(true);
+        assert_eq!(browser.message, "No match for 'missing'");
+    }
+
+    #[test]
+    fn test_range() {
+        let browser = browser();
+        assert_eq!(browser.range(0), Some((0, 2, 21)));
+        assert_eq!(browser.range(2), Some((5, 7, 16)));
+        // The indefinite-length map runs to the end
+        assert_eq!(browser.range(4), Some((21, 22, 29)));
+    }
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(osc52("3000"), "\x1b]52;c;MzAwMA==\x07");
+    }
+}
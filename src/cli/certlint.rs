@@ -0,0 +1,170 @@
+// asn1cbor lint-cert: check certificates against the RFC 5280 profile, for
+// what a dump doesn't look at: serial numbers, the string types in names,
+// the extensions a certificate must have and their criticality. Each
+// certificate gets a line naming it, then a line for each finding in the
+// form lint uses, and the report ends with the totals.
+
+use std::io::{self, BufWriter, Write};
+
+use super::inputs::expand_inputs;
+use super::lint::format_line;
+use super::{read_data, usage_error};
+use crate::asn1::{der, pem, profile, x509};
+use crate::common::args::option_value;
+use crate::common::color::ColorChoice;
+use crate::common::counters::Findings;
+use crate::diagnostics::{self, Diagnostic, Severity, EXIT_CLEAN};
+
+fn print_help(program_name: &str) {
+    println!(
+        "Usage: {} [OPTIONS] <certificate_file|directory|pattern>...",
+        program_name
+    );
+    println!("\nChecks certificates, DER or PEM, against the RFC 5280 profile: a positive");
+    println!("serialNumber of at most 20 octets, no T61String, UniversalString or BMPString");
+    println!("in names, the keyUsage, subjectKeyIdentifier and authorityKeyIdentifier");
+    println!("extensions where they're required, extensions marked critical or not as");
+    println!("they must be, and no extension repeated. Breaking a MUST is an error, and a");
+    println!("SHOULD a warning. Each certificate is named on a line of its own, followed by");
+    println!("what it breaks, as <file>:<offset>: <severity>: <message> [<code>], the offset");
+    println!("being into the file for DER and into the certificate's block for PEM.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  --color <when>          Color the severities: auto, always or never");
+    println!("\nEXAMPLES:");
+    println!("  {} server.pem", program_name);
+    println!("  {} 'issued/**/*.der'", program_name);
+}
+
+/// Run lint-cert with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let mut color = ColorChoice::Auto;
+    let mut names = Vec::new();
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                return Ok(EXIT_CLEAN);
+            }
+            "--color" => {
+                let value = option_value(args, &mut i).unwrap_or_else(|e| usage_error(&e));
+                color = ColorChoice::from_name(value).unwrap_or_else(|| {
+                    usage_error(&format!(
+                        "Unknown color choice: {} (use {})",
+                        value,
+                        ColorChoice::NAMES
+                    ))
+                });
+            }
+            arg if arg.starts_with('-') => usage_error(&format!("Unknown option: {}", arg)),
+            arg => names.push(arg.to_string()),
+        }
+        i += 1;
+    }
+    let inputs = expand_inputs(&names)?;
+    if inputs.is_empty() {
+        usage_error("No input file specified");
+    }
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let colors = color.for_stdout();
+    let mut findings = Findings::default();
+    let mut status = EXIT_CLEAN;
+    let mut checked = 0;
+    for filename in &inputs {
+        let data = match read_data(filename) {
+            Ok(data) => data,
+            Err(e) => {
+                out.flush()?;
+                eprintln!("Error: {}", e);
+                status = status.max(diagnostics::failure_status(&e));
+                continue;
+            }
+        };
+        for (index, result) in certificates(&data).into_iter().enumerate() {
+            let diagnostics = match result {
+                Ok((certificate, offset)) => {
+                    if let Some(parsed) = x509::parse_certificate(&certificate) {
+                        checked += 1;
+                        writeln!(
+                            out,
+                            "{}, certificate {}: {}",
+                            filename,
+                            index + 1,
+                            x509::name_to_string(parsed.subject)
+                        )?;
+                    }
+                    let mut diagnostics = profile::lint_certificate(&certificate);
+                    for diagnostic in &mut diagnostics {
+                        diagnostic.offset = diagnostic.offset.map(|at| offset + at);
+                    }
+                    diagnostics
+                }
+                Err(diagnostic) => vec![diagnostic],
+            };
+            for diagnostic in diagnostics {
+                writeln!(out, "{}", format_line(filename, &diagnostic, colors))?;
+                findings.record(diagnostic);
+            }
+        }
+    }
+    writeln!(out, "\n{} certificate(s) checked.", checked)?;
+    write!(out, "{}", findings.totals())?;
+    out.flush()?;
+    Ok(status.max(findings.exit_status()))
+}
+
+/// The certificates in a file, each with the offset to add to those in it:
+/// the blocks of a PEM file labelled CERTIFICATE, at 0, or the top-level
+/// items of DER at their own offsets. A PEM block that can't be decoded is
+/// an error in its place
+fn certificates(data: &[u8]) -> Vec<Result<(Vec<u8>, usize), Diagnostic>> {
+    if pem::looks_like_pem(data) {
+        return pem::parse_pem(data)
+            .into_iter()
+            .filter(|block| block.label == "CERTIFICATE")
+            .map(|block| {
+                block.data.map(|der| (der, 0)).map_err(|msg| Diagnostic {
+                    code: "pem-decode",
+                    severity: Severity::Error,
+                    offset: None,
+                    path: Vec::new(),
+                    message: format!("Can't decode CERTIFICATE block: {}", msg),
+                })
+            })
+            .collect();
+    }
+    let items: Vec<_> = der::children(data)
+        .map(|(offset, tlv)| Ok((data[offset..offset + tlv.total_len()].to_vec(), offset)))
+        .collect();
+    match items.is_empty() {
+        // Checked anyway, to say it isn't a certificate
+        true => vec![Ok((data.to_vec(), 0))],
+        false => items,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_certificates() {
+        let der = [0x30, 0x00, 0x02, 0x01, 0x01];
+        let found: Vec<_> = certificates(&der).into_iter().map(Result::unwrap).collect();
+        assert_eq!(found, [(vec![0x30, 0x00], 0), (vec![0x02, 0x01, 0x01], 2)]);
+
+        let text = format!(
+            "{}{}-----BEGIN CERTIFICATE-----\n!!\n-----END CERTIFICATE-----\n",
+            pem::encode_pem("CERTIFICATE", &[0x30, 0x00]),
+            pem::encode_pem("PRIVATE KEY", &[0x30, 0x00]),
+        );
+        let found = certificates(text.as_bytes());
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].as_ref().unwrap(), &(vec![0x30, 0x00], 0));
+        assert_eq!(found[1].as_ref().unwrap_err().code, "pem-decode");
+
+        assert_eq!(certificates(b"hello")[0].as_ref().unwrap().0, b"hello");
+    }
+}
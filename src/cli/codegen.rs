@@ -0,0 +1,219 @@
+// asn1cbor codegen: Rust types for the CBOR items in one or more sample
+// files, to start a client of a protocol off from captured messages, or a
+// CDDL skeleton for them, or an ASN.1 module for DER samples, to start
+// documenting a format that has none. Every top-level item of every file is taken as a sample of the same
+// type, and what the samples have in common is what the types describe.
+
+use std::io;
+
+use super::{decoder_option, pem_content, read_input, usage_error, Decoder};
+use crate::asn1::der;
+use crate::asn1::pem;
+use crate::asn1::skeleton::{self, Type};
+use crate::cbor::{self, Schema};
+use crate::common::args::option_value;
+use crate::diagnostics::EXIT_CLEAN;
+
+/// Options for codegen
+#[derive(Debug)]
+struct Options {
+    name: String,             // Of the type for the items
+    cddl: bool,               // CDDL rather than Rust for CBOR
+    decoder: Option<Decoder>, // None to detect it
+    input_files: Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            name: "Root".to_string(),
+            cddl: false,
+            decoder: None,
+            input_files: Vec::new(),
+        }
+    }
+}
+
+fn print_help(program_name: &str) {
+    println!("Usage: {} [OPTIONS] <file>...", program_name);
+    println!("\nWrites Rust types for the CBOR items in the files, taking every top-level item");
+    println!("as a sample of the same type: a struct for each map with text keys, a Vec for");
+    println!("each array of items alike and a tuple for one of items that differ. Integers");
+    println!("widen to fit all the samples, a field missing from some becomes an Option, and");
+    println!("values with nothing in common are left as ciborium::Value. The types derive");
+    println!("serde's Serialize and Deserialize, for reading and writing with ciborium, and");
+    println!("byte strings use serde_bytes.\n");
+    println!("With --cddl, writes CDDL (RFC 8610) for the CBOR items instead: a rule for");
+    println!("each map with text keys, fields missing from some samples optional (?), and");
+    println!("null as a choice for values that were null in some.\n");
+    println!("For DER or PEM, writes an ASN.1 module instead, with a SEQUENCE, SET or CHOICE");
+    println!("type for each structure, the field names of those recognized, such as");
+    println!("certificates, and components missing from some samples OPTIONAL.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  --name <type>           Name of the type for the items (default: Root)");
+    println!("  --cddl                  Write CDDL for CBOR items rather than Rust");
+    println!("  --asn1, --cbor          Decode the files as ASN.1 or CBOR rather than detect");
+    println!("                          which they are");
+    println!("\nEXAMPLES:");
+    println!(
+        "  {} --name Request captured/*.cbor > src/request.rs",
+        program_name
+    );
+    println!(
+        "  {} --cddl --name request captured/*.cbor > request.cddl",
+        program_name
+    );
+    println!(
+        "  {} --name Token token1.der token2.der > token.asn",
+        program_name
+    );
+}
+
+fn parse_args_from(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                std::process::exit(0);
+            }
+            "--name" => {
+                let name = option_value(args, &mut i)?;
+                let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !valid {
+                    return Err(format!("Invalid type name: {}", name));
+                }
+                options.name = name.to_string();
+            }
+            "--cddl" => options.cddl = true,
+            arg @ ("--asn1" | "--cbor" | "--auto") => options.decoder = decoder_option(arg),
+            arg if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            arg => options.input_files.push(arg.to_string()),
+        }
+        i += 1;
+    }
+    if options.input_files.is_empty() {
+        return Err("No input file specified".to_string());
+    }
+    Ok(options)
+}
+
+/// Run codegen with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let options = parse_args_from(args).unwrap_or_else(|e| usage_error(&e));
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    // The samples are all ASN.1 or all CBOR, like the first file
+    let mut decoder = options.decoder;
+    let (mut schema, mut ty) = (Schema::Unknown, Type::Unknown);
+    let mut samples = 0;
+    for filename in &options.input_files {
+        let (found, data) = read_input(filename, options.decoder)?;
+        if decoder.is_some_and(|decoder| decoder != found) {
+            return Err(invalid(format!(
+                "'{}' isn't {} like the files before it",
+                filename,
+                decoder_name(decoder)
+            )));
+        }
+        decoder = Some(found);
+        match found {
+            Decoder::Cbor => {
+                let items = cbor::parse_slice(&data)?;
+                samples += items.len();
+                schema = schema.merge(Schema::infer_all(&items));
+            }
+            Decoder::Asn1 => {
+                let data = match pem::looks_like_pem(&data) {
+                    true => pem_content(&data)?,
+                    false => data,
+                };
+                if !der::is_valid_der(&data) {
+                    return Err(invalid(format!("'{}' isn't definite-length DER", filename)));
+                }
+                samples += der::children(&data).count();
+                ty = ty.merge(Type::infer_all(&data));
+            }
+        }
+    }
+    if samples == 0 {
+        return Err(invalid("The input files hold no items".to_string()));
+    }
+    match decoder {
+        Some(Decoder::Asn1) if options.cddl => {
+            return Err(invalid(
+                "--cddl is for CBOR samples; DER ones get an ASN.1 module".to_string(),
+            ));
+        }
+        Some(Decoder::Asn1) => {
+            println!(
+                "-- Inferred by asn1cbor codegen from {} sample(s)\n",
+                samples
+            );
+            print!("{}", skeleton::asn1_module(&ty, &options.name));
+        }
+        _ if options.cddl => {
+            println!(
+                "; Inferred by asn1cbor codegen from {} sample(s)\n",
+                samples
+            );
+            print!("{}", cbor::cddl(&schema, &options.name));
+        }
+        _ => {
+            println!(
+                "// Inferred by asn1cbor codegen from {} sample(s)\n",
+                samples
+            );
+            print!("{}", cbor::rust_types(&schema, &options.name));
+        }
+    }
+    Ok(EXIT_CLEAN)
+}
+
+/// What a decoder decodes, for messages
+fn decoder_name(decoder: Option<Decoder>) -> &'static str {
+    match decoder {
+        Some(Decoder::Asn1) => "ASN.1",
+        _ => "CBOR",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let args: Vec<String> = ["codegen", "--name", "Request", "a.cbor", "b.cbor"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = parse_args_from(&args).unwrap();
+        assert_eq!(options.name, "Request");
+        assert_eq!(options.input_files, ["a.cbor", "b.cbor"]);
+        assert_eq!(options.decoder, None);
+        assert!(!options.cddl);
+        let args: Vec<String> = ["codegen", "--asn1", "a.der"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(parse_args_from(&args).unwrap().decoder, Some(Decoder::Asn1));
+        let args: Vec<String> = ["codegen", "--cddl", "a.cbor"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(parse_args_from(&args).unwrap().cddl);
+        for bad in [
+            &["codegen"][..],
+            &["codegen", "--name", "2x", "a"],
+            &["codegen", "--name", "a-b", "a"],
+            &["codegen", "-x", "a"],
+        ] {
+            let args: Vec<String> = bad.iter().map(|s| s.to_string()).collect();
+            assert!(parse_args_from(&args).is_err(), "{:?}", bad);
+        }
+    }
+}
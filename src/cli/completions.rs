@@ -0,0 +1,1037 @@
+// asn1cbor completions: print a script for bash, zsh or fish completing the
+// commands of asn1cbor and the options of each, and those of dumpasn1 and
+// dumpcbor. The options are listed here once for all three shells; a test
+// checks the dumpers' lists against their option parsers.
+
+use std::io::{self, Write};
+
+use super::convert::TARGET_NAMES;
+use super::{usage_error, Command, COMMANDS};
+use crate::asn1::crypto::HashAlg;
+use crate::cbor::coap::ContentFormat;
+use crate::common::color::ColorChoice;
+use crate::common::emit::Language;
+use crate::render::Format;
+
+const SHELL_NAMES: &str = "bash, zsh or fish";
+const DIGEST_NAMES: &str = HashAlg::NAMES;
+
+/// What follows an option on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    None,
+    File,
+    Number,
+    Text,
+    Range,                // A byte window, START..END
+    Span,                 // A run of bytes, START:LEN
+    Hex,                  // Bytes written in hex
+    Time,                 // An instant, in ISO 8601 or as @SECONDS
+    Choice(&'static str), // One of a list such as Format::NAMES
+}
+
+/// An option's names, its value and a description for the shells that show
+/// one
+type Flag = (&'static [&'static str], Value, &'static str);
+
+const HELP: Flag = (&["-h", "--help"], Value::None, "Show the help and exit");
+
+const DECODER_FLAGS: &[Flag] = &[
+    (&["--asn1"], Value::None, "Decode the input as ASN.1"),
+    (&["--cbor"], Value::None, "Decode the input as CBOR"),
+    (&["--auto"], Value::None, "Detect what the input is"),
+];
+
+const DUMP_ASN1_FLAGS: &[Flag] = &[
+    HELP,
+    (
+        &["-a", "--print-all"],
+        Value::None,
+        "Print all data in long data blocks",
+    ),
+    (
+        &["-c", "--no-check-charset"],
+        Value::None,
+        "Don't look for text in OCTET STRINGs",
+    ),
+    (
+        &["-d", "--dump-header"],
+        Value::None,
+        "Dump the hex header of each item",
+    ),
+    (
+        &["-dd"],
+        Value::None,
+        "Dump the hex header and the start of the content",
+    ),
+    (
+        &["-e", "--no-check-encaps"],
+        Value::None,
+        "Don't look for encapsulated data",
+    ),
+    (&["-f"], Value::File, "Read input from a file"),
+    (&["-O"], Value::File, "Write the dump to a file"),
+    (
+        &["--expect"],
+        Value::File,
+        "Compare the dump with the one in a file",
+    ),
+    (
+        &["-i", "--shallow-indent"],
+        Value::None,
+        "Indent by 1 space instead of 2",
+    ),
+    (
+        &["-l", "--max-level"],
+        Value::Number,
+        "Maximum nesting level to display",
+    ),
+    (
+        &["-o", "--outline"],
+        Value::None,
+        "Only display the outline of constructed items",
+    ),
+    (
+        &["-p", "--pure"],
+        Value::None,
+        "No offset or length information on the left",
+    ),
+    (
+        &["-r", "--raw-time"],
+        Value::None,
+        "Print times as raw strings",
+    ),
+    (
+        &["-t", "--text"],
+        Value::None,
+        "Dump text alongside hex data",
+    ),
+    (&["-v", "--verbose"], Value::None, "Verbose output"),
+    (
+        &["-w", "--width"],
+        Value::Number,
+        "Output width in characters",
+    ),
+    (
+        &["-x", "--hex-values"],
+        Value::None,
+        "Display sizes and offsets in hex",
+    ),
+    (
+        &["-z", "--zero-length"],
+        Value::None,
+        "Allow zero-length items",
+    ),
+    (&["--dots"], Value::None, "Print dots to align columns"),
+    (&["--no-offset"], Value::None, "Don't print offsets"),
+    (
+        &["--lengths"],
+        Value::None,
+        "Show header lengths in a column of their own",
+    ),
+    (
+        &["--oid-info"],
+        Value::None,
+        "Print extra information about OIDs",
+    ),
+    (
+        &["--oid-config"],
+        Value::File,
+        "Read more OID names from a dumpasn1.cfg",
+    ),
+    (
+        &["--show-secrets"],
+        Value::None,
+        "Show private key material",
+    ),
+    (
+        &["--color"],
+        Value::Choice(ColorChoice::NAMES),
+        "When to color errors and warnings",
+    ),
+    (
+        &["--strict"],
+        Value::None,
+        "Check DER rules and stop at the first error",
+    ),
+    (
+        &["--max-item-bytes"],
+        Value::Number,
+        "Reject longer primitive items",
+    ),
+    (
+        &["--recover"],
+        Value::None,
+        "Skip to the next plausible item after an error",
+    ),
+    (
+        &["--skip"],
+        Value::Number,
+        "Pass over this many top-level items",
+    ),
+    (
+        &["--first"],
+        Value::Number,
+        "Stop after this many top-level items",
+    ),
+    (
+        &["--range"],
+        Value::Range,
+        "Show only the elements in the byte window START..END",
+    ),
+    (
+        &["--highlight"],
+        Value::Span,
+        "Note the elements overlapping the bytes START:LEN",
+    ),
+    (
+        &["--auto"],
+        Value::None,
+        "Detect whether the input is ASN.1 or CBOR",
+    ),
+    (
+        &["--format"],
+        Value::Choice(Format::NAMES),
+        "Write the decoded items in a format",
+    ),
+    (
+        &["--errors-json"],
+        Value::File,
+        "Write errors and warnings as JSON",
+    ),
+    (
+        &["--timing"],
+        Value::None,
+        "Report parsing and rendering times",
+    ),
+    (
+        &["--max-depth"],
+        Value::Number,
+        "Nesting depth at which parsing stops",
+    ),
+    (&["--int-hex"], Value::None, "Print INTEGER values in hex"),
+    (
+        &["--int-both"],
+        Value::None,
+        "Print INTEGER values in decimal and hex",
+    ),
+    (
+        &["--password"],
+        Value::Text,
+        "Password for PKCS #12 EncryptedData",
+    ),
+    (
+        &["--digest"],
+        Value::Choice(DIGEST_NAMES),
+        "Print the digest of each item",
+    ),
+    (
+        &["--digest-each"],
+        Value::Choice(DIGEST_NAMES),
+        "Show the digest of every element",
+    ),
+    (
+        &["--select"],
+        Value::Text,
+        "Item to digest or write code for, by name or offset",
+    ),
+    (
+        &["--find-oid"],
+        Value::Text,
+        "List where this OID occurs instead of dumping",
+    ),
+    (
+        &["--emit-code"],
+        Value::Choice(Language::NAMES),
+        "Write items as a byte array in source code",
+    ),
+    (
+        &["--at"],
+        Value::Time,
+        "Check certificate validity at this time",
+    ),
+    (
+        &["--issuer"],
+        Value::File,
+        "Check certificate signatures with this issuer's key",
+    ),
+];
+
+const DUMP_CBOR_FLAGS: &[Flag] = &[
+    HELP,
+    (
+        &["-a", "--print-all"],
+        Value::None,
+        "Print all data in long byte strings",
+    ),
+    (&["-c", "--compact"], Value::None, "Compact output"),
+    (&["-f"], Value::File, "Read input from a file"),
+    (&["-O"], Value::File, "Write the dump to a file"),
+    (
+        &["--expect"],
+        Value::File,
+        "Compare the dump with the one in a file",
+    ),
+    (
+        &["-l", "--max-level"],
+        Value::Number,
+        "Maximum nesting level to display",
+    ),
+    (
+        &["-m", "--max-bytes"],
+        Value::Number,
+        "Maximum bytes to display for byte strings",
+    ),
+    (
+        &["-o", "--offsets"],
+        Value::None,
+        "Show the offset of each item",
+    ),
+    (&["-t", "--no-types"], Value::None, "Don't show type names"),
+    (&["-v", "--verbose"], Value::None, "Verbose output"),
+    (
+        &["-x", "--hex"],
+        Value::None,
+        "Always show hex dumps of byte strings",
+    ),
+    (&["--hex-offsets"], Value::None, "Display offsets in hex"),
+    (
+        &["--lengths"],
+        Value::None,
+        "Show header and content lengths in columns",
+    ),
+    (&["--int-hex"], Value::None, "Show integers in hex as well"),
+    (
+        &["--inline"],
+        Value::None,
+        "Put small arrays and maps on one line",
+    ),
+    (
+        &["--heads"],
+        Value::None,
+        "Show each item's head bytes as encoded",
+    ),
+    (
+        &["--pretty-json"],
+        Value::None,
+        "Lay out JSON found in text strings",
+    ),
+    (
+        &["--raw-strings"],
+        Value::None,
+        "Don't escape control characters in text",
+    ),
+    (
+        &["--check-canonical"],
+        Value::None,
+        "Check that map keys are in canonical order",
+    ),
+    (
+        &["--validate"],
+        Value::None,
+        "Only list errors and warnings, for CI",
+    ),
+    (
+        &["--roundtrip"],
+        Value::None,
+        "Re-encode each item and compare it with the input",
+    ),
+    (
+        &["--digest-each"],
+        Value::Choice(DIGEST_NAMES),
+        "Show the digest of every element",
+    ),
+    (
+        &["--select"],
+        Value::Text,
+        "Element to digest, by offset or path",
+    ),
+    (
+        &["--no-decode-nested"],
+        Value::None,
+        "Don't decode base64 or PEM in text strings",
+    ),
+    (
+        &["--content-format"],
+        Value::Choice(ContentFormat::CBOR_IDS),
+        "Label the input as a CoAP payload of this format",
+    ),
+    (
+        &["--max-item-bytes"],
+        Value::Number,
+        "Reject longer strings",
+    ),
+    (
+        &["--recover"],
+        Value::None,
+        "Skip to the next plausible item after an error",
+    ),
+    (
+        &["--skip"],
+        Value::Number,
+        "Pass over this many top-level items",
+    ),
+    (
+        &["--first"],
+        Value::Number,
+        "Stop after this many top-level items",
+    ),
+    (
+        &["--range"],
+        Value::Range,
+        "Show only the elements in the byte window START..END",
+    ),
+    (
+        &["--highlight"],
+        Value::Span,
+        "Note the elements overlapping the bytes START:LEN",
+    ),
+    (
+        &["--grep"],
+        Value::Text,
+        "List the strings and map keys matching this regex",
+    ),
+    (
+        &["--grep-bytes"],
+        Value::Hex,
+        "List the byte strings holding these bytes",
+    ),
+    (
+        &["--auto"],
+        Value::None,
+        "Detect whether the input is ASN.1 or CBOR",
+    ),
+    (&["--strict"], Value::None, "Stop at the first error"),
+    (&["--stream"], Value::None, "Print items as they're decoded"),
+    (
+        &["--follow"],
+        Value::None,
+        "Print items as they arrive, as tail -f",
+    ),
+    (
+        &["--listen"],
+        Value::Text,
+        "Dump TCP connections on addr:port",
+    ),
+    (
+        &["--unix"],
+        Value::File,
+        "Dump connections to a Unix socket",
+    ),
+    (
+        &["--format"],
+        Value::Choice(Format::NAMES),
+        "Write the decoded items in a format",
+    ),
+    (
+        &["--errors-json"],
+        Value::File,
+        "Write errors and warnings as JSON",
+    ),
+    (
+        &["--timing"],
+        Value::None,
+        "Report parsing and rendering times",
+    ),
+    (
+        &["--color"],
+        Value::Choice(ColorChoice::NAMES),
+        "When to color errors",
+    ),
+    (
+        &["--max-depth"],
+        Value::Number,
+        "Nesting depth at which parsing stops",
+    ),
+];
+
+const JOBS: Flag = (&["-j", "--jobs"], Value::Number, "Files to work on at once");
+
+const DIFF_FLAGS: &[Flag] = &[
+    HELP,
+    (
+        &["--show-secrets"],
+        Value::None,
+        "Compare and show private key material",
+    ),
+];
+
+const CONVERT_FLAGS: &[Flag] = &[
+    HELP,
+    (&["--to"], Value::Choice(TARGET_NAMES), "Format to write"),
+    (
+        &["--label"],
+        Value::Text,
+        "PEM label for items that aren't recognized",
+    ),
+    (
+        &["--show-secrets"],
+        Value::None,
+        "Show private key material",
+    ),
+    (
+        &["--strip-self-describe"],
+        Value::None,
+        "Leave out a leading tag 55799",
+    ),
+    (&["-o"], Value::File, "Write to a file"),
+];
+
+const LINT_FLAGS: &[Flag] = &[
+    HELP,
+    JOBS,
+    (
+        &["--color"],
+        Value::Choice(ColorChoice::NAMES),
+        "When to color the severities",
+    ),
+];
+
+const LINT_CERT_FLAGS: &[Flag] = &[
+    HELP,
+    (
+        &["--color"],
+        Value::Choice(ColorChoice::NAMES),
+        "When to color the severities",
+    ),
+];
+
+const MINIMIZE_FLAGS: &[Flag] = &[
+    HELP,
+    (
+        &["--error"],
+        Value::Text,
+        "Keep an error with this code or message text",
+    ),
+    (
+        &["--warning"],
+        Value::Text,
+        "Keep a warning with this code or message text",
+    ),
+    (&["--output"], Value::File, "Write the result to a file"),
+];
+
+const MUTATE_FLAGS: &[Flag] = &[
+    HELP,
+    (&["--seed"], Value::Number, "Seed for the changes"),
+    (
+        &["-n", "--count"],
+        Value::Number,
+        "Number of variants to write",
+    ),
+    (
+        &["-o"],
+        Value::File,
+        "Write the variants to files in a directory",
+    ),
+];
+
+const BROWSE_FLAGS: &[Flag] = &[
+    HELP,
+    (
+        &["--show-secrets"],
+        Value::None,
+        "Show private key material",
+    ),
+];
+
+const GEN_CBOR_FLAGS: &[Flag] = &[
+    HELP,
+    (&["--seed"], Value::Number, "Seed for the items"),
+    (
+        &["-n", "--count"],
+        Value::Number,
+        "Number of items to write",
+    ),
+    (
+        &["--max-depth"],
+        Value::Number,
+        "Most nesting of arrays, maps and tags",
+    ),
+    (
+        &["--max-length"],
+        Value::Number,
+        "Most entries or bytes in an item",
+    ),
+    (
+        &["--preferred"],
+        Value::None,
+        "Only preferred serialization",
+    ),
+    (&["--malformed"], Value::None, "Put a fault in each item"),
+    (
+        &["-o"],
+        Value::File,
+        "Write the items to files in a directory",
+    ),
+];
+
+const CODEGEN_FLAGS: &[Flag] = &[
+    HELP,
+    (&["--name"], Value::Text, "Name of the type for the items"),
+    (
+        &["--cddl"],
+        Value::None,
+        "Write CDDL for CBOR items rather than Rust",
+    ),
+];
+
+// The tools that also run on their own, and the commands they run
+const PROGRAMS: &[(&str, Command)] = &[
+    ("dumpasn1", Command::DumpAsn1),
+    ("dumpcbor", Command::DumpCbor),
+    ("gencbor", Command::GenCbor),
+];
+
+/// What a command's arguments other than options are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operands {
+    Files,
+    Shell,
+    None,
+}
+
+fn operands(command: Command) -> Operands {
+    match command {
+        Command::Completions => Operands::Shell,
+        Command::GenCbor => Operands::None,
+        _ => Operands::Files,
+    }
+}
+
+/// The programs completed, for the scripts' first lines
+fn program_names() -> String {
+    let mut names: Vec<&str> = PROGRAMS.iter().map(|(program, _)| *program).collect();
+    names.insert(0, "asn1cbor");
+    let last = names.pop().unwrap_or_default();
+    format!("{} and {}", names.join(", "), last)
+}
+
+/// The options of a command. Those that run a dumper on each file take the
+/// options of both
+fn flags(command: Command) -> Vec<Flag> {
+    let mut flags: Vec<Flag> = match command {
+        Command::Dump => vec![HELP, JOBS],
+        Command::DumpAsn1 => DUMP_ASN1_FLAGS.to_vec(),
+        Command::DumpCbor => DUMP_CBOR_FLAGS.to_vec(),
+        Command::Diff => [DIFF_FLAGS, DECODER_FLAGS].concat(),
+        Command::Convert => [CONVERT_FLAGS, DECODER_FLAGS].concat(),
+        Command::Lint => [LINT_FLAGS, DECODER_FLAGS].concat(),
+        Command::LintCert => LINT_CERT_FLAGS.to_vec(),
+        Command::Minimize => [MINIMIZE_FLAGS, DECODER_FLAGS].concat(),
+        Command::Mutate => MUTATE_FLAGS.to_vec(),
+        Command::Browse => [BROWSE_FLAGS, DECODER_FLAGS].concat(),
+        Command::GenCbor => GEN_CBOR_FLAGS.to_vec(),
+        Command::Codegen => [CODEGEN_FLAGS, DECODER_FLAGS].concat(),
+        Command::TestVectors | Command::Completions => vec![HELP],
+    };
+    if matches!(command, Command::Dump | Command::Lint | Command::Minimize) {
+        // Where the dumpers share a name, such as -c, the first one's is kept
+        for &(names, value, help) in DUMP_ASN1_FLAGS.iter().chain(DUMP_CBOR_FLAGS) {
+            let taken = |name: &str| flags.iter().any(|(names, _, _)| names.contains(&name));
+            if !names.iter().any(|name| taken(name)) {
+                flags.push((names, value, help));
+                continue;
+            }
+            let new: Vec<Flag> = names
+                .iter()
+                .filter(|name| !taken(name))
+                .map(|name| (std::slice::from_ref(name), value, help))
+                .collect();
+            flags.extend(new);
+        }
+    }
+    flags
+}
+
+/// The names in a list such as "tree, diag, json or html"
+fn choices(names: &str) -> Vec<&str> {
+    names
+        .split(", ")
+        .flat_map(|name| name.split(" or "))
+        .collect()
+}
+
+fn print_help(program_name: &str) {
+    println!("Usage: {} <shell>", program_name);
+    println!("\nPrints a script completing the commands and options of asn1cbor, and the");
+    println!(
+        "options of the tools it runs, in {}. For example:\n",
+        SHELL_NAMES
+    );
+    println!(
+        "  {} bash > ~/.local/share/bash-completion/completions/asn1cbor",
+        program_name
+    );
+    println!("  {} zsh > ~/.zfunc/_asn1cbor", program_name);
+    println!(
+        "  {} fish > ~/.config/fish/completions/asn1cbor.fish",
+        program_name
+    );
+    println!("\nOPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+}
+
+/// Run completions with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let mut shell = None;
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                return Ok(0);
+            }
+            arg if arg.starts_with('-') => usage_error(&format!("Unknown option: {}", arg)),
+            _ if shell.is_some() => usage_error("Only one shell can be given"),
+            arg => shell = Some(arg),
+        }
+    }
+    let script = match shell {
+        Some("bash") => bash(),
+        Some("zsh") => zsh(),
+        Some("fish") => fish(),
+        Some(shell) => usage_error(&format!(
+            "Unsupported shell: {} (use {})",
+            shell, SHELL_NAMES
+        )),
+        None => usage_error("No shell specified"),
+    };
+    io::stdout().write_all(script.as_bytes())?;
+    Ok(0)
+}
+
+/// The name of a shell function for a command, such as _asn1cbor_dump_asn1
+fn function_name(command: Command) -> String {
+    format!("_asn1cbor_{}", command.name().replace('-', "_"))
+}
+
+fn bash() -> String {
+    let mut script = format!(
+        "# bash completion for {}\n# Generated by: asn1cbor completions bash\n",
+        program_names()
+    );
+    for (command, _, _) in COMMANDS {
+        let flags = flags(*command);
+        script.push_str(&format!(
+            "\n{}() {{\n    local cur=${{COMP_WORDS[COMP_CWORD]}} prev=${{COMP_WORDS[COMP_CWORD-1]}}\n    case $prev in\n",
+            function_name(*command)
+        ));
+        let names_with = |wanted: fn(Value) -> bool| {
+            flags
+                .iter()
+                .filter(|(_, value, _)| wanted(*value))
+                .flat_map(|(names, _, _)| names.iter().copied())
+                .collect::<Vec<_>>()
+                .join("|")
+        };
+        let files = names_with(|value| value == Value::File);
+        if !files.is_empty() {
+            script.push_str(&format!(
+                "        {}) COMPREPLY=($(compgen -f -- \"$cur\")); return ;;\n",
+                files
+            ));
+        }
+        for (names, value, _) in &flags {
+            if let Value::Choice(list) = value {
+                script.push_str(&format!(
+                    "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n",
+                    names.join("|"),
+                    choices(list).join(" ")
+                ));
+            }
+        }
+        let others = names_with(|value| {
+            matches!(
+                value,
+                Value::Number | Value::Text | Value::Range | Value::Span | Value::Hex | Value::Time
+            )
+        });
+        if !others.is_empty() {
+            script.push_str(&format!("        {}) return ;;\n", others));
+        }
+        let all: Vec<&str> = flags
+            .iter()
+            .flat_map(|(names, _, _)| names.iter().copied())
+            .collect();
+        let operands = match operands(*command) {
+            Operands::Files => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+            Operands::Shell => format!(
+                "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                choices(SHELL_NAMES).join(" ")
+            ),
+            Operands::None => "COMPREPLY=()".to_string(),
+        };
+        script.push_str(&format!(
+            "    esac\n    if [[ $cur == -* ]]; then\n        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n    else\n        {}\n    fi\n}}\n",
+            all.join(" "),
+            operands
+        ));
+    }
+
+    let names: Vec<&str> = COMMANDS.iter().map(|(_, name, _)| *name).collect();
+    script.push_str(&format!(
+        "\n_asn1cbor() {{\n    local i command=\n    for ((i = 1; i < COMP_CWORD; i++)); do\n        case ${{COMP_WORDS[i]}} in\n            -*) ;;\n            *) command=${{COMP_WORDS[i]}}; break ;;\n        esac\n    done\n    case $command in\n        \"\") COMPREPLY=($(compgen -W \"{} -h --help -V --version\" -- \"${{COMP_WORDS[COMP_CWORD]}}\")) ;;\n",
+        names.join(" ")
+    ));
+    for (command, name, _) in COMMANDS {
+        script.push_str(&format!(
+            "        {}) {} ;;\n",
+            name,
+            function_name(*command)
+        ));
+    }
+    script.push_str("    esac\n}\n\ncomplete -o filenames -F _asn1cbor asn1cbor\n");
+    for (program, command) in PROGRAMS {
+        script.push_str(&format!(
+            "complete -o filenames -F {} {}\n",
+            function_name(*command),
+            program
+        ));
+    }
+    script
+}
+
+/// Text for inside a zsh '...' spec, where [ ] and : also mean something
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh() -> String {
+    let programs: Vec<&str> = PROGRAMS.iter().map(|(program, _)| *program).collect();
+    let mut script = format!(
+        "#compdef asn1cbor {}\n# zsh completion for {}\n# Generated by: asn1cbor completions zsh\n",
+        programs.join(" "),
+        program_names()
+    );
+    for (command, _, _) in COMMANDS {
+        script.push_str(&format!(
+            "\n{}() {{\n    _arguments -s",
+            function_name(*command)
+        ));
+        for (names, value, help) in flags(*command) {
+            let action = match value {
+                Value::None => String::new(),
+                Value::File => ":file:_files".to_string(),
+                Value::Number => ":number: ".to_string(),
+                Value::Text => ":text: ".to_string(),
+                Value::Range => ":range: ".to_string(),
+                Value::Span => ":bytes: ".to_string(),
+                Value::Hex => ":hex: ".to_string(),
+                Value::Time => ":time: ".to_string(),
+                Value::Choice(list) => format!(":value:({})", choices(list).join(" ")),
+            };
+            for name in names {
+                script.push_str(&format!(
+                    " \\\n        '{}[{}]{}'",
+                    name,
+                    zsh_escape(help),
+                    action
+                ));
+            }
+        }
+        match operands(*command) {
+            Operands::Files => script.push_str(" \\\n        '*:file:_files'"),
+            Operands::Shell => script.push_str(&format!(
+                " \\\n        '1:shell:({})'",
+                choices(SHELL_NAMES).join(" ")
+            )),
+            Operands::None => {}
+        }
+        script.push_str("\n}\n");
+    }
+
+    script.push_str(
+        "\n_asn1cbor() {\n    local line state\n    _arguments -C \\\n        \
+         '(- *)'{-h,--help}'[Show the help and exit]' \\\n        \
+         '(- *)'{-V,--version}'[Show the version and exit]' \\\n        \
+         '1:command:->command' \\\n        '*::argument:->argument'\n    \
+         case $state in\n        command)\n            local -a commands\n            commands=(\n",
+    );
+    for (_, name, description) in COMMANDS {
+        script.push_str(&format!(
+            "                '{}:{}'\n",
+            name,
+            zsh_escape(description)
+        ));
+    }
+    script.push_str(
+        "            )\n            _describe command commands\n            ;;\n        \
+         argument)\n            case $line[1] in\n",
+    );
+    for (command, name, _) in COMMANDS {
+        script.push_str(&format!(
+            "                {}) {} ;;\n",
+            name,
+            function_name(*command)
+        ));
+    }
+    script.push_str("            esac\n            ;;\n    esac\n}\n\ncase $service in\n");
+    for (program, command) in PROGRAMS {
+        script.push_str(&format!(
+            "    {}) {} \"$@\" ;;\n",
+            program,
+            function_name(*command)
+        ));
+    }
+    script.push_str("    *) _asn1cbor \"$@\" ;;\nesac\n");
+    script
+}
+
+/// Text for inside a fish '...' string
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// A fish complete command for one option of `program`, under `condition`
+fn fish_flag(program: &str, condition: &str, (names, value, help): &Flag) -> String {
+    let mut line = format!("complete -c {}", program);
+    if !condition.is_empty() {
+        line.push_str(&format!(" -n '{}'", condition));
+    }
+    for name in *names {
+        let option = match name.strip_prefix("--") {
+            Some(long) => format!(" -l {}", long),
+            None if name.len() == 2 => format!(" -s {}", &name[1..]),
+            None => format!(" -o {}", &name[1..]),
+        };
+        line.push_str(&option);
+    }
+    match value {
+        Value::None => {}
+        Value::File => line.push_str(" -r -F"),
+        Value::Number | Value::Text | Value::Range | Value::Span | Value::Hex | Value::Time => {
+            line.push_str(" -x")
+        }
+        Value::Choice(list) => line.push_str(&format!(" -x -a '{}'", choices(list).join(" "))),
+    }
+    line.push_str(&format!(" -d '{}'\n", fish_escape(help)));
+    line
+}
+
+fn fish() -> String {
+    let mut script = format!(
+        "# fish completion for {}\n\
+         # Generated by: asn1cbor completions fish\n\n\
+         complete -c asn1cbor -n __fish_use_subcommand -f\n\
+         complete -c asn1cbor -n __fish_use_subcommand -s h -l help -d 'Show the help and exit'\n\
+         complete -c asn1cbor -n __fish_use_subcommand -s V -l version -d 'Show the version and exit'\n",
+        program_names()
+    );
+    for (command, name, description) in COMMANDS {
+        script.push_str(&format!(
+            "complete -c asn1cbor -n __fish_use_subcommand -a {} -d '{}'\n",
+            name,
+            fish_escape(description)
+        ));
+        let condition = format!("__fish_seen_subcommand_from {}", name);
+        match operands(*command) {
+            Operands::Files => {}
+            Operands::Shell => script.push_str(&format!(
+                "complete -c asn1cbor -n '{}' -f -a '{}'\n",
+                condition,
+                choices(SHELL_NAMES).join(" ")
+            )),
+            Operands::None => {
+                script.push_str(&format!("complete -c asn1cbor -n '{}' -f\n", condition));
+            }
+        }
+        for flag in flags(*command) {
+            script.push_str(&fish_flag("asn1cbor", &condition, &flag));
+        }
+    }
+    for (program, command) in PROGRAMS {
+        script.push('\n');
+        if operands(*command) == Operands::None {
+            script.push_str(&format!("complete -c {} -f\n", program));
+        }
+        for flag in flags(*command) {
+            script.push_str(&fish_flag(program, "", &flag));
+        }
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::dumper::Dumper;
+    use crate::dumpasn1::Asn1Dumper;
+    use crate::dumpcbor::CborDumper;
+
+    /// Arguments giving a dumper `flag`, with a value if it takes one
+    fn flag_args(name: &str, value: Value) -> Vec<String> {
+        let mut args = vec!["prog".to_string(), name.to_string()];
+        match value {
+            Value::None => {}
+            Value::File => args.push("/dev/null".to_string()),
+            Value::Number => args.push("1".to_string()),
+            Value::Text => args.push("text".to_string()),
+            Value::Range => args.push("0..1".to_string()),
+            Value::Span => args.push("0:1".to_string()),
+            Value::Hex => args.push("00".to_string()),
+            Value::Time => args.push("2024-06-01".to_string()),
+            Value::Choice(list) => args.push(choices(list)[0].to_string()),
+        }
+        args.push("input".to_string());
+        args
+    }
+
+    #[test]
+    fn test_dumper_flags_are_accepted() {
+        for (flags, input_positions) in [
+            (
+                DUMP_ASN1_FLAGS,
+                Asn1Dumper::input_positions as fn(&[String]) -> _,
+            ),
+            (DUMP_CBOR_FLAGS, CborDumper::input_positions),
+        ] {
+            // Digests need the crypto feature
+            let digests = |value: &Value| matches!(value, Value::Choice(DIGEST_NAMES));
+            for (names, value, _) in flags
+                .iter()
+                .filter(|flag| flag.0 != HELP.0 && (cfg!(feature = "crypto") || !digests(&flag.1)))
+            {
+                for name in names.iter() {
+                    let args = flag_args(name, *value);
+                    assert!(input_positions(&args).is_ok(), "{:?}", args);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_choices() {
+        assert_eq!(choices(Format::NAMES), ["tree", "diag", "json", "html"]);
+        assert_eq!(choices(SHELL_NAMES), ["bash", "zsh", "fish"]);
+        assert_eq!(choices(TARGET_NAMES).len(), 8);
+        for name in choices(ColorChoice::NAMES) {
+            assert!(ColorChoice::from_name(name).is_some(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_scripts() {
+        let scripts = [bash(), zsh(), fish()];
+        for script in &scripts {
+            for (_, name, _) in COMMANDS {
+                assert!(script.contains(name), "{}", name);
+            }
+            assert!(script.contains("oid-config"));
+            assert!(script.contains("gencbor") && script.contains("max-depth"));
+        }
+        let lint = flags(Command::Lint);
+        assert_eq!(lint.iter().filter(|flag| flag.0.contains(&"-c")).count(), 1);
+        assert!(lint.iter().any(|flag| flag.0 == ["--compact"]));
+        assert!(scripts[0].contains(
+            "        --format) COMPREPLY=($(compgen -W \"tree diag json html\" -- \"$cur\")); return ;;\n"
+        ));
+        assert!(scripts[1]
+            .contains("'--color[When to color errors and warnings]:value:(auto always never)'"));
+        assert!(scripts[2].contains(
+            "complete -c dumpasn1 -o dd -d 'Dump the hex header and the start of the content'\n"
+        ));
+    }
+}
@@ -0,0 +1,444 @@
+// The config file, ~/.config/asn1-cbor-tools/config.toml, with defaults for
+// the options that would otherwise be repeated on every run:
+//
+//     color = "auto"                 # --color for the dumpers and lint
+//     width = 100                    # dump-asn1's --width
+//     oid-config = "~/dumpasn1.cfg"  # Names for more OIDs, for every command
+//
+//     [dump-asn1]                    # Options for one command
+//     flags = ["--oid-info", "--shallow-indent"]
+//
+// The environment can override the file's settings: ASN1CBOR_COLOR,
+// ASN1CBOR_WIDTH and, as for the original dumpasn1, DUMPASN1_CFG naming the
+// OID config. The options these make go before those on the command line,
+// so the command line overrides them all. A dumper's section applies wherever that
+// dumper runs, including under dump and lint. Only as much of TOML is read
+// as this needs: comments, [sections], strings, integers and arrays of
+// strings.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::COMMANDS;
+use crate::asn1::oids;
+use crate::common::args::parse_number;
+use crate::common::color::ColorChoice;
+use crate::error::DumpError;
+
+// The settings read when the tool started, if there's a config file
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// What the config file says
+#[derive(Debug, Default, PartialEq)]
+struct Settings {
+    color: Option<String>,
+    width: Option<usize>,
+    oid_config: Option<String>,
+    flags: Vec<(String, Vec<String>)>, // Each command's default options
+}
+
+impl Settings {
+    /// Replace the settings that environment variables are set for, looking
+    /// them up with `var`. An empty variable counts as unset
+    fn override_from(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        let var = |name| var(name).filter(|value| !value.is_empty());
+        if let Some(color) = var("ASN1CBOR_COLOR") {
+            self.color = Some(check_color(color).map_err(|e| format!("ASN1CBOR_COLOR: {}", e))?);
+        }
+        if let Some(width) = var("ASN1CBOR_WIDTH") {
+            self.width =
+                Some(parse_number(&width, "width").map_err(|e| format!("ASN1CBOR_WIDTH: {}", e))?);
+        }
+        if let Some(path) = var("DUMPASN1_CFG") {
+            self.oid_config = Some(path);
+        }
+        Ok(())
+    }
+
+    /// The options from the file for `command`, to go before its own
+    fn default_args(&self, command: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(color) = &self.color {
+            if matches!(command, "dump-asn1" | "dump-cbor" | "lint") {
+                args.extend(["--color".to_string(), color.clone()]);
+            }
+        }
+        if let (Some(width), "dump-asn1") = (self.width, command) {
+            args.extend(["--width".to_string(), width.to_string()]);
+        }
+        for (name, flags) in &self.flags {
+            if name == command {
+                args.extend(flags.iter().cloned());
+            }
+        }
+        args
+    }
+}
+
+/// Read the config file, if there is one, and the environment variables
+/// overriding it, for the commands run from now on
+pub(crate) fn install() -> io::Result<()> {
+    let mut settings = match path().filter(|path| path.is_file()) {
+        Some(path) => read(&path)?,
+        None => Settings::default(),
+    };
+    settings
+        .override_from(|name| env::var(name).ok())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if let Some(oid_config) = &settings.oid_config {
+        oids::load_config(&expand_home(oid_config))?;
+    }
+    // Only ever installed once, at the start
+    let _ = SETTINGS.set(settings);
+    Ok(())
+}
+
+fn read(path: &Path) -> io::Result<Settings> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| DumpError::io(format!("Can't read config file '{}'", path.display()), e))?;
+    parse(&text).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Config file '{}': {}", path.display(), e),
+        )
+    })
+}
+
+/// `args` for `command` with the options from the config file and the
+/// environment put after the program name, and how many there are
+pub(crate) fn with_defaults(command: &str, args: &[String]) -> (Vec<String>, usize) {
+    let defaults = SETTINGS
+        .get()
+        .map_or_else(Vec::new, |settings| settings.default_args(command));
+    let count = defaults.len();
+    let mut with_defaults = args[..1].to_vec();
+    with_defaults.extend(defaults);
+    with_defaults.extend_from_slice(&args[1..]);
+    (with_defaults, count)
+}
+
+/// Where the config file is: under $XDG_CONFIG_HOME if that's set, and
+/// otherwise under ~/.config
+fn path() -> Option<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("asn1-cbor-tools").join("config.toml"))
+}
+
+/// A path with a leading ~/ standing for the home directory
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home.trim_end_matches('/'), rest),
+        _ => path.to_string(),
+    }
+}
+
+/// A value in the file
+#[derive(Debug, PartialEq)]
+enum Value {
+    String(String),
+    Integer(i64),
+    Strings(Vec<String>),
+}
+
+fn parse(text: &str) -> Result<Settings, String> {
+    let mut settings = Settings::default();
+    let mut section: Option<String> = None;
+    let mut lines = text.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let at_line = |e: String| format!("line {}: {}", index + 1, e);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = without_comment(name)
+                .trim_end()
+                .strip_suffix(']')
+                .ok_or_else(|| at_line("unclosed [section]".to_string()))?
+                .trim();
+            if !COMMANDS.iter().any(|(_, command, _)| *command == name) {
+                return Err(at_line(format!("unknown command [{}]", name)));
+            }
+            section = Some(name.to_string());
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| at_line("expected key = value".to_string()))?;
+        let key = key.trim().trim_matches('"');
+        // An array can go on over several lines
+        let mut value = value.trim().to_string();
+        while value.starts_with('[') && !array_closed(&value) {
+            let (_, line) = lines
+                .next()
+                .ok_or_else(|| at_line("unclosed array".to_string()))?;
+            value.push('\n');
+            value.push_str(line);
+        }
+        let value = parse_value(&value).map_err(at_line)?;
+
+        match (section.as_deref(), key, value) {
+            (None, "color", Value::String(color)) => {
+                settings.color = Some(check_color(color).map_err(at_line)?);
+            }
+            (None, "width", Value::Integer(width)) => {
+                settings.width = Some(
+                    usize::try_from(width)
+                        .map_err(|_| at_line(format!("width can't be {}", width)))?,
+                );
+            }
+            (None, "oid-config", Value::String(path)) => settings.oid_config = Some(path),
+            (Some(command), "flags", Value::Strings(flags)) => {
+                settings.flags.push((command.to_string(), flags));
+            }
+            (None, "color" | "oid-config", _) => {
+                return Err(at_line(format!("{} must be a string", key)));
+            }
+            (None, "width", _) => return Err(at_line("width must be a number".to_string())),
+            (Some(_), "flags", _) => {
+                return Err(at_line("flags must be an array of strings".to_string()))
+            }
+            (None, _, _) => return Err(at_line(format!("unknown setting '{}'", key))),
+            (Some(command), _, _) => {
+                return Err(at_line(format!(
+                    "unknown setting '{}' for {}",
+                    key, command
+                )))
+            }
+        }
+    }
+    Ok(settings)
+}
+
+/// A --color value, if it's one of those allowed
+fn check_color(color: String) -> Result<String, String> {
+    match ColorChoice::from_name(&color) {
+        Some(_) => Ok(color),
+        None => Err(format!(
+            "color must be {}, not '{}'",
+            ColorChoice::NAMES,
+            color
+        )),
+    }
+}
+
+/// The text before any # comment that isn't inside a string
+fn without_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), _) if c == open && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &text[..index],
+            _ => {}
+        }
+        escaped = false;
+    }
+    text
+}
+
+/// Whether an array's closing ] ends the last line of `text`
+fn array_closed(text: &str) -> bool {
+    text.lines()
+        .last()
+        .is_some_and(|line| without_comment(line).trim_end().ends_with(']'))
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    let text = text
+        .lines()
+        .map(without_comment)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim();
+    if let Some(items) = text.strip_prefix('[') {
+        let items = items
+            .strip_suffix(']')
+            .ok_or_else(|| "unclosed array".to_string())?;
+        let mut strings = Vec::new();
+        let mut rest = items.trim_start();
+        while !rest.is_empty() {
+            let (string, after) = parse_string(rest)?;
+            strings.push(string);
+            rest = after.trim_start();
+            match rest.strip_prefix(',') {
+                Some(after) => rest = after.trim_start(),
+                None if rest.is_empty() => {}
+                None => return Err(format!("expected , in array, found '{}'", rest)),
+            }
+        }
+        return Ok(Value::Strings(strings));
+    }
+    if text.starts_with(['"', '\'']) {
+        let (string, rest) = parse_string(text)?;
+        if !rest.trim().is_empty() {
+            return Err(format!("unexpected '{}' after string", rest.trim()));
+        }
+        return Ok(Value::String(string));
+    }
+    text.replace('_', "")
+        .parse()
+        .map(Value::Integer)
+        .map_err(|_| format!("can't read value '{}'", text))
+}
+
+/// A string at the start of `text`, "basic" with escapes or 'literal'
+/// without, and what follows it
+fn parse_string(text: &str) -> Result<(String, &str), String> {
+    let mut chars = text.char_indices();
+    let quote = match chars.next() {
+        Some((_, quote @ ('"' | '\''))) => quote,
+        _ => return Err(format!("expected a string, found '{}'", text)),
+    };
+    let mut string = String::new();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            _ if c == quote => return Ok((string, &text[index + 1..])),
+            '\n' => break,
+            '\\' if quote == '"' => {
+                let escaped = match chars.next() {
+                    Some((_, 'n')) => '\n',
+                    Some((_, 't')) => '\t',
+                    Some((_, 'r')) => '\r',
+                    Some((_, c @ ('"' | '\\'))) => c,
+                    Some((_, 'u')) => {
+                        let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                        u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("bad escape \\u{}", hex))?
+                    }
+                    _ => return Err("bad escape in string".to_string()),
+                };
+                string.push(escaped);
+            }
+            _ => string.push(c),
+        }
+    }
+    Err("unclosed string".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "# Defaults\n\
+                    color = \"always\"  # even into a pipe\n\
+                    width = 100\n\
+                    oid-config = '~/dumpasn1.cfg'\n\
+                    \n\
+                    [dump-asn1]\n\
+                    flags = [\"--oid-info\", \"-i\"]\n\
+                    \n\
+                    [lint]\n\
+                    flags = [\n\
+                    \x20   \"--strict\",  # every error is fatal\n\
+                    \x20   \"--max-depth\", \"#64\",\n\
+                    ]\n";
+        let settings = parse(text).unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                color: Some("always".to_string()),
+                width: Some(100),
+                oid_config: Some("~/dumpasn1.cfg".to_string()),
+                flags: vec![
+                    (
+                        "dump-asn1".to_string(),
+                        vec!["--oid-info".to_string(), "-i".to_string()]
+                    ),
+                    (
+                        "lint".to_string(),
+                        vec![
+                            "--strict".to_string(),
+                            "--max-depth".to_string(),
+                            "#64".to_string()
+                        ]
+                    ),
+                ],
+            }
+        );
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            settings.default_args("dump-asn1"),
+            args(&["--color", "always", "--width", "100", "--oid-info", "-i"])
+        );
+        assert_eq!(
+            settings.default_args("dump-cbor"),
+            args(&["--color", "always"])
+        );
+        assert_eq!(settings.default_args("diff"), args(&[]));
+    }
+
+    #[test]
+    fn test_environment_overrides() {
+        let mut settings = parse("color = \"never\"\nwidth = 100\n").unwrap();
+        let environment = |vars: &'static [(&str, &str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        settings
+            .override_from(environment(&[
+                ("ASN1CBOR_COLOR", "always"),
+                ("ASN1CBOR_WIDTH", ""),
+                ("DUMPASN1_CFG", "/etc/dumpasn1.cfg"),
+            ]))
+            .unwrap();
+        assert_eq!(settings.color.as_deref(), Some("always"));
+        assert_eq!(settings.width, Some(100));
+        assert_eq!(settings.oid_config.as_deref(), Some("/etc/dumpasn1.cfg"));
+
+        let err = settings
+            .override_from(environment(&[("ASN1CBOR_WIDTH", "wide")]))
+            .unwrap_err();
+        assert!(err.starts_with("ASN1CBOR_WIDTH: "), "{}", err);
+        let err = settings
+            .override_from(environment(&[("ASN1CBOR_COLOR", "yes")]))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "ASN1CBOR_COLOR: color must be auto, always or never, not 'yes'"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text: &str| parse(text).unwrap_err();
+        assert_eq!(
+            error("colour = \"auto\""),
+            "line 1: unknown setting 'colour'"
+        );
+        assert_eq!(
+            error("color = \"sometimes\""),
+            "line 1: color must be auto, always or never, not 'sometimes'"
+        );
+        assert_eq!(error("\n[dumpasn1]"), "line 2: unknown command [dumpasn1]");
+        assert_eq!(error("width = \"wide\""), "line 1: width must be a number");
+        assert_eq!(
+            error("[lint]\nflags = \"--strict\""),
+            "line 2: flags must be an array of strings"
+        );
+        assert_eq!(
+            error("[lint]\nflags = [\"--strict\""),
+            "line 2: unclosed array"
+        );
+        assert_eq!(error("oid-config = \"x"), "line 1: unclosed string");
+    }
+}
@@ -0,0 +1,362 @@
+// asn1cbor convert: write the items in a file in another form. ASN.1 moves
+// between DER and PEM, CBOR is re-encoded with preferred or canonical
+// serialization, and either can be written in one of the --format outputs.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use super::{decoder_option, file_nodes, read_input, usage_error, Decoder};
+use crate::asn1::{der, pem, templates};
+use crate::cbor::{self, CborEncoder, CborItem};
+use crate::common::args::option_value;
+use crate::diagnostics::EXIT_CLEAN;
+use crate::error::DumpError;
+use crate::render::{self, Content, Format, Node};
+
+/// What the input is converted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Der,
+    Pem,
+    Cbor { canonical: bool },
+    Rendered(Format),
+}
+
+pub(super) const TARGET_NAMES: &str = "der, pem, cbor, canonical-cbor, tree, diag, json or html";
+
+impl Target {
+    fn from_name(name: &str) -> Option<Target> {
+        match name {
+            "der" => Some(Target::Der),
+            "pem" => Some(Target::Pem),
+            "cbor" => Some(Target::Cbor { canonical: false }),
+            "canonical-cbor" => Some(Target::Cbor { canonical: true }),
+            _ => Format::from_name(name).map(Target::Rendered),
+        }
+    }
+}
+
+/// Options for convert
+#[derive(Debug)]
+struct Options {
+    target: Target,
+    decoder: Option<Decoder>, // None to detect it
+    label: Option<String>,
+    show_secrets: bool,
+    strip_self_describe: bool, // Drop a leading tag 55799
+    output_file: Option<String>,
+    input_file: String,
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        "Usage: {} --to <format> [OPTIONS] <input_file>",
+        program_name
+    );
+    println!("\nWrites the items in a file in another form. Whether the input is ASN.1 or");
+    println!("CBOR is detected unless --asn1 or --cbor says.\n");
+    println!("FORMATS:");
+    println!("  der                     DER, from PEM blocks or base64");
+    println!("  pem                     PEM, with a block for each DER item");
+    println!("  cbor                    CBOR re-encoded with preferred serialization");
+    println!("  canonical-cbor          CBOR re-encoded with map keys in canonical order");
+    println!("  tree, diag, json, html  The outputs of --format");
+    println!("\nOPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  --to <format>           Format to write (required)");
+    println!("  --asn1                  Decode the input as ASN.1 DER, BER or PEM");
+    println!("  --cbor                  Decode the input as CBOR");
+    println!("  --auto                  Detect what the input is (the default)");
+    println!("  --label <label>         PEM label, for items that aren't recognized");
+    println!("  --show-secrets          Show private key material in the --format outputs");
+    println!("  --strip-self-describe   Leave out the self-described CBOR tag (55799) that");
+    println!("                          CBOR input starts with, writing only what it tags");
+    println!("  -o <file>               Write to <file> instead of stdout");
+}
+
+fn parse_args_from(args: &[String]) -> Result<Options, String> {
+    let mut target = None;
+    let mut decoder = None;
+    let mut label = None;
+    let mut show_secrets = false;
+    let mut strip_self_describe = false;
+    let mut output_file = None;
+    let mut input_file: Option<String> = None;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                std::process::exit(0);
+            }
+            "--to" => {
+                let name = option_value(args, &mut i)?;
+                target =
+                    Some(Target::from_name(name).ok_or_else(|| {
+                        format!("Unknown format: {} (use {})", name, TARGET_NAMES)
+                    })?);
+            }
+            arg @ ("--asn1" | "--cbor" | "--auto") => decoder = decoder_option(arg),
+            "--label" => label = Some(option_value(args, &mut i)?.to_string()),
+            "--show-secrets" => show_secrets = true,
+            "--strip-self-describe" => strip_self_describe = true,
+            "-o" => output_file = Some(option_value(args, &mut i)?.to_string()),
+            arg if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            arg => {
+                if let Some(existing) = &input_file {
+                    return Err(format!(
+                        "Multiple input files specified: {} and {}",
+                        existing, arg
+                    ));
+                }
+                input_file = Some(arg.to_string());
+            }
+        }
+        i += 1;
+    }
+
+    let target = target.ok_or("No output format given with --to")?;
+    if strip_self_describe && matches!(target, Target::Der | Target::Pem) {
+        return Err("--strip-self-describe needs CBOR input".to_string());
+    }
+    if let Some(decoder) = decoder {
+        check_decoder(target, decoder)?;
+        check_strip(strip_self_describe, decoder)?;
+    }
+    Ok(Options {
+        target,
+        decoder,
+        label,
+        show_secrets,
+        strip_self_describe,
+        output_file,
+        input_file: input_file.ok_or("No input file specified")?,
+    })
+}
+
+/// Check that the input can be written as `target`
+fn check_decoder(target: Target, decoder: Decoder) -> Result<(), String> {
+    match (target, decoder) {
+        (Target::Der | Target::Pem, Decoder::Cbor) => {
+            Err("DER and PEM output need ASN.1 input".to_string())
+        }
+        (Target::Cbor { .. }, Decoder::Asn1) => Err("CBOR output needs CBOR input".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Check that --strip-self-describe, if given, has CBOR input
+fn check_strip(strip_self_describe: bool, decoder: Decoder) -> Result<(), String> {
+    if strip_self_describe && decoder == Decoder::Asn1 {
+        return Err("--strip-self-describe needs CBOR input".to_string());
+    }
+    Ok(())
+}
+
+/// Run convert with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let options = parse_args_from(args).unwrap_or_else(|e| usage_error(&e));
+    let (decoder, data) = read_input(&options.input_file, options.decoder)?;
+    check_decoder(options.target, decoder)
+        .and_then(|()| check_strip(options.strip_self_describe, decoder))
+        .map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+    let mut out: Box<dyn Write> = match &options.output_file {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).map_err(|e| {
+                DumpError::io(format!("Can't create '{}'", path), e)
+            })?))
+        }
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    match options.target {
+        Target::Der => {
+            for (_, der) in der_items(&data)? {
+                out.write_all(&der)?;
+            }
+        }
+        Target::Pem => {
+            for (label, der) in der_items(&data)? {
+                let label = match options.label.as_deref().or(label.as_deref()) {
+                    Some(label) => label.to_string(),
+                    None => pem_label(&der)?.to_string(),
+                };
+                out.write_all(pem::encode_pem(&label, &der).as_bytes())?;
+            }
+        }
+        Target::Cbor { canonical } => {
+            let encoder = CborEncoder::new().canonical(canonical);
+            let mut items = cbor::parse_slice(&data)?;
+            if options.strip_self_describe {
+                strip_self_describe(&mut items);
+            }
+            for item in items {
+                out.write_all(&encoder.encode(&item.value))?;
+            }
+        }
+        Target::Rendered(format) => {
+            let mut nodes = file_nodes(&data, decoder, options.show_secrets)?;
+            if options.strip_self_describe {
+                strip_self_describe_node(&mut nodes);
+            }
+            render::render(format.renderer().as_mut(), &mut out, &nodes)?;
+        }
+    }
+    out.flush()?;
+    Ok(EXIT_CLEAN)
+}
+
+/// Replace a first item tagged as self-described CBOR with what it tags
+fn strip_self_describe(items: &mut [CborItem]) {
+    if let Some(first) = items.first_mut() {
+        if let Some(tagged_item) = first.self_described() {
+            *first = tagged_item.clone();
+        }
+    }
+}
+
+/// The same for the nodes of a CBOR file, keeping the tagged item's offset
+fn strip_self_describe_node(nodes: &mut [Node]) {
+    if let Some(first) = nodes.first_mut() {
+        if let Content::Tagged(cbor::TAG_SELF_DESCRIBE, tagged_node) = &first.content {
+            *first = (**tagged_node).clone();
+        }
+    }
+}
+
+/// The DER items in ASN.1 input, with their PEM labels if it's PEM. DER
+/// input is split into its top-level items.
+fn der_items(data: &[u8]) -> io::Result<Vec<(Option<String>, Vec<u8>)>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    if pem::looks_like_pem(data) {
+        return pem::parse_pem(data)
+            .into_iter()
+            .map(|block| match block.data {
+                Ok(der) => Ok((Some(block.label), der)),
+                Err(msg) => Err(invalid(format!("can't decode block: {}", msg))),
+            })
+            .collect();
+    }
+
+    let mut items = Vec::new();
+    let mut end = 0;
+    for (offset, tlv) in der::children(data) {
+        end = offset + tlv.total_len();
+        items.push((None, data[offset..end].to_vec()));
+    }
+    if end < data.len() {
+        return Err(invalid(format!(
+            "Item at offset {} isn't definite-length DER",
+            end
+        )));
+    }
+    Ok(items)
+}
+
+/// PEM label for a recognized structure
+fn pem_label(der: &[u8]) -> io::Result<&'static str> {
+    templates::recognize(der)
+        .and_then(|template| pem::label_for_template(template.name))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Can't tell what PEM label to use; give one with --label",
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::DerBuilder;
+
+    fn args(slice: &[&str]) -> Vec<String> {
+        slice.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args_from(&args(&["prog", "--to", "pem", "a.der"])).unwrap();
+        assert_eq!(options.target, Target::Pem);
+        assert_eq!(options.input_file, "a.der");
+        let options = parse_args_from(&args(&["prog", "--cbor", "--to", "diag", "a"])).unwrap();
+        assert_eq!(options.target, Target::Rendered(Format::Diag));
+
+        let err = parse_args_from(&args(&["prog", "--to", "xml", "a"])).unwrap_err();
+        assert_eq!(
+            err,
+            "Unknown format: xml (use der, pem, cbor, canonical-cbor, tree, diag, json or html)"
+        );
+        assert!(parse_args_from(&args(&["prog", "a"])).is_err());
+        assert_eq!(
+            parse_args_from(&args(&["prog", "--to", "cbor", "a"]))
+                .unwrap()
+                .decoder,
+            None
+        );
+        assert!(parse_args_from(&args(&["prog", "--asn1", "--to", "cbor", "a"])).is_err());
+        assert!(parse_args_from(&args(&["prog", "--cbor", "--to", "der", "a"])).is_err());
+        assert!(parse_args_from(&args(&[
+            "prog",
+            "--strip-self-describe",
+            "--to",
+            "pem",
+            "a"
+        ]))
+        .is_err());
+        assert!(parse_args_from(&args(&[
+            "prog",
+            "--asn1",
+            "--strip-self-describe",
+            "--to",
+            "json",
+            "a"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_strip_self_describe() {
+        // 55799([1]), 55799(2)
+        let data = [0xD9, 0xD9, 0xF7, 0x81, 0x01, 0xD9, 0xD9, 0xF7, 0x02];
+        let mut items = cbor::parse_slice(&data).unwrap();
+        strip_self_describe(&mut items);
+        let encoder = CborEncoder::new();
+        assert_eq!(encoder.encode(&items[0].value), [0x81, 0x01]);
+        // Only the tag the input starts with is the input's
+        assert_eq!(encoder.encode(&items[1].value), data[5..]);
+
+        let mut nodes = cbor::to_nodes(&cbor::parse_slice(&data).unwrap());
+        strip_self_describe_node(&mut nodes);
+        assert_eq!(nodes[0].kind, "array");
+        assert_eq!(nodes[0].offset, Some(3));
+        assert_eq!(nodes[1].kind, "tag");
+    }
+
+    #[test]
+    fn test_der_items() {
+        let first = DerBuilder::new().sequence(|b| b.integer(1)).finish();
+        let second = DerBuilder::new().null().finish();
+        let data = [first.clone(), second.clone()].concat();
+        assert_eq!(
+            der_items(&data).unwrap(),
+            [(None, first.clone()), (None, second)]
+        );
+
+        let text = pem::encode_pem("CERTIFICATE", &first);
+        assert_eq!(
+            der_items(text.as_bytes()).unwrap(),
+            [(Some("CERTIFICATE".to_string()), first)]
+        );
+        assert!(der_items(&[0x30, 0x80, 0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_pem_label() {
+        let key = DerBuilder::new()
+            .sequence(|b| b.integer(1).octet_string(&[0xAA, 0xBB]))
+            .finish();
+        assert_eq!(pem_label(&key).unwrap(), "EC PRIVATE KEY");
+        assert!(pem_label(&DerBuilder::new().null().finish()).is_err());
+    }
+}
@@ -0,0 +1,647 @@
+// Telling what kind of input a file holds, for --auto. ASN.1 is tried first,
+// walking the TLVs and noting whether any length is indefinite (BER) or all
+// are definite (DER); then base64 of either format; then ASN.1 cut short, as
+// a truncated certificate is, for the dumper to report where; then CBOR.
+// Random bytes are rarely a consistent set of nested TLV lengths, while
+// plenty of them parse as some CBOR item, so CBOR is what's left once the
+// others fail. A gzipped or zstd-compressed file is decompressed before any
+// of that.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+
+use super::inputs::{is_single_file, split_inputs};
+use super::{batch, jobs_option, read_data, read_file, usage_error};
+use crate::asn1::pem;
+use crate::asn1::tags::{CONSTRUCTED, FORM_MASK, LEN_MASK, LEN_XTND, TAG_MASK};
+use crate::cbor;
+use crate::common::compressed::Compression;
+use crate::common::dumper::{self, Dumper};
+use crate::common::jobs;
+use crate::diagnostics::Diagnostic;
+use crate::dumpasn1::{self, Asn1Dumper};
+use crate::dumpcbor::{self, CborDumper};
+use crate::error::DumpError;
+use crate::output::Output;
+
+/// How much of a file is read to tell what it is
+const SNIFF_LEN: u64 = 64 * 1024;
+
+/// Nesting depth beyond which input isn't taken for ASN.1
+const MAX_SNIFF_DEPTH: usize = 64;
+
+/// Shorter text strings aren't taken for base64, as too many words would be
+const MIN_EMBEDDED_BASE64: usize = 8;
+
+/// The decoder to use for an input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decoder {
+    Asn1,
+    Cbor,
+}
+
+/// What kind of encoding an input holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Der,
+    Ber,
+    Pem,
+    Cbor,
+}
+
+impl InputKind {
+    pub fn decoder(self) -> Decoder {
+        match self {
+            InputKind::Cbor => Decoder::Cbor,
+            _ => Decoder::Asn1,
+        }
+    }
+}
+
+/// The result of sniffing an input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detected {
+    pub kind: InputKind,
+    pub base64: bool, // The encoding is wrapped in bare base64
+}
+
+impl fmt::Display for Detected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.kind {
+            InputKind::Der => "DER",
+            InputKind::Ber => "BER",
+            InputKind::Pem => "PEM",
+            InputKind::Cbor => "CBOR",
+        };
+        if self.base64 {
+            write!(f, "base64-encoded {}", name)
+        } else {
+            f.write_str(name)
+        }
+    }
+}
+
+/// Work out what `data` holds. `complete` is false when it's only the start
+/// of the input, so that items running off its end are allowed
+pub fn detect(data: &[u8], complete: bool) -> Option<Detected> {
+    if pem::looks_like_pem(data) {
+        return Some(Detected {
+            kind: InputKind::Pem,
+            base64: false,
+        });
+    }
+    if let Some(kind) = asn1_kind(data, complete) {
+        return Some(Detected {
+            kind,
+            base64: false,
+        });
+    }
+    // Checked before CBOR, as base64 text is often a valid run of CBOR
+    // strings too
+    if let Some(kind) =
+        decode_bare_base64(data, complete).and_then(|decoded| binary_kind(&decoded, complete))
+    {
+        return Some(Detected { kind, base64: true });
+    }
+    if is_cbor(data, complete) {
+        return Some(Detected {
+            kind: InputKind::Cbor,
+            base64: false,
+        });
+    }
+    // Only a guess, for input that's neither complete ASN.1 nor CBOR:
+    // checked after base64, whose text can read as a TLV cut short, and
+    // after CBOR, whose items often read as one too
+    if let Some(kind) = truncated_asn1_kind(data, complete) {
+        return Some(Detected {
+            kind,
+            base64: false,
+        });
+    }
+    None
+}
+
+/// What a text string holds if it's base64 of DER, BER or CBOR, or PEM, and
+/// the encoding, decoded from its base64, for dumpcbor to dump nested in it
+pub fn embedded(text: &str) -> Option<(Detected, Vec<u8>)> {
+    if pem::looks_like_pem(text.as_bytes()) {
+        let mut der = Vec::new();
+        for block in pem::parse_pem(text.as_bytes()) {
+            der.extend(block.data.ok()?);
+        }
+        asn1_kind(&der, true)?;
+        let kind = InputKind::Pem;
+        return Some((
+            Detected {
+                kind,
+                base64: false,
+            },
+            der,
+        ));
+    }
+    if text.trim().len() < MIN_EMBEDDED_BASE64 {
+        return None;
+    }
+    let decoded = decode_bare_base64(text.as_bytes(), true)?;
+    let kind = binary_kind(&decoded, true)?;
+    Some((Detected { kind, base64: true }, decoded))
+}
+
+/// Tell DER, BER and CBOR apart
+fn binary_kind(data: &[u8], complete: bool) -> Option<InputKind> {
+    asn1_kind(data, complete).or_else(|| is_cbor(data, complete).then_some(InputKind::Cbor))
+}
+
+fn asn1_kind(data: &[u8], complete: bool) -> Option<InputKind> {
+    match walk_asn1(data, complete)? {
+        true => Some(InputKind::Der),
+        false => Some(InputKind::Ber),
+    }
+}
+
+/// The kind of ASN.1 complete data holds when it's cut short, so that the
+/// dumper can report where
+fn truncated_asn1_kind(data: &[u8], complete: bool) -> Option<InputKind> {
+    if !complete || !cut_short(data) {
+        return None;
+    }
+    let mut definite = true;
+    match walk(data, 0, Some(data.len()), false, 0, &mut definite) {
+        Err(Stop::Truncated) if definite => Some(InputKind::Der),
+        Err(Stop::Truncated) => Some(InputKind::Ber),
+        _ => None,
+    }
+}
+
+fn is_cbor(data: &[u8], complete: bool) -> bool {
+    match cbor::parse_slice(data) {
+        Ok(items) => !items.is_empty(),
+        Err(e) => !complete && e.is_truncated(),
+    }
+}
+
+/// Decode text that's nothing but base64 and whitespace. A partial input is
+/// cut back to a whole number of base64 quads first
+fn decode_bare_base64(data: &[u8], complete: bool) -> Option<Vec<u8>> {
+    let is_base64 = |b: &u8| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'=');
+    if !data.iter().all(|b| is_base64(b) || b.is_ascii_whitespace()) {
+        return None;
+    }
+    let mut text: String = data
+        .iter()
+        .filter(|b| is_base64(b))
+        .map(|&b| b as char)
+        .collect();
+    if !complete {
+        text.truncate(text.len() / 4 * 4);
+    }
+    if text.len() < 4 {
+        return None;
+    }
+    pem::decode_base64(&text).ok()
+}
+
+/// Why a TLV walk stopped early
+pub(super) enum Stop {
+    Invalid,
+    Truncated,
+}
+
+/// Walk the TLVs in `data`, returning whether every length is definite, or
+/// None if it isn't ASN.1
+fn walk_asn1(data: &[u8], complete: bool) -> Option<bool> {
+    if data.is_empty() {
+        return None;
+    }
+    let mut definite = true;
+    let limit = if complete { Some(data.len()) } else { None };
+    match walk(data, 0, limit, false, 0, &mut definite) {
+        Ok(_) => Some(definite),
+        Err(Stop::Truncated) if !complete => Some(definite),
+        Err(_) => None,
+    }
+}
+
+/// Whether data that runs out partway through is a constructed item cut
+/// short, as a truncated certificate is: the first item is constructed, its
+/// length runs past the end of the data, and the header of its first
+/// component is there. A short run of CBOR items too often reads as a
+/// primitive TLV or a bare header cut short to take those for ASN.1
+fn cut_short(data: &[u8]) -> bool {
+    let Ok(first) = header(data, 0) else {
+        return false;
+    };
+    first.constructed
+        && first
+            .length
+            .is_none_or(|length| first.header_len + length > data.len())
+        && header(data, first.header_len).is_ok()
+}
+
+/// The identifier and length octets of a TLV
+pub(super) struct Header {
+    pub constructed: bool,
+    pub header_len: usize,
+    pub length: Option<usize>, // None for the indefinite form
+}
+
+/// Read the header of the TLV at `pos`
+pub(super) fn header(data: &[u8], pos: usize) -> Result<Header, Stop> {
+    let byte = |index: usize| data.get(index).copied().ok_or(Stop::Truncated);
+    let id = byte(pos)?;
+    let mut header_len = 1;
+    if id & TAG_MASK == TAG_MASK {
+        while byte(pos + header_len)? & LEN_XTND != 0 {
+            header_len += 1;
+            if header_len > 5 {
+                return Err(Stop::Invalid);
+            }
+        }
+        header_len += 1;
+    }
+    let len_byte = byte(pos + header_len)?;
+    header_len += 1;
+    let constructed = id & FORM_MASK == CONSTRUCTED;
+
+    if len_byte == LEN_XTND {
+        if !constructed {
+            return Err(Stop::Invalid);
+        }
+        return Ok(Header {
+            constructed,
+            header_len,
+            length: None,
+        });
+    }
+    let length = if len_byte & LEN_XTND != 0 {
+        let num_octets = (len_byte & LEN_MASK) as usize;
+        if num_octets > 4 {
+            return Err(Stop::Invalid);
+        }
+        let mut length = 0usize;
+        for _ in 0..num_octets {
+            length = (length << 8) | byte(pos + header_len)? as usize;
+            header_len += 1;
+        }
+        length
+    } else {
+        len_byte as usize
+    };
+    Ok(Header {
+        constructed,
+        header_len,
+        length: Some(length),
+    })
+}
+
+/// Walk the TLVs from `pos` up to `limit`, or up to an end-of-contents
+/// marker if `eoc` is set, returning the position after them. At the top
+/// level of a partial input there's no limit and the walk stops at the end
+/// of the data.
+fn walk(
+    data: &[u8],
+    mut pos: usize,
+    limit: Option<usize>,
+    eoc: bool,
+    depth: usize,
+    definite: &mut bool,
+) -> Result<usize, Stop> {
+    if depth > MAX_SNIFF_DEPTH {
+        return Err(Stop::Invalid);
+    }
+    loop {
+        if limit == Some(pos) {
+            // An indefinite-length item has to end inside the one holding it
+            return if eoc { Err(Stop::Invalid) } else { Ok(pos) };
+        }
+        if pos >= data.len() {
+            return if limit.is_none() && !eoc {
+                Ok(pos)
+            } else {
+                Err(Stop::Truncated)
+            };
+        }
+
+        if data[pos] == 0 {
+            // Only an end-of-contents marker can have tag 0
+            if !eoc || *data.get(pos + 1).ok_or(Stop::Truncated)? != 0 {
+                return Err(Stop::Invalid);
+            }
+            return Ok(pos + 2);
+        }
+        let Header {
+            constructed,
+            header_len,
+            length,
+        } = header(data, pos)?;
+        let Some(length) = length else {
+            *definite = false;
+            pos = walk(data, pos + header_len, limit, true, depth + 1, definite)?;
+            continue;
+        };
+
+        let end = pos + header_len + length;
+        if limit.is_some_and(|limit| end > limit) {
+            // Past the end of the data at the top level, or past the end
+            // of the item holding it below that
+            return Err(if depth == 0 {
+                Stop::Truncated
+            } else {
+                Stop::Invalid
+            });
+        }
+        if constructed {
+            walk(
+                data,
+                pos + header_len,
+                Some(end),
+                false,
+                depth + 1,
+                definite,
+            )?;
+        } else if end > data.len() {
+            return Err(Stop::Truncated);
+        }
+        pos = end;
+    }
+}
+
+/// Read enough of a file to tell what it holds. For base64 the whole file is
+/// read and returned decoded, as the dumpers can't read it as it is
+pub fn sniff_file(filename: &str) -> io::Result<(Detected, Option<Vec<u8>>)> {
+    let file =
+        File::open(filename).map_err(|e| DumpError::io(format!("Can't open '{}'", filename), e))?;
+    let mut prefix = Vec::new();
+    file.take(SNIFF_LEN + 1).read_to_end(&mut prefix)?;
+    if Compression::detect(&prefix).is_some() {
+        // What's inside is only seen once it's all decompressed
+        let data = read_data(filename)?;
+        let detected = detect(&data, true).ok_or_else(|| unrecognized(filename))?;
+        return Ok((detected, Some(decoded(filename, detected, data)?)));
+    }
+    let complete = prefix.len() as u64 <= SNIFF_LEN;
+    let detected = detect(&prefix, complete).ok_or_else(|| unrecognized(filename))?;
+
+    let decoded = if detected.base64 {
+        Some(decoded(filename, detected, read_file(filename)?)?)
+    } else {
+        None
+    };
+    Ok((detected, decoded))
+}
+
+/// Work out what data read in full holds, decoding base64
+pub fn resolve(filename: &str, data: Vec<u8>) -> io::Result<(Decoder, Vec<u8>)> {
+    let detected = detect(&data, true).ok_or_else(|| unrecognized(filename))?;
+    report(filename, detected);
+    Ok((detected.kind.decoder(), decoded(filename, detected, data)?))
+}
+
+/// The content of a file read in full, decoded from base64 if need be
+fn decoded(filename: &str, detected: Detected, data: Vec<u8>) -> io::Result<Vec<u8>> {
+    if detected.base64 {
+        decode_bare_base64(&data, true).ok_or_else(|| unrecognized(filename))
+    } else {
+        Ok(data)
+    }
+}
+
+fn report(filename: &str, detected: Detected) {
+    eprintln!("Detected {} in {}", detected, filename);
+}
+
+fn unrecognized(filename: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Can't tell whether '{}' is ASN.1 or CBOR", filename),
+    )
+}
+
+/// The input file named in dumper arguments, which are parsed as
+/// dump-asn1's and failing that as dump-cbor's
+fn input_file(args: &[String]) -> String {
+    dumper::input_file::<Asn1Dumper>(args)
+        .or_else(|e| dumper::input_file::<CborDumper>(args).map_err(|_| e))
+        .unwrap_or_else(|e| usage_error(&e))
+}
+
+/// Dumper arguments without --auto, for the dumper the input calls for
+/// The positions in `args` of the input files named, with options either
+/// dumper takes
+pub(crate) fn input_positions(args: &[String]) -> Vec<usize> {
+    Asn1Dumper::input_positions(args)
+        .or_else(|e| CborDumper::input_positions(args).map_err(|_| e))
+        .unwrap_or_else(|e| usage_error(&e))
+}
+
+pub(super) fn without_auto(args: &[String]) -> Vec<String> {
+    args.iter()
+        .filter(|arg| *arg != "--auto")
+        .cloned()
+        .collect()
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        "Usage: {} [OPTIONS] <input_file|directory|pattern>...",
+        program_name
+    );
+    println!("\nWorks out whether a file holds ASN.1 (DER, BER or PEM) or CBOR, either");
+    println!("possibly in bare base64, says which on stderr, and dumps it as dump-asn1");
+    println!("or dump-cbor would. The options are those of the command chosen.");
+    println!("\nGiven several files, a directory (standing for every file below it) or a");
+    println!("quoted pattern such as 'certs/**/*.der', dumps each file under a header");
+    println!("naming it and ends with a table of what each held and the errors and");
+    println!("warnings found.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  -j, --jobs <n>          Dump up to <n> files at once (default: one per CPU)");
+}
+
+/// Run asn1cbor dump with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    if args[1..].iter().any(|arg| arg == "-h" || arg == "--help") {
+        print_help(&args[0]);
+        return Ok(0);
+    }
+    let mut jobs = jobs::default_jobs();
+    let mut dump_args = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-j" | "--jobs" => jobs = jobs_option(args, &mut i),
+            _ => dump_args.push(args[i].clone()),
+        }
+        i += 1;
+    }
+
+    let (names, options) = split_inputs(dump_args.clone());
+    match &names[..] {
+        [] => dump(&dump_args),
+        [name] if is_single_file(name) => dump(&dump_args),
+        _ => batch::dump(&names, &without_auto(&options), None, jobs),
+    }
+}
+
+/// Dump the file named in `args` with the dumper its content calls for, for
+/// asn1cbor dump and --auto
+pub(crate) fn dump(args: &[String]) -> io::Result<i32> {
+    let filename = input_file(args);
+    let (detected, decoded) = sniff_file(&filename)?;
+    report(&filename, detected);
+    let args = without_auto(args);
+    match detected.kind.decoder() {
+        Decoder::Asn1 => dumpasn1::run_input(&args, decoded),
+        Decoder::Cbor => dumpcbor::run_input(&args, decoded),
+    }
+}
+
+/// A file checked by asn1cbor lint
+pub(crate) struct Checked {
+    pub filename: String,
+    pub detected: Option<Detected>, // What the file was found to hold
+    pub diagnostics: Vec<Diagnostic>,
+    pub status: i32,
+}
+
+impl Checked {
+    /// The note on stderr saying what the file was found to hold
+    pub fn report(&self) {
+        if let Some(detected) = self.detected {
+            report(&self.filename, detected);
+        }
+    }
+}
+
+/// Check the file named in `args` with the dumper its content calls for, or
+/// with the one `decoder` forces
+pub(crate) fn check(args: &[String], decoder: Option<Decoder>) -> io::Result<Checked> {
+    check_to(args, decoder, Output::sink())
+}
+
+/// Check a file as check does, writing its dump to `out`
+pub(crate) fn check_to(
+    args: &[String],
+    decoder: Option<Decoder>,
+    out: Output,
+) -> io::Result<Checked> {
+    let (decoder, detected, decoded) = match decoder {
+        Some(decoder) => (decoder, None, None),
+        None => {
+            let (detected, decoded) = sniff_file(&input_file(args))?;
+            (detected.kind.decoder(), Some(detected), decoded)
+        }
+    };
+    let args = without_auto(args);
+    let (filename, diagnostics, status) = match decoder {
+        Decoder::Asn1 => dumper::check_to::<Asn1Dumper>(&args, decoded, out)?,
+        Decoder::Cbor => dumper::check_to::<CborDumper>(&args, decoded, out)?,
+    };
+    Ok(Checked {
+        filename,
+        detected,
+        diagnostics,
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::hex::parse_hex;
+    use crate::der::DerBuilder;
+
+    fn kind(data: &[u8]) -> Option<String> {
+        detect(data, true).map(|detected| detected.to_string())
+    }
+
+    #[test]
+    fn test_detect() {
+        let der = DerBuilder::new()
+            .sequence(|b| b.integer(5).oid("2.5.4.3").utf8_string("name"))
+            .finish();
+        assert_eq!(kind(&der).as_deref(), Some("DER"));
+        // The same with the SEQUENCE in indefinite-length form
+        let mut ber = vec![0x30, 0x80];
+        ber.extend_from_slice(&der[2..]);
+        ber.extend_from_slice(&[0x00, 0x00]);
+        assert_eq!(kind(&ber).as_deref(), Some("BER"));
+
+        let text = pem::encode_pem("CERTIFICATE", &der);
+        assert_eq!(kind(text.as_bytes()).as_deref(), Some("PEM"));
+        let text = pem::encode_base64(&der);
+        assert_eq!(kind(text.as_bytes()).as_deref(), Some("base64-encoded DER"));
+
+        // {1: [2, 3]}, and the same in base64
+        let cbor = [0xA1, 0x01, 0x82, 0x02, 0x03];
+        assert_eq!(kind(&cbor).as_deref(), Some("CBOR"));
+        assert_eq!(kind(b"oQGCAgM=").as_deref(), Some("base64-encoded CBOR"));
+
+        assert_eq!(kind(b""), None);
+        assert_eq!(kind(&[0xFF, 0xFF]), None);
+        assert_eq!(kind(&[0x1C]), None);
+    }
+
+    #[test]
+    fn test_embedded() {
+        let der = DerBuilder::new().sequence(|b| b.integer(5)).finish();
+        let text = pem::encode_pem("CERTIFICATE", &der);
+        let (detected, decoded) = embedded(&text).unwrap();
+        assert_eq!((detected.to_string(), decoded), ("PEM".to_string(), der));
+
+        let (detected, decoded) = embedded("oQGCAgM=").unwrap();
+        assert_eq!(detected.to_string(), "base64-encoded CBOR");
+        assert_eq!(decoded, [0xA1, 0x01, 0x82, 0x02, 0x03]);
+
+        // Too short, not base64, or base64 of neither
+        assert!(embedded("AQI=").is_none());
+        assert!(embedded("not base64!").is_none());
+        assert!(embedded("Password").is_none());
+    }
+
+    #[test]
+    fn test_detect_partial() {
+        let der = DerBuilder::new()
+            .sequence(|b| b.octet_string(&[0xAB; 300]))
+            .finish();
+        assert_eq!(
+            detect(&der[..100], false).map(|d| d.kind),
+            Some(InputKind::Der)
+        );
+
+        // The self-described CBOR tag, then the start of [1, 2, 3]
+        let cbor = [0xD9, 0xD9, 0xF7, 0x83, 0x01, 0x02];
+        assert_eq!(kind(&cbor), None);
+        assert_eq!(detect(&cbor, false).map(|d| d.kind), Some(InputKind::Cbor));
+    }
+
+    #[test]
+    fn test_detect_truncated() {
+        // A certificate-like SEQUENCE cut off partway through
+        let cert = DerBuilder::new()
+            .sequence(|b| {
+                b.sequence(|b| b.explicit(0, |b| b.integer(2)).integer(1))
+                    .sequence(|b| b.oid("1.2.840.113549.1.1.11").null())
+                    .bit_string(&[0x5A; 200], 0)
+            })
+            .finish();
+        assert_eq!(kind(&cert[..cert.len() / 2]).as_deref(), Some("DER"));
+        // Not CBOR either, as 0x1C is a reserved additional info value
+        assert_eq!(kind(&[0x30, 0x10, 0x04, 0x1C]).as_deref(), Some("DER"));
+
+        // [1, 2, 3] and a 4-byte CBOR byte string read as TLVs cut short, but
+        // not as a constructed one, so they're left for CBOR
+        assert_eq!(kind(&[0x83, 0x01, 0x02, 0x03]).as_deref(), Some("CBOR"));
+        assert_eq!(
+            kind(&[0x44, 0x61, 0x62, 0x63, 0x64]).as_deref(),
+            Some("CBOR")
+        );
+        // Maps with text keys read as a constructed TLV cut short, but
+        // they're complete CBOR: {"a": 1, "b": [2, 3]} and {"name": "x"}
+        for cbor in ["a26161016162820203", "a1646e616d656178"] {
+            let data = parse_hex(cbor).unwrap();
+            assert_eq!(kind(&data).as_deref(), Some("CBOR"), "{}", cbor);
+        }
+    }
+}
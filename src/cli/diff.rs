@@ -0,0 +1,279 @@
+// asn1cbor diff: compare the decoded items in two files, element by element,
+// and list the ones that differ with their path and offsets. Values are
+// compared rather than encodings, so a CBOR integer in a longer head than
+// it needs matches the same integer in the shortest one.
+
+use std::io::{self, BufWriter, Write};
+
+use super::{decoder_option, file_nodes, read_input, usage_error, Decoder, EXIT_DIFFERENT};
+use crate::common::hex::hex_lower;
+use crate::diagnostics::EXIT_CLEAN;
+use crate::render::{json_string, Content, Node};
+
+// Byte strings longer than this are cut short in the listing
+const MAX_SHOWN_BYTES: usize = 32;
+
+/// What differs at one place in the two inputs
+#[derive(Debug, Clone, PartialEq)]
+struct Difference {
+    path: String,
+    old: Option<String>, // None if the element is only in the second input
+    new: Option<String>, // None if it's only in the first
+}
+
+/// Options for diff
+#[derive(Debug, Default)]
+struct Options {
+    decoder: Option<Decoder>, // None to detect each file's
+    show_secrets: bool,
+    files: Vec<String>,
+}
+
+fn print_help(program_name: &str) {
+    println!("Usage: {} [OPTIONS] <file1> <file2>", program_name);
+    println!("\nShows where the decoded items in two files differ, with exit status 1 if");
+    println!("they do. Whether each file is ASN.1 or CBOR is detected unless --asn1 or");
+    println!("--cbor says.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  --asn1                  Decode both files as ASN.1 DER, BER or PEM");
+    println!("  --cbor                  Decode both files as CBOR");
+    println!("  --auto                  Detect what each file is (the default)");
+    println!("  --show-secrets          Compare and show private key material, which is");
+    println!("                          otherwise masked and only compared by length");
+}
+
+fn parse_args_from(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                std::process::exit(0);
+            }
+            "--show-secrets" => options.show_secrets = true,
+            arg @ ("--asn1" | "--cbor" | "--auto") => options.decoder = decoder_option(arg),
+            arg if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            arg => options.files.push(arg.to_string()),
+        }
+        i += 1;
+    }
+    if options.files.len() != 2 {
+        return Err("Two input files are needed".to_string());
+    }
+    Ok(options)
+}
+
+/// Run diff with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let options = parse_args_from(args).unwrap_or_else(|e| usage_error(&e));
+    let mut inputs = Vec::new();
+    for filename in &options.files {
+        let (decoder, data) = read_input(filename, options.decoder)?;
+        inputs.push(file_nodes(&data, decoder, options.show_secrets)?);
+    }
+
+    let differences = compare_items(&inputs[0], &inputs[1]);
+    let mut out = BufWriter::new(io::stdout().lock());
+    for difference in &differences {
+        writeln!(out, "{}", difference.path)?;
+        if let Some(old) = &difference.old {
+            writeln!(out, "  - {}", old)?;
+        }
+        if let Some(new) = &difference.new {
+            writeln!(out, "  + {}", new)?;
+        }
+    }
+    out.flush()?;
+    Ok(if differences.is_empty() {
+        EXIT_CLEAN
+    } else {
+        EXIT_DIFFERENT
+    })
+}
+
+/// Compare two lists of top-level items
+fn compare_items(old: &[Node], new: &[Node]) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    let name = |i: usize, node: &Node| match &node.label {
+        Some(label) => label.clone(),
+        None => format!("item {}", i + 1),
+    };
+    for i in 0..old.len().max(new.len()) {
+        match (old.get(i), new.get(i)) {
+            (Some(a), Some(b)) => compare(a, b, &name(i, a), &mut differences),
+            (a, b) => differences.push(Difference {
+                path: name(i, a.or(b).unwrap()),
+                old: a.map(describe),
+                new: b.map(describe),
+            }),
+        }
+    }
+    differences
+}
+
+/// Compare two nodes at `path`, and what's in them
+fn compare(a: &Node, b: &Node, path: &str, differences: &mut Vec<Difference>) {
+    let differ = |differences: &mut Vec<Difference>| {
+        differences.push(Difference {
+            path: path.to_string(),
+            old: Some(describe(a)),
+            new: Some(describe(b)),
+        })
+    };
+    if a.kind != b.kind {
+        return differ(differences);
+    }
+    match (&a.content, &b.content) {
+        (Content::List(a_items), Content::List(b_items)) => {
+            compare_children(path, a_items, b_items, differences);
+        }
+        (Content::Map(a_pairs), Content::Map(b_pairs)) => {
+            let flatten = |pairs: &[(Node, Node)]| -> Vec<Node> {
+                pairs
+                    .iter()
+                    .flat_map(|(key, value)| [key.clone(), value.clone()])
+                    .collect()
+            };
+            compare_children(path, &flatten(a_pairs), &flatten(b_pairs), differences);
+        }
+        (Content::Tagged(a_tag, a_item), Content::Tagged(b_tag, b_item)) if a_tag == b_tag => {
+            compare(a_item, b_item, path, differences);
+        }
+        (a_content, b_content) => {
+            if a_content != b_content {
+                differ(differences);
+            }
+        }
+    }
+}
+
+/// Compare the elements of two constructed items, pairing them by position
+fn compare_children(path: &str, old: &[Node], new: &[Node], differences: &mut Vec<Difference>) {
+    for i in 0..old.len().max(new.len()) {
+        let (a, b) = (old.get(i), new.get(i));
+        let child_path = match a.or(b).and_then(|node| node.label.as_ref()) {
+            Some(label) => format!("{} > {}", path, label),
+            None => format!("{} > [{}]", path, i),
+        };
+        match (a, b) {
+            (Some(a), Some(b)) => compare(a, b, &child_path, differences),
+            _ => differences.push(Difference {
+                path: child_path,
+                old: a.map(describe),
+                new: b.map(describe),
+            }),
+        }
+    }
+}
+
+/// One-line description of a node for the listing
+fn describe(node: &Node) -> String {
+    let value = match &node.content {
+        Content::Integer(n) => n.clone(),
+        Content::Float(f) => f.to_string(),
+        Content::Bool(b) => b.to_string(),
+        Content::Null | Content::Undefined => String::new(),
+        Content::Simple(n) => n.to_string(),
+        Content::Text(text) => json_string(text),
+        Content::Bytes(bytes) => {
+            let shown = hex_lower(&bytes[..bytes.len().min(MAX_SHOWN_BYTES)]);
+            if bytes.len() > MAX_SHOWN_BYTES {
+                format!("{}... ({} bytes)", shown, bytes.len())
+            } else {
+                shown
+            }
+        }
+        Content::Oid { dotted, name } => match name {
+            Some(name) => format!("{} ({})", dotted, name),
+            None => dotted.clone(),
+        },
+        Content::Other(text) => text.clone(),
+        Content::List(nodes) => count(nodes.len(), "element"),
+        Content::Map(pairs) => count(pairs.len(), "pair"),
+        Content::Tagged(tag, _) => tag.to_string(),
+    };
+    let mut text = node.kind.clone();
+    if !value.is_empty() {
+        text.push(' ');
+        text.push_str(&value);
+    }
+    if let Some(offset) = node.offset {
+        text.push_str(&format!(" at offset {}", offset));
+    }
+    text
+}
+
+/// "with 1 element", "with 2 elements"
+fn count(n: usize, noun: &str) -> String {
+    format!("with {} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::DerBuilder;
+    use crate::dumpasn1;
+
+    fn nodes(der: &[u8]) -> Vec<Node> {
+        dumpasn1::to_nodes(der, false).unwrap()
+    }
+
+    #[test]
+    fn test_compare() {
+        let old = DerBuilder::new()
+            .sequence(|b| b.integer(1).oid("2.5.4.3").utf8_string("a"))
+            .finish();
+        let new = DerBuilder::new()
+            .sequence(|b| b.integer(1).oid("2.5.4.10"))
+            .null()
+            .finish();
+        assert_eq!(compare_items(&nodes(&old), &nodes(&old)), []);
+        assert_eq!(
+            compare_items(&nodes(&old), &nodes(&new)),
+            [
+                Difference {
+                    path: "item 1 > [1]".to_string(),
+                    old: Some("OBJECT IDENTIFIER 2.5.4.3 (commonName) at offset 5".to_string()),
+                    new: Some(
+                        "OBJECT IDENTIFIER 2.5.4.10 (organizationName) at offset 5".to_string()
+                    ),
+                },
+                Difference {
+                    path: "item 1 > [2]".to_string(),
+                    old: Some("UTF8String \"a\" at offset 10".to_string()),
+                    new: None,
+                },
+                Difference {
+                    path: "item 2".to_string(),
+                    old: None,
+                    new: Some("NULL at offset 10".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_cbor() {
+        // {1: [2, 3]} against {1: [2, 4]} with the 2 in a longer head
+        let old = crate::cbor::parse_slice(&[0xA1, 0x01, 0x82, 0x02, 0x03]).unwrap();
+        let new = crate::cbor::parse_slice(&[0xA1, 0x01, 0x82, 0x18, 0x02, 0x04]).unwrap();
+        let differences = compare_items(&crate::cbor::to_nodes(&old), &crate::cbor::to_nodes(&new));
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].path, "item 1 > [1] > [1]");
+        assert_eq!(
+            differences[0].new.as_deref(),
+            Some("unsigned 4 at offset 5")
+        );
+    }
+
+    #[test]
+    fn test_describe_long_bytes() {
+        let node = Node::new("OCTET STRING", Content::Bytes(vec![0xAB; 40]));
+        assert_eq!(
+            describe(&node),
+            format!("OCTET STRING {}... (40 bytes)", "ab".repeat(32))
+        );
+    }
+}
@@ -0,0 +1,185 @@
+// asn1cbor gen-cbor (gencbor): random CBOR items from a seed, for fuzz
+// corpora and for stress-testing dumpcbor and other parsers. Items go to
+// stdout as a CBOR sequence, or to a directory a file each. The seed is
+// reported when it wasn't given, so that the same items can be made again.
+
+use std::fs;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::usage_error;
+use crate::cbor::CborGenerator;
+use crate::common::args::{option_value, parse_number};
+use crate::diagnostics::EXIT_CLEAN;
+use crate::error::DumpError;
+
+/// Options for gen-cbor
+#[derive(Debug)]
+struct Options {
+    seed: Option<u64>, // None to take one from the clock
+    count: usize,
+    max_depth: usize,
+    max_length: usize,
+    preferred: bool,
+    malformed: bool,
+    output_dir: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            seed: None,
+            count: 1,
+            max_depth: 4,
+            max_length: 8,
+            preferred: false,
+            malformed: false,
+            output_dir: None,
+        }
+    }
+}
+
+fn print_help(program_name: &str) {
+    println!("Usage: {} [OPTIONS]", program_name);
+    println!("\nWrites random well-formed CBOR items, or with --malformed items each with a");
+    println!("fault in it, to stdout as a CBOR sequence or to a directory a file each. The");
+    println!("same seed always gives the same items.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  --seed <n>              Seed for the items (default: from the clock, reported");
+    println!("                          on stderr)");
+    println!("  -n, --count <n>         Number of items to write (default: 1)");
+    println!("  --max-depth <n>         Nest arrays, maps and tags at most <n> deep (default: 4)");
+    println!("  --max-length <n>        Most entries in an array or map, and bytes or characters");
+    println!("                          in a string (default: 8)");
+    println!("  --preferred             Only preferred serialization: shortest arguments and");
+    println!("                          floats, no indefinite lengths");
+    println!("  --malformed             Put a fault in each item: cut short, a reserved or");
+    println!("                          misplaced additional info value, or text that isn't UTF-8");
+    println!("  -o <dir>                Write each item to its own file in <dir>, which is");
+    println!("                          created if need be");
+    println!("\nEXAMPLES:");
+    println!("  {} --seed 1 -n 1000 -o corpus", program_name);
+    println!("  {} --malformed | dumpcbor /dev/stdin", program_name);
+}
+
+fn parse_args_from(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                std::process::exit(0);
+            }
+            "--seed" => options.seed = Some(parse_number(option_value(args, &mut i)?, "seed")?),
+            "-n" | "--count" => options.count = parse_number(option_value(args, &mut i)?, "count")?,
+            "--max-depth" => {
+                options.max_depth = parse_number(option_value(args, &mut i)?, "max depth")?;
+            }
+            "--max-length" => {
+                options.max_length = parse_number(option_value(args, &mut i)?, "max length")?;
+            }
+            "--preferred" => options.preferred = true,
+            "--malformed" => options.malformed = true,
+            "-o" => options.output_dir = Some(option_value(args, &mut i)?.to_string()),
+            arg if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            arg => return Err(format!("Unexpected argument: {}", arg)),
+        }
+        i += 1;
+    }
+    Ok(options)
+}
+
+/// Run gen-cbor with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let options = parse_args_from(args).unwrap_or_else(|e| usage_error(&e));
+    if options.output_dir.is_none() && io::stdout().is_terminal() {
+        usage_error("Won't write binary CBOR to a terminal; redirect stdout or use -o <dir>");
+    }
+    let seed = options.seed.unwrap_or_else(reported_seed);
+    let mut generator = CborGenerator::new(seed)
+        .max_depth(options.max_depth)
+        .max_length(options.max_length)
+        .preferred(options.preferred)
+        .malformed(options.malformed);
+
+    write_items(options.output_dir.as_deref(), options.count, || {
+        generator.generate()
+    })?;
+    Ok(EXIT_CLEAN)
+}
+
+/// Write `count` items made by `item` to stdout as a CBOR sequence, or to
+/// a file each in `dir`
+pub(super) fn write_items(
+    dir: Option<&str>,
+    count: usize,
+    mut item: impl FnMut() -> Vec<u8>,
+) -> io::Result<()> {
+    match dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)
+                .map_err(|e| DumpError::io(format!("Can't create '{}'", dir), e))?;
+            for index in 0..count {
+                let path = PathBuf::from(dir).join(item_filename(index, count));
+                fs::write(&path, item())
+                    .map_err(|e| DumpError::io(format!("Can't write '{}'", path.display()), e))?;
+            }
+        }
+        None => {
+            let mut out = BufWriter::new(io::stdout().lock());
+            for _ in 0..count {
+                out.write_all(&item())?;
+            }
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// A seed that differs from run to run, reported on stderr so that the run
+/// can be repeated
+pub(super) fn reported_seed() -> u64 {
+    let seed = clock_seed();
+    eprintln!("Seed: {}", seed);
+    seed
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+/// The name of item `index`'s file, numbered to sort in order among `count`
+fn item_filename(index: usize, count: usize) -> String {
+    let digits = count.saturating_sub(1).to_string().len().max(4);
+    format!("{:0width$}.cbor", index, width = digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let args: Vec<String> = ["gencbor", "--seed", "7", "-n", "3", "--malformed"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = parse_args_from(&args).unwrap();
+        assert_eq!((options.seed, options.count), (Some(7), 3));
+        assert!(options.malformed && !options.preferred);
+        let args = ["gencbor".to_string(), "out.cbor".to_string()];
+        assert!(parse_args_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_item_filename() {
+        assert_eq!(item_filename(7, 100), "0007.cbor");
+        assert_eq!(item_filename(12345, 100000), "12345.cbor");
+        assert_eq!(item_filename(0, 100001), "000000.cbor");
+    }
+}
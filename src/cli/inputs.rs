@@ -0,0 +1,298 @@
+// The input files named on the command line of the commands that take
+// several. A directory stands for every file below it, and a glob pattern
+// such as certs/**/*.der for the files it matches, so that a trust store can
+// be swept in one go even where the shell doesn't expand ** (or anything,
+// as on Windows). Hidden files and directories are left out, as the shell
+// leaves them out, and symbolic links to directories aren't followed, so a
+// link back up the tree can't loop. Each directory's files are taken in
+// name order.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use super::detect;
+use crate::error::DumpError;
+
+/// The files named on the command line, with directories and patterns
+/// replaced by the files they stand for
+pub fn expand_inputs(names: &[String]) -> io::Result<Vec<String>> {
+    let mut inputs = Vec::new();
+    for name in names {
+        let path = Path::new(name);
+        if path.is_dir() {
+            walk(path, &mut |file| inputs.push(file.display().to_string()))?;
+        } else if !is_single_file(name) {
+            let matches = expand_pattern(name)?;
+            if matches.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No files match '{}'", name),
+                ));
+            }
+            inputs.extend(matches);
+        } else {
+            inputs.push(name.clone());
+        }
+    }
+    Ok(inputs)
+}
+
+/// Split dumper arguments into the names of the inputs and the options
+/// that go with each
+pub fn split_inputs(args: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let positions = detect::input_positions(&args);
+    let (names, options): (Vec<_>, Vec<_>) = args
+        .into_iter()
+        .enumerate()
+        .partition(|(index, _)| positions.contains(index));
+    let strip = |args: Vec<(usize, String)>| args.into_iter().map(|(_, arg)| arg).collect();
+    (strip(names), strip(options))
+}
+
+/// Dumper arguments for one input: the options, then the file. With -f, a
+/// name starting with '-' isn't taken for an option
+pub fn file_args(options: &[String], input: &str) -> Vec<String> {
+    let mut args = options.to_vec();
+    args.extend(["-f".to_string(), input.to_string()]);
+    args
+}
+
+/// Whether a name on the command line is of a single file rather than a
+/// directory or a pattern
+pub fn is_single_file(name: &str) -> bool {
+    let path = Path::new(name);
+    // A file whose name has wildcards in it is taken as it is
+    !path.is_dir() && (path.exists() || !is_pattern(name))
+}
+
+fn is_pattern(name: &str) -> bool {
+    name.contains(['*', '?', '['])
+}
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+/// The entries of a directory other than hidden ones, sorted by name, with
+/// whether each is a directory (not following links)
+fn entries(dir: &Path) -> io::Result<Vec<(String, PathBuf, bool)>> {
+    let error = |e| DumpError::io(format!("Can't read directory '{}'", dir.display()), e);
+    // An empty path is the current directory, left off the names found
+    let readable = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(readable).map_err(error)? {
+        let entry = entry.map_err(error)?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !is_hidden(&name) {
+            let is_dir = entry.file_type().map_err(error)?.is_dir();
+            entries.push((name.clone(), dir.join(&name), is_dir));
+        }
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Pass every file below `dir` to `found`, each directory's files before
+/// its subdirectories
+fn walk(dir: &Path, found: &mut dyn FnMut(&Path)) -> io::Result<()> {
+    let entries = entries(dir)?;
+    for (_, path, _) in entries
+        .iter()
+        .filter(|(_, path, is_dir)| !is_dir && path.is_file())
+    {
+        found(path);
+    }
+    for (_, path, _) in entries.iter().filter(|(_, _, is_dir)| *is_dir) {
+        walk(path, found)?;
+    }
+    Ok(())
+}
+
+/// The files matching a pattern, in the order a directory is walked. `**`
+/// as a whole component matches any number of directories, and at the end
+/// everything below
+fn expand_pattern(pattern: &str) -> io::Result<Vec<String>> {
+    // The components up to the first with a wildcard are where to start
+    let mut base = PathBuf::new();
+    let mut components = Vec::new();
+    for component in Path::new(pattern).components() {
+        match component {
+            Component::Normal(part) if components.is_empty() => {
+                let part = part.to_string_lossy();
+                if is_pattern(&part) {
+                    components.push(part.into_owned());
+                } else {
+                    base.push(&*part);
+                }
+            }
+            Component::Normal(part) => components.push(part.to_string_lossy().into_owned()),
+            _ if components.is_empty() => base.push(component),
+            _ => components.push(component.as_os_str().to_string_lossy().into_owned()),
+        }
+    }
+    if components.last().is_some_and(|last| last == "**") {
+        components.push("*".to_string());
+    }
+
+    let mut matches = Vec::new();
+    expand_from(&base, &components, &mut matches)?;
+    // A file can match more than one way, as with **/**/*.der
+    let mut seen = HashSet::new();
+    matches.retain(|path| seen.insert(path.clone()));
+    Ok(matches)
+}
+
+/// Add the files below `dir` matching the pattern components left
+fn expand_from(dir: &Path, components: &[String], matches: &mut Vec<String>) -> io::Result<()> {
+    let Some((component, rest)) = components.split_first() else {
+        if dir.is_file() {
+            matches.push(dir.display().to_string());
+        }
+        return Ok(());
+    };
+    if component == "**" {
+        // No directories, then one more with the ** still to match
+        expand_from(dir, rest, matches)?;
+        for (_, path, _) in entries(dir)?.iter().filter(|(_, _, is_dir)| *is_dir) {
+            expand_from(path, components, matches)?;
+        }
+    } else if !is_pattern(component) {
+        let path = dir.join(component);
+        if path.exists() {
+            expand_from(&path, rest, matches)?;
+        }
+    } else if dir.as_os_str().is_empty() || dir.is_dir() {
+        for (name, path, is_dir) in entries(dir)? {
+            if (rest.is_empty() || is_dir) && matches_component(component, &name) {
+                expand_from(&path, rest, matches)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Match a name against one component of a pattern: `*` matches any run of
+/// characters, `?` any one, and `[...]` any one of those listed, with ranges
+/// such as `a-z`, or any one not listed if it starts with `!` or `^`
+fn matches_component(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches_from(&pattern, &name)
+}
+
+fn matches_from(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| matches_from(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && matches_from(&pattern[1..], &name[1..]),
+        Some('[') => match (class_end(pattern), name.first()) {
+            (Some(end), Some(&c)) => {
+                in_class(&pattern[1..end], c) && matches_from(&pattern[end + 1..], &name[1..])
+            }
+            (Some(_), None) => false,
+            // An unclosed [ is an ordinary character
+            (None, _) => name.first() == Some(&'[') && matches_from(&pattern[1..], &name[1..]),
+        },
+        Some(&c) => name.first() == Some(&c) && matches_from(&pattern[1..], &name[1..]),
+    }
+}
+
+/// The position of the ] closing the class that opens the pattern. A ]
+/// straight after the [ (or after its negation) is one of the characters
+fn class_end(pattern: &[char]) -> Option<usize> {
+    let mut start = 1;
+    if matches!(pattern.get(start), Some('!' | '^')) {
+        start += 1;
+    }
+    pattern
+        .iter()
+        .skip(start + 1)
+        .position(|&c| c == ']')
+        .map(|position| position + start + 1)
+}
+
+fn in_class(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.first() {
+        Some('!' | '^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            found |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_component() {
+        assert!(matches_component("*.der", "cert.der"));
+        assert!(matches_component("*.der", ".der"));
+        assert!(!matches_component("*.der", "cert.pem"));
+        assert!(matches_component("c?rt*", "cert.der"));
+        assert!(matches_component("[a-c]*.pem", "b1.pem"));
+        assert!(!matches_component("[!a-c]*.pem", "b1.pem"));
+        assert!(matches_component("[]x]", "]"));
+        assert!(matches_component("a[b", "a[b"));
+        assert!(!matches_component("?", ""));
+    }
+
+    #[test]
+    fn test_expand_inputs() {
+        let dir = std::env::temp_dir().join(format!("asn1cbor-inputs-{}", std::process::id()));
+        for name in [
+            "b.der",
+            "a.cbor",
+            ".hidden",
+            "sub/c.der",
+            "sub/deeper/d.der",
+            ".git/e.der",
+        ] {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, [0x05, 0x00]).unwrap();
+        }
+        let path = |name: &str| dir.join(name).display().to_string();
+        let expand = |names: &[String]| expand_inputs(names);
+
+        let inputs = expand(&["x.der".to_string(), path("")]).unwrap();
+        let expected = ["x.der".to_string(), path("a.cbor"), path("b.der")];
+        assert_eq!(inputs[..3], expected);
+        assert_eq!(inputs[3..], [path("sub/c.der"), path("sub/deeper/d.der")]);
+
+        let inputs = expand(&[path("**/*.der"), path("**/**/b.der")]).unwrap();
+        assert_eq!(
+            inputs,
+            [
+                path("b.der"),
+                path("sub/c.der"),
+                path("sub/deeper/d.der"),
+                path("b.der")
+            ]
+        );
+        let inputs = expand(&[path("sub/*/*.der")]).unwrap();
+        assert_eq!(inputs, [path("sub/deeper/d.der")]);
+        let inputs = expand(&[path("sub/**")]).unwrap();
+        assert_eq!(inputs, [path("sub/c.der"), path("sub/deeper/d.der")]);
+        let err = expand(&[path("*.pem")]).unwrap_err();
+        assert!(err.to_string().starts_with("No files match"), "{}", err);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,213 @@
+// asn1cbor lint: run a dumper over a file without printing the dump, then
+// list the errors and warnings it found, one per line, for editors and CI
+// logs. Takes the dumpers' own options, so --strict, --max-depth and the
+// rest apply as they would to a dump. Given several files, a directory or a
+// pattern, it checks them on --jobs threads and lists what it finds in the order the
+// files were given.
+
+use std::io::{self, BufWriter, Write};
+
+use super::detect;
+use super::inputs::{expand_inputs, file_args, split_inputs};
+use super::{decoder_option, jobs_option, usage_error};
+use crate::common::args::option_value;
+use crate::common::color::{ColorChoice, Painted, Style};
+use crate::common::jobs;
+use crate::diagnostics::{self, Diagnostic, Severity, EXIT_CLEAN};
+
+fn print_help(program_name: &str) {
+    println!(
+        "Usage: {} [--asn1|--cbor] [OPTIONS] <input_file|directory|pattern>...",
+        program_name
+    );
+    println!("\nChecks files and lists the errors and warnings found, as");
+    println!("<file>:<offset>: <severity>: <message> [<code>], with the exit status of a");
+    println!("dump (the worst of them, for several files). A directory stands for every");
+    println!("file below it, and a pattern such as 'certs/**/*.der' for the files it");
+    println!("matches. Whether each input is ASN.1 or CBOR is detected unless --asn1 or");
+    println!("--cbor says.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  --asn1                  Check the input as ASN.1 DER, BER or PEM");
+    println!("  --cbor                  Check the input as CBOR");
+    println!("  --auto                  Detect what the input is (the default)");
+    println!("  -j, --jobs <n>          Check up to <n> files at once (default: one per CPU)");
+    println!("  --color <when>          Color the severities: auto, always or never");
+    println!("\nThe options of dump-asn1 or dump-cbor, whichever checks the input, are also");
+    println!("taken.");
+}
+
+/// Run lint with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    if args[1..].iter().any(|arg| arg == "-h" || arg == "--help") {
+        print_help(&args[0]);
+        return Ok(0);
+    }
+    let mut decoder = None;
+    let mut jobs = jobs::default_jobs();
+    let mut color = ColorChoice::Auto;
+    let mut dump_args = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        match arg.as_str() {
+            "--asn1" | "--cbor" | "--auto" => decoder = decoder_option(arg),
+            "-j" | "--jobs" => jobs = jobs_option(args, &mut i),
+            "--color" => {
+                let value = option_value(args, &mut i).unwrap_or_else(|e| usage_error(&e));
+                color = ColorChoice::from_name(value).unwrap_or_else(|| {
+                    usage_error(&format!(
+                        "Unknown color choice: {} (use {})",
+                        value,
+                        ColorChoice::NAMES
+                    ))
+                });
+            }
+            _ => dump_args.push(arg.clone()),
+        }
+        i += 1;
+    }
+
+    let (names, options) = split_inputs(dump_args);
+    let inputs = expand_inputs(&names)?;
+    if inputs.is_empty() {
+        usage_error("No input file specified");
+    }
+    // Lint reports on a file once it's been read to the end
+    if options.iter().any(|option| option == "--follow") {
+        usage_error("--follow can't be used with lint");
+    }
+    if inputs.len() > 1 && options.iter().any(|option| option == "--errors-json") {
+        usage_error("--errors-json can't be used with more than one input file");
+    }
+
+    // Stdout isn't locked for the whole run, as that would stop the workers'
+    // dumpers setting up their output
+    let mut out = BufWriter::new(io::stdout());
+    let colors = color.for_stdout();
+    let mut status = EXIT_CLEAN;
+    jobs::run_ordered(
+        &inputs,
+        jobs,
+        |input| detect::check(&file_args(&options, input), decoder),
+        |checked| {
+            let checked = match checked {
+                Ok(checked) => checked,
+                Err(e) => {
+                    out.flush()?;
+                    eprintln!("Error: {}", e);
+                    status = status.max(diagnostics::failure_status(&e));
+                    return Ok(());
+                }
+            };
+            if checked.detected.is_some() {
+                // Keep the note on stderr next to the file's lines
+                out.flush()?;
+                checked.report();
+            }
+            for diagnostic in &checked.diagnostics {
+                writeln!(
+                    out,
+                    "{}",
+                    format_line(&checked.filename, diagnostic, colors)
+                )?;
+            }
+            status = status.max(checked.status);
+            Ok(())
+        },
+    )?;
+    out.flush()?;
+    Ok(status)
+}
+
+/// Run dumpcbor --validate, which is lint --cbor under another name
+pub(crate) fn validate(args: &[String]) -> io::Result<i32> {
+    let mut args: Vec<String> = args
+        .iter()
+        .filter(|arg| *arg != "--validate")
+        .cloned()
+        .collect();
+    args.insert(1, "--cbor".to_string());
+    run(&args)
+}
+
+pub(super) fn format_line(filename: &str, diagnostic: &Diagnostic, colors: bool) -> String {
+    let location = match diagnostic.offset {
+        Some(offset) => format!("{}:{}", filename, offset),
+        None => filename.to_string(),
+    };
+    let severity = Painted {
+        style: match diagnostic.severity {
+            Severity::Error => Style::Error,
+            Severity::Warning => Style::Warning,
+        },
+        text: diagnostic.severity.as_str(),
+        colored: colors,
+    };
+    format!(
+        "{}: {}: {} [{}]",
+        location, severity, diagnostic.message, diagnostic.code
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_on_several_threads() {
+        let dir = std::env::temp_dir().join(format!("asn1cbor-lint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for i in 0..8 {
+            std::fs::write(dir.join(format!("{}.der", i)), [0x02, 0x01, i]).unwrap();
+        }
+        let args: Vec<String> = ["asn1cbor lint", "--asn1", "--jobs", "4"]
+            .iter()
+            .map(|s| s.to_string())
+            .chain([dir.display().to_string()])
+            .collect();
+        let status = run(&args);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(status.unwrap(), EXIT_CLEAN);
+    }
+
+    #[test]
+    fn test_validate() {
+        let path = std::env::temp_dir().join(format!("asn1cbor-validate-{}", std::process::id()));
+        // An indefinite-length chunk in an indefinite-length byte string
+        std::fs::write(&path, [0x5F, 0x5F, 0x41, 0x00, 0xFF, 0xFF]).unwrap();
+        let args: Vec<String> = ["dumpcbor", "--validate", "--stream"]
+            .iter()
+            .map(|s| s.to_string())
+            .chain([path.display().to_string()])
+            .collect();
+        let status = validate(&args);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(status.unwrap(), diagnostics::EXIT_ERRORS);
+    }
+
+    #[test]
+    fn test_format_line() {
+        let mut diagnostic = Diagnostic {
+            code: "zero-length",
+            severity: Severity::Warning,
+            offset: Some(12),
+            path: Vec::new(),
+            message: "Zero-length INTEGER".to_string(),
+        };
+        assert_eq!(
+            format_line("a.der", &diagnostic, false),
+            "a.der:12: warning: Zero-length INTEGER [zero-length]"
+        );
+        diagnostic.offset = None;
+        diagnostic.severity = Severity::Error;
+        assert_eq!(
+            format_line("a.der", &diagnostic, false),
+            "a.der: error: Zero-length INTEGER [zero-length]"
+        );
+        assert_eq!(
+            format_line("a.der", &diagnostic, true),
+            "a.der: \x1b[1;31merror\x1b[0m: Zero-length INTEGER [zero-length]"
+        );
+    }
+}
@@ -0,0 +1,787 @@
+// asn1cbor minimize: cut an input down to a small one that the dumper still
+// reports the same error or warning for, to attach to a bug report. Whole
+// elements are removed while the finding stays, TLVs or CBOR items (a map's
+// key going with its value), outermost first, and the content of primitives
+// and strings is halved, with the lengths and counts of the elements holding
+// them brought down to match. A length or count that was wrong stays wrong
+// by as much, so that an item cut short stays cut short. PEM and base64 are
+// decoded first, so that what's cut is the encoding itself.
+
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+
+use super::detect::header;
+use super::inputs::{file_args, split_inputs};
+use super::{decoder_option, pem_content, read_input, usage_error, Decoder};
+use crate::asn1::pem;
+use crate::asn1::tags::{LEN_XTND, TAG_MASK};
+use crate::cbor::{
+    AI_1BYTE, AI_2BYTES, AI_4BYTES, AI_8BYTES, AI_INDEFINITE, BREAK, MAJOR_ARRAY, MAJOR_BYTES,
+    MAJOR_MAP, MAJOR_SIMPLE, MAJOR_TAG, MAJOR_TEXT,
+};
+use crate::common::args::option_value;
+use crate::common::dumper;
+use crate::diagnostics::{Diagnostic, Severity, EXIT_CLEAN};
+use crate::dumpasn1::Asn1Dumper;
+use crate::dumpcbor::CborDumper;
+use crate::error::DumpError;
+use crate::output::Output;
+
+/// Nesting depth beyond which elements are left whole
+const MAX_DEPTH: usize = 64;
+
+// Dumper options that write files, wait for input or exit, which have no
+// place in a dump that's run over and over
+const REFUSED_OPTIONS: &[&str] = &[
+    "-O",
+    "--expect",
+    "--errors-json",
+    "--timing",
+    "--follow",
+    "--listen",
+    "--unix",
+];
+
+/// The finding an input has to keep
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Wanted {
+    severity: Severity,
+    text: String,         // A code, or part of a message
+    kind: Option<String>, // The message_kind too, for the finding kept by default
+}
+
+impl Wanted {
+    fn matches(&self, diagnostic: &Diagnostic) -> bool {
+        diagnostic.severity == self.severity
+            && (diagnostic.code == self.text || diagnostic.message.contains(&self.text))
+            && self
+                .kind
+                .as_ref()
+                .is_none_or(|kind| *kind == message_kind(&diagnostic.message))
+    }
+}
+
+/// A message with its numbers masked, as the offsets and sizes in it change
+/// while the input is cut, leaving what's wrong with which element
+fn message_kind(message: &str) -> String {
+    let mut kind = String::new();
+    for c in message.chars() {
+        if !c.is_ascii_digit() {
+            kind.push(c);
+        } else if !kind.ends_with('#') {
+            kind.push('#');
+        }
+    }
+    kind
+}
+
+/// Options for minimize
+#[derive(Debug, Default)]
+struct Options {
+    decoder: Option<Decoder>, // None to detect it
+    wanted: Option<Wanted>,   // None for the input's first error or warning
+    output_file: Option<String>,
+    dump_options: Vec<String>,
+    input_file: String,
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        "Usage: {} [OPTIONS] [--error <code|text>] <input_file>",
+        program_name
+    );
+    println!("\nCuts a file down to a small one that the dumper still reports the same error");
+    println!("or warning for, and writes it to stdout or --output. The finding kept is the");
+    println!("one --error or --warning names, by its code (as lint shows it) or part of its");
+    println!("message, and otherwise the file's first error, or if it has none its first");
+    println!("warning, by its code and its message with the numbers in it left out. Whole");
+    println!("TLVs or CBOR items are removed and the content of primitives and strings");
+    println!("shortened, the lengths and counts holding them brought down to match.");
+    println!("Whether the input is ASN.1 or CBOR is detected unless --asn1 or --cbor says;");
+    println!("PEM and base64 are decoded first.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  --asn1                  Decode the input as ASN.1 DER, BER or PEM");
+    println!("  --cbor                  Decode the input as CBOR");
+    println!("  --auto                  Detect what the input is (the default)");
+    println!("  --error <code|text>     Keep an error with this code or message text");
+    println!("  --warning <code|text>   Keep a warning with this code or message text");
+    println!("  --output <file>         Write the result to <file> instead of stdout");
+    println!("\nThe options of dump-asn1 or dump-cbor, whichever decodes the input, are also");
+    println!("taken, and apply to every dump tried; --strict, for one, stops each dump at");
+    println!("its first error, and for ASN.1 checks the DER-only rules.");
+    println!("\nEXAMPLE:");
+    println!(
+        "  {} --error truncated --output small.der broken.der",
+        program_name
+    );
+}
+
+fn parse_args_from(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut dump_args = vec![args[0].clone()];
+    let mut i = 1;
+    while i < args.len() {
+        let arg = &args[i];
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                std::process::exit(0);
+            }
+            "--asn1" | "--cbor" | "--auto" => options.decoder = decoder_option(arg),
+            "--error" | "--warning" => {
+                let severity = if arg == "--error" {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                };
+                let text = option_value(args, &mut i)?.to_string();
+                let wanted = Wanted {
+                    severity,
+                    text,
+                    kind: None,
+                };
+                if options.wanted.replace(wanted).is_some() {
+                    return Err("Only one of --error and --warning can be given".to_string());
+                }
+            }
+            "--output" => options.output_file = Some(option_value(args, &mut i)?.to_string()),
+            option if REFUSED_OPTIONS.contains(&option) => {
+                return Err(format!("{} can't be used with minimize", option));
+            }
+            _ => dump_args.push(arg.clone()),
+        }
+        i += 1;
+    }
+
+    let (names, dump_options) = split_inputs(dump_args);
+    options.dump_options = dump_options;
+    options.input_file = match &names[..] {
+        [name] => name.clone(),
+        [] => return Err("No input file specified".to_string()),
+        [_, extra, ..] => return Err(format!("Unexpected argument: {}", extra)),
+    };
+    Ok(options)
+}
+
+/// Run minimize with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let options = parse_args_from(args).unwrap_or_else(|e| usage_error(&e));
+    if options.output_file.is_none() && io::stdout().is_terminal() {
+        usage_error("Won't write binary input to a terminal; redirect stdout or use --output");
+    }
+    let filename = &options.input_file;
+    let (decoder, data) = read_input(filename, options.decoder)?;
+    let data = match decoder {
+        Decoder::Asn1 if pem::looks_like_pem(&data) => pem_content(&data)?,
+        _ => data,
+    };
+    let dump_args = file_args(&options.dump_options, filename);
+    let findings = |candidate: &[u8]| check(&dump_args, decoder, candidate);
+
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let original = findings(&data)?;
+    let wanted = match options.wanted {
+        Some(wanted) if original.iter().any(|d| wanted.matches(d)) => wanted,
+        Some(wanted) => {
+            return Err(invalid(format!(
+                "The dump of '{}' has no {} matching '{}'",
+                filename,
+                wanted.severity.as_str(),
+                wanted.text
+            )));
+        }
+        None => first_finding(&original).ok_or_else(|| {
+            invalid(format!(
+                "The dump of '{}' has no errors or warnings to keep",
+                filename
+            ))
+        })?,
+    };
+
+    let keeps = |candidate: &[u8]| {
+        findings(candidate).is_ok_and(|found| found.iter().any(|d| wanted.matches(d)))
+    };
+    let (minimized, tries) = minimize(&data, decoder, keeps);
+    match &options.output_file {
+        Some(path) => fs::write(path, &minimized)
+            .map_err(|e| DumpError::io(format!("Can't write '{}'", path), e))?,
+        None => {
+            let mut out = io::stdout().lock();
+            out.write_all(&minimized)?;
+            out.flush()?;
+        }
+    }
+    eprintln!(
+        "Cut '{}' from {} to {} bytes in {} tries, keeping the {} '{}'",
+        filename,
+        data.len(),
+        minimized.len(),
+        tries,
+        wanted.severity.as_str(),
+        wanted.text
+    );
+    Ok(EXIT_CLEAN)
+}
+
+/// The errors and warnings from dumping `data` as the file in `args`
+fn check(args: &[String], decoder: Decoder, data: &[u8]) -> io::Result<Vec<Diagnostic>> {
+    let (_, diagnostics, _) = match decoder {
+        Decoder::Asn1 => dumper::check_to::<Asn1Dumper>(args, Some(data.to_vec()), Output::sink())?,
+        Decoder::Cbor => dumper::check_to::<CborDumper>(args, Some(data.to_vec()), Output::sink())?,
+    };
+    Ok(diagnostics)
+}
+
+/// The finding kept when none is named: the first error, or the first
+/// warning if there are no errors, by its code and the kind of its message
+fn first_finding(diagnostics: &[Diagnostic]) -> Option<Wanted> {
+    [Severity::Error, Severity::Warning]
+        .into_iter()
+        .find_map(|severity| diagnostics.iter().find(|d| d.severity == severity))
+        .map(|diagnostic| Wanted {
+            severity: diagnostic.severity,
+            text: diagnostic.code.to_string(),
+            kind: Some(message_kind(&diagnostic.message)),
+        })
+}
+
+/// Remove and shorten elements of `data` for as long as `keeps` holds for
+/// what's left, returning the smallest input found and the number of inputs
+/// tried. Elements are taken in the order they start, so that each is tried
+/// whole before those inside it, in passes until one changes nothing.
+fn minimize(
+    data: &[u8],
+    decoder: Decoder,
+    mut keeps: impl FnMut(&[u8]) -> bool,
+) -> (Vec<u8>, usize) {
+    let mut data = data.to_vec();
+    let mut tries = 0;
+    loop {
+        let mut changed = false;
+        // The root, holding the top-level elements, is 0
+        let mut index = 1;
+        loop {
+            let root = Parser::root(&data, decoder);
+            let Some(element) = root.find(index) else {
+                break;
+            };
+            let mut edits = Vec::new();
+            if element.removable {
+                edits.push(Edit::Remove(index));
+            }
+            if let Some(length) = element.shortenable() {
+                edits.push(Edit::Shorten(index, length / 2));
+            }
+            let kept = edits.into_iter().find_map(|edit| {
+                let mut candidate = Vec::new();
+                root.write(&data, decoder, edit, &mut candidate);
+                if candidate.is_empty() {
+                    return None;
+                }
+                tries += 1;
+                keeps(&candidate).then_some(candidate)
+            });
+            match kept {
+                // Every edit makes the input shorter, so this ends
+                Some(candidate) => {
+                    data = candidate;
+                    changed = true;
+                }
+                None => index += 1,
+            }
+        }
+        if !changed {
+            return (data, tries);
+        }
+    }
+}
+
+/// How an element's header gives the size of its content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Size {
+    Bytes(u64), // A length
+    Items(u64), // A count of CBOR items, or of a map's pairs
+    Indefinite, // Up to an end marker
+    Fixed,      // Whatever is in it, as for a CBOR tag's one item
+}
+
+/// A change to try
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Remove(usize),         // The element with this index
+    Shorten(usize, usize), // An element's content, to this many bytes
+}
+
+/// A TLV or CBOR item of the input, as much of it as is there
+#[derive(Debug)]
+struct Element {
+    index: usize, // In the order the elements start
+    start: usize,
+    content: usize, // Just past the header
+    end: usize,     // Where what's there of it ends
+    size: Size,
+    children: Option<Vec<Element>>, // None for a primitive
+    pairs: bool,                    // The children are a map's keys and values
+    removable: bool,                // Not a tag's item
+}
+
+impl Element {
+    fn find(&self, index: usize) -> Option<&Element> {
+        if self.index == index {
+            return Some(self);
+        }
+        let children = self.children.as_ref()?;
+        let last = children.iter().rposition(|child| child.index <= index)?;
+        children[last].find(index)
+    }
+
+    /// The length of the content of a primitive whose header gives one
+    fn shortenable(&self) -> Option<usize> {
+        match self.size {
+            Size::Bytes(_) if self.children.is_none() && self.end > self.content => {
+                Some(self.end - self.content)
+            }
+            _ => None,
+        }
+    }
+
+    /// Write the element to `out` with `edit` made, changing its header
+    /// when its content has changed. A length or count is brought down by as
+    /// much as the content is, whatever it was
+    fn write(&self, data: &[u8], decoder: Decoder, edit: Edit, out: &mut Vec<u8>) {
+        let mut content = Vec::new();
+        let mut removed = 0;
+        match &self.children {
+            None => {
+                let bytes = &data[self.content..self.end];
+                match edit {
+                    Edit::Shorten(index, length) if index == self.index => {
+                        content.extend_from_slice(&bytes[..length])
+                    }
+                    _ => content.extend_from_slice(bytes),
+                }
+            }
+            Some(children) => {
+                for (i, child) in children.iter().enumerate() {
+                    let partner = children.get(i ^ 1).filter(|_| self.pairs);
+                    if [Some(child), partner]
+                        .into_iter()
+                        .flatten()
+                        .any(|element| edit == Edit::Remove(element.index))
+                    {
+                        removed += 1;
+                        continue;
+                    }
+                    child.write(data, decoder, edit, &mut content);
+                }
+                // Whatever couldn't be read as elements stays as it is
+                let children_end = children.last().map_or(self.content, |child| child.end);
+                content.extend_from_slice(&data[children_end..self.end]);
+            }
+        }
+        let present = (self.end - self.content) as u64;
+        let argument = match self.size {
+            Size::Bytes(length) if content.len() as u64 != present => {
+                Some(length - present + content.len() as u64)
+            }
+            Size::Items(count) if removed > 0 => match self.pairs {
+                true => Some(count - (removed as u64).div_ceil(2)),
+                false => Some(count - removed as u64),
+            },
+            _ => None,
+        };
+        let header = &data[self.start..self.content];
+        match (argument, decoder) {
+            (Some(length), Decoder::Asn1) => out.extend(asn1_header(header, length)),
+            (Some(argument), Decoder::Cbor) => out.extend(cbor_head(header, argument)),
+            (None, _) => out.extend_from_slice(header),
+        }
+        out.extend(content);
+    }
+}
+
+/// A TLV header with its length changed, in the shortest form unless the
+/// one it had was longer than need be, in which case in as many octets as
+/// before if they hold it
+fn asn1_header(header: &[u8], length: u64) -> Vec<u8> {
+    let id_len = match header[0] & TAG_MASK == TAG_MASK {
+        true => {
+            2 + header[1..]
+                .iter()
+                .take_while(|&&b| b & LEN_XTND != 0)
+                .count()
+        }
+        false => 1,
+    };
+    let mut out = header[..id_len].to_vec();
+    let octets = &header[id_len + 1..];
+    let old = octets.iter().fold(0, |n, &b| n << 8 | b as u64);
+    let minimal = octets.is_empty() || (old >= LEN_XTND as u64 && octets.len() == width(old));
+    let octets = if minimal { 0 } else { octets.len() };
+    if octets == 0 && length < LEN_XTND as u64 {
+        out.push(length as u8);
+    } else {
+        let octets = octets.max(width(length)).max(1);
+        out.push(LEN_XTND | octets as u8);
+        out.extend_from_slice(&length.to_be_bytes()[8 - octets..]);
+    }
+    out
+}
+
+/// A CBOR head with its argument changed, in the shortest form unless the
+/// one it had was longer than need be, in which case at least as wide
+fn cbor_head(head: &[u8], argument: u64) -> Vec<u8> {
+    let shortest = |argument: u64| match argument {
+        0..=23 => 0,
+        _ => width(argument).next_power_of_two(),
+    };
+    let old = head[1..].iter().fold(0, |n, &b| n << 8 | b as u64);
+    let old_width = head.len() - 1;
+    let width = match old_width == shortest(old) {
+        true => shortest(argument),
+        false => old_width.max(shortest(argument)),
+    };
+    let ai = match width {
+        0 => argument as u8,
+        1 => AI_1BYTE,
+        2 => AI_2BYTES,
+        4 => AI_4BYTES,
+        _ => AI_8BYTES,
+    };
+    let mut out = vec![head[0] & 0xE0 | ai];
+    out.extend_from_slice(&argument.to_be_bytes()[8 - width..]);
+    out
+}
+
+/// The number of bytes `n` takes
+fn width(n: u64) -> usize {
+    (64 - n.leading_zeros() as usize).div_ceil(8)
+}
+
+/// Reads an input into elements, as far as it can
+struct Parser<'a> {
+    data: &'a [u8],
+    decoder: Decoder,
+    next_index: usize,
+}
+
+impl Parser<'_> {
+    /// The whole input as an element with no header, holding the top-level
+    /// ones
+    fn root(data: &[u8], decoder: Decoder) -> Element {
+        let mut parser = Parser {
+            data,
+            decoder,
+            next_index: 1,
+        };
+        let children = parser.elements(0, data.len(), u64::MAX, 0, true).0;
+        Element {
+            index: 0,
+            start: 0,
+            content: 0,
+            end: data.len(),
+            size: Size::Fixed,
+            children: Some(children),
+            pairs: false,
+            removable: false,
+        }
+    }
+
+    /// Up to `count` elements from `pos` to `limit`, stopping before an end
+    /// marker or what can't be read, and where the last of them ends. At the
+    /// top level, where nothing can end, an end marker is an element like
+    /// any other, to be removed if it can be
+    fn elements(
+        &mut self,
+        mut pos: usize,
+        limit: usize,
+        count: u64,
+        depth: usize,
+        removable: bool,
+    ) -> (Vec<Element>, usize) {
+        let mut elements = Vec::new();
+        while pos < limit && (elements.len() as u64) < count && depth < MAX_DEPTH {
+            if depth > 0 && self.at_end_marker(pos, limit) {
+                break;
+            }
+            let element = match self.decoder {
+                Decoder::Asn1 => self.tlv(pos, limit, depth, removable),
+                Decoder::Cbor => self.item(pos, limit, depth, removable),
+            };
+            let Some(element) = element else {
+                break;
+            };
+            pos = element.end;
+            elements.push(element);
+        }
+        (elements, pos)
+    }
+
+    fn at_end_marker(&self, pos: usize, limit: usize) -> bool {
+        match self.decoder {
+            Decoder::Asn1 => self.data[pos..limit].starts_with(&[0, 0]),
+            Decoder::Cbor => self.data[pos] == BREAK,
+        }
+    }
+
+    /// Where an indefinite-length element ends: past its end marker if
+    /// that's there
+    fn after_end_marker(&self, pos: usize, limit: usize) -> usize {
+        match (pos < limit && self.at_end_marker(pos, limit), self.decoder) {
+            (true, Decoder::Asn1) => pos + 2,
+            (true, Decoder::Cbor) => pos + 1,
+            (false, _) => pos,
+        }
+    }
+
+    fn take_index(&mut self) -> usize {
+        self.next_index += 1;
+        self.next_index - 1
+    }
+
+    /// The TLV at `pos`
+    fn tlv(&mut self, pos: usize, limit: usize, depth: usize, removable: bool) -> Option<Element> {
+        let header = header(self.data, pos).ok()?;
+        let content = pos + header.header_len;
+        if content > limit {
+            return None;
+        }
+        let index = self.take_index();
+        let (size, children, end) = match header.length {
+            Some(length) => {
+                let end = content + length.min(limit - content);
+                let children = header
+                    .constructed
+                    .then(|| self.elements(content, end, u64::MAX, depth + 1, true).0);
+                (Size::Bytes(length as u64), children, end)
+            }
+            None => {
+                let (children, after) = self.elements(content, limit, u64::MAX, depth + 1, true);
+                let end = self.after_end_marker(after, limit);
+                (Size::Indefinite, Some(children), end)
+            }
+        };
+        Some(Element {
+            index,
+            start: pos,
+            content,
+            end,
+            size,
+            children,
+            pairs: false,
+            removable,
+        })
+    }
+
+    /// The CBOR item at `pos`
+    fn item(&mut self, pos: usize, limit: usize, depth: usize, removable: bool) -> Option<Element> {
+        let major_type = self.data[pos] >> 5;
+        let width = match self.data[pos] & 0x1F {
+            0..=23 | AI_INDEFINITE => 0,
+            ai @ AI_1BYTE..=AI_8BYTES => 1 << (ai - AI_1BYTE),
+            _ => return None,
+        };
+        let content = pos + 1 + width;
+        if content > limit {
+            return None;
+        }
+        let argument = match self.data[pos] & 0x1F {
+            AI_INDEFINITE if (MAJOR_BYTES..=MAJOR_MAP).contains(&major_type) => None,
+            // A break at the top level, which is all head
+            AI_INDEFINITE if major_type == MAJOR_SIMPLE => Some(0),
+            AI_INDEFINITE => return None,
+            ai @ 0..=23 => Some(ai as u64),
+            _ => Some(
+                self.data[pos + 1..content]
+                    .iter()
+                    .fold(0, |n, &b| n << 8 | b as u64),
+            ),
+        };
+        let index = self.take_index();
+        let mut pairs = false;
+        let (size, children, end) = match (major_type, argument) {
+            (MAJOR_BYTES | MAJOR_TEXT, Some(length)) => {
+                let end = content + length.min((limit - content) as u64) as usize;
+                (Size::Bytes(length), None, end)
+            }
+            (MAJOR_ARRAY | MAJOR_MAP, Some(count)) => {
+                pairs = major_type == MAJOR_MAP;
+                let items = if pairs {
+                    count.saturating_mul(2)
+                } else {
+                    count
+                };
+                let (children, end) = self.elements(content, limit, items, depth + 1, true);
+                (Size::Items(count), Some(children), end)
+            }
+            // The chunks of a string, or the items of an array or map
+            (_, None) => {
+                pairs = major_type == MAJOR_MAP;
+                let (children, after) = self.elements(content, limit, u64::MAX, depth + 1, true);
+                let end = self.after_end_marker(after, limit);
+                (Size::Indefinite, Some(children), end)
+            }
+            (MAJOR_TAG, Some(_)) => {
+                let (children, end) = self.elements(content, limit, 1, depth + 1, false);
+                (Size::Fixed, Some(children), end)
+            }
+            // Integers, simple values and floats, which are all head
+            _ => (Size::Fixed, None, content),
+        };
+        Some(Element {
+            index,
+            start: pos,
+            content,
+            end,
+            size,
+            children,
+            pairs,
+            removable,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::hex::parse_hex;
+    use crate::der::DerBuilder;
+
+    fn args(slice: &[&str]) -> Vec<String> {
+        slice.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args_from(&args(&[
+            "minimize",
+            "--cbor",
+            "--error",
+            "truncated",
+            "-c",
+            "in",
+        ]))
+        .unwrap();
+        assert_eq!(options.decoder, Some(Decoder::Cbor));
+        assert_eq!(
+            options.wanted,
+            Some(Wanted {
+                severity: Severity::Error,
+                text: "truncated".to_string(),
+                kind: None,
+            })
+        );
+        assert_eq!(
+            (options.dump_options, options.input_file),
+            (args(&["minimize", "-c"]), "in".to_string())
+        );
+
+        for bad in [
+            &["minimize", "--follow", "in"][..],
+            &["minimize", "--error", "a", "--warning", "b", "in"],
+            &["minimize", "a", "b"],
+        ] {
+            assert!(parse_args_from(&args(bad)).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_minimize() {
+        // SEQUENCE { INTEGER 1, SEQUENCE { OCTET STRING, BOOLEAN TRUE }, NULL },
+        // of which the BOOLEAN has to stay
+        let data = DerBuilder::new()
+            .sequence(|b| {
+                b.integer(1)
+                    .sequence(|b| b.octet_string(&[5; 200]).boolean(true))
+                    .null()
+            })
+            .finish();
+        let keeps = |data: &[u8]| data.windows(3).any(|w| w == [0x01, 0x01, 0xFF]);
+        let (minimized, tries) = minimize(&data, Decoder::Asn1, keeps);
+        // With the lengths brought down, and into the short form
+        assert_eq!(minimized, [0x30, 0x05, 0x30, 0x03, 0x01, 0x01, 0xFF]);
+        assert!(tries < 20, "{}", tries);
+
+        // {1: h'0102', 2: 3}, keeping the 3; the count of pairs goes down
+        let data = [0xA2, 0x01, 0x42, 0x01, 0x02, 0x02, 0x03];
+        let (minimized, _) = minimize(&data, Decoder::Cbor, |data| data.contains(&0x03));
+        assert_eq!(minimized, [0xA1, 0x02, 0x03]);
+        assert_eq!(minimize(&[0x01], Decoder::Cbor, |_| true), (vec![0x01], 0));
+
+        // A length or argument in a longer form than need be stays in it
+        assert_eq!(
+            asn1_header(&[0x04, 0x82, 0x00, 0x05], 3),
+            [0x04, 0x82, 0x00, 0x03]
+        );
+        assert_eq!(asn1_header(&[0x04, 0x81, 0x80], 3), [0x04, 0x03]);
+        assert_eq!(cbor_head(&[0x59, 0x00, 0x05], 3), [0x59, 0x00, 0x03]);
+        assert_eq!(cbor_head(&[0x58, 0x20], 3), [0x43]);
+    }
+
+    #[test]
+    fn test_minimize_dump_error() {
+        // [1, "abc", [2, 3, 0x1C]], where 0x1C has reserved additional info
+        let data = [0x83, 0x01, 0x63, b'a', b'b', b'c', 0x83, 0x02, 0x03, 0x1C];
+        let dump_args = args(&["minimize", "-f", "in.cbor"]);
+        let original = check(&dump_args, Decoder::Cbor, &data).unwrap();
+        let wanted = first_finding(&original).unwrap();
+        assert_eq!(wanted.severity, Severity::Error);
+        let (minimized, _) = minimize(&data, Decoder::Cbor, |candidate| {
+            check(&dump_args, Decoder::Cbor, candidate)
+                .is_ok_and(|found| found.iter().any(|d| wanted.matches(d)))
+        });
+        assert_eq!(minimized, [0x1C]);
+    }
+
+    #[test]
+    fn test_minimize_stray_break() {
+        // {"a": 1, "b": [break, 2], "c": 3}, with a break out of place
+        let data = parse_hex("a3616101616282ff02616303").unwrap();
+        let dump_args = args(&["minimize", "in.cbor"]);
+        let original = check(&dump_args, Decoder::Cbor, &data).unwrap();
+        let wanted = first_finding(&original).unwrap();
+        assert_eq!(wanted.text, "unexpected-break");
+        let (minimized, _) = minimize(&data, Decoder::Cbor, |candidate| {
+            check(&dump_args, Decoder::Cbor, candidate)
+                .is_ok_and(|found| found.iter().any(|d| wanted.matches(d)))
+        });
+        assert_eq!(minimized, [0xFF]);
+    }
+
+    #[test]
+    fn test_minimize_truncated() {
+        // A certificate whose signature BIT STRING is cut short
+        let cert = DerBuilder::new()
+            .sequence(|b| {
+                b.sequence(|b| b.explicit(0, |b| b.integer(2)).integer(1))
+                    .sequence(|b| b.oid("1.2.840.113549.1.1.11").null())
+                    .bit_string(&[0x5A; 100], 0)
+            })
+            .finish();
+        let data = &cert[..cert.len() - 40];
+        let dump_args = args(&["minimize", "in.der"]);
+        let original = check(&dump_args, Decoder::Asn1, data).unwrap();
+        let wanted = first_finding(&original).unwrap();
+        assert!(original[0].message.contains("BIT STRING"), "{:?}", original);
+        let (minimized, _) = minimize(data, Decoder::Asn1, |candidate| {
+            check(&dump_args, Decoder::Asn1, candidate)
+                .is_ok_and(|found| found.iter().any(|d| wanted.matches(d)))
+        });
+        // The same BIT STRING, still 40 bytes short, in what's left of the
+        // SEQUENCE holding it
+        assert!(minimized.len() < 10, "{:02X?}", minimized);
+        let root = Parser::root(&minimized, Decoder::Asn1);
+        let bit_string = root.find(2).unwrap();
+        assert_eq!(minimized[bit_string.start], 0x03);
+        assert_eq!(
+            bit_string.size,
+            Size::Bytes((bit_string.end - bit_string.content) as u64 + 40)
+        );
+        let found = check(&dump_args, Decoder::Asn1, &minimized).unwrap();
+        assert_eq!(
+            message_kind(&found[0].message),
+            message_kind(&original[0].message)
+        );
+    }
+}
@@ -0,0 +1,377 @@
+// The command line of the tools. asn1cbor runs one of several commands,
+// named by its first argument; dumpasn1 and dumpcbor run the dump commands
+// directly. Every command takes its options after its name, and they all
+// report usage errors and set the exit status the same way. The asn1cbor
+// commands tell ASN.1 from CBOR themselves unless told which it is. Default
+// options can come from a config file.
+
+mod batch;
+#[cfg(feature = "tui")]
+mod browse;
+mod certlint;
+mod codegen;
+mod completions;
+mod config;
+mod convert;
+mod detect;
+mod diff;
+mod generate;
+mod inputs;
+mod lint;
+mod minimize;
+mod mutate;
+mod vectors;
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+
+use crate::asn1::pem;
+use crate::common::args::{option_value, parse_number};
+use crate::common::compressed;
+use crate::common::unified::unified_diff;
+use crate::diagnostics::{self, EXIT_CLEAN, EXIT_FAILURE};
+use crate::error::DumpError;
+use crate::render::Node;
+use crate::{cbor, dumpasn1, dumpcbor};
+pub(crate) use batch::dump_expanded;
+pub(crate) use config::with_defaults;
+pub(crate) use detect::dump as dump_auto;
+pub(crate) use detect::Decoder;
+pub(crate) use detect::{embedded as embedded_encoding, InputKind};
+pub(crate) use lint::validate;
+
+// Exit status when two inputs, or a dump and the one expected, differ, as
+// for diff(1)
+const EXIT_DIFFERENT: i32 = 1;
+
+/// A command of asn1cbor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Dump,
+    DumpAsn1,
+    DumpCbor,
+    Diff,
+    Convert,
+    Lint,
+    LintCert,
+    Minimize,
+    Mutate,
+    Browse,
+    GenCbor,
+    TestVectors,
+    Codegen,
+    Completions,
+}
+
+/// Each command's name and a one-line description for the help
+const COMMANDS: &[(Command, &str, &str)] = &[
+    (
+        Command::Dump,
+        "dump",
+        "Dump a file, detecting whether it's ASN.1 or CBOR",
+    ),
+    (
+        Command::DumpAsn1,
+        "dump-asn1",
+        "Dump ASN.1 DER, BER or PEM (the same as dumpasn1)",
+    ),
+    (
+        Command::DumpCbor,
+        "dump-cbor",
+        "Dump CBOR (the same as dumpcbor)",
+    ),
+    (
+        Command::Diff,
+        "diff",
+        "Show where the items in two files differ",
+    ),
+    (
+        Command::Convert,
+        "convert",
+        "Write the items in a file as PEM, DER, CBOR or a --format output",
+    ),
+    (
+        Command::Lint,
+        "lint",
+        "Check a file, listing only the errors and warnings found",
+    ),
+    (
+        Command::LintCert,
+        "lint-cert",
+        "Check certificates against the RFC 5280 profile",
+    ),
+    (
+        Command::Minimize,
+        "minimize",
+        "Cut a file down to a small one that still gets the same error",
+    ),
+    (
+        Command::Mutate,
+        "mutate",
+        "Write variants of a CBOR file with lengths, types and keys changed",
+    ),
+    (
+        Command::Browse,
+        "browse",
+        "Explore the decoded tree of a file in the terminal",
+    ),
+    (
+        Command::GenCbor,
+        "gen-cbor",
+        "Write random CBOR items, for fuzz corpora (the same as gencbor)",
+    ),
+    (
+        Command::TestVectors,
+        "test-vectors",
+        "Check the decoder against a file of CBOR test vectors",
+    ),
+    (
+        Command::Codegen,
+        "codegen",
+        "Write Rust types for CBOR samples, or an ASN.1 module for DER",
+    ),
+    (
+        Command::Completions,
+        "completions",
+        "Print a completion script for bash, zsh or fish",
+    ),
+];
+
+impl Command {
+    pub fn from_name(name: &str) -> Option<Command> {
+        COMMANDS
+            .iter()
+            .find(|(_, command_name, _)| *command_name == name)
+            .map(|(command, _, _)| *command)
+    }
+
+    pub fn name(self) -> &'static str {
+        COMMANDS
+            .iter()
+            .find(|(command, _, _)| *command == self)
+            .map_or("", |(_, name, _)| name)
+    }
+
+    /// Run the command with `args`, the first of which is the program name
+    /// shown in its help, returning the exit status
+    fn run(self, args: &[String]) -> io::Result<i32> {
+        // The dumpers add their options from the config file themselves, as
+        // dump and lint run them too
+        let (args, _) = match self {
+            Command::DumpAsn1 | Command::DumpCbor => (args.to_vec(), 0),
+            _ => config::with_defaults(self.name(), args),
+        };
+        match self {
+            Command::Dump => detect::run(&args),
+            Command::DumpAsn1 => dumpasn1::run(&args),
+            Command::DumpCbor => dumpcbor::run(&args),
+            Command::Diff => diff::run(&args),
+            Command::Convert => convert::run(&args),
+            Command::Lint => lint::run(&args),
+            Command::LintCert => certlint::run(&args),
+            Command::Minimize => minimize::run(&args),
+            Command::Mutate => mutate::run(&args),
+            Command::Browse => browse::run(&args),
+            Command::GenCbor => generate::run(&args),
+            Command::TestVectors => vectors::run(&args),
+            Command::Codegen => codegen::run(&args),
+            Command::Completions => completions::run(&args),
+        }
+    }
+}
+
+// Stands in for browse when it isn't built
+#[cfg(not(feature = "tui"))]
+mod browse {
+    use std::io;
+
+    pub fn run(_args: &[String]) -> io::Result<i32> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "browse needs asn1cbor built with the tui feature (cargo install --features tui)",
+        ))
+    }
+}
+
+/// Run a tool with the process's arguments and exit with its status. For
+/// asn1cbor `command` is None, and the first argument names the command.
+pub fn main(command: Option<Command>) -> ! {
+    let args: Vec<String> = env::args().collect();
+    let result = config::install().and_then(|()| match command {
+        Some(command) => command.run(&args),
+        None => run_named(&args),
+    });
+    let status = match result {
+        Ok(status) => status,
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => EXIT_CLEAN,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            diagnostics::failure_status(&e)
+        }
+    };
+    std::process::exit(status);
+}
+
+/// Run the command named by the first argument, passing it the rest
+fn run_named(args: &[String]) -> io::Result<i32> {
+    let program = args.first().map_or("asn1cbor", String::as_str);
+    let name = match args.get(1) {
+        Some(name) => name.as_str(),
+        None => usage_error("No command given"),
+    };
+    match name {
+        "-h" | "--help" | "help" => {
+            print_help(program);
+            return Ok(EXIT_CLEAN);
+        }
+        "-V" | "--version" => {
+            println!("asn1cbor {}", env!("CARGO_PKG_VERSION"));
+            return Ok(EXIT_CLEAN);
+        }
+        _ => {}
+    }
+    let command = Command::from_name(name)
+        .unwrap_or_else(|| usage_error(&format!("Unknown command: {}", name)));
+
+    // The command's help names it after the program
+    let mut command_args = vec![format!("{} {}", program, name)];
+    command_args.extend_from_slice(&args[2..]);
+    command.run(&command_args)
+}
+
+fn print_help(program_name: &str) {
+    println!("ASN.1 and CBOR Tools - Rust Implementation");
+    println!("Based on the concepts from dumpasn1.c by Peter Gutmann\n");
+    println!("Usage: {} <command> [OPTIONS]", program_name);
+    println!("\nCOMMANDS:");
+    for (_, name, description) in COMMANDS {
+        println!("  {:<12} {}", name, description);
+    }
+    println!("\nOPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  -V, --version           Show the version and exit");
+    println!(
+        "\nUse '{} <command> --help' for the options of a command.",
+        program_name
+    );
+}
+
+/// Report a mistake in the arguments and exit
+pub(crate) fn usage_error(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    eprintln!("\nUse --help for usage information");
+    std::process::exit(EXIT_FAILURE);
+}
+
+/// Read a file, reporting which one couldn't be read
+fn read_file(filename: &str) -> io::Result<Vec<u8>> {
+    fs::read(filename).map_err(|e| DumpError::io(format!("Can't open '{}'", filename), e).into())
+}
+
+/// Read an input file, decompressing it if it's gzipped or zstd-compressed
+fn read_data(filename: &str) -> io::Result<Vec<u8>> {
+    compressed::decompressed(read_file(filename)?)
+        .map_err(|e| DumpError::io(format!("Can't decompress '{}'", filename), e).into())
+}
+
+/// Compare a dump with the expected one in `filename`, for --expect,
+/// printing a unified diff if they differ. Returns the exit status
+pub(crate) fn check_expected(filename: &str, dump: &str) -> io::Result<i32> {
+    let expected = read_file(filename)?;
+    match unified_diff(filename, "dump", &String::from_utf8_lossy(&expected), dump) {
+        None => Ok(EXIT_CLEAN),
+        Some(diff) => {
+            io::stdout().write_all(diff.as_bytes())?;
+            Ok(EXIT_DIFFERENT)
+        }
+    }
+}
+
+/// The value of --jobs, at `args[*i]`, moving `i` on past it
+fn jobs_option(args: &[String], i: &mut usize) -> usize {
+    let jobs = option_value(args, i)
+        .and_then(|value| parse_number(value, "jobs"))
+        .unwrap_or_else(|e| usage_error(&e));
+    if jobs == 0 {
+        usage_error("--jobs must be at least 1");
+    }
+    jobs
+}
+
+/// The decoder that --asn1 or --cbor asks for, or None for --auto
+fn decoder_option(arg: &str) -> Option<Decoder> {
+    match arg {
+        "--asn1" => Some(Decoder::Asn1),
+        "--cbor" => Some(Decoder::Cbor),
+        _ => None,
+    }
+}
+
+/// Read a file and the decoder for it, the one given or else the one its
+/// content calls for. Base64 content is decoded
+fn read_input(filename: &str, decoder: Option<Decoder>) -> io::Result<(Decoder, Vec<u8>)> {
+    let data = read_data(filename)?;
+    match decoder {
+        Some(decoder) => Ok((decoder, data)),
+        None => detect::resolve(filename, data),
+    }
+}
+
+/// The DER in the PEM blocks of `data`, one after the other
+fn pem_content(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut der = Vec::new();
+    for block in pem::parse_pem(data) {
+        let content = block.data.map_err(|msg| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Can't decode {} block: {}", block.label, msg),
+            )
+        })?;
+        der.extend_from_slice(&content);
+    }
+    Ok(der)
+}
+
+/// Describe a file's items as render nodes, decoding it as CBOR or as
+/// ASN.1 DER or PEM
+fn file_nodes(data: &[u8], decoder: Decoder, show_secrets: bool) -> io::Result<Vec<Node>> {
+    match decoder {
+        Decoder::Cbor => Ok(cbor::to_nodes(&cbor::parse_slice(data)?)),
+        Decoder::Asn1 => dumpasn1::to_nodes(data, show_secrets),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_names() {
+        for (command, name, _) in COMMANDS {
+            assert_eq!(Command::from_name(name), Some(*command));
+            assert_eq!(command.name(), *name);
+        }
+        assert_eq!(Command::from_name("dump-der"), None);
+    }
+
+    #[test]
+    fn test_dumper_patterns() {
+        let dir = std::env::temp_dir().join(format!("asn1cbor-dumpers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.der"), [0x02, 0x01, 0x05]).unwrap();
+        std::fs::write(dir.join("b.cbor"), [0x82, 0x01]).unwrap();
+        let run = |command: Command, pattern: &str| {
+            let args = [
+                command.name().to_string(),
+                dir.join(pattern).display().to_string(),
+            ];
+            command.run(&args)
+        };
+        let asn1 = run(Command::DumpAsn1, "*.der");
+        let cbor = run(Command::DumpCbor, "*.cbor");
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(asn1.unwrap(), EXIT_CLEAN);
+        assert_eq!(cbor.unwrap(), diagnostics::EXIT_ERRORS);
+    }
+}
@@ -0,0 +1,113 @@
+// asn1cbor mutate: variants of a well-formed CBOR file, each with one
+// structural change, for testing how parsers cope with data that's nearly
+// right. Lengths, major types, map keys and string contents are changed
+// rather than random bytes, so that most of each variant still parses and
+// the change is reached. Variants are written the way gen-cbor writes items.
+
+use std::io::{self, IsTerminal};
+
+use super::generate::{reported_seed, write_items};
+use super::{read_data, usage_error};
+use crate::cbor::CborMutator;
+use crate::common::args::{option_value, parse_number};
+use crate::diagnostics::EXIT_CLEAN;
+
+/// Options for mutate
+#[derive(Debug)]
+struct Options {
+    seed: Option<u64>, // None to take one from the clock
+    count: usize,
+    output_dir: Option<String>,
+    input_file: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            seed: None,
+            count: 1,
+            output_dir: None,
+            input_file: String::new(),
+        }
+    }
+}
+
+fn print_help(program_name: &str) {
+    println!("Usage: {} [OPTIONS] <seed_file>", program_name);
+    println!("\nWrites variants of a well-formed CBOR file, each with one of its items changed:");
+    println!("a string, array or map given a different length, an item a different major");
+    println!("type, a map pair repeated so that its key is there twice, or a string cut");
+    println!("short. The variants go to stdout as a CBOR sequence or to a directory a file");
+    println!("each. The same seed always gives the same variants.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!("  --seed <n>              Seed for the changes (default: from the clock, reported");
+    println!("                          on stderr)");
+    println!("  -n, --count <n>         Number of variants to write (default: 1)");
+    println!("  -o <dir>                Write each variant to its own file in <dir>, which is");
+    println!("                          created if need be");
+    println!("\nEXAMPLE:");
+    println!("  {} --seed 1 -n 1000 -o corpus request.cbor", program_name);
+}
+
+fn parse_args_from(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut input_file = None;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                std::process::exit(0);
+            }
+            "--seed" => options.seed = Some(parse_number(option_value(args, &mut i)?, "seed")?),
+            "-n" | "--count" => options.count = parse_number(option_value(args, &mut i)?, "count")?,
+            "-o" => options.output_dir = Some(option_value(args, &mut i)?.to_string()),
+            arg if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            arg if input_file.is_none() => input_file = Some(arg.to_string()),
+            arg => return Err(format!("Unexpected argument: {}", arg)),
+        }
+        i += 1;
+    }
+    options.input_file = input_file.ok_or("No seed file specified")?;
+    Ok(options)
+}
+
+/// Run mutate with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let options = parse_args_from(args).unwrap_or_else(|e| usage_error(&e));
+    if options.output_dir.is_none() && io::stdout().is_terminal() {
+        usage_error("Won't write binary CBOR to a terminal; redirect stdout or use -o <dir>");
+    }
+    let data = read_data(&options.input_file)?;
+    let seed = options.seed.unwrap_or_else(reported_seed);
+    let mut mutator = CborMutator::new(&data, seed)?;
+    write_items(options.output_dir.as_deref(), options.count, || {
+        mutator.mutate()
+    })?;
+    Ok(EXIT_CLEAN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let args: Vec<String> = ["mutate", "--seed", "7", "-n", "3", "seed.cbor"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = parse_args_from(&args).unwrap();
+        assert_eq!((options.seed, options.count), (Some(7), 3));
+        assert_eq!(options.input_file, "seed.cbor");
+        for bad in [
+            &["mutate"][..],
+            &["mutate", "a", "b"],
+            &["mutate", "-x", "a"],
+        ] {
+            let args: Vec<String> = bad.iter().map(|s| s.to_string()).collect();
+            assert!(parse_args_from(&args).is_err(), "{:?}", bad);
+        }
+    }
+}
@@ -0,0 +1,422 @@
+// asn1cbor test-vectors: run files of CBOR test vectors through the decoder
+// and the diagnostic notation renderer, and list the vectors that come out
+// differently. Two formats are read. A JSON array is a file in the format of
+// the cbor/test-vectors repository, such as RFC 8949 Appendix A: each vector
+// has its encoding in hex and either the value decoded, as JSON, or its
+// diagnostic notation where JSON can't hold the value. Vectors marked
+// "roundtrip" must also encode back to the same bytes with preferred
+// serialization. The decoder doesn't keep the chunks of indefinite-length
+// strings, so those are compared with their chunks joined. A JSON object is
+// an example in the format of the cose-wg Examples repository, whose output
+// must decode to the COSE message its input describes, with that message's
+// tag and layout. Examples marked "fail" are meant to fail verification, so
+// for them only decoding is checked. Their cbor_diag isn't compared: it's
+// written by hand, spacing and comments and all.
+
+use std::io;
+
+use super::inputs::expand_inputs;
+use super::{read_data, usage_error, EXIT_DIFFERENT};
+use crate::cbor::cose;
+use crate::cbor::{
+    bignum, parse_slice, CborEncoder, CborItem, CborValue, TAG_BIGNUM_NEG, TAG_BIGNUM_POS,
+};
+use crate::common::hex::{hex_lower, parse_hex};
+use crate::common::json::{self, Value};
+use crate::diagnostics::EXIT_CLEAN;
+use crate::error::DumpError;
+use crate::render::diag_notation;
+
+// The input sections of cose-wg examples, and the messages they describe
+const EXAMPLE_MESSAGES: &[(&str, u64)] = &[
+    ("encrypted", 16),
+    ("mac0", 17),
+    ("sign0", 18),
+    ("enveloped", 96),
+    ("mac", 97),
+    ("sign", 98),
+];
+
+fn print_help(program_name: &str) {
+    println!("Usage: {} <vectors.json>...", program_name);
+    println!("\nDecodes each of the test vectors in JSON files in the format of the");
+    println!("cbor/test-vectors repository, such as the examples of RFC 8949 Appendix A,");
+    println!("and lists those whose value, diagnostic notation or preferred encoding isn't");
+    println!("the one the file gives, exiting with 1 if there are any. Files of the");
+    println!("cose-wg Examples repository are checked to decode to the COSE message their");
+    println!("input describes. Directories and glob patterns stand for the files in them.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+}
+
+/// Run test-vectors with `args`, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    let mut names = Vec::new();
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                return Ok(EXIT_CLEAN);
+            }
+            arg if arg.starts_with('-') => usage_error(&format!("Unknown option: {}", arg)),
+            arg => names.push(arg.to_string()),
+        }
+    }
+    if names.is_empty() {
+        usage_error("No test vector file specified");
+    }
+
+    let inputs = expand_inputs(&names)?;
+    let mut count = 0;
+    let mut failures = Vec::new();
+    for input_file in &inputs {
+        let data = read_data(input_file)?;
+        let (file_count, file_failures) = String::from_utf8(data)
+            .map_err(|e| e.to_string())
+            .and_then(|text| check_vectors(&text))
+            .map_err(|e| DumpError::malformed(None, &[], format!("{}: {}", input_file, e)))?;
+        count += file_count;
+        failures.extend(file_failures.into_iter().map(|failure| {
+            if inputs.len() > 1 {
+                format!("{}: {}", input_file, failure)
+            } else {
+                failure
+            }
+        }));
+    }
+    for failure in &failures {
+        println!("{}", failure);
+    }
+    println!(
+        "{} vector(s): {} passed, {} failed",
+        count,
+        count - failures.len(),
+        failures.len()
+    );
+    Ok(if failures.is_empty() {
+        EXIT_CLEAN
+    } else {
+        EXIT_DIFFERENT
+    })
+}
+
+/// Check every vector in the text of a test file, returning how many there
+/// are and a line for each that fails
+fn check_vectors(text: &str) -> Result<(usize, Vec<String>), String> {
+    let vectors = match json::parse(text) {
+        Some(Value::Array(vectors)) => vectors,
+        Some(Value::Object(members)) => {
+            let failure = check_example(&members)?;
+            return Ok((1, failure.into_iter().collect()));
+        }
+        _ => return Err("neither a JSON array of test vectors nor a COSE example".to_string()),
+    };
+    let mut failures = Vec::new();
+    for (index, vector) in vectors.iter().enumerate() {
+        let Value::Object(members) = vector else {
+            return Err(format!("vector {} isn't a JSON object", index));
+        };
+        if let Some(failure) =
+            check_vector(members).map_err(|e| format!("vector {}: {}", index, e))?
+        {
+            failures.push(failure);
+        }
+    }
+    Ok((vectors.len(), failures))
+}
+
+/// The member of a JSON object with the name given
+fn object_member<'v, 'a>(members: &'v [(&'a str, Value<'a>)], name: &str) -> Option<&'v Value<'a>> {
+    members
+        .iter()
+        .find(|(key, _)| json::unescape(key).as_deref() == Some(name))
+        .map(|(_, value)| value)
+}
+
+/// Check a cose-wg example, returning what's wrong if it fails
+fn check_example(members: &[(&str, Value)]) -> Result<Option<String>, String> {
+    let title = match object_member(members, "title") {
+        Some(Value::String(title)) => json::unescape(title).ok_or("bad title string")?,
+        _ => "example".to_string(),
+    };
+    let hex = match object_member(members, "output") {
+        Some(Value::Object(output)) => match object_member(output, "cbor") {
+            Some(Value::String(hex)) => json::unescape(hex).ok_or("bad cbor string")?,
+            _ => return Err("no output.cbor hex string".to_string()),
+        },
+        _ => return Err("no output object".to_string()),
+    };
+    let data = parse_hex(&hex).ok_or("output.cbor isn't hex")?;
+    let item = match decode_one(&data) {
+        Ok(item) => item,
+        Err(e) => return Ok(Some(format!("{}: {}", title, e))),
+    };
+    if let Some(Value::Literal("true")) = object_member(members, "fail") {
+        return Ok(None);
+    }
+
+    // Examples that aren't of a message, such as those of keys, are only
+    // checked to decode
+    let Some(Value::Object(input)) = object_member(members, "input") else {
+        return Ok(None);
+    };
+    let Some(tag) = EXAMPLE_MESSAGES
+        .iter()
+        .find(|(section, _)| object_member(input, section).is_some())
+        .map(|(_, tag)| *tag)
+    else {
+        return Ok(None);
+    };
+    let message = cose::message(tag).expect("the example messages are COSE's");
+    Ok(message
+        .check(&item)
+        .err()
+        .map(|e| format!("{}: {}", title, e)))
+}
+
+/// The one item `data` holds, or why it doesn't hold one
+fn decode_one(data: &[u8]) -> Result<CborItem<'_>, String> {
+    match parse_slice(data) {
+        Ok(mut items) if items.len() == 1 => Ok(items.remove(0)),
+        Ok(items) => Err(format!("decodes as {} items", items.len())),
+        Err(e) => Err(format!("doesn't decode: {}", e)),
+    }
+}
+
+/// Check one vector, returning what's wrong if it fails
+fn check_vector(members: &[(&str, Value)]) -> Result<Option<String>, String> {
+    let member = |name: &str| object_member(members, name);
+    let hex = match member("hex") {
+        Some(Value::String(hex)) => json::unescape(hex).ok_or("bad hex string")?,
+        _ => return Err("no hex string".to_string()),
+    };
+    let data = parse_hex(&hex).ok_or_else(|| format!("'{}' isn't hex", hex))?;
+
+    let item = match decode_one(&data) {
+        Ok(item) => item,
+        Err(e) => return Ok(Some(format!("{}: {}", hex, e))),
+    };
+    let diagnostic = diag_notation(&item.to_node(0));
+    match (member("decoded"), member("diagnostic")) {
+        (Some(decoded), _) => {
+            if !matches_decoded(decoded, &item) {
+                return Ok(Some(format!(
+                    "{}: expected {}, decoded as {}",
+                    hex,
+                    json_text(decoded),
+                    diagnostic
+                )));
+            }
+        }
+        (None, Some(Value::String(expected))) => {
+            let expected = json::unescape(expected).ok_or("bad diagnostic string")?;
+            let joined = joined_chunks(&expected);
+            if diagnostic != expected && joined.as_deref() != Some(diagnostic.as_str()) {
+                return Ok(Some(format!(
+                    "{}: expected {}, decoded as {}",
+                    hex, expected, diagnostic
+                )));
+            }
+        }
+        _ => return Err("neither a decoded value nor a diagnostic string".to_string()),
+    }
+
+    if let Some(Value::Literal("true")) = member("roundtrip") {
+        let encoded = CborEncoder::new().encode(&item.value);
+        if encoded != data {
+            return Ok(Some(format!(
+                "{}: encodes back as {}",
+                hex,
+                hex_lower(&encoded)
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether a JSON value is the one a CBOR item stands for. Integers are
+/// compared with the diagnostic notation the decoder renders for them.
+fn matches_decoded(expected: &Value, item: &CborItem) -> bool {
+    match (expected, &item.value) {
+        (Value::Literal("true"), CborValue::Boolean(true))
+        | (Value::Literal("false"), CborValue::Boolean(false))
+        | (Value::Literal("null"), CborValue::Null) => true,
+        (Value::Number(number), CborValue::Unsigned(_) | CborValue::Negative(_)) => {
+            *number == diag_notation(&item.to_node(0))
+        }
+        (Value::Number(number), value) => matches_number(number, value),
+        (Value::String(quoted), CborValue::Text(text)) => {
+            json::unescape(quoted).as_deref() == Some(text.as_ref())
+        }
+        (Value::Array(elements), CborValue::Array(items)) => {
+            elements.len() == items.len()
+                && elements
+                    .iter()
+                    .zip(items)
+                    .all(|(element, item)| matches_decoded(element, item))
+        }
+        (Value::Object(members), CborValue::Map(pairs)) => {
+            members.len() == pairs.len()
+                && members.iter().zip(pairs).all(|((key, member), (k, v))| {
+                    matches_decoded(&Value::String(key), k) && matches_decoded(member, v)
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Whether a JSON number is the value of a bignum or float
+fn matches_number(number: &str, value: &CborValue) -> bool {
+    let float = |f: f64| {
+        number
+            .parse::<f64>()
+            .is_ok_and(|n| n == f && n.is_sign_negative() == f.is_sign_negative())
+    };
+    match value {
+        CborValue::Tag(tag @ (TAG_BIGNUM_POS | TAG_BIGNUM_NEG), item) => match &item.value {
+            CborValue::Bytes(bytes) => number == bignum(bytes, *tag == TAG_BIGNUM_NEG),
+            _ => false,
+        },
+        CborValue::Float16(f) | CborValue::Float32(f) => float(*f as f64),
+        CborValue::Float64(f) => float(*f),
+        _ => false,
+    }
+}
+
+/// A JSON value written out compactly, for failure messages
+fn json_text(value: &Value) -> String {
+    match value {
+        Value::Object(members) => {
+            let members: Vec<String> = members
+                .iter()
+                .map(|(key, member)| format!("{}: {}", key, json_text(member)))
+                .collect();
+            format!("{{{}}}", members.join(", "))
+        }
+        Value::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(json_text).collect();
+            format!("[{}]", elements.join(", "))
+        }
+        Value::String(text) | Value::Number(text) | Value::Literal(text) => text.to_string(),
+    }
+}
+
+/// The diagnostic notation of an indefinite-length string, such as
+/// (_ h'0102', h'030405'), with its chunks joined into one string
+fn joined_chunks(diagnostic: &str) -> Option<String> {
+    let chunks = diagnostic.strip_prefix("(_ ")?.strip_suffix(')')?;
+    let (quote, prefix) = if chunks.starts_with('"') {
+        ('"', "")
+    } else {
+        ('\'', "h")
+    };
+    let mut joined = String::new();
+    let mut rest = chunks;
+    loop {
+        let body = rest.strip_prefix(prefix)?.strip_prefix(quote)?;
+        // Text chunks may hold escaped quotes
+        let mut end = None;
+        let mut escaped = false;
+        for (i, c) in body.char_indices() {
+            match c {
+                '\\' if !escaped => escaped = true,
+                c if c == quote && !escaped => {
+                    end = Some(i);
+                    break;
+                }
+                _ => escaped = false,
+            }
+        }
+        let end = end?;
+        joined.push_str(&body[..end]);
+        rest = &body[end + 1..];
+        if rest.is_empty() {
+            break;
+        }
+        rest = rest.strip_prefix(", ")?;
+    }
+    Some(format!("{}{}{}{}", prefix, quote, joined, quote))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_appendix_a() {
+        // The examples of RFC 8949 Appendix A
+        let text = include_str!("../../tests/vectors/appendix_a.json");
+        let (count, failures) = check_vectors(text).unwrap();
+        assert_eq!(count, 81);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn test_failures() {
+        let text = r#"[
+            {"hex": "1864", "decoded": 101},
+            {"hex": "f93c00", "roundtrip": true, "decoded": 1.0},
+            {"hex": "fa3f800000", "roundtrip": true, "decoded": 1.0},
+            {"hex": "f818", "diagnostic": "simple(24)"},
+            {"hex": "40", "diagnostic": "h'00'"},
+            {"hex": "3bffffffffffffffff", "decoded": -18446744073709551615}
+        ]"#;
+        let (count, failures) = check_vectors(text).unwrap();
+        assert_eq!(count, 6);
+        assert_eq!(failures[0], "1864: expected 101, decoded as 100");
+        assert_eq!(failures[1], "fa3f800000: encodes back as f93c00");
+        assert!(failures[2].starts_with("f818: doesn't decode: "));
+        assert_eq!(failures[3], "40: expected h'00', decoded as h''");
+        assert_eq!(
+            failures[4],
+            "3bffffffffffffffff: expected -18446744073709551615, \
+             decoded as -18446744073709551616"
+        );
+        assert!(check_vectors(r#"[{"decoded": 1}]"#).is_err());
+    }
+
+    #[test]
+    fn test_cose_example() {
+        // The COSE_Sign1 example sign1-pass-01, cut down to what's checked
+        let example = |tag: &str, fail: &str| {
+            format!(
+                r#"{{
+                    "title": "SIG1-01",
+                    {}
+                    "input": {{"plaintext": "This is the content.", "sign0": {{"alg": "ES256"}}}},
+                    "output": {{
+                        "cbor_diag": "18([h'A10126', {{4: h'3131'}}, h'54...', h'8E...'])",
+                        "cbor": "{}8443A10126A10442313154546869732069732074686520636F6E74656E742E58408EB33E4CA31D1C465AB05AAC34CC6B23D58FEF5C083106C4D25A91AEF0B0117E2AF9A291AA32E14AB834DC56ED2A223444547E01F11D3B0916E5A4C345CACB36"
+                    }}
+                }}"#,
+                fail, tag
+            )
+        };
+        assert_eq!(check_vectors(&example("D2", "")).unwrap(), (1, vec![]));
+        assert_eq!(
+            check_vectors(&example("D1", "")).unwrap().1,
+            ["SIG1-01: tag 17 isn't COSE_Sign1's tag 18"]
+        );
+        // A failing example needs only to decode
+        assert_eq!(
+            check_vectors(&example("D1", r#""fail": true,"#)).unwrap(),
+            (1, vec![])
+        );
+        // The message may be left untagged
+        assert_eq!(check_vectors(&example("", "")).unwrap(), (1, vec![]));
+        assert!(check_vectors(r#"{"title": "no output"}"#).is_err());
+    }
+
+    #[test]
+    fn test_joined_chunks() {
+        assert_eq!(
+            joined_chunks("(_ h'0102', h'030405')").unwrap(),
+            "h'0102030405'"
+        );
+        assert_eq!(
+            joined_chunks(r#"(_ "strea", "m\"ing")"#).unwrap(),
+            r#""stream\"ing""#
+        );
+        assert_eq!(joined_chunks("h'01'"), None);
+    }
+}
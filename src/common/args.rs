@@ -0,0 +1,114 @@
+// Reading option values from the command line
+
+use std::ops::Range;
+use std::str::FromStr;
+
+/// The value following the option at `args[*i]`, moving `i` on to it
+pub fn option_value<'a>(args: &'a [String], i: &mut usize) -> Result<&'a str, String> {
+    *i += 1;
+    args.get(*i)
+        .map(String::as_str)
+        .ok_or_else(|| format!("Missing value after {}", args[*i - 1]))
+}
+
+/// Parse a numeric option value, naming what it's for if it isn't a number
+pub fn parse_number<T: FromStr>(value: &str, what: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid number for {}: {}", what, value))
+}
+
+/// Parse an offset given in decimal or, with a 0x prefix, hex
+pub fn parse_offset(value: &str) -> Option<usize> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parse a byte window given as START..END, either of which can be left out
+/// for the start or end of the input, in decimal or hex like other offsets
+pub fn parse_range(value: &str) -> Result<Range<usize>, String> {
+    let invalid = || format!("Invalid range: {} (use START..END)", value);
+    let (start, end) = value.split_once("..").ok_or_else(invalid)?;
+    let bound = |text: &str, default| match text {
+        "" => Some(default),
+        text => parse_offset(text),
+    };
+    match (bound(start, 0), bound(end, usize::MAX)) {
+        (Some(start), Some(end)) if start < end => Ok(start..end),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse a run of bytes given as START:LEN, in decimal or hex like other
+/// offsets
+pub fn parse_span(value: &str) -> Result<Range<usize>, String> {
+    let invalid = || format!("Invalid byte range: {} (use START:LEN)", value);
+    let (start, length) = value.split_once(':').ok_or_else(invalid)?;
+    match (parse_offset(start), parse_offset(length)) {
+        (Some(start), Some(length)) if length > 0 => {
+            Ok(start..start.checked_add(length).ok_or_else(invalid)?)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_option_value() {
+        let args: Vec<String> = ["prog", "--to", "pem", "--label"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut i = 1;
+        assert_eq!(option_value(&args, &mut i), Ok("pem"));
+        assert_eq!(i, 2);
+        i = 3;
+        assert_eq!(
+            option_value(&args, &mut i),
+            Err("Missing value after --label".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number::<usize>("80", "width"), Ok(80));
+        assert_eq!(
+            parse_number::<usize>("-1", "width"),
+            Err("Invalid number for width: -1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("1234"), Some(1234));
+        assert_eq!(parse_offset("0x4D2"), Some(1234));
+        assert_eq!(parse_offset("tbsCertificate"), None);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("16..0x20"), Ok(16..32));
+        assert_eq!(parse_range("100.."), Ok(100..usize::MAX));
+        assert_eq!(parse_range("..8"), Ok(0..8));
+        for bad in ["16", "8..8", "9..2", "a..b", "1...3"] {
+            assert!(parse_range(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_span() {
+        assert_eq!(parse_span("16:4"), Ok(16..20));
+        assert_eq!(parse_span("0x1F0:0x10"), Ok(0x1F0..0x200));
+        for bad in ["16", "16:0", "16..20", ":4", "1:x"] {
+            assert!(parse_span(bad).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Color for the errors and warnings in a dump and in lint's lines, and for
+// the JSON dumpcbor --pretty-json lays out, with --color (or the config
+// file) saying when: always, never, or auto, which colors output going to a
+// terminal unless NO_COLOR is set
+
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal};
+
+/// When to color the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub const NAMES: &'static str = "auto, always or never";
+
+    pub fn from_name(name: &str) -> Option<ColorChoice> {
+        match name {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    /// Whether to color output written to stdout
+    pub fn for_stdout(self) -> bool {
+        self.enabled(io::stdout().is_terminal())
+    }
+
+    /// Whether to color output that goes to a terminal if `terminal` is set
+    pub fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            // https://no-color.org: set to anything but empty
+            ColorChoice::Auto => {
+                terminal && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+        }
+    }
+}
+
+/// What a piece of text is, which decides its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Error,
+    Warning,
+    JsonKey,
+    JsonString,
+    JsonNumber,
+    JsonLiteral, // true, false and null
+}
+
+impl Style {
+    fn escape(self) -> &'static str {
+        match self {
+            Style::Error => "\x1b[1;31m",     // Bold red
+            Style::Warning => "\x1b[1;33m",   // Bold yellow
+            Style::JsonKey => "\x1b[34m",     // Blue
+            Style::JsonString => "\x1b[32m",  // Green
+            Style::JsonNumber => "\x1b[36m",  // Cyan
+            Style::JsonLiteral => "\x1b[35m", // Magenta
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+/// Text to write in a style's color, or as it is when `colored` is off
+pub struct Painted<'a> {
+    pub style: Style,
+    pub text: &'a str,
+    pub colored: bool,
+}
+
+impl fmt::Display for Painted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.colored {
+            write!(f, "{}{}{}", self.style.escape(), self.text, RESET)
+        } else {
+            f.write_str(self.text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_painted() {
+        let painted = |colored| Painted {
+            style: Style::Warning,
+            text: "Warning:",
+            colored,
+        };
+        assert_eq!(painted(false).to_string(), "Warning:");
+        assert_eq!(painted(true).to_string(), "\x1b[1;33mWarning:\x1b[0m");
+        assert!(ColorChoice::Always.enabled(false));
+        assert!(!ColorChoice::Never.enabled(true));
+        assert!(!ColorChoice::Auto.enabled(false));
+        assert_eq!(ColorChoice::from_name("sometimes"), None);
+    }
+}
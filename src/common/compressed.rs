@@ -0,0 +1,157 @@
+// Compressed input. CT log exports, backups and firmware bundles often come
+// gzipped or zstd-compressed, so files starting with either's magic number
+// are decompressed whole before they're looked at. The decompressors are
+// behind the gzip and zstd features; without them such a file is still
+// recognized, and the error says which feature would read it.
+
+use std::fmt;
+use std::io;
+
+/// How a file is compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip, // RFC 1952
+    Zstd, // RFC 8878
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+
+impl Compression {
+    /// The compression of data starting with `prefix`, if it's compressed.
+    /// Neither magic number starts well-formed CBOR, and gzip's would be a
+    /// DER tag from far beyond the universal types in use
+    pub fn detect(prefix: &[u8]) -> Option<Compression> {
+        if prefix.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if prefix.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Decompress `data`, all the members or frames of it one after the
+    /// other
+    #[cfg_attr(not(any(feature = "gzip", feature = "zstd")), allow(unused_variables))]
+    pub fn decompress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut out = Vec::new();
+                io::copy(&mut flate2::read::MultiGzDecoder::new(data), &mut out)?;
+                Ok(out)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                let (mut out, mut input) = (Vec::new(), data);
+                while !input.is_empty() {
+                    let mut decoder = ruzstd::decoding::StreamingDecoder::new(&mut input)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+                    io::copy(&mut decoder, &mut out)?;
+                }
+                Ok(out)
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "the input is {0}-compressed, which needs a build with the {0} feature \
+                     (cargo install --features {0})",
+                    self
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        })
+    }
+}
+
+/// `data` decompressed if it's compressed, or as it is
+pub fn decompressed(data: Vec<u8>) -> io::Result<Vec<u8>> {
+    match Compression::detect(&data) {
+        Some(compression) => compression.decompress(&data),
+        None => Ok(data),
+    }
+}
+
+/// Read the rest of `reader` and decompress it, if the data waiting in it
+/// starts out compressed. Otherwise nothing is read, and None returned
+pub fn read_decompressed<R: io::BufRead>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let Some(compression) = Compression::detect(reader.fill_buf()?) else {
+        return Ok(None);
+    };
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    compression.decompress(&data).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Compression::detect(&[0x1F, 0x8B, 0x08]),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::detect(&[0x28, 0xB5, 0x2F, 0xFD, 0x00]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::detect(&[0x28, 0xB5]), None);
+        assert_eq!(Compression::detect(&[0x30, 0x03, 0x02, 0x01, 0x05]), None);
+        assert_eq!(
+            decompressed(vec![0x82, 0x01, 0x02]).unwrap(),
+            [0x82, 0x01, 0x02]
+        );
+    }
+
+    // [1, 2] in a zstd frame of one raw block
+    const ZSTD: &[u8] = &[
+        0x28, 0xB5, 0x2F, 0xFD, 0x20, 0x03, 0x19, 0x00, 0x00, 0x82, 0x01, 0x02,
+    ];
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn test_gzip() {
+        // [1, 2] gzipped twice over, as two members
+        let member = [
+            0x1F, 0x8B, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x6B, 0x62, 0x64, 0x02,
+            0x00, 0x91, 0x46, 0xE5, 0xEA, 0x03, 0x00, 0x00, 0x00,
+        ];
+        let data = [member, member].concat();
+        assert_eq!(decompressed(data).unwrap(), [0x82, 1, 2, 0x82, 1, 2]);
+        assert!(decompressed(member[..12].to_vec()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_zstd() {
+        assert_eq!(decompressed(ZSTD.to_vec()).unwrap(), [0x82, 1, 2]);
+        assert!(decompressed(ZSTD[..6].to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_read_decompressed() {
+        let mut reader = io::Cursor::new(vec![0x82, 0x01, 0x02]);
+        assert_eq!(read_decompressed(&mut reader).unwrap(), None);
+        assert_eq!(reader.position(), 0);
+        let result = read_decompressed(&mut io::Cursor::new(ZSTD));
+        if cfg!(feature = "zstd") {
+            assert_eq!(result.unwrap(), Some(vec![0x82, 1, 2]));
+        } else {
+            let err = result.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+            assert!(err.to_string().contains("--features zstd"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// The errors and warnings a dump has found, counted for the closing totals
+// and the exit status and kept in full for --errors-json and lint
+
+use crate::diagnostics::{self, Diagnostic, Severity};
+
+/// Errors and warnings found so far
+#[derive(Debug, Default)]
+pub struct Findings {
+    pub errors: usize,
+    pub warnings: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Findings {
+    /// Count a finding and keep it
+    pub fn record(&mut self, diagnostic: Diagnostic) {
+        match diagnostic.severity {
+            Severity::Error => self.errors += 1,
+            Severity::Warning => self.warnings += 1,
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Add the findings of a nested dump to these
+    pub fn merge(&mut self, other: Findings) {
+        self.errors += other.errors;
+        self.warnings += other.warnings;
+        self.diagnostics.extend(other.diagnostics);
+    }
+
+    /// Exit status for a dump that ran to the end with these findings
+    pub fn exit_status(&self) -> i32 {
+        diagnostics::exit_status(self.errors, self.warnings)
+    }
+
+    /// The "Errors: n" and "Warnings: n" lines ending a dump, for the counts
+    /// that aren't zero
+    pub fn totals(&self) -> String {
+        let mut totals = String::new();
+        if self.errors > 0 {
+            totals.push_str(&format!("Errors: {}\n", self.errors));
+        }
+        if self.warnings > 0 {
+            totals.push_str(&format!("Warnings: {}\n", self.warnings));
+        }
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::{EXIT_CLEAN, EXIT_ERRORS, EXIT_WARNINGS};
+
+    fn finding(severity: Severity) -> Diagnostic {
+        Diagnostic {
+            code: "test",
+            severity,
+            offset: None,
+            path: Vec::new(),
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_findings() {
+        let mut findings = Findings::default();
+        assert_eq!(findings.exit_status(), EXIT_CLEAN);
+        assert_eq!(findings.totals(), "");
+
+        findings.record(finding(Severity::Warning));
+        assert_eq!(findings.exit_status(), EXIT_WARNINGS);
+        assert_eq!(findings.totals(), "Warnings: 1\n");
+
+        let mut nested = Findings::default();
+        nested.record(finding(Severity::Error));
+        nested.record(finding(Severity::Warning));
+        findings.merge(nested);
+        assert_eq!((findings.errors, findings.warnings), (1, 2));
+        assert_eq!(findings.diagnostics.len(), 3);
+        assert_eq!(findings.exit_status(), EXIT_ERRORS);
+        assert_eq!(findings.totals(), "Errors: 1\nWarnings: 2\n");
+    }
+}
@@ -0,0 +1,126 @@
+// What the two dumpers have in common as seen from outside: options parsed
+// from arguments, a file or bytes dumped to an output, and the errors and
+// warnings found. Checking a file for asn1cbor lint, comparing a dump with
+// --expect and dumping to a string for the library are written once here,
+// for either dumper.
+
+use std::io;
+
+use crate::cli;
+use crate::common::color::{ColorChoice, Style};
+use crate::common::counters::Findings;
+use crate::diagnostics::{self, Diagnostic};
+use crate::output::{Captured, Output};
+
+/// A dumper, dumpasn1's or dumpcbor's
+pub trait Dumper: Sized {
+    /// A dumper with the options in `args`, and the input file they name
+    fn from_args(args: &[String]) -> Result<(Self, Option<String>), String>;
+
+    /// The positions in `args` of the input files they name, any number of
+    /// them, for commands that run the dumper on each in turn
+    fn input_positions(args: &[String]) -> Result<Vec<usize>, String>;
+
+    fn output(&mut self) -> &mut Output;
+
+    fn color(&self) -> ColorChoice;
+
+    fn findings(&mut self) -> &mut Findings;
+
+    /// Print errors in the dump where they're found rather than on stderr,
+    /// for a dump that's kept or thrown away whole
+    fn report_errors_inline(&mut self) {}
+
+    /// Dump the file named, or `input` as its content if it's been read
+    fn dump_file(&mut self, filename: &str, input: Option<Vec<u8>>) -> io::Result<()>;
+
+    /// Dump input held in memory, which has no name
+    fn dump_bytes(&mut self, data: &[u8]) -> io::Result<()>;
+}
+
+/// The input file named in `args`, for --auto to sniff before choosing a
+/// dumper
+pub fn input_file<D: Dumper>(args: &[String]) -> Result<String, String> {
+    D::from_args(args)?
+        .1
+        .ok_or_else(|| "No input file specified".to_string())
+}
+
+/// Check the file named in `args`, writing the dump to `out` with the
+/// errors in it, for asn1cbor lint (which throws the dump away) and dump on
+/// several files. Returns the file's name, the errors and warnings found and
+/// the exit status; input too malformed to finish is reported with the rest,
+/// and at the end of the dump, rather than as an error. `input` is the
+/// file's content if it's been read already
+pub fn check_to<D: Dumper>(
+    args: &[String],
+    input: Option<Vec<u8>>,
+    out: Output,
+) -> io::Result<(String, Vec<Diagnostic>, i32)> {
+    // Options meant for the other dumper fail only the files of this kind,
+    // not the others checked alongside them
+    let (mut dumper, filename) =
+        D::from_args(args).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let filename = filename.unwrap_or_else(|| cli::usage_error("No input file specified"));
+
+    // The dump, if kept, is printed on stdout
+    let colors = dumper.color().for_stdout();
+    *dumper.output() = out;
+    dumper.output().set_colors(colors);
+    dumper.report_errors_inline();
+    let status = match dumper.dump_file(&filename, input) {
+        Ok(()) => dumper.findings().exit_status(),
+        Err(e) if diagnostics::is_malformed(&e) => {
+            let out = dumper.output();
+            writeln!(out, "{} {}", out.paint(Style::Error, "Error:"), e);
+            out.flush()?;
+            diagnostics::failure_status(&e)
+        }
+        Err(e) => return Err(e),
+    };
+    let diagnostics = std::mem::take(&mut dumper.findings().diagnostics);
+    Ok((filename, diagnostics, status))
+}
+
+/// Dump a file for --expect and compare the dump, what would have been
+/// written to stdout, with the one in `expected`. Returns the exit status,
+/// which is whether they differ rather than what was found
+pub fn check_expected<D: Dumper>(
+    dumper: &mut D,
+    filename: &str,
+    input: Option<Vec<u8>>,
+    expected: &str,
+) -> io::Result<i32> {
+    let captured = Captured::default();
+    *dumper.output() = captured.output();
+    dumper.output().set_colors(false);
+    if let Err(e) = dumper.dump_file(filename, input) {
+        if !diagnostics::is_malformed(&e) {
+            return Err(e);
+        }
+        // Reported as it would be without --expect, on stderr
+        eprintln!("Error: {}", e);
+    }
+    dumper.output().flush()?;
+    cli::check_expected(expected, &captured.text())
+}
+
+/// Dump input held in memory with the options in `args`, returning the text
+/// of the dump with any error that stopped it at the end, for
+/// dump_asn1_to_string and dump_cbor_to_string
+pub fn dump_to_string<D: Dumper>(data: &[u8], args: &[String]) -> Result<String, String> {
+    let (mut dumper, input_file) = D::from_args(args)?;
+    if let Some(input_file) = input_file {
+        return Err(format!("Unexpected argument: {}", input_file));
+    }
+
+    let captured = Captured::default();
+    let colors = dumper.color().enabled(false);
+    *dumper.output() = captured.output();
+    dumper.output().set_colors(colors);
+    dumper.report_errors_inline();
+    if let Err(e) = dumper.dump_bytes(data) {
+        writeln!(dumper.output(), "Error: {}", e);
+    }
+    Ok(captured.text())
+}
@@ -0,0 +1,143 @@
+// Source code for embedding bytes in a program, as --emit-code writes it: a
+// Rust const or a C array holding the encoding of an item, with a comment
+// saying where in the input it came from
+
+/// The language of the code written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    C,
+}
+
+impl Language {
+    pub const NAMES: &'static str = "rust or c";
+
+    pub fn from_name(name: &str) -> Option<Language> {
+        match name.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Some(Language::Rust),
+            "c" => Some(Language::C),
+            _ => None,
+        }
+    }
+
+    /// The identifier for an array named after a field such as
+    /// tbsCertificate, BYTES (or bytes) for one with no name
+    pub fn identifier(self, name: Option<&str>) -> String {
+        let snake = name.map(snake_case).filter(|s| !s.is_empty());
+        let snake = match snake {
+            // Identifiers can't start with a digit
+            Some(s) if s.starts_with(|c: char| c.is_ascii_digit()) => format!("bytes_{}", s),
+            Some(s) => s,
+            None => "bytes".to_string(),
+        };
+        match self {
+            Language::Rust => snake.to_ascii_uppercase(),
+            Language::C => snake,
+        }
+    }
+}
+
+/// Bytes per line of the array
+const BYTES_PER_LINE: usize = 12;
+
+/// A declaration of `identifier` as an array of `bytes`, after a comment
+/// line holding `comment`
+pub fn byte_array(language: Language, identifier: &str, comment: &str, bytes: &[u8]) -> String {
+    let mut code = match language {
+        Language::Rust => format!(
+            "// {}\nconst {}: [u8; {}] = [\n",
+            comment,
+            identifier,
+            bytes.len()
+        ),
+        Language::C => format!(
+            "/* {} */\nstatic const unsigned char {}[{}] = {{\n",
+            comment,
+            identifier,
+            bytes.len()
+        ),
+    };
+    for row in bytes.chunks(BYTES_PER_LINE) {
+        let row: Vec<String> = row.iter().map(|b| format!("0x{:02X},", b)).collect();
+        code.push_str("    ");
+        code.push_str(&row.join(" "));
+        code.push('\n');
+    }
+    code.push_str(match language {
+        Language::Rust => "];\n",
+        Language::C => "};\n",
+    });
+    code
+}
+
+/// A name such as tbsCertificate or "CERTIFICATE REQUEST" in snake case,
+/// anything but letters and digits becoming an underscore
+pub(crate) fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            let boundary = c.is_ascii_uppercase()
+                && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit());
+            if boundary {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else if previous.is_some_and(|p| p.is_ascii_alphanumeric()) {
+            snake.push('_');
+        }
+        previous = Some(c);
+    }
+    snake.trim_end_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(
+            Language::Rust.identifier(Some("tbsCertificate")),
+            "TBS_CERTIFICATE"
+        );
+        assert_eq!(
+            Language::C.identifier(Some("subjectPublicKeyInfo")),
+            "subject_public_key_info"
+        );
+        assert_eq!(
+            Language::C.identifier(Some("CERTIFICATE REQUEST")),
+            "certificate_request"
+        );
+        assert_eq!(Language::Rust.identifier(Some("3 + 4")), "BYTES_3_4");
+        assert_eq!(Language::Rust.identifier(None), "BYTES");
+        assert_eq!(Language::C.identifier(Some("--")), "bytes");
+        assert_eq!(Language::from_name("C"), Some(Language::C));
+        assert_eq!(Language::from_name("go"), None);
+    }
+
+    #[test]
+    fn test_byte_array() {
+        let bytes: Vec<u8> = (0..14).collect();
+        assert_eq!(
+            byte_array(
+                Language::Rust,
+                "BYTES",
+                "item at offset 0, 14 bytes",
+                &bytes
+            ),
+            "// item at offset 0, 14 bytes\n\
+             const BYTES: [u8; 14] = [\n\
+             \x20   0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B,\n\
+             \x20   0x0C, 0x0D,\n\
+             ];\n"
+        );
+        assert_eq!(
+            byte_array(Language::C, "oid", "OID", &[0x06, 0x01, 0x00]),
+            "/* OID */\n\
+             static const unsigned char oid[3] = {\n\
+             \x20   0x06, 0x01, 0x00,\n\
+             };\n"
+        );
+    }
+}
@@ -0,0 +1,48 @@
+// Escaping decoded text before it reaches a terminal. A string in untrusted
+// input can hold newlines that fake lines of the dump, ANSI escape sequences
+// that move the cursor or retitle the window, or bidirectional overrides that
+// reorder what's shown, so the dumps write these as escapes unless
+// --raw-strings asks for the text as it is.
+
+use std::borrow::Cow;
+
+/// `text` with control characters and bidirectional formatting characters
+/// written as `\n`, `\r`, `\t` or `\u001b`-style escapes
+pub fn escape_controls(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(needs_escape) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if needs_escape(c) => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn needs_escape(c: char) -> bool {
+    // Control characters, and the embeddings, overrides, isolates and marks
+    // of Unicode's bidirectional algorithm
+    c.is_control()
+        || matches!(c, '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_controls() {
+        assert!(matches!(escape_controls("plain café"), Cow::Borrowed(_)));
+        assert_eq!(
+            escape_controls("a\nb\t\x1b[2J\u{7f}\u{9b}"),
+            "a\\nb\\t\\u001b[2J\\u007f\\u009b"
+        );
+        assert_eq!(escape_controls("abc\u{202E}fed"), "abc\\u202efed");
+    }
+}
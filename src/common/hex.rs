@@ -0,0 +1,83 @@
+// Hex formatting. The dumps show bytes as uppercase pairs, digests and
+// comparisons as one run of lowercase digits. Hex given on the command line
+// is read back the same ways, or with colons between the pairs.
+
+/// Bytes as uppercase hex pairs joined by `separator`
+pub fn hex_upper(bytes: &[u8], separator: &str) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Bytes as lowercase hex with nothing between them
+pub fn hex_lower(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Printable ASCII for a byte, or '.' for anything else
+pub fn printable(byte: u8) -> char {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+/// Format a row of bytes as hex padded to the width of `per_line` bytes,
+/// followed by their printable ASCII rendering
+pub fn hex_text_row(row: &[u8], per_line: usize) -> String {
+    let hex: String = row.iter().map(|b| format!("{:02X} ", b)).collect();
+    let text: String = row.iter().map(|&b| printable(b)).collect();
+    format!("{:width$} {}", hex, text, width = per_line * 3)
+}
+
+/// Bytes given as hex digit pairs, which can be separated by spaces or
+/// colons. None if that isn't what `text` holds
+pub fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text
+        .chars()
+        .filter(|&c| c != ' ' && c != ':')
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()?;
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex_upper(&[0x30, 0x0A, 0xFF], " "), "30 0A FF");
+        assert_eq!(hex_upper(&[0x01, 0x02], "-"), "01-02");
+        assert_eq!(hex_upper(&[], " "), "");
+        assert_eq!(hex_lower(&[0xAB, 0x0C]), "ab0c");
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("300aFF"), Some(vec![0x30, 0x0A, 0xFF]));
+        assert_eq!(parse_hex("30 0A:ff"), Some(vec![0x30, 0x0A, 0xFF]));
+        assert_eq!(parse_hex(""), Some(vec![]));
+        assert_eq!(parse_hex("300"), None);
+        assert_eq!(parse_hex("3g"), None);
+    }
+
+    #[test]
+    fn test_hex_text_row() {
+        assert_eq!(
+            hex_text_row(b"Hi\x00", 8),
+            format!("{:24} Hi.", "48 69 00 ")
+        );
+    }
+}
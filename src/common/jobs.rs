@@ -0,0 +1,130 @@
+// Working through many inputs at once, for --jobs. Each worker thread takes
+// the next input not yet started; the results are handed back in the order
+// of the inputs, each as soon as it and all those before it are done, so the
+// output is the same whatever the number of threads.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+// The dumpers recurse once per level of nesting, so workers get a stack as
+// big as the main thread's usually is rather than the 2 MiB default
+const WORKER_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// The number of jobs when --jobs isn't given: one per CPU
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Run `work` on each of `inputs` using up to `jobs` threads, and pass the
+/// results to `emit` in the order of the inputs. Nothing more is started
+/// after `emit` fails, and its error is returned.
+pub fn run_ordered<T, R, W, E>(inputs: &[T], jobs: usize, work: W, mut emit: E) -> io::Result<()>
+where
+    T: Sync,
+    R: Send,
+    W: Fn(&T) -> R + Sync,
+    E: FnMut(R) -> io::Result<()>,
+{
+    let jobs = jobs.clamp(1, inputs.len().max(1));
+    if jobs == 1 {
+        return inputs.iter().try_for_each(|input| emit(work(input)));
+    }
+
+    let next_input = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (next_input, stopped, work) = (&next_input, &stopped, &work);
+            thread::Builder::new()
+                .stack_size(WORKER_STACK_SIZE)
+                .spawn_scoped(scope, move || {
+                    while !stopped.load(Ordering::Relaxed) {
+                        let index = next_input.fetch_add(1, Ordering::Relaxed);
+                        let Some(input) = inputs.get(index) else {
+                            break;
+                        };
+                        if sender.send((index, work(input))).is_err() {
+                            break;
+                        }
+                    }
+                })?;
+        }
+        drop(sender);
+
+        // Results that arrive early wait here for those before them
+        let mut waiting = BTreeMap::new();
+        let mut next_output = 0;
+        for (index, result) in receiver {
+            waiting.insert(index, result);
+            while let Some(result) = waiting.remove(&next_output) {
+                if let Err(e) = emit(result) {
+                    stopped.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+                next_output += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_results_in_input_order() {
+        let inputs: Vec<u64> = (0..50).collect();
+        for jobs in [1, 4, 100] {
+            let mut results = Vec::new();
+            run_ordered(
+                &inputs,
+                jobs,
+                |&n| {
+                    // Later inputs finish first
+                    thread::sleep(Duration::from_micros(50 - n));
+                    n * n
+                },
+                |square| {
+                    results.push(square);
+                    Ok(())
+                },
+            )
+            .unwrap();
+            assert_eq!(results, inputs.iter().map(|n| n * n).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_emit_error_stops() {
+        let inputs: Vec<usize> = (0..1000).collect();
+        let started = AtomicUsize::new(0);
+        let mut emitted = 0;
+        let err = run_ordered(
+            &inputs,
+            4,
+            |_| {
+                started.fetch_add(1, Ordering::Relaxed);
+                thread::sleep(Duration::from_micros(200));
+            },
+            |_| {
+                emitted += 1;
+                match emitted {
+                    3 => Err(io::Error::from(io::ErrorKind::BrokenPipe)),
+                    _ => Ok(()),
+                }
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(emitted, 3);
+        assert!(started.load(Ordering::Relaxed) < inputs.len());
+    }
+}
@@ -0,0 +1,357 @@
+// Recognizing JSON held in a text string, as CWT and JWT payloads and config
+// blobs stored in CBOR often are, and laying it out indented a value to a
+// line for dumpcbor --pretty-json. Only objects and arrays count: a string
+// holding just a number or a word is better shown as it is. Strings and
+// numbers are kept as they were written, escapes included, and unescape()
+// decodes a string for asn1cbor test-vectors, which reads JSON test files.
+
+use crate::common::color::Style;
+
+// Nesting beyond this isn't taken for JSON, to keep the parser's recursion
+// bounded on hostile input
+const MAX_JSON_DEPTH: usize = 128;
+
+#[derive(Debug, PartialEq)]
+pub enum Value<'a> {
+    Object(Vec<(&'a str, Value<'a>)>),
+    Array(Vec<Value<'a>>),
+    String(&'a str), // With its quotes
+    Number(&'a str),
+    Literal(&'a str), // true, false or null
+}
+
+/// The lines of `text` laid out indented by two spaces a level, if it's a
+/// JSON object or array, with `paint` coloring keys and values
+pub fn pretty_lines(text: &str, paint: &dyn Fn(Style, &str) -> String) -> Option<Vec<String>> {
+    if !text.trim_start().starts_with(['{', '[']) {
+        return None;
+    }
+    let value = parse(text)?;
+    let mut lines = Vec::new();
+    layout(&value, String::new(), 0, "", paint, &mut lines);
+    Some(lines)
+}
+
+/// Parse `text` as a JSON value of any kind
+pub fn parse(text: &str) -> Option<Value<'_>> {
+    let mut parser = Parser { text, pos: 0 };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    (parser.pos == text.len()).then_some(value)
+}
+
+/// The text a string as parsed stands for: without its quotes, and with
+/// its escapes decoded
+pub fn unescape(quoted: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut chars = quoted.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut pending_high = None; // The first half of a surrogate pair
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            if pending_high.is_some() {
+                return None;
+            }
+            text.push(c);
+            continue;
+        }
+        let escaped = match chars.next()? {
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let digits: String = chars.by_ref().take(4).collect();
+                let unit = u32::from_str_radix(&digits, 16).ok()?;
+                match (pending_high.take(), unit) {
+                    (None, 0xD800..=0xDBFF) => {
+                        pending_high = Some(unit);
+                        continue;
+                    }
+                    (Some(high), 0xDC00..=0xDFFF) => {
+                        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (unit - 0xDC00))?
+                    }
+                    (None, _) => char::from_u32(unit)?,
+                    (Some(_), _) => return None,
+                }
+            }
+            c => c, // A quote, backslash or slash
+        };
+        text.push(escaped);
+    }
+    pending_high.is_none().then_some(text)
+}
+
+/// Add the lines of `value` at `level`, the first starting with `prefix`
+/// (a key) and the last ending with `suffix` (a comma)
+fn layout(
+    value: &Value,
+    prefix: String,
+    level: usize,
+    suffix: &str,
+    paint: &dyn Fn(Style, &str) -> String,
+    lines: &mut Vec<String>,
+) {
+    let indent = "  ".repeat(level);
+    let (open, close, count) = match value {
+        Value::Object(members) => ('{', '}', members.len()),
+        Value::Array(elements) => ('[', ']', elements.len()),
+        Value::String(s) => {
+            let s = paint(Style::JsonString, s);
+            return lines.push(format!("{}{}{}{}", indent, prefix, s, suffix));
+        }
+        Value::Number(n) => {
+            let n = paint(Style::JsonNumber, n);
+            return lines.push(format!("{}{}{}{}", indent, prefix, n, suffix));
+        }
+        Value::Literal(word) => {
+            let word = paint(Style::JsonLiteral, word);
+            return lines.push(format!("{}{}{}{}", indent, prefix, word, suffix));
+        }
+    };
+    if count == 0 {
+        return lines.push(format!("{}{}{}{}{}", indent, prefix, open, close, suffix));
+    }
+
+    lines.push(format!("{}{}{}", indent, prefix, open));
+    let comma = |i| if i + 1 < count { "," } else { "" };
+    match value {
+        Value::Object(members) => {
+            for (i, (key, member)) in members.iter().enumerate() {
+                let key = format!("{}: ", paint(Style::JsonKey, key));
+                layout(member, key, level + 1, comma(i), paint, lines);
+            }
+        }
+        Value::Array(elements) => {
+            for (i, element) in elements.iter().enumerate() {
+                layout(element, String::new(), level + 1, comma(i), paint, lines);
+            }
+        }
+        _ => unreachable!("scalars are laid out above"),
+    }
+    lines.push(format!("{}{}{}", indent, close, suffix));
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn value(&mut self, depth: usize) -> Option<Value<'a>> {
+        if depth == MAX_JSON_DEPTH {
+            return None;
+        }
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                if !self.close(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.skip_whitespace();
+                        self.expect(b':')?;
+                        members.push((key, self.value(depth + 1)?));
+                        if !self.separator(b'}')? {
+                            break;
+                        }
+                    }
+                }
+                Some(Value::Object(members))
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut elements = Vec::new();
+                if !self.close(b']') {
+                    loop {
+                        elements.push(self.value(depth + 1)?);
+                        if !self.separator(b']')? {
+                            break;
+                        }
+                    }
+                }
+                Some(Value::Array(elements))
+            }
+            b'"' => self.string().map(Value::String),
+            b'-' | b'0'..=b'9' => self.number().map(Value::Number),
+            _ => ["true", "false", "null"]
+                .into_iter()
+                .find(|word| self.text[self.pos..].starts_with(word))
+                .map(|word| {
+                    self.pos += word.len();
+                    Value::Literal(word)
+                }),
+        }
+    }
+
+    /// Take the closing bracket of an empty object or array
+    fn close(&mut self, bracket: u8) -> bool {
+        self.skip_whitespace();
+        let empty = self.peek() == Some(bracket);
+        self.pos += empty as usize;
+        empty
+    }
+
+    /// After a member or element: true for a comma, false for the closing
+    /// bracket, None for anything else
+    fn separator(&mut self, bracket: u8) -> Option<bool> {
+        self.skip_whitespace();
+        let byte = self.peek()?;
+        self.pos += 1;
+        match byte {
+            b',' => Some(true),
+            b if b == bracket => Some(false),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    /// A string, quotes included
+    fn string(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        self.expect(b'"')?;
+        loop {
+            match self.peek()? {
+                b'"' => break,
+                b'\\' => {
+                    self.pos += 1;
+                    match self.peek()? {
+                        b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => self.pos += 1,
+                        b'u' => {
+                            let digits = self.text.get(self.pos + 1..self.pos + 5)?;
+                            if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+                                return None;
+                            }
+                            self.pos += 5;
+                        }
+                        _ => return None,
+                    }
+                }
+                b if b < 0x20 => return None,
+                _ => self.pos += 1,
+            }
+        }
+        self.pos += 1;
+        Some(&self.text[start..self.pos])
+    }
+
+    fn number(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek()? {
+            b'0' => self.pos += 1,
+            b'1'..=b'9' => self.digits(),
+            _ => return None,
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            self.peek().filter(u8::is_ascii_digit)?;
+            self.digits();
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            self.peek().filter(u8::is_ascii_digit)?;
+            self.digits();
+        }
+        Some(&self.text[start..self.pos])
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &str) -> Option<Vec<String>> {
+        pretty_lines(text, &|_, text| text.to_string())
+    }
+
+    #[test]
+    fn test_pretty_lines() {
+        assert_eq!(
+            plain(r#"{"sub":"1234","admin":true,"scope":["a\"b",-1.5e3],"x":{},"y":null}"#)
+                .unwrap(),
+            [
+                "{",
+                r#"  "sub": "1234","#,
+                r#"  "admin": true,"#,
+                r#"  "scope": ["#,
+                r#"    "a\"b","#,
+                "    -1.5e3",
+                "  ],",
+                r#"  "x": {},"#,
+                r#"  "y": null"#,
+                "}",
+            ]
+        );
+        assert_eq!(plain(" [ ] ").unwrap(), ["[]"]);
+
+        let colored = pretty_lines(r#"{"a":1}"#, &|style, text| {
+            format!("<{:?}>{}", style, text)
+        });
+        assert_eq!(colored.unwrap()[1], r#"  <JsonKey>"a": <JsonNumber>1"#);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(" 42 "), Some(Value::Number("42")));
+        assert_eq!(
+            parse(r#"{"a":[true]}"#),
+            Some(Value::Object(vec![(
+                r#""a""#,
+                Value::Array(vec![Value::Literal("true")])
+            )]))
+        );
+        assert_eq!(parse("1 2"), None);
+
+        assert_eq!(unescape(r#""a\"b\\\/""#).unwrap(), r#"a"b\/"#);
+        assert_eq!(unescape(r#""\u00fc\n""#).unwrap(), "\u{fc}\n");
+        assert_eq!(unescape(r#""\ud800\udd51""#).unwrap(), "\u{10151}");
+        assert_eq!(unescape(r#""\ud800""#), None);
+    }
+
+    #[test]
+    fn test_not_json() {
+        for text in [
+            "hello",
+            "42",
+            r#""quoted""#,
+            "{",
+            r#"{"a":1,}"#,
+            r#"{"a":1} trailing"#,
+            "[01]",
+            "[1.]",
+            r#"["\x"]"#,
+            "[tru]",
+        ] {
+            assert_eq!(plain(text), None, "{}", text);
+        }
+        let deep = "[".repeat(MAX_JSON_DEPTH + 1) + &"]".repeat(MAX_JSON_DEPTH + 1);
+        assert_eq!(plain(&deep), None);
+    }
+}
@@ -0,0 +1,93 @@
+// The header and body of a MIME message (RFC 2045), as CBOR tag 36 holds
+// one, split apart so that the dump can show its type and the start of its
+// body rather than the whole message as one string. Folded header lines are
+// unfolded, and lines may end in CRLF or a bare LF.
+
+/// A MIME message split into its header fields and body
+#[derive(Debug, PartialEq)]
+pub struct Message<'a> {
+    pub fields: Vec<(&'a str, String)>, // Names as written, values unfolded
+    pub body: &'a str,
+}
+
+impl Message<'_> {
+    /// The value of the field with `name`, whatever its case
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The content type, text/plain where none is given (RFC 2045 section
+    /// 5.2)
+    pub fn content_type(&self) -> &str {
+        self.field("Content-Type").unwrap_or("text/plain")
+    }
+
+    /// The transfer encoding, 7bit where none is given (RFC 2045 section
+    /// 6.1)
+    pub fn transfer_encoding(&self) -> &str {
+        self.field("Content-Transfer-Encoding").unwrap_or("7bit")
+    }
+}
+
+/// Split `text` into a MIME message, if it starts with header fields
+pub fn parse(text: &str) -> Option<Message<'_>> {
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    let mut rest = text;
+    loop {
+        let (line, next) = match rest.find('\n') {
+            Some(end) => (&rest[..end], &rest[end + 1..]),
+            None => (rest, ""),
+        };
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        rest = next;
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            // A folded line continues the field before it
+            let (_, value) = fields.last_mut()?;
+            value.push(' ');
+            value.push_str(line.trim());
+        } else {
+            let (name, value) = line.split_once(':')?;
+            let printable = |b: u8| b.is_ascii_graphic() && b != b':';
+            if name.is_empty() || !name.bytes().all(printable) {
+                return None;
+            }
+            fields.push((name, value.trim().to_string()));
+        }
+        if rest.is_empty() {
+            break;
+        }
+    }
+    (!fields.is_empty()).then_some(Message { fields, body: rest })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let message = parse(
+            "MIME-Version: 1.0\r\n\
+             content-type: text/plain;\r\n\
+             \tcharset=utf-8\r\n\
+             \r\n\
+             Hello\r\n",
+        )
+        .unwrap();
+        assert_eq!(message.fields.len(), 2);
+        assert_eq!(message.content_type(), "text/plain; charset=utf-8");
+        assert_eq!(message.transfer_encoding(), "7bit");
+        assert_eq!(message.body, "Hello\r\n");
+
+        assert_eq!(parse("Subject: hi").unwrap().body, "");
+        assert_eq!(parse("Just some text\n\nand more"), None);
+        assert_eq!(parse(" folded: first\n"), None);
+        assert_eq!(parse(""), None);
+    }
+}
@@ -0,0 +1,29 @@
+// Pieces shared by the dumpers and the asn1cbor commands: what either dumper
+// does for the commands, reading items and saying how much of them is
+// missing, option values, hex formatting, fitting output to the line width, the offset column, the
+// count of errors and warnings found, the --timing report, the worker
+// threads for --jobs, --color, the unified diffs --expect prints, the
+// decompression of gzip and zstd input, the regular expressions of --grep,
+// the JSON found in text strings and the escaping of control characters in
+// them, the MIME messages of CBOR tag 36, the byte arrays --emit-code
+// writes, and the fixtures the tests share
+
+pub mod args;
+pub mod color;
+pub mod compressed;
+pub mod counters;
+pub mod dumper;
+pub mod emit;
+pub mod escape;
+pub mod hex;
+pub mod jobs;
+pub mod json;
+pub mod mime;
+pub mod offsets;
+pub mod read;
+pub mod regex;
+#[cfg(test)]
+pub mod testutil;
+pub mod timing;
+pub mod unified;
+pub mod wrap;
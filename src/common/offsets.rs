@@ -0,0 +1,24 @@
+// The offset column at the left of a dump. Values are padded to four places
+// so that the columns line up for inputs under 10000 (or 0x10000) bytes.
+
+/// An offset or length for the left-hand column, in decimal or hex
+pub fn format_offset(value: usize, hex: bool) -> String {
+    if hex {
+        format!("{:04X}", value)
+    } else {
+        format!("{:4}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_offset() {
+        assert_eq!(format_offset(42, false), "  42");
+        assert_eq!(format_offset(42, true), "002A");
+        assert_eq!(format_offset(123456, false), "123456");
+        assert_eq!(format_offset(0x12345, true), "12345");
+    }
+}
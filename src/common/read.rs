@@ -0,0 +1,50 @@
+// Reading the items of an input: as much of a buffer as the input holds,
+// saying how much of an item is missing when it holds less, and what
+// --recover skipped to find the next one
+
+use std::io::{self, Read};
+
+/// Read as much of `buffer` as the input holds, returning the byte count
+pub fn read_available<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(count) => filled += count,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// How many of the bytes, or other units, that an item declares are missing
+pub fn missing(expected: u64, present: u64, unit: &str) -> String {
+    let missing = expected - present;
+    format!(
+        "{} more {}{} expected ({} of {} present)",
+        missing,
+        unit,
+        if missing == 1 { "" } else { "s" },
+        present,
+        expected
+    )
+}
+
+/// What --recover says about the `skipped` bytes from `from`, after which
+/// the dump resumes or, if nothing further looks like an item, ends
+pub fn skipped_message(from: usize, skipped: usize, resumes: bool) -> String {
+    if resumes {
+        format!(
+            "{} bytes skipped from offset {}, resuming at offset {}",
+            skipped,
+            from,
+            from + skipped
+        )
+    } else {
+        format!(
+            "{} bytes skipped from offset {} to the end, no further items found",
+            skipped, from
+        )
+    }
+}
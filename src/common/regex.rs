@@ -0,0 +1,628 @@
+// Regular expressions for searching decoded text, as dumpcbor --grep does,
+// without taking on a dependency. The usual subset is supported: literals,
+// `.`, classes such as `[a-z_]` and `[^0-9]`, the escapes `\d`, `\w` and
+// `\s` and their negations, anchors, groups, alternation and the greedy
+// quantifiers `*`, `+`, `?` and `{n,m}`. Matching backtracks, which is
+// plenty for patterns typed on a command line and strings inside a dump.
+//
+// outline() checks a pattern against the wider syntax of PCRE, which the
+// regular expressions of CBOR tag 35 are written in, without compiling it:
+// lookarounds, named groups, backreferences, lazy and possessive quantifiers
+// and the like are taken, and what the pattern is made of is counted.
+
+use std::fmt;
+
+/// A compiled regular expression
+#[derive(Debug, Clone)]
+pub struct Regex {
+    pattern: String,
+    alternatives: Vec<Vec<Node>>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Literal(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    Group(Vec<Vec<Node>>), // Alternatives, each a sequence
+    Repeat(Box<Node>, usize, Option<usize>),
+}
+
+/// A set of characters, listed as inclusive ranges
+#[derive(Debug, Clone)]
+struct Class {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(low, high)| low <= c && c <= high) != self.negated
+    }
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE: &[(char, char)] = &[('\t', '\r'), (' ', ' ')];
+
+/// Largest count a `{n,m}` can give
+const MAX_COUNT: usize = 1000;
+
+impl Regex {
+    /// Compile `pattern`, saying what's wrong with it if it can't be
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+        };
+        let alternatives = parser
+            .alternatives()
+            .and_then(|alternatives| match parser.peek() {
+                None => Ok(alternatives),
+                Some(_) => Err("unmatched ')'".to_string()),
+            })
+            .map_err(|e| format!("Invalid regular expression '{}': {}", pattern, e))?;
+        Ok(Regex {
+            pattern: pattern.to_string(),
+            alternatives,
+        })
+    }
+
+    /// Whether the expression matches anywhere in `text`
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        (0..=chars.len()).any(|start| {
+            self.alternatives
+                .iter()
+                .any(|sequence| match_sequence(sequence, &chars, start, &|_| true))
+        })
+    }
+}
+
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    /// Sequences separated by `|`, up to a `)` or the end
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            let node = match c {
+                '|' | ')' => break,
+                '*' | '+' | '?' | '{' => return Err(format!("nothing for '{}' to repeat", c)),
+                _ => self.atom()?,
+            };
+            nodes.push(self.quantified(node)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        Ok(match self.next().expect("atom follows") {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                // Groups don't capture, so (?:...) is the same thing
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                let alternatives = self.alternatives()?;
+                if self.next() != Some(')') {
+                    return Err("unclosed '('".to_string());
+                }
+                Node::Group(alternatives)
+            }
+            '[' => Node::Class(self.class()?),
+            '\\' => match self.escape()? {
+                Ok(c) => Node::Literal(c),
+                Err(class) => Node::Class(class),
+            },
+            c => Node::Literal(c),
+        })
+    }
+
+    /// The character or class after a backslash
+    fn escape(&mut self) -> Result<Result<char, Class>, String> {
+        let class = |ranges: &[(char, char)], negated| {
+            Err(Class {
+                ranges: ranges.to_vec(),
+                negated,
+            })
+        };
+        Ok(match self.next() {
+            None => return Err("trailing '\\'".to_string()),
+            Some('d') => class(DIGIT, false),
+            Some('D') => class(DIGIT, true),
+            Some('w') => class(WORD, false),
+            Some('W') => class(WORD, true),
+            Some('s') => class(SPACE, false),
+            Some('S') => class(SPACE, true),
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some(c) if c.is_ascii_alphanumeric() => {
+                return Err(format!("unsupported escape '\\{}'", c))
+            }
+            Some(c) => Ok(c),
+        })
+    }
+
+    /// The rest of a class after its `[`
+    fn class(&mut self) -> Result<Class, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let low = match self.next() {
+                None => return Err("unclosed '['".to_string()),
+                Some(']') if !first => break,
+                Some('\\') => match self.escape()? {
+                    Ok(c) => c,
+                    Err(class) if !class.negated => {
+                        ranges.extend(class.ranges);
+                        first = false;
+                        continue;
+                    }
+                    Err(_) => return Err("negated escapes can't go in a class".to_string()),
+                },
+                Some(c) => c,
+            };
+            first = false;
+            // A '-' first or last in the class is itself
+            let high = match (self.peek(), self.chars.get(self.pos + 1)) {
+                (Some('-'), Some(&c)) if c != ']' => {
+                    self.pos += 2;
+                    match c {
+                        '\\' => match self.escape()? {
+                            Ok(c) => c,
+                            Err(_) => return Err("a range can't end in a class".to_string()),
+                        },
+                        c => c,
+                    }
+                }
+                _ => low,
+            };
+            if high < low {
+                return Err(format!("range '{}-{}' is out of order", low, high));
+            }
+            ranges.push((low, high));
+        }
+        Ok(Class { ranges, negated })
+    }
+
+    /// `node` with the quantifier that follows it, if any
+    fn quantified(&mut self, node: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => self.counts().ok_or("bad count in '{}'")?,
+            _ => return Ok(node),
+        };
+        if matches!(node, Node::Start | Node::End) {
+            return Err("an anchor can't be repeated".to_string());
+        }
+        self.pos += 1;
+        Ok(Node::Repeat(Box::new(node), min, max))
+    }
+
+    /// The counts in `{n}`, `{n,}` or `{n,m}`, leaving the `}` to be passed
+    fn counts(&mut self) -> Option<(usize, Option<usize>)> {
+        self.pos += 1;
+        let min = self.count()?;
+        let max = match self.peek()? {
+            '}' => Some(min),
+            ',' => {
+                self.pos += 1;
+                match self.peek()? {
+                    '}' => None,
+                    _ => Some(self.count().filter(|&max| max >= min)?),
+                }
+            }
+            _ => return None,
+        };
+        (self.peek()? == '}').then_some((min, max))
+    }
+
+    fn count(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().ok().filter(|&n| n <= MAX_COUNT)
+    }
+}
+
+/// What a PCRE pattern is made of, as outline() finds it
+#[derive(Debug, Default, PartialEq)]
+pub struct Outline {
+    pub alternatives: usize, // At the top level
+    pub groups: usize,
+    pub classes: usize,
+    pub quantifiers: usize,
+    pub backreferences: usize,
+    pub anchored_start: bool,
+    pub anchored_end: bool,
+}
+
+impl fmt::Display for Outline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        match (self.anchored_start, self.anchored_end) {
+            (true, true) => parts.push("anchored at both ends".to_string()),
+            (true, false) => parts.push("anchored at the start".to_string()),
+            (false, true) => parts.push("anchored at the end".to_string()),
+            (false, false) => {}
+        }
+        for (count, what) in [
+            (self.alternatives, "alternative(s)"),
+            (self.groups, "group(s)"),
+            (self.classes, "class(es)"),
+            (self.quantifiers, "quantifier(s)"),
+            (self.backreferences, "backreference(s)"),
+        ] {
+            if count > 1 || (count == 1 && what != "alternative(s)") {
+                parts.push(format!("{} {}", count, what));
+            }
+        }
+        if parts.is_empty() {
+            return f.write_str("literal text");
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// Check `pattern` against PCRE syntax, saying what's wrong with it or
+/// what it's made of
+pub fn outline(pattern: &str) -> Result<Outline, String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut outline = Outline {
+        alternatives: 1,
+        anchored_start: chars.first() == Some(&'^'),
+        ..Outline::default()
+    };
+    let mut open = 0; // Groups not yet closed
+    let mut repeatable = false; // Whether the last thing can take a quantifier
+    let mut pos = 0;
+    while let Some(&c) = chars.get(pos) {
+        pos += 1;
+        match c {
+            '\\' => {
+                let escaped = *chars.get(pos).ok_or("trailing '\\'")?;
+                pos += 1;
+                if escaped.is_ascii_digit() && escaped != '0' {
+                    outline.backreferences += 1;
+                }
+                // Assertions such as \b match no character to repeat
+                repeatable = !matches!(escaped, 'b' | 'B' | 'A' | 'z' | 'Z' | 'G');
+            }
+            '[' => {
+                pos = class_end(&chars, pos).ok_or("unclosed '['")?;
+                outline.classes += 1;
+                repeatable = true;
+            }
+            '(' => {
+                if chars.get(pos) == Some(&'?') {
+                    pos = group_kind(&chars, pos + 1)?;
+                    // (?i) sets options for what follows rather than opening
+                    // a group
+                    if chars.get(pos - 1) == Some(&')') {
+                        repeatable = false;
+                        continue;
+                    }
+                }
+                outline.groups += 1;
+                open += 1;
+                repeatable = false;
+            }
+            ')' => {
+                if open == 0 {
+                    return Err("unmatched ')'".to_string());
+                }
+                open -= 1;
+                repeatable = true;
+            }
+            '|' => {
+                if open == 0 {
+                    outline.alternatives += 1;
+                }
+                repeatable = false;
+            }
+            '*' | '+' | '?' | '{' => {
+                if c == '{' {
+                    match counts_end(&chars, pos)? {
+                        Some(end) => pos = end,
+                        None => {
+                            // Not a count, so a literal brace, as in PCRE
+                            repeatable = true;
+                            continue;
+                        }
+                    }
+                }
+                if !repeatable {
+                    return Err(format!("nothing for '{}' to repeat", c));
+                }
+                outline.quantifiers += 1;
+                // Lazy or possessive
+                if matches!(chars.get(pos), Some('?' | '+')) {
+                    pos += 1;
+                }
+                repeatable = false;
+            }
+            '^' | '$' => {
+                outline.anchored_end |= c == '$' && pos == chars.len();
+                repeatable = false;
+            }
+            _ => repeatable = true,
+        }
+    }
+    if open > 0 {
+        return Err("unclosed '('".to_string());
+    }
+    Ok(outline)
+}
+
+/// Where a class that opened before `pos` ends, past its `]`
+fn class_end(chars: &[char], mut pos: usize) -> Option<usize> {
+    if chars.get(pos) == Some(&'^') {
+        pos += 1;
+    }
+    // A ']' first in the class is itself
+    if chars.get(pos) == Some(&']') {
+        pos += 1;
+    }
+    loop {
+        match chars.get(pos)? {
+            ']' => return Some(pos + 1),
+            '\\' => pos += 2,
+            // POSIX classes such as [:alpha:] inside the brackets
+            '[' if chars.get(pos + 1) == Some(&':') => {
+                let rest = &chars[pos + 2..];
+                let close = rest.windows(2).position(|w| w == [':', ']'])?;
+                pos += close + 4;
+            }
+            _ => pos += 1,
+        }
+    }
+}
+
+/// Past what follows the `(?` of a group that opened before `pos`: its
+/// kind, or the options it sets and the `)` or `:` that ends them
+fn group_kind(chars: &[char], pos: usize) -> Result<usize, String> {
+    let rest = &chars[pos..];
+    let name_end = |open: usize, close: char| {
+        rest[open..]
+            .iter()
+            .position(|&c| c == close)
+            .filter(|&length| length > 0)
+            .map(|length| pos + open + length + 1)
+            .ok_or_else(|| "bad group name".to_string())
+    };
+    match rest {
+        [':' | '=' | '!' | '>' | '|', ..] => Ok(pos + 1),
+        ['<', '=' | '!', ..] => Ok(pos + 2),
+        ['<', ..] => name_end(1, '>'),
+        ['P', '<', ..] => name_end(2, '>'),
+        ['\'', ..] => name_end(1, '\''),
+        _ => {
+            let options = rest
+                .iter()
+                .take_while(|c| c.is_ascii_alphabetic() || **c == '-')
+                .count();
+            match rest.get(options) {
+                Some(':' | ')') if options > 0 => Ok(pos + options + 1),
+                _ => Err("unknown group after '(?'".to_string()),
+            }
+        }
+    }
+}
+
+/// Where the counts of a `{n}`, `{n,}` or `{n,m}` that opened before `pos`
+/// end, past the `}`, or None if the brace doesn't start counts
+fn counts_end(chars: &[char], pos: usize) -> Result<Option<usize>, String> {
+    let Some(close) = chars[pos..].iter().position(|&c| c == '}') else {
+        return Ok(None);
+    };
+    let inner: String = chars[pos..pos + close].iter().collect();
+    let number = |text: &str| text.parse::<usize>().ok();
+    let (min, max) = match inner.split_once(',') {
+        Some((min, "")) => (number(min), Some(usize::MAX)),
+        Some((min, max)) => (number(min), number(max)),
+        None => (number(&inner), number(&inner)),
+    };
+    match (min, max) {
+        (Some(min), Some(max)) if max < min => {
+            Err(format!("counts out of order in '{{{}}}'", inner))
+        }
+        (Some(_), Some(_)) => Ok(Some(pos + close + 1)),
+        _ => Ok(None),
+    }
+}
+
+/// Whether `sequence` matches `text` from `pos` with the rest of the
+/// expression, `then`, matching after it
+fn match_sequence(
+    sequence: &[Node],
+    text: &[char],
+    pos: usize,
+    then: &dyn Fn(usize) -> bool,
+) -> bool {
+    match sequence.split_first() {
+        None => then(pos),
+        Some((node, rest)) => match_node(node, text, pos, &|next| {
+            match_sequence(rest, text, next, then)
+        }),
+    }
+}
+
+fn match_node(node: &Node, text: &[char], pos: usize, then: &dyn Fn(usize) -> bool) -> bool {
+    let next_char = |matches: &dyn Fn(char) -> bool| {
+        text.get(pos).is_some_and(|&c| matches(c)) && then(pos + 1)
+    };
+    match node {
+        Node::Literal(literal) => next_char(&|c| c == *literal),
+        Node::Any => next_char(&|c| c != '\n'),
+        Node::Class(class) => next_char(&|c| class.matches(c)),
+        Node::Start => pos == 0 && then(pos),
+        Node::End => pos == text.len() && then(pos),
+        Node::Group(alternatives) => alternatives
+            .iter()
+            .any(|sequence| match_sequence(sequence, text, pos, then)),
+        Node::Repeat(node, min, max) => match_repeat(node, (*min, *max), 0, text, pos, then),
+    }
+}
+
+/// Match `node` as many times as it will go, `count` times so far, then
+/// back off until the rest matches
+fn match_repeat(
+    node: &Node,
+    bounds: (usize, Option<usize>),
+    count: usize,
+    text: &[char],
+    pos: usize,
+    then: &dyn Fn(usize) -> bool,
+) -> bool {
+    let (min, max) = bounds;
+    if max.is_none_or(|max| count < max) {
+        // A repeat that matches nothing only counts towards the minimum,
+        // or it would go on forever
+        let more = match_node(node, text, pos, &|next| {
+            (next != pos || count < min) && match_repeat(node, bounds, count + 1, text, next, then)
+        });
+        if more {
+            return true;
+        }
+    }
+    count >= min && then(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_literals_and_anchors() {
+        assert!(matches("bc", "abcd"));
+        assert!(!matches("bd", "abcd"));
+        assert!(matches("^ab", "abcd") && !matches("^bc", "abcd"));
+        assert!(matches("cd$", "abcd") && !matches("bc$", "abcd"));
+        assert!(matches("^$", "") && !matches("^$", "a"));
+        assert!(matches("a.c", "abc") && !matches("a.c", "ac"));
+        assert!(matches(r"a\.c", "a.c") && !matches(r"a\.c", "abc"));
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(matches("^[a-c_]+$", "ab_c") && !matches("^[a-c]+$", "abd"));
+        assert!(matches("[^0-9]", "12a") && !matches("[^0-9]", "123"));
+        assert!(matches(r"^\d{3}-\d{4}$", "555-1234"));
+        assert!(!matches(r"^\d{3}-\d{4}$", "555-123"));
+        assert!(matches(r"\w\s\W", "a !") && !matches(r"\S", " \t"));
+        assert!(matches("[]a]", "]") && matches("[a-]", "-"));
+        assert!(matches(r"[\d.]+", "1.5"));
+    }
+
+    #[test]
+    fn test_groups_and_repeats() {
+        assert!(matches("^(ab|cd)+$", "abcdab") && !matches("^(ab|cd)+$", "abc"));
+        assert!(matches("^colou?r$", "color") && matches("^colou?r$", "colour"));
+        assert!(matches("^a{2,3}$", "aaa") && !matches("^a{2,3}$", "aaaa"));
+        assert!(matches("^a{2,}$", "aaaaa") && !matches("^a{2,}$", "a"));
+        assert!(matches("^(?:x*)+$", "") && matches("^(a*)*b", "aaab"));
+        assert!(matches("^.*@example\\.com$", "alice@example.com"));
+        assert!(matches("é+", "café"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for pattern in [
+            "(ab", "ab)", "[ab", "*a", "a{2", "a{3,1}", r"\b", r"a\", "[z-a]",
+        ] {
+            let err = Regex::new(pattern).unwrap_err();
+            assert!(err.starts_with("Invalid regular expression"), "{}", err);
+        }
+        // A ']' outside a class is itself
+        assert!(matches("a]", "a]"));
+    }
+
+    #[test]
+    fn test_outline() {
+        let outline = outline(r"^(?<year>\d{4})-(\d\d)(?:-\2)*?|[[:alpha:]]+$").unwrap();
+        assert_eq!(
+            outline,
+            Outline {
+                alternatives: 2,
+                groups: 3,
+                classes: 1,
+                quantifiers: 3,
+                backreferences: 1,
+                anchored_start: true,
+                anchored_end: true,
+            }
+        );
+        assert_eq!(
+            outline.to_string(),
+            "anchored at both ends, 2 alternative(s), 3 group(s), 1 class(es), \
+             3 quantifier(s), 1 backreference(s)"
+        );
+        assert_eq!(super::outline("abc").unwrap().to_string(), "literal text");
+        assert!(super::outline(r"(?i)a{,2}\bb(?=c)(?<!d)").is_ok());
+
+        for (pattern, error) in [
+            ("(ab", "unclosed '('"),
+            ("ab)", "unmatched ')'"),
+            ("[ab", "unclosed '['"),
+            ("*a", "nothing for '*' to repeat"),
+            ("a**", "nothing for '*' to repeat"),
+            ("a{3,1}", "counts out of order in '{3,1}'"),
+            ("(?<>a)", "bad group name"),
+            ("(?%)", "unknown group after '(?'"),
+            (r"a\", "trailing '\\'"),
+        ] {
+            assert_eq!(
+                super::outline(pattern),
+                Err(error.to_string()),
+                "{}",
+                pattern
+            );
+        }
+    }
+}
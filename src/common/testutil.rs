@@ -0,0 +1,130 @@
+// Fixtures the tests share: certificates built to order with DerBuilder,
+// CBOR items wrapping values, and a dump captured with the errors and
+// warnings found in it
+
+use crate::asn1::tags::INTEGER;
+use crate::cbor::{CborItem, CborValue};
+use crate::common::counters::Findings;
+use crate::common::dumper::Dumper;
+use crate::der::DerBuilder;
+use crate::output::Captured;
+
+/// Ed25519, the algorithm of the key and signature unless a test says
+/// otherwise
+pub const ED25519: &str = "1.3.101.112";
+
+/// A version 3 certificate for a test, with the fields it cares about set
+/// and the defaults for the rest
+pub struct Certificate<'a> {
+    /// The content octets of the serial number, which needn't be valid
+    pub serial: &'a [u8],
+    /// The algorithms of tbsCertificate.signature and signatureAlgorithm
+    pub signature: &'a str,
+    pub signature_algorithm: &'a str,
+    /// Whether the algorithms have NULL parameters
+    pub null_parameters: bool,
+    /// The common names of the issuer and subject, or None for empty names
+    pub issuer: Option<&'a str>,
+    pub subject: Option<&'a str>,
+    /// UTCTime, or GeneralizedTime if it has a four-digit year
+    pub not_before: &'a str,
+    pub not_after: &'a str,
+    /// The Ed25519 public key
+    pub key: &'a [u8],
+    /// Adds the extensions, if there are any
+    pub extensions: Option<&'a dyn Fn(DerBuilder) -> DerBuilder>,
+}
+
+impl Default for Certificate<'_> {
+    fn default() -> Self {
+        Certificate {
+            serial: &[1],
+            signature: ED25519,
+            signature_algorithm: ED25519,
+            null_parameters: false,
+            issuer: Some("Test"),
+            subject: Some("Test"),
+            not_before: "240101000000Z",
+            not_after: "340101000000Z",
+            key: &[0; 32],
+            extensions: None,
+        }
+    }
+}
+
+impl Certificate<'_> {
+    /// The certificate, with a signature of zeros
+    pub fn build(&self) -> Vec<u8> {
+        self.signed(|_| vec![0; 64])
+    }
+
+    /// The certificate, with the signature `sign` gives for its
+    /// tbsCertificate
+    pub fn signed(&self, sign: impl Fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+        let tbs = self.tbs_certificate();
+        DerBuilder::new()
+            .sequence(|b| {
+                self.algorithm(b.raw(&tbs), self.signature_algorithm)
+                    .bit_string(&sign(&tbs), 0)
+            })
+            .finish()
+    }
+
+    fn tbs_certificate(&self) -> Vec<u8> {
+        DerBuilder::new()
+            .sequence(|b| {
+                let b = b
+                    .explicit(0, |b| b.integer(2))
+                    .primitive(INTEGER, self.serial);
+                let b = self
+                    .algorithm(b, self.signature)
+                    .raw(&name(self.issuer))
+                    .sequence(|b| time(time(b, self.not_before), self.not_after))
+                    .raw(&name(self.subject))
+                    .sequence(|b| b.sequence(|b| b.oid(ED25519)).bit_string(self.key, 0));
+                match self.extensions {
+                    Some(extensions) => b.explicit(3, |b| b.sequence(extensions)),
+                    None => b,
+                }
+            })
+            .finish()
+    }
+
+    fn algorithm(&self, b: DerBuilder, oid: &str) -> DerBuilder {
+        b.sequence(|b| match self.null_parameters {
+            true => b.oid(oid).null(),
+            false => b.oid(oid),
+        })
+    }
+}
+
+/// A name with the common name `cn`, or an empty one
+pub fn name(cn: Option<&str>) -> Vec<u8> {
+    DerBuilder::new()
+        .sequence(|b| match cn {
+            Some(cn) => b.set(|b| b.sequence(|b| b.oid("2.5.4.3").utf8_string(cn))),
+            None => b,
+        })
+        .finish()
+}
+
+fn time(b: DerBuilder, time: &str) -> DerBuilder {
+    match time.len() {
+        13 => b.utc_time(time),
+        _ => b.generalized_time(time),
+    }
+}
+
+/// `value` in an item, with the head of its preferred serialization
+pub fn item(value: CborValue) -> CborItem {
+    CborItem::from_value(value)
+}
+
+/// Dump `data` with `dumper` as input held in memory, returning the dump
+/// and the errors and warnings found
+pub fn dump_with<D: Dumper>(mut dumper: D, data: &[u8]) -> (String, Findings) {
+    let captured = Captured::default();
+    *dumper.output() = captured.output();
+    dumper.dump_bytes(data).unwrap();
+    (captured.text(), std::mem::take(dumper.findings()))
+}
@@ -0,0 +1,34 @@
+// The --timing report, printed to stderr after a dump so the dump itself is
+// unchanged. Parsing is whatever of the time wasn't spent rendering; for
+// dumps that print items as they decode them that includes the small share
+// of formatting done outside the output calls.
+
+use std::time::Duration;
+
+/// One line giving the parse, render and total times in milliseconds
+pub fn timing_report(total: Duration, render: Duration) -> String {
+    let render = render.min(total);
+    format!(
+        "Timing: parse {:.3} ms, render {:.3} ms, total {:.3} ms",
+        millis(total - render),
+        millis(render),
+        millis(total)
+    )
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_report() {
+        assert_eq!(
+            timing_report(Duration::from_micros(3500), Duration::from_micros(1250)),
+            "Timing: parse 2.250 ms, render 1.250 ms, total 3.500 ms"
+        );
+    }
+}
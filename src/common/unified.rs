@@ -0,0 +1,180 @@
+// Line-by-line differences between two texts as a unified diff, as diff -u
+// prints them, for --expect to show where a dump strays from the one
+// expected. Lines are matched by a longest common subsequence of what's left
+// once the lines the texts start and end with alike are set aside.
+
+// Unchanged lines shown around each change
+const CONTEXT: usize = 3;
+
+// Above this many lines in one text times the other, the lines between the
+// common start and end are shown as all removed and then all added, rather
+// than matched up
+const MAX_TABLE_CELLS: usize = 4_000_000;
+
+/// What happened to a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Same,
+    Removed,
+    Added,
+}
+
+/// The unified diff from `old` to `new`, under headers naming them, or None
+/// if their lines are the same. A missing newline at the end doesn't count
+pub fn unified_diff(old_name: &str, new_name: &str, old: &str, new: &str) -> Option<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let edits = edits(&old, &new);
+    if edits.iter().all(|edit| *edit == Edit::Same) {
+        return None;
+    }
+
+    // The lines of each text before each edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut old_line, mut new_line) = (0, 0);
+    for edit in &edits {
+        positions.push((old_line, new_line));
+        match edit {
+            Edit::Same => (old_line, new_line) = (old_line + 1, new_line + 1),
+            Edit::Removed => old_line += 1,
+            Edit::Added => new_line += 1,
+        }
+    }
+    positions.push((old_line, new_line));
+
+    let mut diff = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks(&edits) {
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_end - old_start),
+            range(new_start, new_end - new_start)
+        ));
+        for (k, edit) in edits.iter().enumerate().take(end).skip(start) {
+            let (old_line, new_line) = positions[k];
+            let (mark, line) = match edit {
+                Edit::Same => (' ', old[old_line]),
+                Edit::Removed => ('-', old[old_line]),
+                Edit::Added => ('+', new[new_line]),
+            };
+            diff.push(mark);
+            diff.push_str(line);
+            diff.push('\n');
+        }
+    }
+    Some(diff)
+}
+
+/// A hunk header's line range, which for no lines names the one before
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// The edits taking `old` to `new`, in order
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut edits = vec![Edit::Same; prefix];
+    if old_middle.len().saturating_mul(new_middle.len()) > MAX_TABLE_CELLS {
+        edits.extend(old_middle.iter().map(|_| Edit::Removed));
+        edits.extend(new_middle.iter().map(|_| Edit::Added));
+    } else {
+        edits.extend(matched_edits(old_middle, new_middle));
+    }
+    edits.extend(std::iter::repeat_n(Edit::Same, suffix));
+    edits
+}
+
+/// The edits keeping a longest common subsequence of the lines
+fn matched_edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    // lengths[i][j]: the longest common subsequence of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push(Edit::Same);
+            (i, j) = (i + 1, j + 1);
+        } else if j == new.len()
+            || (i < old.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            edits.push(Edit::Removed);
+            i += 1;
+        } else {
+            edits.push(Edit::Added);
+            j += 1;
+        }
+    }
+    edits
+}
+
+/// The ranges of edits shown in each hunk: the changes with the context
+/// around them, joined where the context would meet
+fn hunks(edits: &[Edit]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (k, _) in edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| **edit != Edit::Same)
+    {
+        let start = k.saturating_sub(CONTEXT);
+        let end = (k + 1 + CONTEXT).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        assert_eq!(unified_diff("old", "new", old, old), None);
+        assert_eq!(unified_diff("old", "new", "a\nb", "a\nb\n"), None);
+        assert_eq!(
+            unified_diff("expected.txt", "dump", old, new).unwrap(),
+            "--- expected.txt\n+++ dump\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -9,3 +9,4 @@\n i\n j\n k\n+l\n"
+        );
+        // Changes closer than twice the context share a hunk
+        assert_eq!(
+            unified_diff("old", "new", "1\n2\n3\n4\n", "2\n3\n").unwrap(),
+            "--- old\n+++ new\n@@ -1,4 +1,2 @@\n-1\n 2\n 3\n-4\n"
+        );
+        assert_eq!(
+            unified_diff("old", "new", "", "x\n").unwrap(),
+            "--- old\n+++ new\n@@ -0,0 +1 @@\n+x\n"
+        );
+    }
+}
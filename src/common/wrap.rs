@@ -0,0 +1,52 @@
+// Fitting output to the line: indentation for nesting levels, and how much
+// of a hex dump or string goes on each line of a given width
+
+/// Indentation for a nesting level, `step` spaces per level
+pub fn indentation(level: usize, step: usize) -> String {
+    " ".repeat(level * step)
+}
+
+/// Number of bytes of a hex dump that fit in `width` columns, in multiples
+/// of four. Each byte takes three columns, or four with the text rendering
+/// alongside, which is also set off by a space
+pub fn hex_bytes_per_line(width: usize, with_text: bool) -> usize {
+    let bytes = if with_text {
+        width.saturating_sub(1) / 4
+    } else {
+        width / 3
+    };
+    (bytes / 4 * 4).max(4)
+}
+
+/// Split text into lines of at most `width` characters
+pub fn wrap_chars(text: &[char], width: usize) -> Vec<String> {
+    text.chunks(width.max(1))
+        .map(|line| line.iter().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indentation() {
+        assert_eq!(indentation(3, 2), "      ");
+        assert_eq!(indentation(3, 1), "   ");
+        assert_eq!(indentation(0, 2), "");
+    }
+
+    #[test]
+    fn test_hex_bytes_per_line() {
+        assert_eq!(hex_bytes_per_line(67, false), 20);
+        assert_eq!(hex_bytes_per_line(67, true), 16);
+        assert_eq!(hex_bytes_per_line(5, false), 4);
+    }
+
+    #[test]
+    fn test_wrap_chars() {
+        let text: Vec<char> = "abcdefg".chars().collect();
+        assert_eq!(wrap_chars(&text, 3), ["abc", "def", "g"]);
+        assert!(wrap_chars(&[], 3).is_empty());
+    }
+}
@@ -0,0 +1,361 @@
+// Building DER programmatically. Elements are appended in order; SEQUENCE,
+// SET and explicit tags take a closure that builds their contents. Lengths
+// are always definite and minimal, integers minimal two's complement, and
+// SET contents sorted by their encodings, so the output is valid DER for
+// whatever values are added.
+
+use crate::asn1::tags::{
+    BITSTRING, BOOLEAN, CONSTRUCTED, CONTEXT, ENUMERATED, GENERALIZEDTIME, IA5STRING, INTEGER,
+    NULLTAG, OCTETSTRING, OID, PRINTABLESTRING, SEQUENCE, SET, TAG_MASK, UTCTIME, UTF8STRING,
+};
+
+/// Builds a sequence of DER elements
+///
+/// ```
+/// use asn1_cbor_tools::der::DerBuilder;
+///
+/// // SEQUENCE { INTEGER 5, OID 1.2.840.113549, [0] { UTF8String "x" } }
+/// let der = DerBuilder::new()
+///     .sequence(|b| {
+///         b.integer(5)
+///             .oid("1.2.840.113549")
+///             .explicit(0, |b| b.utf8_string("x"))
+///     })
+///     .finish();
+/// assert_eq!(der[..2], [0x30, 0x10]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DerBuilder {
+    elements: Vec<Vec<u8>>,
+}
+
+impl DerBuilder {
+    pub fn new() -> Self {
+        DerBuilder::default()
+    }
+
+    /// The encodings of all elements added, in order
+    pub fn finish(self) -> Vec<u8> {
+        self.elements.concat()
+    }
+
+    /// Add a primitive element with the given identifier octet
+    pub fn primitive(mut self, identifier: u8, content: &[u8]) -> Self {
+        self.elements.push(element(&[identifier], content));
+        self
+    }
+
+    /// Add a constructed element with the given identifier octet (which
+    /// should have the constructed bit set) holding what `contents` adds
+    pub fn constructed(self, identifier: u8, contents: impl FnOnce(Self) -> Self) -> Self {
+        let content = contents(DerBuilder::new()).finish();
+        self.primitive(identifier, &content)
+    }
+
+    /// Add already encoded DER as it is
+    pub fn raw(mut self, der: &[u8]) -> Self {
+        self.elements.push(der.to_vec());
+        self
+    }
+
+    pub fn sequence(self, contents: impl FnOnce(Self) -> Self) -> Self {
+        self.constructed(CONSTRUCTED | SEQUENCE, contents)
+    }
+
+    /// Add a SET whose elements are sorted by their encodings, the order DER
+    /// requires for SET OF (and that SET's tag order implies)
+    pub fn set(mut self, contents: impl FnOnce(Self) -> Self) -> Self {
+        let mut elements = contents(DerBuilder::new()).elements;
+        elements.sort();
+        self.elements
+            .push(element(&[CONSTRUCTED | SET], &elements.concat()));
+        self
+    }
+
+    /// Wrap what `contents` adds in a constructed context-specific tag
+    pub fn explicit(mut self, number: u32, contents: impl FnOnce(Self) -> Self) -> Self {
+        let content = contents(DerBuilder::new()).finish();
+        self.elements.push(element(
+            &identifier(CONTEXT | CONSTRUCTED, number),
+            &content,
+        ));
+        self
+    }
+
+    /// Add the single element that `contents` adds with its tag replaced by
+    /// a context-specific one, keeping its primitive or constructed form
+    ///
+    /// Panics unless `contents` adds exactly one element.
+    pub fn implicit(mut self, number: u32, contents: impl FnOnce(Self) -> Self) -> Self {
+        let inner = contents(DerBuilder::new()).elements;
+        let [encoded] = inner.as_slice() else {
+            panic!(
+                "implicit tagging needs exactly one element, got {}",
+                inner.len()
+            );
+        };
+        let mut tagged = identifier(CONTEXT | (encoded[0] & CONSTRUCTED), number);
+        tagged.extend_from_slice(&encoded[identifier_length(encoded)..]);
+        self.elements.push(tagged);
+        self
+    }
+
+    pub fn boolean(self, value: bool) -> Self {
+        self.primitive(BOOLEAN, &[if value { 0xFF } else { 0x00 }])
+    }
+
+    pub fn integer(self, value: i64) -> Self {
+        self.primitive(INTEGER, &minimal_integer(&value.to_be_bytes()))
+    }
+
+    /// Add a non-negative INTEGER given as a big-endian magnitude of any
+    /// length, such as an RSA modulus
+    pub fn unsigned_integer(self, magnitude: &[u8]) -> Self {
+        let mut bytes = vec![0];
+        bytes.extend_from_slice(magnitude);
+        self.primitive(INTEGER, &minimal_integer(&bytes))
+    }
+
+    pub fn enumerated(self, value: i64) -> Self {
+        self.primitive(ENUMERATED, &minimal_integer(&value.to_be_bytes()))
+    }
+
+    pub fn null(self) -> Self {
+        self.primitive(NULLTAG, &[])
+    }
+
+    /// Add an OBJECT IDENTIFIER given in dotted form
+    ///
+    /// Panics if `dotted` isn't at least two numeric arcs, the first of them
+    /// 0, 1 or 2.
+    pub fn oid(self, dotted: &str) -> Self {
+        let arcs: Vec<u64> = dotted
+            .split('.')
+            .map(|arc| {
+                arc.parse()
+                    .unwrap_or_else(|_| panic!("invalid OID {:?}", dotted))
+            })
+            .collect();
+        self.oid_arcs(&arcs)
+    }
+
+    /// Add an OBJECT IDENTIFIER given as its arcs
+    ///
+    /// Panics if there are fewer than two arcs or the first isn't 0, 1 or 2.
+    pub fn oid_arcs(self, arcs: &[u64]) -> Self {
+        assert!(
+            arcs.len() >= 2 && arcs[0] <= 2 && (arcs[0] == 2 || arcs[1] < 40),
+            "invalid OID arcs {:?}",
+            arcs
+        );
+        let mut content = Vec::new();
+        write_base128(&mut content, arcs[0] * 40 + arcs[1]);
+        for &arc in &arcs[2..] {
+            write_base128(&mut content, arc);
+        }
+        self.primitive(OID, &content)
+    }
+
+    pub fn octet_string(self, bytes: &[u8]) -> Self {
+        self.primitive(OCTETSTRING, bytes)
+    }
+
+    /// Add a BIT STRING whose last `unused_bits` bits are padding
+    pub fn bit_string(self, bytes: &[u8], unused_bits: u8) -> Self {
+        assert!(
+            unused_bits < 8 && (unused_bits == 0 || !bytes.is_empty()),
+            "invalid unused bit count {}",
+            unused_bits
+        );
+        let mut content = vec![unused_bits];
+        content.extend_from_slice(bytes);
+        // DER has the padding bits zero
+        if let Some(last) = content.last_mut().filter(|_| unused_bits > 0) {
+            *last &= 0xFF << unused_bits;
+        }
+        self.primitive(BITSTRING, &content)
+    }
+
+    pub fn utf8_string(self, text: &str) -> Self {
+        self.primitive(UTF8STRING, text.as_bytes())
+    }
+
+    pub fn printable_string(self, text: &str) -> Self {
+        self.primitive(PRINTABLESTRING, text.as_bytes())
+    }
+
+    pub fn ia5_string(self, text: &str) -> Self {
+        self.primitive(IA5STRING, text.as_bytes())
+    }
+
+    /// Add a UTCTime, given as its text such as "250101000000Z"
+    pub fn utc_time(self, time: &str) -> Self {
+        self.primitive(UTCTIME, time.as_bytes())
+    }
+
+    /// Add a GeneralizedTime, given as its text such as "20250101000000Z"
+    pub fn generalized_time(self, time: &str) -> Self {
+        self.primitive(GENERALIZEDTIME, time.as_bytes())
+    }
+}
+
+/// Encode an element from its identifier octets and content
+fn element(identifier: &[u8], content: &[u8]) -> Vec<u8> {
+    let mut out = identifier.to_vec();
+    if content.len() < 0x80 {
+        out.push(content.len() as u8);
+    } else {
+        let length = (content.len() as u64).to_be_bytes();
+        let start = length.iter().position(|&b| b != 0).unwrap_or(7);
+        out.push(0x80 | (8 - start) as u8);
+        out.extend_from_slice(&length[start..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// Identifier octets for a tag number with the given class and form bits,
+/// using the high-tag-number form for numbers above 30
+fn identifier(class_form: u8, number: u32) -> Vec<u8> {
+    if number < TAG_MASK as u32 {
+        return vec![class_form | number as u8];
+    }
+    let mut out = vec![class_form | TAG_MASK];
+    write_base128(&mut out, number as u64);
+    out
+}
+
+/// Length of the identifier octets an encoding starts with
+fn identifier_length(encoded: &[u8]) -> usize {
+    if encoded[0] & TAG_MASK != TAG_MASK {
+        return 1;
+    }
+    1 + encoded[1..]
+        .iter()
+        .position(|&b| b & 0x80 == 0)
+        .unwrap_or(0)
+        + 1
+}
+
+/// Append a value in base 128, high bits first, with the top bit of every
+/// octet but the last set
+fn write_base128(out: &mut Vec<u8>, value: u64) {
+    let groups = (64 - value.leading_zeros()).div_ceil(7).max(1);
+    for i in (0..groups).rev() {
+        let group = (value >> (7 * i)) as u8 & 0x7F;
+        out.push(if i > 0 { group | 0x80 } else { group });
+    }
+}
+
+/// Drop leading octets of a two's complement integer that only repeat the
+/// sign
+fn minimal_integer(bytes: &[u8]) -> Vec<u8> {
+    let mut start = 0;
+    while start + 1 < bytes.len()
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::hex::hex_lower;
+
+    #[test]
+    fn test_integers() {
+        let cases = [
+            (0, "020100"),
+            (127, "02017f"),
+            (128, "02020080"),
+            (256, "02020100"),
+            (-1, "0201ff"),
+            (-128, "020180"),
+            (-129, "0202ff7f"),
+            (i64::MIN, "02088000000000000000"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(
+                hex_lower(&DerBuilder::new().integer(value).finish()),
+                expected
+            );
+        }
+        let unsigned =
+            |magnitude: &[u8]| hex_lower(&DerBuilder::new().unsigned_integer(magnitude).finish());
+        assert_eq!(unsigned(&[]), "020100");
+        assert_eq!(unsigned(&[0x00, 0x00, 0x7F]), "02017f");
+        assert_eq!(unsigned(&[0xFF, 0x01]), "020300ff01");
+    }
+
+    #[test]
+    fn test_oids() {
+        let oid = |dotted| hex_lower(&DerBuilder::new().oid(dotted).finish());
+        assert_eq!(oid("1.2.840.113549.1.1.11"), "06092a864886f70d01010b");
+        assert_eq!(oid("2.5.4.3"), "0603550403");
+        assert_eq!(oid("2.999.3"), "0603883703");
+        assert_eq!(oid("0.0"), "060100");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid OID")]
+    fn test_invalid_oid() {
+        DerBuilder::new().oid("1.x");
+    }
+
+    #[test]
+    fn test_structures() {
+        // Name { CN=Test, O=Ex }
+        let name = DerBuilder::new()
+            .sequence(|b| {
+                b.set(|b| b.sequence(|b| b.oid("2.5.4.3").utf8_string("Test")))
+                    .set(|b| b.sequence(|b| b.oid("2.5.4.10").utf8_string("Ex")))
+            })
+            .finish();
+        assert_eq!(
+            hex_lower(&name),
+            "301c310d300b06035504030c0454657374310b3009060355040a0c024578"
+        );
+
+        // SET contents are sorted, whatever order they're added in
+        let set = DerBuilder::new()
+            .set(|b| b.null().integer(1).boolean(true))
+            .finish();
+        assert_eq!(hex_lower(&set), "31080101ff0201010500");
+    }
+
+    #[test]
+    fn test_tagging() {
+        let explicit = DerBuilder::new().explicit(0, |b| b.integer(2)).finish();
+        assert_eq!(hex_lower(&explicit), "a003020102");
+        let implicit = DerBuilder::new()
+            .implicit(1, |b| b.octet_string(b"ab"))
+            .finish();
+        assert_eq!(hex_lower(&implicit), "81026162");
+        let constructed = DerBuilder::new()
+            .implicit(2, |b| b.sequence(|b| b.null()))
+            .finish();
+        assert_eq!(hex_lower(&constructed), "a2020500");
+        // Tag numbers above 30 use the high-tag-number form, both ways
+        let high = DerBuilder::new().explicit(200, |b| b.null()).finish();
+        assert_eq!(hex_lower(&high), "bf8148020500");
+        let retagged = DerBuilder::new()
+            .implicit(5, |b| b.implicit(200, |b| b.null()))
+            .finish();
+        assert_eq!(hex_lower(&retagged), "8500");
+    }
+
+    #[test]
+    fn test_strings_and_lengths() {
+        let bits = DerBuilder::new().bit_string(&[0xFF], 3).finish();
+        assert_eq!(hex_lower(&bits), "030203f8");
+        let long = DerBuilder::new().octet_string(&[0; 200]).finish();
+        assert_eq!(hex_lower(&long[..3]), "0481c8");
+        let longer = DerBuilder::new().octet_string(&[0; 300]).finish();
+        assert_eq!(hex_lower(&longer[..4]), "0482012c");
+        let time = DerBuilder::new().utc_time("250101000000Z").finish();
+        assert_eq!(time[..2], [0x17, 0x0D]);
+    }
+}
@@ -0,0 +1,219 @@
+// Errors and warnings found while dumping, kept so that they can be written
+// out as JSON (--errors-json), and the exit statuses they lead to
+
+use std::fs;
+use std::io::{self, Write};
+
+use crate::error::DumpError;
+use crate::render::json_string;
+
+// Exit statuses, from best to worst
+pub const EXIT_CLEAN: i32 = 0; // No errors or warnings
+pub const EXIT_WARNINGS: i32 = 1; // Warnings only
+pub const EXIT_ERRORS: i32 = 2; // Errors in the input
+pub const EXIT_FAILURE: i32 = 3; // Bad usage, or the input couldn't be read
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A single error or warning, with where in the input it was found
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub code: &'static str, // Stable identifier to match on, e.g. "zero-length"
+    pub severity: Severity,
+    pub offset: Option<usize>, // Start of the item concerned, if known
+    pub path: Vec<String>,     // Names of the items enclosing the finding
+    pub message: String,
+}
+
+/// Exit status for a dump that ran to the end
+pub fn exit_status(errors: usize, warnings: usize) -> i32 {
+    if errors > 0 {
+        EXIT_ERRORS
+    } else if warnings > 0 {
+        EXIT_WARNINGS
+    } else {
+        EXIT_CLEAN
+    }
+}
+
+/// Exit status for a dump stopped by an error
+pub fn failure_status(error: &io::Error) -> i32 {
+    if is_malformed(error) {
+        EXIT_ERRORS
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+/// Check whether an error comes from malformed input, which --recover can
+/// skip past, rather than from failing to read it at all
+pub fn is_malformed(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// Code for an error that stopped parsing an item, from the DumpError it
+/// carries, or else from its kind
+pub fn error_code(error: &io::Error) -> &'static str {
+    match error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<DumpError>())
+    {
+        Some(DumpError::Truncated { .. }) => "truncated",
+        Some(DumpError::Malformed { .. }) => "malformed",
+        Some(DumpError::TooDeep { .. }) => "too-deep",
+        Some(DumpError::TooLong { .. }) => "too-long",
+        Some(DumpError::Io { .. }) => "io-error",
+        None => match error.kind() {
+            io::ErrorKind::UnexpectedEof => "truncated",
+            io::ErrorKind::InvalidData => "malformed",
+            _ => "io-error",
+        },
+    }
+}
+
+/// The error that stopped parsing an item, where the DumpError it carries
+/// says it was, or else at `offset` inside `path`, where the dumper was
+pub fn fatal(error: &io::Error, offset: Option<usize>, path: &[String]) -> Diagnostic {
+    let inner = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<DumpError>())
+        .filter(|inner| inner.offset().is_some());
+    let (offset, path) = match inner {
+        Some(inner) => (inner.offset(), inner.path()),
+        None => (offset, path),
+    };
+    Diagnostic {
+        code: error_code(error),
+        severity: Severity::Error,
+        offset,
+        path: path.to_vec(),
+        message: error.to_string(),
+    }
+}
+
+/// Format diagnostics as a JSON array, one object per line
+pub fn to_json(diagnostics: &[Diagnostic]) -> String {
+    if diagnostics.is_empty() {
+        return "[]\n".to_string();
+    }
+
+    let objects: Vec<String> = diagnostics
+        .iter()
+        .map(|d| {
+            let offset = d.offset.map_or("null".to_string(), |o| o.to_string());
+            let path: Vec<String> = d.path.iter().map(|name| json_string(name)).collect();
+            format!(
+                "  {{\"code\": {}, \"severity\": \"{}\", \"offset\": {}, \"path\": [{}], \
+                 \"message\": {}}}",
+                json_string(d.code),
+                d.severity.as_str(),
+                offset,
+                path.join(", "),
+                json_string(&d.message)
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+/// Write diagnostics as JSON to a file, or to stderr if the name is "-"
+pub fn write_json(diagnostics: &[Diagnostic], destination: &str) -> io::Result<()> {
+    let json = to_json(diagnostics);
+    if destination == "-" {
+        io::stderr().write_all(json.as_bytes())
+    } else {
+        fs::write(destination, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_status() {
+        assert_eq!(exit_status(0, 0), EXIT_CLEAN);
+        assert_eq!(exit_status(0, 3), EXIT_WARNINGS);
+        assert_eq!(exit_status(1, 3), EXIT_ERRORS);
+
+        let truncated = io::Error::from(io::ErrorKind::UnexpectedEof);
+        assert_eq!(failure_status(&truncated), EXIT_ERRORS);
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(failure_status(&missing), EXIT_FAILURE);
+    }
+
+    #[test]
+    fn test_fatal() {
+        let path = ["SEQUENCE".to_string()];
+        let e = io::Error::from(DumpError::truncated(164, "tag", "end of input", &path));
+        let diagnostic = fatal(&e, Some(141), &[]);
+        assert_eq!(diagnostic.code, "truncated");
+        assert_eq!(
+            (diagnostic.offset, diagnostic.path),
+            (Some(164), path.to_vec())
+        );
+        assert!(diagnostic.message.contains("offset 164"));
+
+        // Where the dumper was, for an error that doesn't say
+        let e = io::Error::from(DumpError::malformed(None, &[], "bad"));
+        assert_eq!(fatal(&e, Some(7), &path).offset, Some(7));
+        let e = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(fatal(&e, None, &[]).code, "io-error");
+    }
+
+    #[test]
+    fn test_error_code() {
+        let code = |e: DumpError| error_code(&io::Error::from(e));
+        assert_eq!(code(DumpError::too_deep(Some(3), &[], 8)), "too-deep");
+        assert_eq!(code(DumpError::too_long(4, &[], 300, 256)), "too-long");
+        assert_eq!(code(DumpError::malformed(None, &[], "bad")), "malformed");
+        let eof = io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer");
+        assert_eq!(error_code(&eof), "truncated");
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(to_json(&[]), "[]\n");
+
+        let diagnostics = vec![
+            Diagnostic {
+                code: "zero-length",
+                severity: Severity::Warning,
+                offset: Some(12),
+                path: vec!["Certificate".to_string(), "serialNumber".to_string()],
+                message: "item has zero length".to_string(),
+            },
+            Diagnostic {
+                code: "truncated",
+                severity: Severity::Error,
+                offset: None,
+                path: Vec::new(),
+                message: "Truncated tag".to_string(),
+            },
+        ];
+        assert_eq!(
+            to_json(&diagnostics),
+            "[\n  {\"code\": \"zero-length\", \"severity\": \"warning\", \"offset\": 12, \
+             \"path\": [\"Certificate\", \"serialNumber\"], \"message\": \"item has zero \
+             length\"},\n  {\"code\": \"truncated\", \"severity\": \"error\", \"offset\": null, \
+             \"path\": [], \"message\": \"Truncated tag\"}\n]\n"
+        );
+    }
+}
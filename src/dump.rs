@@ -0,0 +1,112 @@
+// Dumping input held in memory to a string, for programs that want the text
+// of a dump without running the tools: a decoder page in the browser (see
+// the wasm feature), an editor plugin or a test. The options are the tools'
+// own, as they'd be given on the command line.
+
+use crate::common::dumper;
+use crate::dumpasn1::Asn1Dumper;
+use crate::dumpcbor::CborDumper;
+
+// Options that name files, exit or write to stderr, which have no meaning
+// here
+const REFUSED_OPTIONS: &[&str] = &[
+    "-h",
+    "--help",
+    "-f",
+    "-O",
+    "--expect",
+    "--errors-json",
+    "--auto",
+    "--timing",
+    "--oid-config",
+    "--issuer",
+];
+
+/// Dump DER, BER or PEM as dumpasn1 would with the given options, returning
+/// the text. An error that stops the dump ends the text as it would on a
+/// terminal; only bad options are an Err.
+///
+/// ```
+/// let text = asn1_cbor_tools::dump_asn1_to_string(&[0x02, 0x01, 0x05], &["-p"]).unwrap();
+/// assert!(text.starts_with("INTEGER 5"));
+/// ```
+pub fn dump_asn1_to_string(bytes: &[u8], options: &[&str]) -> Result<String, String> {
+    dumper::dump_to_string::<Asn1Dumper>(bytes, &dumper_args("dumpasn1", options)?)
+}
+
+/// Dump CBOR as dumpcbor would with the given options, returning the text.
+/// Errors are shown in the text, and only bad options are an Err.
+///
+/// ```
+/// let text = asn1_cbor_tools::dump_cbor_to_string(&[0x18, 0x64], &["-c"]).unwrap();
+/// assert!(text.starts_with("unsigned(100)"));
+/// ```
+pub fn dump_cbor_to_string(bytes: &[u8], options: &[&str]) -> Result<String, String> {
+    dumper::dump_to_string::<CborDumper>(bytes, &dumper_args("dumpcbor", options)?)
+}
+
+/// Arguments for a dumper's option parser, the program name first
+fn dumper_args(program: &str, options: &[&str]) -> Result<Vec<String>, String> {
+    if let Some(option) = options.iter().find(|o| REFUSED_OPTIONS.contains(o)) {
+        return Err(format!("{} can't be used when dumping from memory", option));
+    }
+    Ok(std::iter::once(program)
+        .chain(options.iter().copied())
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::DerBuilder;
+
+    #[test]
+    fn test_dump_asn1_to_string() {
+        let der = DerBuilder::new()
+            .sequence(|b| b.integer(5).utf8_string("hi"))
+            .finish();
+        let text = dump_asn1_to_string(&der, &[]).unwrap();
+        assert!(text.contains("INTEGER 5"), "{}", text);
+        assert!(text.contains("UTF8String 'hi'"), "{}", text);
+        assert!(!text.contains("Dumping ASN.1 file"));
+
+        let text = dump_asn1_to_string(&der, &["--format", "diag"]).unwrap();
+        assert!(text.starts_with("["), "{}", text);
+
+        // Stopped by a truncated item
+        let text = dump_asn1_to_string(&der[..4], &[]).unwrap();
+        assert!(text
+            .trim_end()
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with("Error: "));
+    }
+
+    #[test]
+    fn test_dump_cbor_to_string() {
+        // [1, "a"], then a text string with invalid UTF-8
+        let text = dump_cbor_to_string(&[0x82, 0x01, 0x61, 0x61, 0x61, 0xFF], &[]).unwrap();
+        assert!(text.contains("unsigned(1)"), "{}", text);
+        assert!(text.contains("Error: "), "{}", text);
+        assert!(text.contains("Errors: 1"), "{}", text);
+    }
+
+    #[test]
+    fn test_refused_options() {
+        assert_eq!(
+            dump_cbor_to_string(&[0x00], &["-O", "out.txt"]),
+            Err("-O can't be used when dumping from memory".to_string())
+        );
+        assert_eq!(
+            dump_asn1_to_string(&[0x05, 0x00], &["extra.der"]),
+            Err("Unexpected argument: extra.der".to_string())
+        );
+        assert_eq!(
+            dump_asn1_to_string(&[0x05, 0x00], &["--issuer", "ca.der"]),
+            Err("--issuer can't be used when dumping from memory".to_string())
+        );
+        assert!(dump_asn1_to_string(&[0x05, 0x00], &["--bogus"]).is_err());
+    }
+}
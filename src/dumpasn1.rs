@@ -2,60 +2,60 @@
 // Based on dumpasn1.c by Peter Gutmann
 // This is a translation of the core concepts and approach to Rust
 
-use std::env;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{self, BufReader, Read, Seek};
-
-// Constants for ASN.1 tag classes
-const CLASS_MASK: u8 = 0xC0;
-const UNIVERSAL: u8 = 0x00;
-const APPLICATION: u8 = 0x40;
-const CONTEXT: u8 = 0x80;
-const PRIVATE: u8 = 0xC0;
-
-// Constants for encoding type
-const FORM_MASK: u8 = 0x20;
-const CONSTRUCTED: u8 = 0x20;
-
-// Universal tag values
-const TAG_MASK: u8 = 0x1F;
-const EOC: u8 = 0x00;
-const BOOLEAN: u8 = 0x01;
-const INTEGER: u8 = 0x02;
-const BITSTRING: u8 = 0x03;
-const OCTETSTRING: u8 = 0x04;
-const NULLTAG: u8 = 0x05;
-const OID: u8 = 0x06;
-const OBJDESCRIPTOR: u8 = 0x07;
-const EXTERNAL: u8 = 0x08;
-const REAL: u8 = 0x09;
-const ENUMERATED: u8 = 0x0A;
-const EMBEDDED_PDV: u8 = 0x0B;
-const UTF8STRING: u8 = 0x0C;
-const SEQUENCE: u8 = 0x10;
-const SET: u8 = 0x11;
-const NUMERICSTRING: u8 = 0x12;
-const PRINTABLESTRING: u8 = 0x13;
-const T61STRING: u8 = 0x14;
-const VIDEOTEXSTRING: u8 = 0x15;
-const IA5STRING: u8 = 0x16;
-const UTCTIME: u8 = 0x17;
-const GENERALIZEDTIME: u8 = 0x18;
-const GRAPHICSTRING: u8 = 0x19;
-const VISIBLESTRING: u8 = 0x1A;
-const GENERALSTRING: u8 = 0x1B;
-const UNIVERSALSTRING: u8 = 0x1C;
-const BMPSTRING: u8 = 0x1E;
-
-// Length encoding
-const LEN_XTND: u8 = 0x80;
-const LEN_MASK: u8 = 0x7F;
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+mod nodes;
+
+use crate::asn1::crypto::HashAlg;
+use crate::asn1::pem::{self, PemBlock};
+use crate::asn1::pkcs12::{self, Decrypted};
+use crate::asn1::signature::{self, Verification};
+use crate::asn1::tags::*;
+use crate::asn1::templates::{self, Annotation};
+use crate::asn1::{der, oids, strings, time, x509, zone};
+use crate::cli::{self, Decoder};
+use crate::common::args::{option_value, parse_number, parse_offset, parse_range, parse_span};
+use crate::common::color::{ColorChoice, Style};
+use crate::common::compressed;
+use crate::common::counters::Findings;
+use crate::common::dumper::{self, Dumper};
+use crate::common::emit::{self, Language};
+use crate::common::hex::{hex_lower, hex_text_row, hex_upper};
+use crate::common::offsets::format_offset;
+use crate::common::read::{missing, read_available, skipped_message};
+use crate::common::timing::timing_report;
+use crate::common::wrap;
+use crate::diagnostics::{self, Diagnostic, Severity};
+use crate::error::DumpError;
+use crate::output::Output;
+use crate::render::{self, Content, Format, Node};
+
+// Deepest nesting parsed before giving up, to keep recursion off the end of
+// the stack
+const MAX_PARSE_DEPTH: usize = 512;
+
+// Deepest nesting of constructed string segments that's followed
+const MAX_SEGMENT_DEPTH: usize = 32;
+
+// Longest BIT STRING content, in bytes, shown in binary rather than hex
+const MAX_BINARY_BITSTRING: i64 = 4;
+
+// Longest time string that's parsed rather than shown as-is
+const MAX_TIME_LENGTH: i64 = 64;
+
+// Number of content bytes shown after the header with -dd
+const HEADER_DUMP_CONTENT: usize = 24;
 
 /// Structure to hold information about an ASN.1 item
 #[derive(Debug, Clone)]
 struct Asn1Item {
     id: u8,              // Tag class + primitive/constructed
-    tag: u8,             // Tag number
+    tag: u32,            // Tag number
     length: i64,         // Data length
     indefinite: bool,    // Item has indefinite length
     non_canonical: bool, // Non-canonical length encoding used
@@ -63,6 +63,13 @@ struct Asn1Item {
     header_size: usize,  // Size of tag+length
 }
 
+/// Where a member of a SET or SET OF lies, for checking DER ordering
+struct SetMember {
+    offset: usize,
+    size: usize,
+    tag: (u8, u32), // Class and tag number
+}
+
 impl Asn1Item {
     fn new() -> Self {
         Asn1Item {
@@ -77,6 +84,39 @@ impl Asn1Item {
     }
 }
 
+/// How INTEGER and ENUMERATED values that fit in 64 bits are displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IntFormat {
+    Decimal,
+    Hex,
+    Both, // Decimal with hex alongside
+}
+
+/// Content gathered from the segments of a constructed string
+#[derive(Debug, Default)]
+struct Segments {
+    data: Vec<u8>,
+    count: usize,
+    unused: u8, // Unused bits in the final BIT STRING segment
+}
+
+/// The two AlgorithmIdentifiers of a certificate, which must match
+#[derive(Debug, Clone)]
+struct SignatureAlgorithms {
+    offset: usize, // Of the outer signatureAlgorithm
+    tbs: Vec<u8>,
+    outer: Vec<u8>,
+}
+
+/// How a certificate's signature checked out against the --issuer
+/// certificate's key
+#[derive(Debug, Clone)]
+struct SignatureCheck {
+    result: Verification,
+    issuer: String,     // The --issuer certificate's subject
+    names_issuer: bool, // Whether that's the certificate's issuer
+}
+
 /// Configuration options for the dumper
 #[derive(Debug, Clone)]
 struct Config {
@@ -97,6 +137,32 @@ struct Config {
     do_outline_only: bool,
     verbose: bool,
     print_offset: bool,
+    show_lengths: bool, // Header length column before the content length
+    show_secrets: bool,
+    password: Option<String>,
+    issuer: Option<String>, // Certificate file whose key checks signatures
+    digest: Option<HashAlg>,
+    digest_each: Option<HashAlg>, // Of every element, shown in the tree
+    select: Option<String>,
+    find_oid: Option<String>, // Dotted form or name
+    emit_code: Option<Language>,
+    at: Option<i64>, // Instant certificate validity is checked at
+    int_format: IntFormat,
+    strict: bool,
+    max_item_bytes: Option<usize>,
+    max_depth: usize,
+    skip: usize,                     // Top-level items passed over unprinted
+    first: Option<usize>,            // Top-level items printed before stopping
+    range: Option<Range<usize>>,     // Byte window of the elements shown
+    highlight: Option<Range<usize>>, // Bytes whose elements are marked
+    recover: bool,
+    auto: bool,
+    format: Option<Format>,
+    timing: bool,
+    errors_json: Option<String>,
+    output_file: Option<String>,
+    expect: Option<String>,
+    color: ColorChoice,
 }
 
 impl Default for Config {
@@ -119,59 +185,77 @@ impl Default for Config {
             do_outline_only: false,
             verbose: false,
             print_offset: true,
+            show_lengths: false,
+            show_secrets: false,
+            password: None,
+            issuer: None,
+            digest: None,
+            digest_each: None,
+            select: None,
+            find_oid: None,
+            emit_code: None,
+            at: None,
+            int_format: IntFormat::Decimal,
+            strict: false,
+            max_item_bytes: None,
+            max_depth: MAX_PARSE_DEPTH,
+            skip: 0,
+            first: None,
+            range: None,
+            highlight: None,
+            recover: false,
+            auto: false,
+            format: None,
+            timing: false,
+            errors_json: None,
+            output_file: None,
+            expect: None,
+            color: ColorChoice::Auto,
         }
     }
 }
 
 /// Main dumper state
-struct Asn1Dumper {
+pub(crate) struct Asn1Dumper {
     config: Config,
-    no_errors: usize,
-    no_warnings: usize,
+    out: Output,
+    findings: Findings,
     f_pos: usize,
+    input_len: usize,
+    annotations: HashMap<usize, Annotation>, // Template labels by item offset
+    decrypted: HashMap<usize, Decrypted>,    // PKCS #12 plaintext by item offset
+    signature_algorithms: Option<SignatureAlgorithms>, // Of a certificate being dumped
+    issuer: Option<Vec<u8>>,                 // The --issuer certificate, once read
+    signature_checks: HashMap<usize, SignatureCheck>, // By signatureValue offset
+    digests_printed: usize,
+    skipped: usize,           // Top-level items passed over for --skip
+    dumped: usize,            // Top-level items printed, counted for --first
+    column: usize,            // Output column after the current item's type name
+    path: Vec<String>,        // Names of the items enclosing the current one
+    item_offsets: Vec<usize>, // Offsets of the same items
+    inline_errors: bool,      // Errors go in the output rather than to stderr
 }
 
 impl Asn1Dumper {
     fn new(config: Config) -> Self {
         Asn1Dumper {
             config,
-            no_errors: 0,
-            no_warnings: 0,
+            out: Output::stdout(),
+            findings: Findings::default(),
             f_pos: 0,
-        }
-    }
-
-    /// Get descriptive string for universal tags
-    fn tag_name(&self, tag: u8) -> &'static str {
-        match tag {
-            EOC => "End-of-contents octets",
-            BOOLEAN => "BOOLEAN",
-            INTEGER => "INTEGER",
-            BITSTRING => "BIT STRING",
-            OCTETSTRING => "OCTET STRING",
-            NULLTAG => "NULL",
-            OID => "OBJECT IDENTIFIER",
-            OBJDESCRIPTOR => "ObjectDescriptor",
-            EXTERNAL => "EXTERNAL",
-            REAL => "REAL",
-            ENUMERATED => "ENUMERATED",
-            EMBEDDED_PDV => "EMBEDDED PDV",
-            UTF8STRING => "UTF8String",
-            SEQUENCE => "SEQUENCE",
-            SET => "SET",
-            NUMERICSTRING => "NumericString",
-            PRINTABLESTRING => "PrintableString",
-            T61STRING => "TeletexString",
-            VIDEOTEXSTRING => "VideotexString",
-            IA5STRING => "IA5String",
-            UTCTIME => "UTCTime",
-            GENERALIZEDTIME => "GeneralizedTime",
-            GRAPHICSTRING => "GraphicString",
-            VISIBLESTRING => "VisibleString",
-            GENERALSTRING => "GeneralString",
-            UNIVERSALSTRING => "UniversalString",
-            BMPSTRING => "BMPString",
-            _ => "Unknown",
+            input_len: 0,
+            annotations: HashMap::new(),
+            decrypted: HashMap::new(),
+            signature_algorithms: None,
+            issuer: None,
+            signature_checks: HashMap::new(),
+            digests_printed: 0,
+            skipped: 0,
+            dumped: 0,
+            column: 0,
+            path: Vec::new(),
+            item_offsets: Vec::new(),
+            inline_errors: false,
         }
     }
 
@@ -179,6 +263,7 @@ impl Asn1Dumper {
     fn get_item<R: Read>(&mut self, reader: &mut R) -> io::Result<Option<Asn1Item>> {
         let mut item = Asn1Item::new();
         let mut header = Vec::new();
+        let offset = self.f_pos;
 
         // Read tag byte
         let mut tag_byte = [0u8; 1];
@@ -196,22 +281,42 @@ impl Asn1Dumper {
             tag_num = 0;
             loop {
                 let mut byte = [0u8; 1];
-                reader.read_exact(&mut byte)?;
+                if read_available(reader, &mut byte)? == 0 {
+                    return Err(DumpError::truncated(
+                        offset,
+                        "tag",
+                        &missing(1, 0, "byte"),
+                        &self.path,
+                    )
+                    .into());
+                }
                 header.push(byte[0]);
+                if tag_num > (u32::MAX >> 7) {
+                    return Err(DumpError::malformed(
+                        Some(offset),
+                        &self.path,
+                        "Tag number too large",
+                    )
+                    .into());
+                }
                 tag_num = (tag_num << 7) | ((byte[0] & 0x7F) as u32);
                 self.f_pos += 1;
 
-                if (byte[0] & LEN_XTND) == 0 || header.len() >= 5 {
+                if (byte[0] & LEN_XTND) == 0 {
                     break;
                 }
             }
         }
 
-        item.tag = tag_num as u8;
+        item.tag = tag_num;
 
         // Read length byte
         let mut len_byte = [0u8; 1];
-        reader.read_exact(&mut len_byte)?;
+        if read_available(reader, &mut len_byte)? == 0 {
+            return Err(
+                DumpError::truncated(offset, "length", &missing(1, 0, "byte"), &self.path).into(),
+            );
+        }
         header.push(len_byte[0]);
         self.f_pos += 2; // Tag + length byte
 
@@ -226,23 +331,32 @@ impl Asn1Dumper {
                 item.indefinite = true;
                 item.length = 0;
             } else if num_octets > 4 {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Length too long",
-                ));
+                return Err(
+                    DumpError::malformed(Some(offset), &self.path, "Length too long").into(),
+                );
             } else {
                 // Definite long form
-                item.length = 0;
-                for _ in 0..num_octets {
-                    let mut byte = [0u8; 1];
-                    reader.read_exact(&mut byte)?;
-                    header.push(byte[0]);
-                    item.length = (item.length << 8) | (byte[0] as i64);
+                let mut octets = [0u8; 4];
+                let octets = &mut octets[..num_octets];
+                let present = read_available(reader, octets)?;
+                if present < num_octets {
+                    return Err(DumpError::truncated(
+                        offset,
+                        "length",
+                        &missing(num_octets as u64, present as u64, "byte"),
+                        &self.path,
+                    )
+                    .into());
                 }
+                header.extend_from_slice(octets);
+                item.length = octets
+                    .iter()
+                    .fold(0, |length, &byte| (length << 8) | byte as i64);
                 self.f_pos += num_octets;
 
-                // Check for non-canonical encoding
-                if item.length < 128 {
+                // Check for non-canonical encoding: the long form for a
+                // length that fits the short form, or leading zero octets
+                if item.length < 128 || header[header.len() - num_octets] == 0 {
                     item.non_canonical = true;
                 }
             }
@@ -251,6 +365,32 @@ impl Asn1Dumper {
             item.length = length as i64;
         }
 
+        // Refuse lengths that can't be right before anything is allocated
+        // for them, since they come straight from the input. The contents of
+        // constructed items are read one item at a time, so those are left
+        // for print_constructed to report once the partial content is shown.
+        let available = self.input_len.saturating_sub(self.f_pos);
+        if item.length as usize > available && item.id & FORM_MASK != CONSTRUCTED {
+            let name = match self.annotations.get(&offset) {
+                Some(annotation) => annotation.name.to_string(),
+                None => type_name(item.id, item.tag),
+            };
+            return Err(DumpError::truncated(
+                offset,
+                &format!("{} content", name),
+                &missing(item.length as u64, available as u64, "byte"),
+                &self.path,
+            )
+            .into());
+        }
+        if let Some(max) = self.config.max_item_bytes {
+            if item.id & FORM_MASK != CONSTRUCTED && item.length as usize > max {
+                return Err(
+                    DumpError::too_long(offset, &self.path, item.length as u64, max).into(),
+                );
+            }
+        }
+
         item.header = header;
         item.header_size = item.header.len();
 
@@ -258,30 +398,120 @@ impl Asn1Dumper {
     }
 
     /// Print indentation
-    fn print_indent(&self, level: usize) {
-        if !self.config.do_pure && self.config.print_offset {
-            if self.config.do_hex_values {
-                print!("{:04X} {:04X}: ", self.f_pos, 0);
-            } else {
-                print!("{:4} {:4}: ", self.f_pos, 0);
-            }
+    /// Print indentation for a line that continues the current item, with
+    /// the offset and length columns left blank
+    fn print_indent(&mut self, level: usize) {
+        if self.shows_offsets() {
+            write!(self.out, "{:width$}: ", "", width = self.offset_width() - 2);
         }
+        self.print_nesting(level);
+    }
 
-        for _ in 0..level {
-            if self.config.shallow_indent {
-                print!(" ");
+    /// Print the offset and length columns and indentation for the first line
+    /// of an item
+    fn print_item_indent(&mut self, item: &Asn1Item, offset: usize, level: usize) {
+        if self.shows_offsets() {
+            let hex = self.config.do_hex_values;
+            let number = |value: usize| format_offset(value, hex);
+            let mut length = if item.indefinite {
+                "NDEF".to_string()
             } else {
-                print!("  ");
+                number(item.length as usize)
+            };
+            if self.config.show_lengths {
+                length = format!("{:>2} {}", item.header_size, length);
+            } else if self.config.verbose {
+                // Header size as well, for checking the arithmetic by hand
+                length = format!("{:>7}", format!("{}+{}", item.header_size, length.trim()));
             }
+            write!(self.out, "{} {}: ", number(offset), length);
+        }
+        self.print_nesting(level);
+    }
+
+    fn shows_offsets(&self) -> bool {
+        !self.config.do_pure && self.config.print_offset
+    }
+
+    /// Width of the offset and length columns, including the separator
+    fn offset_width(&self) -> usize {
+        if self.config.verbose || self.config.show_lengths {
+            14
+        } else {
+            11
         }
+    }
+
+    /// Width of the text printed by print_indent
+    fn indent_width(&self, level: usize) -> usize {
+        let offsets = if self.shows_offsets() {
+            self.offset_width()
+        } else {
+            0
+        };
+        let step = if self.config.shallow_indent { 1 } else { 2 };
+        let dots = if self.config.print_dots && level > 0 {
+            2
+        } else {
+            0
+        };
+        offsets + level * step + dots
+    }
+
+    /// Number of bytes that fit on a hex dump continuation line, in
+    /// multiples of four
+    fn hex_bytes_per_line(&self, level: usize, with_text: bool) -> usize {
+        let available = self
+            .config
+            .output_width
+            .saturating_sub(self.indent_width(level) + 2);
+        wrap::hex_bytes_per_line(available, with_text)
+    }
+
+    /// Print the indentation for a nesting level, without the offset column
+    fn print_nesting(&mut self, level: usize) {
+        let step = if self.config.shallow_indent { 1 } else { 2 };
+        write!(self.out, "{}", wrap::indentation(level, step));
 
         if self.config.print_dots && level > 0 {
-            print!(". ");
+            write!(self.out, ". ");
         }
     }
 
-    /// Print hex dump of data
-    fn dump_hex<R: Read>(&mut self, reader: &mut R, length: i64, level: usize) -> io::Result<()> {
+    /// Print the raw tag and length octets of an item (-d) and, for -dd, the
+    /// start of its content, on a line of their own
+    fn print_header_dump<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        item: &Asn1Item,
+        level: usize,
+    ) -> io::Result<()> {
+        let content = if self.config.dump_header > 1 {
+            let available = self.input_len.saturating_sub(self.f_pos);
+            let length = if item.indefinite {
+                available
+            } else {
+                (item.length as usize).min(available)
+            };
+            self.peek_bytes(reader, length.min(HEADER_DUMP_CONTENT))?
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        self.print_indent(level);
+        writeln!(self.out, "{}", format_header_dump(&item.header, &content));
+        Ok(())
+    }
+
+    /// Print hex dump of data, optionally with its text rendering alongside
+    fn dump_hex<R: Read>(
+        &mut self,
+        reader: &mut R,
+        length: i64,
+        level: usize,
+        with_text: bool,
+    ) -> io::Result<()> {
         let bytes_to_read = length.min(if self.config.print_all_data {
             length
         } else {
@@ -290,25 +520,37 @@ impl Asn1Dumper {
         let mut buffer = vec![0u8; bytes_to_read as usize];
         reader.read_exact(&mut buffer)?;
 
-        print!(" ");
-        for (i, byte) in buffer.iter().enumerate() {
-            if i > 0 && i % 16 == 0 {
-                println!();
+        let per_line = self.hex_bytes_per_line(level, with_text);
+        if with_text {
+            // One row per line with the text rendering alongside, as xxd does
+            for row in buffer.chunks(per_line) {
+                writeln!(self.out);
                 self.print_indent(level);
-                print!("  ");
+                write!(self.out, "  {}", hex_text_row(row, per_line));
+            }
+        } else {
+            // Start on the item's own line if the first row fits there
+            let first_row = buffer.len().min(per_line) * 3;
+            let same_line = self.column + 1 + first_row <= self.config.output_width;
+            for (i, row) in buffer.chunks(per_line).enumerate() {
+                if i == 0 && same_line {
+                    write!(self.out, " ");
+                } else {
+                    writeln!(self.out);
+                    self.print_indent(level);
+                    write!(self.out, "  ");
+                }
+                write!(self.out, "{} ", hex_upper(row, " "));
             }
-            print!("{:02X} ", byte);
         }
 
         if length > bytes_to_read && !self.config.print_all_data {
-            println!("\n  ... ({} more bytes)", length - bytes_to_read);
-            // Skip remaining bytes
-            let mut remaining = vec![0u8; (length - bytes_to_read) as usize];
-            reader.read_exact(&mut remaining)?;
+            writeln!(self.out, "\n  ... ({} more bytes)", length - bytes_to_read);
+            skip_bytes(reader, (length - bytes_to_read) as u64)?;
         }
 
         self.f_pos += length as usize;
-        println!();
+        writeln!(self.out);
         Ok(())
     }
 
@@ -317,49 +559,343 @@ impl Asn1Dumper {
         &mut self,
         reader: &mut R,
         length: i64,
-        _level: usize,
+        level: usize,
+        tag: u8,
     ) -> io::Result<()> {
-        let bytes_to_read = length.min(if self.config.print_all_data {
+        let mut bytes_to_read = length.min(if self.config.print_all_data {
             length
         } else {
             384
         });
+        // Don't split the characters of wide strings when truncating
+        let char_size = match tag {
+            BMPSTRING => 2,
+            UNIVERSALSTRING => 4,
+            _ => 1,
+        };
+        if bytes_to_read < length {
+            bytes_to_read -= bytes_to_read % char_size;
+        }
         let mut buffer = vec![0u8; bytes_to_read as usize];
         reader.read_exact(&mut buffer)?;
 
-        print!(" '");
-        for byte in &buffer {
-            let ch = *byte as char;
-            if ch.is_ascii() && !ch.is_control() {
-                print!("{}", ch);
-            } else {
-                print!(".");
+        let decoded = match tag {
+            BMPSTRING => Some(strings::decode_bmp_string(&buffer)),
+            UNIVERSALSTRING => Some(strings::decode_universal_string(&buffer)),
+            T61STRING | VIDEOTEXSTRING => Some(strings::decode_t61_string(&buffer)),
+            OID_IRI | RELATIVE_OID_IRI => Some(String::from_utf8_lossy(&buffer).into_owned()),
+            _ => None,
+        };
+        let is_decoded = decoded.is_some();
+        let text: Vec<char> = match decoded {
+            Some(decoded) => decoded.chars().collect(),
+            None => buffer.iter().map(|&byte| byte as char).collect(),
+        };
+        let text: Vec<char> = text
+            .into_iter()
+            .map(|ch| {
+                if (ch.is_ascii() || is_decoded) && !ch.is_control() {
+                    ch
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        // Strings too long for the rest of the line go on lines of their own
+        if self.column + text.len() + 3 <= self.config.output_width {
+            write!(self.out, " '{}'", text.iter().collect::<String>());
+        } else {
+            let per_line = self
+                .config
+                .output_width
+                .saturating_sub(self.indent_width(level) + 4)
+                .max(16);
+            let lines = wrap::wrap_chars(&text, per_line);
+            for (i, line) in lines.iter().enumerate() {
+                writeln!(self.out);
+                self.print_indent(level);
+                let open = if i == 0 { '\'' } else { ' ' };
+                let close = if i + 1 == lines.len() { "'" } else { "" };
+                write!(self.out, "  {}{}{}", open, line, close);
             }
         }
-        print!("'");
 
         if length > bytes_to_read && !self.config.print_all_data {
-            println!("\n  ... ({} more bytes)", length - bytes_to_read);
-            // Skip remaining bytes
-            let mut remaining = vec![0u8; (length - bytes_to_read) as usize];
-            reader.read_exact(&mut remaining)?;
+            writeln!(self.out, "\n  ... ({} more bytes)", length - bytes_to_read);
+            skip_bytes(reader, (length - bytes_to_read) as u64)?;
+        }
+
+        self.f_pos += length as usize;
+        writeln!(self.out);
+        if tag == T61STRING || tag == VIDEOTEXSTRING {
+            self.print_warning(
+                level,
+                "deprecated-string-type",
+                "T.61 strings are deprecated, certificates should use UTF8String",
+            );
         }
+        Ok(())
+    }
 
+    /// Print a UTCTime or GeneralizedTime as an ISO 8601 timestamp, noting
+    /// any deviations from the usual profile
+    fn print_time<R: Read>(
+        &mut self,
+        reader: &mut R,
+        length: i64,
+        level: usize,
+        tag: u8,
+    ) -> io::Result<()> {
+        if length > MAX_TIME_LENGTH {
+            return self.print_string(reader, length, level, tag);
+        }
+        let mut buffer = vec![0u8; length as usize];
+        reader.read_exact(&mut buffer)?;
         self.f_pos += length as usize;
-        println!();
+
+        let text = String::from_utf8_lossy(&buffer);
+        let parsed = if tag == UTCTIME {
+            time::parse_utc_time(&text)
+        } else {
+            time::parse_generalized_time(&text)
+        };
+        match parsed {
+            Some(time) => {
+                if !self.config.raw_time_string {
+                    match time.to_utc_iso8601() {
+                        Some(utc) => {
+                            writeln!(self.out, " {} ({} as encoded)", utc, time.to_iso8601())
+                        }
+                        None => writeln!(self.out, " {}", time.to_iso8601()),
+                    }
+                } else if time.two_digit_year {
+                    // Show how the century was chosen
+                    writeln!(
+                        self.out,
+                        " '{}' (year {})",
+                        text.escape_default(),
+                        time.year
+                    );
+                } else {
+                    writeln!(self.out, " '{}'", text.escape_default());
+                }
+                for warning in time.warnings() {
+                    self.print_warning(level, "time-format", &warning);
+                }
+                if let Some(field) = self.path.last().cloned() {
+                    if field == "notBefore" || field == "notAfter" {
+                        self.print_validity(level, &field, time.epoch_seconds());
+                    }
+                }
+            }
+            None => {
+                writeln!(self.out, " '{}'", text.escape_default());
+                self.print_warning(level, "time-format", "unrecognized time format");
+            }
+        }
+        Ok(())
+    }
+
+    /// Name a certificate's signature algorithm at the end of its
+    /// signatureAlgorithm, warning if it isn't the one tbsCertificate.signature
+    /// gives, as RFC 5280 section 4.1.1.2 requires, or if it relies on a
+    /// broken hash
+    fn check_signature_algorithm(&mut self, level: usize) {
+        let Some(algorithms) = self.signature_algorithms.take() else {
+            return;
+        };
+        let name = x509::algorithm_name(&algorithms.outer);
+        if algorithms.outer == algorithms.tbs {
+            self.print_indent(level + 1);
+            writeln!(
+                self.out,
+                "signature algorithm: {}, as in tbsCertificate",
+                name
+            );
+        } else {
+            let tbs_name = x509::algorithm_name(&algorithms.tbs);
+            let msg = if tbs_name == name {
+                format!(
+                    "signatureAlgorithm {} has other parameters than tbsCertificate.signature",
+                    name
+                )
+            } else {
+                format!(
+                    "signatureAlgorithm {} doesn't match tbsCertificate.signature {}",
+                    name, tbs_name
+                )
+            };
+            self.print_warning(level, "signature-mismatch", &msg);
+        }
+        let weak =
+            x509::algorithm_oid(&algorithms.outer).and_then(|oid| x509::weak_signature_hash(&oid));
+        if let Some(hash) = weak {
+            let msg = format!(
+                "{} is a weak signature algorithm, as {} isn't collision-resistant",
+                name, hash
+            );
+            self.print_warning(level, "weak-signature", &msg);
+        }
+    }
+
+    /// Say at the end of a certificate's signatureValue whether the --issuer
+    /// certificate's key verifies it, warning if that certificate isn't the
+    /// one the certificate names as its issuer
+    fn print_signature_check(&mut self, check: SignatureCheck, level: usize) {
+        if !check.names_issuer {
+            let msg = format!(
+                "the --issuer certificate's subject {} isn't this certificate's issuer",
+                check.issuer
+            );
+            self.print_warning(level, "issuer-mismatch", &msg);
+        }
+        match check.result {
+            Verification::Valid => {
+                self.print_indent(level + 1);
+                writeln!(
+                    self.out,
+                    "signature verified with the key of {}",
+                    check.issuer
+                );
+            }
+            Verification::Invalid => {
+                let msg = format!("signature doesn't verify with the key of {}", check.issuer);
+                self.print_warning(level, "bad-signature", &msg);
+            }
+            Verification::Unchecked(reason) => {
+                let msg = format!("signature not checked: {}", reason);
+                self.print_warning(level, "signature-unchecked", &msg);
+            }
+        }
+    }
+
+    /// Show a certificate's notBefore or notAfter time in local time, and
+    /// how it stands against now or the --at time: the remaining lifetime,
+    /// or a warning if the certificate has expired or isn't valid yet
+    fn print_validity(&mut self, level: usize, field: &str, instant: i64) {
+        if let Some(offset) = zone::local_offset(instant) {
+            self.print_indent(level + 1);
+            writeln!(
+                self.out,
+                "local time: {} ({})",
+                time::format_instant(instant, Some(offset.seconds)),
+                offset.abbreviation
+            );
+        }
+        let now = self.config.at.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() as i64)
+        });
+        let span = time::format_span(instant.abs_diff(now));
+        // Relative to the --at time, if there is one
+        let (before, after) = match self.config.at {
+            Some(at) => {
+                let at = time::format_instant(at, None);
+                (
+                    format!("{} before {}", span, at),
+                    format!("{} after {}", span, at),
+                )
+            }
+            None => (format!("{} ago", span), format!("in {}", span)),
+        };
+        match (field, instant > now) {
+            ("notBefore", true) => {
+                let msg = format!("certificate isn't valid yet, its validity starts {}", after);
+                self.print_warning(level, "not-yet-valid", &msg);
+            }
+            ("notBefore", false) => {
+                self.print_indent(level + 1);
+                writeln!(self.out, "validity started {}", before);
+            }
+            (_, true) => {
+                self.print_indent(level + 1);
+                writeln!(self.out, "remaining lifetime: {}", span);
+            }
+            (_, false) => {
+                let msg = format!("certificate expired {}", before);
+                self.print_warning(level, "expired", &msg);
+            }
+        }
+    }
+
+    /// Warn about problems with the encoding of an item's tag and length
+    fn print_header_warnings(&mut self, item: &Asn1Item, level: usize) {
+        if item.non_canonical {
+            let msg = format!(
+                "length {} isn't minimally encoded, header is {}",
+                item.length,
+                format_header_dump(&item.header, &[])
+            );
+            self.print_warning(level, "non-minimal-length", &msg);
+        }
+    }
+
+    /// Print a warning about the item just displayed
+    fn print_warning(&mut self, level: usize, code: &'static str, message: &str) {
+        self.print_indent(level + 1);
+        writeln!(
+            self.out,
+            "{} {}",
+            self.out.paint(Style::Warning, "Warning:"),
+            message
+        );
+        self.record(Severity::Warning, code, message);
+    }
+
+    /// Count an error or warning, noting the item it concerns for
+    /// --errors-json
+    fn record(&mut self, severity: Severity, code: &'static str, message: &str) {
+        self.findings.record(Diagnostic {
+            code,
+            severity,
+            offset: self.item_offsets.last().copied(),
+            path: self.path.clone(),
+            message: message.to_string(),
+        });
+    }
+
+    /// Count an error that doesn't stop the dump, unless --strict makes every
+    /// error fatal
+    fn report_error(&mut self, code: &'static str, message: &str) -> io::Result<()> {
+        self.record(Severity::Error, code, message);
+        if self.config.strict {
+            let offset = self.item_offsets.last().copied();
+            return Err(DumpError::malformed(offset, &self.path, message).into());
+        }
         Ok(())
     }
 
+    /// Make a dumper for data derived from this item's content, such as the
+    /// joined segments of a string. It writes to this dumper's output until
+    /// merged back.
+    fn nested_dumper(&mut self, input_len: usize) -> Asn1Dumper {
+        let mut nested = Asn1Dumper::new(self.config.clone());
+        nested.out = std::mem::replace(&mut self.out, Output::sink());
+        nested.input_len = input_len;
+        nested.path = self.path.clone();
+        nested.item_offsets = self.item_offsets.clone();
+        nested
+    }
+
+    /// Take back the output of a nested dumper along with its findings
+    fn merge(&mut self, nested: Asn1Dumper) {
+        self.out = nested.out;
+        self.findings.merge(nested.findings);
+    }
+
     /// Print integer value
     fn print_integer<R: Read>(
         &mut self,
         reader: &mut R,
         length: i64,
         level: usize,
+        names: Option<&[&str]>,
     ) -> io::Result<()> {
         if length > 8 {
             // Too large for native integer, print as hex
-            self.dump_hex(reader, length, level)
+            self.dump_hex(reader, length, level, false)
         } else {
             let mut buffer = vec![0u8; length as usize];
             reader.read_exact(&mut buffer)?;
@@ -378,52 +914,311 @@ impl Asn1Dumper {
                 value = (value << shift) >> shift;
             }
 
-            println!(" {}", value);
+            // Values with a meaning given by the template, such as an OCSP
+            // responseStatus or a CRL reason code
+            let name = usize::try_from(value)
+                .ok()
+                .and_then(|index| names?.get(index))
+                .filter(|name| !name.is_empty());
+            let text = format_integer(value, self.config.int_format);
+            match name {
+                Some(name) => writeln!(self.out, " {} ({})", text, name),
+                None => writeln!(self.out, " {}", text),
+            }
             self.f_pos += length as usize;
             Ok(())
         }
     }
 
     /// Print OID
-    fn print_oid<R: Read>(&mut self, reader: &mut R, length: i64, _level: usize) -> io::Result<()> {
+    fn print_oid<R: Read>(&mut self, reader: &mut R, length: i64, level: usize) -> io::Result<()> {
         let mut buffer = vec![0u8; length as usize];
         reader.read_exact(&mut buffer)?;
+        self.f_pos += length as usize;
 
         if buffer.is_empty() {
-            println!(" (empty)");
+            writeln!(self.out, " (empty)");
             return Ok(());
         }
 
-        print!(" ");
+        match der::decode_oid(&buffer) {
+            Some(oid) => match oids::oid_name(&oid) {
+                Some(name)
+                    if self.column + oid.len() + name.len() + 4 > self.config.output_width =>
+                {
+                    writeln!(self.out, " {}", oid);
+                    self.print_indent(level);
+                    writeln!(self.out, "  ({})", name);
+                }
+                Some(name) => writeln!(self.out, " {} ({})", oid, name),
+                None => writeln!(self.out, " {}", oid),
+            },
+            None => {
+                writeln!(self.out, " (invalid OID encoding)");
+                self.report_error("invalid-oid", "invalid OID encoding")?;
+            }
+        }
+        Ok(())
+    }
 
-        // First byte encodes first two components
-        let first = buffer[0] / 40;
-        let second = buffer[0] % 40;
-        print!("{}.{}", first, second);
+    /// Print a short BIT STRING in binary, followed by the names of the set
+    /// bits if the template gives them
+    fn print_bits<R: Read>(
+        &mut self,
+        reader: &mut R,
+        length: i64,
+        unused: u8,
+        names: Option<&[&str]>,
+    ) -> io::Result<()> {
+        let mut buffer = vec![0u8; length as usize];
+        reader.read_exact(&mut buffer)?;
+        self.f_pos += length as usize;
 
-        // Decode remaining components
-        let mut i = 1;
-        while i < buffer.len() {
-            let mut value: u64 = 0;
-            loop {
-                if i >= buffer.len() {
-                    break;
-                }
-                let byte = buffer[i];
-                i += 1;
-                value = (value << 7) | ((byte & 0x7F) as u64);
-                if (byte & 0x80) == 0 {
-                    break;
-                }
+        let bits = bit_values(&buffer, unused);
+        let binary: String = bits
+            .iter()
+            .map(|&bit| if bit { '1' } else { '0' })
+            .collect();
+        write!(self.out, " '{}'B", binary);
+        if let Some(names) = names {
+            let set: Vec<String> = bits
+                .iter()
+                .enumerate()
+                .filter(|(_, &bit)| bit)
+                .map(|(index, _)| match names.get(index) {
+                    Some(name) if !name.is_empty() => name.to_string(),
+                    _ => format!("bit {}", index),
+                })
+                .collect();
+            if !set.is_empty() {
+                write!(self.out, " ({})", set.join(", "));
             }
-            print!(".{}", value);
         }
+        writeln!(self.out);
+        Ok(())
+    }
 
-        println!();
+    /// Print a RELATIVE-OID as its dot-separated arcs
+    fn print_relative_oid<R: Read>(&mut self, reader: &mut R, length: i64) -> io::Result<()> {
+        let mut buffer = vec![0u8; length as usize];
+        reader.read_exact(&mut buffer)?;
         self.f_pos += length as usize;
+
+        match der::decode_relative_oid(&buffer) {
+            Some(arcs) => writeln!(self.out, " {}", arcs),
+            None => {
+                writeln!(self.out, " (invalid RELATIVE-OID encoding)");
+                let msg = "invalid RELATIVE-OID encoding";
+                self.report_error("invalid-relative-oid", msg)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read ahead without consuming input, returning None if fewer than
+    /// `length` bytes remain
+    fn peek_bytes<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        length: usize,
+    ) -> io::Result<Option<Vec<u8>>> {
+        if length > self.input_len.saturating_sub(self.f_pos) {
+            return Ok(None);
+        }
+        let mut buffer = vec![0u8; length];
+        reader.read_exact(&mut buffer)?;
+        reader.seek(SeekFrom::Current(-(length as i64)))?;
+        Ok(Some(buffer))
+    }
+
+    /// Check whether the next `length` bytes hold an encapsulated object
+    fn is_encapsulated<R: Read + Seek>(&mut self, reader: &mut R, length: i64) -> io::Result<bool> {
+        if !self.config.check_encaps || length < 2 {
+            return Ok(false);
+        }
+        Ok(self
+            .peek_bytes(reader, length as usize)?
+            .is_some_and(|data| der::looks_encapsulated(&data)))
+    }
+
+    /// Print the object(s) wrapped inside an OCTET STRING or BIT STRING
+    fn print_encapsulated<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        length: i64,
+        level: usize,
+    ) -> io::Result<()> {
+        writeln!(self.out, ", encapsulates {{");
+
+        let end_pos = self.f_pos + length as usize;
+        while self.f_pos < end_pos {
+            if let Some(sub_item) = self.get_item(reader)? {
+                self.print_asn1_object(reader, &sub_item, level + 1)?;
+            } else {
+                break;
+            }
+        }
+
+        self.print_indent(level);
+        writeln!(self.out, "}}");
         Ok(())
     }
 
+    /// Read the complete encoding of a definite-length top-level item whose
+    /// header has just been read, leaving the reader at its content
+    fn peek_item<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        item: &Asn1Item,
+    ) -> io::Result<Option<Vec<u8>>> {
+        if item.indefinite {
+            return Ok(None);
+        }
+        Ok(self
+            .peek_bytes(reader, item.length as usize)?
+            .map(|content| [item.header.as_slice(), &content].concat()))
+    }
+
+    /// Match a top-level item against the known structure templates and
+    /// record labels for its components
+    fn load_annotations(&mut self, data: Option<&[u8]>, offset: usize) {
+        self.annotations.clear();
+        self.decrypted.clear();
+        self.signature_algorithms = None;
+        self.signature_checks.clear();
+        let data = match data {
+            Some(data)
+                if data
+                    .first()
+                    .is_some_and(|id| (id & FORM_MASK) == CONSTRUCTED) =>
+            {
+                data
+            }
+            _ => return,
+        };
+
+        if let Some(template) = templates::recognize(data) {
+            templates::annotate(data, offset, template, &mut self.annotations);
+            if std::ptr::eq(template, &templates::CERTIFICATE_TEMPLATE) {
+                self.signature_algorithms =
+                    x509::parse_certificate(data).map(|certificate| SignatureAlgorithms {
+                        offset: offset + certificate.signature_algorithm_offset,
+                        tbs: certificate.signature.to_vec(),
+                        outer: certificate.signature_algorithm.to_vec(),
+                    });
+                self.check_signature(data, offset);
+            }
+            if let Some(password) = &self.config.password {
+                if std::ptr::eq(template, &templates::PFX_TEMPLATE) {
+                    self.decrypted = pkcs12::decrypt_layers(data, offset, password);
+                }
+            }
+        }
+    }
+
+    /// Check the signature of the certificate in `data` with the --issuer
+    /// certificate's key, for printing after its signatureValue
+    fn check_signature(&mut self, data: &[u8], offset: usize) {
+        let issuer = self.issuer.as_deref().and_then(x509::parse_certificate);
+        let (Some(issuer), Some(certificate)) = (issuer, x509::parse_certificate(data)) else {
+            return;
+        };
+        let check = SignatureCheck {
+            result: signature::verify_certificate(&certificate, &issuer),
+            issuer: x509::name_to_string(issuer.subject),
+            names_issuer: issuer.subject == certificate.issuer,
+        };
+        self.signature_checks
+            .insert(offset + certificate.signature_value_offset, check);
+    }
+
+    /// Read the --issuer certificate, DER or PEM, if there is one and it
+    /// hasn't been read already
+    fn load_issuer(&mut self) -> io::Result<()> {
+        let Some(path) = self.config.issuer.as_deref() else {
+            return Ok(());
+        };
+        if self.issuer.is_some() {
+            return Ok(());
+        }
+        let data =
+            std::fs::read(path).map_err(|e| DumpError::io(format!("Can't open '{}'", path), e))?;
+        let der = match pem::looks_like_pem(&data) {
+            true => pem::parse_pem(&data)
+                .into_iter()
+                .find(|block| block.label == "CERTIFICATE")
+                .and_then(|block| block.data.ok()),
+            false => Some(data),
+        };
+        match der.filter(|der| x509::parse_certificate(der).is_some()) {
+            Some(der) => {
+                self.issuer = Some(der);
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("'{}' isn't an X.509 certificate", path),
+            )),
+        }
+    }
+
+    /// Print the digest of the items in a top-level object picked by
+    /// --select, or of the whole object if there's no selection
+    fn print_digests(&mut self, data: Option<&[u8]>, offset: usize, alg: HashAlg) {
+        let data = match data {
+            Some(data) => data,
+            None => {
+                if self.config.select.is_none() {
+                    let msg = "Can't compute the digest of an indefinite-length item";
+                    writeln!(self.out, "{}", msg);
+                    self.record(Severity::Warning, "digest-unavailable", msg);
+                }
+                return;
+            }
+        };
+
+        // Offsets (relative to `data`) and descriptions of the selected items
+        let mut targets: Vec<(usize, String)> = Vec::new();
+        match self.config.select.as_deref() {
+            None => targets.push((0, "item".to_string())),
+            Some(select) => {
+                if let Some(position) = parse_offset(select) {
+                    if position >= offset && der::find_item(data, position - offset).is_some() {
+                        targets.push((position - offset, "item".to_string()));
+                    }
+                } else {
+                    let mut labeled: Vec<usize> = self
+                        .annotations
+                        .iter()
+                        .filter(|(_, annotation)| annotation.name == select)
+                        .map(|(&position, _)| position - offset)
+                        .collect();
+                    labeled.sort_unstable();
+                    targets.extend(labeled.into_iter().map(|pos| (pos, select.to_string())));
+                }
+            }
+        }
+
+        for (pos, description) in targets {
+            let length = match der::find_item(data, pos) {
+                Some(tlv) => tlv.total_len(),
+                None => continue,
+            };
+            let digest = alg.digest(&data[pos..pos + length]);
+            let hex = hex_lower(&digest);
+            writeln!(
+                self.out,
+                "{} of {} at offset {} ({} bytes): {}",
+                alg.name(),
+                description,
+                offset + pos,
+                length,
+                hex
+            );
+            self.digests_printed += 1;
+        }
+    }
+
     /// Print a constructed object
     fn print_constructed<R: Read + Seek>(
         &mut self,
@@ -432,152 +1227,1045 @@ impl Asn1Dumper {
         item: &Asn1Item,
     ) -> io::Result<()> {
         if item.length == 0 && !item.indefinite {
-            println!(" {{}}");
+            writeln!(self.out, " {{}}");
+            self.print_header_warnings(item, level);
+            if !self.config.zero_length_allowed {
+                self.print_warning(level, "zero-length", "constructed item is empty");
+            }
             return Ok(());
         }
 
-        println!(" {{");
+        writeln!(self.out, " {{");
+        self.print_header_warnings(item, level);
+
+        // Only SETs need their members remembered, for the DER order check
+        let offset = self.f_pos - item.header_size;
+        let set_of = self.annotations.get(&offset).is_some_and(|a| a.set_of);
+        let is_set = item.id & CLASS_MASK == UNIVERSAL && item.tag == SET as u32;
+        let check_order = self.config.strict && (is_set || set_of);
+        let mut members = Vec::new();
+
+        // The item's own name is last in the path while its content is read
+        let truncated = |dumper: &Self, detail: &str| -> io::Error {
+            let (name, enclosing) = dumper.path.split_last().expect("item is in the path");
+            DumpError::truncated(offset, &format!("{} content", name), detail, enclosing).into()
+        };
 
         if item.indefinite {
             // Indefinite length - read until EOC
-            while let Some(sub_item) = self.get_item(reader)? {
-                if sub_item.tag == EOC && sub_item.length == 0 {
+            loop {
+                let sub_item = match self.get_item(reader)? {
+                    Some(sub_item) => sub_item,
+                    None => {
+                        return Err(truncated(
+                            self,
+                            "input ends before the end-of-contents octets",
+                        ))
+                    }
+                };
+                if sub_item.tag == EOC as u32 && sub_item.length == 0 {
                     break;
                 }
                 self.print_asn1_object(reader, &sub_item, level + 1)?;
             }
         } else {
             // Definite length
+            let start_pos = self.f_pos;
             let end_pos = self.f_pos + item.length as usize;
 
             while self.f_pos < end_pos {
                 if let Some(sub_item) = self.get_item(reader)? {
+                    if check_order && !sub_item.indefinite {
+                        members.push(SetMember {
+                            offset: self.f_pos - sub_item.header_size,
+                            size: sub_item.header_size + sub_item.length as usize,
+                            tag: (sub_item.id & CLASS_MASK, sub_item.tag),
+                        });
+                    }
                     self.print_asn1_object(reader, &sub_item, level + 1)?;
                 } else {
-                    break;
+                    let present = (self.f_pos - start_pos) as u64;
+                    return Err(truncated(
+                        self,
+                        &missing(item.length as u64, present, "byte"),
+                    ));
                 }
             }
         }
 
+        if members.len() > 1 {
+            self.check_set_order(reader, &members, set_of, level)?;
+        }
+        if self
+            .signature_algorithms
+            .as_ref()
+            .is_some_and(|algorithms| algorithms.offset == offset)
+        {
+            self.check_signature_algorithm(level);
+        }
+
         self.print_indent(level);
-        println!("}}");
+        writeln!(self.out, "}}");
         Ok(())
     }
 
-    /// Print a single ASN.1 object
-    fn print_asn1_object<R: Read + Seek>(
+    /// Check that the members of the SET just displayed are in the order DER
+    /// requires: SET OF elements sorted by their encodings, SET components by
+    /// tag. A universal SET whose members all share a tag is taken to be a
+    /// SET OF, since a SET's components have distinct tags.
+    fn check_set_order<R: Read + Seek>(
         &mut self,
         reader: &mut R,
-        item: &Asn1Item,
+        members: &[SetMember],
+        set_of: bool,
         level: usize,
     ) -> io::Result<()> {
-        if level > self.config.max_nest_level {
+        if members.iter().any(|m| m.offset + m.size > self.f_pos) {
+            return Ok(()); // Truncated, already reported
+        }
+
+        if !(set_of || members.windows(2).all(|pair| pair[0].tag == pair[1].tag)) {
+            if let Some(index) = members
+                .windows(2)
+                .position(|pair| pair[0].tag > pair[1].tag)
+            {
+                let msg = format!(
+                    "SET components aren't in DER tag order, component {} has a lower tag \
+                     than component {}",
+                    index + 2,
+                    index + 1
+                );
+                self.print_warning(level, "set-order", &msg);
+            }
             return Ok(());
         }
 
-        self.print_indent(level);
+        let mut previous = self.read_at(reader, members[0].offset, members[0].size)?;
+        for (index, member) in members.iter().enumerate().skip(1) {
+            let encoding = self.read_at(reader, member.offset, member.size)?;
+            if compare_padded(&previous, &encoding) == Ordering::Greater {
+                let msg = format!(
+                    "SET OF elements aren't in DER order, element {} sorts before element {}",
+                    index + 1,
+                    index
+                );
+                self.print_warning(level, "set-order", &msg);
+                break;
+            }
+            previous = encoding;
+        }
+        Ok(())
+    }
 
-        // Print tag class if not UNIVERSAL
-        let class = item.id & CLASS_MASK;
-        if class != UNIVERSAL {
-            let class_name = match class {
-                APPLICATION => "APPLICATION",
-                CONTEXT => "",
-                PRIVATE => "PRIVATE",
-                _ => "UNIVERSAL",
-            };
+    /// Read bytes from earlier in the input, leaving the reader where it was
+    fn read_at<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        offset: usize,
+        size: usize,
+    ) -> io::Result<Vec<u8>> {
+        let mut data = vec![0u8; size];
+        reader.seek(SeekFrom::Current(offset as i64 - self.f_pos as i64))?;
+        reader.read_exact(&mut data)?;
+        reader.seek(SeekFrom::Current(
+            self.f_pos as i64 - (offset + size) as i64,
+        ))?;
+        Ok(data)
+    }
 
-            if !class_name.is_empty() {
-                print!("[{} {}]", class_name, item.tag);
-            } else {
-                print!("[{}]", item.tag);
-            }
-        } else {
-            // Universal tag
-            print!("{}", self.tag_name(item.tag));
+    /// Refuse to descend further than the parse depth limit. Levels count
+    /// from zero, so items at `max_depth` are one too many.
+    fn check_depth(&self, level: usize) -> io::Result<()> {
+        if level >= self.config.max_depth {
+            let offset = self.item_offsets.last().copied();
+            return Err(DumpError::too_deep(offset, &self.path, self.config.max_depth).into());
         }
+        Ok(())
+    }
 
-        // Handle constructed vs primitive
-        if (item.id & FORM_MASK) == CONSTRUCTED {
+    /// Read past an item nested too deeply to display. Indefinite-length
+    /// items have to be parsed to find their end.
+    fn skip_item<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        item: &Asn1Item,
+        level: usize,
+    ) -> io::Result<()> {
+        if !item.indefinite {
+            reader.seek(SeekFrom::Current(item.length))?;
+            self.f_pos += item.length as usize;
+            return Ok(());
+        }
+        while let Some(sub_item) = self.get_item(reader)? {
+            if sub_item.id == EOC && sub_item.tag == EOC as u32 && sub_item.length == 0 {
+                break;
+            }
+            self.check_depth(level + 1)?;
+            self.skip_item(reader, &sub_item, level + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Print a single ASN.1 object
+    fn print_asn1_object<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        item: &Asn1Item,
+        level: usize,
+    ) -> io::Result<()> {
+        self.out.check()?;
+        self.check_depth(level)?;
+        if level > self.config.max_nest_level {
+            return self.skip_item(reader, item, level);
+        }
+
+        if self.config.dump_header > 0 {
+            self.print_header_dump(reader, item, level)?;
+        }
+        if self.config.verbose {
+            self.print_indent(level);
+            writeln!(self.out, "({})", header_detail(&item.header));
+        }
+        let offset = self.f_pos - item.header_size;
+        self.print_item_indent(item, offset, level);
+
+        // Print the template field name for recognized structures
+        let annotation = self.annotations.get(&offset).copied();
+        if let Some(annotation) = annotation {
+            write!(self.out, "{} ", annotation.name);
+        }
+
+        let type_name = type_name(item.id, item.tag);
+        write!(self.out, "{}", type_name);
+        self.column =
+            self.indent_width(level) + annotation.map_or(0, |a| a.name.len() + 1) + type_name.len();
+
+        // Anything read from here on is part of this item. If reading fails
+        // the item stays in the path, so the error can be placed.
+        self.path
+            .push(annotation.map_or(type_name, |a| a.name.to_string()));
+        self.item_offsets.push(offset);
+        self.print_content(reader, item, level, annotation)?;
+        self.path.pop();
+        self.item_offsets.pop();
+        Ok(())
+    }
+
+    /// Print the content of an item whose header has been displayed, by form
+    /// and type
+    fn print_content<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        item: &Asn1Item,
+        level: usize,
+        annotation: Option<Annotation>,
+    ) -> io::Result<()> {
+        let offset = self.f_pos - item.header_size;
+        let class = item.id & CLASS_MASK;
+        let redact = annotation.is_some_and(|a| a.secret) && !self.config.show_secrets;
+        let names = annotation.and_then(|a| a.values);
+        let is_string =
+            class == UNIVERSAL && (item.tag == OCTETSTRING as u32 || item.tag == BITSTRING as u32);
+        if (item.id & FORM_MASK) == CONSTRUCTED && is_string {
+            self.print_segmented(reader, item, level, names)?;
+            self.print_header_warnings(item, level);
+        } else if (item.id & FORM_MASK) == CONSTRUCTED {
             self.print_constructed(reader, level, item)?;
+        } else if item.length == 0 && class == UNIVERSAL && requires_content(item.tag) {
+            writeln!(self.out);
+            self.print_header_warnings(item, level);
+            if !self.config.zero_length_allowed {
+                self.print_warning(level, "zero-length", "item has zero length");
+            }
+        } else if redact && !self.is_encapsulated(reader, item.length)? {
+            // Private key material, unless it wraps an object whose own
+            // secret components get masked individually
+            writeln!(
+                self.out,
+                " <{} bytes redacted, use --show-secrets to display>",
+                item.length
+            );
+            reader.seek(SeekFrom::Current(item.length))?;
+            self.f_pos += item.length as usize;
+            self.print_header_warnings(item, level);
         } else {
-            // Primitive type
-            match item.tag {
-                BOOLEAN => {
-                    let mut byte = [0u8; 1];
-                    reader.read_exact(&mut byte)?;
-                    println!(" {}", if byte[0] != 0 { "TRUE" } else { "FALSE" });
-                    self.f_pos += 1;
-                }
-                INTEGER | ENUMERATED => {
-                    self.print_integer(reader, item.length, level)?;
-                }
-                BITSTRING => {
-                    // Read unused bits byte
-                    let mut unused = [0u8; 1];
-                    reader.read_exact(&mut unused)?;
-                    if unused[0] != 0 {
-                        print!(" ({} unused bits)", unused[0]);
-                    }
-                    self.f_pos += 1;
-                    self.dump_hex(reader, item.length - 1, level)?;
+            self.print_primitive(reader, item, level, names)?;
+            self.print_header_warnings(item, level);
+            if let Some(result) = self.decrypted.remove(&offset) {
+                self.print_decrypted(result, level)?;
+            }
+            if let Some(check) = self.signature_checks.remove(&offset) {
+                self.print_signature_check(check, level);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print the content of a primitive item
+    fn print_primitive<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        item: &Asn1Item,
+        level: usize,
+        names: Option<&[&str]>,
+    ) -> io::Result<()> {
+        let class = item.id & CLASS_MASK;
+
+        // Primitive type. Universal tag numbers don't apply to other
+        // classes, whose content is shown as an opaque blob
+        let universal_tag = match u8::try_from(item.tag) {
+            Ok(tag) if class == UNIVERSAL => Some(tag),
+            _ => None,
+        };
+        match universal_tag {
+            Some(BOOLEAN) => {
+                let mut content = vec![0u8; item.length as usize];
+                reader.read_exact(&mut content)?;
+                self.f_pos += content.len();
+                let value = content.iter().any(|&byte| byte != 0);
+                writeln!(self.out, " {}", if value { "TRUE" } else { "FALSE" });
+                if content.len() != 1 {
+                    let msg = format!("BOOLEAN has {} content bytes instead of 1", content.len());
+                    self.print_warning(level, "boolean-encoding", &msg);
+                } else if value && content[0] != 0xFF {
+                    let msg = "BOOLEAN TRUE isn't encoded as 0xFF";
+                    self.print_warning(level, "boolean-encoding", msg);
+                }
+            }
+            Some(INTEGER | ENUMERATED) => {
+                let padded = self
+                    .peek_bytes(reader, item.length.min(2) as usize)?
+                    .is_some_and(|start| has_redundant_sign_byte(&start));
+                self.print_integer(reader, item.length, level, names)?;
+                if padded {
+                    let msg = "value has a redundant leading sign byte";
+                    self.print_warning(level, "redundant-sign-byte", msg);
+                }
+            }
+            Some(BITSTRING) => {
+                // Read unused bits byte
+                let mut unused = [0u8; 1];
+                reader.read_exact(&mut unused)?;
+                if unused[0] != 0 {
+                    let note = format!(" ({} unused bits)", unused[0]);
+                    write!(self.out, "{}", note);
+                    self.column += note.len();
+                }
+                self.f_pos += 1;
+                if unused[0] == 0 && self.is_encapsulated(reader, item.length - 1)? {
+                    self.print_encapsulated(reader, item.length - 1, level)?;
+                } else if item.length - 1 <= MAX_BINARY_BITSTRING || names.is_some() {
+                    self.print_bits(reader, item.length - 1, unused[0], names)?;
+                } else {
+                    self.dump_hex(reader, item.length - 1, level, false)?;
                 }
-                OCTETSTRING => {
+            }
+            Some(OCTETSTRING) => {
+                if self.is_encapsulated(reader, item.length)? {
+                    self.print_encapsulated(reader, item.length, level)?;
+                } else if self.config.check_charset
+                    && !self.config.dump_text
+                    && item.length > 0
+                    && item.length < 1024
+                {
                     // Try to detect if it's text
-                    if self.config.check_charset && item.length > 0 && item.length < 1024 {
-                        self.print_string(reader, item.length, level)?;
-                    } else {
-                        self.dump_hex(reader, item.length, level)?;
-                    }
+                    self.print_string(reader, item.length, level, OCTETSTRING)?;
+                } else {
+                    self.dump_hex(reader, item.length, level, self.config.dump_text)?;
                 }
-                NULLTAG => {
-                    println!();
+            }
+            Some(NULLTAG) => {
+                writeln!(self.out);
+            }
+            Some(OID) => {
+                self.print_oid(reader, item.length, level)?;
+            }
+            Some(RELATIVE_OID) => {
+                self.print_relative_oid(reader, item.length)?;
+            }
+            Some(
+                tag @ (UTF8STRING | PRINTABLESTRING | IA5STRING | VISIBLESTRING | GENERALSTRING
+                | NUMERICSTRING | T61STRING | VIDEOTEXSTRING),
+            ) => {
+                self.print_string(reader, item.length, level, tag)?;
+            }
+            Some(tag @ (UTCTIME | GENERALIZEDTIME)) => {
+                self.print_time(reader, item.length, level, tag)?;
+            }
+            Some(tag @ (BMPSTRING | UNIVERSALSTRING | OID_IRI | RELATIVE_OID_IRI)) => {
+                self.print_string(reader, item.length, level, tag)?;
+            }
+            _ => {
+                self.dump_hex(reader, item.length, level, self.config.dump_text)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print a BER constructed OCTET STRING or BIT STRING as the string made
+    /// up by joining its segments
+    fn print_segmented<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        item: &Asn1Item,
+        level: usize,
+        names: Option<&[&str]>,
+    ) -> io::Result<()> {
+        let mut segments = Segments::default();
+        self.read_segments(reader, item, &mut segments, 0)?;
+
+        let note = format!(" (reassembled from {} segments)", segments.count);
+        write!(self.out, "{}", note);
+
+        // Show the joined content the way a primitive string would be shown,
+        // with offsets in any encapsulated object relative to its start
+        let mut content = Vec::new();
+        if item.tag == BITSTRING as u32 {
+            content.push(segments.unused);
+        }
+        content.extend(segments.data);
+        let joined = Asn1Item {
+            id: item.id & !CONSTRUCTED,
+            length: content.len() as i64,
+            indefinite: false,
+            ..item.clone()
+        };
+        let mut nested = self.nested_dumper(content.len());
+        nested.column = self.column + note.len();
+        let result = nested.print_primitive(&mut io::Cursor::new(content), &joined, level, names);
+        self.merge(nested);
+        result
+    }
+
+    /// Collect the content of the segments of a constructed string, which
+    /// may themselves be constructed
+    fn read_segments<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        item: &Asn1Item,
+        segments: &mut Segments,
+        depth: usize,
+    ) -> io::Result<()> {
+        let invalid = |dumper: &Self, msg: &str| -> io::Error {
+            DumpError::malformed(Some(dumper.f_pos), &dumper.path, msg).into()
+        };
+        if depth > MAX_SEGMENT_DEPTH {
+            return Err(invalid(self, "Constructed string nested too deeply"));
+        }
+
+        let end_pos = self.f_pos + item.length as usize;
+        while item.indefinite || self.f_pos < end_pos {
+            let segment = match self.get_item(reader)? {
+                Some(segment) => segment,
+                None => break,
+            };
+            if item.indefinite && segment.id == 0 && segment.tag == EOC as u32 {
+                break;
+            }
+            if (segment.id & CLASS_MASK) != UNIVERSAL || segment.tag != item.tag {
+                return Err(invalid(
+                    self,
+                    "Constructed string segment has the wrong type",
+                ));
+            }
+
+            if (segment.id & FORM_MASK) == CONSTRUCTED {
+                self.read_segments(reader, &segment, segments, depth + 1)?;
+                continue;
+            }
+            let mut data = vec![0u8; segment.length as usize];
+            reader.read_exact(&mut data)?;
+            self.f_pos += data.len();
+            segments.count += 1;
+            if item.tag == BITSTRING as u32 {
+                // Each BIT STRING segment has its own unused-bits octet, of
+                // which only the last one may be nonzero
+                let (&unused, bits) = data
+                    .split_first()
+                    .ok_or_else(|| invalid(self, "Empty BIT STRING segment"))?;
+                segments.unused = unused;
+                segments.data.extend_from_slice(bits);
+            } else {
+                segments.data.extend(data);
+            }
+        }
+        Ok(())
+    }
+
+    /// Print the plaintext of a decrypted PKCS #12 EncryptedData layer, which
+    /// holds a SafeContents
+    fn print_decrypted(&mut self, result: Decrypted, level: usize) -> io::Result<()> {
+        let plaintext = match result {
+            Ok(plaintext) => plaintext,
+            Err(msg) => {
+                self.print_indent(level + 1);
+                writeln!(self.out, "decryption failed: {}", msg);
+                let msg = format!("decryption failed: {}", msg);
+                self.record(Severity::Warning, "decryption-failed", &msg);
+                return Ok(());
+            }
+        };
+
+        self.print_indent(level + 1);
+        writeln!(self.out, "decrypted content {{");
+        let mut nested = self.nested_dumper(plaintext.len());
+        templates::annotate(
+            &plaintext,
+            0,
+            &templates::SAFE_CONTENTS,
+            &mut nested.annotations,
+        );
+        let result = nested.print_all(&mut io::Cursor::new(plaintext), level + 2);
+        // Offsets in the plaintext don't point into the input, so findings
+        // there are placed at the encrypted item
+        for diagnostic in &mut nested.findings.diagnostics {
+            diagnostic.offset = self.item_offsets.last().copied();
+        }
+        self.merge(nested);
+        result?;
+        self.print_indent(level + 1);
+        writeln!(self.out, "}}");
+        Ok(())
+    }
+
+    /// Print all the items left in the input at the given level
+    fn print_all<R: Read + Seek>(&mut self, reader: &mut R, level: usize) -> io::Result<()> {
+        while let Some(item) = self.get_item(reader)? {
+            self.print_asn1_object(reader, &item, level)?;
+        }
+        Ok(())
+    }
+
+    /// Main entry point to dump ASN.1 data
+    fn dump_asn1<R: Read + Seek>(&mut self, reader: &mut R) -> io::Result<()> {
+        self.dump_items(reader)?;
+        self.print_summary();
+        Ok(())
+    }
+
+    /// Dump a file, then write out the errors and warnings if --errors-json
+    /// asks for them. The error that stopped the dump, if any, is recorded
+    /// with the rest. `input` is the file's content when --auto has decoded
+    /// it from base64 already
+    fn dump_file(&mut self, filename: &str, input: Option<Vec<u8>>) -> io::Result<()> {
+        match input {
+            Some(data) => self.dump_input(Some(filename), &mut Cursor::new(data)),
+            None => {
+                let file = File::open(filename)
+                    .map_err(|e| DumpError::io(format!("Can't open '{}'", filename), e))?;
+                let mut reader = BufReader::new(file);
+                match compressed::read_decompressed(&mut reader)
+                    .map_err(|e| DumpError::io(format!("Can't decompress '{}'", filename), e))?
+                {
+                    Some(data) => self.dump_input(Some(filename), &mut Cursor::new(data)),
+                    None => self.dump_input(Some(filename), &mut reader),
                 }
-                OID => {
-                    self.print_oid(reader, item.length, level)?;
+            }
+        }
+    }
+
+    /// Dump `reader`, read from `filename` or decoded from it, as dump_file.
+    /// Input held in memory has no name, and no header naming it
+    fn dump_input<R: BufRead + Seek>(
+        &mut self,
+        filename: Option<&str>,
+        reader: &mut R,
+    ) -> io::Result<()> {
+        // Not timed otherwise, as there's no clock in some wasm32 targets
+        let started = self.config.timing.then(Instant::now);
+        if started.is_some() {
+            self.out.measure_rendering();
+        }
+
+        self.load_issuer()?;
+
+        // PEM files are decoded in memory, DER is dumped straight from the file
+        let is_pem = pem::looks_like_pem(reader.fill_buf()?);
+
+        if self.config.format.is_some()
+            || self.config.find_oid.is_some()
+            || self.config.emit_code.is_some()
+        {
+            // The output is in the format asked for and nothing else
+        } else if self.config.verbose {
+            if let Some(filename) = filename {
+                writeln!(self.out, "Dumping ASN.1 file: {}", filename);
+            }
+            writeln!(self.out, "Configuration:");
+            writeln!(self.out, "  Print all data: {}", self.config.print_all_data);
+            writeln!(self.out, "  Check charset: {}", self.config.check_charset);
+            writeln!(
+                self.out,
+                "  Check encapsulation: {}",
+                self.config.check_encaps
+            );
+            writeln!(
+                self.out,
+                "  Max nesting level: {}",
+                self.config.max_nest_level
+            );
+            writeln!(self.out);
+        } else if let (false, Some(filename)) = (self.config.do_pure, filename) {
+            writeln!(self.out, "Dumping ASN.1 file: {}\n", filename);
+        }
+
+        let result = if let Some(oid) = self.config.find_oid.clone() {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            self.find_oid(&data, &oid)
+        } else if let Some(language) = self.config.emit_code {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            self.emit_code(&data, language)
+        } else if let Some(format) = self.config.format {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            self.render_asn1(&data, format)
+        } else if is_pem {
+            let mut text = Vec::new();
+            reader.read_to_end(&mut text)?;
+            self.dump_pem(&pem::parse_pem(&text))
+        } else {
+            self.dump_asn1(reader)
+        };
+
+        let result = result.and_then(|()| self.out.render(|out| out.flush()));
+        if let (Some(started), Some(render_time)) = (started, self.out.render_time()) {
+            eprintln!("{}", timing_report(started.elapsed(), render_time));
+        }
+
+        // With --strict, an error found after the first is the one that
+        // stopped the dump, and has been recorded already
+        let aborted = self.config.strict && self.findings.errors > 0;
+        if let (Err(e), false) = (&result, aborted) {
+            let offset = self.item_offsets.last().copied();
+            let diagnostic = diagnostics::fatal(e, offset, &self.path);
+            self.findings.record(diagnostic);
+        }
+        if let Some(destination) = self.config.errors_json.clone() {
+            diagnostics::write_json(&self.findings.diagnostics, &destination)
+                .map_err(|e| DumpError::io(format!("Can't write '{}'", destination), e))?;
+        }
+        result
+    }
+
+    /// Main entry point for --format: describe the input as nodes and hand
+    /// them to the renderer for the format
+    fn render_asn1(&mut self, data: &[u8], format: Format) -> io::Result<()> {
+        let mut nodes = self.top_nodes(data)?;
+        if let Some(range) = &self.config.range {
+            nodes = render::window(&nodes, range);
+        }
+        if let Some(range) = &self.config.highlight {
+            render::highlight(&mut nodes, range);
+        }
+        self.out
+            .render(|out| render::render(format.renderer().as_mut(), out, &nodes))?;
+        if let (Some(_), Some(select)) = (self.config.digest_each, &self.config.select) {
+            if self.digests_printed == 0 {
+                let msg = format!("No item matches '{}'", select);
+                writeln!(self.out, "{}", msg);
+                self.record(Severity::Warning, "no-match", &msg);
+            }
+        }
+        Ok(())
+    }
+
+    /// Main entry point for --find-oid: list where `oid` occurs, by offset
+    /// and the path of field names or types down to it
+    fn find_oid(&mut self, data: &[u8], oid: &str) -> io::Result<()> {
+        let mut found = Vec::new();
+        for node in &self.top_nodes(data)? {
+            occurrences(node, oid, &mut Vec::new(), &mut found);
+        }
+        for (offset, path) in &found {
+            let offset = format_offset(*offset, self.config.do_hex_values);
+            writeln!(self.out, "{}: {}", offset, path.join(" > "));
+        }
+        if found.is_empty() {
+            let msg = format!("No occurrences of '{}'", oid);
+            writeln!(self.out, "{}", msg);
+            self.record(Severity::Warning, "no-match", &msg);
+        } else {
+            writeln!(self.out, "\n{} occurrence(s) found.", found.len());
+        }
+        Ok(())
+    }
+
+    /// Main entry point for --emit-code: print the encoding of each item
+    /// --select picks, or of each top-level item, as an array in `language`
+    fn emit_code(&mut self, data: &[u8], language: Language) -> io::Result<()> {
+        let blocks = self.der_blocks(data)?;
+        let mut items = Vec::new(); // The block each is in, and the node
+        for (index, (label, der)) in blocks.iter().enumerate() {
+            for mut node in nodes::to_nodes(der, &self.config)? {
+                if let Some(label) = label {
+                    node.label.get_or_insert_with(|| label.clone());
                 }
-                UTF8STRING | PRINTABLESTRING | IA5STRING | VISIBLESTRING | GENERALSTRING
-                | NUMERICSTRING | T61STRING | VIDEOTEXSTRING => {
-                    self.print_string(reader, item.length, level)?;
+                items.push((index, node));
+            }
+        }
+        let items = items
+            .iter()
+            .skip(self.config.skip)
+            .take(self.config.first.unwrap_or(usize::MAX));
+
+        let mut targets: Vec<(usize, &Node)> = Vec::new();
+        match self.config.select.clone() {
+            None => targets.extend(items.map(|(index, node)| (*index, node))),
+            Some(select) => {
+                for (index, node) in items {
+                    selected(node, &select, &mut |node| targets.push((*index, node)));
                 }
-                UTCTIME | GENERALIZEDTIME => {
-                    self.print_string(reader, item.length, level)?;
+                if targets.is_empty() {
+                    let msg = format!("No item matches '{}'", select);
+                    writeln!(self.out, "{}", msg);
+                    self.record(Severity::Warning, "no-match", &msg);
                 }
-                BMPSTRING | UNIVERSALSTRING => {
-                    self.print_string(reader, item.length, level)?;
+            }
+        }
+
+        // Arrays with the same name are numbered from the second on
+        let mut names: HashMap<String, usize> = HashMap::new();
+        for (emitted, (index, node)) in targets.into_iter().enumerate() {
+            // The nodes are of definite-length DER, which to_nodes insists on
+            let offset = node.offset.unwrap_or(0);
+            let length = node.header_length + node.length.unwrap_or(0);
+            let mut identifier = language.identifier(node.label.as_deref());
+            let count = names.entry(identifier.clone()).or_insert(0);
+            *count += 1;
+            if *count > 1 {
+                identifier = format!("{}_{}", identifier, count);
+            }
+            let comment = format!(
+                "{} at offset {}, {} bytes",
+                node.label.as_deref().unwrap_or(&node.kind),
+                offset,
+                length
+            );
+            let bytes = &blocks[index].1[offset..offset + length];
+            if emitted > 0 {
+                writeln!(self.out);
+            }
+            write!(
+                self.out,
+                "{}",
+                emit::byte_array(language, &identifier, &comment, bytes)
+            );
+        }
+        Ok(())
+    }
+
+    /// The top-level items as render nodes, less those --skip and --first
+    /// leave out
+    fn top_nodes(&mut self, data: &[u8]) -> io::Result<Vec<Node>> {
+        Ok(self
+            .input_nodes(data)?
+            .into_iter()
+            .skip(self.config.skip)
+            .take(self.config.first.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Describe DER, or the blocks of a PEM file one after the other, as
+    /// render nodes, with the digests --digest-each asks for
+    fn input_nodes(&mut self, data: &[u8]) -> io::Result<Vec<Node>> {
+        if !pem::looks_like_pem(data) && self.config.digest_each.is_none() {
+            return nodes::to_nodes(data, &self.config);
+        }
+        let mut nodes = Vec::new();
+        for (label, der) in self.der_blocks(data)? {
+            for mut node in nodes::to_nodes(&der, &self.config)? {
+                if let Some(label) = &label {
+                    node.label.get_or_insert_with(|| label.clone());
                 }
-                _ => {
-                    self.dump_hex(reader, item.length, level)?;
+                // Noted while it's known which block the offsets are in,
+                // for the items --skip and --first keep
+                let first = self.config.first.unwrap_or(usize::MAX);
+                let kept =
+                    nodes.len() >= self.config.skip && nodes.len() - self.config.skip < first;
+                if let (Some(alg), true) = (self.config.digest_each, kept) {
+                    let select = self.config.select.as_deref();
+                    self.digests_printed += note_digests(&mut node, &der, alg, select);
                 }
+                nodes.push(node);
             }
         }
+        Ok(nodes)
+    }
 
+    /// The DER in the input, the whole of it or each block of a PEM file
+    /// with the block's label
+    fn der_blocks(&mut self, data: &[u8]) -> io::Result<Vec<(Option<String>, Vec<u8>)>> {
+        if !pem::looks_like_pem(data) {
+            return Ok(vec![(None, data.to_vec())]);
+        }
+        let mut blocks = Vec::new();
+        for block in pem::parse_pem(data) {
+            match block.data {
+                Ok(der) => blocks.push((Some(block.label), der)),
+                Err(msg) => {
+                    // With --strict the error stops the dump, and is
+                    // printed as that
+                    let msg = format!("can't decode block: {}", msg);
+                    self.report_error("pem-decode", &msg)?;
+                    if self.inline_errors {
+                        writeln!(
+                            self.out,
+                            "{} {}",
+                            self.out.paint(Style::Error, "Error:"),
+                            msg
+                        );
+                    } else {
+                        // The output is a rendering or code, which the error
+                        // can't go in; flushed first so that on a terminal
+                        // it comes after what's been printed
+                        self.out.flush()?;
+                        eprintln!("Error: {}", msg);
+                    }
+                }
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Dump the DER blocks of a PEM file one after the other, with offsets
+    /// relative to the start of each block
+    fn dump_pem(&mut self, blocks: &[PemBlock]) -> io::Result<()> {
+        for (index, block) in blocks.iter().enumerate() {
+            if self.config.first == Some(self.dumped) {
+                break;
+            }
+            if index > 0 {
+                writeln!(self.out);
+            }
+            writeln!(
+                self.out,
+                "PEM block {} of {}: {}",
+                index + 1,
+                blocks.len(),
+                block.label
+            );
+            match &block.data {
+                Ok(data) => self.dump_items(&mut io::Cursor::new(data))?,
+                Err(msg) => {
+                    let msg = format!("can't decode block: {}", msg);
+                    self.report_error("pem-decode", &msg)?;
+                    writeln!(
+                        self.out,
+                        "{} {}",
+                        self.out.paint(Style::Error, "Error:"),
+                        msg
+                    );
+                }
+            }
+        }
+
+        self.print_chain_order(blocks);
+        self.print_summary();
         Ok(())
     }
 
-    /// Main entry point to dump ASN.1 data
-    fn dump_asn1<R: Read + Seek>(&mut self, reader: &mut R) -> io::Result<()> {
-        while let Some(item) = self.get_item(reader)? {
-            self.print_asn1_object(reader, &item, 0)?;
+    /// Summarize how the certificates in a PEM file chain together
+    fn print_chain_order(&mut self, blocks: &[PemBlock]) {
+        let certificates: Vec<x509::Certificate> = blocks
+            .iter()
+            .filter(|block| block.label == "CERTIFICATE")
+            .filter_map(|block| block.data.as_deref().ok())
+            .filter_map(x509::parse_certificate)
+            .collect();
+        if certificates.len() < 2 {
+            return;
         }
 
-        println!("\nParsing complete.");
-        if self.no_errors > 0 {
-            println!("Errors: {}", self.no_errors);
+        writeln!(self.out, "\nCertificate chain:");
+        let mut in_order = true;
+        for (index, certificate) in certificates.iter().enumerate() {
+            writeln!(
+                self.out,
+                "  {}: {}",
+                index + 1,
+                x509::name_to_string(certificate.subject)
+            );
+            let issuer = certificates
+                .iter()
+                .position(|other| other.subject == certificate.issuer);
+            let status = if certificate.issuer == certificate.subject {
+                "self-signed".to_string()
+            } else {
+                match issuer {
+                    Some(position) if position == index + 1 => {
+                        format!("issued by {}", position + 1)
+                    }
+                    Some(position) => format!("issued by {}, out of order", position + 1),
+                    None => format!(
+                        "issuer {} not in file",
+                        x509::name_to_string(certificate.issuer)
+                    ),
+                }
+            };
+            writeln!(self.out, "     {}", status);
+            if index + 1 < certificates.len() && issuer != Some(index + 1) {
+                in_order = false;
+            }
         }
-        if self.no_warnings > 0 {
-            println!("Warnings: {}", self.no_warnings);
+
+        if in_order {
+            writeln!(
+                self.out,
+                "Chain order: leaf first, each certificate issued by the next"
+            );
+        } else {
+            let msg = "certificates aren't in issuing order";
+            writeln!(self.out, "Chain order: {}", msg);
+            self.record(Severity::Warning, "chain-order", msg);
+        }
+    }
+
+    /// Dump all the top-level items in the input
+    fn dump_items<R: Read + Seek>(&mut self, reader: &mut R) -> io::Result<()> {
+        let start = reader.stream_position()?;
+        self.input_len = (reader.seek(SeekFrom::End(0))? - start) as usize;
+        reader.seek(SeekFrom::Start(start))?;
+        self.f_pos = 0;
+
+        while self.config.first != Some(self.dumped) {
+            let item_start = self.f_pos;
+            let next = if self.skipped < self.config.skip {
+                self.skip_next_item(reader)
+            } else {
+                self.dump_next_item(reader)
+            };
+            match next {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) if self.config.recover && diagnostics::is_malformed(&e) => {
+                    writeln!(
+                        self.out,
+                        "\n{} {}",
+                        self.out.paint(Style::Error, "Error:"),
+                        e
+                    );
+                    let offset = self.item_offsets.last().copied();
+                    let diagnostic = diagnostics::fatal(&e, offset, &self.path);
+                    self.findings.record(diagnostic);
+                    self.path.clear();
+                    self.item_offsets.clear();
+                    // Start looking after whatever of the bad item was read,
+                    // and at least one byte past its start
+                    let position = (reader.stream_position()? - start) as usize;
+                    let from = position.max(item_start + 1);
+                    if !self.resync(reader, start, item_start, from)? {
+                        break;
+                    }
+                }
+                Err(e) => return Err(e),
+            }
         }
 
         Ok(())
     }
+
+    /// Dump the next top-level item, returning false at the end of the input
+    fn dump_next_item<R: Read + Seek>(&mut self, reader: &mut R) -> io::Result<bool> {
+        let item = match self.get_item(reader)? {
+            Some(item) => item,
+            None => return Ok(false),
+        };
+        let offset = self.f_pos - item.header_size;
+        let encoding = self.peek_item(reader, &item)?;
+        self.load_annotations(encoding.as_deref(), offset);
+        self.print_asn1_object(reader, &item, 0)?;
+        if let Some(alg) = self.config.digest {
+            self.print_digests(encoding.as_deref(), offset, alg);
+        }
+        self.dumped += 1;
+        Ok(true)
+    }
+
+    /// Read past the next top-level item without printing it, for --skip,
+    /// returning false at the end of the input
+    fn skip_next_item<R: Read + Seek>(&mut self, reader: &mut R) -> io::Result<bool> {
+        let item = match self.get_item(reader)? {
+            Some(item) => item,
+            None => return Ok(false),
+        };
+        self.skip_item(reader, &item, 0)?;
+        self.skipped += 1;
+        Ok(true)
+    }
+
+    /// Skip ahead to the next plausible item at or after `from` once the
+    /// item at `item_start` has turned out malformed (--recover), noting the
+    /// bytes passed over from its start. Returns false if nothing else in
+    /// the input looks like an item.
+    fn resync<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        start: u64,
+        item_start: usize,
+        from: usize,
+    ) -> io::Result<bool> {
+        let from = from.min(self.input_len);
+        reader.seek(SeekFrom::Start(start + from as u64))?;
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest)?;
+
+        let found = der::find_plausible_item(&rest);
+        let resume = from + found.unwrap_or(rest.len());
+        let msg = skipped_message(item_start, resume - item_start, found.is_some());
+        self.print_indent(0);
+        writeln!(self.out, "[{}]", msg);
+        self.findings.record(Diagnostic {
+            code: "skipped-bytes",
+            severity: Severity::Warning,
+            offset: Some(item_start),
+            path: Vec::new(),
+            message: msg,
+        });
+
+        self.f_pos = resume;
+        reader.seek(SeekFrom::Start(start + self.f_pos as u64))?;
+        Ok(found.is_some())
+    }
+
+    /// Print the closing totals
+    fn print_summary(&mut self) {
+        if let (Some(_), Some(select)) = (self.config.digest, &self.config.select) {
+            if self.digests_printed == 0 {
+                let msg = format!("No item matches '{}'", select);
+                writeln!(self.out, "{}", msg);
+                self.record(Severity::Warning, "no-match", &msg);
+            }
+        }
+
+        writeln!(self.out, "\nParsing complete.");
+        if self.skipped > 0 {
+            writeln!(self.out, "{} item(s) skipped.", self.skipped);
+        }
+        if self.config.first == Some(self.dumped) {
+            writeln!(
+                self.out,
+                "Stopped after the first {}; the rest of the input wasn't read.",
+                self.dumped
+            );
+        }
+        write!(self.out, "{}", self.findings.totals());
+    }
 }
 
 fn print_help(program_name: &str) {
     println!("ASN.1 DER Dumper - Rust Implementation");
     println!("Based on dumpasn1.c by Peter Gutmann\n");
-    println!("Usage: {} [OPTIONS] <input_file>", program_name);
-    println!("\nDumps ASN.1 DER-encoded data in a human-readable format.\n");
+    println!(
+        "Usage: {} [OPTIONS] <input_file|directory|pattern>",
+        program_name
+    );
+    println!("\nDumps ASN.1 DER-encoded data in a human-readable format.");
+    println!("Given a directory (standing for every file below it) or a quoted pattern");
+    println!("such as 'certs/**/*.der', dumps each file under a header naming it and");
+    println!("ends with a table of the errors and warnings found in each.\n");
     println!("OPTIONS:");
     println!("  -h, --help              Show this help message and exit");
     println!(
@@ -592,6 +2280,11 @@ fn print_help(program_name: &str) {
     println!(
         "  -f <file>               Read input from <file> (alternative to positional argument)"
     );
+    println!("  -O <file>               Write the dump to <file> instead of stdout");
+    println!(
+        "  --expect <file>         Compare the dump with the one in <file>, printing a unified"
+    );
+    println!("                          diff and exiting with 1 if they differ, or 0 if not");
     println!("  -i, --shallow-indent    Use shallow indenting (1 space instead of 2)");
     println!("  -l <length>             Maximum nesting level for which to display output (default: 100)");
     println!(
@@ -600,13 +2293,67 @@ fn print_help(program_name: &str) {
     println!("  -p, --pure              Pure display mode: no offset/length information on left");
     println!("  -r, --raw-time          Print time values as raw string instead of formatted");
     println!("  -t, --text              Dump text alongside hex data for OCTET STRINGs");
-    println!("  -v, --verbose           Verbose output with extra information");
+    println!("  -v, --verbose           Verbose output, with the class, form and length encoding");
+    println!("                          of each item on a line before it");
     println!("  -w <width>              Set output width in characters (default: 80)");
     println!("  -x, --hex-values        Display size and offset in hex, not decimal");
-    println!("  -z, --zero-length       Allow zero-length items (normally flagged as errors)");
+    println!("  -z, --zero-length       Allow zero-length items (normally flagged as warnings)");
     println!("  --dots                  Print dots to align columns");
     println!("  --no-offset             Don't print offset information");
+    println!("  --lengths               Show the header length of each item in a column of its");
+    println!("                          own, before the content length");
     println!("  --oid-info              Print extra information about OIDs");
+    println!("  --oid-config <file>     Read more OID names from <file>, as in dumpasn1.cfg");
+    println!("  --show-secrets          Show private key material instead of masking it");
+    println!("  --color <when>          Color errors and warnings: auto, always or never");
+    println!("  --strict                Check DER-only rules such as SET OF element ordering,");
+    println!("                          and stop at the first error");
+    println!("  --max-item-bytes <n>    Reject primitive items longer than <n> bytes");
+    println!("  --recover               After a malformed item, skip to the next plausible one");
+    println!("  --skip <n>              Pass over the first <n> top-level items unprinted");
+    println!("  --first <n>             Stop after <n> top-level items, leaving the rest unread");
+    println!("  --range <start>..<end>  Show only the elements encoded in this byte window, as");
+    println!("                          --format tree unless --format says otherwise, noting");
+    println!("                          those partly outside it (either end can be left out)");
+    println!("  --highlight <start>:<len>");
+    println!("                          Note which elements overlap these bytes, such as the");
+    println!("                          ones an error message pointed at, and which enclose");
+    println!("                          them, shown as for --range");
+    println!("  --auto                  Detect whether the input is ASN.1 or CBOR, bare base64");
+    println!("                          of either included, and dump it accordingly");
+    println!(
+        "  --format <format>       Write the decoded items as {} instead of the dump",
+        Format::NAMES
+    );
+    println!(
+        "  --errors-json <file>    Write errors and warnings as JSON to <file> (- for stderr)"
+    );
+    println!("  --timing                Report the time spent parsing and rendering on stderr");
+    println!(
+        "  --max-depth <n>         Nesting depth at which parsing stops (default: {})",
+        MAX_PARSE_DEPTH
+    );
+    println!("  --int-hex               Print INTEGER and ENUMERATED values in hex");
+    println!("  --int-both              Print INTEGER and ENUMERATED values in decimal and hex");
+    println!("  --password <pw>         Decrypt PKCS #12 EncryptedData using this password");
+    println!("  --digest <alg>          Print the sha1/sha256 digest of each top-level item,");
+    println!("                          or of the items chosen with --select");
+    println!("  --digest-each <alg>     Show the elements as --format tree does, each with the");
+    println!("                          sha1/sha256 digest of its encoding, or only those");
+    println!("                          chosen with --select");
+    println!("  --select <item>         Item to digest or write code for, by field name");
+    println!("                          (tbsCertificate) or offset");
+    println!("  --find-oid <oid>        List where this OID, dotted or by name, occurs, with the");
+    println!("                          path of the structure around it, instead of the dump");
+    println!("  --emit-code <lang>      Write each top-level item, or the items chosen with");
+    println!(
+        "                          --select, as a byte array in {} source code",
+        Language::NAMES
+    );
+    println!("  --at <time>             Check certificate validity at this time rather than now,");
+    println!("                          as 2024-06-01, 2024-06-01T12:00:00Z or @<epoch seconds>");
+    println!("  --issuer <file>         Check certificate signatures with the key of this issuer");
+    println!("                          certificate, DER or PEM (needs the crypto feature)");
     println!("\nEXAMPLES:");
     println!("  {} certificate.der", program_name);
     println!(
@@ -617,16 +2364,230 @@ fn print_help(program_name: &str) {
         "  {} --outline --max-level 5 large.der  # Show only top 5 levels",
         program_name
     );
-    println!("\nThe input file should contain binary DER-encoded ASN.1 data.");
+    println!("\nThe input file should contain binary DER-encoded ASN.1 data. Default");
+    println!("options can be set in ~/.config/asn1-cbor-tools/config.toml, or with");
+    println!("ASN1CBOR_COLOR, ASN1CBOR_WIDTH and DUMPASN1_CFG (the OID config).");
+}
+
+/// Type of an item as displayed: the universal type name, or the class
+/// and tag number in brackets
+fn type_name(id: u8, tag: u32) -> String {
+    let class_name = match id & CLASS_MASK {
+        APPLICATION => "APPLICATION ",
+        CONTEXT => "",
+        PRIVATE => "PRIVATE ",
+        _ => return tag_name(tag).to_string(),
+    };
+    format!("[{}{}]", class_name, tag)
+}
+
+/// The bits of a BIT STRING's content, most significant bit of the first
+/// octet first, without the unused trailing bits
+fn bit_values(content: &[u8], unused: u8) -> Vec<bool> {
+    let total = (content.len() * 8).saturating_sub(unused as usize);
+    (0..total)
+        .map(|index| content[index / 8] & (0x80 >> (index % 8)) != 0)
+        .collect()
+}
+
+/// Read past content that isn't displayed without buffering it
+fn skip_bytes<R: Read>(reader: &mut R, count: u64) -> io::Result<()> {
+    if io::copy(&mut reader.take(count), &mut io::sink())? < count {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+    Ok(())
+}
+
+/// Check whether a universal type needs at least one content octet. NULL
+/// and the string types can legitimately be empty.
+fn requires_content(tag: u32) -> bool {
+    u8::try_from(tag).is_ok_and(|tag| {
+        matches!(
+            tag,
+            BOOLEAN
+                | INTEGER
+                | BITSTRING
+                | OID
+                | ENUMERATED
+                | RELATIVE_OID
+                | UTCTIME
+                | GENERALIZEDTIME
+                | OID_IRI
+                | RELATIVE_OID_IRI
+        )
+    })
+}
+
+/// Compare two encodings the way DER sorts SET OF elements, as octet strings
+/// with the shorter one padded with trailing zero octets
+fn compare_padded(a: &[u8], b: &[u8]) -> Ordering {
+    let octet = |data: &[u8], index: usize| data.get(index).copied().unwrap_or(0);
+    (0..a.len().max(b.len()))
+        .map(|index| octet(a, index).cmp(&octet(b, index)))
+        .find(|order| order.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Check whether an INTEGER starts with a 0x00 or 0xFF byte that DER
+/// requires to be omitted, given its first two content bytes
+fn has_redundant_sign_byte(start: &[u8]) -> bool {
+    match start {
+        [0x00, next, ..] => next & 0x80 == 0,
+        [0xFF, next, ..] => next & 0x80 != 0,
+        _ => false,
+    }
+}
+
+/// Format an integer value in decimal, hex, or both
+fn format_integer(value: i64, format: IntFormat) -> String {
+    let hex = if value < 0 {
+        format!("-0x{:X}", value.unsigned_abs())
+    } else {
+        format!("0x{:X}", value)
+    };
+    match format {
+        IntFormat::Decimal => value.to_string(),
+        IntFormat::Hex => hex,
+        IntFormat::Both => format!("{} ({})", value, hex),
+    }
+}
+
+/// Note the offsets of the OBJECT IDENTIFIERs in `node` that are `oid`, by
+/// dotted form or name, with the path to each: the field names, or types
+/// where there are none, of the items enclosing it, and its own field name
+fn occurrences(
+    node: &Node,
+    oid: &str,
+    path: &mut Vec<String>,
+    found: &mut Vec<(usize, Vec<String>)>,
+) {
+    if let Content::Oid { dotted, name } = &node.content {
+        if dotted == oid || name.as_deref() == Some(oid) {
+            let mut path = path.clone();
+            path.extend(node.label.clone());
+            found.push((node.offset.unwrap_or(0), path));
+        }
+        return;
+    }
+    path.push(node.label.clone().unwrap_or_else(|| node.kind.clone()));
+    for child in node.children() {
+        occurrences(child, oid, path, found);
+    }
+    path.pop();
+}
+
+/// Pass `found` the nodes under and including `node` that --select picks,
+/// by field name or offset, not looking inside a node that was picked
+fn selected<'a>(node: &'a Node, select: &str, found: &mut impl FnMut(&'a Node)) {
+    if picks(node, select) {
+        found(node);
+        return;
+    }
+    for child in node.children() {
+        selected(child, select, found);
+    }
+}
+
+/// Whether --select picks `node`
+fn picks(node: &Node, select: &str) -> bool {
+    match parse_offset(select) {
+        Some(position) => node.offset == Some(position),
+        None => node.label.as_deref() == Some(select),
+    }
+}
+
+/// Note the `alg` digest of each element in `node`, whose encoding `data`
+/// holds, for --digest-each: of every one, or of those --select picks as
+/// `selected` does. Returns how many were noted
+fn note_digests(node: &mut Node, data: &[u8], alg: HashAlg, select: Option<&str>) -> usize {
+    if select.is_none_or(|select| picks(node, select)) {
+        let noted = render::note_digest(node, data, alg) as usize;
+        if select.is_some() {
+            return noted;
+        }
+    }
+    node.children_mut()
+        .into_iter()
+        .map(|child| note_digests(child, data, alg, select))
+        .sum()
+}
+
+/// What an item's identifier and length octets say, for -v: the class and
+/// constructed bits, and which forms the tag number and length were given
+fn header_detail(header: &[u8]) -> String {
+    let id = header[0];
+    let class = match id & CLASS_MASK {
+        UNIVERSAL => "universal",
+        APPLICATION => "application",
+        CONTEXT => "context-specific",
+        _ => "private",
+    };
+    let form = if id & FORM_MASK == CONSTRUCTED {
+        "constructed"
+    } else {
+        "primitive"
+    };
+    // A high tag number goes on in octets with the top bit set, up to the
+    // first without it
+    let tag_octets = if id & TAG_MASK == TAG_MASK {
+        1 + header[1..]
+            .iter()
+            .position(|octet| octet & 0x80 == 0)
+            .map_or(header.len() - 1, |last| last + 1)
+    } else {
+        1
+    };
+    let tag = match tag_octets {
+        1 => "tag number in the identifier".to_string(),
+        2 => "tag number in 1 more octet".to_string(),
+        n => format!("tag number in {} more octets", n - 1),
+    };
+    let length = match header.get(tag_octets) {
+        Some(0x80) => "indefinite length".to_string(),
+        Some(&octet) if octet < 0x80 => "short form length".to_string(),
+        Some(&octet) => format!("long form length in {} more octets", octet & 0x7F),
+        None => "no length".to_string(),
+    };
+    format!(
+        "identifier 0x{:02X}: {}, {}, {}; {}",
+        id, class, form, tag, length
+    )
+}
+
+/// Format the header octets of an item in angle brackets, followed by any
+/// content octets shown with -dd
+fn format_header_dump(header: &[u8], content: &[u8]) -> String {
+    if content.is_empty() {
+        format!("<{}>", hex_upper(header, " "))
+    } else {
+        format!("<{}> {}", hex_upper(header, " "), hex_upper(content, " "))
+    }
 }
 
 fn parse_args_from(args: &[String]) -> Result<(Config, Option<String>), String> {
+    let (config, inputs) = parse_options(args)?;
+    match inputs[..] {
+        [] => Ok((config, None)),
+        [input] => Ok((config, Some(args[input].clone()))),
+        [first, second, ..] => Err(format!(
+            "Multiple input files specified: {} and {}",
+            args[first], args[second]
+        )),
+    }
+}
+
+/// Parse the options in `args`, returning them with the positions in `args`
+/// of the input files named
+fn parse_options(args: &[String]) -> Result<(Config, Vec<usize>), String> {
     if args.len() < 2 {
         return Err("No input file specified".to_string());
     }
 
+    // Options from the config file come first, for those given to override
+    let (with_defaults, defaults) = cli::with_defaults("dump-asn1", args);
+    let args = &with_defaults[..];
     let mut config = Config::default();
-    let mut input_file: Option<String> = None;
+    let mut inputs = Vec::new();
     let mut i = 1;
 
     while i < args.len() {
@@ -653,23 +2614,20 @@ fn parse_args_from(args: &[String]) -> Result<(Config, Option<String>), String>
                 config.check_encaps = false;
             }
             "-f" => {
-                i += 1;
-                if i >= args.len() {
-                    return Err("Missing filename after -f".to_string());
-                }
-                input_file = Some(args[i].clone());
+                option_value(args, &mut i)?;
+                inputs.push(i);
+            }
+            "-O" => {
+                config.output_file = Some(option_value(args, &mut i)?.to_string());
+            }
+            "--expect" => {
+                config.expect = Some(option_value(args, &mut i)?.to_string());
             }
             "-i" | "--shallow-indent" => {
                 config.shallow_indent = true;
             }
             "-l" | "--max-level" => {
-                i += 1;
-                if i >= args.len() {
-                    return Err("Missing value after -l".to_string());
-                }
-                config.max_nest_level = args[i]
-                    .parse()
-                    .map_err(|_| format!("Invalid number for max level: {}", args[i]))?;
+                config.max_nest_level = parse_number(option_value(args, &mut i)?, "max level")?;
             }
             "-o" | "--outline" => {
                 config.do_outline_only = true;
@@ -687,13 +2645,7 @@ fn parse_args_from(args: &[String]) -> Result<(Config, Option<String>), String>
                 config.verbose = true;
             }
             "-w" | "--width" => {
-                i += 1;
-                if i >= args.len() {
-                    return Err("Missing value after -w".to_string());
-                }
-                config.output_width = args[i]
-                    .parse()
-                    .map_err(|_| format!("Invalid number for width: {}", args[i]))?;
+                config.output_width = parse_number(option_value(args, &mut i)?, "width")?;
             }
             "-x" | "--hex-values" => {
                 config.do_hex_values = true;
@@ -707,91 +2659,311 @@ fn parse_args_from(args: &[String]) -> Result<(Config, Option<String>), String>
             "--no-offset" => {
                 config.print_offset = false;
             }
+            "--lengths" => {
+                config.show_lengths = true;
+            }
             "--oid-info" => {
                 config.extra_oid_info = true;
             }
+            "--oid-config" => {
+                let path = option_value(args, &mut i)?;
+                oids::load_config(path).map_err(|e| e.to_string())?;
+            }
+            "--color" => {
+                let value = option_value(args, &mut i)?;
+                config.color = ColorChoice::from_name(value).ok_or_else(|| {
+                    format!(
+                        "Unknown color choice: {} (use {})",
+                        value,
+                        ColorChoice::NAMES
+                    )
+                })?;
+            }
+            "--show-secrets" => {
+                config.show_secrets = true;
+            }
+            "--strict" => {
+                config.strict = true;
+            }
+            "--recover" => {
+                config.recover = true;
+            }
+            "--auto" => {
+                config.auto = true;
+            }
+            "--format" => {
+                let value = option_value(args, &mut i)?;
+                config.format =
+                    Some(Format::from_name(value).ok_or_else(|| {
+                        format!("Unknown format: {} (use {})", value, Format::NAMES)
+                    })?);
+            }
+            "--timing" => {
+                config.timing = true;
+            }
+            "--errors-json" => {
+                config.errors_json = Some(option_value(args, &mut i)?.to_string());
+            }
+            "--max-depth" => {
+                config.max_depth = parse_number(option_value(args, &mut i)?, "max depth")?;
+            }
+            "--max-item-bytes" => {
+                config.max_item_bytes =
+                    Some(parse_number(option_value(args, &mut i)?, "max item bytes")?);
+            }
+            "--skip" => {
+                config.skip = parse_number(option_value(args, &mut i)?, "skip")?;
+            }
+            "--first" => {
+                config.first = Some(parse_number(option_value(args, &mut i)?, "first")?);
+            }
+            "--range" => {
+                config.range = Some(parse_range(option_value(args, &mut i)?)?);
+            }
+            "--highlight" => {
+                config.highlight = Some(parse_span(option_value(args, &mut i)?)?);
+            }
+            "--int-hex" => {
+                config.int_format = IntFormat::Hex;
+            }
+            "--int-both" => {
+                config.int_format = IntFormat::Both;
+            }
+            "--digest" => {
+                let value = option_value(args, &mut i)?;
+                config.digest = Some(HashAlg::from_name(value)?);
+            }
+            "--digest-each" => {
+                let value = option_value(args, &mut i)?;
+                config.digest_each = Some(HashAlg::from_name(value)?);
+            }
+            "--select" => {
+                config.select = Some(option_value(args, &mut i)?.to_string());
+            }
+            "--find-oid" => {
+                config.find_oid = Some(option_value(args, &mut i)?.to_string());
+            }
+            "--emit-code" => {
+                let value = option_value(args, &mut i)?;
+                config.emit_code = Some(Language::from_name(value).ok_or_else(|| {
+                    format!(
+                        "Unsupported language for --emit-code: {} (use {})",
+                        value,
+                        Language::NAMES
+                    )
+                })?);
+            }
+            "--at" => {
+                let value = option_value(args, &mut i)?;
+                config.at = Some(
+                    time::parse_instant(value)
+                        .ok_or_else(|| format!("Invalid time for --at: {}", value))?,
+                );
+            }
+            "--password" => {
+                config.password = Some(option_value(args, &mut i)?.to_string());
+            }
+            "--issuer" => {
+                config.issuer = Some(option_value(args, &mut i)?.to_string());
+            }
             _ => {
                 if arg.starts_with('-') {
                     return Err(format!("Unknown option: {}", arg));
                 }
                 // Positional argument - input file
-                if let Some(existing) = &input_file {
-                    return Err(format!(
-                        "Multiple input files specified: {} and {}",
-                        existing, arg
-                    ));
-                } else {
-                    input_file = Some(arg.clone());
-                }
+                inputs.push(i);
             }
         }
         i += 1;
     }
 
-    Ok((config, input_file))
+    if config.expect.is_some() && config.output_file.is_some() {
+        return Err("--expect and -O can't be used together".to_string());
+    }
+    if config.strict && config.recover {
+        return Err("--strict and --recover can't be used together".to_string());
+    }
+    if config.issuer.is_some()
+        && (config.format.is_some()
+            || config.digest_each.is_some()
+            || config.find_oid.is_some()
+            || config.emit_code.is_some()
+            || config.range.is_some()
+            || config.highlight.is_some())
+    {
+        // The signature is checked in the dump, which these replace
+        return Err(
+            "--issuer can't be used with --format, --digest-each, --find-oid, \
+                    --emit-code, --range or --highlight"
+                .to_string(),
+        );
+    }
+    if config.find_oid.is_some()
+        && (config.format.is_some()
+            || config.range.is_some()
+            || config.highlight.is_some()
+            || config.recover)
+    {
+        return Err(
+            "--find-oid can't be used with --format, --range, --highlight or --recover".to_string(),
+        );
+    }
+    if config.emit_code.is_some()
+        && (config.format.is_some()
+            || config.find_oid.is_some()
+            || config.digest.is_some()
+            || config.range.is_some()
+            || config.highlight.is_some()
+            || config.recover)
+    {
+        return Err(
+            "--emit-code can't be used with --format, --find-oid, --digest, --range, \
+                    --highlight or --recover"
+                .to_string(),
+        );
+    }
+    if config.digest_each.is_some() {
+        if config.digest.is_some()
+            || config.find_oid.is_some()
+            || config.emit_code.is_some()
+            || config.recover
+        {
+            return Err(
+                "--digest-each can't be used with --digest, --find-oid, --emit-code or \
+                 --recover"
+                    .to_string(),
+            );
+        }
+        // The digests are noted in the decoded tree
+        config.format.get_or_insert(Format::Tree);
+    }
+    if config.range.is_some() || config.highlight.is_some() {
+        if config.recover {
+            return Err("--range and --highlight can't be used with --recover".to_string());
+        }
+        // The elements are picked out of the decoded tree, so it's shown as one
+        config.format.get_or_insert(Format::Tree);
+    }
+    if config.format.is_some() && config.recover {
+        return Err("--format and --recover can't be used together".to_string());
+    }
+    if config.first == Some(0) {
+        return Err("--first must be at least 1".to_string());
+    }
+    if let Some(&input) = inputs.iter().find(|&&input| input <= defaults) {
+        return Err(format!(
+            "The config file's options for dump-asn1 name an input file: {}",
+            args[input]
+        ));
+    }
+
+    // Where the inputs are in the arguments as given
+    let inputs = inputs.iter().map(|input| input - defaults).collect();
+    Ok((config, inputs))
 }
 
-fn parse_args() -> Result<(Config, Option<String>), String> {
-    let args: Vec<String> = env::args().collect();
-    parse_args_from(&args)
+/// Run dumpasn1 with `args`, the first of which is the program name shown in
+/// the help, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    if let Some(status) = cli::dump_expanded::<Asn1Dumper>(args, Decoder::Asn1) {
+        return status;
+    }
+    run_input(args, None)
 }
 
-fn run() -> io::Result<()> {
-    let (config, filename) = match parse_args() {
-        Ok((cfg, file)) => (cfg, file),
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            eprintln!("\nUse --help for usage information");
-            std::process::exit(1);
-        }
+/// Run dumpasn1 with `args` on `input` if given, as the content of the file
+/// they name, or otherwise on the file itself
+pub fn run_input(args: &[String], input: Option<Vec<u8>>) -> io::Result<i32> {
+    let (config, filename) = parse_args_from(args).unwrap_or_else(|e| cli::usage_error(&e));
+    let filename = filename.unwrap_or_else(|| cli::usage_error("No input file specified"));
+    if config.auto {
+        return cli::dump_auto(args);
+    }
+
+    let mut dumper = Asn1Dumper::new(config);
+    if let Some(expected) = dumper.config.expect.clone() {
+        return dumper::check_expected(&mut dumper, &filename, input, &expected);
+    }
+    if let Some(path) = &dumper.config.output_file {
+        let file =
+            File::create(path).map_err(|e| DumpError::io(format!("Can't create '{}'", path), e))?;
+        dumper.out = Output::new(Box::new(BufWriter::new(file)));
+    }
+    let colors = match dumper.config.output_file {
+        Some(_) => dumper.config.color.enabled(false),
+        None => dumper.config.color.for_stdout(),
     };
+    dumper.out.set_colors(colors);
+    dumper.dump_file(&filename, input)?;
+    Ok(dumper.findings.exit_status())
+}
 
-    let filename = match filename {
-        Some(f) => f,
-        None => {
-            eprintln!("Error: No input file specified");
-            eprintln!("\nUse --help for usage information");
-            std::process::exit(1);
-        }
+/// Describe DER or PEM input as render nodes, for the asn1cbor commands that
+/// work on decoded items. Key material is masked unless `show_secrets` is
+/// set, and a PEM block that can't be decoded is an error
+pub fn to_nodes(data: &[u8], show_secrets: bool) -> io::Result<Vec<Node>> {
+    let config = Config {
+        show_secrets,
+        strict: true,
+        ..Config::default()
     };
+    Asn1Dumper::new(config).input_nodes(data)
+}
 
-    let file = File::open(&filename).map_err(|e| {
-        eprintln!("Error opening file '{}': {}", filename, e);
-        e
-    })?;
-    let mut reader = BufReader::new(file);
+/// Describe DER held in memory as render nodes, with nothing masked, for
+/// try_parse_asn1
+pub fn der_to_nodes(data: &[u8]) -> Result<Vec<Node>, DumpError> {
+    let config = Config {
+        show_secrets: true,
+        ..Config::default()
+    };
+    nodes::to_nodes(data, &config).map_err(DumpError::from)
+}
 
-    let mut dumper = Asn1Dumper::new(config);
+impl Dumper for Asn1Dumper {
+    fn from_args(args: &[String]) -> Result<(Self, Option<String>), String> {
+        let (config, filename) = if args.len() > 1 {
+            parse_args_from(args)?
+        } else {
+            (Config::default(), None)
+        };
+        Ok((Asn1Dumper::new(config), filename))
+    }
 
-    if dumper.config.verbose {
-        println!("Dumping ASN.1 file: {}", filename);
-        println!("Configuration:");
-        println!("  Print all data: {}", dumper.config.print_all_data);
-        println!("  Check charset: {}", dumper.config.check_charset);
-        println!("  Check encapsulation: {}", dumper.config.check_encaps);
-        println!("  Max nesting level: {}", dumper.config.max_nest_level);
-        println!();
-    } else if !dumper.config.do_pure {
-        println!("Dumping ASN.1 file: {}\n", filename);
+    fn input_positions(args: &[String]) -> Result<Vec<usize>, String> {
+        Ok(parse_options(args)?.1)
     }
 
-    dumper.dump_asn1(&mut reader)
-}
+    fn output(&mut self) -> &mut Output {
+        &mut self.out
+    }
 
-fn main() {
-    match run() {
-        Ok(()) => {}
-        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
-        }
+    fn color(&self) -> ColorChoice {
+        self.config.color
+    }
+
+    fn findings(&mut self) -> &mut Findings {
+        &mut self.findings
+    }
+
+    fn report_errors_inline(&mut self) {
+        self.inline_errors = true;
+    }
+
+    fn dump_file(&mut self, filename: &str, input: Option<Vec<u8>>) -> io::Result<()> {
+        Asn1Dumper::dump_file(self, filename, input)
+    }
+
+    fn dump_bytes(&mut self, data: &[u8]) -> io::Result<()> {
+        self.dump_input(None, &mut Cursor::new(data))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::hex::parse_hex;
+    use crate::common::testutil::{dump_with, Certificate};
 
     fn args(slice: &[&str]) -> Vec<String> {
         slice.iter().map(|s| s.to_string()).collect()
@@ -822,6 +2994,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_input_positions() {
+        // Option values aren't input files, whatever they look like
+        let args = args(&["dumpasn1", "a.der", "-O", "out.der", "-f", "b.der", "c.der"]);
+        assert_eq!(Asn1Dumper::input_positions(&args), Ok(vec![1, 5, 6]));
+        assert!(Asn1Dumper::input_positions(&args[..3]).is_err());
+    }
+
+    #[test]
+    fn test_fatal_error_offset() {
+        // SEQUENCE { INTEGER 1, then a tag with its length missing }
+        let data = vec![0x30, 0x06, 0x02, 0x01, 0x01, 0x04];
+        let (_, diagnostics, _) = dumper::check_to::<Asn1Dumper>(
+            &args(&["dumpasn1", "in.der"]),
+            Some(data),
+            Output::sink(),
+        )
+        .unwrap();
+        let diagnostic = diagnostics.last().unwrap();
+        assert_eq!(diagnostic.code, "truncated");
+        let said = format!("at offset {}:", diagnostic.offset.unwrap());
+        assert!(diagnostic.message.contains(&said), "{:?}", diagnostic);
+        assert_eq!(
+            diagnostics::to_json(&diagnostics)
+                .matches("\"offset\": 5,")
+                .count(),
+            1
+        );
+    }
+
     #[test]
     fn test_parse_no_args_errors() {
         let result = parse_args_from(&args(&["dumpasn1"]));
@@ -848,10 +3050,828 @@ mod tests {
         assert_eq!(file, Some("via_flag.der".to_string()));
     }
 
+    #[test]
+    fn test_parse_expect() {
+        let (config, _) = parse_args_from(&args(&["dumpasn1", "--expect", "a.txt", "a.der"]))
+            .expect("should succeed");
+        assert_eq!(config.expect.as_deref(), Some("a.txt"));
+        let err = parse_args_from(&args(&[
+            "dumpasn1", "--expect", "a.txt", "-O", "b", "a.der",
+        ]))
+        .unwrap_err();
+        assert!(err.contains("-O"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_parse_show_secrets() {
+        let (config, _) = parse_args_from(&args(&["dumpasn1", "key.der"])).expect("should succeed");
+        assert!(!config.show_secrets);
+        let (config, _) = parse_args_from(&args(&["dumpasn1", "--show-secrets", "key.der"]))
+            .expect("should succeed");
+        assert!(config.show_secrets);
+    }
+
+    #[test]
+    fn test_parse_password() {
+        let (config, _) = parse_args_from(&args(&["dumpasn1", "--password", "secret", "a.p12"]))
+            .expect("should succeed");
+        assert_eq!(config.password.as_deref(), Some("secret"));
+        let err = parse_args_from(&args(&["dumpasn1", "a.p12", "--password"]))
+            .expect_err("should fail without a value");
+        assert!(err.contains("--password"), "unexpected error: {err}");
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn test_parse_digest_and_select() {
+        let (config, _) = parse_args_from(&args(&[
+            "dumpasn1",
+            "--digest",
+            "SHA-256",
+            "--select",
+            "tbsCertificate",
+            "cert.der",
+        ]))
+        .expect("should succeed");
+        assert_eq!(config.digest, Some(HashAlg::Sha256));
+        assert_eq!(config.select.as_deref(), Some("tbsCertificate"));
+
+        let err = parse_args_from(&args(&["dumpasn1", "--digest", "md5", "cert.der"]))
+            .expect_err("should fail on unknown algorithm");
+        assert!(err.contains("md5"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_get_item_high_tag_number() {
+        let mut dumper = Asn1Dumper::new(Config::default());
+        dumper.input_len = 6;
+        let mut input = io::Cursor::new(vec![0x5F, 0x83, 0xFF, 0x7F, 0x01, 0x05]);
+        let item = dumper.get_item(&mut input).unwrap().expect("item");
+        assert_eq!((item.id, item.tag, item.length), (0x40, 65535, 1));
+        assert_eq!(item.header_size, 5);
+
+        // Tag numbers that don't fit in 32 bits
+        let mut input = io::Cursor::new(vec![0x7F, 0x9F, 0xFF, 0xFF, 0xFF, 0x7F, 0x00]);
+        assert!(dumper.get_item(&mut input).is_err());
+    }
+
+    #[test]
+    fn test_strict_stops_at_first_error() {
+        // SEQUENCE { OID with a bad final arc, INTEGER 1 }
+        let data = vec![0x30, 0x06, 0x06, 0x01, 0x80, 0x02, 0x01, 0x01];
+        let config = Config {
+            strict: true,
+            ..Config::default()
+        };
+        let mut dumper = Asn1Dumper::new(config);
+        dumper.input_len = data.len();
+        let mut input = io::Cursor::new(data);
+        let item = dumper.get_item(&mut input).unwrap().unwrap();
+        let err = dumper.print_asn1_object(&mut input, &item, 0).unwrap_err();
+        assert_eq!(err.to_string(), "invalid OID encoding");
+        assert_eq!(dumper.findings.errors, 1);
+        assert_eq!(dumper.findings.diagnostics[0].code, "invalid-oid");
+
+        assert!(parse_args_from(&args(&["dumpasn1", "--strict", "--recover", "x.der"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_int_format() {
+        let (config, _) = parse_args_from(&args(&["prog", "--int-hex", "a.der"])).unwrap();
+        assert_eq!(config.int_format, IntFormat::Hex);
+        let (config, _) = parse_args_from(&args(&["prog", "--int-both", "a.der"])).unwrap();
+        assert_eq!(config.int_format, IntFormat::Both);
+    }
+
+    #[test]
+    fn test_get_item_non_canonical_length() {
+        let mut dumper = Asn1Dumper::new(Config::default());
+        dumper.input_len = 0x100; // Only the headers are present
+        for (data, non_canonical) in [
+            (vec![0x04, 0x81, 0x05], true),
+            (vec![0x04, 0x82, 0x00, 0x80], true),
+            (vec![0x04, 0x81, 0x80], false),
+        ] {
+            let item = dumper
+                .get_item(&mut io::Cursor::new(data))
+                .unwrap()
+                .unwrap();
+            assert_eq!(item.non_canonical, non_canonical);
+        }
+    }
+
+    #[test]
+    fn test_get_item_length_limits() {
+        // A 4 GB string in a 7-byte input
+        let data = vec![0x04, 0x84, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+        let mut dumper = Asn1Dumper::new(Config::default());
+        dumper.input_len = data.len();
+        let err = dumper.get_item(&mut io::Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Truncated OCTET STRING content at offset 0: 4294967294 more bytes expected \
+             (1 of 4294967295 present)"
+        );
+
+        let config = Config {
+            max_item_bytes: Some(2),
+            ..Config::default()
+        };
+        let mut dumper = Asn1Dumper::new(config);
+        dumper.input_len = 0x100;
+        let mut input = io::Cursor::new(vec![0x04, 0x03, 0x30, 0x03]);
+        assert!(dumper.get_item(&mut input).is_err());
+        // Constructed items may exceed the limit, their contents are checked
+        // one at a time
+        dumper.f_pos = 0;
+        let mut input = io::Cursor::new(vec![0x30, 0x03]);
+        assert!(dumper.get_item(&mut input).is_ok());
+    }
+
+    #[test]
+    fn test_truncation_error_path() {
+        // SEQUENCE { SEQUENCE { INTEGER cut off after one of its two bytes
+        let data = vec![0x30, 0x06, 0x30, 0x04, 0x02, 0x02, 0x01];
+        let mut dumper = Asn1Dumper::new(Config::default());
+        dumper.input_len = data.len();
+        let mut input = io::Cursor::new(data);
+        let item = dumper.get_item(&mut input).unwrap().unwrap();
+        let err = dumper.print_asn1_object(&mut input, &item, 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            err.to_string(),
+            "Truncated INTEGER content at offset 4: 1 more byte expected (1 of 2 present), \
+             in SEQUENCE > SEQUENCE"
+        );
+        let err = DumpError::from(err);
+        assert!(err.is_truncated());
+        assert_eq!(err.offset(), Some(4));
+        assert_eq!(err.path(), ["SEQUENCE", "SEQUENCE"]);
+    }
+
+    #[test]
+    fn test_dump_output() {
+        // SEQUENCE { INTEGER 5, BOOLEAN TRUE encoded as 0x01 }
+        let data = [0x30, 0x06, 0x02, 0x01, 0x05, 0x01, 0x01, 0x01];
+        assert_eq!(
+            dump_with(Asn1Dumper::new(Config::default()), &data).0,
+            "   0    6: SEQUENCE {\n\
+             \x20  2    1:   INTEGER 5\n\
+             \x20  5    1:   BOOLEAN TRUE\n\
+             \x20        :     Warning: BOOLEAN TRUE isn't encoded as 0xFF\n\
+             \x20        : }\n\
+             \nParsing complete.\n\
+             Warnings: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_pem_decode_error() {
+        // An INTEGER, then a block that isn't base64
+        let pem = "-----BEGIN A-----\nAgEF\n-----END A-----\n\
+                   -----BEGIN B-----\n!!!!\n-----END B-----\n";
+        for options in [&["--format", "diag"][..], &["--format", "diag", "--strict"]] {
+            let args: Vec<String> = std::iter::once("dumpasn1")
+                .chain(options.iter().copied())
+                .map(String::from)
+                .collect();
+            let text = dumper::dump_to_string::<Asn1Dumper>(pem.as_bytes(), &args).unwrap();
+            assert_eq!(
+                text.matches("can't decode block").count(),
+                1,
+                "{:?}: {}",
+                options,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_recover() {
+        // SEQUENCE { INTEGER 1 }, a tag number too long to hold, then
+        // INTEGER 5 and SEQUENCE { INTEGER 7 }
+        let data = parse_hex("30030201011fffffffffffff7f0201053003020107").unwrap();
+        let config = Config {
+            recover: true,
+            ..Config::default()
+        };
+        let (text, findings) = dump_with(Asn1Dumper::new(config), &data);
+        assert!(
+            text.contains("[8 bytes skipped from offset 5, resuming at offset 13]"),
+            "{}",
+            text
+        );
+        assert!(text.contains("  13    1: INTEGER 5\n"), "{}", text);
+        let skipped = &findings.diagnostics[1];
+        assert_eq!((skipped.code, skipped.offset), ("skipped-bytes", Some(5)));
+    }
+
+    #[test]
+    fn test_non_universal_primitive() {
+        // SEQUENCE { [1] FF, [2] 05 }, which aren't BOOLEAN or INTEGER
+        let data = [0x30, 0x06, 0x81, 0x01, 0xFF, 0x82, 0x01, 0x05];
+        let dump = |dump_text| {
+            let config = Config {
+                dump_text,
+                ..Config::default()
+            };
+            dump_with(Asn1Dumper::new(config), &data).0
+        };
+        assert_eq!(
+            dump(false),
+            "   0    6: SEQUENCE {\n\
+             \x20  2    1:   [1] FF \n\
+             \x20  5    1:   [2] 05 \n\
+             \x20        : }\n\
+             \nParsing complete.\n"
+        );
+        assert!(dump(true).contains(
+            "   2    1:   [1]\n\
+             \x20        :     FF                                               .\n"
+        ));
+
+        // SEQUENCE { [6] 80, [5] 00, [3] 07 }, IMPLICIT tags on content that
+        // would be a bad OID, a NULL with content and a BIT STRING with
+        // unused bits but none to hold them, were the tags universal
+        let data = [
+            0x30, 0x09, 0x86, 0x01, 0x80, 0x85, 0x01, 0x00, 0x83, 0x01, 0x07,
+        ];
+        let (text, findings) = dump_with(Asn1Dumper::new(Config::default()), &data);
+        assert_eq!(
+            text,
+            "   0    9: SEQUENCE {\n\
+             \x20  2    1:   [6] 80 \n\
+             \x20  5    1:   [5] 00 \n\
+             \x20  8    1:   [3] 07 \n\
+             \x20        : }\n\
+             \nParsing complete.\n"
+        );
+        assert_eq!((findings.errors, findings.warnings), (0, 0));
+    }
+
+    #[test]
+    fn test_lengths() {
+        // SEQUENCE { INTEGER 5 } with an indefinite length
+        let data = [0x30, 0x80, 0x02, 0x01, 0x05, 0x00, 0x00];
+        let config = Config {
+            show_lengths: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(Asn1Dumper::new(config), &data).0,
+            "   0  2 NDEF: SEQUENCE {\n\
+             \x20  2  2    1:   INTEGER 5\n\
+             \x20           : }\n\
+             \nParsing complete.\n"
+        );
+    }
+
+    #[test]
+    fn test_skip_and_first() {
+        // INTEGER 1, INTEGER 2, SEQUENCE { INTEGER 3 }
+        let data = [
+            0x02, 0x01, 0x01, 0x02, 0x01, 0x02, 0x30, 0x03, 0x02, 0x01, 0x03,
+        ];
+        let config = Config {
+            skip: 1,
+            first: Some(1),
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(Asn1Dumper::new(config), &data).0,
+            "   3    1: INTEGER 2\n\
+             \nParsing complete.\n\
+             1 item(s) skipped.\n\
+             Stopped after the first 1; the rest of the input wasn't read.\n"
+        );
+        assert!(parse_args_from(&args(&["dumpasn1", "--first", "0", "x.der"])).is_err());
+    }
+
+    #[test]
+    fn test_render_output() {
+        // SEQUENCE { INTEGER 5, OID 2.5.4.3 }
+        let data = [0x30, 0x08, 0x02, 0x01, 0x05, 0x06, 0x03, 0x55, 0x04, 0x03];
+        let config = Config {
+            format: Some(Format::Diag),
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(Asn1Dumper::new(config), &data).0,
+            "[5, \"2.5.4.3\" / commonName /]\n"
+        );
+
+        let (config, _) = parse_args_from(&args(&["prog", "--format", "json", "a.der"])).unwrap();
+        assert_eq!(config.format, Some(Format::Json));
+        let err = parse_args_from(&args(&["prog", "--format", "xml", "a.der"])).unwrap_err();
+        assert_eq!(err, "Unknown format: xml (use tree, diag, json or html)");
+        assert!(parse_args_from(&args(&["prog", "--format", "tree", "--recover", "a"])).is_err());
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn test_digest_each() {
+        // INTEGER 1, then SEQUENCE { INTEGER 3 } from offset 3
+        let data = [0x02, 0x01, 0x01, 0x30, 0x03, 0x02, 0x01, 0x03];
+        let sha256 = |bytes: &[u8]| hex_lower(&HashAlg::Sha256.digest(bytes));
+        for (select, expected) in [
+            (
+                None,
+                format!(
+                    "    3    3: SEQUENCE {{\n\
+                     \x20         :   SHA-256: {}\n\
+                     \x20   5    1:   INTEGER 3\n\
+                     \x20         :     SHA-256: {}\n\
+                     \x20         : }}\n",
+                    sha256(&data[3..]),
+                    sha256(&data[5..])
+                ),
+            ),
+            (
+                Some("5"),
+                format!(
+                    "    3    3: SEQUENCE {{\n\
+                     \x20   5    1:   INTEGER 3\n\
+                     \x20         :     SHA-256: {}\n\
+                     \x20         : }}\n",
+                    sha256(&data[5..])
+                ),
+            ),
+            (
+                Some("x"),
+                "    3    3: SEQUENCE {\n\
+                 \x20   5    1:   INTEGER 3\n\
+                 \x20         : }\n\
+                 No item matches 'x'\n"
+                    .to_string(),
+            ),
+        ] {
+            let config = Config {
+                skip: 1,
+                digest_each: Some(HashAlg::Sha256),
+                select: select.map(str::to_string),
+                format: Some(Format::Tree),
+                ..Config::default()
+            };
+            assert_eq!(dump_with(Asn1Dumper::new(config), &data).0, expected);
+        }
+
+        let (config, _) = parse_args_from(&args(&["prog", "--digest-each", "sha1", "a"])).unwrap();
+        assert_eq!(config.digest_each, Some(HashAlg::Sha1));
+        assert_eq!(config.format, Some(Format::Tree));
+        let err = parse_args_from(&args(&["prog", "--digest-each", "md5", "a"])).unwrap_err();
+        assert_eq!(
+            err,
+            "Unsupported digest algorithm: md5 (use sha1 or sha256)"
+        );
+        let both = ["prog", "--digest-each", "sha1", "--digest", "sha1", "a"];
+        assert!(parse_args_from(&args(&both)).is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        // SEQUENCE { INTEGER 5, OID 2.5.4.3 }, INTEGER 7
+        let data = [
+            0x30, 0x08, 0x02, 0x01, 0x05, 0x06, 0x03, 0x55, 0x04, 0x03, 0x02, 0x01, 0x07,
+        ];
+        let config = Config {
+            range: Some(2..5),
+            format: Some(Format::Tree),
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(Asn1Dumper::new(config), &data).0,
+            "    0    8: SEQUENCE {\n\
+             \x20         :   partly outside the range: bytes 0..10\n\
+             \x20   2    1:   INTEGER 5\n\
+             \x20         : }\n"
+        );
+
+        let (config, _) = parse_args_from(&args(&["prog", "--range", "..0x20", "a.der"])).unwrap();
+        assert_eq!(
+            (config.range, config.format),
+            (Some(0..32), Some(Format::Tree))
+        );
+        let (config, _) =
+            parse_args_from(&args(&["prog", "--range", "1..", "--format", "json", "a"])).unwrap();
+        assert_eq!(config.format, Some(Format::Json));
+        assert!(parse_args_from(&args(&["prog", "--range", "1..", "--recover", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_highlight() {
+        // SEQUENCE { INTEGER 5, OID 2.5.4.3 }, with the OID's last byte
+        let data = [0x30, 0x08, 0x02, 0x01, 0x05, 0x06, 0x03, 0x55, 0x04, 0x03];
+        let config = Config {
+            highlight: Some(9..10),
+            format: Some(Format::Tree),
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(Asn1Dumper::new(config), &data).0,
+            "    0    8: SEQUENCE {\n\
+             \x20         :   highlighted: bytes 9..10\n\
+             \x20   2    1:   INTEGER 5\n\
+             \x20   5    3:   OBJECT IDENTIFIER 2.5.4.3 (commonName)\n\
+             \x20         :     highlighted: bytes 9..10\n\
+             \x20         : }\n"
+        );
+
+        let (config, _) = parse_args_from(&args(&["prog", "--highlight", "0x10:4", "a"])).unwrap();
+        assert_eq!(
+            (config.highlight, config.format),
+            (Some(16..20), Some(Format::Tree))
+        );
+        assert!(parse_args_from(&args(&["prog", "--highlight", "16", "a"])).is_err());
+        assert!(parse_args_from(&args(&["prog", "--highlight", "1:1", "--recover", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_find_oid() {
+        // SEQUENCE { SET { OID 2.5.4.3 }, OCTET STRING { OID 2.5.4.3 } }
+        let data = [
+            0x30, 0x0E, 0x31, 0x05, 0x06, 0x03, 0x55, 0x04, 0x03, 0x04, 0x05, 0x06, 0x03, 0x55,
+            0x04, 0x03,
+        ];
+        let find = |oid: &str| {
+            let config = Config {
+                find_oid: Some(oid.to_string()),
+                ..Config::default()
+            };
+            dump_with(Asn1Dumper::new(config), &data)
+        };
+        assert_eq!(
+            find("commonName").0,
+            "   4: SEQUENCE > SET\n\
+             \x20 11: SEQUENCE > OCTET STRING\n\
+             \n2 occurrence(s) found.\n"
+        );
+        assert_eq!(find("2.5.4.4").1.warnings, 1);
+
+        let (config, _) = parse_args_from(&args(&["prog", "--find-oid", "2.5.4.3", "a"])).unwrap();
+        assert_eq!(config.find_oid.as_deref(), Some("2.5.4.3"));
+        assert!(
+            parse_args_from(&args(&["prog", "--find-oid", "x", "--format", "json", "a"])).is_err()
+        );
+    }
+
+    #[test]
+    fn test_emit_code() {
+        // SEQUENCE { INTEGER 1, SEQUENCE { NULL } }, INTEGER 2
+        let data = [
+            0x30, 0x07, 0x02, 0x01, 0x01, 0x30, 0x02, 0x05, 0x00, 0x02, 0x01, 0x02,
+        ];
+        let emit = |language, select: Option<&str>| {
+            let config = Config {
+                emit_code: Some(language),
+                select: select.map(str::to_string),
+                ..Config::default()
+            };
+            dump_with(Asn1Dumper::new(config), &data)
+        };
+        assert_eq!(
+            emit(Language::C, None).0,
+            "/* SEQUENCE at offset 0, 9 bytes */\n\
+             static const unsigned char bytes[9] = {\n\
+             \x20   0x30, 0x07, 0x02, 0x01, 0x01, 0x30, 0x02, 0x05, 0x00,\n\
+             };\n\
+             \n\
+             /* INTEGER at offset 9, 3 bytes */\n\
+             static const unsigned char bytes_2[3] = {\n\
+             \x20   0x02, 0x01, 0x02,\n\
+             };\n"
+        );
+
+        assert_eq!(
+            emit(Language::Rust, Some("5")).0,
+            "// SEQUENCE at offset 5, 4 bytes\n\
+             const BYTES: [u8; 4] = [\n\
+             \x20   0x30, 0x02, 0x05, 0x00,\n\
+             ];\n"
+        );
+        assert_eq!(emit(Language::Rust, Some("tbsCertificate")).1.warnings, 1);
+
+        let (config, _) = parse_args_from(&args(&["prog", "--emit-code", "rust", "a"])).unwrap();
+        assert_eq!(config.emit_code, Some(Language::Rust));
+        assert!(parse_args_from(&args(&["prog", "--emit-code", "go", "a"])).is_err());
+        assert!(parse_args_from(&args(&[
+            "prog",
+            "--emit-code",
+            "c",
+            "--digest",
+            "sha256",
+            "a"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_validity() {
+        let cert = Certificate {
+            signature: "1.2.840.10045.4.3.2",
+            signature_algorithm: "1.2.840.10045.4.3.2",
+            not_after: "250101000000Z",
+            ..Certificate::default()
+        }
+        .build();
+        let dump = |at: &str| {
+            let (config, _) = parse_args_from(&args(&["prog", "--at", at, "a"])).unwrap();
+            dump_with(Asn1Dumper::new(config), &cert)
+        };
+
+        let (text, findings) = dump("2024-07-01");
+        assert!(text.contains("validity started 182 days before 2024-07-01T00:00:00Z\n"));
+        assert!(text.contains("remaining lifetime: 184 days\n"));
+        assert_eq!(findings.warnings, 0);
+
+        let (text, findings) = dump("2025-01-01T12:00:00+02:00");
+        assert!(text.contains(
+            "Warning: certificate expired 10 hours, 0 minutes before 2025-01-01T10:00:00Z\n"
+        ));
+        assert_eq!(findings.diagnostics[0].code, "expired");
+
+        let (text, findings) = dump("@1672531200");
+        assert!(text.contains(
+            "Warning: certificate isn't valid yet, its validity starts 365 days after \
+             2023-01-01T00:00:00Z\n"
+        ));
+        assert_eq!(findings.diagnostics[0].code, "not-yet-valid");
+
+        assert_eq!(
+            parse_args_from(&args(&["prog", "--at", "June", "a"])).unwrap_err(),
+            "Invalid time for --at: June"
+        );
+    }
+
+    #[test]
+    fn test_signature_algorithm() {
+        let dump = |signature, signature_algorithm| {
+            let cert = Certificate {
+                signature,
+                signature_algorithm,
+                null_parameters: true,
+                ..Certificate::default()
+            }
+            .build();
+            let (text, findings) = dump_with(Asn1Dumper::new(Config::default()), &cert);
+            let codes: Vec<&str> = findings
+                .diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.code)
+                .collect();
+            (text, codes)
+        };
+
+        let (text, codes) = dump("1.2.840.113549.1.1.11", "1.2.840.113549.1.1.11");
+        assert!(text.contains(
+            " 152    0:     parameters NULL\n\
+             \x20        :     signature algorithm: sha256WithRSAEncryption, as in \
+             tbsCertificate\n\
+             \x20        :   }\n"
+        ));
+        assert!(codes.is_empty());
+
+        let (text, codes) = dump("1.2.840.113549.1.1.11", "1.2.840.113549.1.1.5");
+        assert!(text.contains(
+            "Warning: signatureAlgorithm sha1WithRSAEncryption doesn't match \
+             tbsCertificate.signature sha256WithRSAEncryption\n"
+        ));
+        assert!(text.contains(
+            "Warning: sha1WithRSAEncryption is a weak signature algorithm, as SHA-1 isn't \
+             collision-resistant\n"
+        ));
+        assert_eq!(codes, ["signature-mismatch", "weak-signature"]);
+
+        let (_, codes) = dump("1.2.840.113549.1.1.4", "1.2.840.113549.1.1.4");
+        assert_eq!(codes, ["weak-signature"]);
+    }
+
+    #[test]
+    fn test_issuer() {
+        // A self-issued certificate named `name`
+        let certificate = |name| Certificate {
+            issuer: Some(name),
+            subject: Some(name),
+            not_after: "99991231235959Z",
+            ..Certificate::default()
+        };
+        let dump = |cert: &[u8], issuer: &[u8]| {
+            let mut dumper = Asn1Dumper::new(Config::default());
+            dumper.issuer = Some(issuer.to_vec());
+            let (text, findings) = dump_with(dumper, cert);
+            let codes: Vec<&str> = findings
+                .diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.code)
+                .collect();
+            (text, codes)
+        };
+
+        #[cfg(feature = "crypto")]
+        {
+            use ring::signature::{Ed25519KeyPair, KeyPair};
+
+            let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+            let key = key_pair.public_key().as_ref();
+            let sign = |tbs: &[u8]| key_pair.sign(tbs).as_ref().to_vec();
+            let cert = Certificate {
+                key,
+                ..certificate("Test")
+            }
+            .signed(sign);
+            let (text, codes) = dump(&cert, &cert);
+            assert!(text.contains("signature verified with the key of CN=Test\n"));
+            assert!(codes.is_empty());
+
+            let forged = Certificate {
+                key,
+                ..certificate("Test")
+            }
+            .build();
+            let (text, codes) = dump(&forged, &cert);
+            assert!(text.contains("Warning: signature doesn't verify with the key of CN=Test\n"));
+            assert_eq!(codes, ["bad-signature"]);
+
+            let other = Certificate {
+                key,
+                ..certificate("Other")
+            }
+            .signed(sign);
+            let (text, codes) = dump(&cert, &other);
+            assert!(text.contains(
+                "Warning: the --issuer certificate's subject CN=Other isn't this certificate's \
+                 issuer\n"
+            ));
+            assert_eq!(codes, ["issuer-mismatch"]);
+        }
+        #[cfg(not(feature = "crypto"))]
+        {
+            let cert = certificate("Test").build();
+            let (_, codes) = dump(&cert, &cert);
+            assert_eq!(codes, ["signature-unchecked"]);
+        }
+
+        let (config, _) = parse_args_from(&args(&["prog", "--issuer", "/dev/null", "a"])).unwrap();
+        let err = Asn1Dumper::new(config).load_issuer().unwrap_err();
+        assert_eq!(err.to_string(), "'/dev/null' isn't an X.509 certificate");
+        let both = ["prog", "--issuer", "ca.der", "--format", "json", "a"];
+        assert!(parse_args_from(&args(&both)).is_err());
+    }
+
+    #[test]
+    fn test_record_diagnostics() {
+        // SEQUENCE { INTEGER with no content, BOOLEAN TRUE encoded as 0x01 }
+        let data = vec![0x30, 0x05, 0x02, 0x00, 0x01, 0x01, 0x01];
+        let mut dumper = Asn1Dumper::new(Config::default());
+        dumper.input_len = data.len();
+        let mut input = io::Cursor::new(data);
+        let item = dumper.get_item(&mut input).unwrap().unwrap();
+        dumper.print_asn1_object(&mut input, &item, 0).unwrap();
+        assert_eq!(dumper.findings.warnings, 2);
+
+        let found: Vec<_> = dumper
+            .findings
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.severity, d.offset, d.path.join(" > ")))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "zero-length",
+                    Severity::Warning,
+                    Some(2),
+                    "SEQUENCE > INTEGER".to_string()
+                ),
+                (
+                    "boolean-encoding",
+                    Severity::Warning,
+                    Some(4),
+                    "SEQUENCE > BOOLEAN".to_string()
+                ),
+            ]
+        );
+        assert!(dumper.path.is_empty() && dumper.item_offsets.is_empty());
+    }
+
+    #[test]
+    fn test_nesting_depth_limit() {
+        // SEQUENCE { SEQUENCE { SEQUENCE {} } } with room for only two levels
+        let data = vec![0x30, 0x04, 0x30, 0x02, 0x30, 0x00];
+        let config = Config {
+            max_depth: 2,
+            ..Config::default()
+        };
+        let mut dumper = Asn1Dumper::new(config);
+        dumper.input_len = data.len();
+        let mut input = io::Cursor::new(data);
+        let item = dumper.get_item(&mut input).unwrap().unwrap();
+        let err = dumper.print_asn1_object(&mut input, &item, 0).unwrap_err();
+        assert!(err.to_string().contains("Nesting too deep"), "{err}");
+    }
+
+    #[test]
+    fn test_requires_content() {
+        assert!(requires_content(INTEGER as u32));
+        assert!(requires_content(BITSTRING as u32));
+        assert!(!requires_content(NULLTAG as u32));
+        assert!(!requires_content(OCTETSTRING as u32));
+        assert!(!requires_content(UTF8STRING as u32));
+        assert!(!requires_content(0x102));
+    }
+
+    #[test]
+    fn test_compare_padded() {
+        assert_eq!(
+            compare_padded(&[0x02, 0x01, 0x03], &[0x02, 0x01, 0x05]),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_padded(&[0x04, 0x01], &[0x04, 0x01, 0x00]),
+            Ordering::Equal
+        );
+        assert_eq!(
+            compare_padded(&[0x04, 0x02, 0x01], &[0x04, 0x01]),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_redundant_sign_byte() {
+        assert!(has_redundant_sign_byte(&[0x00, 0x7F]));
+        assert!(has_redundant_sign_byte(&[0xFF, 0x80]));
+        assert!(!has_redundant_sign_byte(&[0x00, 0x80]));
+        assert!(!has_redundant_sign_byte(&[0x00]));
+    }
+
+    #[test]
+    fn test_bit_values() {
+        // keyUsage digitalSignature, keyCertSign, cRLSign with 1 unused bit
+        let bits = bit_values(&[0x86], 1);
+        assert_eq!(bits, vec![true, false, false, false, false, true, true]);
+        assert_eq!(bit_values(&[0x80, 0x80], 7).len(), 9);
+        assert!(bit_values(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn test_format_integer() {
+        assert_eq!(format_integer(500, IntFormat::Decimal), "500");
+        assert_eq!(format_integer(500, IntFormat::Hex), "0x1F4");
+        assert_eq!(format_integer(-1, IntFormat::Both), "-1 (-0x1)");
+        assert_eq!(
+            format_integer(i64::MIN, IntFormat::Hex),
+            "-0x8000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_format_header_dump() {
+        assert_eq!(
+            format_header_dump(&[0x30, 0x82, 0x01, 0x0A], &[]),
+            "<30 82 01 0A>"
+        );
+        assert_eq!(format_header_dump(&[0x02, 0x01], &[0x05]), "<02 01> 05");
+    }
+
+    #[test]
+    fn test_header_detail() {
+        assert_eq!(
+            header_detail(&[0x30, 0x82, 0x01, 0x0A]),
+            "identifier 0x30: universal, constructed, tag number in the identifier; \
+             long form length in 2 more octets"
+        );
+        assert_eq!(
+            header_detail(&[0x9F, 0x81, 0x00, 0x01]),
+            "identifier 0x9F: context-specific, primitive, tag number in 2 more octets; \
+             short form length"
+        );
+        assert_eq!(
+            header_detail(&[0x61, 0x80]),
+            "identifier 0x61: application, constructed, tag number in the identifier; \
+             indefinite length"
+        );
+    }
+
     #[test]
     fn test_parse_unknown_option_errors() {
         let result = parse_args_from(&args(&["dumpasn1", "--unknown"]));
         let err = result.expect_err("should fail on unknown option");
         assert!(err.contains("Unknown option"), "unexpected error: {err}");
     }
+
+    #[test]
+    fn test_directory_input() {
+        let dir = std::env::temp_dir().join(format!("dumpasn1-inputs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.der"), [0x02, 0x01, 0x05]).unwrap();
+        // A truncated INTEGER
+        std::fs::write(dir.join("sub").join("b.der"), [0x02, 0x05, 0x01]).unwrap();
+        let dir_name = dir.display().to_string();
+        let all = run(&args(&["dumpasn1", &dir_name]));
+        let pattern = format!("{}/*.der", dir_name);
+        let matched = run(&args(&["dumpasn1", "-f", &pattern]));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(all.unwrap(), diagnostics::EXIT_ERRORS);
+        assert_eq!(matched.unwrap(), diagnostics::EXIT_CLEAN);
+    }
 }
@@ -0,0 +1,252 @@
+// Describing DER as render nodes, for --format. The nodes are built from
+// the in-memory TLV helpers, so only definite-length encodings are handled;
+// the classic dump remains the way to look at BER or damaged input.
+
+use std::collections::HashMap;
+use std::io;
+
+use super::{type_name, Config};
+use crate::asn1::der::{self, Tlv};
+use crate::asn1::tags::{
+    BITSTRING, BMPSTRING, BOOLEAN, CLASS_MASK, ENUMERATED, GENERALIZEDTIME, GENERALSTRING,
+    GRAPHICSTRING, IA5STRING, INTEGER, NULLTAG, NUMERICSTRING, OBJDESCRIPTOR, OCTETSTRING, OID,
+    PRINTABLESTRING, RELATIVE_OID, T61STRING, UNIVERSAL, UNIVERSALSTRING, UTCTIME, UTF8STRING,
+    VIDEOTEXSTRING, VISIBLESTRING,
+};
+use crate::asn1::templates::{self, Annotation};
+use crate::asn1::{oids, strings};
+use crate::error::DumpError;
+use crate::render::{Content, Node};
+
+/// Integers up to this many octets are shown in decimal, longer ones in hex
+const MAX_DECIMAL_INTEGER: usize = 16;
+
+/// Builds nodes for the items in a buffer
+struct NodeBuilder<'a> {
+    config: &'a Config,
+    annotations: HashMap<usize, Annotation>, // Template labels by item offset
+}
+
+/// Describe the top-level items in `data`, which must all be definite-length
+/// DER
+pub fn to_nodes(data: &[u8], config: &Config) -> io::Result<Vec<Node>> {
+    let mut builder = NodeBuilder {
+        config,
+        annotations: HashMap::new(),
+    };
+    let mut nodes = Vec::new();
+    let mut end = 0;
+    for (offset, tlv) in der::children(data) {
+        let encoding = &data[offset..offset + tlv.total_len()];
+        builder.annotations.clear();
+        if tlv.is_constructed() {
+            if let Some(template) = templates::recognize(encoding) {
+                templates::annotate(encoding, offset, template, &mut builder.annotations);
+            }
+        }
+        nodes.push(builder.node(encoding, offset, tlv, 0)?);
+        end = offset + tlv.total_len();
+    }
+    if end < data.len() {
+        return Err(not_der(end));
+    }
+    Ok(nodes)
+}
+
+impl NodeBuilder<'_> {
+    /// Node for the item `encoding` holds, found at `offset` in the input
+    fn node(&self, encoding: &[u8], offset: usize, tlv: Tlv, depth: usize) -> io::Result<Node> {
+        if depth >= self.config.max_depth {
+            return Err(DumpError::too_deep(Some(offset), &[], self.config.max_depth).into());
+        }
+
+        let annotation = self.annotations.get(&offset);
+        let content = tlv.content(encoding);
+        let content_offset = offset + tlv.header_len;
+        let universal = tlv.id & CLASS_MASK == UNIVERSAL;
+        let mut notes = Vec::new();
+
+        let content = if annotation.is_some_and(|a| a.secret) && !self.config.show_secrets {
+            Content::Other(format!("<{} bytes redacted>", content.len()))
+        } else if tlv.is_constructed() {
+            Content::List(self.children(content, content_offset, depth)?)
+        } else if !universal {
+            Content::Bytes(content.to_vec())
+        } else {
+            match tlv.tag as u8 {
+                BOOLEAN if content.len() == 1 => Content::Bool(content[0] != 0),
+                INTEGER | ENUMERATED if !content.is_empty() => integer(content),
+                BITSTRING if !content.is_empty() => {
+                    let bits = &content[1..];
+                    if self.is_encapsulated(bits) {
+                        Content::List(self.children(bits, content_offset + 1, depth)?)
+                    } else {
+                        if content[0] > 0 {
+                            notes.push(format!("{} unused bits", content[0]));
+                        }
+                        Content::Bytes(bits.to_vec())
+                    }
+                }
+                OCTETSTRING if self.is_encapsulated(content) => {
+                    Content::List(self.children(content, content_offset, depth)?)
+                }
+                NULLTAG => Content::Null,
+                OID => match der::decode_oid(content) {
+                    Some(dotted) => Content::Oid {
+                        name: oids::oid_name(&dotted).map(str::to_string),
+                        dotted,
+                    },
+                    None => Content::Bytes(content.to_vec()),
+                },
+                RELATIVE_OID => match der::decode_relative_oid(content) {
+                    Some(arcs) => Content::Other(arcs),
+                    None => Content::Bytes(content.to_vec()),
+                },
+                T61STRING => Content::Text(strings::decode_t61_string(content)),
+                BMPSTRING => Content::Text(strings::decode_bmp_string(content)),
+                UNIVERSALSTRING => Content::Text(strings::decode_universal_string(content)),
+                UTF8STRING | NUMERICSTRING | PRINTABLESTRING | VIDEOTEXSTRING | IA5STRING
+                | UTCTIME | GENERALIZEDTIME | GRAPHICSTRING | VISIBLESTRING | GENERALSTRING
+                | OBJDESCRIPTOR => Content::Text(String::from_utf8_lossy(content).into_owned()),
+                _ => Content::Bytes(content.to_vec()),
+            }
+        };
+
+        let mut node = Node::new(type_name(tlv.id, tlv.tag), content).at(
+            offset,
+            tlv.header_len,
+            Some(tlv.length),
+        );
+        node.label = annotation.map(|a| a.name.to_string());
+        node.notes = notes;
+        Ok(node)
+    }
+
+    /// Nodes for the items making up `content`, which starts at `offset`
+    fn children(&self, content: &[u8], offset: usize, depth: usize) -> io::Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        let mut end = 0;
+        for (pos, tlv) in der::children(content) {
+            let encoding = &content[pos..pos + tlv.total_len()];
+            nodes.push(self.node(encoding, offset + pos, tlv, depth + 1)?);
+            end = pos + tlv.total_len();
+        }
+        if end < content.len() {
+            return Err(not_der(offset + end));
+        }
+        Ok(nodes)
+    }
+
+    /// Check whether string content holds an encapsulated object
+    fn is_encapsulated(&self, content: &[u8]) -> bool {
+        self.config.check_encaps && content.len() >= 2 && der::looks_encapsulated(content)
+    }
+}
+
+/// Decimal value of a two's complement integer if it's short enough,
+/// otherwise its octets
+fn integer(content: &[u8]) -> Content {
+    if content.len() > MAX_DECIMAL_INTEGER {
+        return Content::Bytes(content.to_vec());
+    }
+    let negative = content[0] & 0x80 != 0;
+    let value = content
+        .iter()
+        .fold(if negative { -1i128 } else { 0 }, |n, &b| {
+            n << 8 | b as i128
+        });
+    Content::Integer(value.to_string())
+}
+
+fn not_der(offset: usize) -> io::Error {
+    DumpError::malformed(
+        Some(offset),
+        &[],
+        format!(
+            "Item at offset {} isn't definite-length DER, which --format needs",
+            offset
+        ),
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::DerBuilder;
+
+    #[test]
+    fn test_to_nodes() {
+        // ECPrivateKey { 1, 'AABB'H } followed by a certificate-like SEQUENCE
+        let data = DerBuilder::new()
+            .sequence(|b| b.integer(1).octet_string(&[0xAA, 0xBB]))
+            .sequence(|b| {
+                b.integer(-129)
+                    .oid("2.5.4.3")
+                    .octet_string(&DerBuilder::new().boolean(true).null().finish())
+                    .bit_string(&[0x30, 0x03, 0x02, 0x01, 0x05], 0)
+                    .bit_string(&[0xF0], 4)
+                    .explicit(3, |b| b.printable_string("x"))
+            })
+            .finish();
+        let nodes = to_nodes(&data, &Config::default()).unwrap();
+        assert_eq!(nodes.len(), 2);
+
+        // Template labels, with the private key masked
+        let key = nodes[0].children();
+        assert_eq!(nodes[0].label.as_deref(), Some("ecPrivateKey"));
+        assert_eq!(key[1].label.as_deref(), Some("privateKey"));
+        assert_eq!(
+            key[1].content,
+            Content::Other("<2 bytes redacted>".to_string())
+        );
+
+        let items = nodes[1].children();
+        assert_eq!(nodes[1].offset, Some(9));
+        assert_eq!(items[0].content, Content::Integer("-129".to_string()));
+        assert_eq!(
+            items[1].content,
+            Content::Oid {
+                dotted: "2.5.4.3".to_string(),
+                name: Some("commonName".to_string())
+            }
+        );
+        // Two primitives aren't taken for an encapsulated object
+        assert_eq!(
+            items[2].content,
+            Content::Bytes(vec![0x01, 0x01, 0xFF, 0x05, 0x00])
+        );
+        assert_eq!(items[3].kind, "BIT STRING");
+        let encapsulated = items[3].children();
+        assert_eq!(encapsulated[0].offset, items[3].offset.map(|o| o + 3));
+        assert_eq!(
+            encapsulated[0].children()[0].content,
+            Content::Integer("5".to_string())
+        );
+        assert_eq!(items[4].notes, ["4 unused bits"]);
+        assert_eq!(items[5].kind, "[3]");
+        assert_eq!(
+            items[5].children()[0].content,
+            Content::Text("x".to_string())
+        );
+    }
+
+    #[test]
+    fn test_not_der() {
+        // Indefinite length, and trailing garbage
+        let err = to_nodes(&[0x30, 0x80, 0x00, 0x00], &Config::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Item at offset 0 isn't definite-length DER, which --format needs"
+        );
+        let err = to_nodes(&[0x30, 0x02, 0x05, 0x00, 0x04], &Config::default()).unwrap_err();
+        assert!(err.to_string().starts_with("Item at offset 4 "));
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(integer(&[0x00, 0xFF]), Content::Integer("255".to_string()));
+        assert_eq!(integer(&[0xFF, 0x7F]), Content::Integer("-129".to_string()));
+        assert_eq!(integer(&[0x80; 17]), Content::Bytes(vec![0x80; 17]));
+    }
+}
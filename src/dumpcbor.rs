@@ -2,90 +2,77 @@
 // Based on the concepts and approach from dumpasn1.c
 // Dumps CBOR-encoded data in a human-readable format
 
-use std::env;
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::{self, BufReader, Read};
-
-// CBOR major types
-const MAJOR_UNSIGNED: u8 = 0;
-const MAJOR_NEGATIVE: u8 = 1;
-const MAJOR_BYTES: u8 = 2;
-const MAJOR_TEXT: u8 = 3;
-const MAJOR_ARRAY: u8 = 4;
-const MAJOR_MAP: u8 = 5;
-const MAJOR_TAG: u8 = 6;
-const MAJOR_SIMPLE: u8 = 7;
-
-// Additional info values
-const AI_1BYTE: u8 = 24;
-const AI_2BYTES: u8 = 25;
-const AI_4BYTES: u8 = 26;
-const AI_8BYTES: u8 = 27;
-const AI_INDEFINITE: u8 = 31;
-
-// Simple values
-const SIMPLE_FALSE: u8 = 20;
-const SIMPLE_TRUE: u8 = 21;
-const SIMPLE_NULL: u8 = 22;
-const SIMPLE_UNDEFINED: u8 = 23;
-
-// Well-known CBOR tags
-const TAG_DATETIME: u64 = 0;
-const TAG_EPOCH: u64 = 1;
-const TAG_BIGNUM_POS: u64 = 2;
-const TAG_BIGNUM_NEG: u64 = 3;
-const TAG_DECIMAL: u64 = 4;
-const TAG_BIGFLOAT: u64 = 5;
-const TAG_BASE64URL: u64 = 21;
-const TAG_BASE64: u64 = 22;
-const TAG_BASE16: u64 = 23;
-const TAG_CBOR: u64 = 24;
-const TAG_URI: u64 = 32;
-const TAG_BASE64URL_ENC: u64 = 33;
-const TAG_BASE64_ENC: u64 = 34;
-const TAG_REGEX: u64 = 35;
-const TAG_MIME: u64 = 36;
-const TAG_SELF_DESCRIBE: u64 = 55799;
-
-/// Structure to hold information about a CBOR item
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
-struct CborItem {
-    major_type: u8,
-    additional_info: u8,
-    value: CborValue,
-    raw_bytes: Vec<u8>,
-}
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::net::TcpListener;
+use std::ops::Range;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::thread;
+use std::time::{Duration, Instant};
 
-/// CBOR value types
-#[derive(Debug, Clone)]
-enum CborValue {
-    Unsigned(u64),
-    Negative(i64),
-    Bytes(Vec<u8>),
-    Text(String),
-    Array(Vec<CborItem>),
-    Map(Vec<(CborItem, CborItem)>),
-    Tag(u64, Box<CborItem>),
-    Simple(u8),
-    Boolean(bool),
-    Null,
-    Undefined,
-    Float16(f32),
-    Float32(f32),
-    Float64(f64),
-    Break,
-}
+use crate::asn1::crypto::HashAlg;
+use crate::asn1::pem::{self, Alphabet};
+use crate::cbor::c509::{self, Label};
+use crate::cbor::coap::{self, ContentFormat, Payload};
+use crate::cbor::cose;
+use crate::cbor::{
+    self, f16_to_f32, half_bits, CborEncoder, CborItem, CborValue, AI_1BYTE, AI_2BYTES, AI_4BYTES,
+    AI_8BYTES, AI_INDEFINITE, BREAK, END_BEFORE_BREAK, MAJOR_ARRAY, MAJOR_BYTES, MAJOR_MAP,
+    MAJOR_NEGATIVE, MAJOR_SIMPLE, MAJOR_TAG, MAJOR_TEXT, MAJOR_UNSIGNED, MAX_PARSE_DEPTH,
+    SIMPLE_FALSE, SIMPLE_NULL, SIMPLE_TRUE, SIMPLE_UNDEFINED, TAG_BASE16, TAG_BASE64,
+    TAG_BASE64URL, TAG_BIGNUM_NEG, TAG_BIGNUM_POS, TAG_CBOR, TAG_CBOR_SEQUENCE, TAG_MIME,
+    TAG_RATIONAL, TAG_REGEX, TAG_SELF_DESCRIBE,
+};
+use crate::cli::{self, Decoder, InputKind};
+use crate::common::args::{option_value, parse_number, parse_offset, parse_range, parse_span};
+use crate::common::color::{ColorChoice, Style};
+use crate::common::compressed::{self, Compression};
+use crate::common::counters::Findings;
+use crate::common::dumper::{self, Dumper};
+use crate::common::escape::escape_controls;
+use crate::common::hex::{hex_lower, hex_upper, parse_hex};
+use crate::common::json;
+use crate::common::mime;
+use crate::common::offsets::format_offset;
+use crate::common::read::{missing, read_available, skipped_message};
+use crate::common::regex::{self, Regex};
+use crate::common::timing::timing_report;
+use crate::common::wrap;
+use crate::diagnostics::{self, Diagnostic, Severity};
+use crate::dumpasn1;
+use crate::error::DumpError;
+use crate::output::Output;
+use crate::render::{self, Content, Format, Node};
 
-impl CborItem {
-    fn new(major_type: u8, additional_info: u8, value: CborValue) -> Self {
-        CborItem {
-            major_type,
-            additional_info,
-            value,
-            raw_bytes: Vec::new(),
-        }
-    }
+// Strings longer than this are read a piece at a time by --stream, and only
+// the first TEXT_DISPLAY_LIMIT bytes of a text string are shown by default
+const STREAM_STRING_LIMIT: u64 = 64 * 1024;
+const STREAM_BUFFER_SIZE: usize = 8192;
+const TEXT_DISPLAY_LIMIT: usize = 80;
+
+// Lines of the body of a MIME message (tag 36) shown unless -a asks for all
+const MIME_PREVIEW_LINES: usize = 3;
+
+// How long --follow waits before looking for more of a file it's read to
+// the end of
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// Bytes on each line of a byte string's hex dump
+const HEX_BYTES_PER_LINE: usize = 16;
+
+// Width of the lines that arrays and maps are put on whole by --inline
+const INLINE_WIDTH: usize = 80;
+
+// Printed in place of the tag an input starting with tag 55799 has
+const SELF_DESCRIBED: &str = "Self-described CBOR (tag 55799)";
+
+/// Where --listen and --unix take connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Listen {
+    Tcp(String),  // addr:port
+    Unix(String), // Socket path
 }
 
 /// Configuration options for the dumper
@@ -96,11 +83,43 @@ struct Config {
     max_nest_level: usize,
     decode_nested: bool,
     show_offsets: bool,
+    show_lengths: bool, // Header and content length columns
+    show_heads: bool,   // Each item's head bytes before it
+    pretty_json: bool,  // Lay out JSON found in text strings
+    raw_strings: bool,  // Print control characters in text unescaped
     verbose: bool,
     compact: bool,
+    inline: bool,                                   // Small arrays and maps on one line
+    content_format: Option<&'static ContentFormat>, // CoAP format of the payload
+    int_hex: bool,
     print_all_data: bool,
     hex_values: bool,
     show_types: bool,
+    max_item_bytes: Option<usize>,
+    max_depth: usize,
+    check_canonical: bool, // Map keys in bytewise order of their encodings
+    roundtrip: bool,       // Re-encode each item and compare it with the input
+    digest_each: Option<HashAlg>, // Of every element, shown in the tree
+    select: Option<String>, // Element to digest, by offset or --grep path
+    validate: bool,        // Errors and warnings only, as asn1cbor lint lists them
+    skip: usize,           // Top-level items passed over unprinted
+    first: Option<usize>,  // Top-level items printed before stopping
+    range: Option<Range<usize>>, // Byte window of the elements shown
+    highlight: Option<Range<usize>>, // Bytes whose elements are marked
+    grep: Option<Regex>,   // For text strings and map keys
+    grep_bytes: Option<Vec<u8>>, // For byte strings
+    recover: bool,
+    auto: bool,
+    strict: bool,
+    stream: bool,
+    follow: bool,
+    listen: Option<Listen>,
+    format: Option<Format>,
+    timing: bool,
+    errors_json: Option<String>,
+    output_file: Option<String>,
+    expect: Option<String>,
+    color: ColorChoice,
 }
 
 impl Default for Config {
@@ -111,336 +130,999 @@ impl Default for Config {
             max_nest_level: 100,
             decode_nested: true,
             show_offsets: false,
+            show_lengths: false,
+            show_heads: false,
+            pretty_json: false,
+            raw_strings: false,
             verbose: false,
             compact: false,
+            inline: false,
+            content_format: None,
+            int_hex: false,
             print_all_data: false,
             hex_values: false,
             show_types: true,
+            max_item_bytes: None,
+            check_canonical: false,
+            roundtrip: false,
+            digest_each: None,
+            select: None,
+            validate: false,
+            max_depth: MAX_PARSE_DEPTH,
+            skip: 0,
+            first: None,
+            range: None,
+            highlight: None,
+            grep: None,
+            grep_bytes: None,
+            recover: false,
+            auto: false,
+            strict: false,
+            stream: false,
+            follow: false,
+            listen: None,
+            format: None,
+            timing: false,
+            errors_json: None,
+            output_file: None,
+            expect: None,
+            color: ColorChoice::Auto,
         }
     }
 }
 
 /// Main dumper state
-struct CborDumper {
+pub(crate) struct CborDumper {
     config: Config,
-    no_errors: usize,
-    no_warnings: usize,
+    out: Output,
+    findings: Findings,
     offset: usize,
+    depth: usize,                // Nesting of the item being read
+    path: Vec<String>,           // Position of the item being read
+    label: Option<&'static str>, // Name to print before the next item
+    child_labels: Vec<Label>,    // Labels for the next array's elements
+    label_encoded: bool,         // The next item's labels are for the CBOR it holds
+    key_labels: Option<fn(&CborValue) -> Option<&'static str>>, // Names of map keys
+    inline_errors: bool,         // Errors go in the dump rather than to stderr
 }
 
 impl CborDumper {
     fn new(config: Config) -> Self {
         CborDumper {
             config,
-            no_errors: 0,
-            no_warnings: 0,
+            out: Output::stdout(),
+            findings: Findings::default(),
             offset: 0,
+            depth: 0,
+            path: Vec::new(),
+            label: None,
+            child_labels: Vec::new(),
+            label_encoded: false,
+            key_labels: None,
+            inline_errors: false,
+        }
+    }
+
+    /// Read additional info value (length or value) of the item starting at
+    /// `start`
+    fn read_additional<R: Read>(
+        &mut self,
+        reader: &mut R,
+        ai: u8,
+        start: usize,
+    ) -> io::Result<u64> {
+        self.read_argument(reader, ai, start, "argument")
+    }
+
+    /// Read the argument of the item at `start`, naming it `part` if it's
+    /// cut short
+    fn read_argument<R: Read>(
+        &mut self,
+        reader: &mut R,
+        ai: u8,
+        start: usize,
+        part: &str,
+    ) -> io::Result<u64> {
+        let size = match ai {
+            0..=23 => return Ok(ai as u64),
+            AI_1BYTE => 1,
+            AI_2BYTES => 2,
+            AI_4BYTES => 4,
+            AI_8BYTES => 8,
+            AI_INDEFINITE => return Ok(u64::MAX), // Marker for indefinite length
+            _ => {
+                return Err(DumpError::malformed(
+                    Some(start),
+                    &self.path,
+                    "Invalid additional info",
+                )
+                .into())
+            }
+        };
+        let mut buf = [0u8; 8];
+        self.read_fixed(reader, &mut buf[8 - size..], start, part)?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    /// Reject an indefinite length on an integer or tag, whose argument
+    /// can't be left out
+    fn check_indefinite(&self, major_type: u8, ai: u8, start: usize) -> io::Result<()> {
+        if ai == AI_INDEFINITE && matches!(major_type, MAJOR_UNSIGNED | MAJOR_NEGATIVE | MAJOR_TAG)
+        {
+            return Err(DumpError::malformed(
+                Some(start),
+                &self.path,
+                format!(
+                    "Indefinite length isn't allowed for major type {}",
+                    major_type
+                ),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Fill `buffer` from the input, reporting which part of the item at
+    /// `start` was cut off if the input runs out
+    fn read_fixed<R: Read>(
+        &mut self,
+        reader: &mut R,
+        buffer: &mut [u8],
+        start: usize,
+        part: &str,
+    ) -> io::Result<()> {
+        let present = read_available(reader, buffer)?;
+        self.offset += present;
+        if present < buffer.len() {
+            return Err(DumpError::truncated(
+                start,
+                part,
+                &missing(buffer.len() as u64, present as u64, "byte"),
+                &self.path,
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Check the length of the string at `start` against --max-item-bytes
+    fn check_length(&self, length: u64, start: usize) -> io::Result<()> {
+        match self.config.max_item_bytes {
+            Some(max) if length > max as u64 => {
+                Err(DumpError::too_long(start, &self.path, length, max).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Read the content of a string. The declared length comes straight from
+    /// the input, so the buffer grows only as data actually arrives rather
+    /// than being allocated up front.
+    fn read_content<R: Read>(
+        &mut self,
+        reader: &mut R,
+        length: u64,
+        start: usize,
+        part: &str,
+    ) -> io::Result<Vec<u8>> {
+        self.check_length(length, start)?;
+        let mut bytes = Vec::new();
+        reader.take(length).read_to_end(&mut bytes)?;
+        self.offset += bytes.len();
+        if (bytes.len() as u64) < length {
+            return Err(DumpError::truncated(
+                start,
+                part,
+                &missing(length, bytes.len() as u64, "byte"),
+                &self.path,
+            )
+            .into());
+        }
+        Ok(bytes)
+    }
+
+    /// Skip ahead from offset `from` to the next plausible item after a
+    /// malformed one (--recover), noting the bytes passed over. Returns false
+    /// if nothing else in the input looks like an item.
+    fn resync<R: Read + Seek>(&mut self, reader: &mut R, from: usize) -> io::Result<bool> {
+        reader.seek(SeekFrom::Start(from as u64))?;
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest)?;
+
+        let found = self.find_plausible_item(&rest);
+        let skipped = found.unwrap_or(rest.len());
+        let msg = skipped_message(from, skipped, found.is_some());
+        writeln!(self.out, "[{}]", msg);
+        self.record(Severity::Warning, "skipped-bytes", Some(from), &msg);
+
+        self.offset = from + skipped;
+        reader.seek(SeekFrom::Start(self.offset as u64))?;
+        Ok(found.is_some())
+    }
+
+    /// Find the first offset in damaged data where a plausible item starts:
+    /// an array, map or tag that decodes cleanly. Almost any byte starts some
+    /// well-formed scalar, so those aren't enough to resync on.
+    fn find_plausible_item(&self, data: &[u8]) -> Option<usize> {
+        (0..data.len()).find(|&pos| {
+            let major_type = data[pos] >> 5;
+            if !matches!(major_type, MAJOR_ARRAY | MAJOR_MAP | MAJOR_TAG) {
+                return false;
+            }
+            let mut scratch = CborDumper::new(self.config.clone());
+            scratch.out = Output::sink();
+            let parsed = scratch.read_item(&mut io::Cursor::new(&data[pos..]));
+            matches!(parsed, Ok(Some(_))) && scratch.findings.errors == 0
+        })
+    }
+
+    /// Read an item nested in the one being read, named in the path by
+    /// its position
+    fn read_child<R: Read>(
+        &mut self,
+        reader: &mut R,
+        name: String,
+    ) -> io::Result<Option<CborItem<'static>>> {
+        // If reading fails the item stays in the path, so the error can be
+        // placed
+        self.path.push(name);
+        let item = self.read_item(reader)?;
+        self.path.pop();
+        Ok(item)
+    }
+
+    /// Keys read so far of a map whose order --check-canonical checks, by
+    /// their encodings, names and offsets. None if it isn't checked, or the
+    /// map has had a key out of order already
+    fn key_order(&self) -> Option<Vec<(Vec<u8>, String, usize)>> {
+        self.config.check_canonical.then(Vec::new)
+    }
+
+    /// Check that a map key at `offset` sorts after the keys before it,
+    /// reporting it and the key it should follow if it doesn't
+    fn check_key_order(
+        &mut self,
+        keys: &mut Option<Vec<(Vec<u8>, String, usize)>>,
+        key: &CborValue,
+        index: usize,
+        offset: usize,
+    ) -> io::Result<()> {
+        let Some(previous) = keys else {
+            return Ok(());
+        };
+        let encoded = key.encode();
+        let name = match key {
+            CborValue::Text(_) | CborValue::Unsigned(_) | CborValue::Negative(_) => {
+                value_name(key, index)
+            }
+            _ => format!("key {}", index),
+        };
+        match previous.last() {
+            Some((last, last_name, _)) if *last >= encoded => {
+                let last_name = last_name.clone();
+                let place = match previous.iter().filter(|(e, _, _)| *e <= encoded).max() {
+                    Some((e, name, at)) if *e == encoded => {
+                        format!("and duplicates {} at offset {}", name, at)
+                    }
+                    Some((_, name, at)) => {
+                        format!("where it should follow {} at offset {}", name, at)
+                    }
+                    None => "where it should come first".to_string(),
+                };
+                let msg = format!(
+                    "Map key {} at offset {} is out of canonical order, following {} {}",
+                    name, offset, last_name, place
+                );
+                *keys = None;
+                self.report_error("key-order", offset, &msg)
+            }
+            _ => {
+                previous.push((encoded, name, offset));
+                Ok(())
+            }
+        }
+    }
+
+    /// Report a container the input ends inside of. What was read is still
+    /// shown, so this isn't fatal.
+    fn report_truncation(&mut self, start: usize, part: &str, detail: &str) -> io::Result<()> {
+        let error = DumpError::truncated(start, part, detail, &self.path);
+        self.report_error("truncated", start, &error.to_string())
+    }
+
+    /// Report an error that doesn't stop the dump, unless --strict makes
+    /// every error fatal
+    fn report_error(&mut self, code: &'static str, offset: usize, message: &str) -> io::Result<()> {
+        self.record(Severity::Error, code, Some(offset), message);
+        if self.config.strict {
+            return Err(DumpError::malformed(Some(offset), &self.path, message).into());
+        }
+        if self.inline_errors {
+            writeln!(
+                self.out,
+                "{} {}",
+                self.out.paint(Style::Error, "Error:"),
+                message
+            );
+        } else {
+            // Keep the error in its place when the dump goes to the same
+            // terminal
+            self.out.flush()?;
+            eprintln!("Error: {}", message);
         }
+        Ok(())
+    }
+
+    /// Report a break code just read where no indefinite-length item is
+    /// open for it to end. It's still shown, where it was found.
+    fn check_break(&mut self, value: &CborValue) -> io::Result<()> {
+        if matches!(value, CborValue::Break) {
+            let offset = self.offset - 1;
+            let msg = format!(
+                "Break code at offset {} outside an indefinite-length item",
+                offset
+            );
+            self.report_error("unexpected-break", offset, &msg)?;
+        }
+        Ok(())
+    }
+
+    /// Warn of the text of a tag 35 item, at `offset`, that isn't a regular
+    /// expression in PCRE syntax
+    fn check_regex(&mut self, value: &CborValue, offset: usize) {
+        if let CborValue::Text(text) = value {
+            if let Err(e) = regex::outline(text) {
+                let msg = format!(
+                    "Tag 35 text at offset {} isn't a valid regular expression: {}",
+                    offset, e
+                );
+                self.record(Severity::Warning, "regex", Some(offset), &msg);
+            }
+        }
+    }
+
+    /// Warn of the content of a tag 30 item, at `offset`, that isn't a
+    /// numerator and a positive denominator
+    fn check_rational(&mut self, value: &CborValue, offset: usize) {
+        if let Err(e) = rational_parts(value) {
+            let msg = format!("Tag 30 content at offset {} {}", offset, e);
+            self.record(Severity::Warning, "rational", Some(offset), &msg);
+        }
+    }
+
+    /// Nesting past --max-depth at the item at `offset`
+    fn too_deep(&self, offset: usize) -> io::Error {
+        DumpError::too_deep(Some(offset), &self.path, self.config.max_depth).into()
+    }
+
+    /// Count an error or warning, noting where it was found for
+    /// --errors-json
+    fn record(
+        &mut self,
+        severity: Severity,
+        code: &'static str,
+        offset: Option<usize>,
+        message: &str,
+    ) {
+        self.findings.record(Diagnostic {
+            code,
+            severity,
+            offset,
+            path: self.path.clone(),
+            message: message.to_string(),
+        });
     }
 
-    /// Get the name of a well-known tag
-    fn tag_name(&self, tag: u64) -> Option<&'static str> {
-        match tag {
-            TAG_DATETIME => Some("date/time string"),
-            TAG_EPOCH => Some("epoch-based date/time"),
-            TAG_BIGNUM_POS => Some("positive bignum"),
-            TAG_BIGNUM_NEG => Some("negative bignum"),
-            TAG_DECIMAL => Some("decimal fraction"),
-            TAG_BIGFLOAT => Some("bigfloat"),
-            TAG_BASE64URL => Some("base64url encoding"),
-            TAG_BASE64 => Some("base64 encoding"),
-            TAG_BASE16 => Some("base16 encoding"),
-            TAG_CBOR => Some("encoded CBOR data item"),
-            TAG_URI => Some("URI"),
-            TAG_BASE64URL_ENC => Some("base64url"),
-            TAG_BASE64_ENC => Some("base64"),
-            TAG_REGEX => Some("regular expression"),
-            TAG_MIME => Some("MIME message"),
-            TAG_SELF_DESCRIBE => Some("self-describe CBOR"),
-            _ => None,
-        }
-    }
-
-    /// Read additional info value (length or value)
-    fn read_additional<R: Read>(&mut self, reader: &mut R, ai: u8) -> io::Result<u64> {
-        match ai {
-            0..=23 => Ok(ai as u64),
-            AI_1BYTE => {
-                let mut buf = [0u8; 1];
-                reader.read_exact(&mut buf)?;
-                self.offset += 1;
-                Ok(buf[0] as u64)
-            }
-            AI_2BYTES => {
-                let mut buf = [0u8; 2];
-                reader.read_exact(&mut buf)?;
-                self.offset += 2;
-                Ok(u16::from_be_bytes(buf) as u64)
-            }
-            AI_4BYTES => {
-                let mut buf = [0u8; 4];
-                reader.read_exact(&mut buf)?;
-                self.offset += 4;
-                Ok(u32::from_be_bytes(buf) as u64)
-            }
-            AI_8BYTES => {
-                let mut buf = [0u8; 8];
-                reader.read_exact(&mut buf)?;
-                self.offset += 8;
-                Ok(u64::from_be_bytes(buf))
-            }
-            AI_INDEFINITE => Ok(u64::MAX), // Marker for indefinite length
-            _ => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid additional info",
-            )),
-        }
-    }
-
-    /// Read a CBOR item
-    fn read_item<R: Read>(&mut self, reader: &mut R) -> io::Result<Option<CborItem>> {
+    /// Read a CBOR item, refusing to nest deeper than the configured limit
+    fn read_item<R: Read>(&mut self, reader: &mut R) -> io::Result<Option<CborItem<'static>>> {
+        if self.depth >= self.config.max_depth {
+            return Err(self.too_deep(self.offset));
+        }
+        self.depth += 1;
+        let result = self.read_item_inner(reader);
+        self.depth -= 1;
+        result
+    }
+
+    fn read_item_inner<R: Read>(
+        &mut self,
+        reader: &mut R,
+    ) -> io::Result<Option<CborItem<'static>>> {
+        match self.next_byte(reader)? {
+            Some(byte) => self.read_value(reader, byte, self.offset - 1).map(Some),
+            None => Ok(None), // EOF
+        }
+    }
+
+    /// Read the initial byte of the next item, if there is one
+    fn next_byte<R: Read>(&mut self, reader: &mut R) -> io::Result<Option<u8>> {
         let mut initial_byte = [0u8; 1];
         if reader.read(&mut initial_byte)? == 0 {
-            return Ok(None); // EOF
+            return Ok(None);
         }
+        self.offset += 1;
+        Ok(Some(initial_byte[0]))
+    }
 
-        let byte = initial_byte[0];
+    /// Read the rest of the item at `start` whose initial byte has been read
+    fn read_value<R: Read>(
+        &mut self,
+        reader: &mut R,
+        byte: u8,
+        start: usize,
+    ) -> io::Result<CborItem<'static>> {
         let major_type = (byte >> 5) & 0x07;
         let additional_info = byte & 0x1F;
-        self.offset += 1;
+        self.check_indefinite(major_type, additional_info, start)?;
+        // The argument comes straight after the initial byte, and is read
+        // here so the head can be kept as it was encoded. A float's is its
+        // bits
+        let part = match (major_type, additional_info) {
+            (MAJOR_SIMPLE, AI_2BYTES..=AI_8BYTES) => "float",
+            _ => "argument",
+        };
+        let argument = self.read_argument(reader, additional_info, start, part)?;
 
         let value = match major_type {
-            MAJOR_UNSIGNED => {
-                let val = self.read_additional(reader, additional_info)?;
-                CborValue::Unsigned(val)
-            }
-            MAJOR_NEGATIVE => {
-                let val = self.read_additional(reader, additional_info)?;
-                // CBOR negative int is -1 - n
-                CborValue::Negative(-1 - (val as i64))
-            }
+            MAJOR_UNSIGNED => CborValue::Unsigned(argument),
+            MAJOR_NEGATIVE => CborValue::Negative(argument),
             MAJOR_BYTES => {
                 if additional_info == AI_INDEFINITE {
                     // Indefinite-length byte string
                     let mut chunks = Vec::new();
-                    while let Some(chunk) = self.read_item(reader)? {
+                    for index in 0.. {
+                        // Errors in a chunk are placed at the chunk
+                        let chunk_start = self.offset;
+                        let chunk = match self.read_child(reader, format!("chunk {}", index))? {
+                            Some(chunk) => chunk,
+                            None => {
+                                self.report_truncation(start, "byte string", END_BEFORE_BREAK)?;
+                                break;
+                            }
+                        };
                         if let CborValue::Break = chunk.value {
                             break;
                         }
-                        if let CborValue::Bytes(b) = chunk.value {
-                            chunks.extend(b);
+                        if let CborValue::Bytes(b) = &chunk.value {
+                            if chunk.additional_info == AI_INDEFINITE {
+                                let msg = "Indefinite-length chunk in indefinite byte string";
+                                self.report_error("chunk-type", chunk_start, msg)?;
+                            }
+                            chunks.extend_from_slice(b);
                         } else {
-                            self.no_errors += 1;
-                            eprintln!("Error: Non-byte-string chunk in indefinite byte string");
+                            let msg = "Non-byte-string chunk in indefinite byte string";
+                            self.report_error("chunk-type", chunk_start, msg)?;
                         }
                     }
-                    CborValue::Bytes(chunks)
+                    CborValue::Bytes(chunks.into())
                 } else {
-                    let length = self.read_additional(reader, additional_info)? as usize;
-                    let mut bytes = vec![0u8; length];
-                    reader.read_exact(&mut bytes)?;
-                    self.offset += length;
-                    CborValue::Bytes(bytes)
+                    let length = argument;
+                    self.read_string(reader, major_type, length, start)?
                 }
             }
             MAJOR_TEXT => {
                 if additional_info == AI_INDEFINITE {
                     // Indefinite-length text string
                     let mut text = String::new();
-                    while let Some(chunk) = self.read_item(reader)? {
+                    for index in 0.. {
+                        // Errors in a chunk are placed at the chunk
+                        let chunk_start = self.offset;
+                        let chunk = match self.read_child(reader, format!("chunk {}", index))? {
+                            Some(chunk) => chunk,
+                            None => {
+                                self.report_truncation(start, "text string", END_BEFORE_BREAK)?;
+                                break;
+                            }
+                        };
                         if let CborValue::Break = chunk.value {
                             break;
                         }
-                        if let CborValue::Text(t) = chunk.value {
-                            text.push_str(&t);
+                        if let CborValue::Text(t) = &chunk.value {
+                            if chunk.additional_info == AI_INDEFINITE {
+                                let msg = "Indefinite-length chunk in indefinite text string";
+                                self.report_error("chunk-type", chunk_start, msg)?;
+                            }
+                            text.push_str(t);
                         } else {
-                            self.no_errors += 1;
-                            eprintln!("Error: Non-text-string chunk in indefinite text string");
+                            let msg = "Non-text-string chunk in indefinite text string";
+                            self.report_error("chunk-type", chunk_start, msg)?;
                         }
                     }
-                    CborValue::Text(text)
+                    CborValue::Text(text.into())
                 } else {
-                    let length = self.read_additional(reader, additional_info)? as usize;
-                    let mut bytes = vec![0u8; length];
-                    reader.read_exact(&mut bytes)?;
-                    self.offset += length;
-                    match String::from_utf8(bytes) {
-                        Ok(s) => CborValue::Text(s),
-                        Err(e) => {
-                            self.no_errors += 1;
-                            CborValue::Text(format!("<invalid UTF-8: {}>", e))
-                        }
-                    }
+                    let length = argument;
+                    self.read_string(reader, major_type, length, start)?
                 }
             }
             MAJOR_ARRAY => {
-                if additional_info == AI_INDEFINITE {
-                    // Indefinite-length array
-                    let mut items = Vec::new();
-                    while let Some(item) = self.read_item(reader)? {
-                        if let CborValue::Break = item.value {
+                let length = argument;
+                let mut items = Vec::new();
+                // An indefinite-length array ends at a break code instead
+                while (items.len() as u64) < length {
+                    let item = match self.read_child(reader, format!("[{}]", items.len()))? {
+                        Some(item) => item,
+                        None if length == u64::MAX => {
+                            self.report_truncation(start, "array", END_BEFORE_BREAK)?;
                             break;
                         }
-                        items.push(item);
-                    }
-                    CborValue::Array(items)
-                } else {
-                    let length = self.read_additional(reader, additional_info)? as usize;
-                    let mut items = Vec::new();
-                    for _ in 0..length {
-                        if let Some(item) = self.read_item(reader)? {
-                            items.push(item);
-                        } else {
-                            self.no_errors += 1;
-                            eprintln!("Error: Unexpected EOF in array");
+                        None => {
+                            let detail = missing(length, items.len() as u64, "item");
+                            self.report_truncation(start, "array", &detail)?;
                             break;
                         }
+                    };
+                    if length == u64::MAX && matches!(item.value, CborValue::Break) {
+                        break;
                     }
-                    CborValue::Array(items)
+                    self.check_break(&item.value)?;
+                    items.push(item);
                 }
+                CborValue::Array(items)
             }
             MAJOR_MAP => {
-                if additional_info == AI_INDEFINITE {
-                    // Indefinite-length map
-                    let mut pairs = Vec::new();
-                    while let Some(key) = self.read_item(reader)? {
-                        if let CborValue::Break = key.value {
+                let length = argument;
+                let mut pairs = Vec::new();
+                let mut keys = self.key_order();
+                while (pairs.len() as u64) < length {
+                    let index = pairs.len();
+                    let key_start = self.offset;
+                    let key = match self.read_child(reader, format!("key {}", index))? {
+                        Some(key)
+                            if length == u64::MAX && matches!(key.value, CborValue::Break) =>
+                        {
                             break;
                         }
-                        if let Some(value) = self.read_item(reader)? {
-                            pairs.push((key, value));
-                        } else {
-                            self.no_errors += 1;
-                            eprintln!("Error: Missing value in map");
+                        Some(key) => key,
+                        None if length == u64::MAX => {
+                            self.report_truncation(start, "map", END_BEFORE_BREAK)?;
                             break;
                         }
-                    }
-                    CborValue::Map(pairs)
-                } else {
-                    let length = self.read_additional(reader, additional_info)? as usize;
-                    let mut pairs = Vec::new();
-                    for _ in 0..length {
-                        if let Some(key) = self.read_item(reader)? {
-                            if let Some(value) = self.read_item(reader)? {
-                                pairs.push((key, value));
-                            } else {
-                                self.no_errors += 1;
-                                eprintln!("Error: Missing value in map");
-                                break;
-                            }
-                        } else {
-                            self.no_errors += 1;
-                            eprintln!("Error: Unexpected EOF in map");
+                        None => {
+                            let detail = missing(length, index as u64, "pair");
+                            self.report_truncation(start, "map", &detail)?;
+                            break;
+                        }
+                    };
+                    self.check_break(&key.value)?;
+                    self.check_key_order(&mut keys, &key.value, index, key_start)?;
+                    match self.read_child(reader, value_name(&key.value, index))? {
+                        Some(value) => {
+                            self.check_break(&value.value)?;
+                            pairs.push((key, value));
+                        }
+                        None => {
+                            self.report_truncation(
+                                start,
+                                "map",
+                                "input ends before the value of the last key",
+                            )?;
                             break;
                         }
                     }
-                    CborValue::Map(pairs)
                 }
+                CborValue::Map(pairs)
             }
             MAJOR_TAG => {
-                let tag = self.read_additional(reader, additional_info)?;
-                if let Some(tagged_item) = self.read_item(reader)? {
+                let tag = argument;
+                if let Some(tagged_item) = self.read_child(reader, format!("tag {}", tag))? {
+                    self.check_break(&tagged_item.value)?;
+                    match tag {
+                        TAG_REGEX => self.check_regex(&tagged_item.value, start),
+                        TAG_RATIONAL => self.check_rational(&tagged_item.value, start),
+                        _ => {}
+                    }
                     CborValue::Tag(tag, Box::new(tagged_item))
                 } else {
-                    self.no_errors += 1;
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "Missing tagged value",
-                    ));
+                    return Err(DumpError::truncated(
+                        start,
+                        "tag",
+                        "input ends before the tagged item",
+                        &self.path,
+                    )
+                    .into());
                 }
             }
-            MAJOR_SIMPLE => {
-                match additional_info {
-                    SIMPLE_FALSE => CborValue::Boolean(false),
-                    SIMPLE_TRUE => CborValue::Boolean(true),
-                    SIMPLE_NULL => CborValue::Null,
-                    SIMPLE_UNDEFINED => CborValue::Undefined,
-                    25 => {
-                        // Float16
-                        let mut buf = [0u8; 2];
-                        reader.read_exact(&mut buf)?;
-                        self.offset += 2;
-                        let val = f16_to_f32(u16::from_be_bytes(buf));
-                        CborValue::Float16(val)
-                    }
-                    26 => {
-                        // Float32
-                        let mut buf = [0u8; 4];
-                        reader.read_exact(&mut buf)?;
-                        self.offset += 4;
-                        CborValue::Float32(f32::from_be_bytes(buf))
-                    }
-                    27 => {
-                        // Float64
-                        let mut buf = [0u8; 8];
-                        reader.read_exact(&mut buf)?;
-                        self.offset += 8;
-                        CborValue::Float64(f64::from_be_bytes(buf))
-                    }
-                    AI_INDEFINITE => CborValue::Break,
-                    _ => {
-                        if additional_info < 24 {
-                            CborValue::Simple(additional_info)
-                        } else {
-                            let val = self.read_additional(reader, additional_info)? as u8;
-                            CborValue::Simple(val)
-                        }
-                    }
+            MAJOR_SIMPLE => match additional_info {
+                SIMPLE_FALSE => CborValue::Boolean(false),
+                SIMPLE_TRUE => CborValue::Boolean(true),
+                SIMPLE_NULL => CborValue::Null,
+                SIMPLE_UNDEFINED => CborValue::Undefined,
+                AI_2BYTES => CborValue::Float16(f16_to_f32(argument as u16)),
+                AI_4BYTES => CborValue::Float32(f32::from_bits(argument as u32)),
+                AI_8BYTES => CborValue::Float64(f64::from_bits(argument)),
+                AI_INDEFINITE => CborValue::Break,
+                AI_1BYTE if argument < 32 => {
+                    // Shown anyway, as the value it was meant to be
+                    let message = format!(
+                        "Simple value {} at offset {} is in the two-byte form, \
+                         which is only for values from 32 up",
+                        argument, start
+                    );
+                    self.report_error("simple-value", start, &message)?;
+                    CborValue::Simple(argument as u8)
                 }
-            }
+                _ => CborValue::Simple(argument as u8),
+            },
             _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Invalid major type",
-                ));
+                return Err(
+                    DumpError::malformed(Some(start), &self.path, "Invalid major type").into(),
+                );
             }
         };
 
-        Ok(Some(CborItem::new(major_type, additional_info, value)))
+        Ok(CborItem::new(major_type, additional_info, value).with_head(argument))
+    }
+
+    /// Read the content of a definite-length byte or text string
+    fn read_string<R: Read>(
+        &mut self,
+        reader: &mut R,
+        major_type: u8,
+        length: u64,
+        start: usize,
+    ) -> io::Result<CborValue<'static>> {
+        if major_type == MAJOR_BYTES {
+            let bytes = self.read_content(reader, length, start, "byte string content")?;
+            return Ok(CborValue::Bytes(bytes.into()));
+        }
+        let bytes = self.read_content(reader, length, start, "text string content")?;
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(CborValue::Text(s.into())),
+            Err(e) => {
+                let msg = format!("invalid UTF-8: {}", e);
+                self.report_error("invalid-utf8", start, &msg)?;
+                Ok(CborValue::Text(format!("<{}>", msg).into()))
+            }
+        }
     }
 
     /// Print indentation
-    fn print_indent(&self, level: usize) {
+    fn print_indent(&mut self, level: usize) {
+        self.print_columns(None, level);
+    }
+
+    /// Print indentation for the first line of an item, with its lengths
+    /// if they're shown
+    fn print_item_indent(&mut self, item: &CborItem, level: usize) {
+        self.print_columns(Some(item), level);
+        if self.config.show_heads {
+            write!(self.out, "<{}> ", hex_upper(&item.raw_bytes, " "));
+        }
+    }
+
+    /// Print the offset and length columns, blank where a line doesn't
+    /// start an item, and indentation
+    fn print_columns(&mut self, item: Option<&CborItem>, level: usize) {
         if self.config.show_offsets {
-            if self.config.hex_values {
-                print!("[{:04X}] ", self.offset);
-            } else {
-                print!("[{:4}] ", self.offset);
+            let offset = format_offset(self.offset, self.config.hex_values);
+            write!(self.out, "[{}] ", offset);
+        }
+        if self.config.show_lengths {
+            match item {
+                Some(item) => {
+                    let content = match item.content_length() {
+                        Some(length) => format_offset(length, self.config.hex_values),
+                        None => "NDEF".to_string(),
+                    };
+                    write!(self.out, "{:>2} {}: ", item.header_length(), content);
+                }
+                None => write!(self.out, "{:7}: ", ""),
             }
         }
 
         if !self.config.compact {
-            for _ in 0..level {
-                print!("  ");
+            write!(self.out, "{}", wrap::indentation(level, 2));
+        }
+    }
+
+    /// An integer in decimal and, with --int-hex, in hex too
+    fn integer_text(&self, n: i128) -> String {
+        if self.config.int_hex {
+            let sign = if n < 0 { "-" } else { "" };
+            format!("{} / {}0x{:X}", n, sign, n.unsigned_abs())
+        } else {
+            n.to_string()
+        }
+    }
+
+    /// An array or map in diagnostic notation, for --inline, if that fits
+    /// on the rest of a line at `level` that has `used` characters on it
+    fn inline_form(&self, item: &CborItem, level: usize, used: usize) -> Option<String> {
+        if !matches!(item.value, CborValue::Array(_) | CborValue::Map(_)) {
+            return None;
+        }
+        let indent = if self.config.compact { 0 } else { level * 2 };
+        let room = INLINE_WIDTH.saturating_sub(indent + used);
+        // Diagnostic notation is never shorter than the encoding, so large
+        // items can be passed over without writing them out
+        if item.header_length() + item.content_length()? > room {
+            return None;
+        }
+        let line = render::diag_notation(&item.to_node(0));
+        let line = self.shown_text(&line).into_owned();
+        (line.chars().count() <= room).then_some(line)
+    }
+
+    /// Note a text string holding a JSON object or array, laying it out
+    /// below with --pretty-json
+    fn print_embedded_json(&mut self, text: &str, level: usize) {
+        let paint = |style, text: &str| {
+            let text = self.shown_text(text);
+            self.out.paint(style, &text).to_string()
+        };
+        let Some(lines) = json::pretty_lines(text, &paint) else {
+            return;
+        };
+        self.print_indent(level);
+        writeln!(self.out, "  (embedded JSON)");
+        if self.config.pretty_json {
+            for line in lines {
+                self.print_indent(level);
+                writeln!(self.out, "  {}", line);
+            }
+        }
+    }
+
+    /// Dump the DER or CBOR that a text string holds as base64 or PEM below
+    /// it: CBOR as the rest of the dump, ASN.1 as --format tree shows it
+    fn print_embedded_encoding(&mut self, text: &str, level: usize) -> io::Result<()> {
+        let Some((detected, data)) = cli::embedded_encoding(text) else {
+            return Ok(());
+        };
+        self.print_indent(level);
+        writeln!(self.out, "  ({})", detected);
+        if detected.kind == InputKind::Cbor {
+            for item in cbor::parse_slice(&data)? {
+                self.print_item(&item, level + 1)?;
+            }
+            return Ok(());
+        }
+
+        let mut rendered = Vec::new();
+        match dumpasn1::to_nodes(&data, false) {
+            Ok(nodes) => render::render(Format::Tree.renderer().as_mut(), &mut rendered, &nodes)?,
+            Err(e) => writeln!(rendered, "<{}>", e)?,
+        }
+        for line in String::from_utf8_lossy(&rendered).lines() {
+            self.print_indent(level + 1);
+            writeln!(self.out, "{}", line);
+        }
+        Ok(())
+    }
+
+    /// Dump the CBOR that a byte string tagged 24 or 63 holds below it: one
+    /// data item for tag 24, a sequence of any number of them for tag 63.
+    /// A byte string whose label says it holds CBOR is taken as tag 24, with
+    /// `labels` for the elements of the item in it.
+    fn print_encoded_cbor(
+        &mut self,
+        tag: u64,
+        bytes: &[u8],
+        level: usize,
+        labels: Vec<Label>,
+    ) -> io::Result<()> {
+        let items = match cbor::parse_slice(bytes) {
+            Ok(items) => items,
+            Err(e) => {
+                self.print_indent(level);
+                writeln!(self.out, "  (not well-formed CBOR: {})", e);
+                return Ok(());
+            }
+        };
+        self.print_indent(level);
+        match (tag, items.len()) {
+            (TAG_CBOR_SEQUENCE, count) => {
+                writeln!(self.out, "  (encoded CBOR sequence of {} item(s))", count)
+            }
+            (_, 1) => writeln!(self.out, "  (encoded CBOR)"),
+            (_, count) => writeln!(self.out, "  (encoded CBOR of {} items, not one)", count),
+        }
+        self.child_labels = labels;
+        for (i, item) in items.iter().enumerate() {
+            self.print_item(item, level + 1)?;
+            if i + 1 < items.len() && !self.config.compact {
+                self.print_indent(level + 1);
+                writeln!(self.out, ",");
             }
         }
+        Ok(())
+    }
+
+    /// Name the next item by `label`, returning the note to print after it
+    fn apply_label(&mut self, label: Label) -> Option<String> {
+        self.label = Some(label.name).filter(|name| !name.is_empty());
+        self.child_labels = label.children;
+        self.label_encoded = label.encoded;
+        label.note
+    }
+
+    /// Show the text that a byte string tagged 21, 22 or 23 is to become
+    /// when converted to JSON (RFC 8949 section 3.4.5.2), which is what the
+    /// tag is there to say
+    fn print_expected_conversion(&mut self, tag: u64, bytes: &[u8], level: usize) {
+        let (name, mut text) = match tag {
+            TAG_BASE64URL => (
+                "base64url",
+                pem::encode_base64_with(bytes, Alphabet::UrlSafe),
+            ),
+            TAG_BASE64 => ("base64", pem::encode_base64(bytes)),
+            _ => ("base16", hex_lower(bytes)),
+        };
+        if text.len() > TEXT_DISPLAY_LIMIT && !self.config.print_all_data {
+            text.truncate(TEXT_DISPLAY_LIMIT);
+            text.push_str("...");
+        }
+        self.print_indent(level);
+        writeln!(self.out, "  (in JSON as {}: \"{}\")", name, text);
+    }
+
+    /// Print the text of a MIME message (tag 36) as its content type and
+    /// transfer encoding, a count of its other header fields and the first
+    /// lines of its body, rather than as one long string
+    fn print_mime(&mut self, item: &CborItem, text: &str, message: &mime::Message, level: usize) {
+        self.print_item_indent(item, level);
+        if self.config.show_types {
+            writeln!(self.out, "text: MIME message of {} bytes", text.len());
+        } else {
+            writeln!(self.out, "MIME message of {} bytes", text.len());
+        }
+
+        let mut fields = vec![
+            ("Content-Type", message.content_type()),
+            ("Content-Transfer-Encoding", message.transfer_encoding()),
+        ];
+        let others: Vec<(&str, &str)> = message
+            .fields
+            .iter()
+            .filter(|(name, _)| {
+                !fields
+                    .iter()
+                    .any(|(shown, _)| shown.eq_ignore_ascii_case(name))
+            })
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        let hidden = if self.config.print_all_data {
+            fields.extend(others);
+            0
+        } else {
+            others.len()
+        };
+        for (name, value) in fields {
+            self.print_indent(level);
+            writeln!(self.out, "  {}: {}", name, self.shown_text(value));
+        }
+        if hidden > 0 {
+            self.print_indent(level);
+            writeln!(self.out, "  ({} other header field(s))", hidden);
+        }
+
+        let lines: Vec<&str> = message.body.lines().collect();
+        self.print_indent(level);
+        if lines.is_empty() {
+            writeln!(self.out, "  (no body)");
+            return;
+        }
+        writeln!(self.out, "  body ({} line(s)):", lines.len());
+        let shown = if self.config.print_all_data {
+            lines.len()
+        } else {
+            lines.len().min(MIME_PREVIEW_LINES)
+        };
+        for line in &lines[..shown] {
+            self.print_indent(level);
+            writeln!(self.out, "    {}", self.shown_text(line));
+        }
+        if shown < lines.len() {
+            self.print_indent(level);
+            writeln!(self.out, "    ... ({} more line(s))", lines.len() - shown);
+        }
+    }
+
+    /// Decoded text as it's printed: with control characters escaped,
+    /// unless --raw-strings asks for it as it is
+    fn shown_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.config.raw_strings {
+            Cow::Borrowed(text)
+        } else {
+            escape_controls(text)
+        }
     }
 
     /// Print hex dump of bytes
-    fn print_hex_dump(&self, bytes: &[u8], max_bytes: usize) {
+    fn print_hex_dump(&mut self, bytes: &[u8], max_bytes: usize) {
         let display_bytes = bytes.len().min(max_bytes);
 
-        for (i, byte) in bytes.iter().take(display_bytes).enumerate() {
-            if i > 0 && i % 16 == 0 {
-                print!("\n    ");
+        for (i, row) in bytes[..display_bytes]
+            .chunks(HEX_BYTES_PER_LINE)
+            .enumerate()
+        {
+            if i > 0 {
+                write!(self.out, "\n    ");
             }
-            print!("{:02X} ", byte);
+            write!(self.out, "{} ", hex_upper(row, " "));
         }
 
         if bytes.len() > display_bytes {
-            print!("\n    ... ({} more bytes)", bytes.len() - display_bytes);
+            write!(
+                self.out,
+                "\n    ... ({} more bytes)",
+                bytes.len() - display_bytes
+            );
+        }
+    }
+
+    /// Finish the line opening an array, map or chunked string, with the
+    /// number of elements if the length is definite
+    fn print_open(&mut self, kind: &str, count: Option<u64>, unit: &str, bracket: char) {
+        if !self.config.show_types {
+            writeln!(self.out, "{}", bracket);
+            return;
+        }
+        match count {
+            Some(count) => writeln!(self.out, "{}({} {}) {}", kind, count, unit, bracket),
+            None => writeln!(self.out, "{}(indefinite length) {}", kind, bracket),
+        }
+    }
+
+    /// Finish the line opening a tagged item
+    fn print_tag_open(&mut self, tag: u64) {
+        match (cbor::tag_name(tag), self.config.show_types) {
+            (Some(name), true) => writeln!(self.out, "tag {} ({}) {{", tag, name),
+            (Some(name), false) => writeln!(self.out, "tag({}) {{", name),
+            (None, true) => writeln!(self.out, "tag {} {{", tag),
+            (None, false) => writeln!(self.out, "tag({}) {{", tag),
         }
     }
 
     /// Print a CBOR item
     fn print_item(&mut self, item: &CborItem, level: usize) -> io::Result<()> {
+        self.out.check()?;
         if level > self.config.max_nest_level {
             self.print_indent(level);
-            println!("<max nesting level exceeded>");
+            writeln!(self.out, "<max nesting level exceeded>");
             return Ok(());
         }
 
-        self.print_indent(level);
+        if self.config.verbose {
+            self.print_indent(level);
+            let byte = (item.major_type << 5) | item.additional_info;
+            writeln!(self.out, "({})", head_detail(byte));
+        }
+        self.print_item_indent(item, level);
+
+        // Arrays of a recognized format label their own elements, otherwise
+        // the labels come from an enclosing recognized structure
+        let mut child_labels = std::mem::take(&mut self.child_labels);
+        let encoded = std::mem::take(&mut self.label_encoded);
+        let mut label = self.label.take();
+        match &item.value {
+            CborValue::Array(items) => {
+                if let Some(labels) = c509::certificate_labels(items) {
+                    child_labels = labels;
+                    label = label.or(Some("c509Certificate"));
+                }
+            }
+            CborValue::Tag(tag, tagged_item) if child_labels.is_empty() => {
+                if let (Some(message), CborValue::Array(items)) =
+                    (cose::message(*tag), &tagged_item.value)
+                {
+                    if message.check(item).is_ok() {
+                        child_labels = message.labels(items);
+                    }
+                }
+            }
+            _ => {}
+        }
+        if let Some(label) = label {
+            write!(self.out, "{}: ", label);
+        }
+        if self.config.inline {
+            let used = label.map_or(0, |label| label.len() + 2);
+            if let Some(line) = self.inline_form(item, level, used) {
+                writeln!(self.out, "{}", line);
+                return Ok(());
+            }
+        }
 
         let type_prefix = if self.config.show_types {
             match &item.value {
@@ -464,150 +1146,217 @@ impl CborDumper {
         };
 
         match &item.value {
-            CborValue::Unsigned(n) => {
-                if self.config.show_types {
-                    println!("{}({})", type_prefix, n);
-                } else {
-                    println!("{}", n);
-                }
-            }
-            CborValue::Negative(n) => {
+            CborValue::Unsigned(_) | CborValue::Negative(_) => {
+                let n = self.integer_text(item.value.integer().unwrap_or_default());
                 if self.config.show_types {
-                    println!("{}({})", type_prefix, n);
+                    writeln!(self.out, "{}({})", type_prefix, n);
                 } else {
-                    println!("{}", n);
+                    writeln!(self.out, "{}", n);
                 }
             }
             CborValue::Bytes(bytes) => {
                 if self.config.show_types {
-                    println!("{}({} bytes)", type_prefix, bytes.len());
+                    writeln!(self.out, "{}({} bytes)", type_prefix, bytes.len());
                 } else {
-                    println!("<{} bytes>", bytes.len());
+                    writeln!(self.out, "<{} bytes>", bytes.len());
                 }
                 if self.config.print_hex || bytes.len() <= 64 {
                     self.print_indent(level);
-                    print!("  ");
+                    write!(self.out, "  ");
                     let max = if self.config.print_all_data {
                         usize::MAX
                     } else {
                         self.config.max_bytes_display
                     };
                     self.print_hex_dump(bytes, max);
-                    println!();
+                    writeln!(self.out);
+                }
+                if encoded && self.config.decode_nested {
+                    self.print_encoded_cbor(TAG_CBOR, bytes, level, child_labels)?;
                 }
             }
             CborValue::Text(s) => {
-                if s.len() > 80 && !self.config.print_all_data {
+                if s.len() > TEXT_DISPLAY_LIMIT && !self.config.print_all_data {
+                    // Cut at a character boundary, not partway through one
+                    let mut end = TEXT_DISPLAY_LIMIT;
+                    while !s.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    let shown = self.shown_text(&s[..end]);
                     if self.config.show_types {
-                        println!(
+                        writeln!(
+                            self.out,
                             "{}: \"{}...\" ({} chars total)",
                             type_prefix,
-                            &s[..80],
+                            shown,
                             s.len()
                         );
                     } else {
-                        println!("\"{}...\"", &s[..80]);
+                        writeln!(self.out, "\"{}...\"", shown);
                     }
                 } else if self.config.show_types {
-                    println!("{}: \"{}\"", type_prefix, s);
+                    writeln!(self.out, "{}: \"{}\"", type_prefix, self.shown_text(s));
                 } else {
-                    println!("\"{}\"", s);
+                    writeln!(self.out, "\"{}\"", self.shown_text(s));
+                }
+                self.print_embedded_json(s, level);
+                if self.config.decode_nested {
+                    self.print_embedded_encoding(s, level)?;
                 }
             }
             CborValue::Array(items) => {
-                if self.config.show_types {
-                    println!("{}({} items) [", type_prefix, items.len());
-                } else {
-                    println!("[");
-                }
+                self.print_open("array", Some(items.len() as u64), "items", '[');
+                let mut labels = child_labels.into_iter();
                 for (i, sub_item) in items.iter().enumerate() {
+                    let note = labels.next().and_then(|label| self.apply_label(label));
                     self.print_item(sub_item, level + 1)?;
+                    if let Some(note) = note {
+                        self.print_indent(level + 1);
+                        writeln!(self.out, "  ({})", note);
+                    }
                     if i < items.len() - 1 && !self.config.compact {
                         self.print_indent(level + 1);
-                        println!(",");
+                        writeln!(self.out, ",");
                     }
                 }
                 self.print_indent(level);
-                println!("]");
+                writeln!(self.out, "]");
             }
             CborValue::Map(pairs) => {
-                if self.config.show_types {
-                    println!("{}({} pairs) {{", type_prefix, pairs.len());
-                } else {
-                    println!("{{");
-                }
+                self.print_open("map", Some(pairs.len() as u64), "pairs", '{');
+                let mut labels = child_labels.into_iter();
                 for (i, (key, value)) in pairs.iter().enumerate() {
                     self.print_item(key, level + 1)?;
                     self.print_indent(level + 1);
-                    println!("=>");
+                    writeln!(self.out, "=>");
+                    let note = match labels.next() {
+                        Some(label) => self.apply_label(label),
+                        None => {
+                            self.label = self.key_labels.and_then(|names| names(&key.value));
+                            None
+                        }
+                    };
                     self.print_item(value, level + 1)?;
+                    if let Some(note) = note {
+                        self.print_indent(level + 1);
+                        writeln!(self.out, "  ({})", note);
+                    }
                     if i < pairs.len() - 1 && !self.config.compact {
                         self.print_indent(level + 1);
-                        println!(",");
+                        writeln!(self.out, ",");
                     }
                 }
                 self.print_indent(level);
-                println!("}}");
+                writeln!(self.out, "}}");
             }
             CborValue::Tag(tag, tagged_item) => {
-                if let Some(name) = self.tag_name(*tag) {
-                    if self.config.show_types {
-                        println!("{} {} ({}) {{", type_prefix, tag, name);
-                    } else {
-                        println!("tag({}) {{", name);
+                self.print_tag_open(*tag);
+                let message = match (*tag, &tagged_item.value) {
+                    (TAG_MIME, CborValue::Text(text)) => mime::parse(text).map(|m| (text, m)),
+                    _ => None,
+                };
+                // Labels for a tagged array are for the array
+                self.child_labels = child_labels;
+                match message {
+                    Some((text, message)) => {
+                        self.print_mime(tagged_item, text, &message, level + 1)
                     }
-                } else if self.config.show_types {
-                    println!("{} {} {{", type_prefix, tag);
-                } else {
-                    println!("tag({}) {{", tag);
+                    None => self.print_item(tagged_item, level + 1)?,
+                }
+                match (*tag, &tagged_item.value) {
+                    (TAG_CBOR | TAG_CBOR_SEQUENCE, CborValue::Bytes(bytes))
+                        if self.config.decode_nested =>
+                    {
+                        self.print_encoded_cbor(*tag, bytes, level + 1, Vec::new())?;
+                    }
+                    (TAG_BASE64URL..=TAG_BASE16, CborValue::Bytes(bytes)) => {
+                        self.print_expected_conversion(*tag, bytes, level + 1);
+                    }
+                    (TAG_RATIONAL, value) => {
+                        let note = match rational_parts(value) {
+                            Ok((numerator, denominator, value)) => {
+                                format!("{}/{}, about {}", numerator, denominator, value)
+                            }
+                            Err(e) => format!("not a rational number: it {}", e),
+                        };
+                        self.print_indent(level + 1);
+                        writeln!(self.out, "  ({})", note);
+                    }
+                    (TAG_REGEX, CborValue::Text(text)) => {
+                        // Only -v shows what a valid one is made of
+                        let note = match regex::outline(text) {
+                            Ok(outline) if self.config.verbose => {
+                                format!("regular expression: {}", outline)
+                            }
+                            Ok(_) => String::new(),
+                            Err(e) => format!("not a valid regular expression: {}", e),
+                        };
+                        if !note.is_empty() {
+                            self.print_indent(level + 1);
+                            writeln!(self.out, "  ({})", note);
+                        }
+                    }
+                    _ => {}
                 }
-                self.print_item(tagged_item, level + 1)?;
                 self.print_indent(level);
-                println!("}}");
+                writeln!(self.out, "}}");
             }
             CborValue::Simple(n) => {
+                // false, true, null and undefined, the values assigned so
+                // far, are shown by name
+                let status = if (24..32).contains(n) {
+                    "reserved"
+                } else {
+                    "unassigned"
+                };
                 if self.config.show_types {
-                    println!("simple({})", n);
+                    writeln!(self.out, "simple({}) ({})", n, status);
                 } else {
-                    println!("simple:{}", n);
+                    writeln!(self.out, "simple:{} ({})", n, status);
                 }
             }
             CborValue::Boolean(b) => {
                 if self.config.show_types {
-                    println!("{}: {}", type_prefix, b);
+                    writeln!(self.out, "{}: {}", type_prefix, b);
                 } else {
-                    println!("{}", b);
+                    writeln!(self.out, "{}", b);
                 }
             }
             CborValue::Null => {
-                println!("{}", type_prefix);
+                writeln!(self.out, "{}", type_prefix);
             }
             CborValue::Undefined => {
-                println!("{}", type_prefix);
+                writeln!(self.out, "{}", type_prefix);
             }
             CborValue::Float16(f) => {
+                // A half-precision value is exactly representable in single
+                // precision, so its bits can be had back
+                let bits = half_bits(*f as f64).unwrap_or_default();
+                let detail = half_detail(bits, &f.to_string());
                 if self.config.show_types {
-                    println!("{}: {}", type_prefix, f);
+                    writeln!(self.out, "{}: {} ({})", type_prefix, f, detail);
                 } else {
-                    println!("{}", f);
+                    writeln!(self.out, "{} ({})", f, detail);
                 }
             }
             CborValue::Float32(f) => {
+                let note = shorter_float_note(*f as f64);
                 if self.config.show_types {
-                    println!("{}: {}", type_prefix, f);
+                    writeln!(self.out, "{}: {}{}", type_prefix, f, note);
                 } else {
-                    println!("{}", f);
+                    writeln!(self.out, "{}{}", f, note);
                 }
             }
             CborValue::Float64(f) => {
+                let note = shorter_float_note(*f);
                 if self.config.show_types {
-                    println!("{}: {}", type_prefix, f);
+                    writeln!(self.out, "{}: {}{}", type_prefix, f, note);
                 } else {
-                    println!("{}", f);
+                    writeln!(self.out, "{}{}", f, note);
                 }
             }
             CborValue::Break => {
-                println!("break");
+                writeln!(self.out, "break");
             }
         }
 
@@ -615,263 +1364,1870 @@ impl CborDumper {
     }
 
     /// Main entry point to dump CBOR data
-    fn dump_cbor<R: Read>(&mut self, reader: &mut R) -> io::Result<()> {
+    fn dump_cbor<R: Read + Seek>(&mut self, reader: &mut R) -> io::Result<()> {
+        let skipped = self.skip_items(reader)?;
         let mut item_count = 0;
+        if let Some(format) = self.config.content_format {
+            writeln!(
+                self.out,
+                "CoAP content-format {}: {}\n",
+                format.id, format.media_type
+            );
+        }
 
-        while let Some(item) = self.read_item(reader)? {
+        while self.config.first != Some(item_count) {
+            let item_start = self.offset;
+            let item = match self.read_item(reader) {
+                Ok(Some(item)) => item,
+                Ok(None) => break,
+                Err(e) if self.config.recover && diagnostics::is_malformed(&e) => {
+                    writeln!(
+                        self.out,
+                        "\n{} {}",
+                        self.out.paint(Style::Error, "Error:"),
+                        e
+                    );
+                    let diagnostic = diagnostics::fatal(&e, Some(item_start), &self.path);
+                    self.findings.record(diagnostic);
+                    self.path.clear();
+                    // Nothing of the bad item has been printed yet, and its
+                    // declared lengths can't be trusted, so rescan everything
+                    // after its first byte
+                    if !self.resync(reader, item_start + 1)? {
+                        break;
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            self.check_break(&item.value)?;
             if item_count > 0 {
-                println!();
+                writeln!(self.out);
+            }
+            // The self-described CBOR tag marks the input rather than
+            // holding a value, so it's noted instead of nesting what it tags
+            let shown = match item.self_described() {
+                Some(tagged_item) if item_start == 0 => {
+                    writeln!(self.out, "{}", SELF_DESCRIBED);
+                    tagged_item
+                }
+                _ => &item,
+            };
+            if let Some(format) = self.config.content_format {
+                self.label_payload(shown, format, item_start);
             }
-            self.print_item(&item, 0)?;
+            self.print_item(shown, 0)?;
             item_count += 1;
         }
 
-        println!("\nParsing complete. {} item(s) found.", item_count);
-        if self.no_errors > 0 {
-            println!("Errors: {}", self.no_errors);
-        }
-        if self.no_warnings > 0 {
-            println!("Warnings: {}", self.no_warnings);
-        }
-
+        self.print_summary(item_count, skipped);
         Ok(())
     }
-}
 
-/// Convert IEEE 754 half-precision float to single-precision
-fn f16_to_f32(bits: u16) -> f32 {
-    let sign = ((bits >> 15) & 1) as u32;
-    let exp = ((bits >> 10) & 0x1F) as u32;
-    let mant = (bits & 0x3FF) as u32;
-
-    if exp == 0 {
-        if mant == 0 {
-            // Zero
-            f32::from_bits(sign << 31)
-        } else {
-            // Subnormal
-            let new_exp = 127 - 15;
-            let new_mant = mant << 13;
-            f32::from_bits((sign << 31) | (new_exp << 23) | new_mant)
-        }
-    } else if exp == 0x1F {
-        if mant == 0 {
-            // Infinity
-            f32::from_bits((sign << 31) | (0xFF << 23))
-        } else {
-            // NaN
-            f32::from_bits((sign << 31) | (0xFF << 23) | (mant << 13))
+    /// Label a top-level item, at `offset`, as the payload of a CoAP
+    /// message of content `format`: the elements of a COSE message, or the
+    /// fields of SenML records, warning of a payload that doesn't fit
+    fn label_payload(&mut self, item: &CborItem, format: &ContentFormat, offset: usize) {
+        match format.payload {
+            Payload::Cose(tag) => {
+                let message = cose::message(tag).expect("COSE content formats have COSE tags");
+                if let Err(e) = message.check(item) {
+                    let msg = format!(
+                        "Payload at offset {} isn't the {} of content-format {}: {}",
+                        offset, message.name, format.id, e
+                    );
+                    self.record(Severity::Warning, "content-format", Some(offset), &msg);
+                    writeln!(self.out, "[{}]", msg);
+                    return;
+                }
+                // A tagged message is recognized as it's printed
+                if let CborValue::Array(items) = &item.value {
+                    self.label = Some(message.name);
+                    self.child_labels = message.labels(items);
+                }
+            }
+            Payload::Senml => self.key_labels = Some(coap::senml_label),
+            Payload::Key => {
+                if let CborValue::Map(pairs) = &item.value {
+                    self.label = Some("COSE_Key");
+                    self.child_labels = cose::key_labels(pairs);
+                }
+            }
+            Payload::KeySet => {
+                if let CborValue::Array(keys) = &item.value {
+                    self.label = Some("COSE_KeySet");
+                    self.child_labels = cose::key_set_labels(keys);
+                }
+            }
+            Payload::Cbor | Payload::NotCbor => {}
         }
-    } else {
-        // Normalized
-        let new_exp = exp + 127 - 15;
-        let new_mant = mant << 13;
-        f32::from_bits((sign << 31) | (new_exp << 23) | new_mant)
     }
-}
 
-fn print_help(program_name: &str) {
-    println!("CBOR Dumper - Rust Implementation");
-    println!("Based on the concepts from dumpasn1.c by Peter Gutmann\n");
-    println!("Usage: {} [OPTIONS] <input_file>", program_name);
-    println!("\nDumps CBOR-encoded data (RFC 8949) in a human-readable format.\n");
-    println!("OPTIONS:");
-    println!("  -h, --help              Show this help message and exit");
-    println!(
-        "  -a, --print-all         Print all data in long byte strings (not just first 384 bytes)"
-    );
-    println!("  -c, --compact           Compact output mode with minimal whitespace");
-    println!(
-        "  -f <file>               Read input from <file> (alternative to positional argument)"
-    );
-    println!("  -l <level>              Maximum nesting level to display (default: 100)");
-    println!("  -m <bytes>              Maximum bytes to display for byte strings (default: 384)");
-    println!("  -o, --offsets           Show byte offsets for each item");
-    println!("  -t, --no-types          Don't show type names, only values");
-    println!("  -v, --verbose           Verbose output with extra information");
-    println!("  -x, --hex               Always show hex dump for byte strings");
-    println!("  --hex-offsets           Display offsets in hexadecimal instead of decimal");
-    println!("  --no-decode-nested      Don't try to decode nested CBOR in byte strings");
-    println!("\nEXAMPLES:");
-    println!("  {} data.cbor", program_name);
-    println!(
-        "  {} --hex --offsets message.cbor     # Show hex and offsets",
-        program_name
-    );
-    println!(
-        "  {} -c -l 3 large.cbor               # Compact mode, max 3 levels deep",
-        program_name
-    );
-    println!("\nThe input file should contain binary CBOR-encoded data.");
-    println!("\nCBOR MAJOR TYPES:");
-    println!("  0: Unsigned integer       4: Array");
-    println!("  1: Negative integer       5: Map");
-    println!("  2: Byte string            6: Tagged value");
-    println!("  3: Text string (UTF-8)    7: Simple value/float");
-}
+    /// Read past the top-level items --skip passes over without printing
+    /// them, or building them in memory, returning how many there were
+    fn skip_items<R: Read>(&mut self, reader: &mut R) -> io::Result<usize> {
+        let out = std::mem::replace(&mut self.out, Output::sink());
+        let mut skipped = 0;
+        let mut result = Ok(());
+        while skipped < self.config.skip {
+            match self.next_byte(reader) {
+                Ok(Some(byte)) => result = self.stream_item(reader, byte, 0).map(|_| ()),
+                Ok(None) => break,
+                Err(e) => result = Err(e),
+            }
+            if result.is_err() {
+                break;
+            }
+            skipped += 1;
+        }
+        self.out = out;
+        result.map(|()| skipped)
+    }
 
-fn parse_args_from(args: &[String]) -> Result<(Config, Option<String>), String> {
-    if args.len() < 2 {
-        return Err("No input file specified".to_string());
+    /// Print the closing totals, noting the items --skip and --first left
+    /// out
+    fn print_summary(&mut self, item_count: usize, skipped: usize) {
+        writeln!(
+            self.out,
+            "\nParsing complete. {} item(s) found.",
+            item_count
+        );
+        if skipped > 0 {
+            writeln!(self.out, "{} item(s) skipped before them.", skipped);
+        }
+        if self.config.first == Some(item_count) {
+            writeln!(
+                self.out,
+                "Stopped after the first {}; the rest of the input wasn't read.",
+                item_count
+            );
+        }
+        write!(self.out, "{}", self.findings.totals());
     }
 
-    let mut config = Config::default();
-    let mut input_file: Option<String> = None;
-    let mut i = 1;
+    /// Dump a file, then write out the errors and warnings if --errors-json
+    /// asks for them. The error that stopped the dump, if any, is recorded
+    /// with the rest. `input` is the file's content when --auto has decoded
+    /// it from base64 already
+    fn dump_file(&mut self, filename: &str, input: Option<Vec<u8>>) -> io::Result<()> {
+        match input {
+            Some(data) => self.dump_input(Some(filename), &mut Cursor::new(data)),
+            None => {
+                let file = File::open(filename)
+                    .map_err(|e| DumpError::io(format!("Can't open '{}'", filename), e))?;
+                // Reads from a pipe or FIFO wait for more anyway, and its end
+                // means the writer has gone
+                let poll = self.config.follow && file.metadata()?.is_file();
+                let mut reader = BufReader::new(Follow { inner: file, poll });
+                // A compressed file is decompressed whole, so it's read to
+                // its end rather than followed
+                reader.get_mut().poll &= Compression::detect(reader.fill_buf()?).is_none();
+                match compressed::read_decompressed(&mut reader)
+                    .map_err(|e| DumpError::io(format!("Can't decompress '{}'", filename), e))?
+                {
+                    Some(data) => self.dump_input(Some(filename), &mut Cursor::new(data)),
+                    None => self.dump_input(Some(filename), &mut reader),
+                }
+            }
+        }
+    }
 
-    while i < args.len() {
-        let arg = &args[i];
+    /// Dump `reader`, read from `filename` or decoded from it, as dump_file.
+    /// Input held in memory has no name, and no header naming it
+    fn dump_input<R: Read + Seek>(
+        &mut self,
+        filename: Option<&str>,
+        reader: &mut R,
+    ) -> io::Result<()> {
+        // Not timed otherwise, as there's no clock in some wasm32 targets
+        let started = self.config.timing.then(Instant::now);
+        if started.is_some() {
+            self.out.measure_rendering();
+        }
 
-        match arg.as_str() {
-            "-h" | "--help" => {
-                print_help(&args[0]);
-                std::process::exit(0);
-            }
-            "-a" | "--print-all" => {
-                config.print_all_data = true;
-            }
-            "-c" | "--compact" => {
-                config.compact = true;
-            }
-            "-f" => {
-                i += 1;
-                if i >= args.len() {
-                    return Err("Missing filename after -f".to_string());
-                }
-                input_file = Some(args[i].clone());
+        let grep = self.config.grep.is_some() || self.config.grep_bytes.is_some();
+        if self.config.format.is_some() || grep || self.config.roundtrip {
+            // The output is in the format asked for and nothing else
+        } else if self.config.verbose {
+            if let Some(filename) = filename {
+                writeln!(self.out, "Dumping CBOR file: {}", filename);
             }
-            "-l" | "--max-level" => {
-                i += 1;
-                if i >= args.len() {
-                    return Err("Missing value after -l".to_string());
+            writeln!(self.out, "Configuration:");
+            writeln!(self.out, "  Print all data: {}", self.config.print_all_data);
+            writeln!(self.out, "  Show hex: {}", self.config.print_hex);
+            writeln!(self.out, "  Show offsets: {}", self.config.show_offsets);
+            writeln!(self.out, "  Show lengths: {}", self.config.show_lengths);
+            writeln!(self.out, "  Show heads: {}", self.config.show_heads);
+            writeln!(
+                self.out,
+                "  Max nesting level: {}",
+                self.config.max_nest_level
+            );
+            writeln!(
+                self.out,
+                "  Max bytes display: {}",
+                self.config.max_bytes_display
+            );
+            writeln!(self.out);
+        } else if let (false, Some(filename)) = (self.config.compact, filename) {
+            writeln!(self.out, "Dumping CBOR file: {}\n", filename);
+        }
+
+        let result = if grep {
+            self.grep_cbor(reader)
+        } else if self.config.roundtrip {
+            self.roundtrip_cbor(reader)
+        } else if let Some(format) = self.config.format {
+            self.render_cbor(reader, format)
+        } else if self.config.stream {
+            self.stream_cbor(reader)
+        } else {
+            self.dump_cbor(reader)
+        };
+
+        // Flush what was printed before an error too, as --stream may have
+        // printed part of the item that failed
+        let flushed = self.out.render(|out| out.flush());
+        let result = result.and(flushed);
+        if let (Some(started), Some(render_time)) = (started, self.out.render_time()) {
+            eprintln!("{}", timing_report(started.elapsed(), render_time));
+        }
+
+        // With --strict, an error found after the first is the one that
+        // stopped the dump, and has been recorded already
+        let aborted = self.config.strict && self.findings.errors > 0;
+        if let (Err(e), false) = (&result, aborted) {
+            let diagnostic = diagnostics::fatal(e, None, &self.path);
+            self.findings.record(diagnostic);
+        }
+        if let Some(destination) = self.config.errors_json.clone() {
+            diagnostics::write_json(&self.findings.diagnostics, &destination)
+                .map_err(|e| DumpError::io(format!("Can't write '{}'", destination), e))?;
+        }
+        result
+    }
+
+    /// Main entry point for --listen and --unix: take connections one after
+    /// another, dumping what each sends as it arrives
+    fn dump_connections(&mut self, listen: &Listen) -> io::Result<()> {
+        let cant_listen =
+            |address: &str, e| DumpError::io(format!("Can't listen on '{}'", address), e);
+        match listen {
+            Listen::Tcp(address) => {
+                let listener = TcpListener::bind(address).map_err(|e| cant_listen(address, e))?;
+                eprintln!("Listening on {}", listener.local_addr()?);
+                for stream in listener.incoming() {
+                    let stream = stream?;
+                    let peer = format!("from {}", stream.peer_addr()?);
+                    self.dump_connection(&peer, stream)?;
                 }
-                config.max_nest_level = args[i]
-                    .parse()
-                    .map_err(|_| format!("Invalid number for max level: {}", args[i]))?;
             }
-            "-m" | "--max-bytes" => {
-                i += 1;
-                if i >= args.len() {
-                    return Err("Missing value after -m".to_string());
+            #[cfg(unix)]
+            Listen::Unix(path) => {
+                let listener = UnixListener::bind(path).map_err(|e| cant_listen(path, e))?;
+                eprintln!("Listening on {}", path);
+                // Clients of a Unix socket rarely have names of their own
+                let name = format!("on {}", path);
+                for stream in listener.incoming() {
+                    self.dump_connection(&name, stream?)?;
                 }
-                config.max_bytes_display = args[i]
-                    .parse()
-                    .map_err(|_| format!("Invalid number for max bytes: {}", args[i]))?;
-            }
-            "-o" | "--offsets" => {
-                config.show_offsets = true;
             }
-            "-t" | "--no-types" => {
-                config.show_types = false;
+            #[cfg(not(unix))]
+            Listen::Unix(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Unix sockets aren't supported on this system",
+                ));
             }
-            "-v" | "--verbose" => {
-                config.verbose = true;
+        }
+        Ok(())
+    }
+
+    /// Dump the items a connection sends until it closes, under a header
+    /// naming it by `origin`. What goes wrong in one connection is reported,
+    /// and the next is taken
+    fn dump_connection<R: Read>(&mut self, origin: &str, stream: R) -> io::Result<()> {
+        writeln!(self.out, "Connection {}\n", origin);
+        self.offset = 0;
+        self.path.clear();
+        if let Err(e) = self.dump_input(None, &mut BufReader::new(Unseekable(stream))) {
+            writeln!(
+                self.out,
+                "\n{} {}",
+                self.out.paint(Style::Error, "Error:"),
+                e
+            );
+        }
+        writeln!(self.out, "\nConnection {} closed\n", origin);
+        self.out.flush()
+    }
+
+    /// Main entry point for --format: decode the whole input, then hand it
+    /// to the renderer for the format
+    fn render_cbor<R: Read + Seek>(&mut self, reader: &mut R, format: Format) -> io::Result<()> {
+        self.skip_items(reader)?;
+        let start = self.offset;
+        let mut items = Vec::new();
+        while self.config.first != Some(items.len()) {
+            match self.read_item(reader)? {
+                Some(item) => items.push(item),
+                None => break,
             }
-            "-x" | "--hex" => {
-                config.print_hex = true;
+        }
+        let mut nodes = cbor::to_nodes(&items);
+        nodes.iter_mut().for_each(|node| node.shift(start));
+        let mut digests = 0;
+        if let Some(alg) = self.config.digest_each {
+            // Offsets in the input are positions in the reader
+            let mut data = vec![0; self.offset];
+            reader.seek(SeekFrom::Start(0))?;
+            reader.read_exact(&mut data)?;
+            let select = self.config.select.as_deref();
+            for node in &mut nodes {
+                digests += note_digests(node, &data, alg, select, &mut Vec::new());
             }
-            "--hex-offsets" => {
-                config.hex_values = true;
+        }
+        if let Some(range) = &self.config.range {
+            nodes = render::window(&nodes, range);
+        }
+        if let Some(range) = &self.config.highlight {
+            render::highlight(&mut nodes, range);
+        }
+        self.out
+            .render(|out| render::render(format.renderer().as_mut(), out, &nodes))?;
+        if let (0, Some(select)) = (digests, self.config.select.clone()) {
+            let msg = format!("No element matches '{}'", select);
+            writeln!(self.out, "{}", msg);
+            self.record(Severity::Warning, "no-match", None, &msg);
+        }
+        Ok(())
+    }
+
+    /// Main entry point for --grep and --grep-bytes: list the strings and
+    /// keys in each item that match, by offset and path
+    fn grep_cbor<R: Read>(&mut self, reader: &mut R) -> io::Result<()> {
+        self.skip_items(reader)?;
+        let (mut item_count, mut found) = (0, 0);
+        while self.config.first != Some(item_count) {
+            let start = self.offset;
+            let Some(item) = self.read_item(reader)? else {
+                break;
+            };
+            item_count += 1;
+            for mut node in cbor::to_nodes(&[item]) {
+                node.shift(start);
+                found += self.grep_node(&node, None, &mut Vec::new());
             }
-            "--no-decode-nested" => {
-                config.decode_nested = false;
+        }
+        if found == 0 {
+            let msg = "No strings or keys match";
+            writeln!(self.out, "{}", msg);
+            self.record(Severity::Warning, "no-match", None, msg);
+        } else {
+            writeln!(self.out, "\n{} match(es) found.", found);
+        }
+        Ok(())
+    }
+
+    /// Print the matches in `node`, which is the key of `value` if that's
+    /// given, and the items nested in it, returning how many there were
+    fn grep_node(&mut self, node: &Node, value: Option<&Node>, path: &mut Vec<String>) -> usize {
+        let matches = match &node.content {
+            Content::Text(text) => self.config.grep.as_ref().is_some_and(|r| r.is_match(text)),
+            Content::Integer(n) if value.is_some() => {
+                self.config.grep.as_ref().is_some_and(|r| r.is_match(n))
             }
-            _ => {
-                if arg.starts_with('-') {
-                    return Err(format!("Unknown option: {}", arg));
+            Content::Bytes(bytes) => self.config.grep_bytes.as_ref().is_some_and(|pattern| {
+                bytes.windows(pattern.len()).any(|window| window == pattern)
+            }),
+            _ => false,
+        };
+        let mut found = 0;
+        if matches {
+            let offset = format_offset(node.offset.unwrap_or(0), self.config.hex_values);
+            let mut shown = path.clone();
+            shown.push(match value {
+                Some(value) => format!("{}: {}", brief(node), brief(value)),
+                None => brief(node),
+            });
+            writeln!(self.out, "{}: {}", offset, shown.join(" > "));
+            found += 1;
+        }
+        if value.is_some() {
+            return found;
+        }
+        let nested = |dumper: &mut Self, path: &mut Vec<String>, name, node| {
+            path.push(name);
+            let found = dumper.grep_node(node, None, path);
+            path.pop();
+            found
+        };
+        match &node.content {
+            Content::List(nodes) => {
+                for (index, child) in nodes.iter().enumerate() {
+                    found += nested(self, path, format!("[{}]", index), child);
                 }
-                // Positional argument - input file
-                if let Some(existing) = &input_file {
-                    return Err(format!(
-                        "Multiple input files specified: {} and {}",
-                        existing, arg
-                    ));
-                } else {
-                    input_file = Some(arg.clone());
+            }
+            Content::Map(pairs) => {
+                for (index, (key, value)) in pairs.iter().enumerate() {
+                    found += self.grep_node(key, Some(value), path);
+                    found += nested(self, path, node_value_name(key, index), value);
                 }
             }
+            Content::Tagged(tag, child) => {
+                found += nested(self, path, format!("tag {}", tag), child)
+            }
+            _ => {}
         }
-        i += 1;
+        found
     }
 
-    Ok((config, input_file))
-}
+    /// Main entry point for --roundtrip: re-encode each item with preferred
+    /// serialization, or deterministic encoding with --check-canonical, and
+    /// report where the encoding first differs from the input, and whether
+    /// it still decodes to the same value
+    fn roundtrip_cbor<R: Read + Seek>(&mut self, reader: &mut R) -> io::Result<()> {
+        self.skip_items(reader)?;
+        self.inline_errors = true;
+        let encoder = CborEncoder::new().canonical(self.config.check_canonical);
+        let (mut item_count, mut identical) = (0, 0);
+        while self.config.first != Some(item_count) {
+            let start = self.offset;
+            let Some(item) = self.read_item(reader)? else {
+                break;
+            };
+            self.check_break(&item.value)?;
+            item_count += 1;
 
-fn parse_args() -> Result<(Config, Option<String>), String> {
-    let args: Vec<String> = env::args().collect();
-    parse_args_from(&args)
-}
+            // Offsets in the input are positions in the reader
+            let mut input = vec![0; self.offset - start];
+            reader.seek(SeekFrom::Start(start as u64))?;
+            reader.read_exact(&mut input)?;
+            let encoded = encoder.encode(&item.value);
+            if encoded == input {
+                identical += 1;
+                writeln!(
+                    self.out,
+                    "Item {} at offset {}: {} byte(s) re-encode identically",
+                    item_count,
+                    start,
+                    input.len()
+                );
+                continue;
+            }
 
-fn run() -> io::Result<()> {
-    let (config, filename) = match parse_args() {
-        Ok((cfg, file)) => (cfg, file),
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            eprintln!("\nUse --help for usage information");
-            std::process::exit(1);
-        }
-    };
+            let at = input
+                .iter()
+                .zip(&encoded)
+                .position(|(a, b)| a != b)
+                .unwrap_or(input.len().min(encoded.len()));
+            let mut nodes = cbor::to_nodes(std::slice::from_ref(&item));
+            nodes.iter_mut().for_each(|node| node.shift(start));
+            let path = match nodes.first() {
+                Some(node) => path_at(node, start + at),
+                None => Vec::new(),
+            };
+            let place = match path.is_empty() {
+                true => String::new(),
+                false => format!(" in {}", path.join(" > ")),
+            };
+            let msg = format!(
+                "Item {} at offset {} re-encodes differently from offset {}{}: {} in the \
+                 input, {} re-encoded",
+                item_count,
+                start,
+                start + at,
+                place,
+                excerpt(&input, at),
+                excerpt(&encoded, at)
+            );
+            self.report_error("roundtrip", start + at, &msg)?;
 
-    let filename = match filename {
-        Some(f) => f,
-        None => {
-            eprintln!("Error: No input file specified");
-            eprintln!("\nUse --help for usage information");
-            std::process::exit(1);
+            let decoded = cbor::parse_slice(&encoded).unwrap_or_default();
+            if !matches!(&decoded[..], [again] if again.value.equivalent(&item.value)) {
+                let msg = format!(
+                    "Item {} at offset {} doesn't decode to the same value once re-encoded",
+                    item_count, start
+                );
+                self.report_error("roundtrip-value", start, &msg)?;
+            }
         }
-    };
 
-    let file = File::open(&filename).map_err(|e| {
-        eprintln!("Error opening file '{}': {}", filename, e);
-        e
-    })?;
-    let mut reader = BufReader::new(file);
+        writeln!(
+            self.out,
+            "\n{} item(s) checked: {} re-encode identically, {} differently.",
+            item_count,
+            identical,
+            item_count - identical
+        );
+        write!(self.out, "{}", self.findings.totals());
+        Ok(())
+    }
 
-    let mut dumper = CborDumper::new(config);
+    /// Main entry point for --stream: print each item as it's decoded,
+    /// without building it in memory first
+    fn stream_cbor<R: Read>(&mut self, reader: &mut R) -> io::Result<()> {
+        let skipped = self.skip_items(reader)?;
+        let mut item_count = 0;
+        while self.config.first != Some(item_count) {
+            // With --follow, show each item before waiting for the next
+            if self.config.follow {
+                self.out.flush()?;
+            }
+            let Some(byte) = self.next_byte(reader)? else {
+                break;
+            };
+            if item_count > 0 {
+                writeln!(self.out);
+            }
+            self.stream_item(reader, byte, 0)?;
+            item_count += 1;
+        }
+        self.print_summary(item_count, skipped);
+        Ok(())
+    }
 
-    if dumper.config.verbose {
-        println!("Dumping CBOR file: {}", filename);
-        println!("Configuration:");
-        println!("  Print all data: {}", dumper.config.print_all_data);
-        println!("  Show hex: {}", dumper.config.print_hex);
-        println!("  Show offsets: {}", dumper.config.show_offsets);
-        println!("  Max nesting level: {}", dumper.config.max_nest_level);
-        println!("  Max bytes display: {}", dumper.config.max_bytes_display);
-        println!();
-    } else if !dumper.config.compact {
-        println!("Dumping CBOR file: {}\n", filename);
+    /// Stream an item nested in the one being read
+    fn stream_child<R: Read>(
+        &mut self,
+        reader: &mut R,
+        byte: u8,
+        level: usize,
+        name: String,
+    ) -> io::Result<Option<CborValue<'static>>> {
+        // If reading fails the item stays in the path, so the error can be
+        // placed
+        self.path.push(name);
+        let value = self.stream_item(reader, byte, level)?;
+        self.path.pop();
+        Ok(value)
     }
 
-    dumper.dump_cbor(&mut reader)
-}
+    /// Print the item whose initial byte has been read, reading the rest of
+    /// it as it's printed. Scalars and short strings are read whole and
+    /// returned, to name the map values they're keys of.
+    fn stream_item<R: Read>(
+        &mut self,
+        reader: &mut R,
+        byte: u8,
+        level: usize,
+    ) -> io::Result<Option<CborValue<'static>>> {
+        self.out.check()?;
+        if self.depth >= self.config.max_depth {
+            // The initial byte has been read
+            return Err(self.too_deep(self.offset - 1));
+        }
+        if self.config.max_nest_level < level {
+            self.print_indent(level);
+            writeln!(self.out, "<max nesting level exceeded>");
+            // The item still has to be read past
+            let out = std::mem::replace(&mut self.out, Output::sink());
+            let result = self.stream_item(reader, byte, self.config.max_nest_level);
+            self.out = out;
+            return result;
+        }
 
-fn main() {
-    match run() {
-        Ok(()) => {}
-        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+        if self.config.verbose {
+            self.print_indent(level);
+            writeln!(self.out, "({})", head_detail(byte));
         }
+        self.depth += 1;
+        let result = self.stream_content(reader, byte, self.offset - 1, level);
+        self.depth -= 1;
+        result
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn stream_content<R: Read>(
+        &mut self,
+        reader: &mut R,
+        byte: u8,
+        start: usize,
+        level: usize,
+    ) -> io::Result<Option<CborValue<'static>>> {
+        let major_type = byte >> 5;
+        let additional_info = byte & 0x1F;
+        self.check_indefinite(major_type, additional_info, start)?;
+        match major_type {
+            MAJOR_BYTES | MAJOR_TEXT if additional_info == AI_INDEFINITE => {
+                self.stream_chunks(reader, major_type, start, level)?;
+            }
+            MAJOR_BYTES | MAJOR_TEXT => {
+                let length = self.read_additional(reader, additional_info, start)?;
+                if length > STREAM_STRING_LIMIT {
+                    self.stream_string(reader, major_type, length, start, level)?;
+                } else {
+                    let value = self.read_string(reader, major_type, length, start)?;
+                    let item = CborItem::new(major_type, additional_info, value).with_head(length);
+                    self.print_item(&item, level)?;
+                    return Ok(Some(item.value));
+                }
+            }
+            MAJOR_ARRAY => {
+                let length = self.read_additional(reader, additional_info, start)?;
+                self.print_indent(level);
+                let count = (length != u64::MAX).then_some(length);
+                self.print_open("array", count, "items", '[');
+                let mut index = 0;
+                while index < length {
+                    let byte = match self.next_byte(reader)? {
+                        Some(byte) => byte,
+                        None if length == u64::MAX => {
+                            self.report_truncation(start, "array", END_BEFORE_BREAK)?;
+                            break;
+                        }
+                        None => {
+                            let detail = missing(length, index, "item");
+                            self.report_truncation(start, "array", &detail)?;
+                            break;
+                        }
+                    };
+                    if length == u64::MAX && byte == BREAK {
+                        break;
+                    }
+                    self.print_separator(index, level + 1);
+                    self.stream_child(reader, byte, level + 1, format!("[{}]", index))?;
+                    index += 1;
+                }
+                self.print_indent(level);
+                writeln!(self.out, "]");
+            }
+            MAJOR_MAP => {
+                let length = self.read_additional(reader, additional_info, start)?;
+                self.print_indent(level);
+                let count = (length != u64::MAX).then_some(length);
+                self.print_open("map", count, "pairs", '{');
+                let mut keys = self.key_order();
+                let mut index = 0;
+                while index < length {
+                    let byte = match self.next_byte(reader)? {
+                        Some(byte) => byte,
+                        None if length == u64::MAX => {
+                            self.report_truncation(start, "map", END_BEFORE_BREAK)?;
+                            break;
+                        }
+                        None => {
+                            let detail = missing(length, index, "pair");
+                            self.report_truncation(start, "map", &detail)?;
+                            break;
+                        }
+                    };
+                    if length == u64::MAX && byte == BREAK {
+                        break;
+                    }
+                    self.print_separator(index, level + 1);
+                    let key_start = self.offset - 1;
+                    let key =
+                        self.stream_child(reader, byte, level + 1, format!("key {}", index))?;
+                    // Only keys short enough to be returned can be checked
+                    if let Some(key) = &key {
+                        self.check_key_order(&mut keys, key, index as usize, key_start)?;
+                    }
+                    self.print_indent(level + 1);
+                    writeln!(self.out, "=>");
+                    let byte = match self.next_byte(reader)? {
+                        Some(byte) => byte,
+                        None => {
+                            let detail = "input ends before the value of the last key";
+                            self.report_truncation(start, "map", detail)?;
+                            break;
+                        }
+                    };
+                    let name = match &key {
+                        Some(key) => value_name(key, index as usize),
+                        None => format!("value {}", index),
+                    };
+                    self.stream_child(reader, byte, level + 1, name)?;
+                    index += 1;
+                }
+                self.print_indent(level);
+                writeln!(self.out, "}}");
+            }
+            MAJOR_TAG => {
+                let tag = self.read_additional(reader, additional_info, start)?;
+                // Noted, as when the dump isn't streamed
+                let self_described = tag == TAG_SELF_DESCRIBE && start == 0;
+                if self_described {
+                    writeln!(self.out, "{}", SELF_DESCRIBED);
+                } else {
+                    self.print_indent(level);
+                    self.print_tag_open(tag);
+                }
+                let byte = match self.next_byte(reader)? {
+                    Some(byte) => byte,
+                    None => {
+                        return Err(DumpError::truncated(
+                            start,
+                            "tag",
+                            "input ends before the tagged item",
+                            &self.path,
+                        )
+                        .into())
+                    }
+                };
+                if self_described {
+                    self.stream_child(reader, byte, level, format!("tag {}", tag))?;
+                } else {
+                    self.stream_child(reader, byte, level + 1, format!("tag {}", tag))?;
+                    self.print_indent(level);
+                    writeln!(self.out, "}}");
+                }
+            }
+            _ => {
+                let item = self.read_value(reader, byte, start)?;
+                // Break codes that end items are taken before they get here
+                self.check_break(&item.value)?;
+                self.print_item(&item, level)?;
+                return Ok(Some(item.value));
+            }
+        }
+        Ok(None)
+    }
 
-    fn args(slice: &[&str]) -> Vec<String> {
-        slice.iter().map(|s| s.to_string()).collect()
+    /// Print the comma between the elements of an array or map, before
+    /// element `index`
+    fn print_separator(&mut self, index: u64, level: usize) {
+        if index > 0 && !self.config.compact {
+            self.print_indent(level);
+            writeln!(self.out, ",");
+        }
     }
 
-    #[test]
-    fn test_parse_single_input_file() {
-        let result = parse_args_from(&args(&["dumpcbor", "input.cbor"]));
-        let (_, file) = result.expect("should succeed");
-        assert_eq!(file, Some("input.cbor".to_string()));
+    /// Stream the chunks of an indefinite-length string as its elements
+    fn stream_chunks<R: Read>(
+        &mut self,
+        reader: &mut R,
+        major_type: u8,
+        start: usize,
+        level: usize,
+    ) -> io::Result<()> {
+        let (kind, part) = if major_type == MAJOR_BYTES {
+            ("bytes", "byte string")
+        } else {
+            ("text", "text string")
+        };
+        self.print_indent(level);
+        self.print_open(kind, None, "chunks", '[');
+        let mut index = 0;
+        loop {
+            let byte = match self.next_byte(reader)? {
+                Some(byte) => byte,
+                None => {
+                    self.report_truncation(start, part, END_BEFORE_BREAK)?;
+                    break;
+                }
+            };
+            if byte == BREAK {
+                break;
+            }
+            let chunk_start = self.offset - 1;
+            if byte >> 5 != major_type {
+                let msg = format!(
+                    "Non-{} chunk in indefinite {}",
+                    part.replace(' ', "-"),
+                    part
+                );
+                self.report_error("chunk-type", chunk_start, &msg)?;
+            } else if byte & 0x1F == AI_INDEFINITE {
+                let msg = format!("Indefinite-length chunk in indefinite {}", part);
+                self.report_error("chunk-type", chunk_start, &msg)?;
+            }
+            self.print_separator(index, level + 1);
+            self.stream_child(reader, byte, level + 1, format!("chunk {}", index))?;
+            index += 1;
+        }
+        self.print_indent(level);
+        writeln!(self.out, "]");
+        Ok(())
     }
 
-    #[test]
+    /// Print a string too long to read whole, showing as much of it as the
+    /// display options ask for and passing over the rest
+    fn stream_string<R: Read>(
+        &mut self,
+        reader: &mut R,
+        major_type: u8,
+        length: u64,
+        start: usize,
+        level: usize,
+    ) -> io::Result<()> {
+        self.check_length(length, start)?;
+        let part = if major_type == MAJOR_BYTES {
+            "byte string content"
+        } else {
+            "text string content"
+        };
+        let mut content = StreamedContent {
+            start,
+            part,
+            length,
+            read: 0,
+        };
+
+        self.print_indent(level);
+        if major_type == MAJOR_TEXT {
+            return self.stream_text(reader, &mut content);
+        }
+
+        if self.config.show_types {
+            writeln!(self.out, "bytes({} bytes)", length);
+        } else {
+            writeln!(self.out, "<{} bytes>", length);
+        }
+        if self.config.print_hex {
+            self.print_indent(level);
+            write!(self.out, "  ");
+            let shown = if self.config.print_all_data {
+                length
+            } else {
+                length.min(self.config.max_bytes_display as u64)
+            };
+            let mut buffer = [0u8; STREAM_BUFFER_SIZE];
+            while content.read < shown {
+                let first = content.read;
+                let chunk = match self.read_chunk(reader, &mut content, &mut buffer, shown) {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        writeln!(self.out); // End the partly printed dump
+                        return Err(e);
+                    }
+                };
+                for (i, byte) in chunk.iter().enumerate() {
+                    let position = first + i as u64;
+                    if position > 0 && position.is_multiple_of(HEX_BYTES_PER_LINE as u64) {
+                        write!(self.out, "\n    ");
+                    }
+                    write!(self.out, "{:02X} ", byte);
+                }
+            }
+            if length > shown {
+                write!(self.out, "\n    ... ({} more bytes)", length - shown);
+            }
+            writeln!(self.out);
+        }
+        self.skip_content(reader, &mut content)
+    }
+
+    /// Print a long text string, checking that all of it is valid UTF-8
+    fn stream_text<R: Read>(
+        &mut self,
+        reader: &mut R,
+        content: &mut StreamedContent,
+    ) -> io::Result<()> {
+        let shown = if self.config.print_all_data {
+            content.length
+        } else {
+            TEXT_DISPLAY_LIMIT as u64
+        };
+        if self.config.show_types {
+            write!(self.out, "text: ");
+        }
+        write!(self.out, "\"");
+
+        let mut buffer = [0u8; STREAM_BUFFER_SIZE];
+        let mut pending = Vec::new(); // Bytes of a character split across reads
+        let mut printed = 0u64;
+        let mut invalid = None;
+        while content.read < content.length {
+            let base = content.read - pending.len() as u64;
+            let chunk = match self.read_chunk(reader, content, &mut buffer, content.length) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    writeln!(self.out); // End the partly printed string
+                    return Err(e);
+                }
+            };
+            pending.extend_from_slice(chunk);
+            let mut text = String::new();
+            let mut used = 0;
+            loop {
+                match std::str::from_utf8(&pending[used..]) {
+                    Ok(valid) => {
+                        text.push_str(valid);
+                        used = pending.len();
+                        break;
+                    }
+                    Err(e) => {
+                        let valid_up_to = used + e.valid_up_to();
+                        text.push_str(std::str::from_utf8(&pending[used..valid_up_to]).unwrap());
+                        let Some(bad) = e.error_len() else {
+                            used = valid_up_to; // Wait for the rest of the character
+                            break;
+                        };
+                        invalid.get_or_insert(format!(
+                            "invalid utf-8 sequence of {} bytes from index {}",
+                            bad,
+                            base + valid_up_to as u64
+                        ));
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        used = valid_up_to + bad;
+                    }
+                }
+            }
+            pending.drain(..used);
+
+            let room = shown.saturating_sub(printed) as usize;
+            let mut end = room.min(text.len());
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            let shown = self.shown_text(&text[..end]);
+            write!(self.out, "{}", shown);
+            printed += end as u64;
+        }
+        if !pending.is_empty() {
+            invalid.get_or_insert(format!(
+                "incomplete utf-8 byte sequence from index {}",
+                content.length - pending.len() as u64
+            ));
+        }
+
+        if printed < content.length {
+            write!(self.out, "...\"");
+            if self.config.show_types {
+                write!(self.out, " ({} chars total)", content.length);
+            }
+            writeln!(self.out);
+        } else {
+            writeln!(self.out, "\"");
+        }
+        if let Some(detail) = invalid {
+            let msg = format!("invalid UTF-8: {}", detail);
+            self.report_error("invalid-utf8", content.start, &msg)?;
+        }
+        Ok(())
+    }
+
+    /// Read the next part of a streamed string, up to `limit` bytes into it
+    fn read_chunk<'a, R: Read>(
+        &mut self,
+        reader: &mut R,
+        content: &mut StreamedContent,
+        buffer: &'a mut [u8],
+        limit: u64,
+    ) -> io::Result<&'a [u8]> {
+        let wanted = (limit - content.read).min(buffer.len() as u64) as usize;
+        let present = read_available(reader, &mut buffer[..wanted])?;
+        self.offset += present;
+        content.read += present as u64;
+        if present < wanted {
+            return Err(self.content_truncated(content));
+        }
+        Ok(&buffer[..present])
+    }
+
+    /// Pass over what's left of a streamed string
+    fn skip_content<R: Read>(
+        &mut self,
+        reader: &mut R,
+        content: &mut StreamedContent,
+    ) -> io::Result<()> {
+        let remaining = content.length - content.read;
+        let skipped = io::copy(&mut reader.take(remaining), &mut io::sink())?;
+        self.offset += skipped as usize;
+        content.read += skipped;
+        if skipped < remaining {
+            return Err(self.content_truncated(content));
+        }
+        Ok(())
+    }
+
+    fn content_truncated(&self, content: &StreamedContent) -> io::Error {
+        DumpError::truncated(
+            content.start,
+            content.part,
+            &missing(content.length, content.read, "byte"),
+            &self.path,
+        )
+        .into()
+    }
+}
+
+/// How far through a string --stream has read
+struct StreamedContent {
+    start: usize,       // Offset of the string's item
+    part: &'static str, // Name for truncation errors
+    length: u64,
+    read: u64,
+}
+
+/// Name a map value in item paths by its key, if that's a string or number
+fn value_name(key: &CborValue, index: usize) -> String {
+    match key {
+        CborValue::Text(text) => format!("{:?}", text),
+        CborValue::Unsigned(_) | CborValue::Negative(_) => {
+            key.integer().unwrap_or_default().to_string()
+        }
+        _ => format!("value {}", index),
+    }
+}
+
+/// The numerator and denominator of the array of a rational number (tag
+/// 30), with the value they make, or what's wrong with them
+fn rational_parts(value: &CborValue) -> Result<(String, String, f64), String> {
+    let CborValue::Array(items) = value else {
+        return Err("isn't an array of numerator and denominator".to_string());
+    };
+    let [numerator, denominator] = items.as_slice() else {
+        return Err(format!("has {} elements, not 2", items.len()));
+    };
+    let numerator =
+        integer_parts(&numerator.value).ok_or("has a numerator that isn't an integer")?;
+    let denominator =
+        integer_parts(&denominator.value).ok_or("has a denominator that isn't an integer")?;
+    if !denominator.2 {
+        return Err(format!(
+            "has a denominator of {}, not above 0",
+            denominator.0
+        ));
+    }
+    Ok((numerator.0, denominator.0, numerator.1 / denominator.1))
+}
+
+/// An integer or bignum as decimal text and as the nearest float, and
+/// whether it's above 0
+fn integer_parts(value: &CborValue) -> Option<(String, f64, bool)> {
+    let text = match value {
+        CborValue::Unsigned(_) | CborValue::Negative(_) => value.integer()?.to_string(),
+        CborValue::Tag(tag @ (TAG_BIGNUM_POS | TAG_BIGNUM_NEG), item) => match &item.value {
+            CborValue::Bytes(bytes) => cbor::bignum(bytes, *tag == TAG_BIGNUM_NEG),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let positive = !text.starts_with('-') && text != "0";
+    Some((text.clone(), text.parse().ok()?, positive))
+}
+
+/// The bits of a half-precision float, and whether `shown`, its value as
+/// printed, is exact or else the exact value: every finite float has a
+/// finite decimal expansion, though 0.1 as a float16 is 0.0999755859375
+fn half_detail(bits: u16, shown: &str) -> String {
+    let exp = (bits >> 10) & 0x1F;
+    if exp == 0x1F {
+        return format!("0x{:04X}", bits);
+    }
+    // The value is the significand in units of 2^-24 for subnormals and
+    // 2^(exp - 25) otherwise
+    let mant = (bits & 0x3FF) as u128;
+    let (significand, power) = if exp == 0 {
+        (mant, -24)
+    } else {
+        (mant | 0x400, exp as i32 - 25)
+    };
+    let sign = if bits & 0x8000 != 0 { "-" } else { "" };
+    let exact = if power >= 0 {
+        format!("{}{}", sign, significand << power)
+    } else {
+        // m * 2^-k is m * 5^k / 10^k
+        let places = -power as usize;
+        let digits = format!(
+            "{:0>width$}",
+            significand * 5u128.pow(places as u32),
+            width = places + 1
+        );
+        let (whole, fraction) = digits.split_at(digits.len() - places);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}.{}", sign, whole, fraction)
+        }
+    };
+    if exact == shown {
+        format!("0x{:04X}, exact", bits)
+    } else {
+        format!("0x{:04X}, exactly {}", bits, exact)
+    }
+}
+
+/// Note a float32 or float64 that preferred serialization would have
+/// written as a float16, which holds its value just as well
+fn shorter_float_note(f: f64) -> String {
+    match half_bits(f) {
+        Some(bits) => format!(" (could be float16 0x{:04X})", bits),
+        None => String::new(),
+    }
+}
+
+/// What the initial byte of an item says, for -v: its major type and
+/// additional info, and so where the argument is and how wide it was made
+fn head_detail(byte: u8) -> String {
+    let (major_type, additional_info) = (byte >> 5, byte & 0x1F);
+    let argument = match additional_info {
+        0..=23 => "argument in the initial byte",
+        AI_1BYTE => "argument in 1 following byte",
+        AI_2BYTES => "argument in 2 following bytes",
+        AI_4BYTES => "argument in 4 following bytes",
+        AI_8BYTES => "argument in 8 following bytes",
+        AI_INDEFINITE if major_type == MAJOR_SIMPLE => "break code",
+        AI_INDEFINITE => "indefinite length",
+        _ => "reserved additional info",
+    };
+    format!(
+        "initial byte 0x{:02X}: major type {}, additional info {}, {}",
+        byte, major_type, additional_info, argument
+    )
+}
+
+/// Name a map value in --grep paths by its key node, as value_name does
+fn node_value_name(key: &Node, index: usize) -> String {
+    match &key.content {
+        Content::Text(text) => format!("{:?}", text),
+        Content::Integer(n) => n.clone(),
+        _ => format!("value {}", index),
+    }
+}
+
+/// Where in `node` the input at `offset` is, as --grep paths name the
+/// elements leading to it
+fn path_at(node: &Node, offset: usize) -> Vec<String> {
+    let contains = |node: &Node| {
+        node.offset.is_some_and(|start| start <= offset)
+            && node.end().is_some_and(|end| offset < end)
+    };
+    for (name, child) in child_names(node).into_iter().zip(node.children()) {
+        if contains(child) {
+            let mut path = vec![name];
+            path.extend(path_at(child, offset));
+            return path;
+        }
+    }
+    Vec::new()
+}
+
+/// The names --grep paths give the nodes nested directly inside `node`, in
+/// the order children() lists them
+fn child_names(node: &Node) -> Vec<String> {
+    match &node.content {
+        Content::List(nodes) => (0..nodes.len()).map(|i| format!("[{}]", i)).collect(),
+        Content::Map(pairs) => pairs
+            .iter()
+            .enumerate()
+            .flat_map(|(index, (key, _))| {
+                [format!("key {}", brief(key)), node_value_name(key, index)]
+            })
+            .collect(),
+        Content::Tagged(tag, _) => vec![format!("tag {}", tag)],
+        _ => Vec::new(),
+    }
+}
+
+/// Note the `alg` digest of each element in `node`, at `path`, whose
+/// encoding `data` holds, for --digest-each: of every one, or of those
+/// picked by --select, an offset or a --grep path such as
+/// `tag 18 > [2]`, not looking inside one that's picked. Returns how many
+/// were noted
+fn note_digests(
+    node: &mut Node,
+    data: &[u8],
+    alg: HashAlg,
+    select: Option<&str>,
+    path: &mut Vec<String>,
+) -> usize {
+    let picks = |select: &str| match parse_offset(select) {
+        Some(position) => node.offset == Some(position),
+        None => select
+            .split('>')
+            .map(str::trim)
+            .eq(path.iter().map(String::as_str)),
+    };
+    if select.is_none_or(picks) {
+        let noted = render::note_digest(node, data, alg) as usize;
+        if select.is_some() {
+            return noted;
+        }
+    }
+    let mut noted = 0;
+    for (name, child) in child_names(node).into_iter().zip(node.children_mut()) {
+        path.push(name);
+        noted += note_digests(child, data, alg, select, path);
+        path.pop();
+    }
+    noted
+}
+
+/// Up to four bytes of `bytes` from `at`, for --roundtrip to show where
+/// encodings differ
+fn excerpt(bytes: &[u8], at: usize) -> String {
+    match bytes.get(at..) {
+        Some([]) | None => "nothing".to_string(),
+        Some(rest) if rest.len() > 4 => format!("{}...", hex_upper(&rest[..4], " ")),
+        Some(rest) => hex_upper(rest, " "),
+    }
+}
+
+/// A short description of a node for a --grep match: the value of a
+/// string or other scalar, or the bracket of an array or map
+fn brief(node: &Node) -> String {
+    match &node.content {
+        Content::Text(text) => render::json_string(text),
+        Content::Bytes(bytes) => format!("h'{}'", hex_lower(bytes)),
+        Content::Integer(n) => n.clone(),
+        Content::Float(f) => f.to_string(),
+        Content::Bool(b) => b.to_string(),
+        Content::Null => "null".to_string(),
+        Content::Undefined => "undefined".to_string(),
+        Content::Simple(n) => format!("simple({})", n),
+        Content::Oid { dotted, .. } => dotted.clone(),
+        Content::Other(text) => text.clone(),
+        Content::List(_) => "[...]".to_string(),
+        Content::Map(_) => "{...}".to_string(),
+        Content::Tagged(tag, _) => format!("{}(...)", tag),
+    }
+}
+
+/// A file read by --follow, which on reaching the end waits for more to be
+/// written rather than stopping, as tail -f does, if `poll` is set
+struct Follow<R> {
+    inner: R,
+    poll: bool,
+}
+
+impl<R: Read> Read for Follow<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.inner.read(buffer)? {
+                0 if self.poll && !buffer.is_empty() => thread::sleep(FOLLOW_POLL_INTERVAL),
+                count => return Ok(count),
+            }
+        }
+    }
+}
+
+impl<R: Seek> Seek for Follow<R> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(position)
+    }
+}
+
+/// A connection read like a file, which it can't go back in as --recover
+/// would (though --listen doesn't allow it)
+struct Unseekable<R>(R);
+
+impl<R: Read> Read for Unseekable<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buffer)
+    }
+}
+
+impl<R> Seek for Unseekable<R> {
+    fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Can't seek in a connection",
+        ))
+    }
+}
+
+fn print_help(program_name: &str) {
+    println!("CBOR Dumper - Rust Implementation");
+    println!("Based on the concepts from dumpasn1.c by Peter Gutmann\n");
+    println!(
+        "Usage: {} [OPTIONS] <input_file|directory|pattern>",
+        program_name
+    );
+    println!("\nDumps CBOR-encoded data (RFC 8949) in a human-readable format.");
+    println!("Given a directory (standing for every file below it) or a quoted pattern");
+    println!("such as 'certs/**/*.der', dumps each file under a header naming it and");
+    println!("ends with a table of the errors and warnings found in each.\n");
+    println!("OPTIONS:");
+    println!("  -h, --help              Show this help message and exit");
+    println!(
+        "  -a, --print-all         Print all data in long byte strings (not just first 384 bytes)"
+    );
+    println!("  -c, --compact           Compact output mode with minimal whitespace");
+    println!("  --int-hex               Show integers in hex as well as in decimal");
+    println!("  --inline                Put arrays and maps that fit on one line there, in");
+    println!("                          diagnostic notation: [1, 2, \"x\"], {{1: h'00'}}");
+    println!("  --content-format <id>   Take the input for a CoAP payload of content-format");
+    println!("                          <id>, labeling COSE messages and SenML fields");
+    println!(
+        "  -f <file>               Read input from <file> (alternative to positional argument)"
+    );
+    println!("  -O <file>               Write the dump to <file> instead of stdout");
+    println!(
+        "  --expect <file>         Compare the dump with the one in <file>, printing a unified"
+    );
+    println!("                          diff and exiting with 1 if they differ, or 0 if not");
+    println!("  -l <level>              Maximum nesting level to display (default: 100)");
+    println!("  -m <bytes>              Maximum bytes to display for byte strings (default: 384)");
+    println!("  -o, --offsets           Show byte offsets for each item");
+    println!("  -t, --no-types          Don't show type names, only values");
+    println!("  -v, --verbose           Verbose output, with the initial byte of each item taken");
+    println!("                          apart on a line before it");
+    println!("  -x, --hex               Always show hex dump for byte strings");
+    println!("  --hex-offsets           Display offsets in hexadecimal instead of decimal");
+    println!("  --lengths               Show the header and content lengths of each item in");
+    println!("                          columns, NDEF where an indefinite-length string hides it");
+    println!("  --heads                 Show the bytes of each item's head as they were encoded,");
+    println!("                          so arguments wider than they need to be stand out");
+    println!(
+        "  --pretty-json           Lay out the JSON objects and arrays found in text strings,"
+    );
+    println!("                          such as JWT payloads, indented below them");
+    println!(
+        "  --raw-strings           Print text strings as they are, without escaping newlines,"
+    );
+    println!("                          terminal escape sequences and other control characters");
+    println!("  --no-decode-nested      Don't dump the DER or CBOR that text strings hold as");
+    println!("                          base64 or PEM, or byte strings tagged 24 or 63 hold");
+    println!("  --max-item-bytes <n>    Reject strings longer than <n> bytes");
+    println!("  --check-canonical       Report the first key of each map that isn't in bytewise");
+    println!("                          order of the key encodings, and the one it should follow");
+    println!("  --roundtrip             Instead of the dump, re-encode each item with preferred");
+    println!("                          serialization (deterministic encoding with");
+    println!("                          --check-canonical) and report the first offset where");
+    println!("                          it differs from the input, and any value that changes");
+    println!("  --digest-each <alg>     Show the items as --format tree does, each element with");
+    println!("                          the sha1/sha256 digest of its encoding, or only those");
+    println!("                          chosen with --select");
+    println!("  --select <element>      Element to digest, by offset or path as --grep shows");
+    println!("                          it, such as 'tag 18 > [2]' for a COSE_Sign1 payload");
+    println!("  --validate              Check that the input is well-formed CBOR (RFC 8949");
+    println!("                          section 5) without dumping it, listing only the errors");
+    println!("                          and warnings, as asn1cbor lint does, for CI jobs");
+    println!("  --recover               After a malformed item, skip to the next plausible one");
+    println!("  --skip <n>              Pass over the first <n> top-level items unprinted");
+    println!("  --first <n>             Stop after <n> top-level items, leaving the rest unread");
+    println!("  --range <start>..<end>  Show only the elements encoded in this byte window, as");
+    println!("                          --format tree unless --format says otherwise, noting");
+    println!("                          those partly outside it (either end can be left out)");
+    println!("  --highlight <start>:<len>");
+    println!("                          Note which elements overlap these bytes, such as the");
+    println!("                          ones an error message pointed at, and which enclose");
+    println!("                          them, shown as for --range");
+    println!("  --grep <regex>          Instead of the dump, list the text strings and map keys");
+    println!("                          that match, by offset and path");
+    println!("  --grep-bytes <hex>      Likewise for the byte strings holding these bytes");
+    println!("  --auto                  Detect whether the input is ASN.1 or CBOR, bare base64");
+    println!("                          of either included, and dump it accordingly");
+    println!("  --strict                Stop at the first error");
+    println!(
+        "  --stream                Print items as they're decoded, for inputs too big for memory"
+    );
+    println!(
+        "  --follow                Stream a pipe, FIFO or growing file, printing each item as"
+    );
+    println!("                          it arrives and waiting for more at the end (as tail -f)");
+    println!(
+        "  --listen <addr:port>    Instead of a file, take TCP connections on <addr:port> and"
+    );
+    println!("                          print the items each sends as they arrive");
+    println!("  --unix <path>           The same as --listen, for a Unix socket at <path>");
+    println!(
+        "  --format <format>       Write the decoded items as {} instead of the dump",
+        Format::NAMES
+    );
+    println!(
+        "  --errors-json <file>    Write errors and warnings as JSON to <file> (- for stderr)"
+    );
+    println!("  --timing                Report the time spent parsing and rendering on stderr");
+    println!("  --color <when>          Color errors: auto, always or never");
+    println!(
+        "  --max-depth <n>         Nesting depth at which parsing stops (default: {})",
+        MAX_PARSE_DEPTH
+    );
+    println!("\nEXAMPLES:");
+    println!("  {} data.cbor", program_name);
+    println!(
+        "  {} --hex --offsets message.cbor     # Show hex and offsets",
+        program_name
+    );
+    println!(
+        "  {} -c -l 3 large.cbor               # Compact mode, max 3 levels deep",
+        program_name
+    );
+    println!(
+        "  {} --follow /tmp/telemetry.fifo     # Items from a device as they come",
+        program_name
+    );
+    println!("\nThe input file should contain binary CBOR-encoded data. Default options can");
+    println!("be set in ~/.config/asn1-cbor-tools/config.toml, or with ASN1CBOR_COLOR.");
+    println!("\nCBOR MAJOR TYPES:");
+    println!("  0: Unsigned integer       4: Array");
+    println!("  1: Negative integer       5: Map");
+    println!("  2: Byte string            6: Tagged value");
+    println!("  3: Text string (UTF-8)    7: Simple value/float");
+}
+
+fn parse_args_from(args: &[String]) -> Result<(Config, Option<String>), String> {
+    let (config, inputs) = parse_options(args)?;
+    match inputs[..] {
+        [] => Ok((config, None)),
+        [input] if config.listen.is_some() => Err(format!(
+            "--listen and --unix read connections, not an input file: {}",
+            args[input]
+        )),
+        [input] => Ok((config, Some(args[input].clone()))),
+        [first, second, ..] => Err(format!(
+            "Multiple input files specified: {} and {}",
+            args[first], args[second]
+        )),
+    }
+}
+
+/// Parse the options in `args`, returning them with the positions in `args`
+/// of the input files named
+fn parse_options(args: &[String]) -> Result<(Config, Vec<usize>), String> {
+    if args.len() < 2 {
+        return Err("No input file specified".to_string());
+    }
+
+    // Options from the config file come first, for those given to override
+    let (with_defaults, defaults) = cli::with_defaults("dump-cbor", args);
+    let args = &with_defaults[..];
+    let mut config = Config::default();
+    let mut inputs = Vec::new();
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = &args[i];
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print_help(&args[0]);
+                std::process::exit(0);
+            }
+            "-a" | "--print-all" => {
+                config.print_all_data = true;
+            }
+            "-c" | "--compact" => {
+                config.compact = true;
+            }
+            "-f" => {
+                option_value(args, &mut i)?;
+                inputs.push(i);
+            }
+            "-O" => {
+                config.output_file = Some(option_value(args, &mut i)?.to_string());
+            }
+            "--expect" => {
+                config.expect = Some(option_value(args, &mut i)?.to_string());
+            }
+            "-l" | "--max-level" => {
+                config.max_nest_level = parse_number(option_value(args, &mut i)?, "max level")?;
+            }
+            "-m" | "--max-bytes" => {
+                config.max_bytes_display = parse_number(option_value(args, &mut i)?, "max bytes")?;
+            }
+            "-o" | "--offsets" => {
+                config.show_offsets = true;
+            }
+            "-t" | "--no-types" => {
+                config.show_types = false;
+            }
+            "-v" | "--verbose" => {
+                config.verbose = true;
+            }
+            "-x" | "--hex" => {
+                config.print_hex = true;
+            }
+            "--hex-offsets" => {
+                config.hex_values = true;
+            }
+            "--lengths" => {
+                config.show_lengths = true;
+            }
+            "--heads" => {
+                config.show_heads = true;
+            }
+            "--pretty-json" => {
+                config.pretty_json = true;
+            }
+            "--inline" => {
+                config.inline = true;
+            }
+            "--int-hex" => {
+                config.int_hex = true;
+            }
+            "--raw-strings" => {
+                config.raw_strings = true;
+            }
+            "--check-canonical" => {
+                config.check_canonical = true;
+            }
+            "--roundtrip" => {
+                config.roundtrip = true;
+            }
+            "--digest-each" => {
+                let value = option_value(args, &mut i)?;
+                config.digest_each = Some(HashAlg::from_name(value)?);
+            }
+            "--select" => {
+                config.select = Some(option_value(args, &mut i)?.to_string());
+            }
+            "--validate" => {
+                config.validate = true;
+            }
+            "--no-decode-nested" => {
+                config.decode_nested = false;
+            }
+            "--recover" => {
+                config.recover = true;
+            }
+            "--auto" => {
+                config.auto = true;
+            }
+            "--strict" => {
+                config.strict = true;
+            }
+            "--stream" => {
+                config.stream = true;
+            }
+            "--follow" => {
+                config.stream = true;
+                config.follow = true;
+            }
+            "--listen" => {
+                config.listen = Some(Listen::Tcp(option_value(args, &mut i)?.to_string()));
+            }
+            "--unix" => {
+                config.listen = Some(Listen::Unix(option_value(args, &mut i)?.to_string()));
+            }
+            "--format" => {
+                let value = option_value(args, &mut i)?;
+                config.format =
+                    Some(Format::from_name(value).ok_or_else(|| {
+                        format!("Unknown format: {} (use {})", value, Format::NAMES)
+                    })?);
+            }
+            "--timing" => {
+                config.timing = true;
+            }
+            "--color" => {
+                let value = option_value(args, &mut i)?;
+                config.color = ColorChoice::from_name(value).ok_or_else(|| {
+                    format!(
+                        "Unknown color choice: {} (use {})",
+                        value,
+                        ColorChoice::NAMES
+                    )
+                })?;
+            }
+            "--errors-json" => {
+                config.errors_json = Some(option_value(args, &mut i)?.to_string());
+            }
+            "--max-depth" => {
+                config.max_depth = parse_number(option_value(args, &mut i)?, "max depth")?;
+            }
+            "--content-format" => {
+                let id = parse_number(option_value(args, &mut i)?, "content-format")?;
+                let format = coap::content_format(id)
+                    .ok_or_else(|| format!("Unknown CoAP content-format: {}", id))?;
+                if format.payload == Payload::NotCbor {
+                    return Err(format!(
+                        "Content-format {} ({}) isn't CBOR",
+                        id, format.media_type
+                    ));
+                }
+                config.content_format = Some(format);
+            }
+            "--max-item-bytes" => {
+                config.max_item_bytes =
+                    Some(parse_number(option_value(args, &mut i)?, "max item bytes")?);
+            }
+            "--skip" => {
+                config.skip = parse_number(option_value(args, &mut i)?, "skip")?;
+            }
+            "--first" => {
+                config.first = Some(parse_number(option_value(args, &mut i)?, "first")?);
+            }
+            "--range" => {
+                config.range = Some(parse_range(option_value(args, &mut i)?)?);
+            }
+            "--highlight" => {
+                config.highlight = Some(parse_span(option_value(args, &mut i)?)?);
+            }
+            "--grep" => {
+                config.grep = Some(Regex::new(option_value(args, &mut i)?)?);
+            }
+            "--grep-bytes" => {
+                let value = option_value(args, &mut i)?;
+                let bytes = parse_hex(value).filter(|bytes| !bytes.is_empty());
+                config.grep_bytes =
+                    Some(bytes.ok_or_else(|| format!("Invalid hex for --grep-bytes: {}", value))?);
+            }
+            _ => {
+                if arg.starts_with('-') {
+                    return Err(format!("Unknown option: {}", arg));
+                }
+                // Positional argument - input file
+                inputs.push(i);
+            }
+        }
+        i += 1;
+    }
+
+    if config.validate
+        && (config.format.is_some()
+            || config.grep.is_some()
+            || config.grep_bytes.is_some()
+            || config.expect.is_some()
+            || config.output_file.is_some()
+            || config.listen.is_some())
+    {
+        return Err(
+            "--validate prints no dump, so can't be used with --format, --grep, --grep-bytes, \
+             --expect, -O, --listen or --unix"
+                .to_string(),
+        );
+    }
+    if config.roundtrip
+        && (config.validate
+            || config.format.is_some()
+            || config.grep.is_some()
+            || config.grep_bytes.is_some()
+            || config.range.is_some()
+            || config.highlight.is_some()
+            || config.stream
+            || config.recover
+            || config.listen.is_some())
+    {
+        return Err(
+            "--roundtrip can't be used with --validate, --format, --grep, --grep-bytes, \
+             --range, --highlight, --stream, --follow, --recover, --listen or --unix"
+                .to_string(),
+        );
+    }
+    if config.expect.is_some() && config.output_file.is_some() {
+        return Err("--expect and -O can't be used together".to_string());
+    }
+    if config.strict && config.recover {
+        return Err("--strict and --recover can't be used together".to_string());
+    }
+    if config.first == Some(0) {
+        return Err("--first must be at least 1".to_string());
+    }
+    if (config.grep.is_some() || config.grep_bytes.is_some())
+        && (config.format.is_some()
+            || config.range.is_some()
+            || config.highlight.is_some()
+            || config.stream
+            || config.follow
+            || config.recover
+            || config.listen.is_some())
+    {
+        return Err(
+            "--grep and --grep-bytes can't be used with --format, --range, --highlight, \
+             --stream, --follow, --recover, --listen or --unix"
+                .to_string(),
+        );
+    }
+    if config.digest_each.is_some() {
+        if config.validate
+            || config.roundtrip
+            || config.grep.is_some()
+            || config.grep_bytes.is_some()
+            || config.stream
+            || config.recover
+            || config.listen.is_some()
+        {
+            return Err(
+                "--digest-each can't be used with --validate, --roundtrip, --grep, \
+                 --grep-bytes, --stream, --follow, --recover, --listen or --unix"
+                    .to_string(),
+            );
+        }
+        // The digests are noted in the decoded tree
+        config.format.get_or_insert(Format::Tree);
+    }
+    if config.range.is_some() || config.highlight.is_some() {
+        if config.stream || config.follow || config.recover || config.listen.is_some() {
+            return Err(
+                "--range and --highlight can't be used with --stream, --follow, \
+                 --recover, --listen or --unix"
+                    .to_string(),
+            );
+        }
+        // The elements are picked out of the decoded items, so they're shown
+        // as a tree
+        config.format.get_or_insert(Format::Tree);
+    }
+    if config.listen.is_some() {
+        if config.recover || config.format.is_some() || config.auto {
+            return Err(
+                "--listen and --unix can't be used with --recover, --format or --auto".to_string(),
+            );
+        }
+        // Each connection is streamed as --follow streams a pipe
+        config.stream = true;
+        config.follow = true;
+    }
+    if config.follow && config.expect.is_some() {
+        return Err("--expect can't be used with --follow, --listen or --unix".to_string());
+    }
+    if config.follow && (config.recover || config.format.is_some()) {
+        return Err("--follow can't be used with --recover or --format".to_string());
+    }
+    if config.stream && config.recover {
+        return Err("--stream and --recover can't be used together".to_string());
+    }
+    if config.stream && config.show_lengths {
+        // Streamed items are printed before their content has been read
+        return Err(
+            "--lengths can't be used with --stream, --follow, --listen or --unix".to_string(),
+        );
+    }
+    if config.stream && config.show_heads {
+        // Nor are streamed arrays, maps and tags kept as items
+        return Err(
+            "--heads can't be used with --stream, --follow, --listen or --unix".to_string(),
+        );
+    }
+    if config.stream && config.inline {
+        return Err(
+            "--inline can't be used with --stream, --follow, --listen or --unix".to_string(),
+        );
+    }
+    if config.stream && config.content_format.is_some() {
+        return Err(
+            "--content-format can't be used with --stream, --follow, --listen or --unix"
+                .to_string(),
+        );
+    }
+    if config.format.is_some() && (config.stream || config.recover) {
+        return Err("--format can't be used with --stream or --recover".to_string());
+    }
+    if let Some(&input) = inputs.iter().find(|&&input| input <= defaults) {
+        return Err(format!(
+            "The config file's options for dump-cbor name an input file: {}",
+            args[input]
+        ));
+    }
+
+    // Where the inputs are in the arguments as given
+    let inputs = inputs.iter().map(|input| input - defaults).collect();
+    Ok((config, inputs))
+}
+
+/// Run dumpcbor with `args`, the first of which is the program name shown in
+/// the help, returning the exit status
+pub fn run(args: &[String]) -> io::Result<i32> {
+    // --validate is lint's, which takes directories and patterns itself
+    if !args.iter().any(|arg| arg == "--validate") {
+        if let Some(status) = cli::dump_expanded::<CborDumper>(args, Decoder::Cbor) {
+            return status;
+        }
+    }
+    run_input(args, None)
+}
+
+/// Run dumpcbor with `args` on `input` if given, as the content of the file
+/// they name, or otherwise on the file itself
+pub fn run_input(args: &[String], input: Option<Vec<u8>>) -> io::Result<i32> {
+    let (config, filename) = parse_args_from(args).unwrap_or_else(|e| cli::usage_error(&e));
+    if filename.is_none() && config.listen.is_none() {
+        cli::usage_error("No input file specified");
+    }
+    if config.validate {
+        return cli::validate(args);
+    }
+    if config.auto {
+        return cli::dump_auto(args);
+    }
+
+    let mut dumper = CborDumper::new(config);
+    if let (Some(expected), Some(filename)) = (dumper.config.expect.clone(), &filename) {
+        return dumper::check_expected(&mut dumper, filename, input, &expected);
+    }
+    if let Some(path) = &dumper.config.output_file {
+        let file =
+            File::create(path).map_err(|e| DumpError::io(format!("Can't create '{}'", path), e))?;
+        dumper.out = Output::new(Box::new(BufWriter::new(file)));
+    }
+    let colors = match dumper.config.output_file {
+        Some(_) => dumper.config.color.enabled(false),
+        None => dumper.config.color.for_stdout(),
+    };
+    dumper.out.set_colors(colors);
+    match (filename, dumper.config.listen.clone()) {
+        (Some(filename), _) => dumper.dump_file(&filename, input)?,
+        (None, Some(listen)) => dumper.dump_connections(&listen)?,
+        (None, None) => unreachable!("checked above"),
+    }
+    Ok(dumper.findings.exit_status())
+}
+
+impl Dumper for CborDumper {
+    fn from_args(args: &[String]) -> Result<(Self, Option<String>), String> {
+        let (config, filename) = if args.len() > 1 {
+            parse_args_from(args)?
+        } else {
+            (Config::default(), None)
+        };
+        Ok((CborDumper::new(config), filename))
+    }
+
+    fn input_positions(args: &[String]) -> Result<Vec<usize>, String> {
+        Ok(parse_options(args)?.1)
+    }
+
+    fn output(&mut self) -> &mut Output {
+        &mut self.out
+    }
+
+    fn color(&self) -> ColorChoice {
+        self.config.color
+    }
+
+    fn findings(&mut self) -> &mut Findings {
+        &mut self.findings
+    }
+
+    fn report_errors_inline(&mut self) {
+        self.inline_errors = true;
+    }
+
+    fn dump_file(&mut self, filename: &str, input: Option<Vec<u8>>) -> io::Result<()> {
+        CborDumper::dump_file(self, filename, input)
+    }
+
+    fn dump_bytes(&mut self, data: &[u8]) -> io::Result<()> {
+        self.dump_input(None, &mut Cursor::new(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::Captured;
+
+    fn args(slice: &[&str]) -> Vec<String> {
+        slice.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Dump `data` as dumpcbor would with `config`, returning the dump and
+    /// the errors and warnings found, a line each as asn1cbor lint prints
+    /// them less the file name, with any error that stopped the dump last
+    fn dump_with(config: Config, data: &[u8]) -> (String, String) {
+        let captured = Captured::default();
+        let mut dumper = CborDumper::new(config);
+        dumper.out = captured.output();
+        // An error that stops the dump is recorded with the others
+        let _ = dumper.dump_input(None, &mut io::Cursor::new(data));
+        let findings = dumper
+            .findings
+            .diagnostics
+            .iter()
+            .map(|d| {
+                let offset = d
+                    .offset
+                    .map_or(String::new(), |offset| format!("{}: ", offset));
+                let severity = d.severity.as_str();
+                format!("{}{}: {} [{}]\n", offset, severity, d.message, d.code)
+            })
+            .collect();
+        (captured.text(), findings)
+    }
+
+    #[test]
+    fn test_parse_single_input_file() {
+        let result = parse_args_from(&args(&["dumpcbor", "input.cbor"]));
+        let (_, file) = result.expect("should succeed");
+        assert_eq!(file, Some("input.cbor".to_string()));
+    }
+
+    #[test]
     fn test_parse_multiple_input_files_errors() {
         let result = parse_args_from(&args(&["dumpcbor", "first.cbor", "second.cbor"]));
         let err = result.expect_err("should fail with multiple files");
@@ -921,4 +3277,1166 @@ mod tests {
         let err = result.expect_err("should fail on unknown option");
         assert!(err.contains("Unknown option"), "unexpected error: {err}");
     }
+
+    #[test]
+    fn test_truncation_error_path() {
+        // {"a": [1, h'0102'] with the last byte missing
+        let data = vec![0xA1, 0x61, b'a', 0x82, 0x01, 0x42, 0x01];
+        let mut dumper = CborDumper::new(Config::default());
+        let err = dumper.read_item(&mut io::Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Truncated byte string content at offset 5: 1 more byte expected (1 of 2 present), \
+             in \"a\" > [1]"
+        );
+        // Kept for placing the error in --errors-json output
+        assert_eq!(dumper.path, ["\"a\"", "[1]"]);
+        assert_eq!(DumpError::from(err).path(), dumper.path);
+
+        // Over --max-item-bytes
+        let config = Config {
+            max_item_bytes: Some(1),
+            ..Config::default()
+        };
+        let data = vec![0x81, 0x42, 0x01, 0x02];
+        let err = CborDumper::new(config)
+            .read_item(&mut io::Cursor::new(data))
+            .unwrap_err();
+        match DumpError::from(err) {
+            DumpError::TooLong {
+                offset,
+                path,
+                length,
+                limit,
+            } => {
+                assert_eq!((offset, length, limit), (Some(1), 2, 1));
+                assert_eq!(path, ["[0]"]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_dump_output() {
+        // {"a": [1, -2]}
+        let data = vec![0xA1, 0x61, b'a', 0x82, 0x01, 0x21];
+        let (text, findings) = dump_with(Config::default(), &data);
+        assert_eq!(findings, "");
+        assert_eq!(
+            text,
+            "map(1 pairs) {\n  text: \"a\"\n  =>\n  array(2 items) [\n    unsigned(1)\n    ,\n    \
+             negative(-2)\n  ]\n}\n\nParsing complete. 1 item(s) found.\n"
+        );
+    }
+
+    #[test]
+    fn test_lengths() {
+        // {"a": [_ 1000]}
+        let data = vec![0xA1, 0x61, b'a', 0x9F, 0x19, 0x03, 0xE8, 0xFF];
+        let config = Config {
+            show_lengths: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(config, &data).0,
+            " 1    7: map(1 pairs) {\n\
+             \x201    1:   text: \"a\"\n\
+             \x20      :   =>\n\
+             \x201    4:   array(1 items) [\n\
+             \x203    0:     unsigned(1000)\n\
+             \x20      :   ]\n\
+             \x20      : }\n\
+             \nParsing complete. 1 item(s) found.\n"
+        );
+        assert!(parse_args_from(&args(&["prog", "--lengths", "--stream", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_self_described() {
+        // 55799([1]), 55799(2)
+        let data = vec![0xD9, 0xD9, 0xF7, 0x81, 0x01, 0xD9, 0xD9, 0xF7, 0x02];
+        let expected = "Self-described CBOR (tag 55799)\n\
+                        array(1 items) [\n\
+                        \x20 unsigned(1)\n\
+                        ]\n\
+                        \n\
+                        tag 55799 (self-describe CBOR) {\n\
+                        \x20 unsigned(2)\n\
+                        }\n\
+                        \nParsing complete. 2 item(s) found.\n";
+        for stream in [false, true] {
+            let config = Config {
+                stream,
+                ..Config::default()
+            };
+            assert_eq!(
+                dump_with(config, &data),
+                (expected.to_string(), String::new())
+            );
+        }
+    }
+
+    #[test]
+    fn test_embedded_json() {
+        // ["{\"a\":[1,true]}", "{not json"]
+        let mut data = vec![0x82, 0x6E];
+        data.extend_from_slice(br#"{"a":[1,true]}"#);
+        data.push(0x69);
+        data.extend_from_slice(b"{not json");
+        let dump = |pretty_json| {
+            let config = Config {
+                pretty_json,
+                ..Config::default()
+            };
+            dump_with(config, &data).0
+        };
+        assert_eq!(
+            dump(true),
+            "array(2 items) [\n\
+             \x20 text: \"{\"a\":[1,true]}\"\n\
+             \x20   (embedded JSON)\n\
+             \x20   {\n\
+             \x20     \"a\": [\n\
+             \x20       1,\n\
+             \x20       true\n\
+             \x20     ]\n\
+             \x20   }\n\
+             \x20 ,\n\
+             \x20 text: \"{not json\"\n\
+             ]\n\
+             \nParsing complete. 1 item(s) found.\n"
+        );
+        assert!(dump(false).contains("  (embedded JSON)\n  ,\n"));
+    }
+
+    #[test]
+    fn test_embedded_encoding() {
+        // ["MAMCAQU=", "oQE="]: base64 of SEQUENCE { INTEGER 5 } and {1: 1},
+        // the second too short to be taken for base64
+        let mut data = vec![0x82, 0x68];
+        data.extend_from_slice(b"MAMCAQU=");
+        data.push(0x64);
+        data.extend_from_slice(b"oQE=");
+        let dump = |decode_nested| {
+            let config = Config {
+                decode_nested,
+                ..Config::default()
+            };
+            dump_with(config, &data).0
+        };
+        assert_eq!(
+            dump(true),
+            "array(2 items) [\n\
+             \x20 text: \"MAMCAQU=\"\n\
+             \x20   (base64-encoded DER)\n\
+             \x20       0    3: SEQUENCE {\n\
+             \x20       2    1:   INTEGER 5\n\
+             \x20             : }\n\
+             \x20 ,\n\
+             \x20 text: \"oQE=\"\n\
+             ]\n\
+             \nParsing complete. 1 item(s) found.\n"
+        );
+        assert!(!dump(false).contains("DER"));
+
+        // {1: "oQGCAgM="}, the string base64 of {1: [2, 3]}
+        let mut data = vec![0xA1, 0x01, 0x68];
+        data.extend_from_slice(b"oQGCAgM=");
+        assert!(dump_with(Config::default(), &data).0.contains(
+            "  text: \"oQGCAgM=\"\n\
+             \x20   (base64-encoded CBOR)\n\
+             \x20   map(1 pairs) {\n"
+        ));
+    }
+
+    #[test]
+    fn test_encoded_cbor() {
+        // 63(h'016161'), the sequence 1, "a", and 24(h'18'), cut short
+        let data = [0xD8, 0x3F, 0x43, 0x01, 0x61, 0x61, 0xD8, 0x18, 0x41, 0x18];
+        let dump = |decode_nested| {
+            let config = Config {
+                decode_nested,
+                ..Config::default()
+            };
+            dump_with(config, &data).0
+        };
+        let text = dump(true);
+        assert!(text.starts_with(
+            "tag 63 (encoded CBOR sequence) {\n\
+             \x20 bytes(3 bytes)\n\
+             \x20   01 61 61 \n\
+             \x20   (encoded CBOR sequence of 2 item(s))\n\
+             \x20   unsigned(1)\n\
+             \x20   ,\n\
+             \x20   text: \"a\"\n\
+             }\n"
+        ));
+        assert!(text.contains("    (not well-formed CBOR: Truncated argument"));
+        assert!(!dump(false).contains("encoded CBOR sequence of"));
+    }
+
+    #[test]
+    fn test_expected_conversion() {
+        // 21(h'FBFF01'), 22(h'FBFF01') and 23(h'ABCD')
+        let data = [
+            0xD5, 0x43, 0xFB, 0xFF, 0x01, 0xD6, 0x43, 0xFB, 0xFF, 0x01, 0xD7, 0x42, 0xAB, 0xCD,
+        ];
+        let (text, _) = dump_with(Config::default(), &data);
+        assert!(text.contains("    FB FF 01 \n    (in JSON as base64url: \"-_8B\")\n"));
+        assert!(text.contains("    (in JSON as base64: \"+/8B\")\n"));
+        assert!(text.contains("    (in JSON as base16: \"abcd\")\n"));
+    }
+
+    #[test]
+    fn test_mime_preview() {
+        let text = "MIME-Version: 1.0\r\nContent-Type: text/plain\r\n\r\na\r\nb\r\nc\r\nd\r\n";
+        let mut data = vec![0xD8, 0x24, 0x78, text.len() as u8];
+        data.extend_from_slice(text.as_bytes());
+        let dump = |print_all_data| {
+            let config = Config {
+                print_all_data,
+                ..Config::default()
+            };
+            dump_with(config, &data).0
+        };
+        assert!(dump(false).starts_with(
+            "tag 36 (MIME message) {\n\
+             \x20 text: MIME message of 59 bytes\n\
+             \x20   Content-Type: text/plain\n\
+             \x20   Content-Transfer-Encoding: 7bit\n\
+             \x20   (1 other header field(s))\n\
+             \x20   body (4 line(s)):\n\
+             \x20     a\n\
+             \x20     b\n\
+             \x20     c\n\
+             \x20     ... (1 more line(s))\n\
+             }\n"
+        ));
+        let all = dump(true);
+        assert!(all.contains("    MIME-Version: 1.0\n"));
+        assert!(all.contains("      d\n}\n"));
+    }
+
+    #[test]
+    fn test_regex_tag() {
+        // 35("^a+$") and 35("(a")
+        let data = [
+            0xD8, 0x23, 0x64, b'^', b'a', b'+', b'$', 0xD8, 0x23, 0x62, b'(', b'a',
+        ];
+        let dump = |verbose| {
+            let config = Config {
+                verbose,
+                ..Config::default()
+            };
+            dump_with(config, &data)
+        };
+        let (text, findings) = dump(false);
+        assert!(!text.contains("anchored"));
+        assert!(
+            text.contains("  text: \"(a\"\n    (not a valid regular expression: unclosed '(')\n")
+        );
+        assert_eq!(
+            findings,
+            "7: warning: Tag 35 text at offset 7 isn't a valid regular expression: \
+             unclosed '(' [regex]\n"
+        );
+
+        let (text, _) = dump(true);
+        assert!(text.contains("    (regular expression: anchored at both ends, 1 quantifier(s))\n"));
+    }
+
+    #[test]
+    fn test_rational() {
+        // 30([1, 3]), 30([-1, 2(h'0100')]) and 30([1, 0])
+        let data = [
+            0xD8, 0x1E, 0x82, 0x01, 0x03, 0xD8, 0x1E, 0x82, 0x20, 0xC2, 0x42, 0x01, 0x00, 0xD8,
+            0x1E, 0x82, 0x01, 0x00,
+        ];
+        let (text, findings) = dump_with(Config::default(), &data);
+        assert!(text.contains("]\n    (1/3, about 0.3333333333333333)\n}\n"));
+        assert!(text.contains("]\n    (-1/256, about -0.00390625)\n}\n"));
+        assert!(text.contains(
+            "]\n    (not a rational number: it has a denominator of 0, not above 0)\n}\n"
+        ));
+        assert_eq!(
+            findings,
+            "13: warning: Tag 30 content at offset 13 has a denominator of 0, not above 0 \
+             [rational]\n"
+        );
+        assert_eq!(
+            rational_parts(&CborValue::Array(vec![])),
+            Err("has 0 elements, not 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_content_format() {
+        let dump = |id: &str, data: &[u8]| {
+            let (config, _) =
+                parse_args_from(&args(&["dumpcbor", "--content-format", id, "x"])).unwrap();
+            dump_with(config, data)
+        };
+
+        // 18([h'A10126', {}, null, h'00'])
+        let sign1 = [0xD2, 0x84, 0x43, 0xA1, 0x01, 0x26, 0xA0, 0xF6, 0x41, 0x00];
+        let (text, findings) = dump("18", &sign1);
+        assert!(text.starts_with(
+            "CoAP content-format 18: application/cose; cose-type=\"cose-sign1\"\n\n\
+             tag 18 (COSE_Sign1) {\n\
+             \x20 array(4 items) [\n\
+             \x20   protected: bytes(3 bytes)\n"
+        ));
+        assert!(text.contains("    unprotected: map(0 pairs) {\n"));
+        assert!(text.contains("    payload: null\n"));
+        assert!(text.contains("    signature: bytes(1 bytes)\n"));
+        assert_eq!(findings, "");
+
+        let (text, findings) = dump("17", &sign1);
+        assert!(text.contains(
+            "[Payload at offset 0 isn't the COSE_Mac0 of content-format 17: \
+             tag 18 isn't COSE_Mac0's tag 17]\n"
+        ));
+        assert!(findings.ends_with(" [content-format]\n"), "{}", findings);
+
+        // [{-2: "a", 2: 1}], a SenML pack of one record
+        let senml = [0x81, 0xA2, 0x21, 0x61, b'a', 0x02, 0x01];
+        let (text, _) = dump("112", &senml);
+        assert!(text.contains("    bn: text: \"a\"\n"));
+        assert!(text.contains("    v: unsigned(1)\n"));
+
+        let parse = |id| parse_args_from(&args(&["dumpcbor", "--content-format", id, "x"]));
+        assert_eq!(
+            parse("50").unwrap_err(),
+            "Content-format 50 (application/json) isn't CBOR"
+        );
+        assert_eq!(parse("1").unwrap_err(), "Unknown CoAP content-format: 1");
+        assert!(parse_args_from(&args(&[
+            "dumpcbor",
+            "--content-format",
+            "60",
+            "--stream",
+            "x"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_cose_signatures() {
+        // 98([h'A10126', {}, h'00', [[h'A10126', {4: h'31'}, h'00'],
+        //     [h'A10127', {7: [h'', {}, h'01']}, h'02']]])
+        let data = [
+            0xD8, 0x62, 0x84, 0x43, 0xA1, 0x01, 0x26, 0xA0, 0x41, 0x00, 0x82, 0x83, 0x43, 0xA1,
+            0x01, 0x26, 0xA1, 0x04, 0x41, 0x31, 0x41, 0x00, 0x83, 0x43, 0xA1, 0x01, 0x27, 0xA1,
+            0x07, 0x83, 0x40, 0xA0, 0x41, 0x01, 0x41, 0x02,
+        ];
+        let (text, _) = dump_with(Config::default(), &data);
+        assert!(text.contains("tag 98 (COSE_Sign) {\n"));
+        assert!(text.contains("    signatures: array(2 items) [\n"));
+        assert_eq!(
+            text.matches("      COSE_Signature: array(3 items) [\n")
+                .count(),
+            2
+        );
+        assert!(text.contains("      ]\n        (signature 1 of 2)\n"));
+        assert!(text.contains("      ]\n        (signature 2 of 2)\n"));
+        // Each signer's protected header is decoded on its own
+        assert!(text.contains(
+            "        protected: bytes(3 bytes)\n\
+             \x20         A1 01 27 \n\
+             \x20         (encoded CBOR)\n\
+             \x20         map(1 pairs) {\n\
+             \x20           unsigned(1)\n\
+             \x20           =>\n\
+             \x20           alg: negative(-8)\n"
+        ));
+        assert!(text.contains("          kid: bytes(1 bytes)\n"));
+        assert!(text.contains("          counter signature: array(3 items) [\n"));
+        assert!(text.contains("            signature: bytes(1 bytes)\n              01 \n"));
+    }
+
+    #[test]
+    fn test_cose_recipients() {
+        // 96([h'A10101', {5: h'00'}, h'AA', [[h'A101381C',
+        //     {-1: {1: 2, -1: 1, -2: h'01', -3: h'02'}, 4: h'6B'}, h'BB',
+        //     [[h'', {1: -5}, h'CC']]]]])
+        let data = [
+            0xD8, 0x60, 0x84, 0x43, 0xA1, 0x01, 0x01, 0xA1, 0x05, 0x41, 0x00, 0x41, 0xAA, 0x81,
+            0x84, 0x44, 0xA1, 0x01, 0x38, 0x1C, 0xA2, 0x20, 0xA4, 0x01, 0x02, 0x20, 0x01, 0x21,
+            0x41, 0x01, 0x22, 0x41, 0x02, 0x04, 0x41, 0x6B, 0x41, 0xBB, 0x81, 0x83, 0x40, 0xA1,
+            0x01, 0x24, 0x41, 0xCC,
+        ];
+        let (text, _) = dump_with(Config::default(), &data);
+        assert!(text.contains("    recipients: array(1 items) [\n"));
+        assert!(text.contains("      COSE_recipient: array(4 items) [\n"));
+        assert!(text.contains("            alg: negative(-29)\n"));
+        assert!(text.contains(
+            "          ephemeral key: map(4 pairs) {\n\
+             \x20           unsigned(1)\n\
+             \x20           =>\n\
+             \x20           kty: unsigned(2)\n"
+        ));
+        assert!(text.contains("            crv: unsigned(1)\n"));
+        assert!(text.contains("            y: bytes(1 bytes)\n"));
+        assert!(text.contains("          kid: bytes(1 bytes)\n"));
+        // The recipient's own recipients, a layer down
+        assert!(text.contains("          COSE_recipient: array(3 items) [\n"));
+        assert!(text.contains("              alg: negative(-5)\n"));
+    }
+
+    #[test]
+    fn test_cose_registered_names() {
+        // 18([h'A10126', {15: {1: "a", 8: {1: {1: 2, -1: 1, -2: h'01', -3: h'02'}}}},
+        //     null, h'00']), with a CWT Claims header confirming an EC2 key
+        let data = [
+            0xD2, 0x84, 0x43, 0xA1, 0x01, 0x26, 0xA1, 0x0F, 0xA2, 0x01, 0x61, b'a', 0x08, 0xA1,
+            0x01, 0xA4, 0x01, 0x02, 0x20, 0x01, 0x21, 0x41, 0x01, 0x22, 0x41, 0x02, 0xF6, 0x41,
+            0x00,
+        ];
+        let (text, _) = dump_with(Config::default(), &data);
+        assert!(text.contains("        alg: negative(-7)\n          (ES256)\n"));
+        assert!(text.contains("      CWT Claims: map(2 pairs) {\n"));
+        assert!(text.contains("        iss: text: \"a\"\n"));
+        assert!(text.contains("          COSE_Key: map(4 pairs) {\n"));
+        assert!(text.contains("            kty: unsigned(2)\n              (EC2)\n"));
+        assert!(text.contains("            crv: unsigned(1)\n              (P-256)\n"));
+
+        // [{1: 1, 3: -8, -1: 6}], a key set of content-format 102
+        let (config, _) =
+            parse_args_from(&args(&["dumpcbor", "--content-format", "102", "x"])).unwrap();
+        let data = [0x81, 0xA3, 0x01, 0x01, 0x03, 0x27, 0x20, 0x06];
+        let (text, _) = dump_with(config, &data);
+        assert!(text.contains("COSE_KeySet: array(1 items) [\n  COSE_Key: map(3 pairs) {\n"));
+        assert!(text.contains("    alg: negative(-8)\n      (EdDSA)\n"));
+        assert!(text.contains("    crv: unsigned(6)\n      (Ed25519)\n"));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        // 1, then [1, 5] with 5 in two bytes, then {"b": 0, "a": 0}
+        let data = vec![
+            0x01, 0x82, 0x01, 0x18, 0x05, 0xA2, 0x61, b'b', 0x00, 0x61, b'a', 0x00,
+        ];
+        for (check_canonical, expected) in [
+            (
+                false,
+                "Item 1 at offset 0: 1 byte(s) re-encode identically\n\
+                 Error: Item 2 at offset 1 re-encodes differently from offset 3 in [1]: \
+                 18 05 in the input, 05 re-encoded\n\
+                 Item 3 at offset 5: 7 byte(s) re-encode identically\n\
+                 \n3 item(s) checked: 2 re-encode identically, 1 differently.\n",
+            ),
+            (
+                true,
+                "Item 1 at offset 0: 1 byte(s) re-encode identically\n\
+                 Error: Item 2 at offset 1 re-encodes differently from offset 3 in [1]: \
+                 18 05 in the input, 05 re-encoded\n\
+                 Error: Map key \"a\" at offset 9 is out of canonical order, following \
+                 \"b\" where it should come first\n\
+                 Error: Item 3 at offset 5 re-encodes differently from offset 7 in \
+                 key \"b\": 62 00 61 61... in the input, 61 00 61 62... re-encoded\n\
+                 \n3 item(s) checked: 1 re-encode identically, 2 differently.\n",
+            ),
+        ] {
+            let config = Config {
+                roundtrip: true,
+                check_canonical,
+                ..Config::default()
+            };
+            let (text, findings) = dump_with(config, &data);
+            assert!(text.starts_with(expected), "{}", text);
+            assert!(findings
+                .lines()
+                .all(|line| line.ends_with(" [roundtrip]") || line.ends_with(" [key-order]")));
+        }
+
+        let parse = |extra: &str| parse_args_from(&args(&["prog", "--roundtrip", extra, "a"]));
+        assert!(parse("--check-canonical").unwrap().0.roundtrip);
+        assert!(parse("--stream").is_err());
+        assert!(parse("--validate").is_err());
+    }
+
+    #[test]
+    fn test_check_canonical() {
+        // {1: 0, "a": 0, 2: 0, "a": 0}, and [{-1: 0, 1: 0}]: 1 sorts first
+        let data = vec![
+            0xA4, 0x01, 0x00, 0x61, b'a', 0x00, 0x02, 0x00, 0x61, b'a', 0x00, 0x81, 0xA2, 0x20,
+            0x00, 0x01, 0x00,
+        ];
+        for stream in [false, true] {
+            let config = Config {
+                check_canonical: true,
+                stream,
+                ..Config::default()
+            };
+            assert_eq!(
+                dump_with(config, &data).1,
+                "6: error: Map key 2 at offset 6 is out of canonical order, following \"a\" \
+                 where it should follow 1 at offset 1 [key-order]\n\
+                 15: error: Map key 1 at offset 15 is out of canonical order, following -1 \
+                 where it should come first [key-order]\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_raw_strings() {
+        // "a\n\x1b[2Jb"
+        let data = vec![0x67, b'a', b'\n', 0x1B, b'[', b'2', b'J', b'b'];
+        let dump = |raw_strings| {
+            let config = Config {
+                raw_strings,
+                ..Config::default()
+            };
+            dump_with(config, &data).0
+        };
+        assert!(dump(false).starts_with("text: \"a\\n\\u001b[2Jb\"\n"));
+        assert!(dump(true).starts_with("text: \"a\n\x1b[2Jb\"\n"));
+    }
+
+    #[test]
+    fn test_heads() {
+        // [24, 1000] with the 24 given two bytes it doesn't need
+        let data = vec![0x82, 0x19, 0x00, 0x18, 0x19, 0x03, 0xE8];
+        let config = Config {
+            show_heads: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(config, &data).0,
+            "<82> array(2 items) [\n\
+             \x20 <19 00 18> unsigned(24)\n\
+             \x20 ,\n\
+             \x20 <19 03 E8> unsigned(1000)\n\
+             ]\n\
+             \nParsing complete. 1 item(s) found.\n"
+        );
+        assert!(parse_args_from(&args(&["prog", "--heads", "--stream", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_int_hex() {
+        // [1000, -7], as in a COSE key's parameters
+        let data = vec![0x82, 0x19, 0x03, 0xE8, 0x26];
+        let config = Config {
+            int_hex: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(config, &data).0,
+            "array(2 items) [\n\
+             \x20 unsigned(1000 / 0x3E8)\n\
+             \x20 ,\n\
+             \x20 negative(-7 / -0x7)\n\
+             ]\n\
+             \nParsing complete. 1 item(s) found.\n"
+        );
+        assert!(
+            parse_args_from(&args(&["prog", "--int-hex", "a"]))
+                .unwrap()
+                .0
+                .int_hex
+        );
+    }
+
+    #[test]
+    fn test_negative_range() {
+        // -2^63, -2^64 + 1 and -2^64, past what an i64 holds
+        let data = parse_hex("833b7fffffffffffffff3bfffffffffffffffe3bffffffffffffffff").unwrap();
+        let config = Config {
+            int_hex: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(config, &data).0,
+            "array(3 items) [\n\
+             \x20 negative(-9223372036854775808 / -0x8000000000000000)\n\
+             \x20 ,\n\
+             \x20 negative(-18446744073709551615 / -0xFFFFFFFFFFFFFFFF)\n\
+             \x20 ,\n\
+             \x20 negative(-18446744073709551616 / -0x10000000000000000)\n\
+             ]\n\
+             \nParsing complete. 1 item(s) found.\n"
+        );
+        let items = cbor::parse_slice(&data).unwrap();
+        assert_eq!(
+            render::diag_notation(&items[0].to_node(0)),
+            "[-9223372036854775808, -18446744073709551615, -18446744073709551616]"
+        );
+    }
+
+    #[test]
+    fn test_inline() {
+        let dump = |data: Vec<u8>| {
+            let config = Config {
+                inline: true,
+                ..Config::default()
+            };
+            dump_with(config, &data).0
+        };
+        // [[1, 2, "x"], {1: h'00'}]
+        let small = vec![0x82, 0x83, 0x01, 0x02, 0x61, b'x', 0xA1, 0x01, 0x41, 0x00];
+        assert_eq!(
+            dump(small),
+            "[[1, 2, \"x\"], {1: h'00'}]\n\nParsing complete. 1 item(s) found.\n"
+        );
+
+        // Forty 100s don't fit on a line, but the map after them does
+        let mut wide = vec![0x82, 0x98, 40];
+        for _ in 0..40 {
+            wide.extend([0x18, 0x64]);
+        }
+        wide.extend([0xA1, 0x01, 0x41, 0x00]);
+        let text = dump(wide);
+        assert!(text.starts_with("array(2 items) [\n  array(40 items) [\n    unsigned(100)\n"));
+        assert!(text.contains("\n  {1: h'00'}\n"));
+
+        assert!(parse_args_from(&args(&["prog", "--inline", "--follow", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_simple_values() {
+        // [simple(16), simple(24) in two bytes, simple(32)]
+        let data = vec![0x83, 0xF0, 0xF8, 0x18, 0xF8, 0x20];
+        let (text, findings) = dump_with(Config::default(), &data);
+        assert!(text.starts_with(
+            "array(3 items) [\n\
+             \x20 simple(16) (unassigned)\n\
+             \x20 ,\n\
+             \x20 simple(24) (reserved)\n\
+             \x20 ,\n\
+             \x20 simple(32) (unassigned)\n\
+             ]\n"
+        ));
+        assert_eq!(
+            findings,
+            "2: error: Simple value 24 at offset 2 is in the two-byte form, which is only \
+             for values from 32 up [simple-value]\n"
+        );
+
+        let config = Config {
+            strict: true,
+            ..Config::default()
+        };
+        // Stopped at the error, which is the only one
+        let (text, strict_findings) = dump_with(config, &data);
+        assert!(!text.contains("Parsing complete"), "{}", text);
+        assert_eq!(strict_findings, findings);
+    }
+
+    #[test]
+    fn test_stray_break() {
+        // A break at the top level, one in a definite-length array, and
+        // one ending an indefinite-length array, which is fine
+        let data = vec![0xFF, 0x82, 0x01, 0xFF, 0x9F, 0xFF];
+        for stream in [false, true] {
+            let config = Config {
+                stream,
+                ..Config::default()
+            };
+            assert_eq!(
+                dump_with(config, &data).1,
+                "0: error: Break code at offset 0 outside an indefinite-length item \
+                 [unexpected-break]\n\
+                 3: error: Break code at offset 3 outside an indefinite-length item \
+                 [unexpected-break]\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nested_indefinite_chunk() {
+        // (_ (_ h'00'))
+        let data = vec![0x5F, 0x5F, 0x41, 0x00, 0xFF, 0xFF];
+        for stream in [false, true] {
+            let config = Config {
+                stream,
+                ..Config::default()
+            };
+            assert_eq!(
+                dump_with(config, &data).1,
+                "1: error: Indefinite-length chunk in indefinite byte string [chunk-type]\n"
+            );
+        }
+
+        // An indefinite-length text string whose first chunk, h'00', is bytes
+        let data = [0x7F, 0x41, 0x00, 0x61, b'a', 0xFF];
+        for stream in [false, true] {
+            let config = Config {
+                validate: true,
+                stream,
+                ..Config::default()
+            };
+            assert_eq!(
+                dump_with(config, &data).1,
+                "1: error: Non-text-string chunk in indefinite text string [chunk-type]\n"
+            );
+        }
+
+        let (config, _) = parse_args_from(&args(&["prog", "--validate", "a"])).unwrap();
+        assert!(config.validate);
+        assert!(parse_args_from(&args(&["prog", "--validate", "--format", "json", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_floats() {
+        // [1.5, 0.1 as float16, float32 1.5, float64 1.1]
+        let data = vec![
+            0x84, 0xF9, 0x3E, 0x00, 0xF9, 0x2E, 0x66, 0xFA, 0x3F, 0xC0, 0x00, 0x00, 0xFB, 0x3F,
+            0xF1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9A,
+        ];
+        assert_eq!(
+            dump_with(Config::default(), &data).0,
+            "array(4 items) [\n\
+             \x20 float16: 1.5 (0x3E00, exact)\n\
+             \x20 ,\n\
+             \x20 float16: 0.099975586 (0x2E66, exactly 0.0999755859375)\n\
+             \x20 ,\n\
+             \x20 float32: 1.5 (could be float16 0x3E00)\n\
+             \x20 ,\n\
+             \x20 float64: 1.1\n\
+             ]\n\
+             \nParsing complete. 1 item(s) found.\n"
+        );
+    }
+
+    #[test]
+    fn test_half_detail() {
+        assert_eq!(half_detail(0x7C00, "inf"), "0x7C00");
+        assert_eq!(half_detail(0xFBFF, "-65504"), "0xFBFF, exact");
+        assert_eq!(half_detail(0x8000, "-0"), "0x8000, exact");
+        assert_eq!(
+            half_detail(0x0001, "0.000000059604645"),
+            "0x0001, exactly 0.000000059604644775390625"
+        );
+    }
+
+    #[test]
+    fn test_head_detail() {
+        assert_eq!(
+            head_detail(0x82),
+            "initial byte 0x82: major type 4, additional info 2, argument in the initial byte"
+        );
+        assert_eq!(
+            head_detail(0x19),
+            "initial byte 0x19: major type 0, additional info 25, argument in 2 following bytes"
+        );
+        assert_eq!(
+            head_detail(0x5F),
+            "initial byte 0x5F: major type 2, additional info 31, indefinite length"
+        );
+        assert_eq!(
+            head_detail(0xFF),
+            "initial byte 0xFF: major type 7, additional info 31, break code"
+        );
+    }
+
+    #[test]
+    fn test_skip_and_first() {
+        // 1, [2, 3], 4, 5
+        let data = vec![0x01, 0x82, 0x02, 0x03, 0x04, 0x05];
+        for stream in [false, true] {
+            let config = Config {
+                skip: 1,
+                first: Some(2),
+                stream,
+                ..Config::default()
+            };
+            let (text, _) = dump_with(config.clone(), &data);
+            assert!(!text.contains("unsigned(1)") && !text.contains("unsigned(5)"));
+            assert!(text.contains("unsigned(3)") && text.contains("unsigned(4)"));
+            assert!(text.contains("1 item(s) skipped") && text.contains("Stopped after"));
+
+            // The last item is never read
+            let mut dumper = CborDumper::new(config);
+            dumper.out = Output::sink();
+            let mut reader = io::Cursor::new(data.clone());
+            dumper.dump_input(None, &mut reader).unwrap();
+            assert_eq!(reader.position(), 5);
+        }
+        assert!(parse_args_from(&args(&["dumpcbor", "--first", "0", "x.cbor"])).is_err());
+    }
+
+    #[test]
+    fn test_render_output() {
+        // {"a": [1, -2]}
+        let data = vec![0xA1, 0x61, b'a', 0x82, 0x01, 0x21];
+        let config = Config {
+            format: Some(Format::Diag),
+            ..Config::default()
+        };
+        assert_eq!(dump_with(config, &data).0, "{\"a\": [1, -2]}\n");
+
+        let (config, _) = parse_args_from(&args(&["prog", "--format", "html", "a"])).unwrap();
+        assert_eq!(config.format, Some(Format::Html));
+        let err = parse_args_from(&args(&["prog", "--format", "xml", "a"])).unwrap_err();
+        assert_eq!(err, "Unknown format: xml (use tree, diag, json or html)");
+        assert!(parse_args_from(&args(&["prog", "--format", "diag", "--stream", "a"])).is_err());
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn test_digest_each() {
+        // 18([h'A10126', {}, h'01020304', h'00']), a COSE_Sign1
+        let data = vec![
+            0xD2, 0x84, 0x43, 0xA1, 0x01, 0x26, 0xA0, 0x44, 0x01, 0x02, 0x03, 0x04, 0x41, 0x00,
+        ];
+        let sha256 = |bytes: &[u8]| hex_lower(&HashAlg::Sha256.digest(bytes));
+        let render = |select: Option<&str>| {
+            let config = Config {
+                digest_each: Some(HashAlg::Sha256),
+                select: select.map(str::to_string),
+                format: Some(Format::Json),
+                ..Config::default()
+            };
+            dump_with(config, &data).0
+        };
+        let text = render(None);
+        for (start, end) in [(0, 14), (1, 14), (2, 6), (6, 7), (7, 12), (12, 14)] {
+            assert!(
+                text.contains(&sha256(&data[start..end])),
+                "{}..{}",
+                start,
+                end
+            );
+        }
+        let payload = format!("SHA-256: {}", sha256(&data[7..12]));
+        for select in ["tag 18 > [2]", "tag 18>[2]", "7"] {
+            let text = render(Some(select));
+            assert_eq!(text.matches("SHA-256").count(), 1, "{}", select);
+            assert!(text.contains(&payload), "{}", select);
+        }
+        assert!(render(Some("[2]")).ends_with("\nNo element matches '[2]'\n"));
+
+        let (config, _) =
+            parse_args_from(&args(&["prog", "--digest-each", "sha256", "a"])).unwrap();
+        assert_eq!(config.format, Some(Format::Tree));
+        let streamed = ["prog", "--digest-each", "sha256", "--stream", "a"];
+        assert!(parse_args_from(&args(&streamed)).is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        // 0, then {"a": [1, -2]} from offset 1
+        let data = vec![0x00, 0xA1, 0x61, b'a', 0x82, 0x01, 0x21];
+        let config = Config {
+            skip: 1,
+            range: Some(5..7),
+            format: Some(Format::Tree),
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(config, &data).0,
+            "    1    5: map {\n\
+             \x20         :   partly outside the range: bytes 1..7\n\
+             \x20   2    1:   text \"a\"\n\
+             \x20         :     outside the range\n\
+             \x20   4    2:   => array {\n\
+             \x20         :     partly outside the range: bytes 4..7\n\
+             \x20   5    0:     unsigned 1\n\
+             \x20   6    0:     negative -2\n\
+             \x20         :   }\n\
+             \x20         : }\n"
+        );
+
+        let (config, _) = parse_args_from(&args(&["prog", "--range", "0x10..", "a"])).unwrap();
+        assert_eq!(config.range, Some(16..usize::MAX));
+        assert_eq!(config.format, Some(Format::Tree));
+        assert!(parse_args_from(&args(&["prog", "--range", "4..2", "a"])).is_err());
+        assert!(parse_args_from(&args(&["prog", "--range", "..4", "--stream", "a"])).is_err());
+        let (config, _) = parse_args_from(&args(&["prog", "--highlight", "5:2", "a"])).unwrap();
+        assert_eq!(
+            (config.highlight, config.format),
+            (Some(5..7), Some(Format::Tree))
+        );
+        assert!(parse_args_from(&args(&["prog", "--highlight", "5:2", "--follow", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_grep() {
+        // 0, then {"name": "Alice", "id": [h'DEAD01']} from offset 1
+        let mut data = vec![0x00, 0xA2, 0x64];
+        data.extend_from_slice(b"name");
+        data.push(0x65);
+        data.extend_from_slice(b"Alice");
+        data.extend_from_slice(&[0x62, b'i', b'd', 0x81, 0x43, 0xDE, 0xAD, 0x01]);
+        let config = Config {
+            grep: Some(Regex::new("^(?:na|Al)").unwrap()),
+            grep_bytes: Some(vec![0xAD, 0x01]),
+            ..Config::default()
+        };
+        assert_eq!(
+            dump_with(config, &data).0,
+            "   2: \"name\": \"Alice\"\n\
+             \x20  7: \"name\" > \"Alice\"\n\
+             \x20 17: \"id\" > [0] > h'dead01'\n\
+             \n3 match(es) found.\n"
+        );
+
+        let config = Config {
+            grep: Some(Regex::new("Bob").unwrap()),
+            ..Config::default()
+        };
+        let (text, findings) = dump_with(config, &[0x61, b'a']);
+        assert_eq!(text, "No strings or keys match\n");
+        assert!(findings.ends_with(" [no-match]\n"), "{}", findings);
+
+        assert!(parse_args_from(&args(&["prog", "--grep", "(", "a"])).is_err());
+        assert!(parse_args_from(&args(&["prog", "--grep-bytes", "0g", "a"])).is_err());
+        assert!(parse_args_from(&args(&["prog", "--grep", "a", "--stream", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_record_diagnostics() {
+        // ["a", (_ h'01', "b")] with a text chunk in the indefinite byte string
+        let data = vec![0x82, 0x61, b'a', 0x5F, 0x41, 0x01, 0x61, b'b', 0xFF];
+        let mut dumper = CborDumper::new(Config::default());
+        dumper.read_item(&mut io::Cursor::new(data)).unwrap();
+        assert_eq!(dumper.findings.errors, 1);
+        let diagnostic = &dumper.findings.diagnostics[0];
+        assert_eq!(diagnostic.code, "chunk-type");
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.offset, Some(6));
+        assert_eq!(diagnostic.path, ["[1]"]);
+    }
+
+    #[test]
+    fn test_strict_stops_at_first_error() {
+        // [h'01' with the array cut short
+        let data = vec![0x82, 0x41, 0x01];
+        let mut dumper = CborDumper::new(Config::default());
+        assert!(dumper.read_item(&mut io::Cursor::new(data.clone())).is_ok());
+        assert_eq!(dumper.findings.errors, 1);
+
+        let config = Config {
+            strict: true,
+            ..Config::default()
+        };
+        let mut dumper = CborDumper::new(config);
+        let err = dumper.read_item(&mut io::Cursor::new(data)).unwrap_err();
+        assert!(err.to_string().starts_with("Truncated array"), "{err}");
+        assert_eq!(dumper.findings.diagnostics[0].code, "truncated");
+
+        assert!(parse_args_from(&args(&["dumpcbor", "--strict", "--recover", "x.cbor"])).is_err());
+    }
+
+    #[test]
+    fn test_find_plausible_item() {
+        let dumper = CborDumper::new(Config::default());
+        // Stray bytes, an array cut short, then a complete map
+        let data = [0xFF, 0x01, 0x83, 0x01, 0xA1, 0x01, 0x02];
+        assert_eq!(dumper.find_plausible_item(&data), Some(4));
+        assert_eq!(dumper.find_plausible_item(&[0x01, 0x02, 0x83]), None);
+    }
+
+    #[test]
+    fn test_read_item_depth_limit() {
+        let config = Config {
+            max_depth: 3,
+            ..Config::default()
+        };
+        let mut dumper = CborDumper::new(config);
+        // [[1]] fits in three levels, [[[1]]] doesn't
+        assert!(dumper
+            .read_item(&mut io::Cursor::new(vec![0x81, 0x81, 0x01]))
+            .is_ok());
+        let err = dumper
+            .read_item(&mut io::Cursor::new(vec![0x81, 0x81, 0x81, 0x01]))
+            .unwrap_err();
+        assert!(err.to_string().contains("Nesting too deep"), "{err}");
+        assert_eq!(dumper.depth, 0);
+    }
+
+    #[test]
+    fn test_read_item_length_limits() {
+        // Byte string claiming 4 GB, with one byte of content
+        let mut dumper = CborDumper::new(Config::default());
+        let mut input = io::Cursor::new(vec![0x5A, 0xFF, 0xFF, 0xFF, 0xFF, 0x00]);
+        let err = dumper.read_item(&mut input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Truncated byte string content at offset 0: 4294967294 more bytes expected \
+             (1 of 4294967295 present)"
+        );
+
+        let (config, _) =
+            parse_args_from(&args(&["dumpcbor", "--max-item-bytes", "2", "a.cbor"])).unwrap();
+        let mut dumper = CborDumper::new(config);
+        let mut input = io::Cursor::new(vec![0x63, b'a', b'b', b'c']);
+        assert!(dumper.read_item(&mut input).is_err());
+        let mut input = io::Cursor::new(vec![0x62, b'a', b'b']);
+        assert!(dumper.read_item(&mut input).is_ok());
+    }
+
+    /// Options for --stream, otherwise the defaults
+    fn streamed() -> Config {
+        Config {
+            stream: true,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_stream_matches_dump() {
+        // {"a": [1, -2]}
+        let data = vec![0xA1, 0x61, b'a', 0x82, 0x01, 0x21];
+        for config in [
+            Config::default(),
+            Config {
+                compact: true,
+                show_types: false,
+                ..Config::default()
+            },
+        ] {
+            let stream = Config {
+                stream: true,
+                ..config.clone()
+            };
+            assert_eq!(dump_with(stream, &data), dump_with(config, &data));
+        }
+    }
+
+    /// Input arriving a piece at a time, with nothing yet where a piece is
+    /// empty, and then closed
+    struct Arrivals(Vec<Vec<u8>>);
+
+    impl Read for Arrivals {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::other("closed"));
+            }
+            let count = self.0[0].len().min(buffer.len());
+            buffer[..count].copy_from_slice(&self.0[0][..count]);
+            self.0[0].drain(..count);
+            if count == 0 || self.0[0].is_empty() {
+                self.0.remove(0);
+            }
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn test_follow_waits_for_more() {
+        // [1, 2] and "abc", written in pieces with pauses between them
+        let pieces = [&[0x82, 0x01][..], &[], &[0x02, 0x63, b'a'], &[], b"bc"];
+        let config = Config {
+            stream: true,
+            follow: true,
+            ..Config::default()
+        };
+        let captured = Captured::default();
+        let mut dumper = CborDumper::new(config.clone());
+        dumper.out = captured.output();
+        let mut reader = Follow {
+            inner: Arrivals(pieces.iter().map(|piece| piece.to_vec()).collect()),
+            poll: true,
+        };
+        let result = dumper.stream_cbor(&mut reader);
+        assert_eq!(result.unwrap_err().to_string(), "closed");
+        let (streamed, _) = dump_with(config, &pieces.concat());
+        let text = captured.text();
+        assert!(text.contains("\"abc\""), "{}", text);
+        assert!(streamed.starts_with(&text), "{}", text);
+    }
+
+    #[test]
+    fn test_listen_options() {
+        let (config, input) = parse_args_from(&args(&["prog", "--unix", "/tmp/s"])).unwrap();
+        assert_eq!(config.listen, Some(Listen::Unix("/tmp/s".to_string())));
+        assert!(config.stream && config.follow && input.is_none());
+        assert!(parse_args_from(&args(&["prog", "--listen", ":4000", "a.cbor"])).is_err());
+        assert!(parse_args_from(&args(&["prog", "--listen", ":4000", "--recover"])).is_err());
+    }
+
+    #[test]
+    fn test_dump_connection() {
+        let captured = Captured::default();
+        let mut dumper = CborDumper::new(Config {
+            stream: true,
+            follow: true,
+            ..Config::default()
+        });
+        dumper.out = captured.output();
+        // 1, then a reserved additional info value, which ends the connection
+        let result = dumper.dump_connection("from 127.0.0.1:4000", &[0x01, 0x1C][..]);
+        assert!(result.is_ok());
+        assert_eq!(
+            captured.text(),
+            "Connection from 127.0.0.1:4000\n\n\
+             unsigned(1)\n\n\
+             \nError: Invalid additional info\n\
+             \nConnection from 127.0.0.1:4000 closed\n\n"
+        );
+        assert_eq!(dumper.findings.errors, 1);
+    }
+
+    #[test]
+    fn test_stream_indefinite_length() {
+        // [_ h'01', (_ "b", "c")], whose lengths aren't known until the end
+        let data = vec![0x9F, 0x41, 0x01, 0x7F, 0x61, b'b', 0x61, b'c', 0xFF, 0xFF];
+        let (text, findings) = dump_with(streamed(), &data);
+        assert_eq!(findings, "");
+        assert_eq!(
+            text,
+            "array(indefinite length) [\n  bytes(1 bytes)\n    01 \n  ,\n  \
+             text(indefinite length) [\n    text: \"b\"\n    ,\n    text: \"c\"\n  ]\n]\n\n\
+             Parsing complete. 1 item(s) found.\n"
+        );
+    }
+
+    #[test]
+    fn test_long_text_cut_at_char_boundary() {
+        // An "é" straddles the display limit, so the text stops short of it
+        let text = format!("{}é{}", "a".repeat(TEXT_DISPLAY_LIMIT - 1), "b".repeat(10));
+        let mut data = vec![0x78, text.len() as u8];
+        data.extend_from_slice(text.as_bytes());
+        let shown = "a".repeat(TEXT_DISPLAY_LIMIT - 1);
+        let dump = dumper::dump_to_string::<CborDumper>(&data, &[]).unwrap();
+        assert!(dump.contains(&format!("\"{}...\"", shown)), "{}", dump);
+    }
+
+    #[test]
+    fn test_stream_long_strings() {
+        // A text string too long to read whole, then a truncated byte string
+        let length = STREAM_STRING_LIMIT as usize + 1;
+        let mut data = vec![0x7A];
+        data.extend_from_slice(&(length as u32).to_be_bytes());
+        data.extend(std::iter::repeat_n(b'x', length - 2));
+        data.extend_from_slice("é".as_bytes());
+        data.push(0x5A);
+        data.extend_from_slice(&(length as u32).to_be_bytes());
+        data.extend_from_slice(&[0x01; 100]);
+
+        let (text, findings) = dump_with(streamed(), &data);
+        let shown = "x".repeat(TEXT_DISPLAY_LIMIT);
+        assert!(text.starts_with(&format!(
+            "text: \"{}...\" ({} chars total)\n",
+            shown, length
+        )));
+        assert!(text.ends_with("\nbytes(65537 bytes)\n"));
+        assert!(findings.ends_with(" [truncated]\n"), "{}", findings);
+
+        // A character split across reads isn't taken for invalid UTF-8,
+        // but a broken one is
+        let end = 5 + length;
+        assert_eq!(dump_with(streamed(), &data[..end]).1, "");
+        data[end - 1] = b'x';
+        let (_, findings) = dump_with(streamed(), &data[..end]);
+        assert!(findings.ends_with(" [invalid-utf8]\n"), "{}", findings);
+    }
+
+    #[test]
+    fn test_directory_input() {
+        let dir = std::env::temp_dir().join(format!("dumpcbor-inputs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.cbor"), [0x83, 0x01, 0x02, 0x03]).unwrap();
+        // An array short of its second item
+        std::fs::write(dir.join("sub").join("b.cbor"), [0x82, 0x01]).unwrap();
+        let dir_name = dir.display().to_string();
+        let all = run(&args(&["dumpcbor", &dir_name]));
+        let pattern = format!("{}/*.cbor", dir_name);
+        let matched = run(&args(&["dumpcbor", &pattern]));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(all.unwrap(), diagnostics::EXIT_ERRORS);
+        assert_eq!(matched.unwrap(), diagnostics::EXIT_CLEAN);
+    }
 }
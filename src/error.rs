@@ -0,0 +1,237 @@
+// Errors that stop a parse or a dump. The dumpers read through io::Read, so
+// inside the library a DumpError travels wrapped in an io::Error whose kind
+// matches it: UnexpectedEof for truncation, InvalidData for other problems
+// with the input. The public functions unwrap it again.
+
+use std::fmt;
+use std::io;
+
+/// Why a parse or dump stopped
+#[derive(Debug)]
+pub enum DumpError {
+    /// The input ends partway through an item
+    Truncated {
+        offset: Option<usize>,
+        path: Vec<String>,
+        message: String,
+    },
+    /// An item isn't validly encoded
+    Malformed {
+        offset: Option<usize>,
+        path: Vec<String>,
+        message: String,
+    },
+    /// Items nest more than `limit` levels deep
+    TooDeep {
+        offset: Option<usize>,
+        path: Vec<String>,
+        limit: usize,
+    },
+    /// An item is longer than the `--max-item-bytes` limit
+    TooLong {
+        offset: Option<usize>,
+        path: Vec<String>,
+        length: u64,
+        limit: usize,
+    },
+    /// Reading the input or writing the dump failed. `context` says what was
+    /// being done, e.g. "Can't open 'cert.der'".
+    Io {
+        context: Option<String>,
+        source: io::Error,
+    },
+}
+
+impl DumpError {
+    /// Input ending partway through the `part` of the item at `offset`,
+    /// which the items named in `path` enclose
+    pub(crate) fn truncated(offset: usize, part: &str, detail: &str, path: &[String]) -> Self {
+        let mut message = format!("Truncated {} at offset {}: {}", part, offset, detail);
+        if !path.is_empty() {
+            message.push_str(&format!(", in {}", path.join(" > ")));
+        }
+        DumpError::Truncated {
+            offset: Some(offset),
+            path: path.to_vec(),
+            message,
+        }
+    }
+
+    /// A malformed item, `offset` giving where it starts if that's known
+    pub(crate) fn malformed(
+        offset: Option<usize>,
+        path: &[String],
+        message: impl Into<String>,
+    ) -> Self {
+        DumpError::Malformed {
+            offset,
+            path: path.to_vec(),
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn too_deep(offset: Option<usize>, path: &[String], limit: usize) -> Self {
+        DumpError::TooDeep {
+            offset,
+            path: path.to_vec(),
+            limit,
+        }
+    }
+
+    pub(crate) fn too_long(offset: usize, path: &[String], length: u64, limit: usize) -> Self {
+        DumpError::TooLong {
+            offset: Some(offset),
+            path: path.to_vec(),
+            length,
+            limit,
+        }
+    }
+
+    /// A failed I/O operation, described by `context`
+    pub(crate) fn io(context: impl Into<String>, source: io::Error) -> Self {
+        DumpError::Io {
+            context: Some(context.into()),
+            source,
+        }
+    }
+
+    /// Where in the input the item concerned starts, if known
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            DumpError::Truncated { offset, .. }
+            | DumpError::Malformed { offset, .. }
+            | DumpError::TooDeep { offset, .. }
+            | DumpError::TooLong { offset, .. } => *offset,
+            DumpError::Io { .. } => None,
+        }
+    }
+
+    /// Names of the items enclosing the one concerned, outermost first
+    pub fn path(&self) -> &[String] {
+        match self {
+            DumpError::Truncated { path, .. }
+            | DumpError::Malformed { path, .. }
+            | DumpError::TooDeep { path, .. }
+            | DumpError::TooLong { path, .. } => path,
+            DumpError::Io { .. } => &[],
+        }
+    }
+
+    /// Whether the input ends too soon, so that more of it might parse
+    pub fn is_truncated(&self) -> bool {
+        matches!(self, DumpError::Truncated { .. })
+    }
+
+    fn io_kind(&self) -> io::ErrorKind {
+        match self {
+            DumpError::Truncated { .. } => io::ErrorKind::UnexpectedEof,
+            DumpError::Io { source, .. } => source.kind(),
+            _ => io::ErrorKind::InvalidData,
+        }
+    }
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DumpError::Truncated { message, .. } | DumpError::Malformed { message, .. } => {
+                f.write_str(message)
+            }
+            DumpError::TooDeep { limit, .. } => {
+                write!(f, "Nesting too deep (more than {} levels)", limit)
+            }
+            DumpError::TooLong { length, limit, .. } => write!(
+                f,
+                "Length {} exceeds the --max-item-bytes limit of {}",
+                length, limit
+            ),
+            DumpError::Io {
+                context: Some(context),
+                source,
+            } => write!(f, "{}: {}", context, source),
+            DumpError::Io { source, .. } => source.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DumpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DumpError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<DumpError> for io::Error {
+    fn from(e: DumpError) -> Self {
+        match e {
+            DumpError::Io {
+                context: None,
+                source,
+            } => source,
+            e => io::Error::new(e.io_kind(), e),
+        }
+    }
+}
+
+/// Recover the DumpError an io::Error carries, or classify one raised by
+/// the reader itself from its kind
+impl From<io::Error> for DumpError {
+    fn from(e: io::Error) -> Self {
+        let e = match e.downcast::<DumpError>() {
+            Ok(inner) => return inner,
+            Err(e) => e,
+        };
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => DumpError::Truncated {
+                offset: None,
+                path: Vec::new(),
+                message: e.to_string(),
+            },
+            io::ErrorKind::InvalidData => DumpError::malformed(None, &[], e.to_string()),
+            _ => DumpError::Io {
+                context: None,
+                source: e,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncated() {
+        let path = ["Certificate".to_string(), "tbsCertificate".to_string()];
+        let e = DumpError::truncated(4, "INTEGER content", "2 more bytes expected", &path);
+        assert_eq!(
+            e.to_string(),
+            "Truncated INTEGER content at offset 4: 2 more bytes expected, \
+             in Certificate > tbsCertificate"
+        );
+        assert_eq!(e.offset(), Some(4));
+        assert_eq!(e.path(), path);
+        assert!(e.is_truncated());
+    }
+
+    #[test]
+    fn test_io_error_round_trip() {
+        let e: io::Error = DumpError::too_deep(Some(9), &[], 3).into();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), "Nesting too deep (more than 3 levels)");
+        match DumpError::from(e) {
+            DumpError::TooDeep { offset, limit, .. } => assert_eq!((offset, limit), (Some(9), 3)),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Errors from the reader are classified by kind
+        let eof = io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer");
+        assert!(DumpError::from(eof).is_truncated());
+        let denied = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        let e = DumpError::io("Can't open 'x.der'", denied);
+        assert_eq!(e.to_string(), "Can't open 'x.der': denied");
+        assert_eq!(io::Error::from(e).kind(), io::ErrorKind::PermissionDenied);
+    }
+}
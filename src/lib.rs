@@ -0,0 +1,29 @@
+// ASN.1/CBOR tools library
+// The CBOR data model and parsers behind dumpcbor, for programs that want to
+// decode CBOR themselves rather than read a dump, a DER builder for
+// constructing ASN.1 test data, and the renderers behind --format.
+// try_parse_cbor() and try_parse_asn1() are the entry points for untrusted
+// input. The dumpers themselves live here too, run by the binaries through
+// cli, and can dump input held in memory to a string.
+
+pub mod cbor;
+#[doc(hidden)]
+pub mod cli; // For the binaries; not a stable interface
+pub mod der;
+pub mod render;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+mod asn1;
+mod common;
+mod diagnostics;
+mod dump;
+mod dumpasn1;
+mod dumpcbor;
+mod error;
+mod output;
+mod parse;
+
+pub use dump::{dump_asn1_to_string, dump_cbor_to_string};
+pub use error::DumpError;
+pub use parse::{try_parse_asn1, try_parse_cbor};
@@ -0,0 +1,243 @@
+// Destination for the dump: stdout, or the file given with -O. Write errors
+// are held until checked, so the formatting code doesn't need to handle them
+// on every line it prints. For --timing it also measures how long goes on
+// rendering: formatting and writing the lines of a dump, or running one of
+// the --format renderers. It also knows whether what's written is colored.
+
+use std::fmt;
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+use crate::common::color::{Painted, Style};
+
+/// Where a dumper's output goes
+pub struct Output {
+    writer: Box<dyn Write>,
+    error: Option<io::Error>,      // First write error, until checked
+    render_time: Option<Duration>, // Time spent rendering, if measured
+    colors: bool,                  // Whether to color errors and warnings
+}
+
+impl Output {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Output {
+            writer,
+            error: None,
+            render_time: None,
+            colors: false,
+        }
+    }
+
+    /// Buffered output to stdout, which stays locked once written to so that
+    /// each write doesn't have to take the lock and flush a line at a time.
+    /// It isn't locked before then, as a dumper whose output is replaced,
+    /// such as one of several checking files at once, never writes to it
+    pub fn stdout() -> Self {
+        Output::new(Box::new(LazyStdout::default()))
+    }
+
+    /// Output that's thrown away
+    pub fn sink() -> Self {
+        Output::new(Box::new(io::sink()))
+    }
+
+    /// Write formatted text, for the write! and writeln! macros. Nothing more
+    /// is written after an error.
+    pub fn write_fmt(&mut self, args: fmt::Arguments) {
+        if self.error.is_some() {
+            return;
+        }
+        // The arguments are only formatted as they're written, so this
+        // times both
+        let started = self.render_time.map(|_| Instant::now());
+        if let Err(e) = self.writer.write_fmt(args) {
+            self.error = Some(e);
+        }
+        if let (Some(started), Some(total)) = (started, &mut self.render_time) {
+            *total += started.elapsed();
+        }
+    }
+
+    /// Color what's painted from now on, or stop coloring it
+    pub fn set_colors(&mut self, colors: bool) {
+        self.colors = colors;
+    }
+
+    /// `text` in the color for `style`, if this output is colored
+    pub fn paint<'a>(&self, style: Style, text: &'a str) -> Painted<'a> {
+        Painted {
+            style,
+            text,
+            colored: self.colors,
+        }
+    }
+
+    /// Start measuring the time spent rendering
+    pub fn measure_rendering(&mut self) {
+        self.render_time.get_or_insert(Duration::ZERO);
+    }
+
+    /// The time spent rendering so far, if it's being measured
+    pub fn render_time(&self) -> Option<Duration> {
+        self.render_time
+    }
+
+    /// Run `render`, which writes to this output, counting all of the time
+    /// it takes as rendering
+    pub fn render<T>(&mut self, render: impl FnOnce(&mut Self) -> T) -> T {
+        let Some(total) = self.render_time.take() else {
+            return render(self);
+        };
+        let started = Instant::now();
+        let result = render(self);
+        self.render_time = Some(total + started.elapsed());
+        result
+    }
+
+    /// Return the first write error since the last check, so that a dump
+    /// can stop once its output is gone (e.g. a closed pipe)
+    pub fn check(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Check for write errors and flush anything buffered
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.check()?;
+        self.writer.flush()
+    }
+}
+
+/// Lets code that writes to any io::Write, such as the renderers, write to
+/// the dump's destination
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check()?;
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Output::flush(self)
+    }
+}
+
+/// Stdout, locked and buffered on the first write
+#[derive(Default)]
+struct LazyStdout(Option<BufWriter<io::StdoutLock<'static>>>);
+
+impl Write for LazyStdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .get_or_insert_with(|| BufWriter::new(io::stdout().lock()))
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.0 {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Output captured in memory, for dumps returned as strings and to check
+/// what a dumper prints
+#[derive(Clone, Default)]
+pub struct Captured(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+impl Captured {
+    pub fn output(&self) -> Output {
+        Output::new(Box::new(self.clone()))
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Accepts a few bytes, then fails like a closed pipe
+    struct ClosedPipe(usize);
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.0 == 0 {
+                return Err(io::Error::from(io::ErrorKind::BrokenPipe));
+            }
+            let n = buf.len().min(self.0);
+            self.0 -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_captured_output() {
+        let captured = Captured::default();
+        let mut out = captured.output();
+        write!(out, "{} ", 1);
+        writeln!(out, "two");
+        writeln!(out);
+        assert!(out.flush().is_ok());
+        assert_eq!(captured.text(), "1 two\n\n");
+    }
+
+    #[test]
+    fn test_write_error_is_kept() {
+        let mut out = Output::new(Box::new(ClosedPipe(3)));
+        writeln!(out, "first line");
+        writeln!(out, "second line");
+        let err = out.check().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert!(out.check().is_ok());
+    }
+
+    #[test]
+    fn test_stdout_locked_when_written() {
+        // Made while another thread holds stdout, without waiting for it
+        let lock = io::stdout().lock();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut out = Output::stdout();
+            sender.send(out.flush().is_ok()).unwrap();
+        });
+        let made = receiver.recv_timeout(Duration::from_secs(5));
+        drop(lock);
+        assert_eq!(made, Ok(true));
+    }
+
+    #[test]
+    fn test_render_time() {
+        let mut out = Output::sink();
+        writeln!(out, "not measured");
+        assert_eq!(out.render_time(), None);
+
+        out.measure_rendering();
+        writeln!(out, "{}", 1);
+        let after_write = out.render_time().unwrap();
+        out.render(|out| {
+            std::thread::sleep(Duration::from_millis(2));
+            writeln!(out, "measured once, as part of the render");
+        });
+        assert!(out.render_time().unwrap() >= after_write + Duration::from_millis(2));
+    }
+}
@@ -0,0 +1,101 @@
+// Parse entry points for untrusted input. They never panic, and what they
+// allocate is bounded by the size of the input rather than by lengths read
+// from it, so they're safe to run on anything; the targets in fuzz/ hold them
+// to that.
+
+use crate::cbor::{self, CborItem};
+use crate::dumpasn1;
+use crate::error::DumpError;
+use crate::render::Node;
+
+/// Parse a CBOR sequence into its top-level items, as `cbor::parse_slice`
+/// does
+///
+/// ```
+/// use asn1_cbor_tools::try_parse_cbor;
+///
+/// assert_eq!(try_parse_cbor(&[0x18, 0x64]).unwrap().len(), 1);
+/// // A byte string claiming 2^64-1 bytes
+/// let huge = [0x5B, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+/// assert!(try_parse_cbor(&huge).unwrap_err().is_truncated());
+/// ```
+pub fn try_parse_cbor(data: &[u8]) -> Result<Vec<CborItem<'_>>, DumpError> {
+    cbor::parse_slice(data)
+}
+
+/// Parse definite-length DER into nodes for its top-level items, decoded as
+/// `--format` shows them, with nothing masked
+///
+/// ```
+/// use asn1_cbor_tools::try_parse_asn1;
+///
+/// let nodes = try_parse_asn1(&[0x30, 0x03, 0x02, 0x01, 0x05]).unwrap();
+/// assert_eq!(nodes[0].children()[0].kind, "INTEGER");
+/// assert!(try_parse_asn1(&[0x30, 0x84, 0x7F, 0xFF, 0xFF, 0xFF]).is_err());
+/// ```
+pub fn try_parse_asn1(data: &[u8]) -> Result<Vec<Node>, DumpError> {
+    dumpasn1::der_to_nodes(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::DerBuilder;
+
+    #[test]
+    fn test_try_parse_cbor() {
+        // Lengths far beyond the input, for strings, arrays and maps
+        for data in [
+            &[0x7B, 0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF][..],
+            &[0x9B, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+            &[0xBA, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 0x02],
+        ] {
+            let err = try_parse_cbor(data).unwrap_err();
+            assert!(err.is_truncated(), "{}", err);
+        }
+
+        // Nesting as deep as the input allows
+        let deep = vec![0x81; 100_000];
+        assert!(matches!(
+            try_parse_cbor(&deep),
+            Err(DumpError::TooDeep { .. })
+        ));
+
+        assert!(matches!(
+            try_parse_cbor(&[0x1C]),
+            Err(DumpError::Malformed {
+                offset: Some(0),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_try_parse_asn1() {
+        let der = DerBuilder::new()
+            .sequence(|b| b.integer(5).utf8_string("hi"))
+            .finish();
+        let nodes = try_parse_asn1(&der).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].children().len(), 2);
+
+        let mut deep = DerBuilder::new().null().finish();
+        for _ in 0..1000 {
+            deep = DerBuilder::new().sequence(|b| b.raw(&deep)).finish();
+        }
+        assert!(matches!(
+            try_parse_asn1(&deep),
+            Err(DumpError::TooDeep { .. })
+        ));
+
+        // Indefinite length, and a length beyond the input
+        assert!(matches!(
+            try_parse_asn1(&[0x30, 0x80, 0x00, 0x00]),
+            Err(DumpError::Malformed {
+                offset: Some(0),
+                ..
+            })
+        ));
+        assert!(try_parse_asn1(&der[..der.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+// CBOR diagnostic notation (RFC 8949 section 8, with the extensions of RFC
+// 8610 Appendix G), one line per top-level item. ASN.1 elements map onto
+// the CBOR data model: constructed types become arrays and OIDs text in
+// dotted form. Labels and OID names are kept as comments.
+
+use super::*;
+
+/// Renders nodes in CBOR diagnostic notation
+#[derive(Debug, Clone, Default)]
+pub struct DiagRenderer;
+
+impl DiagRenderer {
+    pub fn new() -> Self {
+        DiagRenderer
+    }
+}
+
+impl Renderer for DiagRenderer {
+    fn node(&mut self, out: &mut dyn Write, node: &Node) -> io::Result<()> {
+        writeln!(out, "{}", diag_notation(node))
+    }
+}
+
+/// Diagnostic notation for a node and everything in it, on one line
+pub fn diag_notation(node: &Node) -> String {
+    // Indefinite-length containers are marked with an underscore
+    let open = |bracket: &str| match node.length {
+        Some(_) => bracket.to_string(),
+        None => format!("{}_ ", bracket),
+    };
+    let value = match &node.content {
+        Content::Integer(n) => n.clone(),
+        Content::Float(f) => float(*f),
+        Content::Bool(b) => b.to_string(),
+        Content::Null => "null".to_string(),
+        Content::Undefined => "undefined".to_string(),
+        Content::Simple(n) => format!("simple({})", n),
+        Content::Text(text) => json_string(text),
+        Content::Bytes(bytes) => format!("h'{}'", hex_lower(bytes)),
+        Content::Oid { dotted, name } => match name {
+            Some(name) => format!("{} / {} /", json_string(dotted), name),
+            None => json_string(dotted),
+        },
+        Content::Other(text) => json_string(text),
+        Content::List(nodes) => {
+            let items: Vec<String> = nodes.iter().map(diag_notation).collect();
+            format!("{}{}]", open("["), items.join(", "))
+        }
+        Content::Map(pairs) => {
+            let pairs: Vec<String> = pairs
+                .iter()
+                .map(|(key, value)| format!("{}: {}", diag_notation(key), diag_notation(value)))
+                .collect();
+            format!("{}{}}}", open("{"), pairs.join(", "))
+        }
+        Content::Tagged(tag, node) => format!("{}({})", tag, diag_notation(node)),
+    };
+    match &node.label {
+        Some(label) => format!("/ {} / {}", label, value),
+        None => value,
+    }
+}
+
+/// A float as diagnostic notation writes it: always with a fraction or an
+/// exponent, so it can't be taken for an integer
+fn float(f: f64) -> String {
+    if f.is_nan() {
+        return "NaN".to_string();
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    let text = format!("{:?}", f);
+    match text.find('e') {
+        Some(e) if !text[..e].contains('.') => format!("{}.0{}", &text[..e], &text[e..]),
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diag() {
+        let mut out = Vec::new();
+        render(&mut DiagRenderer::new(), &mut out, &sample()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[5, / algorithm / \"2.16.840.1.101.3.4.2.1\" / sha256 /, \"a\\\"b\"]\n\
+             {_ 1: h'0102', 2: 1(null)}\n"
+        );
+    }
+
+    #[test]
+    fn test_float() {
+        assert_eq!(float(1.0), "1.0");
+        assert_eq!(float(-0.0), "-0.0");
+        assert_eq!(float(1.5e300), "1.5e300");
+        assert_eq!(float(1e300), "1.0e300");
+        assert_eq!(float(f64::NEG_INFINITY), "-Infinity");
+        assert_eq!(float(f64::NAN), "NaN");
+    }
+}
@@ -0,0 +1,172 @@
+// A standalone HTML page showing the nodes as nested lists, with each
+// constructed element in a <details> block so it can be folded away
+
+use super::*;
+
+const HEADER: &str = "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Dump</title>
+<style>
+body { font-family: monospace; }
+ul { list-style: none; padding-left: 2em; }
+summary { cursor: pointer; }
+.offset { color: #888; }
+.label { color: #07a; }
+.kind { font-weight: bold; }
+.value { color: #a50; }
+.note { display: block; color: #080; padding-left: 2em; }
+</style>
+</head>
+<body>
+";
+
+const FOOTER: &str = "</body>\n</html>\n";
+
+/// Renders nodes as an HTML page
+#[derive(Debug, Clone, Default)]
+pub struct HtmlRenderer;
+
+impl HtmlRenderer {
+    pub fn new() -> Self {
+        HtmlRenderer
+    }
+
+    fn write_node(
+        &self,
+        out: &mut dyn Write,
+        node: &Node,
+        depth: usize,
+        prefix: &str,
+    ) -> io::Result<()> {
+        let indent = "  ".repeat(depth);
+        let mut line = String::new();
+        if let Some(offset) = node.offset {
+            line.push_str(&format!("<span class=\"offset\">{}</span> ", offset));
+        }
+        line.push_str(prefix);
+        if let Some(label) = &node.label {
+            line.push_str(&format!("<span class=\"label\">{}</span> ", escape(label)));
+        }
+        line.push_str(&format!(
+            "<span class=\"kind\">{}</span>",
+            escape(&node.kind)
+        ));
+        if let Some(value) = value(&node.content) {
+            line.push_str(&format!(" <span class=\"value\">{}</span>", escape(&value)));
+        }
+        for note in &node.notes {
+            line.push_str(&format!("<span class=\"note\">{}</span>", escape(note)));
+        }
+
+        let children: Vec<(&Node, &str)> = match &node.content {
+            Content::List(nodes) => nodes.iter().map(|n| (n, "")).collect(),
+            Content::Map(pairs) => pairs
+                .iter()
+                .flat_map(|(key, value)| [(key, ""), (value, "=&gt; ")])
+                .collect(),
+            Content::Tagged(_, node) => vec![(node.as_ref(), "")],
+            _ => return writeln!(out, "{}<li>{}</li>", indent, line),
+        };
+        writeln!(
+            out,
+            "{}<li><details open><summary>{}</summary>",
+            indent, line
+        )?;
+        writeln!(out, "{}<ul>", indent)?;
+        for (child, prefix) in children {
+            self.write_node(out, child, depth + 1, prefix)?;
+        }
+        writeln!(out, "{}</ul>", indent)?;
+        writeln!(out, "{}</details></li>", indent)
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn start(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "{}", HEADER)
+    }
+
+    fn node(&mut self, out: &mut dyn Write, node: &Node) -> io::Result<()> {
+        writeln!(out, "<ul>")?;
+        self.write_node(out, node, 0, "")?;
+        writeln!(out, "</ul>")
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "{}", FOOTER)
+    }
+}
+
+/// Text shown after the type name, if the content has any
+fn value(content: &Content) -> Option<String> {
+    match content {
+        Content::Integer(n) => Some(n.clone()),
+        Content::Float(f) => Some(f.to_string()),
+        Content::Bool(b) => Some(b.to_string()),
+        Content::Simple(n) => Some(n.to_string()),
+        Content::Text(text) => Some(json_string(text)),
+        Content::Bytes(bytes) => Some(hex_lower(bytes)),
+        Content::Oid { dotted, name } => Some(match name {
+            Some(name) => format!("{} ({})", dotted, name),
+            None => dotted.clone(),
+        }),
+        Content::Other(text) => Some(text.clone()),
+        Content::Tagged(tag, _) => Some(tag.to_string()),
+        Content::Null | Content::Undefined | Content::List(_) | Content::Map(_) => None,
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html() {
+        let mut out = Vec::new();
+        render(&mut HtmlRenderer::new(), &mut out, &sample()).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with(HEADER));
+        assert!(text.ends_with(FOOTER));
+        let body = &text[HEADER.len()..text.len() - FOOTER.len()];
+        assert_eq!(
+            body.lines().take(5).collect::<Vec<_>>(),
+            [
+                "<ul>",
+                "<li><details open><summary><span class=\"offset\">0</span> \
+                 <span class=\"kind\">SEQUENCE</span></summary>",
+                "<ul>",
+                "  <li><span class=\"offset\">2</span> <span class=\"kind\">INTEGER</span> \
+                 <span class=\"value\">5</span></li>",
+                "  <li><span class=\"offset\">5</span> <span class=\"label\">algorithm</span> \
+                 <span class=\"kind\">OBJECT IDENTIFIER</span> \
+                 <span class=\"value\">2.16.840.1.101.3.4.2.1 (sha256)</span></li>",
+            ]
+        );
+        assert!(body.contains("<span class=\"value\">&quot;a\\&quot;b&quot;</span>"));
+        assert!(body.contains(
+            "=&gt; <span class=\"kind\">tag</span> <span class=\"value\">1</span>\
+             <span class=\"note\">epoch-based date/time</span></summary>"
+        ));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
@@ -0,0 +1,139 @@
+// JSON: an array with an object per top-level node, one to a line. Every
+// object has "offset", "header_length", "length" (null if indefinite) and
+// "kind", plus "label" and "notes" when there are any. Scalars add "value"
+// (byte strings as hex, OIDs in dotted form with their "name" alongside),
+// constructed elements "children", maps "entries" of {"key", "value"}
+// objects, and tags "tag" and "item".
+
+use super::*;
+
+/// Renders nodes as JSON
+#[derive(Debug, Clone, Default)]
+pub struct JsonRenderer {
+    started: bool,
+}
+
+impl JsonRenderer {
+    pub fn new() -> Self {
+        JsonRenderer::default()
+    }
+}
+
+impl Renderer for JsonRenderer {
+    fn start(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "[")
+    }
+
+    fn node(&mut self, out: &mut dyn Write, node: &Node) -> io::Result<()> {
+        let separator = if std::mem::replace(&mut self.started, true) {
+            ","
+        } else {
+            ""
+        };
+        write!(out, "{}\n  {}", separator, json(node))
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        if self.started {
+            writeln!(out)?;
+        }
+        writeln!(out, "]")
+    }
+}
+
+/// JSON object for a node and everything in it
+fn json(node: &Node) -> String {
+    let number = |n: Option<usize>| n.map_or("null".to_string(), |n| n.to_string());
+    let mut fields = vec![
+        format!("\"offset\": {}", number(node.offset)),
+        format!("\"header_length\": {}", node.header_length),
+        format!("\"length\": {}", number(node.length)),
+        format!("\"kind\": {}", json_string(&node.kind)),
+    ];
+    if let Some(label) = &node.label {
+        fields.push(format!("\"label\": {}", json_string(label)));
+    }
+
+    let value = |text: String| format!("\"value\": {}", text);
+    match &node.content {
+        Content::Integer(n) => fields.push(value(n.clone())),
+        Content::Float(f) if f.is_finite() => fields.push(value(format!("{:?}", f))),
+        Content::Float(_) | Content::Null | Content::Undefined => {
+            fields.push(value("null".to_string()))
+        }
+        Content::Bool(b) => fields.push(value(b.to_string())),
+        Content::Simple(n) => fields.push(value(n.to_string())),
+        Content::Text(text) | Content::Other(text) => fields.push(value(json_string(text))),
+        Content::Bytes(bytes) => fields.push(value(json_string(&hex_lower(bytes)))),
+        Content::Oid { dotted, name } => {
+            fields.push(value(json_string(dotted)));
+            if let Some(name) = name {
+                fields.push(format!("\"name\": {}", json_string(name)));
+            }
+        }
+        Content::List(nodes) => {
+            let children: Vec<String> = nodes.iter().map(json).collect();
+            fields.push(format!("\"children\": [{}]", children.join(", ")));
+        }
+        Content::Map(pairs) => {
+            let entries: Vec<String> = pairs
+                .iter()
+                .map(|(key, value)| {
+                    format!("{{\"key\": {}, \"value\": {}}}", json(key), json(value))
+                })
+                .collect();
+            fields.push(format!("\"entries\": [{}]", entries.join(", ")));
+        }
+        Content::Tagged(tag, node) => {
+            fields.push(format!("\"tag\": {}", tag));
+            fields.push(format!("\"item\": {}", json(node)));
+        }
+    }
+
+    if !node.notes.is_empty() {
+        let notes: Vec<String> = node.notes.iter().map(|n| json_string(n)).collect();
+        fields.push(format!("\"notes\": [{}]", notes.join(", ")));
+    }
+    format!("{{{}}}", fields.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(nodes: &[Node]) -> String {
+        let mut out = Vec::new();
+        render(&mut JsonRenderer::new(), &mut out, nodes).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_json() {
+        let nodes = sample();
+        let text = rendered(&nodes);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "[");
+        assert!(lines[1].starts_with(
+            "  {\"offset\": 0, \"header_length\": 2, \"length\": 19, \"kind\": \"SEQUENCE\", \
+             \"children\": [{\"offset\": 2, \"header_length\": 2, \"length\": 1, \
+             \"kind\": \"INTEGER\", \"value\": 5}, "
+        ));
+        assert!(lines[1].contains(
+            "\"label\": \"algorithm\", \"value\": \"2.16.840.1.101.3.4.2.1\", \"name\": \"sha256\""
+        ));
+        assert!(lines[1].ends_with("\"value\": \"a\\\"b\"}]},"));
+        assert!(lines[2].starts_with("  {\"offset\": 21, \"header_length\": 1, \"length\": null"));
+        assert!(lines[2].contains("\"value\": \"0102\""));
+        assert!(lines[2].contains(
+            "\"tag\": 1, \"item\": {\"offset\": 28, \"header_length\": 1, \"length\": 0, \
+             \"kind\": \"null\", \"value\": null}, \"notes\": [\"epoch-based date/time\"]"
+        ));
+        assert_eq!(lines[3], "]");
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(rendered(&[]), "[]\n");
+    }
+}
@@ -0,0 +1,450 @@
+// Output formats for decoded data. Both dumpers can describe what they
+// decoded as a tree of format-neutral nodes, and a renderer turns the tree
+// into text: an indented outline, CBOR diagnostic notation, JSON or HTML.
+// Other programs can supply their own renderer by implementing the trait.
+
+mod diag;
+mod html;
+mod json;
+mod tree;
+
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crate::asn1::crypto::HashAlg;
+use crate::common::hex::hex_lower;
+
+pub use diag::{diag_notation, DiagRenderer};
+pub use html::HtmlRenderer;
+pub use json::JsonRenderer;
+pub use tree::TreeRenderer;
+
+/// One decoded element: an ASN.1 TLV or a CBOR data item
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub offset: Option<usize>, // Start in the input, if known
+    pub header_length: usize,  // Size of the tag and length, or CBOR head
+    pub length: Option<usize>, // Content size, None for indefinite length
+    pub kind: String,          // Type name, e.g. "SEQUENCE" or "array"
+    pub label: Option<String>, // Field name from a recognized structure
+    pub content: Content,
+    pub notes: Vec<String>, // Remarks shown with the element
+}
+
+/// What an element holds
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Integer(String), // Decimal, of any size
+    Float(f64),
+    Bool(bool),
+    Null,
+    Undefined,
+    Simple(u8),
+    Text(String),
+    Bytes(Vec<u8>),
+    Oid {
+        dotted: String,
+        name: Option<String>,
+    },
+    Other(String), // A value with no structured form, already described
+    List(Vec<Node>),
+    Map(Vec<(Node, Node)>),
+    Tagged(u64, Box<Node>),
+}
+
+impl Node {
+    pub fn new(kind: impl Into<String>, content: Content) -> Self {
+        Node {
+            offset: None,
+            header_length: 0,
+            length: Some(0),
+            kind: kind.into(),
+            label: None,
+            content,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Set where the element was found and how it was encoded
+    pub fn at(mut self, offset: usize, header_length: usize, length: Option<usize>) -> Self {
+        self.offset = Some(offset);
+        self.header_length = header_length;
+        self.length = length;
+        self
+    }
+
+    /// Nodes nested directly inside this one, in order
+    pub fn children(&self) -> Vec<&Node> {
+        match &self.content {
+            Content::List(nodes) => nodes.iter().collect(),
+            Content::Map(pairs) => pairs.iter().flat_map(|(k, v)| [k, v]).collect(),
+            Content::Tagged(_, node) => vec![node],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The same, to change them
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        match &mut self.content {
+            Content::List(nodes) => nodes.iter_mut().collect(),
+            Content::Map(pairs) => pairs.iter_mut().flat_map(|(k, v)| [k, v]).collect(),
+            Content::Tagged(_, node) => vec![&mut **node],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Where the encoding ends: after the content, or for an indefinite
+    /// length after the end-of-contents marker. None if that isn't known
+    pub fn end(&self) -> Option<usize> {
+        let start = self.offset? + self.header_length;
+        match self.length {
+            Some(length) => Some(start + length),
+            None => match self.children().last() {
+                Some(last) => last.end().map(|end| end + 1),
+                None => {
+                    matches!(self.content, Content::List(_) | Content::Map(_)).then_some(start + 1)
+                }
+            },
+        }
+    }
+
+    /// Move the element, and those nested in it, `by` bytes further into the
+    /// input
+    pub fn shift(&mut self, by: usize) {
+        self.offset = self.offset.map(|offset| offset + by);
+        match &mut self.content {
+            Content::List(nodes) => nodes.iter_mut().for_each(|node| node.shift(by)),
+            Content::Map(pairs) => pairs.iter_mut().for_each(|(key, value)| {
+                key.shift(by);
+                value.shift(by);
+            }),
+            Content::Tagged(_, node) => node.shift(by),
+            _ => {}
+        }
+    }
+}
+
+/// The elements whose encodings lie in the byte window `range`, for --range.
+/// An element only partly inside is kept, with a note saying so, and with
+/// what's nested in it cut down the same way; one wholly outside is dropped,
+/// unless it's the other half of a map entry, or the item a tag applies to,
+/// that's needed to show the rest. Elements that can't be placed are dropped
+pub fn window(nodes: &[Node], range: &Range<usize>) -> Vec<Node> {
+    nodes.iter().filter_map(|node| clip(node, range)).collect()
+}
+
+/// Mark the elements whose encodings overlap the bytes in `range`, for
+/// --highlight, with a note of the bytes they share. Those enclosing an
+/// element that's marked are marked too, so the path to it shows
+pub fn highlight(nodes: &mut [Node], range: &Range<usize>) {
+    for node in nodes {
+        let Some((start, end)) = extent(node) else {
+            continue;
+        };
+        if end <= range.start || start >= range.end {
+            continue;
+        }
+        node.notes.push(format!(
+            "highlighted: bytes {}..{}",
+            start.max(range.start),
+            end.min(range.end)
+        ));
+        match &mut node.content {
+            Content::List(nodes) => highlight(nodes, range),
+            Content::Map(pairs) => pairs.iter_mut().for_each(|(key, value)| {
+                highlight(std::slice::from_mut(key), range);
+                highlight(std::slice::from_mut(value), range);
+            }),
+            Content::Tagged(_, node) => highlight(std::slice::from_mut(node), range),
+            _ => {}
+        }
+    }
+}
+
+/// Where an element's encoding starts and ends, if it can be placed. An
+/// indefinite-length string's end isn't known, but its head is there
+fn extent(node: &Node) -> Option<(usize, usize)> {
+    let start = node.offset?;
+    Some((start, node.end().unwrap_or(start + node.header_length)))
+}
+
+fn clip(node: &Node, range: &Range<usize>) -> Option<Node> {
+    let (start, end) = extent(node)?;
+    if end <= range.start || start >= range.end {
+        return None;
+    }
+    if start >= range.start && end <= range.end {
+        return Some(node.clone());
+    }
+    let mut clipped = node.clone();
+    clipped.notes.push(format!(
+        "partly outside the range: bytes {}..{}",
+        start, end
+    ));
+    clipped.content = match &node.content {
+        Content::List(nodes) => Content::List(window(nodes, range)),
+        Content::Map(pairs) => Content::Map(
+            pairs
+                .iter()
+                .filter_map(
+                    |(key, value)| match (clip(key, range), clip(value, range)) {
+                        (None, None) => None,
+                        (key_clip, value_clip) => Some((
+                            key_clip.unwrap_or_else(|| outside(key)),
+                            value_clip.unwrap_or_else(|| outside(value)),
+                        )),
+                    },
+                )
+                .collect(),
+        ),
+        Content::Tagged(tag, item) => Content::Tagged(
+            *tag,
+            Box::new(clip(item, range).unwrap_or_else(|| outside(item))),
+        ),
+        content => content.clone(),
+    };
+    Some(clipped)
+}
+
+/// An element outside the --range window, kept for one that's inside
+fn outside(node: &Node) -> Node {
+    let mut node = node.clone();
+    node.notes.push("outside the range".to_string());
+    node
+}
+
+/// Note the `alg` digest of an element's encoding, which `data` holds at the
+/// element's offset, for --digest-each. Returns false, noting nothing, if
+/// the encoding can't be placed in `data`
+pub fn note_digest(node: &mut Node, data: &[u8], alg: HashAlg) -> bool {
+    let encoding = match (node.offset, node.end()) {
+        (Some(start), Some(end)) => data.get(start..end),
+        _ => None,
+    };
+    match encoding {
+        Some(encoding) => {
+            let digest = hex_lower(&alg.digest(encoding));
+            node.notes.push(format!("{}: {}", alg.name(), digest));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Writes a sequence of top-level nodes in some output format
+pub trait Renderer {
+    /// Called once before the first node
+    fn start(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Write a top-level node and everything nested in it
+    fn node(&mut self, out: &mut dyn Write, node: &Node) -> io::Result<()>;
+
+    /// Called once after the last node
+    fn finish(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Render a whole document
+pub fn render(renderer: &mut dyn Renderer, out: &mut dyn Write, nodes: &[Node]) -> io::Result<()> {
+    renderer.start(out)?;
+    for node in nodes {
+        renderer.node(out, node)?;
+    }
+    renderer.finish(out)
+}
+
+/// The built-in renderers, by the names the dumpers' --format takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Tree,
+    Diag,
+    Json,
+    Html,
+}
+
+impl Format {
+    pub const NAMES: &'static str = "tree, diag, json or html";
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "tree" => Some(Format::Tree),
+            "diag" => Some(Format::Diag),
+            "json" => Some(Format::Json),
+            "html" => Some(Format::Html),
+            _ => None,
+        }
+    }
+
+    pub fn renderer(self) -> Box<dyn Renderer> {
+        match self {
+            Format::Tree => Box::new(TreeRenderer::new()),
+            Format::Diag => Box::new(DiagRenderer::new()),
+            Format::Json => Box::new(JsonRenderer::new()),
+            Format::Html => Box::new(HtmlRenderer::new()),
+        }
+    }
+}
+
+/// Quote a string for JSON
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A sample document for the renderers' tests: SEQUENCE { INTEGER 5,
+/// OID sha256 labeled "algorithm", UTF8String "a\"b" } followed by the
+/// CBOR item {1: h'0102', 2: 1(null)}
+#[cfg(test)]
+pub(crate) fn sample() -> Vec<Node> {
+    let mut oid = Node::new(
+        "OBJECT IDENTIFIER",
+        Content::Oid {
+            dotted: "2.16.840.1.101.3.4.2.1".to_string(),
+            name: Some("sha256".to_string()),
+        },
+    )
+    .at(5, 2, Some(9));
+    oid.label = Some("algorithm".to_string());
+    let sequence = Node::new(
+        "SEQUENCE",
+        Content::List(vec![
+            Node::new("INTEGER", Content::Integer("5".to_string())).at(2, 2, Some(1)),
+            oid,
+            Node::new("UTF8String", Content::Text("a\"b".to_string())).at(16, 2, Some(3)),
+        ]),
+    )
+    .at(0, 2, Some(19));
+
+    let mut tagged = Node::new(
+        "tag",
+        Content::Tagged(
+            1,
+            Box::new(Node::new("null", Content::Null).at(28, 1, Some(0))),
+        ),
+    )
+    .at(27, 1, Some(0));
+    tagged.notes.push("epoch-based date/time".to_string());
+    let map = Node::new(
+        "map",
+        Content::Map(vec![
+            (
+                Node::new("unsigned", Content::Integer("1".to_string())).at(22, 1, Some(0)),
+                Node::new("bytes", Content::Bytes(vec![1, 2])).at(23, 1, Some(2)),
+            ),
+            (
+                Node::new("unsigned", Content::Integer("2".to_string())).at(26, 1, Some(0)),
+                tagged,
+            ),
+        ]),
+    )
+    .at(21, 1, None);
+    vec![sequence, map]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a \"b\"\\c"), "\"a \\\"b\\\"\\\\c\"");
+        assert_eq!(json_string("x\ny\u{1}"), "\"x\\ny\\u0001\"");
+    }
+
+    #[test]
+    fn test_format_names() {
+        for name in ["tree", "diag", "json", "html"] {
+            assert!(Format::from_name(name).is_some(), "{}", name);
+        }
+        assert_eq!(Format::from_name("xml"), None);
+    }
+
+    #[test]
+    fn test_end() {
+        let nodes = sample();
+        assert_eq!(nodes[0].end(), Some(21));
+        // The indefinite-length map ends with the break after the tag
+        assert_eq!(nodes[1].end(), Some(29));
+        let mut shifted = nodes[1].clone();
+        shifted.shift(100);
+        assert_eq!(shifted.end(), Some(129));
+        assert_eq!(shifted.children()[3].children()[0].offset, Some(128));
+    }
+
+    #[test]
+    fn test_window() {
+        let nodes = sample();
+        assert_eq!(window(&nodes, &(0..21)), nodes[..1]);
+        assert_eq!(window(&nodes, &(21..50)), nodes[1..]);
+        assert!(window(&nodes, &(50..60)).is_empty());
+
+        // The INTEGER and the OID are inside, the UTF8String isn't
+        let clipped = window(&nodes, &(2..16));
+        assert_eq!(clipped.len(), 1);
+        assert_eq!(clipped[0].notes, ["partly outside the range: bytes 0..21"]);
+        let kinds: Vec<&str> = clipped[0]
+            .children()
+            .iter()
+            .map(|n| n.kind.as_str())
+            .collect();
+        assert_eq!(kinds, ["INTEGER", "OBJECT IDENTIFIER"]);
+
+        // Only the bytes value of the first map entry, with its key kept
+        let clipped = window(&nodes, &(23..26));
+        let entry = clipped[0].children();
+        assert_eq!(entry.len(), 2);
+        assert_eq!(entry[0].notes, ["outside the range"]);
+        assert!(entry[1].notes.is_empty());
+    }
+
+    #[test]
+    fn test_highlight() {
+        // Two bytes into the OID, and the one after it
+        let mut nodes = sample();
+        highlight(&mut nodes, &(12..17));
+        assert_eq!(nodes[0].notes, ["highlighted: bytes 12..17"]);
+        let notes: Vec<&[String]> = nodes[0]
+            .children()
+            .iter()
+            .map(|n| n.notes.as_slice())
+            .collect();
+        assert_eq!(
+            notes,
+            [
+                &[][..],
+                &["highlighted: bytes 12..16".to_string()],
+                &["highlighted: bytes 16..17".to_string()]
+            ]
+        );
+        assert_eq!(nodes[1], sample()[1]);
+    }
+
+    #[test]
+    fn test_children() {
+        let nodes = sample();
+        assert_eq!(nodes[0].children().len(), 3);
+        let kinds: Vec<&str> = nodes[1]
+            .children()
+            .iter()
+            .map(|n| n.kind.as_str())
+            .collect();
+        assert_eq!(kinds, ["unsigned", "bytes", "unsigned", "tag"]);
+    }
+}
@@ -0,0 +1,173 @@
+// An indented outline, one element per line with its offset and content
+// length in front, in the manner of dumpasn1's classic output
+
+use super::*;
+
+/// Width of the offset and length columns, and what follows them
+const OFFSET_WIDTH: usize = 5;
+const LENGTH_WIDTH: usize = 4;
+const GUTTER: &str = ": ";
+
+/// Byte strings longer than this go on lines of their own
+const INLINE_BYTES: usize = 16;
+
+/// Renders nodes as an indented outline
+#[derive(Debug, Clone, Default)]
+pub struct TreeRenderer {
+    started: bool,
+}
+
+impl TreeRenderer {
+    pub fn new() -> Self {
+        TreeRenderer::default()
+    }
+
+    fn write_node(
+        &self,
+        out: &mut dyn Write,
+        node: &Node,
+        depth: usize,
+        prefix: &str,
+    ) -> io::Result<()> {
+        let offset = node.offset.map_or(String::new(), |o| o.to_string());
+        let length = node.length.map_or("NDEF".to_string(), |l| l.to_string());
+        write!(
+            out,
+            "{:>ow$} {:>lw$}{}{}{}",
+            offset,
+            length,
+            GUTTER,
+            indent(depth),
+            prefix,
+            ow = OFFSET_WIDTH,
+            lw = LENGTH_WIDTH
+        )?;
+        if let Some(label) = &node.label {
+            write!(out, "{}: ", label)?;
+        }
+        write!(out, "{}", node.kind)?;
+
+        let mut long_bytes = None;
+        match &node.content {
+            Content::Null | Content::Undefined => {}
+            Content::Integer(n) => write!(out, " {}", n)?,
+            Content::Float(f) => write!(out, " {}", f)?,
+            Content::Bool(b) => write!(out, " {}", b)?,
+            Content::Simple(n) => write!(out, " {}", n)?,
+            Content::Text(text) => write!(out, " {}", json_string(text))?,
+            Content::Bytes(bytes) if bytes.len() <= INLINE_BYTES => {
+                write!(out, " {}", spaced_hex(bytes))?
+            }
+            Content::Bytes(bytes) => long_bytes = Some(bytes),
+            Content::Oid { dotted, name } => match name {
+                Some(name) => write!(out, " {} ({})", dotted, name)?,
+                None => write!(out, " {}", dotted)?,
+            },
+            Content::Other(text) => write!(out, " {}", text)?,
+            Content::List(_) | Content::Map(_) => write!(out, " {{")?,
+            Content::Tagged(tag, _) => write!(out, " {} {{", tag)?,
+        }
+        writeln!(out)?;
+
+        for note in &node.notes {
+            self.write_continuation(out, depth + 1, note)?;
+        }
+        if let Some(bytes) = long_bytes {
+            for row in bytes.chunks(INLINE_BYTES) {
+                self.write_continuation(out, depth + 1, &spaced_hex(row))?;
+            }
+        }
+
+        match &node.content {
+            Content::List(nodes) => {
+                for child in nodes {
+                    self.write_node(out, child, depth + 1, "")?;
+                }
+            }
+            Content::Map(pairs) => {
+                for (key, value) in pairs {
+                    self.write_node(out, key, depth + 1, "")?;
+                    self.write_node(out, value, depth + 1, "=> ")?;
+                }
+            }
+            Content::Tagged(_, child) => self.write_node(out, child, depth + 1, "")?,
+            _ => return Ok(()),
+        }
+        self.write_continuation(out, depth, "}")
+    }
+
+    /// Write a line that belongs to the element above, with the offset and
+    /// length columns left blank
+    fn write_continuation(&self, out: &mut dyn Write, depth: usize, text: &str) -> io::Result<()> {
+        writeln!(
+            out,
+            "{:w$}{}{}{}",
+            "",
+            GUTTER,
+            indent(depth),
+            text,
+            w = OFFSET_WIDTH + 1 + LENGTH_WIDTH
+        )
+    }
+}
+
+impl Renderer for TreeRenderer {
+    fn node(&mut self, out: &mut dyn Write, node: &Node) -> io::Result<()> {
+        // Separate top-level elements with a blank line
+        if std::mem::replace(&mut self.started, true) {
+            writeln!(out)?;
+        }
+        self.write_node(out, node, 0, "")
+    }
+}
+
+fn indent(depth: usize) -> String {
+    "  ".repeat(depth)
+}
+
+fn spaced_hex(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    hex.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree() {
+        let mut out = Vec::new();
+        render(&mut TreeRenderer::new(), &mut out, &sample()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "    0   19: SEQUENCE {\n\
+             \x20   2    1:   INTEGER 5\n\
+             \x20   5    9:   algorithm: OBJECT IDENTIFIER 2.16.840.1.101.3.4.2.1 (sha256)\n\
+             \x20  16    3:   UTF8String \"a\\\"b\"\n\
+             \x20         : }\n\
+             \n\
+             \x20  21 NDEF: map {\n\
+             \x20  22    0:   unsigned 1\n\
+             \x20  23    2:   => bytes 01 02\n\
+             \x20  26    0:   unsigned 2\n\
+             \x20  27    0:   => tag 1 {\n\
+             \x20         :     epoch-based date/time\n\
+             \x20  28    0:     null\n\
+             \x20         :   }\n\
+             \x20         : }\n"
+        );
+    }
+
+    #[test]
+    fn test_long_bytes() {
+        let node = Node::new("OCTET STRING", Content::Bytes((0..20).collect())).at(0, 2, Some(20));
+        let mut out = Vec::new();
+        render(&mut TreeRenderer::new(), &mut out, &[node]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "    0   20: OCTET STRING\n\
+             \x20         :   00 01 02 03 04 05 06 07 08 09 0A 0B 0C 0D 0E 0F\n\
+             \x20         :   10 11 12 13\n"
+        );
+    }
+}
@@ -0,0 +1,24 @@
+// JavaScript bindings for the string dumpers, so that they can run in a
+// browser page. Options are passed as one string, separated by spaces as on
+// the command line. Build with
+//
+//   cargo rustc --lib --release --features wasm \
+//       --target wasm32-unknown-unknown --crate-type cdylib
+//
+// then run wasm-bindgen over the .wasm file for the JavaScript glue.
+
+use wasm_bindgen::prelude::*;
+
+/// Dump DER, BER or PEM as dumpasn1 would, e.g. with options "-t -i"
+#[wasm_bindgen]
+pub fn dump_asn1_to_string(bytes: &[u8], options: &str) -> Result<String, JsError> {
+    let options: Vec<&str> = options.split_whitespace().collect();
+    crate::dump_asn1_to_string(bytes, &options).map_err(|e| JsError::new(&e))
+}
+
+/// Dump CBOR as dumpcbor would, e.g. with options "--offsets --hex"
+#[wasm_bindgen]
+pub fn dump_cbor_to_string(bytes: &[u8], options: &str) -> Result<String, JsError> {
+    let options: Vec<&str> = options.split_whitespace().collect();
+    crate::dump_cbor_to_string(bytes, &options).map_err(|e| JsError::new(&e))
+}
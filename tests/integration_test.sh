@@ -234,6 +234,144 @@ else
     test_failed "CBOR -v option"
 fi
 
+# Test 19: --timing reports on stderr, leaving the dump unchanged
+echo "Test 19: --timing option"
+if $DUMPASN1 --timing test_opts.der 2>&1 >/dev/null | grep -q "^Timing: parse" &&
+    [ "$($DUMPCBOR --timing test_cbor_opts.cbor 2>/dev/null)" = "$($DUMPCBOR test_cbor_opts.cbor)" ]; then
+    test_passed "--timing option"
+else
+    test_failed "--timing option"
+fi
+
+# Test 20: defaults from the config file, overridden by the command line
+echo "Test 20: Config file"
+mkdir -p home/.config/asn1-cbor-tools
+printf '[dump-asn1]\nflags = ["--pure"]\n' > home/.config/asn1-cbor-tools/config.toml
+if [ "$(HOME=$TEST_DIR/home XDG_CONFIG_HOME= $DUMPASN1 test_opts.der)" = "$($DUMPASN1 --pure test_opts.der)" ] &&
+    printf 'colour = "never"\n' > home/.config/asn1-cbor-tools/config.toml &&
+    ! HOME=$TEST_DIR/home XDG_CONFIG_HOME= $DUMPASN1 test_opts.der >/dev/null 2>&1; then
+    test_passed "Config file"
+else
+    test_failed "Config file"
+fi
+
+# Test 21: environment variables override the config file
+echo "Test 21: Environment variables"
+printf 'color = "never"\n' > home/.config/asn1-cbor-tools/config.toml
+echo "30 81 03 02 01 2A" | xxd -r -p > test_long_length.der
+if HOME=$TEST_DIR/home ASN1CBOR_COLOR=always $DUMPASN1 test_long_length.der | grep -q $'\e\\[1;33mWarning:' &&
+    ! ASN1CBOR_WIDTH=wide $DUMPASN1 test_opts.der >/dev/null 2>&1; then
+    test_passed "Environment variables"
+else
+    test_failed "Environment variables"
+fi
+
+# Test 22: completion scripts
+echo "Test 22: Completion scripts"
+ASN1CBOR="$(dirname "$DUMPASN1")/asn1cbor"
+if $ASN1CBOR completions bash | bash -n &&
+    $ASN1CBOR completions fish | grep -q -- "-l oid-config" &&
+    ! $ASN1CBOR completions tcsh >/dev/null 2>&1; then
+    test_passed "Completion scripts"
+else
+    test_failed "Completion scripts"
+fi
+
+# Test 23: --follow prints items appended to a file as they arrive
+echo "Test 23: Follow mode"
+DUMPCBOR="$(dirname "$DUMPASN1")/dumpcbor"
+: > growing.cbor
+$DUMPCBOR --follow growing.cbor > follow.txt 2>&1 &
+FOLLOW_PID=$!
+sleep 0.5
+printf '\x82\x01' >> growing.cbor
+sleep 0.5
+printf '\x02\x63abc' >> growing.cbor
+sleep 0.5
+kill $FOLLOW_PID 2>/dev/null
+wait $FOLLOW_PID 2>/dev/null || true
+if grep -q "unsigned(2)" follow.txt && grep -q '"abc"' follow.txt; then
+    test_passed "Follow mode"
+else
+    test_failed "Follow mode"
+fi
+
+# Test 24: --listen dumps what a TCP connection sends
+echo "Test 24: Listen mode"
+$DUMPCBOR --listen 127.0.0.1:0 > listen.txt 2> listen_err.txt &
+LISTEN_PID=$!
+sleep 0.5
+PORT=$(sed -n 's/^Listening on .*:\([0-9]*\)$/\1/p' listen_err.txt)
+if [ -n "$PORT" ] && printf '\x82\x01\x02' > /dev/tcp/127.0.0.1/$PORT; then
+    sleep 0.5
+fi
+kill $LISTEN_PID 2>/dev/null
+wait $LISTEN_PID 2>/dev/null || true
+if grep -q "array(2 items)" listen.txt && grep -q "closed" listen.txt; then
+    test_passed "Listen mode"
+else
+    test_failed "Listen mode"
+fi
+
+# Test 25: --expect compares the dump with a stored one
+echo "Test 25: Expected output"
+$DUMPASN1 test_opts.der > expected.txt
+sed 's/INTEGER/BOOLEAN/' expected.txt > unexpected.txt
+if $DUMPASN1 --expect expected.txt test_opts.der > /dev/null &&
+    { $DUMPASN1 --expect unexpected.txt test_opts.der > expect_diff.txt; [ $? -eq 1 ]; } &&
+    grep -q '^+.*INTEGER' expect_diff.txt && grep -q '^--- unexpected.txt' expect_diff.txt; then
+    test_passed "Expected output"
+else
+    test_failed "Expected output"
+fi
+
+# Test 26: gencbor items dump cleanly, and malformed ones fail
+echo "Test 26: Generated CBOR"
+GENCBOR="$(dirname "$DUMPASN1")/gencbor"
+rm -rf gen_ok gen_bad
+$GENCBOR --seed 1 -n 20 -o gen_ok
+$GENCBOR --seed 1 -n 20 --malformed -o gen_bad
+GEN_RESULT=ok
+for f in gen_ok/*.cbor; do
+    $DUMPCBOR "$f" > /dev/null 2>&1 || [ $? -eq 1 ] || GEN_RESULT="failed on $f"
+done
+for f in gen_bad/*.cbor; do
+    ! $DUMPCBOR "$f" > /dev/null 2>&1 || GEN_RESULT="accepted $f"
+done
+if [ "$GEN_RESULT" = ok ] && [ "$(ls gen_ok | wc -l)" -eq 20 ] &&
+    [ "$($GENCBOR --seed 5 -n 3 | od -An -tx1)" = "$($GENCBOR --seed 5 -n 3 | od -An -tx1)" ]; then
+    test_passed "Generated CBOR"
+else
+    test_failed "Generated CBOR ($GEN_RESULT)"
+fi
+
+# Test 27: minimize keeps the error while cutting the input down
+echo "Test 27: Minimize"
+printf '\x83\x01\x63abc\x83\x02\x03\x1c' > minimize_in.cbor
+if $ASN1CBOR minimize --cbor --output minimized.cbor minimize_in.cbor 2> minimize_err.txt &&
+    [ "$(od -An -tx1 minimized.cbor | tr -d ' ')" = "1c" ] &&
+    grep -q "from 10 to 1 bytes" minimize_err.txt; then
+    test_passed "Minimize"
+else
+    test_failed "Minimize"
+fi
+
+# Test 28: mutate writes variants that dumpcbor copes with without crashing
+echo "Test 28: Mutate"
+rm -rf mutated
+printf '\xa2\x61a\x82\x01\x63xyz\x61b\x5f\x41\x01\xff' > mutate_seed.cbor
+$ASN1CBOR mutate --seed 1 -n 50 -o mutated mutate_seed.cbor
+MUTATE_RESULT=ok
+for f in mutated/*.cbor; do
+    $DUMPCBOR "$f" > /dev/null 2>&1 || [ $? -lt 100 ] || MUTATE_RESULT="crashed on $f"
+done
+if [ "$MUTATE_RESULT" = ok ] && [ "$(ls mutated | wc -l)" -eq 50 ] &&
+    ! cmp -s mutated/0000.cbor mutate_seed.cbor; then
+    test_passed "Mutate"
+else
+    test_failed "Mutate ($MUTATE_RESULT)"
+fi
+
 # Summary
 echo ""
 echo "=================================="